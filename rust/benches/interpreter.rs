@@ -0,0 +1,138 @@
+//! Interpreter benchmarks: small loops that stress one part of the
+//! interpreter each, and whole contracts for the overall picture.
+//!
+//! Run with `cargo bench`, or `cargo bench -- <filter>` for some of them.
+//! Criterion keeps the previous results under `target/criterion` and reports
+//! the change against them.
+
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use evm::abi::encode_call;
+use evm::keccak::KeccakCache;
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const ALICE: u64 = 0xa11ce;
+const BOB: u64 = 0xb0b;
+
+fn tx(value: serde_json::Value) -> TxData {
+    serde_json::from_value(value).unwrap()
+}
+
+fn data() -> EvmData {
+    EvmData {
+        tx_data: Some(tx(json!({ "to": "0xc0de" }))),
+        ..Default::default()
+    }
+}
+
+fn contract(path: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path);
+    hex::decode(std::fs::read_to_string(path).unwrap().trim()).unwrap()
+}
+
+fn bench_code(c: &mut Criterion, name: &str, code: &str, setup: fn() -> EvmData) {
+    let code = hex::decode(code).unwrap();
+    c.bench_function(name, |b| {
+        b.iter_batched(
+            setup,
+            |mut data| assert!(evm(&code, &mut data, true).success),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn micro(c: &mut Criterion) {
+    // Counts 10000 down to zero: PUSH1 1, SWAP1, SUB, DUP1, JUMPI per turn
+    bench_code(c, "arithmetic_loop", "6127105b600190038060035700", data);
+
+    // MSTOREs every word up to 32 KiB, growing memory a word at a time
+    bench_code(
+        c,
+        "memory_expansion",
+        "60005b8080526020018061800011600257 00"
+            .replace(' ', "")
+            .as_str(),
+        data,
+    );
+
+    // SHA3 of the same 64 bytes, as mapping lookups do, 4096 times
+    let keccak_loop = "6110005b604060002050600190038060035700";
+    bench_code(c, "keccak_loop", keccak_loop, data);
+    bench_code(c, "keccak_loop_cached", keccak_loop, || EvmData {
+        keccak_cache: Some(KeccakCache::default()),
+        ..data()
+    });
+}
+
+fn erc20_transfer(c: &mut Criterion) {
+    let code = contract("tests/contracts/weth9.hex");
+    let weth9 = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    let call = |calldata: Vec<u8>, value: u64| {
+        tx(json!({
+            "from": format!("{:#x}", ALICE),
+            "origin": format!("{:#x}", ALICE),
+            "to": weth9,
+            "data": hex::encode(calldata),
+            "value": format!("{:x}", value),
+            "gasprice": "1",
+            "gas": "1000000",
+        }))
+    };
+
+    let mut funded = EvmData {
+        tx_data: Some(call(encode_call("deposit()", &[]), 1000)),
+        ..Default::default()
+    };
+    assert!(evm(&code, &mut funded, true).success);
+    let transfer = call(
+        encode_call(
+            "transfer(address,uint256)",
+            &[U256::from(BOB), U256::from(1)],
+        ),
+        0,
+    );
+
+    c.bench_function("erc20_transfer", |b| {
+        b.iter_batched(
+            || EvmData {
+                tx_data: Some(transfer.clone()),
+                ..funded.clone()
+            },
+            |mut data| assert!(evm(&code, &mut data, true).success),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn snailtracer(c: &mut Criterion) {
+    // The ray tracer of the Ethereum client benchmarks, one call to its
+    // `Benchmark()` entry point. The runtime code is the one revm benchmarks
+    // with.
+    let code = contract("benches/contracts/snailtracer.hex");
+    let call = tx(json!({
+        "from": "0x2000",
+        "to": "0x1000",
+        "data": "30627b7c",
+        "gas": "0x3b9aca00",
+    }));
+
+    let mut group = c.benchmark_group("macro");
+    group.sample_size(10);
+    group.bench_function("snailtracer", |b| {
+        b.iter_batched(
+            || EvmData {
+                tx_data: Some(call.clone()),
+                ..Default::default()
+            },
+            |mut data| assert!(evm(&code, &mut data, true).success),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, micro, erc20_transfer, snailtracer);
+criterion_main!(benches);
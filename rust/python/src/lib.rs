@@ -0,0 +1,87 @@
+//! The `evmrs` Python module: the interpreter, the t8n transaction executor
+//! and the assembler, taking and returning plain dicts and lists.
+//!
+//! ```python
+//! import evmrs
+//!
+//! result = evmrs.run({"code": evmrs.assemble("PUSH1 1\nPUSH1 2\nADD"), "trace": True})
+//! result["stack"], result["trace"]
+//! ```
+//!
+//! Values cross over as JSON, so words and bytes are 0x-prefixed hex
+//! strings, as in the JSON of `evmrs run` and `evm t8n`.
+
+use evm::t8n::{alloc_json, fork};
+use evm::{asm, hexutil, opcodes, playground, t8n};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde_json::{json, Value};
+
+fn to_value(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let json = object.py().import_bound("json")?;
+    let text: String = json.call_method1("dumps", (object,))?.extract()?;
+    serde_json::from_str(&text).map_err(|error| PyValueError::new_err(error.to_string()))
+}
+
+fn to_object(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    let json = py.import_bound("json")?;
+    Ok(json.call_method1("loads", (value.to_string(),))?.unbind())
+}
+
+/// Runs bytecode as `evm()` does. `request` is a playground request:
+/// `code`, and optionally `spec`, `tx`, `block`, `static` and `trace` for
+/// an EIP-3155 trace of every step.
+#[pyfunction]
+fn run(py: Python<'_>, request: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let output = playground::run(&to_value(request)?).map_err(PyValueError::new_err)?;
+    to_object(py, &output)
+}
+
+/// Applies signed or unsigned transactions to a pre-state, as `evm t8n`
+/// does, and returns the post-state `alloc` and the `result` of the block.
+#[pyfunction]
+#[pyo3(signature = (alloc, env, txs, fork_name = "Cancun", chain_id = 1))]
+fn transition(
+    py: Python<'_>,
+    alloc: &Bound<'_, PyAny>,
+    env: &Bound<'_, PyAny>,
+    txs: &Bound<'_, PyAny>,
+    fork_name: &str,
+    chain_id: u64,
+) -> PyResult<PyObject> {
+    let spec = fork(fork_name)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown fork {}", fork_name)))?;
+    let output = t8n::transition(
+        &to_value(alloc)?,
+        &to_value(env)?,
+        &to_value(txs)?,
+        spec,
+        chain_id,
+    )
+    .map_err(|error| PyValueError::new_err(error.to_string()))?;
+    let output = json!({ "alloc": alloc_json(&output.alloc), "result": output.result });
+    to_object(py, &output)
+}
+
+/// Bytecode, 0x-prefixed, of an assembly listing.
+#[pyfunction]
+fn assemble(source: &str) -> PyResult<String> {
+    let code = asm::assemble(source).map_err(|error| PyValueError::new_err(error.to_string()))?;
+    Ok(hexutil::encode_prefixed(&code))
+}
+
+/// One line per instruction of the bytecode.
+#[pyfunction]
+fn disassemble(code: &str) -> PyResult<String> {
+    let code = hexutil::decode(code).map_err(|error| PyValueError::new_err(error.to_string()))?;
+    Ok(opcodes::disassemble(&code))
+}
+
+#[pymodule]
+fn evmrs(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(run, module)?)?;
+    module.add_function(wrap_pyfunction!(transition, module)?)?;
+    module.add_function(wrap_pyfunction!(assemble, module)?)?;
+    module.add_function(wrap_pyfunction!(disassemble, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+"""The evmrs module, after `maturin develop` in rust/python."""
+
+import pytest
+
+import evmrs
+
+SENDER = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b"
+CONTRACT = "0x0000000000000000000000000000000000001000"
+SLOT = "0x" + "00" * 31 + "01"
+
+
+def test_run_returns_the_stack_and_trace():
+    code = evmrs.assemble("PUSH1 1\nPUSH1 2\nADD")
+    result = evmrs.run({"code": code, "trace": True})
+    assert result["success"]
+    assert result["stack"] == ["0x3"]
+    assert [step["opName"] for step in result["trace"]] == ["PUSH1", "PUSH1", "ADD"]
+
+
+def test_bad_requests_raise():
+    with pytest.raises(ValueError, match="code is required"):
+        evmrs.run({})
+    with pytest.raises(ValueError, match="unknown fork"):
+        evmrs.transition({}, {}, [], fork_name="Atlantis")
+
+
+def test_transition_applies_transactions():
+    alloc = {
+        SENDER: {"balance": "0x1000000000"},
+        # SSTORE(1, 42)
+        CONTRACT: {"code": "0x602a600155"},
+    }
+    env = {
+        "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+        "currentGasLimit": "0x1000000",
+        "currentNumber": "0x1",
+        "currentTimestamp": "0x3e8",
+        "currentBaseFee": "0x7",
+        "currentRandom": "0x0",
+    }
+    tx = {
+        "sender": SENDER,
+        "nonce": "0x0",
+        "gasPrice": "0xa",
+        "gas": "0x186a0",
+        "to": CONTRACT,
+        "value": "0x1",
+        "input": "0x",
+        "v": "0x1b",
+        "r": "0x1",
+        "s": "0x1",
+    }
+    output = evmrs.transition(alloc, env, [tx], fork_name="London")
+    assert output["result"]["receipts"][0]["status"] == "0x1"
+    assert output["alloc"][CONTRACT]["storage"][SLOT] == "0x" + "00" * 31 + "2a"
+
+
+def test_disassemble():
+    assert evmrs.disassemble("0x600101") == evmrs.disassemble(evmrs.assemble("PUSH1 1\nADD"))
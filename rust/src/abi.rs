@@ -0,0 +1,305 @@
+use core::fmt;
+
+use primitive_types::U256;
+use sha3::{Digest, Keccak256};
+
+use crate::prelude::*;
+
+/// First four bytes of the keccak hash of a signature like
+/// `transfer(address,uint256)`.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Calldata for a call to `signature` with static word arguments, such as
+/// `uint256` and `address`.
+pub fn encode_call(signature: &str, args: &[U256]) -> Vec<u8> {
+    let mut calldata = selector(signature).to_vec();
+    for arg in args {
+        let mut word = [0u8; 32];
+        arg.to_big_endian(&mut word);
+        calldata.extend_from_slice(&word);
+    }
+    calldata
+}
+
+/// The `index`th word of return data, `None` if it is too short.
+pub fn decode_word(output: &[u8], index: usize) -> Option<U256> {
+    let word = output.get(index * 32..(index + 1) * 32)?;
+    Some(U256::from_big_endian(word))
+}
+
+/// A Solidity type this module can encode and decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    /// Any `uintN`, held in a word.
+    Uint,
+    /// Any `intN`, held in a word in two's complement.
+    Int,
+    Address,
+    Bool,
+    Bytes,
+    String,
+    /// A dynamic array, `T[]`.
+    Array(Box<ParamType>),
+}
+
+impl ParamType {
+    /// Parses a type as it appears in a signature, `uint256[]` say.
+    pub fn parse(name: &str) -> Option<ParamType> {
+        if let Some(inner) = name.strip_suffix("[]") {
+            return Some(ParamType::Array(Box::new(ParamType::parse(inner)?)));
+        }
+        let bits = |digits: &str| {
+            digits.is_empty()
+                || digits
+                    .parse::<usize>()
+                    .is_ok_and(|bits| bits > 0 && bits <= 256 && bits % 8 == 0)
+        };
+        match name {
+            "address" => Some(ParamType::Address),
+            "bool" => Some(ParamType::Bool),
+            "bytes" => Some(ParamType::Bytes),
+            "string" => Some(ParamType::String),
+            _ => match (name.strip_prefix("uint"), name.strip_prefix("int")) {
+                (Some(digits), _) if bits(digits) => Some(ParamType::Uint),
+                (_, Some(digits)) if bits(digits) => Some(ParamType::Int),
+                _ => None,
+            },
+        }
+    }
+
+    fn is_dynamic(&self) -> bool {
+        matches!(
+            self,
+            ParamType::Bytes | ParamType::String | ParamType::Array(_)
+        )
+    }
+}
+
+/// A value of one of the types of `ParamType`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Uint(U256),
+    Int(U256),
+    Address(U256),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+}
+
+impl Token {
+    fn is_dynamic(&self) -> bool {
+        matches!(self, Token::Bytes(_) | Token::String(_) | Token::Array(_))
+    }
+}
+
+/// The types in the parentheses of a signature like
+/// `transfer(address,uint256)`, `None` if one is not supported.
+pub fn parse_params(signature: &str) -> Option<Vec<ParamType>> {
+    let start = signature.find('(')?;
+    let params = signature[start + 1..].strip_suffix(')')?;
+    if params.is_empty() {
+        return Some(vec![]);
+    }
+    params.split(',').map(ParamType::parse).collect()
+}
+
+/// Encodes `tokens` as the arguments of a call, or the values a function
+/// returns.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let mut head = vec![];
+    let mut tail = vec![];
+    for token in tokens {
+        if token.is_dynamic() {
+            head.extend_from_slice(&word(U256::from(tokens.len() * 32 + tail.len())));
+            tail.extend(encode_dynamic(token));
+        } else {
+            head.extend_from_slice(&word(static_word(token)));
+        }
+    }
+    head.extend(tail);
+    head
+}
+
+/// Calldata for a call to `signature` with `tokens` as its arguments.
+pub fn encode_function(signature: &str, tokens: &[Token]) -> Vec<u8> {
+    let mut calldata = selector(signature).to_vec();
+    calldata.extend(encode(tokens));
+    calldata
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+fn static_word(token: &Token) -> U256 {
+    match token {
+        Token::Uint(value) | Token::Int(value) | Token::Address(value) => *value,
+        Token::Bool(value) => U256::from(*value as u8),
+        _ => unreachable!("dynamic tokens have no static word"),
+    }
+}
+
+// Length first, then the contents padded to whole words
+fn encode_dynamic(token: &Token) -> Vec<u8> {
+    let bytes = match token {
+        Token::Bytes(bytes) => bytes.as_slice(),
+        Token::String(string) => string.as_bytes(),
+        Token::Array(items) => {
+            let mut encoded = word(U256::from(items.len())).to_vec();
+            encoded.extend(encode(items));
+            return encoded;
+        }
+        _ => unreachable!("static tokens are encoded in place"),
+    };
+    let mut encoded = word(U256::from(bytes.len())).to_vec();
+    encoded.extend_from_slice(bytes);
+    encoded.resize(32 + bytes.len().div_ceil(32) * 32, 0);
+    encoded
+}
+
+/// Decodes `data` as values of `types`, such as what a function returned.
+/// `None` if it is too short or a value is out of range for its type.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Option<Vec<Token>> {
+    types
+        .iter()
+        .enumerate()
+        .map(|(index, kind)| {
+            let value = decode_word(data, index)?;
+            if !kind.is_dynamic() {
+                return decode_static(kind, value);
+            }
+            if value > U256::from(data.len()) {
+                return None;
+            }
+            decode_dynamic(kind, &data[value.as_usize()..])
+        })
+        .collect()
+}
+
+fn decode_static(kind: &ParamType, value: U256) -> Option<Token> {
+    match kind {
+        ParamType::Uint => Some(Token::Uint(value)),
+        ParamType::Int => Some(Token::Int(value)),
+        ParamType::Address if value.bits() <= 160 => Some(Token::Address(value)),
+        ParamType::Bool if value <= U256::one() => Some(Token::Bool(!value.is_zero())),
+        _ => None,
+    }
+}
+
+fn decode_dynamic(kind: &ParamType, data: &[u8]) -> Option<Token> {
+    let length = decode_word(data, 0)?;
+    // Every item or byte takes up room, which bounds the length
+    if length > U256::from(data.len()) {
+        return None;
+    }
+    let length = length.as_usize();
+    let contents = &data[32..];
+    match kind {
+        ParamType::Bytes => Some(Token::Bytes(contents.get(..length)?.to_vec())),
+        ParamType::String => {
+            let bytes = contents.get(..length)?.to_vec();
+            String::from_utf8(bytes).ok().map(Token::String)
+        }
+        ParamType::Array(inner) => {
+            let types = vec![inner.as_ref().clone(); length];
+            decode(&types, contents).map(Token::Array)
+        }
+        _ => None,
+    }
+}
+
+/// Selector of `Error(string)`, what `require` and `revert` with a message
+/// revert with.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`, what failed `assert`s and checked
+/// arithmetic revert with.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// A revert payload, decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertReason {
+    Error(String),
+    Panic(U256),
+    /// A custom error, or anything else that starts with a selector. Its
+    /// arguments are left encoded.
+    Custom {
+        selector: [u8; 4],
+        data: Vec<u8>,
+    },
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevertReason::Error(message) => write!(f, "Error({:?})", message),
+            RevertReason::Panic(code) => match panic_name(*code) {
+                Some(name) => write!(f, "Panic({:#x}: {})", code, name),
+                None => write!(f, "Panic({:#x})", code),
+            },
+            RevertReason::Custom { selector, data } => write!(
+                f,
+                "custom error 0x{} with data 0x{}",
+                hex::encode(selector),
+                hex::encode(data)
+            ),
+        }
+    }
+}
+
+/// What a Solidity panic code stands for.
+pub fn panic_name(code: U256) -> Option<&'static str> {
+    if code > U256::from(u8::MAX) {
+        return None;
+    }
+    let name = match code.as_u32() {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "too much memory allocated",
+        0x51 => "call to an uninitialized internal function",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Decodes what REVERT returned, `None` for less than a selector. An
+/// `Error(string)` whose string does not decode is reported as `Custom`.
+pub fn decode_revert(output: &[u8]) -> Option<RevertReason> {
+    let selector: [u8; 4] = output.get(..4)?.try_into().unwrap();
+    let data = &output[4..];
+    let reason = match selector {
+        ERROR_SELECTOR => decode_string(data).map(RevertReason::Error),
+        PANIC_SELECTOR => decode_word(data, 0).map(RevertReason::Panic),
+        _ => None,
+    };
+    Some(reason.unwrap_or_else(|| RevertReason::Custom {
+        selector,
+        data: data.to_vec(),
+    }))
+}
+
+// A `string` that is the only argument: an offset to a length and the bytes
+fn decode_string(data: &[u8]) -> Option<String> {
+    let offset = decode_word(data, 0)?;
+    if offset > U256::from(data.len()) {
+        return None;
+    }
+    let offset = offset.as_usize();
+    let length = U256::from_big_endian(data.get(offset..offset + 32)?);
+    if length > U256::from(data.len()) {
+        return None;
+    }
+    let bytes = data.get(offset + 32..offset + 32 + length.as_usize())?;
+    String::from_utf8(bytes.to_vec()).ok()
+}
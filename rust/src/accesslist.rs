@@ -0,0 +1,83 @@
+//! The EIP-2930 access list of a transaction, as `eth_createAccessList`
+//! makes it: every account and storage key the execution touches, found
+//! from the instructions that touch them.
+//!
+//! Accounts that start out warm anyway, the sender, the recipient and the
+//! precompiles, are left out unless storage keys of theirs were touched.
+//! Keys are listed under the account whose storage they are, which for code
+//! run by DELEGATECALL or CALLCODE is the caller.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use primitive_types::U256;
+
+use crate::inspector::{Inspector, Step};
+use crate::prelude::*;
+use crate::AccessListItem;
+
+/// Collects the access list of the executions it inspects.
+#[derive(Debug, Clone, Default)]
+pub struct AccessListTracer {
+    excluded: BTreeSet<U256>,
+    accounts: BTreeMap<U256, BTreeSet<U256>>,
+}
+
+impl AccessListTracer {
+    /// Leaves out `excluded` when nothing but the account was touched.
+    pub fn new(excluded: impl IntoIterator<Item = U256>) -> Self {
+        Self {
+            excluded: excluded.into_iter().collect(),
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// The accounts touched, in the order of their addresses, each with its
+    /// storage keys in order.
+    pub fn access_list(&self) -> Vec<AccessListItem> {
+        self.accounts
+            .iter()
+            .map(|(address, keys)| AccessListItem {
+                address: *address,
+                storage_keys: keys.iter().copied().collect(),
+            })
+            .collect()
+    }
+
+    fn account(&mut self, address: U256) {
+        if !self.excluded.contains(&address) {
+            self.accounts.entry(address).or_default();
+        }
+    }
+
+    fn slot(&mut self, address: U256, key: U256) {
+        self.accounts.entry(address).or_default().insert(key);
+    }
+}
+
+impl Inspector for AccessListTracer {
+    fn step(&mut self, step: &Step) {
+        let stack = step.stack;
+        let top = |n: usize| stack.len().checked_sub(n + 1).map(|index| stack[index]);
+        match step.opcode {
+            // SLOAD, SSTORE
+            0x54 | 0x55 => {
+                if let Some(key) = top(0) {
+                    self.slot(step.data.env.tx.address, key);
+                }
+            }
+            // BALANCE, EXTCODESIZE, EXTCODECOPY, EXTCODEHASH, SELFDESTRUCT
+            0x31 | 0x3b | 0x3c | 0x3f | 0xff => {
+                if let Some(address) = top(0) {
+                    self.account(address);
+                }
+            }
+            // CALL, CALLCODE, DELEGATECALL, STATICCALL
+            0xf1 | 0xf2 | 0xf4 | 0xfa => {
+                if let Some(address) = top(1) {
+                    self.account(address);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! What the interpreter works out about code before running it: the offsets
+//! that are JUMPDEST instructions rather than PUSH data. It is done once per
+//! contract and shared by every frame that runs the same code.
+
+use alloc::sync::Arc;
+
+use primitive_types::H256;
+
+use crate::prelude::*;
+use crate::trie::keccak;
+
+/// The valid jump destinations of some code, one bit per byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpDests {
+    bits: Vec<u64>,
+}
+
+impl JumpDests {
+    pub fn analyze(code: &[u8]) -> Self {
+        let mut bits = vec![0u64; code.len().div_ceil(64)];
+        let mut pc = 0;
+        while pc < code.len() {
+            let opcode = code[pc];
+            if opcode == 0x5b {
+                bits[pc / 64] |= 1 << (pc % 64);
+            } else if (0x60..=0x7f).contains(&opcode) {
+                // Skip the immediate of PUSH1-PUSH32
+                pc += (opcode - 0x5f) as usize;
+            }
+            pc += 1;
+        }
+        Self { bits }
+    }
+
+    pub fn is_valid(&self, dest: usize) -> bool {
+        self.bits
+            .get(dest / 64)
+            .is_some_and(|word| word & (1 << (dest % 64)) != 0)
+    }
+}
+
+/// Analyses by code hash.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisCache {
+    jump_dests: HashMap<H256, Arc<JumpDests>>,
+}
+
+impl AnalysisCache {
+    /// The jump destinations of `code`, analysed on first sight.
+    pub fn jump_dests(&mut self, code: &[u8]) -> Arc<JumpDests> {
+        self.jump_dests
+            .entry(keccak(code))
+            .or_insert_with(|| Arc::new(JumpDests::analyze(code)))
+            .clone()
+    }
+
+    /// Number of distinct pieces of code analysed.
+    pub fn len(&self) -> usize {
+        self.jump_dests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jump_dests.is_empty()
+    }
+}
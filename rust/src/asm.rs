@@ -0,0 +1,221 @@
+//! The inverse of `opcodes::disassemble`: assembly text to bytecode.
+//!
+//! One instruction per line, as in the fixtures: `PUSH1 0x42`, `JUMPDEST`.
+//! Push data is hex with a `0x` prefix or decimal, and is left padded to the
+//! width of the push. A line `name:` marks the position of the next
+//! instruction, and a push of `name` pushes that position, so jumps can be
+//! written without counting bytes. Everything after `//` is a comment.
+//!
+//! The EOF instructions take their immediates the same way. The offsets of
+//! RJUMP and RJUMPI may be negative, and a label there stands for the
+//! offset to it; RJUMPV takes all its immediates as one hex argument.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use primitive_types::U256;
+
+use crate::hexutil;
+use crate::opcodes;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownInstruction {
+        line: usize,
+        text: String,
+    },
+    MissingArgument {
+        line: usize,
+    },
+    UnexpectedArgument {
+        line: usize,
+    },
+    InvalidArgument {
+        line: usize,
+        text: String,
+    },
+    /// The argument does not fit in the bytes of the push.
+    ArgumentTooLong {
+        line: usize,
+        width: usize,
+    },
+    UnknownLabel {
+        line: usize,
+        label: String,
+    },
+    DuplicateLabel {
+        line: usize,
+        label: String,
+    },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::UnknownInstruction { line, text } => {
+                write!(f, "line {}: unknown instruction {}", line, text)
+            }
+            AsmError::MissingArgument { line } => write!(f, "line {}: push without data", line),
+            AsmError::UnexpectedArgument { line } => {
+                write!(f, "line {}: instruction takes no argument", line)
+            }
+            AsmError::InvalidArgument { line, text } => {
+                write!(f, "line {}: invalid push data {}", line, text)
+            }
+            AsmError::ArgumentTooLong { line, width } => {
+                write!(f, "line {}: push data longer than {} bytes", line, width)
+            }
+            AsmError::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label {}", line, label)
+            }
+            AsmError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label {} defined twice", line, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Names accepted besides the ones `opcodes::name` gives.
+const ALIASES: [(&str, u8); 2] = [("KECCAK256", 0x20), ("PREVRANDAO", 0x44)];
+
+pub fn opcode(name: &str) -> Option<u8> {
+    let name = name.to_uppercase();
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, opcode)| *opcode)
+        .or_else(|| (0..=255u8).find(|opcode| opcodes::name(*opcode) == Some(name.as_str())))
+}
+
+pub fn assemble(text: &str) -> Result<Vec<u8>, AsmError> {
+    let mut code = Vec::new();
+    let mut labels = HashMap::new();
+    // Pushes of labels, filled in once every label is known
+    let mut references = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split("//").next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(label) = line.strip_suffix(':') {
+            let label = label.trim().to_string();
+            if labels.insert(label.clone(), code.len()).is_some() {
+                return Err(AsmError::DuplicateLabel {
+                    line: line_number,
+                    label,
+                });
+            }
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap();
+        let argument = words.next();
+        if let Some(extra) = words.next() {
+            return Err(AsmError::InvalidArgument {
+                line: line_number,
+                text: extra.to_string(),
+            });
+        }
+        let opcode = opcode(name).ok_or_else(|| AsmError::UnknownInstruction {
+            line: line_number,
+            text: name.to_string(),
+        })?;
+        code.push(opcode);
+
+        let invalid = |text: &str| AsmError::InvalidArgument {
+            line: line_number,
+            text: text.to_string(),
+        };
+        // PUSH1 to PUSH32 and the EOF instructions, PUSH0 takes no data
+        let width = match (opcode, argument) {
+            (0xe2, Some(argument)) => {
+                let immediates = hexutil::decode(argument).map_err(|_| invalid(argument))?;
+                code.extend(immediates);
+                continue;
+            }
+            _ => opcodes::immediate_size(&[opcode]),
+        };
+        if width == 0 {
+            if argument.is_some() {
+                return Err(AsmError::UnexpectedArgument { line: line_number });
+            }
+            continue;
+        }
+        let argument = argument.ok_or(AsmError::MissingArgument { line: line_number })?;
+        // RJUMP, RJUMPI
+        let relative = opcode == 0xe0 || opcode == 0xe1;
+        let value = if let Some(offset) = argument.strip_prefix('-').filter(|_| relative) {
+            let offset = parse_number(offset).ok_or_else(|| invalid(argument))?;
+            negative(offset, width, line_number)?
+        } else if argument.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_number(argument).ok_or_else(|| invalid(argument))?
+        } else {
+            references.push((
+                line_number,
+                code.len(),
+                width,
+                relative,
+                argument.to_string(),
+            ));
+            U256::zero()
+        };
+        code.extend(push_data(value, width, line_number)?);
+    }
+
+    for (line, offset, width, relative, label) in references {
+        let position = *labels.get(&label).ok_or_else(|| AsmError::UnknownLabel {
+            line,
+            label: label.clone(),
+        })?;
+        let value = if relative {
+            // From the end of the immediate, in the width of the immediate
+            let from = offset + width;
+            let distance = U256::from(position.abs_diff(from));
+            if position >= from {
+                distance
+            } else {
+                negative(distance, width, line)?
+            }
+        } else {
+            U256::from(position)
+        };
+        let data = push_data(value, width, line)?;
+        code[offset..offset + width].copy_from_slice(&data);
+    }
+    Ok(code)
+}
+
+fn parse_number(text: &str) -> Option<U256> {
+    if text.starts_with("0x") || text.starts_with("0X") {
+        hexutil::parse_u256(text).ok()
+    } else {
+        U256::from_dec_str(text).ok()
+    }
+}
+
+// `value` as the `width` big-endian bytes of a push
+fn push_data(value: U256, width: usize, line: usize) -> Result<Vec<u8>, AsmError> {
+    if value.bits() > width * 8 {
+        return Err(AsmError::ArgumentTooLong { line, width });
+    }
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    Ok(bytes[32 - width..].to_vec())
+}
+
+// `-value` in two's complement, in the `width` bytes of an immediate
+fn negative(value: U256, width: usize, line: usize) -> Result<U256, AsmError> {
+    let limit = U256::one() << (width * 8 - 1);
+    if value.is_zero() {
+        return Ok(value);
+    }
+    if value > limit {
+        return Err(AsmError::ArgumentTooLong { line, width });
+    }
+    Ok((limit << 1) - value)
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use primitive_types::U256;
+
+use crate::inspector::{Inspector, Step};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Assertion {
+    /// Every time the instruction at `pc` is about to run, the top of the
+    /// stack equals `value`. The pc must be reached at least once.
+    StackTop { pc: usize, value: U256 },
+    /// Storage slot `key` is written exactly `count` times.
+    StorageWrites { key: U256, count: usize },
+}
+
+/// Checks assertions against the execution as it happens, for regression
+/// tests of interpreter internals. Collect the outcome with `failures`.
+#[derive(Debug, Default)]
+pub struct AssertionInspector {
+    assertions: Vec<Assertion>,
+    reached: HashMap<usize, usize>,
+    writes: HashMap<U256, usize>,
+    failures: Vec<String>,
+}
+
+impl AssertionInspector {
+    pub fn new(assertions: Vec<Assertion>) -> Self {
+        Self {
+            assertions,
+            ..Default::default()
+        }
+    }
+
+    pub fn stack_top_at(mut self, pc: usize, value: U256) -> Self {
+        self.assertions.push(Assertion::StackTop { pc, value });
+        self
+    }
+
+    pub fn slot_written(mut self, key: U256, count: usize) -> Self {
+        self.assertions
+            .push(Assertion::StorageWrites { key, count });
+        self
+    }
+
+    /// Every assertion that did not hold, empty when all of them passed.
+    pub fn failures(&self) -> Vec<String> {
+        let mut failures = self.failures.clone();
+        for assertion in &self.assertions {
+            match assertion {
+                Assertion::StackTop { pc, .. } => {
+                    if !self.reached.contains_key(pc) {
+                        failures.push(format!("pc {:#x} was never reached", pc));
+                    }
+                }
+                Assertion::StorageWrites { key, count } => {
+                    let writes = self.writes.get(key).copied().unwrap_or(0);
+                    if writes != *count {
+                        failures.push(format!(
+                            "slot {:#x} written {} times, expected {}",
+                            key, writes, count
+                        ));
+                    }
+                }
+            }
+        }
+        failures
+    }
+}
+
+impl Inspector for AssertionInspector {
+    fn step(&mut self, step: &Step) {
+        *self.reached.entry(step.pc).or_insert(0) += 1;
+
+        for assertion in &self.assertions {
+            if let Assertion::StackTop { pc, value } = assertion {
+                if *pc != step.pc {
+                    continue;
+                }
+                match step.stack.last() {
+                    Some(top) if top == value => {}
+                    Some(top) => self.failures.push(format!(
+                        "at pc {:#x} stack top is {:#x}, expected {:#x}",
+                        pc, top, value
+                    )),
+                    None => self.failures.push(format!(
+                        "at pc {:#x} stack is empty, expected {:#x}",
+                        pc, value
+                    )),
+                }
+            }
+        }
+    }
+
+    fn sstore(&mut self, key: U256, _value: U256) {
+        *self.writes.entry(key).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,666 @@
+//! Tools for running raw bytecode.
+//!
+//! `evmrs run --code <hex>` runs the code once, in a block and transaction
+//! set up with the options below, and prints the result. `evmrs debug <hex>
+//! [--data <hex>]` steps through the code in a terminal debugger.
+//! `evmrs run --profile` adds a table of counts, gas and time by opcode,
+//! and whatever the code prints through Hardhat's console.log follows the
+//! result.
+//! `evmrs t8n` is a state transition tool taking the flags and JSON of
+//! geth's `evm t8n`. `evmrs scenario run <file>` runs the deploys and calls
+//! of a scenario file and checks what each one should come to. `evmrs
+//! trace-diff <left> <right>` points at the first step where two EIP-3155
+//! traces differ. `evmrs replay <hash> --rpc <url>` runs a transaction of a
+//! live chain again and checks it against its receipt.
+
+use std::io::{self, BufRead, Read, Write};
+use std::panic;
+use std::path::Path;
+use std::thread;
+
+use evm::console::ConsoleLogger;
+use evm::debugger::Debugger;
+use evm::explain::ExplainTracer;
+use evm::fmtutil::hex_address;
+use evm::fork::ForkDb;
+use evm::inspector::Inspector;
+use evm::profiler::Profiler;
+use evm::replay::ReplayOptions;
+use evm::scenario::Scenario;
+use evm::spec::{ChainConfig, SpecId};
+use evm::t8n::{alloc_json, fork, transition};
+use evm::tracediff::{diff, load_trace};
+use evm::tracer::StreamTracer;
+use evm::tui::{render, Command};
+use evm::{
+    evm_with_inspector, hexutil, EvmContext, EvmData, EvmResult, TxData, EXECUTION_STACK_SIZE,
+};
+use serde_json::{json, Map, Value};
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+const USAGE: &str = "Usage:
+  evmrs run --code <hex> [options]
+  evmrs debug <hex> [--data <hex>]
+  evmrs t8n [options]
+  evmrs scenario run <file> [--json]
+                    Steps of a JSON or TOML scenario, failing on any
+                    expectation not met
+  evmrs replay <tx hash> --rpc <url> [--spec <fork>] [--isolated] [--json]
+                    Run a transaction of the chain again, on the state of
+                    its block, and compare it with its receipt
+  evmrs trace-diff <left> <right>
+                    The first step where two EIP-3155 traces differ
+  evmrs serve [options]     With the rpc feature
+
+Options of run:
+  --calldata <hex>  --value <n>  --from <address>  --to <address>
+  --origin <address>  --gas <n>  --gasprice <n>
+  --coinbase <address>  --number <n>  --timestamp <n>  --basefee <n>
+  --gaslimit <n>  --difficulty <n>  --chainid <n>
+  --spec <fork>     Cancun unless given
+  --static          Run without state changes, as STATICCALL does
+  --prestate <file> Start from a state written by --dump
+  --dump <file>     Write the state after the run, as JSON
+  --trace           An EIP-3155 line per instruction on stderr
+  --explain         Each instruction in words
+  --profile         Count, gas and time by opcode on stderr
+  --cheatcodes      Answer Foundry's cheatcodes at 0x7109709e...d12d
+  --json            The result as JSON
+
+Options of t8n, as geth's `evm t8n` takes them:
+  --input.alloc <file>  --input.env <file>  --input.txs <file>
+                    alloc.json, env.json and txs.json unless given; `stdin`
+                    reads one object with alloc, env and txs
+  --output.basedir <dir>
+  --output.result <file>  --output.alloc <file>
+                    result.json and alloc.json unless given; `stdout` or
+                    `stderr` print them in one object
+  --state.fork <fork>  Cancun unless given
+  --state.chainid <n>  1 unless given
+
+Options of serve, a JSON-RPC server for eth_call and friends:
+  --port <n>        8545 unless given
+  --spec <fork>     Cancun unless given
+  --fork.url <url>  --fork.block <n>
+                    Read state missing locally from a node, as of the block
+  --call-cache <n>  Answer repeated calls from the results of up to n calls,
+                    while what they read is unchanged
+  --dev             A dev node: take transactions, mine them into blocks
+                    and answer the cheats of Anvil
+  --chainid <n>     Of the dev node, 31337 unless given
+  --genesis <file>  Start from the accounts and block 0 of a geth genesis
+                    file, with its fork and chain id unless given";
+
+// Options of `run` that set a field of the transaction or of the block,
+// with the name of the field
+const TX_OPTIONS: [(&str, &str); 7] = [
+    ("--calldata", "data"),
+    ("--value", "value"),
+    ("--from", "from"),
+    ("--to", "to"),
+    ("--origin", "origin"),
+    ("--gas", "gas"),
+    ("--gasprice", "gasprice"),
+];
+const BLOCK_OPTIONS: [(&str, &str); 7] = [
+    ("--coinbase", "coinbase"),
+    ("--number", "number"),
+    ("--timestamp", "timestamp"),
+    ("--basefee", "basefee"),
+    ("--gaslimit", "gaslimit"),
+    ("--difficulty", "difficulty"),
+    ("--chainid", "chainid"),
+];
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+// Numbers can be given in decimal or hex, the maps of `EvmData` take hex
+fn quantity(option: &str, text: &str) -> String {
+    let value = if text.starts_with("0x") {
+        hexutil::parse_u256(text).map_err(|error| error.to_string())
+    } else {
+        primitive_types::U256::from_dec_str(text).map_err(|error| format!("{:?}", error))
+    };
+    match value {
+        Ok(value) => format!("{:#x}", value),
+        Err(error) => fail(format!("Invalid {} {}: {}", option, text, error)),
+    }
+}
+
+fn run(args: &[String]) {
+    let mut code = None;
+    let mut tx = Map::new();
+    let mut block = Map::new();
+    let mut spec = SpecId::default();
+    let (mut writable, mut trace, mut explain, mut as_json) = (true, false, false, false);
+    let (mut profile, mut cheatcodes) = (false, false);
+    let (mut prestate, mut dump) = (None, None);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage()).clone();
+        match arg.as_str() {
+            "--code" => code = Some(value()),
+            "--spec" => {
+                let name = value();
+                spec = serde_json::from_value(json!(name))
+                    .unwrap_or_else(|_| fail(format!("Unknown fork {}", name)));
+            }
+            "--static" => writable = false,
+            "--prestate" => prestate = Some(value()),
+            "--dump" => dump = Some(value()),
+            "--trace" => trace = true,
+            "--explain" => explain = true,
+            "--profile" => profile = true,
+            "--cheatcodes" => cheatcodes = true,
+            "--json" => as_json = true,
+            option => {
+                if let Some((_, field)) = TX_OPTIONS.iter().find(|(name, _)| *name == option) {
+                    let value = value();
+                    let value = match *field {
+                        "data" | "from" | "to" | "origin" => value,
+                        _ => quantity(option, &value),
+                    };
+                    tx.insert(field.to_string(), json!(value));
+                } else if let Some((_, field)) =
+                    BLOCK_OPTIONS.iter().find(|(name, _)| *name == option)
+                {
+                    let value = value();
+                    let value = match *field {
+                        "coinbase" => value,
+                        _ => quantity(option, &value),
+                    };
+                    block.insert(field.to_string(), json!(value));
+                } else {
+                    usage();
+                }
+            }
+        }
+    }
+    if [trace, explain, profile].iter().filter(|&&on| on).count() > 1 {
+        fail("Only one of --trace, --explain and --profile can be given".to_string());
+    }
+    let code = code.unwrap_or_else(|| usage());
+    let code =
+        hexutil::decode(&code).unwrap_or_else(|error| fail(format!("Invalid bytecode: {}", error)));
+    tx.entry("data").or_insert(json!(""));
+
+    let mut data = EvmData {
+        tx_data: Some(serde_json::from_value::<TxData>(Value::Object(tx)).unwrap()),
+        context: Some(serde_json::from_value::<EvmContext>(Value::Object(block)).unwrap()),
+        config: ChainConfig::new(spec),
+        ..Default::default()
+    };
+    data.evm_config.cheatcodes = cheatcodes;
+    if let Some(path) = &prestate {
+        data.load_dump(path)
+            .unwrap_or_else(|error| fail(format!("Cannot load {}: {}", path, error)));
+    }
+
+    let (result, explanation, console) = if trace {
+        let mut tracer = StreamTracer::new(io::stderr());
+        let (result, console) = run_logged(&code, &mut data, writable, &mut tracer);
+        if let Err(error) = tracer.finish() {
+            fail(format!("Cannot write the trace: {}", error));
+        }
+        (result, vec![], console)
+    } else if profile {
+        let mut profiler = Profiler::new();
+        let (result, console) = run_logged(&code, &mut data, writable, &mut profiler);
+        eprint!("{}", profiler.table());
+        (result, vec![], console)
+    } else if explain {
+        let mut tracer = ExplainTracer::new();
+        let (result, console) = run_logged(&code, &mut data, writable, &mut tracer);
+        let lines = tracer.finish(&result);
+        (result, lines, console)
+    } else {
+        let (result, console) = run_logged(&code, &mut data, writable, &mut ());
+        (result, vec![], console)
+    };
+    if let Some(path) = &dump {
+        data.save_dump(path)
+            .unwrap_or_else(|error| fail(format!("Cannot write {}: {}", path, error)));
+    }
+
+    if as_json {
+        let mut output = result.to_json();
+        if explain {
+            output["explanation"] = json!(explanation);
+        }
+        if !console.is_empty() {
+            output["console"] = json!(console);
+        }
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        for line in &explanation {
+            println!("{}", line);
+        }
+        if !explanation.is_empty() {
+            println!();
+        }
+        print_result(&result);
+        if !console.is_empty() {
+            println!("Console:");
+            for message in &console {
+                println!("  {}", message);
+            }
+        }
+    }
+}
+
+// Runs the code with `inspector`, collecting what it prints through
+// console.log
+fn run_logged(
+    code: &[u8],
+    data: &mut EvmData,
+    writable: bool,
+    inspector: &mut dyn Inspector,
+) -> (EvmResult, Vec<String>) {
+    let mut logger = ConsoleLogger::new(inspector);
+    let result = evm_with_inspector(code, data, writable, &mut logger);
+    (result, logger.messages)
+}
+
+fn print_result(result: &EvmResult) {
+    println!("Success: {}", result.success);
+    println!(
+        "Gas used: {} (refunded {})",
+        result.gas_used, result.gas_refunded
+    );
+    println!("Stack ({} items, top first):", result.stack.len());
+    for value in &result.stack {
+        println!("  {:#x}", value);
+    }
+    println!(
+        "Return data: {}",
+        hexutil::encode_prefixed(&result.return_data)
+    );
+    if let Some(reason) = result.revert_reason() {
+        println!("Revert reason: {}", reason);
+    }
+    println!("Logs: {}", result.logs.len());
+    for log in &result.logs {
+        println!("  {}", hex_address(log.address));
+        for topic in &log.topics {
+            println!("    topic {:#x}", topic);
+        }
+        println!("    data {}", hexutil::encode_prefixed(&log.data));
+    }
+}
+
+fn debug(args: &[String]) {
+    let mut code = None;
+    let mut input = String::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--data" {
+            input = args.next().unwrap_or_else(|| usage()).clone();
+        } else if code.is_none() {
+            code = Some(arg.clone());
+        } else {
+            usage();
+        }
+    }
+    let code = code.unwrap_or_else(|| usage());
+    let code = hexutil::decode(&code).unwrap_or_else(|error| {
+        eprintln!("Invalid bytecode: {}", error);
+        std::process::exit(1);
+    });
+
+    let mut data = EvmData {
+        tx_data: Some(serde_json::from_value::<TxData>(json!({ "data": input })).unwrap()),
+        ..Default::default()
+    };
+    let mut debugger = Debugger::new(code, &mut data, true);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}{}\n> ", CLEAR, render(&debugger));
+        io::stdout().flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        match Command::parse(&line) {
+            Some(command) => {
+                if !command.apply(&mut debugger) {
+                    break;
+                }
+            }
+            None => continue,
+        }
+    }
+}
+
+// One of the inputs of `t8n`, from its file or from the object on stdin
+fn t8n_input(path: &str, field: &str, stdin: &mut Option<Value>) -> Value {
+    if path == "stdin" {
+        if stdin.is_none() {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .unwrap_or_else(|error| fail(format!("Cannot read stdin: {}", error)));
+            let input = serde_json::from_str(&text)
+                .unwrap_or_else(|error| fail(format!("Invalid JSON on stdin: {}", error)));
+            *stdin = Some(input);
+        }
+        return stdin.as_ref().unwrap()[field].clone();
+    }
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|error| fail(format!("Cannot read {}: {}", path, error)));
+    serde_json::from_str(&text).unwrap_or_else(|error| fail(format!("Invalid {}: {}", path, error)))
+}
+
+fn t8n(args: &[String]) {
+    let mut inputs = [
+        ("alloc", "alloc.json".to_string()),
+        ("env", "env.json".to_string()),
+        ("txs", "txs.json".to_string()),
+    ];
+    let mut basedir = String::new();
+    let mut result_path = "result.json".to_string();
+    let mut alloc_path = "alloc.json".to_string();
+    let mut spec = SpecId::default();
+    let mut chain_id = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // Both `--flag value` and `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let value = || inline.unwrap_or_else(|| args.next().unwrap_or_else(|| usage()).clone());
+        match flag {
+            "--input.alloc" => inputs[0].1 = value(),
+            "--input.env" => inputs[1].1 = value(),
+            "--input.txs" => inputs[2].1 = value(),
+            "--output.basedir" => basedir = value(),
+            "--output.result" => result_path = value(),
+            "--output.alloc" => alloc_path = value(),
+            "--state.fork" => {
+                let name = value();
+                spec = fork(&name).unwrap_or_else(|| fail(format!("Unknown fork {}", name)));
+            }
+            "--state.chainid" => {
+                let text = quantity(flag, &value());
+                chain_id = hexutil::parse_u64(&text)
+                    .unwrap_or_else(|_| fail(format!("Invalid {} {}", flag, text)));
+            }
+            _ => usage(),
+        }
+    }
+
+    let mut stdin = None;
+    let [alloc, env, txs] = inputs.map(|(field, path)| t8n_input(&path, field, &mut stdin));
+    let output = transition(&alloc, &env, &txs, spec, chain_id)
+        .unwrap_or_else(|error| fail(error.to_string()));
+
+    let mut printed = Map::new();
+    let mut printed_to = None;
+    for (path, field, value) in [
+        (result_path, "result", output.result),
+        (alloc_path, "alloc", alloc_json(&output.alloc)),
+    ] {
+        match path.as_str() {
+            "stdout" | "stderr" => {
+                printed.insert(field.to_string(), value);
+                printed_to = Some(path);
+            }
+            _ => {
+                let path = Path::new(&basedir).join(&path);
+                let text = serde_json::to_string_pretty(&value).unwrap();
+                std::fs::write(&path, text).unwrap_or_else(|error| {
+                    fail(format!("Cannot write {}: {}", path.display(), error))
+                });
+            }
+        }
+    }
+    let printed = serde_json::to_string_pretty(&Value::Object(printed)).unwrap();
+    match printed_to.as_deref() {
+        Some("stdout") => println!("{}", printed),
+        Some(_) => eprintln!("{}", printed),
+        None => {}
+    }
+}
+
+fn scenario(args: &[String]) {
+    let (path, as_json) = match args {
+        [command, path] if command == "run" => (path, false),
+        [command, path, json] | [command, json, path] if command == "run" && json == "--json" => {
+            (path, true)
+        }
+        _ => usage(),
+    };
+    let scenario = Scenario::load(path).unwrap_or_else(|error| fail(error.to_string()));
+    let report = scenario
+        .run()
+        .unwrap_or_else(|error| fail(error.to_string()));
+    if as_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_json()).unwrap()
+        );
+    } else {
+        for step in &report.steps {
+            let status = if step.failures.is_empty() {
+                "ok"
+            } else {
+                "FAIL"
+            };
+            println!("{:4}  {}", status, step.name);
+            for failure in &step.failures {
+                println!("        {}", failure);
+            }
+        }
+    }
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
+
+fn replay(args: &[String]) {
+    let (mut hash, mut url, mut as_json) = (None, None, false);
+    let mut options = ReplayOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage()).clone();
+        match arg.as_str() {
+            "--rpc" => url = Some(value()),
+            "--spec" => {
+                let name = value();
+                options.spec = Some(
+                    serde_json::from_value(json!(name))
+                        .unwrap_or_else(|_| fail(format!("Unknown fork {}", name))),
+                );
+            }
+            "--isolated" => options.isolated = true,
+            "--json" => as_json = true,
+            _ if hash.is_none() => hash = Some(arg.clone()),
+            _ => usage(),
+        }
+    }
+    let (Some(hash), Some(url)) = (hash, url) else {
+        usage();
+    };
+    let hash = hexutil::parse_u256(&hash)
+        .unwrap_or_else(|error| fail(format!("Invalid transaction hash {}: {}", hash, error)));
+    let fork = ForkDb::http(&url, 0).unwrap_or_else(|error| fail(error.to_string()));
+    let replay = evm::replay::replay(fork, hash, &options)
+        .unwrap_or_else(|error| fail(format!("Cannot replay: {}", error)));
+
+    if as_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&replay.to_json()).unwrap()
+        );
+    } else {
+        println!(
+            "Block {}, transaction {}, as {:?}",
+            replay.block, replay.index, replay.spec
+        );
+        println!("Success: {}", replay.actual.success);
+        println!("Gas used: {}", replay.actual.gas_used);
+        println!("Logs: {}", replay.actual.logs.len());
+        println!("Output: {}", hexutil::encode_prefixed(&replay.output));
+        if replay.matches() {
+            println!("Matches the receipt");
+        } else {
+            println!("Differs from the receipt:");
+            for mismatch in &replay.mismatches {
+                println!("  {}", mismatch);
+            }
+        }
+    }
+    if !replay.matches() {
+        std::process::exit(1);
+    }
+}
+
+fn trace_diff(args: &[String]) {
+    let [left, right] = args else {
+        usage();
+    };
+    let load = |path: &String| {
+        load_trace(path).unwrap_or_else(|error| fail(format!("Cannot read {}: {}", path, error)))
+    };
+    let (left, right) = (load(left), load(right));
+    match diff(&left, &right) {
+        None => println!("Traces match over {} steps", left.len()),
+        Some(divergence) => {
+            print!("{}", divergence);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(feature = "rpc")]
+fn serve(args: &[String]) {
+    use std::sync::Arc;
+
+    use evm::fork::ForkDb;
+    use evm::genesis::Genesis;
+    use evm::node::{DevNode, DEV_CHAIN_ID};
+    use evm::rpc::RpcServer;
+    use evm::service::EvmService;
+
+    let mut port = 8545;
+    let (mut spec, mut chain_id) = (None, None);
+    let mut dev = false;
+    let mut genesis = None;
+    let (mut fork_url, mut fork_block) = (None, None);
+    let mut call_cache = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage()).clone();
+        match arg.as_str() {
+            "--port" => {
+                let text = value();
+                port = text
+                    .parse()
+                    .unwrap_or_else(|_| fail(format!("Invalid --port {}", text)));
+            }
+            "--spec" => {
+                let name = value();
+                spec = Some(
+                    serde_json::from_value(json!(name))
+                        .unwrap_or_else(|_| fail(format!("Unknown fork {}", name))),
+                );
+            }
+            "--dev" => dev = true,
+            "--call-cache" => {
+                let text = value();
+                let capacity = text.parse().unwrap_or_else(|_| {
+                    fail(format!(
+                        "--call-cache takes a number of calls, not {}",
+                        text
+                    ))
+                });
+                call_cache = Some(capacity);
+            }
+            "--chainid" => {
+                let text = value();
+                chain_id = Some(hexutil::parse_u64(&quantity("--chainid", &text)).unwrap());
+            }
+            "--genesis" => {
+                let path = value();
+                genesis = Some(
+                    Genesis::load(&path)
+                        .unwrap_or_else(|error| fail(format!("Cannot load {}: {}", path, error))),
+                );
+            }
+            "--fork.url" => fork_url = Some(value()),
+            "--fork.block" => {
+                let text = value();
+                let block = hexutil::parse_u64(&quantity("--fork.block", &text)).unwrap();
+                fork_block = Some(block);
+            }
+            _ => usage(),
+        }
+    }
+
+    let mut data = genesis.as_ref().map(Genesis::evm_data).unwrap_or_default();
+    let genesis_spec = genesis.as_ref().map(|genesis| genesis.spec);
+    data.config = ChainConfig::new(spec.or(genesis_spec).unwrap_or_default());
+    let chain_id = chain_id
+        .or(genesis.map(|genesis| genesis.chain_id))
+        .unwrap_or(DEV_CHAIN_ID);
+    match (fork_url, fork_block) {
+        (Some(url), Some(block)) => {
+            let db = ForkDb::http(&url, block).unwrap_or_else(|error| fail(error.to_string()));
+            data.database = Some(Arc::new(db));
+        }
+        (None, None) => {}
+        _ => fail("--fork.url and --fork.block go together".to_string()),
+    }
+    let address = ("127.0.0.1", port);
+    let server = if dev {
+        RpcServer::bind_dev(address, DevNode::new(data, chain_id))
+    } else {
+        let service = EvmService::new(data);
+        match call_cache {
+            Some(capacity) => RpcServer::bind(address, service.with_call_cache(capacity)),
+            None => RpcServer::bind(address, service),
+        }
+    };
+    let server =
+        server.unwrap_or_else(|error| fail(format!("Cannot listen on port {}: {}", port, error)));
+    eprintln!("Listening on http://{}", server.local_addr().unwrap());
+    if let Err(error) = server.serve() {
+        fail(error.to_string());
+    }
+}
+
+#[cfg(not(feature = "rpc"))]
+fn serve(_args: &[String]) {
+    fail("evmrs was built without the rpc feature".to_string());
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // The main thread is too small for calls nested to the depth limit
+    let command = thread::Builder::new().stack_size(EXECUTION_STACK_SIZE);
+    let command = command.spawn(move || match args.first().map(String::as_str) {
+        Some("run") => run(&args[1..]),
+        Some("debug") => debug(&args[1..]),
+        Some("t8n") => t8n(&args[1..]),
+        Some("scenario") => scenario(&args[1..]),
+        Some("replay") => replay(&args[1..]),
+        Some("trace-diff") => trace_diff(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        _ => usage(),
+    });
+    if let Err(payload) = command.unwrap().join() {
+        panic::resume_unwind(payload);
+    }
+}
@@ -0,0 +1,641 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use primitive_types::{H256, U256};
+use sha3::{Digest, Keccak256};
+
+use crate::cheatcodes::CHEATCODE_ADDRESS;
+use crate::env::{fee_caps, BlockEnv};
+use crate::host::Database;
+use crate::inspector::{CallFrame, CreateFrame, Inspector, Step};
+use crate::journal::{JournalEntry, SnapshotId};
+use crate::spec::SpecId;
+use crate::t8n::{self, Outcome, Transaction};
+use crate::trie::ordered_trie_root;
+use crate::tx::AccessListEntry;
+use crate::{delegation, hexutil, rlp, EvmContext, EvmData, Log, TxData, EXECUTION_STACK_SIZE};
+
+/// The 2048-bit bloom filter of receipts and blocks, over the addresses and
+/// topics of their logs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Bloom(pub [u8; 256]);
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom([0; 256])
+    }
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bloom({})", hexutil::encode_prefixed(self.0))
+    }
+}
+
+impl Bloom {
+    /// Sets the three bits picked by the low 11 bits of the first three byte
+    /// pairs of the keccak hash of `input`.
+    pub fn accrue(&mut self, input: &[u8]) {
+        for (byte, bit) in bit_positions(input) {
+            self.0[byte] |= bit;
+        }
+    }
+
+    pub fn accrue_log(&mut self, log: &Log) {
+        self.accrue(&address_bytes(log.address));
+        for topic in &log.topics {
+            let mut word = [0u8; 32];
+            topic.to_big_endian(&mut word);
+            self.accrue(&word);
+        }
+    }
+
+    pub fn accrue_bloom(&mut self, other: &Bloom) {
+        for (byte, other) in self.0.iter_mut().zip(other.0) {
+            *byte |= other;
+        }
+    }
+
+    /// Whether `input` may have been added. False positives are possible,
+    /// false negatives are not.
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        bit_positions(input)
+            .into_iter()
+            .all(|(byte, bit)| self.0[byte] & bit != 0)
+    }
+}
+
+fn bit_positions(input: &[u8]) -> [(usize, u8); 3] {
+    let hash = Keccak256::digest(input);
+    [0, 2, 4].map(|i| {
+        let index = (usize::from(hash[i]) << 8 | usize::from(hash[i + 1])) & 2047;
+        (255 - index / 8, 1 << (index % 8))
+    })
+}
+
+fn address_bytes(address: U256) -> [u8; 20] {
+    let mut word = [0u8; 32];
+    address.to_big_endian(&mut word);
+    word[12..].try_into().unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    /// The EIP-2718 type of the transaction, 0 for legacy ones.
+    pub tx_type: u8,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Gas used by this transaction and the ones before it in the block.
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+    pub bloom: Bloom,
+}
+
+impl Receipt {
+    /// Encoding of the receipt in the receipt trie (EIP-658), after the
+    /// transaction type for typed transactions (EIP-2718).
+    pub fn rlp(&self) -> Vec<u8> {
+        let logs: Vec<Vec<u8>> = self.logs.iter().map(log_rlp).collect();
+        let mut encoded = match self.tx_type {
+            0 => vec![],
+            tx_type => vec![tx_type],
+        };
+        encoded.extend(rlp::encode_list(&[
+            rlp::encode_u64(self.success as u64),
+            rlp::encode_u64(self.cumulative_gas_used),
+            rlp::encode_bytes(&self.bloom.0),
+            rlp::encode_list(&logs),
+        ]));
+        encoded
+    }
+}
+
+/// Encoding of a log in receipts: `[address, [topics], data]`.
+pub fn log_rlp(log: &Log) -> Vec<u8> {
+    let topics: Vec<Vec<u8>> = log
+        .topics
+        .iter()
+        .map(|topic| {
+            let mut word = [0u8; 32];
+            topic.to_big_endian(&mut word);
+            rlp::encode_bytes(&word)
+        })
+        .collect();
+    rlp::encode_list(&[
+        rlp::encode_bytes(&address_bytes(log.address)),
+        rlp::encode_list(&topics),
+        rlp::encode_bytes(&log.data),
+    ])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// The gas limit of a transaction is more than the block has left.
+    GasLimitExceeded {
+        index: usize,
+        gas: u64,
+        available: u64,
+    },
+    /// EIP-1559: the priority fee of a transaction is above its max fee.
+    TipAboveFeeCap {
+        index: usize,
+        max_priority_fee_per_gas: U256,
+        max_fee_per_gas: U256,
+    },
+    /// EIP-1559: the max fee of a transaction does not cover the base fee.
+    FeeCapTooLow {
+        index: usize,
+        max_fee_per_gas: U256,
+        base_fee: U256,
+    },
+    /// A transaction that cannot be applied, such as one with the wrong
+    /// nonce or a sender that cannot pay for it, as `t8n` words it.
+    Rejected { index: usize, reason: String },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockError::GasLimitExceeded {
+                index,
+                gas,
+                available,
+            } => write!(
+                f,
+                "transaction {} asks for {} gas, the block has {} left",
+                index, gas, available
+            ),
+            BlockError::TipAboveFeeCap {
+                index,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+            } => write!(
+                f,
+                "transaction {} has a priority fee of {}, above its max fee of {}",
+                index, max_priority_fee_per_gas, max_fee_per_gas
+            ),
+            BlockError::FeeCapTooLow {
+                index,
+                max_fee_per_gas,
+                base_fee,
+            } => write!(
+                f,
+                "transaction {} has a max fee of {}, below the base fee of {}",
+                index, max_fee_per_gas, base_fee
+            ),
+            BlockError::Rejected { index, reason } => {
+                write!(f, "transaction {} cannot be applied: {}", index, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockResult {
+    pub receipts: Vec<Receipt>,
+    pub gas_used: u64,
+    /// The union of the blooms of all receipts.
+    pub bloom: Bloom,
+}
+
+impl BlockResult {
+    pub fn receipts_root(&self) -> H256 {
+        ordered_trie_root(self.receipts.iter().map(Receipt::rlp))
+    }
+}
+
+/// Transactions executed one after the other in the same block context.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub context: EvmContext,
+    pub transactions: Vec<TxData>,
+}
+
+impl Block {
+    pub fn new(context: EvmContext, transactions: Vec<TxData>) -> Self {
+        Self {
+            context,
+            transactions,
+        }
+    }
+
+    /// Applies every transaction the way `t8n` does: the nonce of the
+    /// sender is checked and bumped, the gas is paid for up front at the
+    /// effective gas price and what is left refunded, the value moves, the
+    /// base fee is burnt and the priority fee goes to the coinbase. Returns
+    /// the post-block state with a receipt for each.
+    ///
+    /// The block is applied as a whole: when a transaction does not fit in
+    /// the gas the block has left, it is an error and no post state comes
+    /// out. Transactions without a gas limit get all that is left, those
+    /// without a nonce the next one of their sender. Without a block gas
+    /// limit in the context, the block is unbounded. So it is for an
+    /// EIP-1559 transaction whose max fee does not cover the base fee, and
+    /// for one the sender cannot pay for.
+    pub fn execute(&self, data: &EvmData) -> Result<(EvmData, BlockResult), BlockError> {
+        let mut post = data.clone();
+        post.context = Some(self.context.clone());
+        let mut result = BlockResult {
+            receipts: vec![],
+            gas_used: 0,
+            bloom: Bloom::default(),
+        };
+        for index in 0..self.transactions.len() {
+            let tx = self.prepare(index, &post, &result)?;
+            let outcome = self.apply(index, &mut post, &tx, &mut ())?;
+            result.push(&tx, outcome);
+        }
+        Ok((post, result))
+    }
+
+    /// `execute` with the transactions run on up to `jobs` threads, for the
+    /// same post state and receipts.
+    ///
+    /// Every transaction first runs on its own against the state before the
+    /// block, noting the slots and accounts it reads. They are then taken in
+    /// order, and one that read what a transaction before it changed runs
+    /// again on the state as it is by then. The fees paid to the coinbase
+    /// are added to its balance as it is by then, so they only count when
+    /// the coinbase is read. Transactions that create or destroy accounts,
+    /// call cheatcodes or have no gas limit of their own always run in
+    /// order, and so does the whole block when `data` has instructions of
+    /// its own, which could read anything.
+    pub fn execute_parallel(
+        &self,
+        data: &EvmData,
+        jobs: usize,
+    ) -> Result<(EvmData, BlockResult), BlockError> {
+        if jobs <= 1 || !data.instructions.is_empty() {
+            return self.execute(data);
+        }
+        let mut post = data.clone();
+        post.context = Some(self.context.clone());
+
+        let count = self.transactions.len();
+        let next = AtomicUsize::new(0);
+        let mut speculations: Vec<Option<Speculation>> = (0..count).map(|_| None).collect();
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.min(count))
+                .map(|_| {
+                    let (next, pre) = (&next, &post);
+                    let worker = thread::Builder::new().stack_size(EXECUTION_STACK_SIZE);
+                    worker
+                        .spawn_scoped(scope, move || {
+                            let mut data = pre.clone();
+                            let mut done = vec![];
+                            loop {
+                                let index = next.fetch_add(1, Ordering::Relaxed);
+                                let Some(tx) = self.transactions.get(index) else {
+                                    break;
+                                };
+                                if tx.gas.is_some() {
+                                    let tx = self.transaction(&data, tx);
+                                    done.push((index, self.speculate(&mut data, tx)));
+                                }
+                            }
+                            done
+                        })
+                        .unwrap()
+                })
+                .collect();
+            for worker in workers {
+                for (index, speculation) in worker.join().unwrap() {
+                    speculations[index] = speculation;
+                }
+            }
+        });
+
+        let mut result = BlockResult {
+            receipts: vec![],
+            gas_used: 0,
+            bloom: Bloom::default(),
+        };
+        // What the transactions taken so far changed
+        let mut changed = Accesses::default();
+        for (index, speculation) in speculations.into_iter().enumerate() {
+            let tx = self.prepare(index, &post, &result)?;
+            let speculation = speculation.filter(|speculation| speculation.holds(&tx, &changed));
+            let outcome = match speculation {
+                Some(speculation) => speculation.replay(&mut post, &mut changed),
+                None => {
+                    let snapshot = post.snapshot();
+                    let outcome = self.apply(index, &mut post, &tx, &mut ());
+                    let changes = Changes::since(&post, snapshot);
+                    post.release(snapshot);
+                    changed.slots.extend(changes.slots.keys());
+                    changed.accounts.extend(changes.accounts);
+                    outcome?
+                }
+            };
+            result.push(&tx, outcome);
+        }
+        Ok((post, result))
+    }
+
+    // The transaction `index` as it runs on `data` after the receipts of
+    // `result`, once it is known to fit in the block
+    fn prepare(
+        &self,
+        index: usize,
+        data: &EvmData,
+        result: &BlockResult,
+    ) -> Result<Transaction, BlockError> {
+        // No block can use more gas than 64 bits hold, whatever its limit
+        let gas_limit = self
+            .context
+            .gaslimit
+            .map(|gas| gas.min(U256::from(u64::MAX)).as_u64());
+        let base_fee = BlockEnv::from(&self.context).basefee;
+        let available = gas_limit.map(|limit| limit - result.gas_used);
+        let mut tx = self.transactions[index].clone();
+        match (tx.gas, available) {
+            (Some(gas), Some(available)) if gas > available => {
+                return Err(BlockError::GasLimitExceeded {
+                    index,
+                    gas,
+                    available,
+                });
+            }
+            (None, Some(available)) => tx.gas = Some(available),
+            _ => {}
+        }
+        if let Some((max_fee_per_gas, max_priority_fee_per_gas)) = fee_caps(&tx) {
+            if max_priority_fee_per_gas > max_fee_per_gas {
+                return Err(BlockError::TipAboveFeeCap {
+                    index,
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                });
+            }
+            if max_fee_per_gas < base_fee {
+                return Err(BlockError::FeeCapTooLow {
+                    index,
+                    max_fee_per_gas,
+                    base_fee,
+                });
+            }
+        }
+        Ok(self.transaction(data, &tx))
+    }
+
+    // `tx` as `t8n::apply` takes it, typed from its fields. Without a nonce
+    // it gets the next one of its sender in `data`
+    fn transaction(&self, data: &EvmData, tx: &TxData) -> Transaction {
+        let sender = tx.from.unwrap_or_default();
+        let gas_price = tx.gasprice.unwrap_or_default();
+        let (tx_type, gas_price, max_priority_fee_per_gas) = match (fee_caps(tx), &tx.access_list) {
+            (Some((max_fee, priority_fee)), _) => (2, max_fee, priority_fee),
+            (None, Some(_)) => (1, gas_price, U256::zero()),
+            (None, None) => (0, gas_price, U256::zero()),
+        };
+        Transaction {
+            tx_type,
+            chain_id: BlockEnv::from(&self.context).chainid.low_u64(),
+            sender,
+            nonce: tx
+                .nonce
+                .unwrap_or_else(|| data.basic(sender).unwrap_or_default().nonce),
+            gas_price,
+            max_priority_fee_per_gas,
+            gas: tx.gas.unwrap_or_default(),
+            to: tx.to,
+            value: tx.value.unwrap_or_default(),
+            input: tx.data.as_deref().cloned().unwrap_or_default(),
+            access_list: tx
+                .access_list
+                .iter()
+                .flatten()
+                .map(|item| AccessListEntry {
+                    address: item.address,
+                    storage_keys: item.storage_keys.clone(),
+                })
+                .collect(),
+            authorization_list: vec![],
+            signature: [U256::zero(); 3],
+            #[cfg(feature = "optimism")]
+            deposit: None,
+        }
+    }
+
+    // Applies the transaction `index` to `data` in this block
+    fn apply(
+        &self,
+        index: usize,
+        data: &mut EvmData,
+        tx: &Transaction,
+        inspector: &mut dyn Inspector,
+    ) -> Result<Outcome, BlockError> {
+        let spec = data.config.spec;
+        let coinbase = BlockEnv::from(&self.context).coinbase;
+        let base_fee = self
+            .context
+            .basefee
+            .filter(|_| spec.is_enabled_in(SpecId::London));
+        t8n::apply_with_inspector(data, tx, spec, coinbase, base_fee, None, inspector)
+            .map_err(|reason| BlockError::Rejected { index, reason })
+    }
+
+    // Applies `tx` to `data` and winds `data` back. `None` when it cannot
+    // be applied there, which leaves it to run in order
+    fn speculate(&self, data: &mut EvmData, tx: Transaction) -> Option<Speculation> {
+        let snapshot = data.snapshot();
+        // The sender pays for it
+        let mut reads = Reads::default();
+        reads.accesses.accounts.insert(tx.sender);
+        let outcome = self.apply(0, data, &tx, &mut reads);
+        let mut changes = Changes::since(data, snapshot);
+        data.revert(snapshot);
+        let outcome = outcome.ok()?;
+
+        // A delegation runs the code of its delegate (EIP-7702)
+        let delegates: Vec<U256> = reads
+            .accesses
+            .accounts
+            .iter()
+            .filter_map(|account| delegation::delegate(&data.code(*account)?))
+            .collect();
+        reads.accesses.accounts.extend(delegates);
+        // New code and wiped storage come with what makes a transaction
+        // opaque, and one that has them some other way runs again in order
+        // too
+        reads.opaque |= changes.opaque;
+        for (address, (before, _)) in &mut changes.balances {
+            *before = data.basic(*address).unwrap_or_default().balance;
+        }
+        Some(Speculation {
+            tx,
+            outcome,
+            reads,
+            changes,
+        })
+    }
+}
+
+impl BlockResult {
+    // Adds the receipt of `tx`, which came to `outcome`
+    fn push(&mut self, tx: &Transaction, outcome: Outcome) {
+        self.gas_used += outcome.gas_used;
+        let mut bloom = Bloom::default();
+        for log in &outcome.logs {
+            bloom.accrue_log(log);
+        }
+        self.bloom.accrue_bloom(&bloom);
+        self.receipts.push(Receipt {
+            tx_type: tx.tx_type,
+            success: outcome.success,
+            gas_used: outcome.gas_used,
+            cumulative_gas_used: self.gas_used,
+            logs: outcome.logs,
+            bloom,
+        });
+    }
+}
+
+// Slots, by account, and accounts, read or changed
+#[derive(Debug, Default)]
+struct Accesses {
+    slots: HashSet<(U256, U256)>,
+    accounts: HashSet<U256>,
+}
+
+// What a transaction reads as it runs
+#[derive(Debug, Default)]
+struct Reads {
+    accesses: Accesses,
+    /// It creates or destroys accounts, or calls cheatcodes, which read and
+    /// change more than is worth following.
+    opaque: bool,
+}
+
+impl Inspector for Reads {
+    fn step(&mut self, step: &Step) {
+        let top = step.stack.last().copied();
+        match step.opcode {
+            // SLOAD, SSTORE
+            0x54 | 0x55 => {
+                let address = step.data.env.tx.address;
+                self.accesses.slots.extend(top.map(|key| (address, key)));
+            }
+            // BALANCE, EXTCODESIZE, EXTCODECOPY, EXTCODEHASH
+            0x31 | 0x3b | 0x3c | 0x3f => self.accesses.accounts.extend(top),
+            // SELFBALANCE
+            0x47 => {
+                self.accesses.accounts.insert(step.data.env.tx.address);
+            }
+            // CREATE, CREATE2, SELFDESTRUCT
+            0xf0 | 0xf5 | 0xff => self.opaque = true,
+            _ => {}
+        }
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        self.accesses.accounts.insert(call.address);
+        self.accesses.accounts.insert(call.code_address);
+        if call.code_address == CHEATCODE_ADDRESS {
+            self.opaque = true;
+        }
+    }
+
+    fn create_start(&mut self, _create: &CreateFrame) {
+        self.opaque = true;
+    }
+}
+
+// What transactions changed since a snapshot, as they left it
+#[derive(Debug, Default)]
+struct Changes {
+    slots: BTreeMap<(U256, U256), Option<U256>>,
+    nonces: BTreeMap<U256, u64>,
+    /// Balances before, once known, and after.
+    balances: BTreeMap<U256, (U256, U256)>,
+    /// Every account with a balance, nonce or code changed.
+    accounts: HashSet<U256>,
+    /// Code or storage that was replaced as a whole.
+    opaque: bool,
+}
+
+impl Changes {
+    fn since(data: &EvmData, snapshot: SnapshotId) -> Self {
+        let start = data.snapshots.open[snapshot.0];
+        let word = |key: &str| U256::from_dec_str(key).unwrap();
+        let mut changes = Changes::default();
+        for entry in data.snapshots.history[start..].iter().chain(&data.journal) {
+            match entry {
+                JournalEntry::StateChanged { address, key, .. } => {
+                    let address = word(address);
+                    let stored = data.state.get(&address.to_string());
+                    let value = stored.and_then(|slots| slots.get(key)).copied();
+                    changes.slots.insert((address, *key), value);
+                }
+                JournalEntry::BalanceChanged { address, .. } => {
+                    let address = word(address);
+                    let balance = data.basic(address).unwrap_or_default().balance;
+                    changes.balances.insert(address, (U256::zero(), balance));
+                }
+                JournalEntry::NonceChanged { address, .. } => {
+                    let address = word(address);
+                    let nonce = data.basic(address).unwrap_or_default().nonce;
+                    changes.nonces.insert(address, nonce);
+                }
+                JournalEntry::CodeChanged { address, .. } => {
+                    changes.accounts.insert(word(address));
+                    changes.opaque = true;
+                }
+                JournalEntry::StorageCleared(_) => changes.opaque = true,
+                _ => {}
+            }
+        }
+        changes.accounts.extend(changes.balances.keys());
+        changes.accounts.extend(changes.nonces.keys());
+        changes
+    }
+}
+
+// A transaction run against the state before the block
+struct Speculation {
+    tx: Transaction,
+    outcome: Outcome,
+    reads: Reads,
+    changes: Changes,
+}
+
+impl Speculation {
+    // Whether `tx` comes to the same after the changes of the transactions
+    // before it
+    fn holds(&self, tx: &Transaction, changed: &Accesses) -> bool {
+        let reads = &self.reads.accesses;
+        !self.reads.opaque
+            && self.tx == *tx
+            && reads.slots.is_disjoint(&changed.slots)
+            && reads.accounts.is_disjoint(&changed.accounts)
+    }
+
+    // Makes the changes of the transaction on `post`, noting them in
+    // `changed`, and returns what it came to
+    fn replay(self, post: &mut EvmData, changed: &mut Accesses) -> Outcome {
+        for ((address, key), value) in self.changes.slots {
+            changed.slots.insert((address, key));
+            post.set_state(address, key, value);
+        }
+        for (address, nonce) in self.changes.nonces {
+            post.set_nonce(address, nonce);
+        }
+        changed.accounts.extend(self.changes.accounts);
+        for (address, (before, after)) in self.changes.balances {
+            // What an account it did not read got, the fees, adds to what
+            // it has by then
+            let balance = if self.reads.accesses.accounts.contains(&address) {
+                after
+            } else {
+                post.basic(address).unwrap_or_default().balance + after - before
+            };
+            post.set_balance(address, balance);
+        }
+        post.finish_transaction();
+        self.outcome
+    }
+}
@@ -0,0 +1,158 @@
+//! An `Evm` set up piece by piece from typed values, instead of filling in
+//! `EvmData` by hand: `Evm::builder().with_spec(..).with_block(..)
+//! .with_tx(..).with_db(..).build()`, then `transact()`.
+//!
+//! What is left out has the defaults of `EvmData`: the default fork, an
+//! empty block and no backing database.
+
+use alloc::sync::Arc;
+
+use primitive_types::U256;
+
+use crate::config::EvmConfig;
+use crate::env::{BlockEnv, TxEnv};
+use crate::hexutil::Bytes;
+use crate::host::Database;
+use crate::inspector::Inspector;
+use crate::instruction::Instruction;
+use crate::precompiles::Precompile;
+use crate::prelude::*;
+use crate::spec::{ChainConfig, SpecId};
+use crate::{evm_with_inspector, EvmContext, EvmData, EvmResult, TxData};
+
+#[derive(Debug, Clone, Default)]
+pub struct EvmBuilder {
+    data: EvmData,
+    tx: Option<TxEnv>,
+    gas_limit: Option<u64>,
+    input: Vec<u8>,
+    code: Option<Vec<u8>>,
+    read_only: bool,
+}
+
+impl EvmBuilder {
+    /// The fork, with its gas schedule.
+    pub fn with_spec(mut self, spec: SpecId) -> Self {
+        self.data.config = ChainConfig::new(spec);
+        self
+    }
+
+    pub fn with_chain_config(mut self, config: ChainConfig) -> Self {
+        self.data.config = config;
+        self
+    }
+
+    pub fn with_config(mut self, config: EvmConfig) -> Self {
+        self.data.evm_config = config;
+        self
+    }
+
+    pub fn with_block(mut self, block: BlockEnv) -> Self {
+        self.data.context = Some(EvmContext::from(&block));
+        self
+    }
+
+    pub fn with_tx(mut self, tx: TxEnv) -> Self {
+        self.tx = Some(tx);
+        self
+    }
+
+    /// Without one the transaction is unmetered.
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Calldata of the transaction.
+    pub fn with_input(mut self, input: Vec<u8>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Code to run, in place of the code of the account the transaction
+    /// calls.
+    pub fn with_code(mut self, code: Vec<u8>) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Accounts, storage and block hashes to read.
+    pub fn with_db(mut self, database: Arc<dyn Database>) -> Self {
+        self.data.database = Some(database);
+        self
+    }
+
+    /// Runs `handler` for `opcode` instead of the interpreter, see
+    /// `instruction`.
+    pub fn with_instruction(mut self, opcode: u8, handler: Instruction) -> Self {
+        self.data.instructions.insert(opcode, handler);
+        self
+    }
+
+    /// Adds a precompile at `address`, or replaces the one of the fork.
+    pub fn with_precompile(mut self, address: U256, precompile: Precompile) -> Self {
+        self.data.precompiles.insert(address, precompile);
+        self
+    }
+
+    /// Run without state changes, as STATICCALL does.
+    pub fn with_writable(mut self, writable: bool) -> Self {
+        self.read_only = !writable;
+        self
+    }
+
+    pub fn build(self) -> Evm {
+        let mut data = self.data;
+        let mut tx_data = self.tx.as_ref().map(TxData::from).unwrap_or_default();
+        tx_data.gas = self.gas_limit;
+        tx_data.data = Some(Bytes(self.input));
+        data.tx_data = Some(tx_data);
+        Evm {
+            data,
+            code: self.code,
+            writable: !self.read_only,
+        }
+    }
+}
+
+/// A configured execution. State changes of a transaction stay in the
+/// `EvmData` for the next one.
+#[derive(Debug, Clone)]
+pub struct Evm {
+    data: EvmData,
+    code: Option<Vec<u8>>,
+    writable: bool,
+}
+
+impl Evm {
+    pub fn builder() -> EvmBuilder {
+        EvmBuilder::default()
+    }
+
+    pub fn transact(&mut self) -> EvmResult {
+        self.transact_with_inspector(&mut ())
+    }
+
+    pub fn transact_with_inspector(&mut self, inspector: &mut dyn Inspector) -> EvmResult {
+        let code = match &self.code {
+            Some(code) => code.clone(),
+            None => {
+                let to = self.data.tx_data.as_ref().and_then(|tx| tx.to);
+                to.and_then(|to| self.data.code(to)).unwrap_or_default()
+            }
+        };
+        evm_with_inspector(code, &mut self.data, self.writable, inspector)
+    }
+
+    pub fn data(&self) -> &EvmData {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut EvmData {
+        &mut self.data
+    }
+
+    pub fn into_data(self) -> EvmData {
+        self.data
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::{BTreeSet, HashMap};
+
+use primitive_types::{H256, U256};
+
+use crate::hexutil::Bytes;
+use crate::host::Database;
+use crate::statediff::StateDiff;
+use crate::t8n::{self, Transaction};
+use crate::trie::keccak;
+use crate::tx::Transaction as SignedTransaction;
+use crate::{evm, EvmContext, EvmData, Log, TxData};
+
+// balanceOf(address)
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BalanceChange {
+    pub before: U256,
+    pub after: U256,
+}
+
+impl BalanceChange {
+    pub fn is_profit(&self) -> bool {
+        self.after >= self.before
+    }
+
+    /// Absolute size of the change, see `is_profit` for its sign.
+    pub fn amount(&self) -> U256 {
+        if self.is_profit() {
+            self.after - self.before
+        } else {
+            self.before - self.after
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfitReport {
+    pub searcher: U256,
+    /// ETH balance changes keyed by address, unchanged balances are left out.
+    pub eth: HashMap<U256, BalanceChange>,
+    /// Token balance changes keyed by token and then by holder.
+    pub tokens: HashMap<U256, HashMap<U256, BalanceChange>>,
+}
+
+impl ProfitReport {
+    pub fn searcher_eth(&self) -> BalanceChange {
+        self.eth.get(&self.searcher).copied().unwrap_or_default()
+    }
+
+    pub fn searcher_token(&self, token: U256) -> BalanceChange {
+        self.tokens
+            .get(&token)
+            .and_then(|holders| holders.get(&self.searcher))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Compares the state `pre` a bundle ran on with the state it left and
+/// reports who gained or lost ETH and, for every token in `tokens`, ERC-20
+/// balance.
+///
+/// The accounts looked at are the searcher, the ones the bundle changed,
+/// the senders and recipients of its transactions, and the ones its logs
+/// come from or name as the parties of an ERC-20 `Transfer`. A token that
+/// moves balances without logging them can leave a holder out.
+pub fn profit_and_loss(
+    pre: &EvmData,
+    bundle: &BundleResult,
+    searcher: U256,
+    tokens: &[U256],
+) -> ProfitReport {
+    let post = &bundle.state;
+    let mut addresses = touched(bundle);
+    addresses.insert(searcher);
+
+    let mut eth = HashMap::new();
+    for address in &addresses {
+        let change = BalanceChange {
+            before: eth_balance(pre, *address),
+            after: eth_balance(post, *address),
+        };
+        if change.before != change.after {
+            eth.insert(*address, change);
+        }
+    }
+
+    let mut token_changes = HashMap::new();
+    for token in tokens {
+        let mut holders = HashMap::new();
+        for address in &addresses {
+            let change = BalanceChange {
+                before: token_balance(pre, *token, *address).unwrap_or_default(),
+                after: token_balance(post, *token, *address).unwrap_or_default(),
+            };
+            if change.before != change.after {
+                holders.insert(*address, change);
+            }
+        }
+        token_changes.insert(*token, holders);
+    }
+
+    ProfitReport {
+        searcher,
+        eth,
+        tokens: token_changes,
+    }
+}
+
+// The accounts a bundle changed, ran or logged a token transfer of
+fn touched(bundle: &BundleResult) -> BTreeSet<U256> {
+    let transfer = U256::from_big_endian(keccak(b"Transfer(address,address,uint256)").as_bytes());
+    let mut addresses: BTreeSet<U256> = bundle.state_diff.accounts.keys().copied().collect();
+    for result in &bundle.results {
+        addresses.extend(result.from.into_iter().chain(result.to));
+        for log in &result.logs {
+            addresses.insert(log.address);
+            if log.topics.len() == 3 && log.topics[0] == transfer {
+                addresses.extend(&log.topics[1..]);
+            }
+        }
+    }
+    addresses
+}
+
+pub fn eth_balance(data: &EvmData, address: U256) -> U256 {
+    data.basic(address).unwrap_or_default().balance
+}
+
+/// Runs `balanceOf(holder)` against the token code in a read-only frame.
+/// Returns `None` when the token has no code or the call fails.
+pub fn token_balance(data: &EvmData, token: U256, holder: U256) -> Option<U256> {
+    let code = data.code(token)?;
+
+    let mut data = data.clone();
+    let mut input = [0u8; 36];
+    input[..4].copy_from_slice(&BALANCE_OF_SELECTOR);
+    holder.to_big_endian(&mut input[4..]);
+    data.tx_data = Some(TxData {
+        data: Some(Bytes::from(&input[..])),
+        to: Some(token),
+        ..Default::default()
+    });
+
+    let result = evm(code, &mut data, false);
+    match result.value {
+        Some(value) if result.success && value.len() >= 32 => {
+            Some(U256::from_big_endian(&value[0..32]))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleOptions {
+    /// Throw the whole bundle away when one of its transactions reverts or
+    /// cannot be included, as builders do with bundles that do not allow
+    /// reverts.
+    pub revert_on_failure: bool,
+}
+
+/// What one transaction of a bundle came to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleTxResult {
+    pub hash: H256,
+    /// `None` when it could not be recovered from the signature.
+    pub from: Option<U256>,
+    pub to: Option<U256>,
+    pub success: bool,
+    pub gas_used: u64,
+    /// What it paid per unit of gas.
+    pub gas_price: U256,
+    /// The balance of the coinbase around it, priority fees and direct
+    /// transfers alike.
+    pub coinbase: BalanceChange,
+    pub logs: Vec<Log>,
+    /// What it returned, or reverted with.
+    pub output: Vec<u8>,
+    /// Why it could not be included, in which case it changed nothing.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BundleResult {
+    /// One for each transaction that ran. With `revert_on_failure` the
+    /// transactions after the one that failed are not run.
+    pub results: Vec<BundleTxResult>,
+    /// Whether the bundle was thrown away, leaving the state as it was.
+    pub reverted: bool,
+    pub gas_used: u64,
+    /// The balance of the coinbase before and after the bundle.
+    pub coinbase: BalanceChange,
+    /// What the bundle changed, as a whole.
+    pub state_diff: StateDiff,
+    /// The state after the bundle, for `profit_and_loss` among others.
+    pub state: EvmData,
+}
+
+// Runs a signed transaction of the bundle on `data`
+fn simulate_tx(
+    data: &mut EvmData,
+    signed: &SignedTransaction,
+    coinbase: U256,
+    base_fee: Option<U256>,
+    available: Option<u64>,
+) -> BundleTxResult {
+    let before = eth_balance(data, coinbase);
+    let mut result = BundleTxResult {
+        hash: signed.hash(),
+        from: signed.sender(),
+        to: signed.to(),
+        success: false,
+        gas_used: 0,
+        gas_price: U256::zero(),
+        coinbase: BalanceChange {
+            before,
+            after: before,
+        },
+        logs: vec![],
+        output: vec![],
+        error: None,
+    };
+    let Some(sender) = result.from else {
+        result.error = Some("invalid signature".to_string());
+        return result;
+    };
+    let spec = data.config.spec;
+    let outcome = Transaction::from_signed(signed, sender)
+        .and_then(|tx| t8n::apply(data, &tx, spec, coinbase, base_fee, available));
+    match outcome {
+        Ok(outcome) => {
+            result.success = outcome.success;
+            result.gas_used = outcome.gas_used;
+            result.gas_price = outcome.gas_price;
+            result.coinbase.after = eth_balance(data, coinbase);
+            result.logs = outcome.logs;
+            result.output = outcome.output;
+        }
+        Err(reason) => result.error = Some(reason),
+    }
+    result
+}
+
+/// Runs `txs` one after the other on a copy of `data`, in the block
+/// `block`, and reports each of them along with what the bundle changed and
+/// paid the coinbase. `data` itself is left as it is.
+///
+/// The senders are recovered from the signatures, which needs the `k256`
+/// feature. Transactions that cannot be included, with a wrong nonce or too
+/// little balance, change nothing and the bundle goes on without them,
+/// unless `revert_on_failure` is set. Blob transactions cannot be included.
+pub fn simulate_bundle(
+    data: &EvmData,
+    txs: Vec<SignedTransaction>,
+    block: &EvmContext,
+    options: &BundleOptions,
+) -> BundleResult {
+    let mut state = data.clone();
+    state.context = Some(block.clone());
+    let coinbase = block.coinbase.unwrap_or_default();
+    let base_fee = block.basefee;
+    let gas_limit = block
+        .gaslimit
+        .map(|limit| limit.min(U256::from(u64::MAX)).as_u64());
+    let coinbase_before = eth_balance(&state, coinbase);
+
+    // Keeps the journal of every transaction, for the diff of the bundle
+    let snapshot = state.snapshot();
+    let start = state.snapshots.history.len();
+    let mut results = vec![];
+    let mut gas_used = 0;
+    let mut reverted = false;
+    for tx in &txs {
+        let available = gas_limit.map(|limit| limit.saturating_sub(gas_used));
+        let result = simulate_tx(&mut state, tx, coinbase, base_fee, available);
+        gas_used += result.gas_used;
+        let failed = !result.success;
+        results.push(result);
+        if failed && options.revert_on_failure {
+            reverted = true;
+            break;
+        }
+    }
+
+    if reverted {
+        return BundleResult {
+            results,
+            reverted,
+            gas_used,
+            coinbase: BalanceChange {
+                before: coinbase_before,
+                after: coinbase_before,
+            },
+            state_diff: StateDiff::default(),
+            state: data.clone(),
+        };
+    }
+
+    let mut changes = state.clone();
+    changes.journal = state.snapshots.history[start..].to_vec();
+    let state_diff = changes.state_diff();
+    state.release(snapshot);
+    BundleResult {
+        results,
+        reverted,
+        gas_used,
+        coinbase: BalanceChange {
+            before: coinbase_before,
+            after: eth_balance(&state, coinbase),
+        },
+        state_diff,
+        state,
+    }
+}
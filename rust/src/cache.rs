@@ -0,0 +1,263 @@
+//! A bounded cache of remote state lookups that can be written to disk and
+//! loaded back, so simulations against the same block skip the node and can
+//! run offline.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+use crate::hexutil;
+use crate::host::AccountInfo;
+
+const FORMAT_VERSION: u64 = 1;
+
+/// What was looked up. Everything but block hashes is as of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKey {
+    Account {
+        block: u64,
+        address: U256,
+    },
+    Code {
+        block: u64,
+        address: U256,
+    },
+    Storage {
+        block: u64,
+        address: U256,
+        key: U256,
+    },
+    BlockHash {
+        number: U256,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheValue {
+    Account(AccountInfo),
+    Code(Vec<u8>),
+    Storage(U256),
+    /// `None` for a block the node did not have.
+    BlockHash(Option<U256>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+    Io(String),
+    /// The file is not a cache this version can read.
+    Invalid(String),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(error) => write!(f, "cache file error: {}", error),
+            CacheError::Invalid(reason) => write!(f, "invalid cache file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/// Lookups kept up to `capacity` entries, evicting the least recently used
+/// one when full.
+#[derive(Debug, Clone)]
+pub struct StateCache {
+    capacity: usize,
+    // Each entry with the tick it was last used at, and the entries by tick
+    entries: HashMap<CacheKey, (CacheValue, u64)>,
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for StateCache {
+    fn default() -> Self {
+        Self::new(100_000)
+    }
+}
+
+impl StateCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn get(&mut self, key: &CacheKey) -> Option<CacheValue> {
+        let Some((value, used)) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.tick += 1;
+        self.recency.remove(used);
+        self.recency.insert(self.tick, *key);
+        *used = self.tick;
+        Some(value.clone())
+    }
+
+    pub fn insert(&mut self, key: CacheKey, value: CacheValue) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key, (value, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            let (_, oldest) = self.recency.pop_first().unwrap();
+            self.entries.remove(&oldest);
+        }
+    }
+
+    /// The entries as JSON, least recently used first so loading them back
+    /// keeps the eviction order.
+    pub fn to_json(&self) -> Value {
+        let entries: Vec<Value> = self
+            .recency
+            .values()
+            .map(|key| entry_json(key, &self.entries[key].0))
+            .collect();
+        json!({ "version": FORMAT_VERSION, "entries": entries })
+    }
+
+    /// Reads entries written by `to_json`. When there are more than
+    /// `capacity`, the most recently used ones are kept.
+    pub fn from_json(value: &Value, capacity: usize) -> Result<Self, CacheError> {
+        if value["version"].as_u64() != Some(FORMAT_VERSION) {
+            return Err(CacheError::Invalid(format!(
+                "expected version {}",
+                FORMAT_VERSION
+            )));
+        }
+        let entries = value["entries"]
+            .as_array()
+            .ok_or_else(|| CacheError::Invalid("no entries".to_string()))?;
+        let mut cache = Self::new(capacity);
+        for entry in entries {
+            let (key, value) = parse_entry(entry)
+                .ok_or_else(|| CacheError::Invalid(format!("bad entry {}", entry)))?;
+            cache.insert(key, value);
+        }
+        Ok(cache)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CacheError> {
+        std::fs::write(path, self.to_json().to_string())
+            .map_err(|error| CacheError::Io(error.to_string()))
+    }
+
+    pub fn load(path: impl AsRef<Path>, capacity: usize) -> Result<Self, CacheError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|error| CacheError::Io(error.to_string()))?;
+        let value: Value =
+            serde_json::from_str(&text).map_err(|error| CacheError::Invalid(error.to_string()))?;
+        Self::from_json(&value, capacity)
+    }
+}
+
+fn entry_json(key: &CacheKey, value: &CacheValue) -> Value {
+    let mut entry = match *key {
+        CacheKey::Account { block, address } => {
+            json!({ "kind": "account", "block": block, "address": format!("{:#x}", address) })
+        }
+        CacheKey::Code { block, address } => {
+            json!({ "kind": "code", "block": block, "address": format!("{:#x}", address) })
+        }
+        CacheKey::Storage {
+            block,
+            address,
+            key,
+        } => json!({
+            "kind": "storage",
+            "block": block,
+            "address": format!("{:#x}", address),
+            "key": format!("{:#x}", key),
+        }),
+        CacheKey::BlockHash { number } => {
+            json!({ "kind": "block_hash", "number": format!("{:#x}", number) })
+        }
+    };
+    match value {
+        CacheValue::Account(account) => {
+            entry["balance"] = json!(format!("{:#x}", account.balance));
+            entry["nonce"] = json!(account.nonce);
+        }
+        CacheValue::Code(code) => entry["code"] = json!(hexutil::encode_prefixed(code)),
+        CacheValue::Storage(value) => entry["value"] = json!(format!("{:#x}", value)),
+        CacheValue::BlockHash(hash) => {
+            entry["hash"] = json!(hash.map(|hash| format!("{:#x}", hash)))
+        }
+    }
+    entry
+}
+
+fn parse_entry(entry: &Value) -> Option<(CacheKey, CacheValue)> {
+    let word = |field: &str| hexutil::parse_u256(entry[field].as_str()?).ok();
+    let block = || entry["block"].as_u64();
+    match entry["kind"].as_str()? {
+        "account" => Some((
+            CacheKey::Account {
+                block: block()?,
+                address: word("address")?,
+            },
+            CacheValue::Account(AccountInfo {
+                balance: word("balance")?,
+                nonce: entry["nonce"].as_u64()?,
+            }),
+        )),
+        "code" => Some((
+            CacheKey::Code {
+                block: block()?,
+                address: word("address")?,
+            },
+            CacheValue::Code(hexutil::decode(entry["code"].as_str()?).ok()?),
+        )),
+        "storage" => Some((
+            CacheKey::Storage {
+                block: block()?,
+                address: word("address")?,
+                key: word("key")?,
+            },
+            CacheValue::Storage(word("value")?),
+        )),
+        "block_hash" => {
+            let hash = match &entry["hash"] {
+                Value::Null => None,
+                _ => Some(word("hash")?),
+            };
+            Some((
+                CacheKey::BlockHash {
+                    number: word("number")?,
+                },
+                CacheValue::BlockHash(hash),
+            ))
+        }
+        _ => None,
+    }
+}
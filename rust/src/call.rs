@@ -0,0 +1,91 @@
+//! The request and state overrides of JSON-RPC `eth_call`, for
+//! `EvmService::call`. Fields are hex strings, as in the JSON.
+
+use std::collections::HashMap;
+
+use primitive_types::U256;
+use serde::Deserialize;
+
+use crate::hexutil::{self, HexError};
+
+/// A call as `eth_call` takes it. Without `to` the data runs as init code,
+/// like a contract creation.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub gas: Option<String>,
+    pub gas_price: Option<String>,
+    /// EIP-1559 fees, which can not come with a gas price.
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub value: Option<String>,
+    /// Also accepted as `input`, which newer clients send.
+    #[serde(alias = "input")]
+    pub data: Option<String>,
+}
+
+/// Changes to one account that last for a single call. `state` replaces
+/// the storage of the account, `state_diff` only sets the slots it lists.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    pub balance: Option<String>,
+    pub nonce: Option<String>,
+    pub code: Option<String>,
+    pub state: Option<HashMap<String, String>>,
+    pub state_diff: Option<HashMap<String, String>>,
+}
+
+/// Overrides by account address.
+pub type StateOverrides = HashMap<String, AccountOverride>;
+
+/// An account override with its fields parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ParsedOverride {
+    pub balance: Option<U256>,
+    pub nonce: Option<u64>,
+    pub code: Option<Vec<u8>>,
+    pub storage: HashMap<U256, U256>,
+    /// The account has no slots but `storage`.
+    pub replaces_storage: bool,
+}
+
+impl AccountOverride {
+    pub(crate) fn parse(&self) -> Result<ParsedOverride, String> {
+        let field = |name: &str, error: HexError| format!("{}: {}", name, error);
+        if self.state.is_some() && self.state_diff.is_some() {
+            return Err("state and stateDiff can not both be set".to_string());
+        }
+        let mut storage = HashMap::new();
+        for (key, value) in self.state.iter().chain(&self.state_diff).flatten() {
+            let key = hexutil::parse_u256(key).map_err(|error| field("storage key", error))?;
+            let value =
+                hexutil::parse_u256(value).map_err(|error| field("storage value", error))?;
+            storage.insert(key, value);
+        }
+        Ok(ParsedOverride {
+            balance: self
+                .balance
+                .as_deref()
+                .map(hexutil::parse_u256)
+                .transpose()
+                .map_err(|error| field("balance", error))?,
+            nonce: self
+                .nonce
+                .as_deref()
+                .map(hexutil::parse_u64)
+                .transpose()
+                .map_err(|error| field("nonce", error))?,
+            code: self
+                .code
+                .as_deref()
+                .map(hexutil::decode)
+                .transpose()
+                .map_err(|error| field("code", error))?,
+            storage,
+            replaces_storage: self.state.is_some(),
+        })
+    }
+}
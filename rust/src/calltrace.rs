@@ -0,0 +1,165 @@
+//! The nested call tree of an execution, in the format of geth's
+//! `callTracer`, so tools that read `debug_traceTransaction` output can read
+//! these too.
+
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+use crate::fmtutil::hex_address;
+use crate::hexutil;
+use crate::inspector::{CallFrame, CallKind, CreateFrame, Inspector};
+use crate::EvmResult;
+
+/// One call or creation and the ones it made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallTrace {
+    /// `CALL`, `DELEGATECALL`, `STATICCALL` or `CREATE`.
+    pub kind: &'static str,
+    pub from: U256,
+    /// The account called or created. For DELEGATECALL, the account the code
+    /// comes from.
+    pub to: U256,
+    /// `None` for calls that cannot carry value.
+    pub value: Option<U256>,
+    pub gas: u64,
+    pub gas_used: u64,
+    pub input: Vec<u8>,
+    pub output: Vec<u8>,
+    pub error: Option<String>,
+    pub calls: Vec<CallTrace>,
+}
+
+impl CallTrace {
+    fn start(
+        kind: &'static str,
+        from: U256,
+        to: U256,
+        value: Option<U256>,
+        gas: Option<u64>,
+        input: &[u8],
+    ) -> Self {
+        Self {
+            kind,
+            from,
+            to,
+            value,
+            gas: gas.unwrap_or_default(),
+            gas_used: 0,
+            input: input.to_vec(),
+            output: vec![],
+            error: None,
+            calls: vec![],
+        }
+    }
+
+    fn end(&mut self, result: &EvmResult) {
+        self.gas_used = result.gas_used;
+        self.output = result.value.clone().unwrap_or_default();
+        if !result.success {
+            self.error = Some(match result.value {
+                Some(_) => "execution reverted".to_string(),
+                None => "execution halted".to_string(),
+            });
+        }
+    }
+
+    /// The trace as `callTracer` prints it: quantities and data in hex, and
+    /// empty `output`, `error` and `calls` left out.
+    pub fn to_json(&self) -> Value {
+        let mut trace = json!({
+            "type": self.kind,
+            "from": hex_address(self.from),
+            "to": hex_address(self.to),
+            "gas": format!("{:#x}", self.gas),
+            "gasUsed": format!("{:#x}", self.gas_used),
+            "input": hexutil::encode_prefixed(&self.input),
+        });
+        if let Some(value) = self.value {
+            trace["value"] = json!(format!("{:#x}", value));
+        }
+        if !self.output.is_empty() {
+            trace["output"] = json!(hexutil::encode_prefixed(&self.output));
+        }
+        if let Some(error) = &self.error {
+            trace["error"] = json!(error);
+        }
+        if !self.calls.is_empty() {
+            trace["calls"] = json!(self
+                .calls
+                .iter()
+                .map(CallTrace::to_json)
+                .collect::<Vec<_>>());
+        }
+        trace
+    }
+}
+
+/// Builds the `CallTrace` of the transaction from inspector events.
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    // Frames that have started and not ended yet, the outermost first
+    open: Vec<CallTrace>,
+    root: Option<CallTrace>,
+}
+
+impl CallTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The trace of the transaction, once it has run.
+    pub fn finish(self) -> Option<CallTrace> {
+        self.root
+    }
+
+    fn push(&mut self, trace: CallTrace) {
+        self.open.push(trace);
+    }
+
+    fn pop(&mut self, result: &EvmResult) {
+        let mut trace = self.open.pop().unwrap();
+        trace.end(result);
+        match self.open.last_mut() {
+            Some(parent) => parent.calls.push(trace),
+            None => self.root = Some(trace),
+        }
+    }
+}
+
+impl Inspector for CallTracer {
+    fn call_start(&mut self, call: &CallFrame) {
+        let (kind, to, value) = match call.kind {
+            CallKind::Call => ("CALL", call.address, Some(call.value)),
+            CallKind::CallCode => ("CALLCODE", call.code_address, Some(call.value)),
+            CallKind::DelegateCall => ("DELEGATECALL", call.code_address, None),
+            CallKind::StaticCall => ("STATICCALL", call.address, None),
+        };
+        self.push(CallTrace::start(
+            kind,
+            call.caller,
+            to,
+            value,
+            call.gas,
+            call.input,
+        ));
+    }
+
+    fn call_end(&mut self, _call: &CallFrame, result: &EvmResult) {
+        self.pop(result);
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        self.push(CallTrace::start(
+            "CREATE",
+            create.caller,
+            create.address,
+            Some(create.value),
+            create.gas,
+            create.init_code,
+        ));
+    }
+
+    fn create_end(&mut self, _create: &CreateFrame, result: &EvmResult) {
+        self.pop(result);
+    }
+}
@@ -0,0 +1,105 @@
+//! Foundry's cheatcodes, answered at `CHEATCODE_ADDRESS` when
+//! `EvmConfig::cheatcodes` is on, so bytecode compiled from forge tests runs
+//! as it does under forge: `warp`, `roll`, `deal`, `prank`, `startPrank`,
+//! `stopPrank`, `etch`, `store` and `load`.
+//!
+//! Cheatcodes cost no gas, and one that is unknown or has arguments it
+//! cannot read fails the call. What they change in accounts is journaled
+//! like any other change; the block fields set by `warp` and `roll` hold for
+//! the rest of the transaction. A prank sets the caller, and with a second
+//! argument the origin, of the calls made by the frame that asked for it.
+
+use primitive_types::U256;
+
+use crate::abi::{self, ParamType, Token};
+use crate::host::Database;
+use crate::prelude::*;
+use crate::EvmData;
+
+/// 0x7109709ecfa91a80626ff3989d68f67f5b1dd12d, the address of `vm` in
+/// forge-std.
+pub const CHEATCODE_ADDRESS: U256 =
+    U256([0x9d68_f67f_5b1d_d12d, 0xcfa9_1a80_626f_f398, 0x7109_709e, 0]);
+
+/// Who the calls of a pranking frame come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prank {
+    pub sender: U256,
+    pub origin: Option<U256>,
+    /// Of the frame that asked for the prank, whose calls it applies to.
+    pub depth: usize,
+    /// Only the next call, as `prank` has it, rather than every call until
+    /// `stopPrank`.
+    pub once: bool,
+}
+
+// The `index`th argument word
+fn word(args: &[u8], index: usize) -> Result<U256, String> {
+    abi::decode_word(args, index).ok_or_else(|| "missing argument".to_string())
+}
+
+/// Runs the cheatcode `input` calls, for a frame at `depth`, and returns its
+/// ABI-encoded output.
+pub(crate) fn call(data: &mut EvmData, depth: usize, input: &[u8]) -> Result<Vec<u8>, String> {
+    if input.len() < 4 {
+        return Err("no selector".to_string());
+    }
+    let (selector, args) = input.split_at(4);
+    let is = |signature: &str| selector == abi::selector(signature);
+    let prank = |sender: U256, origin: Option<U256>, once: bool| Prank {
+        sender,
+        origin,
+        depth,
+        once,
+    };
+
+    if is("warp(uint256)") {
+        let timestamp = word(args, 0)?;
+        data.env.block.timestamp = timestamp;
+        data.context.get_or_insert_with(Default::default).timestamp = Some(timestamp);
+    } else if is("roll(uint256)") {
+        let number = word(args, 0)?;
+        data.env.block.number = number;
+        data.context.get_or_insert_with(Default::default).number = Some(number);
+    } else if is("deal(address,uint256)") {
+        data.set_balance(word(args, 0)?, word(args, 1)?);
+    } else if is("prank(address)") {
+        data.substate.prank = Some(prank(word(args, 0)?, None, true));
+    } else if is("prank(address,address)") {
+        data.substate.prank = Some(prank(word(args, 0)?, Some(word(args, 1)?), true));
+    } else if is("startPrank(address)") {
+        data.substate.prank = Some(prank(word(args, 0)?, None, false));
+    } else if is("startPrank(address,address)") {
+        data.substate.prank = Some(prank(word(args, 0)?, Some(word(args, 1)?), false));
+    } else if is("stopPrank()") {
+        data.substate.prank = None;
+    } else if is("etch(address,bytes)") {
+        let decoded = abi::decode(&[ParamType::Address, ParamType::Bytes], args);
+        let Some([Token::Address(address), Token::Bytes(code)]) = decoded.as_deref() else {
+            return Err("invalid arguments".to_string());
+        };
+        data.set_code(*address, code);
+    } else if is("store(address,bytes32,bytes32)") {
+        let (address, key, value) = (word(args, 0)?, word(args, 1)?, word(args, 2)?);
+        data.set_state(address, key, Some(value));
+    } else if is("load(address,bytes32)") {
+        let value = data.storage(word(args, 0)?, word(args, 1)?);
+        return Ok(abi::encode(&[Token::Uint(value)]));
+    } else {
+        return Err(format!("unknown cheatcode 0x{}", hex::encode(selector)));
+    }
+    Ok(vec![])
+}
+
+/// The sender and origin a prank gives a call to `to` from a frame at
+/// `depth`, which uses up a one-off prank.
+pub(crate) fn pranked(data: &mut EvmData, depth: usize, to: U256) -> Option<Prank> {
+    let prank = data.substate.prank?;
+    if prank.depth != depth || to == CHEATCODE_ADDRESS {
+        return None;
+    }
+    if prank.once {
+        data.substate.prank = None;
+    }
+    Some(prank)
+}
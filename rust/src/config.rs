@@ -0,0 +1,67 @@
+use core::time::Duration;
+
+use serde::Deserialize;
+
+/// Curated sets of interpreter options, see `EvmConfig::preset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Preset {
+    /// Behaves like a client would: gas is enforced and anything the spec
+    /// does not define halts the frame.
+    #[default]
+    SpecExact,
+    /// For learning and experimenting: no gas, opcodes that are unknown or
+    /// not yet enabled are skipped, and tracers explain every step.
+    Teaching,
+}
+
+/// Interpreter behaviour that is not fixed by the fork. Journaling is not an
+/// option: both presets roll back failed frames in full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmConfig {
+    /// Enforce the gas limit of the transaction. Without it execution is
+    /// unmetered whatever the limit, gas used is still added up.
+    pub gas_metering: bool,
+    /// Halt on opcodes that are unknown or not enabled in the spec, instead
+    /// of skipping them.
+    pub strict_halts: bool,
+    /// Have tracers describe every step in plain words.
+    pub explain: bool,
+    /// Halt once a transaction has run this many instructions, across all
+    /// its frames. Unmetered code terminates only with a limit like this.
+    pub max_instructions: Option<u64>,
+    /// Halt once a transaction has run this long. Not enforced without std
+    /// or on wasm32-unknown-unknown, which have no clock.
+    pub max_duration: Option<Duration>,
+    /// Answer Foundry's cheatcodes at `cheatcodes::CHEATCODE_ADDRESS`, for
+    /// running the bytecode of forge tests.
+    pub cheatcodes: bool,
+}
+
+impl EvmConfig {
+    pub fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::SpecExact => Self {
+                gas_metering: true,
+                strict_halts: true,
+                explain: false,
+                max_instructions: None,
+                max_duration: None,
+                cheatcodes: false,
+            },
+            Preset::Teaching => Self {
+                gas_metering: false,
+                strict_halts: false,
+                explain: true,
+                max_instructions: None,
+                max_duration: None,
+                cheatcodes: false,
+            },
+        }
+    }
+}
+
+impl Default for EvmConfig {
+    fn default() -> Self {
+        Self::preset(Preset::default())
+    }
+}
@@ -0,0 +1,206 @@
+//! Hardhat's `console.log`, which contracts call at `CONSOLE_ADDRESS` to
+//! print while they run.
+//!
+//! Nothing answers at the address, so the calls change nothing; the
+//! interpreter decodes what they were given and hands the message to
+//! `Inspector::console_log`. Every overload of `console.sol` is understood:
+//! `log` with up to four of uint256, string, bool and address, `log(int256)`,
+//! and `logString`, `logUint`, `logInt`, `logBool`, `logAddress`, `logBytes`
+//! and `logBytes1` to `logBytes32`. Signatures spelled with `uint`, as older
+//! versions of the library hashed them, are understood as well.
+
+use primitive_types::U256;
+
+use crate::abi::{self, ParamType, Token};
+use crate::fmtutil::{format_signed, hex_address};
+use crate::inspector::{CallFrame, CreateFrame, Inspector, Step};
+use crate::prelude::*;
+use crate::{EvmResult, Log};
+
+/// 0x000000000000000000636f6e736f6c652e6c6f67, "console.log" in ASCII.
+pub const CONSOLE_ADDRESS: U256 = U256([0x736f_6c65_2e6c_6f67, 0x0063_6f6e, 0, 0]);
+
+// The types `log` takes, in any combination of up to four
+const LOG_TYPES: [(&str, ParamType); 4] = [
+    ("uint256", ParamType::Uint),
+    ("string", ParamType::String),
+    ("bool", ParamType::Bool),
+    ("address", ParamType::Address),
+];
+
+fn matches(selector: &[u8], signature: &str) -> bool {
+    selector == abi::selector(signature)
+        || (signature.contains("uint256")
+            && selector == abi::selector(&signature.replace("uint256", "uint")))
+}
+
+// The parameters of the overload `selector` calls, with the width of the
+// fixed bytes for `logBytesN`
+fn overload(selector: &[u8]) -> Option<(Vec<ParamType>, Option<usize>)> {
+    let single = [
+        ("log(int256)", ParamType::Int),
+        ("logInt(int256)", ParamType::Int),
+        ("logUint(uint256)", ParamType::Uint),
+        ("logString(string)", ParamType::String),
+        ("logBool(bool)", ParamType::Bool),
+        ("logAddress(address)", ParamType::Address),
+        ("logBytes(bytes)", ParamType::Bytes),
+    ];
+    for (signature, param) in single {
+        if matches(selector, signature) {
+            return Some((vec![param], None));
+        }
+    }
+    for size in 1..=32 {
+        if selector == abi::selector(&format!("logBytes{}(bytes{})", size, size)) {
+            return Some((vec![ParamType::Uint], Some(size)));
+        }
+    }
+
+    // Every combination of `LOG_TYPES`, shortest first
+    let mut combinations: Vec<Vec<usize>> = vec![vec![]];
+    let mut start = 0;
+    for _ in 0..=4 {
+        let end = combinations.len();
+        for index in start..end {
+            let types = combinations[index].clone();
+            let names: Vec<&str> = types.iter().map(|&ty| LOG_TYPES[ty].0).collect();
+            if matches(selector, &format!("log({})", names.join(","))) {
+                let params = types.iter().map(|&ty| LOG_TYPES[ty].1.clone()).collect();
+                return Some((params, None));
+            }
+            if types.len() < 4 {
+                for ty in 0..LOG_TYPES.len() {
+                    let mut longer = types.clone();
+                    longer.push(ty);
+                    combinations.push(longer);
+                }
+            }
+        }
+        start = end;
+    }
+    None
+}
+
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Uint(value) => value.to_string(),
+        Token::Int(value) => format_signed(*value),
+        Token::Address(address) => hex_address(*address),
+        Token::Bool(value) => value.to_string(),
+        Token::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::String(string) => string.clone(),
+        Token::Array(tokens) => {
+            let items: Vec<String> = tokens.iter().map(format_token).collect();
+            format!("[{}]", items.join(", "))
+        }
+    }
+}
+
+// The arguments joined by spaces, with the `%s`, `%d`, `%i` and `%o` of a
+// leading string replaced by the arguments after it, as Hardhat prints them
+fn format_args(tokens: &[Token]) -> String {
+    let mut rest = tokens.iter();
+    let mut message = String::new();
+    if let Some(Token::String(template)) = tokens.first() {
+        rest.next();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('%', Some('%')) => {
+                    chars.next();
+                    message.push('%');
+                }
+                ('%', Some('s' | 'd' | 'i' | 'o')) if rest.len() > 0 => {
+                    chars.next();
+                    message += &format_token(rest.next().unwrap());
+                }
+                _ => message.push(c),
+            }
+        }
+    }
+    for token in rest {
+        if !message.is_empty() {
+            message.push(' ');
+        }
+        message += &format_token(token);
+    }
+    message
+}
+
+/// The message a call to `CONSOLE_ADDRESS` with `input` prints, `None` for
+/// input that is not a `console.sol` call.
+pub fn format(input: &[u8]) -> Option<String> {
+    if input.len() < 4 {
+        return None;
+    }
+    let (selector, args) = input.split_at(4);
+    let (params, fixed_bytes) = overload(selector)?;
+    let tokens = abi::decode(&params, args)?;
+    if let (Some(size), Some(Token::Uint(word))) = (fixed_bytes, tokens.first()) {
+        let mut bytes = [0; 32];
+        word.to_big_endian(&mut bytes);
+        return Some(format!("0x{}", hex::encode(&bytes[..size])));
+    }
+    Some(format_args(&tokens))
+}
+
+/// Reports the message of `call` to `inspector` if the call is to
+/// `CONSOLE_ADDRESS`.
+pub(crate) fn inspect(inspector: &mut dyn Inspector, call: &CallFrame) {
+    if call.code_address == CONSOLE_ADDRESS {
+        if let Some(message) = format(call.input) {
+            inspector.console_log(&message);
+        }
+    }
+}
+
+/// Collects what contracts print, passing every hook on to `inner`.
+pub struct ConsoleLogger<'a> {
+    pub inner: &'a mut dyn Inspector,
+    pub messages: Vec<String>,
+}
+
+impl<'a> ConsoleLogger<'a> {
+    pub fn new(inner: &'a mut dyn Inspector) -> Self {
+        ConsoleLogger {
+            inner,
+            messages: vec![],
+        }
+    }
+}
+
+impl Inspector for ConsoleLogger<'_> {
+    fn step(&mut self, step: &Step) {
+        self.inner.step(step);
+    }
+
+    fn sstore(&mut self, key: U256, value: U256) {
+        self.inner.sstore(key, value);
+    }
+
+    fn log(&mut self, log: &Log) {
+        self.inner.log(log);
+    }
+
+    fn console_log(&mut self, message: &str) {
+        self.messages.push(message.to_string());
+        self.inner.console_log(message);
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        self.inner.call_start(call);
+    }
+
+    fn call_end(&mut self, call: &CallFrame, result: &EvmResult) {
+        self.inner.call_end(call, result);
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        self.inner.create_start(create);
+    }
+
+    fn create_end(&mut self, create: &CreateFrame, result: &EvmResult) {
+        self.inner.create_end(create, result);
+    }
+}
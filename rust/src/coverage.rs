@@ -0,0 +1,201 @@
+//! Which instructions a set of runs executed: how often each opcode ran,
+//! and the pcs of every contract that were reached, so the summary can
+//! point at interpreter paths and contract branches no run exercised.
+//!
+//! Contracts are told apart by the hash of their code, so the same code
+//! deployed twice is covered as one contract, and code run as init code is
+//! a contract of its own.
+
+use alloc::collections::{btree_map, BTreeMap, BTreeSet};
+use core::fmt::Write;
+
+use primitive_types::H256;
+
+use crate::inspector::{CallFrame, CreateFrame, Inspector, Step};
+use crate::opcodes;
+use crate::prelude::*;
+use crate::trie::keccak;
+use crate::EvmResult;
+
+/// The instructions of one contract that ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractCoverage {
+    /// Given with `Coverage::name`, for the summary.
+    pub name: Option<String>,
+    pub code: Vec<u8>,
+    /// The pcs of the instructions that ran.
+    pub executed: BTreeSet<usize>,
+}
+
+impl ContractCoverage {
+    /// Each instruction that never ran, with its pc.
+    pub fn unexecuted(&self) -> Vec<(usize, String)> {
+        opcodes::instructions(&self.code)
+            .into_iter()
+            .filter(|(pc, _)| !self.executed.contains(pc))
+            .collect()
+    }
+
+    /// How many of its instructions ran, and how many it has.
+    pub fn ratio(&self) -> (usize, usize) {
+        let total = opcodes::instructions(&self.code).len();
+        (self.executed.len(), total)
+    }
+}
+
+/// Collects coverage over any number of runs, with one `Coverage` inspecting
+/// them all.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    opcodes: BTreeMap<u8, u64>,
+    contracts: BTreeMap<H256, ContractCoverage>,
+    names: BTreeMap<H256, String>,
+    // The code of the running frame and its hash, until a frame starts or
+    // ends, so code is hashed once per frame rather than per instruction
+    current: Option<(usize, usize, H256)>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the contract whose code is `code` in the summary.
+    pub fn name(&mut self, code: &[u8], name: &str) {
+        let hash = keccak(code);
+        self.names.insert(hash, name.to_string());
+        if let Some(contract) = self.contracts.get_mut(&hash) {
+            contract.name = Some(name.to_string());
+        }
+    }
+
+    /// Times each opcode ran, for those that did.
+    pub fn opcodes(&self) -> &BTreeMap<u8, u64> {
+        &self.opcodes
+    }
+
+    /// Every defined opcode that never ran.
+    pub fn unexecuted_opcodes(&self) -> Vec<u8> {
+        (0..=u8::MAX)
+            .filter(|opcode| opcodes::name(*opcode).is_some())
+            .filter(|opcode| !self.opcodes.contains_key(opcode))
+            .collect()
+    }
+
+    /// Every contract that ran, in the order of their code hashes.
+    pub fn contracts(&self) -> impl Iterator<Item = &ContractCoverage> {
+        self.contracts.values()
+    }
+
+    /// The opcodes that never ran, then the contracts with instructions that
+    /// never ran, each instruction on a line of its own.
+    pub fn summary(&self) -> String {
+        let unexecuted = self.unexecuted_opcodes();
+        let defined = (0..=u8::MAX)
+            .filter(|opcode| opcodes::name(*opcode).is_some())
+            .count();
+        let mut summary = format!(
+            "Opcodes executed: {} of {}\n",
+            defined - unexecuted.len(),
+            defined
+        );
+        if !unexecuted.is_empty() {
+            let names: Vec<&str> = unexecuted
+                .iter()
+                .filter_map(|opcode| opcodes::name(*opcode))
+                .collect();
+            summary += &format!("Never executed: {}\n", names.join(" "));
+        }
+
+        for (hash, contract) in &self.contracts {
+            let unexecuted = contract.unexecuted();
+            if unexecuted.is_empty() {
+                continue;
+            }
+            let (executed, total) = contract.ratio();
+            let name = contract
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{:#x}", hash));
+            let _ = writeln!(
+                summary,
+                "\n{}: {} of {} instructions executed",
+                name, executed, total
+            );
+            for (pc, instruction) in unexecuted {
+                let _ = writeln!(summary, "  {:#06x}  {}", pc, instruction);
+            }
+        }
+        summary
+    }
+
+    /// Adds in what `other` collected, over runs inspected apart from these,
+    /// on other threads for one.
+    pub fn merge(&mut self, other: Coverage) {
+        for (opcode, count) in other.opcodes {
+            *self.opcodes.entry(opcode).or_insert(0) += count;
+        }
+        self.names.extend(other.names);
+        for (hash, contract) in other.contracts {
+            match self.contracts.entry(hash) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(contract);
+                }
+                btree_map::Entry::Occupied(mut entry) => {
+                    entry.get_mut().executed.extend(contract.executed);
+                }
+            }
+        }
+        for (hash, contract) in &mut self.contracts {
+            if contract.name.is_none() {
+                contract.name = self.names.get(hash).cloned();
+            }
+        }
+    }
+
+    fn frame_changed(&mut self) {
+        self.current = None;
+    }
+}
+
+impl Inspector for Coverage {
+    fn step(&mut self, step: &Step) {
+        *self.opcodes.entry(step.opcode).or_insert(0) += 1;
+
+        let code = (step.code.as_ptr() as usize, step.code.len());
+        let hash = match self.current {
+            Some((pointer, len, hash)) if (pointer, len) == code => hash,
+            _ => {
+                let hash = keccak(step.code);
+                self.current = Some((code.0, code.1, hash));
+                hash
+            }
+        };
+        let names = &self.names;
+        let contract = self
+            .contracts
+            .entry(hash)
+            .or_insert_with(|| ContractCoverage {
+                name: names.get(&hash).cloned(),
+                code: step.code.to_vec(),
+                executed: BTreeSet::new(),
+            });
+        contract.executed.insert(step.pc);
+    }
+
+    fn call_start(&mut self, _call: &CallFrame) {
+        self.frame_changed();
+    }
+
+    fn call_end(&mut self, _call: &CallFrame, _result: &EvmResult) {
+        self.frame_changed();
+    }
+
+    fn create_start(&mut self, _create: &CreateFrame) {
+        self.frame_changed();
+    }
+
+    fn create_end(&mut self, _create: &CreateFrame, _result: &EvmResult) {
+        self.frame_changed();
+    }
+}
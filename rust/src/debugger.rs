@@ -0,0 +1,249 @@
+//! Stepping through an execution one instruction at a time.
+//!
+//! The `Debugger` runs the transaction once up front and records the
+//! interpreter state before every instruction, then moves through the
+//! recording: stepping is instant, and a REPL or TUI can inspect any point
+//! without the execution being paused behind it.
+
+use std::sync::Arc;
+
+use primitive_types::U256;
+
+use crate::host::Database;
+use crate::inspector::{CallFrame, CreateFrame, Inspector, Step};
+use crate::{evm_with_inspector, EvmData, EvmResult};
+
+/// Where `resume` stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Pc(usize),
+    Opcode(u8),
+    /// An SLOAD or SSTORE of the slot.
+    StorageKey(U256),
+}
+
+/// The interpreter right before one instruction.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Code of the running frame.
+    pub code: Arc<[u8]>,
+    pub pc: usize,
+    pub opcode: u8,
+    pub depth: usize,
+    /// The account whose code is running.
+    pub address: U256,
+    /// Bottom of the stack first.
+    pub stack: Vec<U256>,
+    /// Memory up to its current size.
+    pub memory: Vec<u8>,
+    // Shared by the snapshots between two changes of state
+    state: Arc<EvmData>,
+}
+
+impl Snapshot {
+    /// Value of slot `key` as the instruction sees it.
+    pub fn storage(&self, key: U256) -> U256 {
+        self.state.storage(self.address, key)
+    }
+
+    fn hits(&self, breakpoint: &Breakpoint) -> bool {
+        match *breakpoint {
+            Breakpoint::Pc(pc) => self.pc == pc,
+            Breakpoint::Opcode(opcode) => self.opcode == opcode,
+            // SLOAD, SSTORE
+            Breakpoint::StorageKey(key) => {
+                matches!(self.opcode, 0x54 | 0x55) && self.stack.last() == Some(&key)
+            }
+        }
+    }
+}
+
+pub struct Debugger {
+    snapshots: Vec<Snapshot>,
+    // Index of the snapshot of the next instruction to run, past the end
+    // once the execution is over
+    position: usize,
+    breakpoints: Vec<Breakpoint>,
+    result: EvmResult,
+}
+
+impl Debugger {
+    /// Runs `code` on `data` and stops before its first instruction.
+    pub fn new(code: impl AsRef<[u8]>, data: &mut EvmData, writable: bool) -> Self {
+        let mut recorder = Recorder::default();
+        let result = evm_with_inspector(code, data, writable, &mut recorder);
+        Self {
+            snapshots: recorder.snapshots,
+            position: 0,
+            breakpoints: vec![],
+            result,
+        }
+    }
+
+    /// The instruction about to run, `None` once the execution is over.
+    pub fn current(&self) -> Option<&Snapshot> {
+        self.snapshots.get(self.position)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.snapshots.len()
+    }
+
+    /// Instructions run so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Every instruction of the execution, in the order they ran.
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// What the execution returned.
+    pub fn result(&self) -> &EvmResult {
+        &self.result
+    }
+
+    pub fn pc(&self) -> Option<usize> {
+        self.current().map(|snapshot| snapshot.pc)
+    }
+
+    pub fn stack(&self) -> &[U256] {
+        self.current()
+            .map(|snapshot| snapshot.stack.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        self.current()
+            .map(|snapshot| snapshot.memory.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Slot `key` of the running account, zero once the execution is over.
+    pub fn storage(&self, key: U256) -> U256 {
+        self.current()
+            .map(|snapshot| snapshot.storage(key))
+            .unwrap_or_default()
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) {
+        self.breakpoints.retain(|existing| existing != breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Runs the current instruction.
+    pub fn step(&mut self) -> Option<&Snapshot> {
+        if !self.is_finished() {
+            self.position += 1;
+        }
+        self.current()
+    }
+
+    /// Goes back to before the previous instruction.
+    pub fn step_back(&mut self) -> Option<&Snapshot> {
+        self.position = self.position.saturating_sub(1);
+        self.current()
+    }
+
+    /// Runs until the next instruction at `pc`, in any frame, or to the end.
+    pub fn run_until(&mut self, pc: usize) -> Option<&Snapshot> {
+        self.run_to(|snapshot| snapshot.pc == pc)
+    }
+
+    /// Runs until an instruction hits a breakpoint, or to the end.
+    pub fn resume(&mut self) -> Option<&Snapshot> {
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        self.run_to(|snapshot| {
+            breakpoints
+                .iter()
+                .any(|breakpoint| snapshot.hits(breakpoint))
+        });
+        self.breakpoints = breakpoints;
+        self.current()
+    }
+
+    // Runs at least one instruction, then up to the first one that `stop`
+    // accepts
+    fn run_to(&mut self, stop: impl Fn(&Snapshot) -> bool) -> Option<&Snapshot> {
+        self.step();
+        while let Some(snapshot) = self.current() {
+            if stop(snapshot) {
+                break;
+            }
+            self.position += 1;
+        }
+        self.current()
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    snapshots: Vec<Snapshot>,
+    // Account each open frame runs as, the innermost last
+    frames: Vec<U256>,
+    // The state last copied and the journal length it was copied at
+    state: Option<(Arc<EvmData>, usize)>,
+    // The code last copied, shared by the snapshots of the frames running it
+    code: Option<Arc<[u8]>>,
+}
+
+impl Inspector for Recorder {
+    fn step(&mut self, step: &Step) {
+        // Every change of state is journaled, so an unchanged journal means
+        // the last copy is still current
+        let journal = step.data.journal.len();
+        let state = match &self.state {
+            Some((state, length)) if *length == journal => state.clone(),
+            _ => {
+                let state = Arc::new(step.data.clone());
+                self.state = Some((state.clone(), journal));
+                state
+            }
+        };
+        let code = match &self.code {
+            Some(code) if **code == *step.code => code.clone(),
+            _ => {
+                let code: Arc<[u8]> = step.code.into();
+                self.code = Some(code.clone());
+                code
+            }
+        };
+        let memory = step.memory.data().to_vec();
+        self.snapshots.push(Snapshot {
+            code,
+            pc: step.pc,
+            opcode: step.opcode,
+            depth: step.depth,
+            address: self.frames.last().copied().unwrap_or_default(),
+            stack: step.stack.to_vec(),
+            memory,
+            state,
+        });
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        self.frames.push(call.address);
+    }
+
+    fn call_end(&mut self, _call: &CallFrame, _result: &EvmResult) {
+        self.frames.pop();
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        self.frames.push(create.address);
+    }
+
+    fn create_end(&mut self, _create: &CreateFrame, _result: &EvmResult) {
+        self.frames.pop();
+    }
+}
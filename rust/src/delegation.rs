@@ -0,0 +1,83 @@
+//! EIP-7702 delegations. An account signs an authorization naming a
+//! contract, a set code transaction carries it, and the account gets a
+//! delegation designator for code: `0xef0100` followed by the address of
+//! the contract. Calls to the account then run the code of the contract,
+//! as the account.
+//!
+//! Only the code that runs follows the designator: EXTCODESIZE,
+//! EXTCODECOPY and EXTCODEHASH see the 23 bytes of the designator itself.
+
+use primitive_types::U256;
+
+use crate::env::BlockEnv;
+use crate::gas::{PER_AUTH_BASE_COST, PER_EMPTY_ACCOUNT_COST};
+use crate::host::Database;
+use crate::prelude::*;
+use crate::tx::Authorization;
+use crate::EvmData;
+
+pub const DESIGNATOR_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// The code of an account that delegates to `address`.
+pub fn designator(address: U256) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    address.to_big_endian(&mut word);
+    [&DESIGNATOR_PREFIX[..], &word[12..]].concat()
+}
+
+/// The account `code` delegates to, if it is a delegation designator.
+pub fn delegate(code: &[u8]) -> Option<U256> {
+    match code.strip_prefix(&DESIGNATOR_PREFIX[..]) {
+        Some(address) if address.len() == 20 => Some(U256::from_big_endian(address)),
+        _ => None,
+    }
+}
+
+/// Applies the authorizations of a set code transaction, after the nonce
+/// of the sender went up and before the transaction runs. Authorizations
+/// for another chain, with a nonce other than their authority's, or from an
+/// account with code of its own are skipped. An authorization for the zero
+/// address clears the delegation.
+///
+/// Returns the gas refunded for authorities that already existed, which
+/// paid for creating an account in the intrinsic gas.
+pub fn authorize(data: &mut EvmData, authorizations: &[Authorization]) -> u64 {
+    let chain_id = data
+        .context
+        .as_ref()
+        .map(BlockEnv::from)
+        .unwrap_or_default()
+        .chainid;
+    let mut refund = 0;
+    for authorization in authorizations {
+        if !authorization.chain_id.is_zero() && authorization.chain_id != chain_id {
+            continue;
+        }
+        if authorization.nonce == u64::MAX {
+            continue;
+        }
+        let Some(authority) = authorization.authority() else {
+            continue;
+        };
+        data.access_address(authority);
+        let code = data.code(authority).unwrap_or_default();
+        if !code.is_empty() && delegate(&code).is_none() {
+            continue;
+        }
+        let account = data.basic(authority);
+        if account.unwrap_or_default().nonce != authorization.nonce {
+            continue;
+        }
+        if account.is_some() || !code.is_empty() {
+            refund += PER_EMPTY_ACCOUNT_COST - PER_AUTH_BASE_COST;
+        }
+        let code = if authorization.address.is_zero() {
+            vec![]
+        } else {
+            designator(authorization.address)
+        };
+        data.set_code(authority, &code);
+        data.set_nonce(authority, authorization.nonce + 1);
+    }
+    refund
+}
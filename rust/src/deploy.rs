@@ -0,0 +1,172 @@
+use core::fmt;
+
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::funcs::create_address;
+use crate::hexutil::Bytes;
+use crate::host::Database;
+use crate::prelude::*;
+use crate::spec::SpecId;
+use crate::{evm, hexutil, EvmData, EvmResult, TxData};
+
+// EIP-170
+pub const MAX_CODE_SIZE: usize = 0x6000;
+// EIP-3860
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+pub const CODE_DEPOSIT_COST: u64 = 200;
+// Sizes at or above this percentage of a limit are reported as near it.
+pub const LIMIT_WARNING_PERCENT: usize = 90;
+
+/// Sizes and costs of a contract creation, reported for every CREATE that
+/// deployed code so pipelines can spot contracts approaching the size limits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReport {
+    #[serde(with = "hexutil::address")]
+    pub address: U256,
+    pub init_code_size: usize,
+    pub deployed_code_size: usize,
+    pub code_deposit_cost: u64,
+}
+
+impl CreateReport {
+    pub fn new(address: U256, init_code_size: usize, deployed_code_size: usize) -> Self {
+        Self {
+            address,
+            init_code_size,
+            deployed_code_size,
+            code_deposit_cost: CODE_DEPOSIT_COST * deployed_code_size as u64,
+        }
+    }
+
+    pub fn near_code_size_limit(&self) -> bool {
+        self.deployed_code_size * 100 >= MAX_CODE_SIZE * LIMIT_WARNING_PERCENT
+    }
+
+    pub fn near_init_code_size_limit(&self) -> bool {
+        self.init_code_size * 100 >= MAX_INITCODE_SIZE * LIMIT_WARNING_PERCENT
+    }
+
+    pub fn exceeds_code_size_limit(&self) -> bool {
+        self.deployed_code_size > MAX_CODE_SIZE
+    }
+
+    pub fn exceeds_init_code_size_limit(&self) -> bool {
+        self.init_code_size > MAX_INITCODE_SIZE
+    }
+}
+
+/// Why `deploy` did not create the contract.
+#[derive(Debug, Clone)]
+pub enum DeployError {
+    /// Over the EIP-3860 limit, from Shanghai.
+    InitCodeTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// The constructor returned more code than EIP-170 allows, from Spurious
+    /// Dragon.
+    CodeTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    InsufficientBalance {
+        balance: U256,
+        value: U256,
+    },
+    /// The constructor reverted or halted.
+    Failed(Box<EvmResult>),
+}
+
+impl fmt::Display for DeployError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeployError::InitCodeTooLarge { size, limit } => {
+                write!(f, "init code is {} bytes, the limit is {}", size, limit)
+            }
+            DeployError::CodeTooLarge { size, limit } => {
+                write!(f, "deployed code is {} bytes, the limit is {}", size, limit)
+            }
+            DeployError::InsufficientBalance { balance, value } => {
+                write!(f, "balance {} is below the value {}", balance, value)
+            }
+            DeployError::Failed(result) => match result.halt_reason {
+                Some(reason) => write!(f, "constructor failed: {:?}", reason),
+                None => write!(f, "constructor failed"),
+            },
+        }
+    }
+}
+
+impl core::error::Error for DeployError {}
+
+/// Deploys a contract the way a creation transaction from `deployer` would:
+/// runs `init_code` with `value`, stores the code it returns under the
+/// address derived from the deployer's nonce and returns that address. The
+/// constructor runs without a gas limit.
+///
+/// On error `data` is left as it was, deployer nonce included.
+pub fn deploy(
+    init_code: &[u8],
+    deployer: U256,
+    value: U256,
+    data: &mut EvmData,
+) -> Result<U256, DeployError> {
+    let spec = data.config.spec;
+    if spec.is_enabled_in(SpecId::Shanghai) && init_code.len() > MAX_INITCODE_SIZE {
+        return Err(DeployError::InitCodeTooLarge {
+            size: init_code.len(),
+            limit: MAX_INITCODE_SIZE,
+        });
+    }
+    let account = data.basic(deployer).unwrap_or_default();
+    if account.balance < value {
+        return Err(DeployError::InsufficientBalance {
+            balance: account.balance,
+            value,
+        });
+    }
+
+    let pre = data.clone();
+    let address = create_address(deployer, account.nonce);
+    data.set_nonce(deployer, account.nonce + 1);
+    data.set_balance(deployer, account.balance - value);
+    let received = data.basic(address).unwrap_or_default().balance;
+    data.set_balance(address, received + value);
+    data.set_nonce(address, 1);
+    data.mark_created(address);
+
+    let tx_data = TxData {
+        from: Some(deployer),
+        origin: Some(deployer),
+        to: Some(address),
+        value: Some(value),
+        ..Default::default()
+    };
+    let previous = data.tx_data.replace(tx_data);
+    let result = evm(init_code, data, true);
+    data.tx_data = previous;
+
+    let code = result.value.clone().unwrap_or_default();
+    let error = if !result.success {
+        Some(DeployError::Failed(Box::new(result)))
+    } else if spec.is_enabled_in(SpecId::SpuriousDragon) && code.len() > MAX_CODE_SIZE {
+        Some(DeployError::CodeTooLarge {
+            size: code.len(),
+            limit: MAX_CODE_SIZE,
+        })
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        *data = pre;
+        return Err(error);
+    }
+
+    // A constructor that self-destructed leaves no account to hold the code
+    if !code.is_empty() && data.basic(address).is_some() {
+        data.codes.insert(address.to_string(), Bytes::from(code));
+    }
+    Ok(address)
+}
@@ -0,0 +1,155 @@
+//! The state held in the maps of `EvmData` as JSON, to write a simulation's
+//! result to disk, diff it against another run, and load it back as the
+//! pre-state of the next one.
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "accounts": {
+//!     "0x000000000000000000000000000000000000c0de": {
+//!       "balance": "0x0", "nonce": "0x1", "code": "0x6000",
+//!       "storage": { "0x00…01": "0x00…2a" }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Keys come out sorted and words at full width, so the same state always
+//! dumps to the same text. What the `database` holds behind the maps is not
+//! dumped.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use primitive_types::U256;
+use serde_json::{json, Map, Value};
+
+use crate::fmtutil::{hex_address, hex_word};
+use crate::hexutil::{self, Bytes, Hex};
+use crate::journal::{Snapshots, Substate};
+use crate::EvmData;
+
+const FORMAT_VERSION: u64 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DumpError {
+    Io(String),
+    /// The JSON is not a dump this version can read.
+    Invalid(String),
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpError::Io(error) => write!(f, "state dump file error: {}", error),
+            DumpError::Invalid(reason) => write!(f, "invalid state dump: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DumpError {}
+
+impl EvmData {
+    /// Every account with a balance, nonce or code, and every slot written.
+    pub fn dump(&self) -> Value {
+        let word = |key: &String| U256::from_dec_str(key).unwrap();
+        let mut accounts: BTreeMap<U256, Map<String, Value>> = BTreeMap::new();
+        for (address, balance) in &self.balances {
+            let account = accounts.entry(word(address)).or_default();
+            account.insert("balance".to_string(), json!(format!("{:#x}", balance)));
+        }
+        for (address, nonce) in &self.nonces {
+            let account = accounts.entry(word(address)).or_default();
+            account.insert("nonce".to_string(), json!(format!("{:#x}", nonce)));
+        }
+        for (address, code) in &self.codes {
+            let account = accounts.entry(word(address)).or_default();
+            account.insert("code".to_string(), json!(code.to_hex()));
+        }
+        for (address, slots) in &self.state {
+            let account = accounts.entry(word(address)).or_default();
+            // Full-width keys sort like the slots
+            let storage: Map<String, Value> = slots
+                .iter()
+                .map(|(key, value)| (hex_word(*key), json!(hex_word(*value))))
+                .collect();
+            account.insert("storage".to_string(), Value::Object(storage));
+        }
+        let accounts: Map<String, Value> = accounts
+            .into_iter()
+            .map(|(address, account)| (hex_address(address), Value::Object(account)))
+            .collect();
+        json!({ "version": FORMAT_VERSION, "accounts": accounts })
+    }
+
+    /// Replaces the state with a `dump`. The journal, snapshots and
+    /// substate, which refer to the state replaced, are dropped.
+    pub fn load(&mut self, dump: &Value) -> Result<(), DumpError> {
+        let invalid = |what: &str, text: &Value| DumpError::Invalid(format!("{} {}", what, text));
+        if dump["version"].as_u64() != Some(FORMAT_VERSION) {
+            return Err(DumpError::Invalid(format!(
+                "expected version {}",
+                FORMAT_VERSION
+            )));
+        }
+        let hex = |what: &str, text: &Value| {
+            text.as_str()
+                .and_then(|text| hexutil::parse_u256(text).ok())
+                .ok_or_else(|| invalid(what, text))
+        };
+
+        let mut balances = HashMap::new();
+        let mut nonces = HashMap::new();
+        let mut codes = HashMap::new();
+        let mut state = HashMap::new();
+        for (address, account) in dump["accounts"].as_object().into_iter().flatten() {
+            let key = hex("address", &json!(address))?.to_string();
+            if !account["balance"].is_null() {
+                balances.insert(key.clone(), hex("balance", &account["balance"])?);
+            }
+            if !account["nonce"].is_null() {
+                let nonce = hex("nonce", &account["nonce"])?;
+                let nonce =
+                    u64::try_from(nonce).map_err(|_| invalid("nonce", &account["nonce"]))?;
+                nonces.insert(key.clone(), nonce);
+            }
+            if !account["code"].is_null() {
+                let code = account["code"]
+                    .as_str()
+                    .and_then(|code| Bytes::from_hex(code).ok())
+                    .ok_or_else(|| invalid("code", &account["code"]))?;
+                codes.insert(key.clone(), code);
+            }
+            let mut slots = HashMap::new();
+            for (slot, value) in account["storage"].as_object().into_iter().flatten() {
+                slots.insert(hex("slot", &json!(slot))?, hex("slot value", value)?);
+            }
+            if !slots.is_empty() {
+                state.insert(key, slots);
+            }
+        }
+
+        self.balances = balances;
+        self.nonces = nonces;
+        self.codes = codes;
+        self.state = state;
+        self.journal.clear();
+        self.snapshots = Snapshots::default();
+        self.substate = Substate::default();
+        Ok(())
+    }
+
+    pub fn save_dump(&self, path: impl AsRef<Path>) -> Result<(), DumpError> {
+        let text = serde_json::to_string_pretty(&self.dump()).unwrap();
+        std::fs::write(path, text).map_err(|error| DumpError::Io(error.to_string()))
+    }
+
+    pub fn load_dump(&mut self, path: impl AsRef<Path>) -> Result<(), DumpError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|error| DumpError::Io(error.to_string()))?;
+        let value: Value =
+            serde_json::from_str(&text).map_err(|error| DumpError::Invalid(error.to_string()))?;
+        self.load(&value)
+    }
+}
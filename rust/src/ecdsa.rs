@@ -0,0 +1,87 @@
+//! secp256k1 signatures over 32-byte hashes, as Ethereum uses them: the
+//! signer is known by address and recovered from the signature with its
+//! recovery id (the parity of the y coordinate of R).
+//!
+//! Built with the `k256` feature. Without it nothing is signed and no
+//! signature recovers.
+
+use primitive_types::U256;
+
+/// Half the order of the curve. Transaction signatures with a larger `s`
+/// are rejected from Homestead on (EIP-2), for every `s` has a twin.
+pub const HALF_ORDER: &str = "7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0";
+
+/// False for `s` in the upper half, which EIP-2 rules out for transactions.
+pub fn is_low_s(s: U256) -> bool {
+    s <= U256::from_str_radix(HALF_ORDER, 16).unwrap()
+}
+
+/// The address that signed `hash`, `None` when the signature does not
+/// recover to any key.
+#[cfg(feature = "k256")]
+pub fn recover(hash: &[u8; 32], recovery_id: u8, r: U256, s: U256) -> Option<U256> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let mut r_bytes = [0u8; 32];
+    let mut s_bytes = [0u8; 32];
+    r.to_big_endian(&mut r_bytes);
+    s.to_big_endian(&mut s_bytes);
+    let mut signature = Signature::from_scalars(r_bytes, s_bytes).ok()?;
+    let mut recovery_id = RecoveryId::from_byte(recovery_id).filter(|id| id.to_byte() < 2)?;
+    // The key only verifies low `s`, the twin of a high one recovers the
+    // same key with the other parity
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), false);
+    }
+    let key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id).ok()?;
+    Some(key_address(&key))
+}
+
+#[cfg(not(feature = "k256"))]
+pub fn recover(_hash: &[u8; 32], _recovery_id: u8, _r: U256, _s: U256) -> Option<U256> {
+    None
+}
+
+/// Signs `hash` with `secret_key` and returns the recovery id, `r` and a
+/// low `s`. `None` for a key outside the curve order.
+#[cfg(feature = "k256")]
+pub fn sign(hash: &[u8; 32], secret_key: &[u8; 32]) -> Option<(u8, U256, U256)> {
+    use k256::ecdsa::SigningKey;
+
+    let key = SigningKey::from_bytes(secret_key.into()).ok()?;
+    let (signature, recovery_id) = key.sign_prehash_recoverable(hash).ok()?;
+    let (r, s) = signature.split_bytes();
+    Some((
+        recovery_id.to_byte(),
+        U256::from_big_endian(&r),
+        U256::from_big_endian(&s),
+    ))
+}
+
+#[cfg(not(feature = "k256"))]
+pub fn sign(_hash: &[u8; 32], _secret_key: &[u8; 32]) -> Option<(u8, U256, U256)> {
+    None
+}
+
+/// The address of the account `secret_key` controls.
+#[cfg(feature = "k256")]
+pub fn address_of(secret_key: &[u8; 32]) -> Option<U256> {
+    use k256::ecdsa::SigningKey;
+
+    let key = SigningKey::from_bytes(secret_key.into()).ok()?;
+    Some(key_address(key.verifying_key()))
+}
+
+#[cfg(not(feature = "k256"))]
+pub fn address_of(_secret_key: &[u8; 32]) -> Option<U256> {
+    None
+}
+
+// The low 20 bytes of the keccak of the uncompressed key, without its tag
+#[cfg(feature = "k256")]
+fn key_address(key: &k256::ecdsa::VerifyingKey) -> U256 {
+    let point = key.to_encoded_point(false);
+    let hash = crate::trie::keccak(&point.as_bytes()[1..]);
+    U256::from_big_endian(&hash[12..])
+}
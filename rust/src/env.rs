@@ -0,0 +1,131 @@
+//! The block and transaction a run sees, from `EvmContext` and `TxData`,
+//! with every field a word so the environment opcodes only read one.
+//!
+//! Addresses are words, as everywhere else in the interpreter. A field left
+//! out reads as zero.
+
+use primitive_types::U256;
+
+use crate::gas::effective_gas_price;
+use crate::prelude::*;
+use crate::{EvmContext, TxData};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockEnv {
+    pub coinbase: U256,
+    pub basefee: U256,
+    pub timestamp: U256,
+    pub number: U256,
+    pub difficulty: U256,
+    pub gaslimit: U256,
+    pub chainid: U256,
+    pub excess_blob_gas: U256,
+}
+
+/// What the running frame sees of the transaction. CREATE swaps in its own
+/// caller, address and value for the init code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxEnv {
+    pub caller: U256,
+    /// The account whose code is running.
+    pub address: U256,
+    pub origin: U256,
+    pub value: U256,
+    pub gasprice: U256,
+    pub blob_hashes: Vec<U256>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Env {
+    pub block: BlockEnv,
+    pub tx: TxEnv,
+}
+
+impl From<&EvmContext> for BlockEnv {
+    fn from(context: &EvmContext) -> Self {
+        Self {
+            coinbase: context.coinbase.unwrap_or_default(),
+            basefee: context.basefee.unwrap_or_default(),
+            timestamp: context.timestamp.unwrap_or_default(),
+            number: context.number.unwrap_or_default(),
+            difficulty: context.difficulty.unwrap_or_default(),
+            gaslimit: context.gaslimit.unwrap_or_default(),
+            chainid: context.chainid.unwrap_or_default(),
+            excess_blob_gas: context.excess_blob_gas.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&TxData> for TxEnv {
+    fn from(tx: &TxData) -> Self {
+        let address = tx.to.unwrap_or_default();
+        Self {
+            // Without a sender the account calls itself
+            caller: tx.from.unwrap_or(address),
+            address,
+            origin: tx.origin.unwrap_or_default(),
+            value: tx.value.unwrap_or_default(),
+            gasprice: tx.gasprice.unwrap_or_default(),
+            blob_hashes: tx.blob_versioned_hashes.clone(),
+        }
+    }
+}
+
+impl From<&BlockEnv> for EvmContext {
+    fn from(block: &BlockEnv) -> Self {
+        Self {
+            coinbase: Some(block.coinbase),
+            basefee: Some(block.basefee),
+            timestamp: Some(block.timestamp),
+            number: Some(block.number),
+            difficulty: Some(block.difficulty),
+            gaslimit: Some(block.gaslimit),
+            chainid: Some(block.chainid),
+            excess_blob_gas: Some(block.excess_blob_gas),
+            #[cfg(feature = "optimism")]
+            l1_base_fee: None,
+            #[cfg(feature = "optimism")]
+            l1_blob_base_fee: None,
+            #[cfg(feature = "optimism")]
+            base_fee_scalar: None,
+            #[cfg(feature = "optimism")]
+            blob_base_fee_scalar: None,
+        }
+    }
+}
+
+/// A transaction with a gas price, without gas limit and input.
+impl From<&TxEnv> for TxData {
+    fn from(tx: &TxEnv) -> Self {
+        Self {
+            from: Some(tx.caller),
+            to: Some(tx.address),
+            origin: Some(tx.origin),
+            value: Some(tx.value),
+            gasprice: Some(tx.gasprice),
+            blob_versioned_hashes: tx.blob_hashes.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Env {
+    /// An EIP-1559 transaction sees the gas price it pays in the block.
+    pub fn new(context: Option<&EvmContext>, tx: Option<&TxData>) -> Self {
+        let block = context.map(BlockEnv::from).unwrap_or_default();
+        let mut tx_env = tx.map(TxEnv::from).unwrap_or_default();
+        if let Some((max_fee, max_priority_fee)) = tx.and_then(fee_caps) {
+            tx_env.gasprice = effective_gas_price(max_fee, max_priority_fee, block.basefee);
+        }
+        Self { block, tx: tx_env }
+    }
+}
+
+/// The max fee and the priority fee of an EIP-1559 transaction, `None` for
+/// one with a gas price.
+pub(crate) fn fee_caps(tx: &TxData) -> Option<(U256, U256)> {
+    Some((
+        tx.max_fee_per_gas?,
+        tx.max_priority_fee_per_gas.unwrap_or_default(),
+    ))
+}
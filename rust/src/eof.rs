@@ -0,0 +1,234 @@
+//! EOF containers (EIP-3540): code behind the `0xef00` magic, split into
+//! sections by a header.
+//!
+//! ```text
+//! magic 0xef00, version 0x01
+//! 0x01 types_size:u16
+//! 0x02 num_code_sections:u16 code_size:u16...
+//! 0x03 num_container_sections:u16 container_size:u32...   (optional)
+//! 0xff data_size:u16
+//! 0x00
+//! types, code sections, container sections, data
+//! ```
+//!
+//! Each code section is a function with a type: the words it takes, the
+//! words it returns (0x80 for one that never returns) and how high it grows
+//! the stack. From Osaka, code in a container runs with relative jumps and
+//! CALLF/RETF/JUMPF between sections (EIP-4200, EIP-4750, EIP-6206) instead
+//! of JUMP and JUMPI, and with DUPN, SWAPN and EXCHANGE (EIP-663). Only the
+//! header is checked here, not the code of the sections; EXTCALL, EOFCREATE
+//! and the data section instructions are not implemented.
+
+use core::fmt;
+
+use crate::prelude::*;
+
+pub const MAGIC: [u8; 2] = [0xef, 0x00];
+pub const VERSION: u8 = 0x01;
+/// The outputs of a section that never returns.
+pub const NON_RETURNING: u8 = 0x80;
+/// Most CALLF frames deep a container may go.
+pub const RETURN_STACK_LIMIT: usize = 1024;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0xff;
+const TERMINATOR: u8 = 0x00;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EofError {
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    /// A section of the header missing or out of order.
+    MissingSection(u8),
+    MissingTerminator,
+    /// The types section does not hold 4 bytes for each code section.
+    InvalidTypesSize,
+    /// A code or container section of zero bytes, or none at all.
+    EmptySection,
+    /// Section 0 must take nothing and never return.
+    InvalidFirstSectionType,
+    /// The body is shorter than the header says.
+    Truncated,
+    /// The body is longer than the header says.
+    TrailingBytes,
+}
+
+impl fmt::Display for EofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EofError::InvalidMagic => write!(f, "no EOF magic"),
+            EofError::UnsupportedVersion(version) => {
+                write!(f, "unsupported EOF version {}", version)
+            }
+            EofError::MissingSection(kind) => write!(f, "missing section kind {:#04x}", kind),
+            EofError::MissingTerminator => write!(f, "missing header terminator"),
+            EofError::InvalidTypesSize => write!(f, "types size does not match code sections"),
+            EofError::EmptySection => write!(f, "empty section"),
+            EofError::InvalidFirstSectionType => {
+                write!(f, "first code section must take nothing and not return")
+            }
+            EofError::Truncated => write!(f, "container shorter than its header"),
+            EofError::TrailingBytes => write!(f, "container longer than its header"),
+        }
+    }
+}
+
+impl core::error::Error for EofError {}
+
+/// The type of a code section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeType {
+    pub inputs: u8,
+    pub outputs: u8,
+    /// Most words the section adds on top of its inputs.
+    pub max_stack_increase: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container<'a> {
+    pub types: Vec<CodeType>,
+    pub code_sections: Vec<&'a [u8]>,
+    pub container_sections: Vec<&'a [u8]>,
+    pub data: &'a [u8],
+}
+
+/// True for code behind the EOF magic, valid or not.
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&MAGIC)
+}
+
+/// Instructions EOF code cannot use: jumps to computed destinations, code
+/// and gas introspection, the legacy calls and creations, SELFDESTRUCT.
+pub fn is_legacy_only(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        0x38 | 0x39 | 0x3b | 0x3c | 0x3f | 0x56..=0x58 | 0x5a | 0xf0..=0xf2 | 0xf4 | 0xf5 | 0xfa | 0xff
+    )
+}
+
+/// The EOF instructions, which legacy code cannot use.
+pub fn is_eof_only(opcode: u8) -> bool {
+    (0xe0..=0xe8).contains(&opcode)
+}
+
+impl<'a> Container<'a> {
+    pub fn parse(code: &'a [u8]) -> Result<Self, EofError> {
+        let mut reader = Reader { code, position: 0 };
+        if reader.bytes(2)? != MAGIC {
+            return Err(EofError::InvalidMagic);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(EofError::UnsupportedVersion(version));
+        }
+
+        reader.kind(KIND_TYPES)?;
+        let types_size = reader.u16()? as usize;
+        reader.kind(KIND_CODE)?;
+        let code_sizes = reader.sizes(|reader| reader.u16().map(usize::from))?;
+        if types_size != code_sizes.len() * 4 {
+            return Err(EofError::InvalidTypesSize);
+        }
+        let container_sizes = if reader.peek() == Some(KIND_CONTAINER) {
+            reader.position += 1;
+            reader.sizes(|reader| reader.u32().map(|size| size as usize))?
+        } else {
+            vec![]
+        };
+        reader.kind(KIND_DATA)?;
+        let data_size = reader.u16()? as usize;
+        if reader.u8()? != TERMINATOR {
+            return Err(EofError::MissingTerminator);
+        }
+
+        let types: Vec<_> = reader
+            .bytes(types_size)?
+            .chunks(4)
+            .map(|chunk| CodeType {
+                inputs: chunk[0],
+                outputs: chunk[1],
+                max_stack_increase: u16::from_be_bytes([chunk[2], chunk[3]]),
+            })
+            .collect();
+        if types[0].inputs != 0 || types[0].outputs != NON_RETURNING {
+            return Err(EofError::InvalidFirstSectionType);
+        }
+        let code_sections = code_sizes
+            .into_iter()
+            .map(|size| reader.bytes(size))
+            .collect::<Result<_, _>>()?;
+        let container_sections = container_sizes
+            .into_iter()
+            .map(|size| reader.bytes(size))
+            .collect::<Result<_, _>>()?;
+        let data = reader.bytes(data_size)?;
+        if reader.position != code.len() {
+            return Err(EofError::TrailingBytes);
+        }
+        Ok(Self {
+            types,
+            code_sections,
+            container_sections,
+            data,
+        })
+    }
+}
+
+struct Reader<'a> {
+    code: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8], EofError> {
+        let bytes = self
+            .code
+            .get(self.position..self.position + size)
+            .ok_or(EofError::Truncated)?;
+        self.position += size;
+        Ok(bytes)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.code.get(self.position).copied()
+    }
+
+    fn u8(&mut self) -> Result<u8, EofError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, EofError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, EofError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn kind(&mut self, kind: u8) -> Result<(), EofError> {
+        if self.peek() != Some(kind) {
+            return Err(EofError::MissingSection(kind));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    // A count of sections followed by the size of each, none empty
+    fn sizes(
+        &mut self,
+        size: impl Fn(&mut Self) -> Result<usize, EofError>,
+    ) -> Result<Vec<usize>, EofError> {
+        let count = self.u16()?;
+        let sizes = (0..count)
+            .map(|_| size(self))
+            .collect::<Result<Vec<_>, _>>()?;
+        if sizes.is_empty() || sizes.contains(&0) {
+            return Err(EofError::EmptySection);
+        }
+        Ok(sizes)
+    }
+}
@@ -0,0 +1,417 @@
+use primitive_types::U256;
+
+use crate::fmtutil::{format_ether, format_signed};
+use crate::inspector::{Inspector, Step};
+use crate::{opcodes, EvmResult};
+
+// Stack items shown in the before/after summaries, from the top
+const SUMMARY_ITEMS: usize = 4;
+
+struct Pending {
+    line: usize,
+    pc: usize,
+    opcode: u8,
+    depth: usize,
+    before: Vec<U256>,
+}
+
+/// Describes every executed instruction in a line of plain English, with the
+/// top of the stack before and after it, for readers learning the EVM:
+///
+/// ```text
+/// 0x0000  PUSH1 places 0x42 on the stack  [] -> [0x42]
+/// ```
+///
+/// What an instruction left behind is only known once the next one in its
+/// frame starts, so lines are completed one step late. Call `finish` with
+/// the result of the execution for the last ones.
+#[derive(Default)]
+pub struct ExplainTracer {
+    lines: Vec<String>,
+    pending: Vec<Pending>,
+}
+
+impl ExplainTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Completes the lines still waiting for their result and returns all of
+    /// them, in execution order. The final stack of `result` completes the
+    /// last instruction of the outermost frame, and a last line says where
+    /// its return data came from, if it has any.
+    pub fn finish(mut self, result: &EvmResult) -> Vec<String> {
+        // The result lists the stack from the top down
+        let stack: Vec<U256> = result.stack.iter().rev().copied().collect();
+        while let Some(pending) = self.pending.pop() {
+            let after = (pending.depth == 1).then_some(stack.as_slice());
+            self.complete(pending, after);
+        }
+        if let Some(origin) = &result.return_origin {
+            self.lines.push(format!(
+                "{} bytes of return data, from the {}",
+                result.return_data.len(),
+                origin
+            ));
+        }
+        self.lines
+    }
+
+    fn complete(&mut self, pending: Pending, after: Option<&[U256]>) {
+        let indent = "  ".repeat(pending.depth - 1);
+        let mut line = format!(
+            "{}{:#06x}  {}",
+            indent,
+            pending.pc,
+            describe(pending.opcode, &pending.before, after)
+        );
+        if let Some(after) = after {
+            line += &format!("  {} -> {}", summary(&pending.before), summary(after));
+        }
+        self.lines[pending.line] = line;
+    }
+}
+
+impl Inspector for ExplainTracer {
+    fn step(&mut self, step: &Step) {
+        // The previous instruction of this frame is done, and so are the
+        // frames it called
+        while let Some(pending) = self.pending.pop() {
+            if pending.depth < step.depth {
+                self.pending.push(pending);
+                break;
+            }
+            if pending.depth == step.depth {
+                self.complete(pending, Some(step.stack));
+                break;
+            }
+            self.complete(pending, None);
+        }
+
+        self.pending.push(Pending {
+            line: self.lines.len(),
+            pc: step.pc,
+            opcode: step.opcode,
+            depth: step.depth,
+            before: step.stack.to_vec(),
+        });
+        self.lines.push(String::new());
+    }
+
+    fn console_log(&mut self, message: &str) {
+        // In the frame of the call to the console, under the instruction
+        // that made it
+        let depth = self.pending.last().map_or(1, |pending| pending.depth + 1);
+        self.lines.push(format!(
+            "{}console.log  {}",
+            "  ".repeat(depth - 1),
+            message
+        ));
+    }
+}
+
+fn summary(stack: &[U256]) -> String {
+    let items: Vec<String> = stack
+        .iter()
+        .rev()
+        .take(SUMMARY_ITEMS)
+        .map(|value| format!("{:#x}", value))
+        .collect();
+    let more = if stack.len() > SUMMARY_ITEMS {
+        ", ..."
+    } else {
+        ""
+    };
+    format!("[{}{}]", items.join(", "), more)
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+// What an opcode that only places a value on the stack places there
+fn pushed_value(opcode: u8) -> Option<&'static str> {
+    let what = match opcode {
+        0x30 => "the address of the running account",
+        0x32 => "the transaction origin",
+        0x33 => "the caller",
+        0x36 => "the calldata size",
+        0x38 => "the code size",
+        0x3a => "the gas price",
+        0x3d => "the size of the last return data",
+        0x41 => "the block's coinbase",
+        0x42 => "the block timestamp",
+        0x43 => "the block number",
+        0x44 => "the block's prevrandao",
+        0x45 => "the block gas limit",
+        0x46 => "the chain id",
+        0x48 => "the block base fee",
+        0x4a => "the blob base fee",
+        0x58 => "the program counter",
+        0x59 => "the memory size",
+        0x5a => "the gas left",
+        _ => return None,
+    };
+    Some(what)
+}
+
+/// One sentence on what `opcode` does with the stack `before` it ran, and
+/// `after` when that is known.
+pub fn describe(opcode: u8, before: &[U256], after: Option<&[U256]>) -> String {
+    let name = opcodes::name(opcode).unwrap_or("INVALID");
+    // Stack items before the instruction, counting from the top
+    let arg = |n: usize| {
+        before
+            .len()
+            .checked_sub(n + 1)
+            .map(|index| format!("{:#x}", before[index]))
+            .unwrap_or_else(|| "nothing".to_string())
+    };
+    let result = after
+        .and_then(|after| after.last())
+        .map(|value| format!("{:#x}", value));
+    let result_or = |fallback: &str| result.clone().unwrap_or_else(|| fallback.to_string());
+    // The same, for amounts of wei
+    let ether_or = |fallback: &str| {
+        after
+            .and_then(|after| after.last())
+            .map(|value| format_ether(*value))
+            .unwrap_or_else(|| fallback.to_string())
+    };
+    let signed_arg = |n: usize| {
+        before
+            .len()
+            .checked_sub(n + 1)
+            .map(|index| format_signed(before[index]))
+            .unwrap_or_else(|| "nothing".to_string())
+    };
+    let signed_binary = |symbol: &str| {
+        let result = after
+            .and_then(|after| after.last())
+            .map(|value| format_signed(*value))
+            .unwrap_or_else(|| "?".to_string());
+        format!(
+            "{}: {} {} {} = {}",
+            name,
+            signed_arg(0),
+            symbol,
+            signed_arg(1),
+            result
+        )
+    };
+
+    let binary = |symbol: &str| {
+        format!(
+            "{}: {} {} {} = {}",
+            name,
+            arg(0),
+            symbol,
+            arg(1),
+            result_or("?")
+        )
+    };
+
+    match opcode {
+        0x00 => format!("{} ends the call", name),
+        0x01 => binary("+"),
+        0x02 => binary("*"),
+        0x03 => binary("-"),
+        0x04 => binary("/"),
+        0x05 => signed_binary("/"),
+        0x06 => binary("%"),
+        0x07 => signed_binary("%"),
+        0x08 | 0x09 => format!(
+            "{}: ({} {} {}) % {} = {}",
+            name,
+            arg(0),
+            if opcode == 0x08 { "+" } else { "*" },
+            arg(1),
+            arg(2),
+            result_or("?")
+        ),
+        0x0a => binary("**"),
+        0x10 => binary("<"),
+        0x11 => binary(">"),
+        0x12 => format!(
+            "SLT: {} < {} is {}",
+            signed_arg(0),
+            signed_arg(1),
+            result_or("?")
+        ),
+        0x13 => format!(
+            "SGT: {} > {} is {}",
+            signed_arg(0),
+            signed_arg(1),
+            result_or("?")
+        ),
+        0x14 => binary("=="),
+        0x16 => binary("&"),
+        0x17 => binary("|"),
+        0x18 => binary("^"),
+        0x15 => format!("ISZERO: {} == 0 is {}", arg(0), result_or("?")),
+        0x19 => format!(
+            "NOT flips every bit of {}, giving {}",
+            arg(0),
+            result_or("?")
+        ),
+        0x1a => format!(
+            "BYTE takes byte {} of {}, giving {}",
+            arg(0),
+            arg(1),
+            result_or("?")
+        ),
+        0x1b..=0x1d => format!(
+            "{}: {} {} {} = {}",
+            name,
+            arg(1),
+            if opcode == 0x1b { "<<" } else { ">>" },
+            arg(0),
+            result_or("?")
+        ),
+        0x20 => format!(
+            "SHA3 hashes {} bytes of memory at {}, giving {}",
+            arg(1),
+            arg(0),
+            result_or("?")
+        ),
+        0x31 => format!("BALANCE of {} is {}", arg(0), ether_or("?")),
+        0x34 => format!(
+            "CALLVALUE places the value sent with the call, {}, on the stack",
+            ether_or("?")
+        ),
+        0x47 => format!(
+            "SELFBALANCE places the balance of the running account, {}, on the stack",
+            ether_or("?")
+        ),
+        0x35 => format!(
+            "CALLDATALOAD reads {} from the calldata at {}",
+            result_or("a word"),
+            arg(0)
+        ),
+        0x37 | 0x39 | 0x3e => format!(
+            "{} copies {} bytes from {} to memory at {}",
+            name,
+            arg(2),
+            arg(1),
+            arg(0)
+        ),
+        0x3b => format!("EXTCODESIZE of {} is {}", arg(0), result_or("?")),
+        0x3c => format!(
+            "EXTCODECOPY copies {} bytes of the code of {} to memory at {}",
+            arg(3),
+            arg(0),
+            arg(1)
+        ),
+        0x3f => format!("EXTCODEHASH of {} is {}", arg(0), result_or("?")),
+        0x40 => format!("BLOCKHASH of block {} is {}", arg(0), result_or("?")),
+        0x49 => format!("BLOBHASH {} is {}", arg(0), result_or("?")),
+        0x50 => format!("POP discards {}", arg(0)),
+        0x51 => format!(
+            "MLOAD reads {} from memory at {}",
+            result_or("a word"),
+            arg(0)
+        ),
+        0x52 => format!("MSTORE writes {} to memory at {}", arg(1), arg(0)),
+        0x53 => format!(
+            "MSTORE8 writes the low byte of {} to memory at {}",
+            arg(1),
+            arg(0)
+        ),
+        0x54 => format!(
+            "SLOAD reads {} from storage slot {}",
+            result_or("a value"),
+            arg(0)
+        ),
+        0x55 => format!("SSTORE writes {} to storage slot {}", arg(1), arg(0)),
+        0x56 => format!("JUMP: jumping to {}", arg(0)),
+        0x57 => {
+            if before.len() >= 2 && before[before.len() - 2].is_zero() {
+                "JUMPI: condition is zero, not jumping".to_string()
+            } else {
+                format!("JUMPI: condition is nonzero, jumping to {}", arg(0))
+            }
+        }
+        0x5b => "JUMPDEST marks a place jumps may land".to_string(),
+        0x5c => format!(
+            "TLOAD reads {} from transient slot {}",
+            result_or("a value"),
+            arg(0)
+        ),
+        0x5d => format!("TSTORE writes {} to transient slot {}", arg(1), arg(0)),
+        0x5e => format!(
+            "MCOPY copies {} bytes of memory from {} to {}",
+            arg(2),
+            arg(1),
+            arg(0)
+        ),
+        0x5f..=0x7f => format!(
+            "{} places {} on the stack",
+            name,
+            result_or("a value from the code")
+        ),
+        0x80..=0x8f => {
+            let n = (opcode - 0x80) as usize;
+            format!(
+                "{} copies the {} item, {}, to the top",
+                name,
+                ordinal(n + 1),
+                arg(n)
+            )
+        }
+        0x90..=0x9f => {
+            let n = (opcode - 0x90 + 1) as usize;
+            format!(
+                "{} swaps the top item, {}, with the {}, {}",
+                name,
+                arg(0),
+                ordinal(n + 1),
+                arg(n)
+            )
+        }
+        0xa0..=0xa4 => format!(
+            "{} logs {} bytes of memory at {} with {} topics",
+            name,
+            arg(1),
+            arg(0),
+            opcode - 0xa0
+        ),
+        0xf0 | 0xf5 => format!(
+            "{} deploys {} bytes of init code from memory at {} with {} wei",
+            name,
+            arg(2),
+            arg(1),
+            arg(0)
+        ),
+        0xf1 | 0xf2 | 0xf4 | 0xfa => {
+            let outcome = match result.as_deref() {
+                Some("0x1") => ", it succeeded",
+                Some(_) => ", it failed",
+                None => "",
+            };
+            format!("{} calls {}{}", name, arg(1), outcome)
+        }
+        0xf3 => format!(
+            "RETURN ends the call, returning {} bytes of memory at {}",
+            arg(1),
+            arg(0)
+        ),
+        0xfd => format!(
+            "REVERT ends the call and undoes its changes, returning {} bytes of memory at {}",
+            arg(1),
+            arg(0)
+        ),
+        0xfe => "INVALID halts with an error".to_string(),
+        0xff => format!("SELFDESTRUCT sends the balance to {}", arg(0)),
+        _ => match pushed_value(opcode) {
+            Some(what) => format!("{} places {}, {}, on the stack", name, what, result_or("?")),
+            None => format!("{} runs", name),
+        },
+    }
+}
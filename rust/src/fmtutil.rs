@@ -0,0 +1,61 @@
+//! Formatting of 256-bit words for people: amounts of ether, fixed-width
+//! hex, and words read as signed numbers.
+
+use primitive_types::U256;
+
+use crate::prelude::*;
+
+pub const ETHER_DECIMALS: u32 = 18;
+
+// Below this, words print in decimal
+const SMALL: u64 = 1 << 32;
+
+/// `value` in units of 10^`decimals`, without trailing zeros in the fraction:
+/// `format_units(1_500_000, 6)` is `"1.5"`.
+pub fn format_units(value: U256, decimals: u32) -> String {
+    let unit = U256::exp10(decimals as usize);
+    let (whole, fraction) = value.div_mod(unit);
+    if fraction.is_zero() {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0>width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+/// An amount of wei in ether: `"1.5 ETH"`.
+pub fn format_ether(wei: U256) -> String {
+    format!("{} ETH", format_units(wei, ETHER_DECIMALS))
+}
+
+/// All 64 digits, as in storage dumps and traces.
+pub fn hex_word(value: U256) -> String {
+    format!("{:#066x}", value)
+}
+
+/// The low 20 bytes, zero padded.
+pub fn hex_address(value: U256) -> String {
+    format!("0x{:040x}", value & ((U256::one() << 160) - 1))
+}
+
+/// `value` read as a two's complement signed number, the way SDIV, SLT and
+/// friends see it.
+pub fn format_signed(value: U256) -> String {
+    if value.bit(255) {
+        format!("-{}", (!value).overflowing_add(U256::one()).0)
+    } else {
+        value.to_string()
+    }
+}
+
+/// A word the way it is easiest to take in: small numbers in decimal, small
+/// negative ones signed, anything else in hex.
+pub fn format_word(value: U256) -> String {
+    let negated = (!value).overflowing_add(U256::one()).0;
+    if value < U256::from(SMALL) {
+        value.to_string()
+    } else if value.bit(255) && negated <= U256::from(SMALL) {
+        format!("-{}", negated)
+    } else {
+        format!("{:#x}", value)
+    }
+}
@@ -0,0 +1,356 @@
+//! Reading the state of a live chain over JSON-RPC, to run calls locally
+//! against it.
+//!
+//! `ForkDb` is a `Database` that asks a node for every account, slot and
+//! block hash the interpreter reads, as of one pinned block. What the
+//! execution writes stays in the maps of `EvmData`, which lay over the fork
+//! and never reach the node.
+//!
+//! Give the fork a `StateCache` to ask the node only once for each lookup,
+//! and save the cache to run the same simulations offline later.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+use crate::cache::{CacheError, CacheKey, CacheValue, StateCache};
+use crate::fmtutil::hex_address;
+use crate::hexutil;
+use crate::host::{AccountInfo, Database};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpcError {
+    /// Only plain `http://` endpoints are built in, see `Transport`.
+    UnsupportedUrl(String),
+    Io(String),
+    HttpStatus(u16),
+    InvalidResponse(String),
+    /// The node answered with a JSON-RPC error object.
+    Node {
+        code: i64,
+        message: String,
+    },
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::UnsupportedUrl(url) => write!(f, "unsupported RPC url {}", url),
+            RpcError::Io(error) => write!(f, "RPC connection failed: {}", error),
+            RpcError::HttpStatus(status) => write!(f, "RPC request failed with HTTP {}", status),
+            RpcError::InvalidResponse(reason) => write!(f, "invalid RPC response: {}", reason),
+            RpcError::Node { code, message } => {
+                write!(f, "node returned error {}: {}", code, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Carries a JSON-RPC request body to a node and brings back the response
+/// body. Implement it to reach nodes over TLS, IPC or through a client the
+/// embedding application already has.
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn send(&self, body: &str) -> Result<String, RpcError>;
+}
+
+/// HTTP/1.0 over a plain TCP connection per request, enough for a local
+/// node or one behind a proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpTransport {
+    host: String,
+    port: u16,
+    path: String,
+    pub timeout: Duration,
+}
+
+impl HttpTransport {
+    pub fn new(url: &str) -> Result<Self, RpcError> {
+        let unsupported = || RpcError::UnsupportedUrl(url.to_string());
+        let rest = url.strip_prefix("http://").ok_or_else(unsupported)?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| unsupported())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(unsupported());
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            timeout: Duration::from_secs(30),
+        })
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send(&self, body: &str) -> Result<String, RpcError> {
+        let io = |error: std::io::Error| RpcError::Io(error.to_string());
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(io)?;
+        stream.set_read_timeout(Some(self.timeout)).map_err(io)?;
+        stream.set_write_timeout(Some(self.timeout)).map_err(io)?;
+
+        // HTTP/1.0 keeps the response unchunked and closes it at the end
+        let request = format!(
+            "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).map_err(io)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(io)?;
+        let response = String::from_utf8(response)
+            .map_err(|_| RpcError::InvalidResponse("body is not UTF-8".to_string()))?;
+
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| RpcError::InvalidResponse("no end of headers".to_string()))?;
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| RpcError::InvalidResponse("no status line".to_string()))?;
+        if status != 200 {
+            return Err(RpcError::HttpStatus(status));
+        }
+        Ok(body.to_string())
+    }
+}
+
+/// State of a remote chain as of block `block`.
+///
+/// `Database` has no way to report failures, so a lookup that fails reads as
+/// empty state and the error is kept: check `error` after running, a result
+/// computed while one occurred cannot be trusted.
+#[derive(Debug)]
+pub struct ForkDb {
+    transport: Box<dyn Transport>,
+    block: u64,
+    next_id: AtomicU64,
+    error: Mutex<Option<RpcError>>,
+    cache: Option<Mutex<StateCache>>,
+}
+
+impl ForkDb {
+    pub fn new(transport: impl Transport + 'static, block: u64) -> Self {
+        Self {
+            transport: Box::new(transport),
+            block,
+            next_id: AtomicU64::new(1),
+            error: Mutex::new(None),
+            cache: None,
+        }
+    }
+
+    /// The same node as of `block` instead.
+    pub fn with_block(mut self, block: u64) -> Self {
+        self.block = block;
+        self
+    }
+
+    /// Answers lookups from `cache` when it can and adds what it fetches to
+    /// it. Failed lookups are not cached.
+    pub fn with_cache(mut self, cache: StateCache) -> Self {
+        self.cache = Some(Mutex::new(cache));
+        self
+    }
+
+    /// A copy of the cache as it is now, `None` without one.
+    pub fn cache(&self) -> Option<StateCache> {
+        self.cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().clone())
+    }
+
+    /// Writes the cache to `path`, for `StateCache::load`. Does nothing
+    /// without a cache.
+    pub fn save_cache(&self, path: impl AsRef<std::path::Path>) -> Result<(), CacheError> {
+        match &self.cache {
+            Some(cache) => cache.lock().unwrap().save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Forks the node at `url` over plain HTTP.
+    pub fn http(url: &str, block: u64) -> Result<Self, RpcError> {
+        Ok(Self::new(HttpTransport::new(url)?, block))
+    }
+
+    pub fn block(&self) -> u64 {
+        self.block
+    }
+
+    /// The first lookup that failed, if any did.
+    pub fn error(&self) -> Option<RpcError> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Sends one request and returns its `result`.
+    pub fn request(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let response = self.transport.send(&body.to_string())?;
+        let mut response: Value = serde_json::from_str(&response)
+            .map_err(|error| RpcError::InvalidResponse(error.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Node {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(RpcError::InvalidResponse("no result".to_string())),
+        }
+    }
+
+    pub fn fetch_balance(&self, address: U256) -> Result<U256, RpcError> {
+        let result = self.request("eth_getBalance", json!([hex_address(address), self.tag()]))?;
+        parse_quantity(&result)
+    }
+
+    pub fn fetch_nonce(&self, address: U256) -> Result<u64, RpcError> {
+        let result = self.request(
+            "eth_getTransactionCount",
+            json!([hex_address(address), self.tag()]),
+        )?;
+        Ok(parse_quantity(&result)?.low_u64())
+    }
+
+    pub fn fetch_code(&self, address: U256) -> Result<Vec<u8>, RpcError> {
+        let result = self.request("eth_getCode", json!([hex_address(address), self.tag()]))?;
+        let code = result
+            .as_str()
+            .ok_or_else(|| RpcError::InvalidResponse("code is not a string".to_string()))?;
+        hexutil::decode(code).map_err(|error| RpcError::InvalidResponse(error.to_string()))
+    }
+
+    pub fn fetch_storage(&self, address: U256, key: U256) -> Result<U256, RpcError> {
+        let result = self.request(
+            "eth_getStorageAt",
+            json!([hex_address(address), format!("{:#x}", key), self.tag()]),
+        )?;
+        parse_quantity(&result)
+    }
+
+    /// `None` for blocks the node does not have.
+    pub fn fetch_block_hash(&self, number: U256) -> Result<Option<U256>, RpcError> {
+        let result = self.request(
+            "eth_getBlockByNumber",
+            json!([format!("{:#x}", number), false]),
+        )?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        parse_quantity(&result["hash"]).map(Some)
+    }
+
+    fn tag(&self) -> String {
+        format!("{:#x}", self.block)
+    }
+
+    // Answers from the cache or with `fetch`. A failed fetch keeps the first
+    // error and gives `None`, for the caller to stand in an empty value.
+    fn lookup(
+        &self,
+        key: CacheKey,
+        fetch: impl FnOnce() -> Result<CacheValue, RpcError>,
+    ) -> Option<CacheValue> {
+        if let Some(value) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.lock().unwrap().get(&key))
+        {
+            return Some(value);
+        }
+        match fetch() {
+            Ok(value) => {
+                if let Some(cache) = &self.cache {
+                    cache.lock().unwrap().insert(key, value.clone());
+                }
+                Some(value)
+            }
+            Err(error) => {
+                self.error.lock().unwrap().get_or_insert(error);
+                None
+            }
+        }
+    }
+}
+
+impl Database for ForkDb {
+    fn basic(&self, address: U256) -> Option<AccountInfo> {
+        let key = CacheKey::Account {
+            block: self.block,
+            address,
+        };
+        let account = self.lookup(key, || {
+            Ok(CacheValue::Account(AccountInfo {
+                balance: self.fetch_balance(address)?,
+                nonce: self.fetch_nonce(address)?,
+            }))
+        });
+        match account {
+            Some(CacheValue::Account(account)) => Some(account),
+            _ => Some(AccountInfo::default()),
+        }
+    }
+
+    fn code(&self, address: U256) -> Option<Vec<u8>> {
+        let key = CacheKey::Code {
+            block: self.block,
+            address,
+        };
+        match self.lookup(key, || self.fetch_code(address).map(CacheValue::Code)) {
+            Some(CacheValue::Code(code)) => Some(code),
+            _ => Some(vec![]),
+        }
+    }
+
+    fn storage(&self, address: U256, key: U256) -> U256 {
+        let cache_key = CacheKey::Storage {
+            block: self.block,
+            address,
+            key,
+        };
+        let value = self.lookup(cache_key, || {
+            self.fetch_storage(address, key).map(CacheValue::Storage)
+        });
+        match value {
+            Some(CacheValue::Storage(value)) => value,
+            _ => U256::zero(),
+        }
+    }
+
+    fn block_hash(&self, number: U256) -> Option<U256> {
+        let key = CacheKey::BlockHash { number };
+        match self.lookup(key, || {
+            self.fetch_block_hash(number).map(CacheValue::BlockHash)
+        }) {
+            Some(CacheValue::BlockHash(hash)) => hash,
+            _ => None,
+        }
+    }
+}
+
+fn parse_quantity(value: &Value) -> Result<U256, RpcError> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| RpcError::InvalidResponse(format!("{} is not a hex string", value)))?;
+    hexutil::parse_u256(text).map_err(|error| RpcError::InvalidResponse(error.to_string()))
+}
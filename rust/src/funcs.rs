@@ -1,125 +1,35 @@
-use primitive_types::U256;
-
-pub fn is_valid_jump_dest(code: &[u8], jump_dest: usize) -> bool {
-    // If jump destination is out of bounds, return false
-    if jump_dest >= code.len() {
-        return false;
-    }
-
-    let mut pc = 0;
-    while pc < code.len() {
-        let opcode = code[pc];
-
-        // If opcode is JUMPDEST, check if pc is the jump destination
-        if opcode == 0x5b && pc == jump_dest {
-            return true;
+use primitive_types::{H256, U256, U512};
+use sha3::{Digest, Keccak256};
+
+use crate::i256::I256;
+
+// By squaring, wrapping around 2^256 as EXP does
+pub fn exp(base: U256, exponent: U256) -> U256 {
+    let mut result = U256::one();
+    let mut power = base;
+    for bit in 0..exponent.bits() {
+        if exponent.bit(bit) {
+            result = result.overflowing_mul(power).0;
         }
-
-        // If opcode is PUSH1-PUSH32, skip the next N bytes
-        if opcode >= 0x60 && opcode <= 0x7f {
-            let data_size = (opcode - 0x60) + 1;
-            pc += data_size as usize;
-        }
-
-        pc += 1;
+        power = power.overflowing_mul(power).0;
     }
-
-    false
+    result
 }
 
-pub fn sdiv(mut a: U256, mut b: U256) -> U256 {
-    let mask = U256::one() << 255;
-    let a_flag = a & mask;
-    let b_flag = b & mask;
-
-    if a_flag & b_flag == mask {
-        let a = a.overflowing_neg().0;
-        let b = b.overflowing_neg().0;
-        a.checked_div(b).or_else(|| Some(U256::zero())).unwrap()
-    } else if a_flag == U256::zero() && b_flag == U256::zero() {
-        a.checked_div(b).or_else(|| Some(U256::zero())).unwrap()
-    } else {
-        if a_flag == U256::zero() {
-            b = b.overflowing_neg().0;
-        } else {
-            a = a.overflowing_neg().0;
-        }
-        let val = a.checked_div(b).or_else(|| Some(U256::zero())).unwrap();
-        val.overflowing_neg().0
-    }
+pub fn sdiv(a: U256, b: U256) -> U256 {
+    (I256::from(a) / I256::from(b)).into()
 }
 
-pub fn smod(mut a: U256, mut b: U256) -> U256 {
-    let mask = U256::one() << 255;
-    let a_flag = a & mask;
-    let b_flag = b & mask;
-
-    if a_flag == U256::zero() && b_flag == U256::zero() {
-        a.checked_rem(b).or_else(|| Some(U256::zero())).unwrap()
-    } else {
-        if a_flag == U256::zero() {
-            b = b.overflowing_neg().0;
-        } else if b_flag == U256::zero() {
-            a = a.overflowing_neg().0;
-        } else {
-            a = a.overflowing_neg().0;
-            b = b.overflowing_neg().0;
-        }
-        let val = a.checked_rem(b).or_else(|| Some(U256::zero())).unwrap();
-        val.overflowing_neg().0
-    }
+pub fn smod(a: U256, b: U256) -> U256 {
+    (I256::from(a) % I256::from(b)).into()
 }
 
-pub fn slt(mut a: U256, mut b: U256) -> U256 {
-    let mask = U256::one() << 255;
-    let a_flag = a & mask;
-    let b_flag = b & mask;
-
-    if a_flag == U256::zero() && b_flag == U256::zero() {
-        if a < b {
-            U256::one()
-        } else {
-            U256::zero()
-        }
-    } else if a_flag == U256::zero() {
-        U256::zero()
-    } else if b_flag == U256::zero() {
-        U256::one()
-    } else {
-        a = a.overflowing_neg().0;
-        b = b.overflowing_neg().0;
-        if a < b {
-            U256::one()
-        } else {
-            U256::zero()
-        }
-    }
+pub fn slt(a: U256, b: U256) -> U256 {
+    U256::from((I256::from(a) < I256::from(b)) as u8)
 }
 
-pub fn sgt(mut a: U256, mut b: U256) -> U256 {
-    let mask = U256::one() << 255;
-    let a_flag = a & mask;
-    let b_flag = b & mask;
-
-    if a_flag == U256::zero() && b_flag == U256::zero() {
-        if a > b {
-            U256::one()
-        } else {
-            U256::zero()
-        }
-    } else if a_flag == U256::zero() {
-        U256::zero()
-    } else if b_flag == U256::zero() {
-        U256::one()
-    } else {
-        a = a.overflowing_neg().0;
-        b = b.overflowing_neg().0;
-        if a < b {
-            U256::one()
-        } else {
-            U256::zero()
-        }
-    }
+pub fn sgt(a: U256, b: U256) -> U256 {
+    U256::from((I256::from(a) > I256::from(b)) as u8)
 }
 
 pub fn signextend(k: U256, v: U256) -> U256 {
@@ -137,22 +47,98 @@ pub fn signextend(k: U256, v: U256) -> U256 {
     }
 }
 
+// Byte `i` of `x`, counting from the most significant, zero past the last
+pub fn byte(i: U256, x: U256) -> U256 {
+    if i >= U256::from(32) {
+        return U256::zero();
+    }
+    U256::from(x.byte(31 - i.as_usize()))
+}
+
+// Shifts of 256 bits or more shift everything out
+pub fn shl(shift: U256, value: U256) -> U256 {
+    if shift >= U256::from(256) {
+        return U256::zero();
+    }
+    value << shift.as_usize()
+}
+
+pub fn shr(shift: U256, value: U256) -> U256 {
+    if shift >= U256::from(256) {
+        return U256::zero();
+    }
+    value >> shift.as_usize()
+}
+
 pub fn sar(a: U256, b: U256) -> U256 {
-    let mask = U256::one() << 255;
-    let msb_is_zero = b & mask == U256::zero();
-    if a >= U256::from(256) {
-        if msb_is_zero {
-            U256::zero()
-        } else {
-            U256::MAX
-        }
+    (I256::from(b) >> a).into()
+}
+
+pub const MIN_BLOB_BASE_FEE: u64 = 1;
+pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3338477;
+
+pub fn blob_base_fee(excess_blob_gas: U256) -> U256 {
+    fake_exponential(
+        U256::from(MIN_BLOB_BASE_FEE),
+        excess_blob_gas,
+        U256::from(BLOB_BASE_FEE_UPDATE_FRACTION),
+    )
+}
+
+// Approximates factor * e ** (numerator / denominator) using Taylor expansion (EIP-4844)
+pub fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let numerator: U512 = numerator.into();
+    let denominator: U512 = denominator.into();
+
+    let mut i = U512::one();
+    let mut output = U512::zero();
+    let mut numerator_accum = U512::from(factor) * denominator;
+    while numerator_accum > U512::zero() {
+        output += numerator_accum;
+        numerator_accum = match numerator_accum.checked_mul(numerator) {
+            Some(product) => product / (denominator * i),
+            None => return U256::MAX,
+        };
+        i += U512::one();
+    }
+
+    (output / denominator).try_into().unwrap_or(U256::MAX)
+}
+
+// keccak256(0xff ++ sender ++ salt ++ keccak256(init_code))[12..]
+pub fn create2_address(sender: U256, salt: U256, init_code_hash: H256) -> U256 {
+    let mut payload = [0u8; 85];
+    payload[0] = 0xff;
+    let mut word = [0u8; 32];
+    sender.to_big_endian(&mut word);
+    payload[1..21].copy_from_slice(&word[12..]);
+    salt.to_big_endian(&mut payload[21..53]);
+    payload[53..].copy_from_slice(init_code_hash.as_bytes());
+
+    let hash = Keccak256::digest(payload);
+    U256::from_big_endian(&hash[12..])
+}
+
+// keccak256(rlp([sender, nonce]))[12..]
+pub fn create_address(sender: U256, nonce: u64) -> U256 {
+    let mut sender_bytes = [0u8; 32];
+    sender.to_big_endian(&mut sender_bytes);
+
+    let mut payload = vec![0x94];
+    payload.extend_from_slice(&sender_bytes[12..]);
+    if nonce == 0 {
+        payload.push(0x80);
+    } else if nonce < 0x80 {
+        payload.push(nonce as u8);
     } else {
-        if msb_is_zero {
-            b >> a
-        } else {
-            let v_zero = b >> a;
-            let mask_one = U256::MAX << (U256::from(256) - a);
-            mask_one | v_zero
-        }
+        let nonce_bytes = nonce.to_be_bytes();
+        let leading_zeros = nonce.leading_zeros() as usize / 8;
+        payload.push(0x80 + (8 - leading_zeros) as u8);
+        payload.extend_from_slice(&nonce_bytes[leading_zeros..]);
     }
+
+    let mut rlp = vec![0xc0 + payload.len() as u8];
+    rlp.extend(payload);
+    let hash = Keccak256::digest(&rlp);
+    U256::from_big_endian(&hash[12..])
 }
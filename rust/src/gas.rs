@@ -0,0 +1,285 @@
+use primitive_types::U256;
+
+use crate::spec::{ChainConfig, SpecId};
+use crate::TxData;
+
+pub const CALL_VALUE_COST: u64 = 9000;
+pub const CALL_STIPEND: u64 = 2300;
+pub const SSTORE_SENTRY: u64 = 2300;
+pub const TX_BASE_COST: u64 = 21000;
+/// On top of the base cost, for transactions that create a contract.
+pub const TX_CREATE_COST: u64 = 32000;
+pub const TX_DATA_ZERO_COST: u64 = 4;
+pub const TX_DATA_NON_ZERO_COST: u64 = 16;
+pub const ACCESS_LIST_ADDRESS_COST: u64 = 2400;
+pub const ACCESS_LIST_STORAGE_KEY_COST: u64 = 1900;
+/// Per authorization of a set code transaction (EIP-7702), which pays for
+/// creating the account. What an existing account did not need comes back
+/// as a refund, down to the base cost.
+pub const PER_EMPTY_ACCOUNT_COST: u64 = 25000;
+pub const PER_AUTH_BASE_COST: u64 = 12500;
+/// Per word of init code, from Shanghai (EIP-3860).
+pub const INITCODE_WORD_COST: u64 = 2;
+
+/// Gas accounting of a single frame. Without a limit the frame is unmetered:
+/// costs are still added up, but it never runs out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gas {
+    limit: Option<u64>,
+    used: u64,
+    memory_words: usize,
+}
+
+impl Gas {
+    pub fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit,
+            used: 0,
+            memory_words: 0,
+        }
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit - self.used)
+    }
+
+    /// Spends `cost`, returns false when there is not enough gas left.
+    #[must_use]
+    pub fn charge(&mut self, cost: u64) -> bool {
+        let used = self.used.saturating_add(cost);
+        match self.limit {
+            Some(limit) if used > limit => false,
+            _ => {
+                self.used = used;
+                true
+            }
+        }
+    }
+
+    /// Sets aside gas for a nested frame and returns its limit, `None` when
+    /// this frame is unmetered. The stipend is given on top, free of charge.
+    pub fn reserve(&mut self, requested: U256, stipend: u64) -> Option<u64> {
+        let given = call_gas(self.remaining()?, requested);
+        self.used += given;
+        Some(given + stipend)
+    }
+
+    /// Settles a nested frame that was given `limit` and used `used` of it.
+    pub fn settle(&mut self, limit: Option<u64>, used: u64) {
+        match limit {
+            Some(limit) => self.used = self.used.saturating_sub(limit.saturating_sub(used)),
+            None => self.used = self.used.saturating_add(used),
+        }
+    }
+
+    /// Charges for growing the memory to `size` bytes, if it grew.
+    #[must_use]
+    pub fn charge_memory(&mut self, size: usize) -> bool {
+        let words = size.div_ceil(32);
+        if words <= self.memory_words {
+            return true;
+        }
+        let cost = memory_cost(words) - memory_cost(self.memory_words);
+        self.memory_words = words;
+        self.charge(cost)
+    }
+}
+
+pub fn memory_cost(words: usize) -> u64 {
+    let words = words as u64;
+    3 * words + words * words / 512
+}
+
+/// Cost per word of the copying opcodes.
+pub fn copy_cost(size: usize) -> u64 {
+    3 * size.div_ceil(32) as u64
+}
+
+/// Gas handed to a nested frame: what was asked for, capped at all but one
+/// 64th of what is left (EIP-150).
+pub fn call_gas(remaining: u64, requested: U256) -> u64 {
+    let available = remaining - remaining / 64;
+    if requested < U256::from(available) {
+        requested.as_u64()
+    } else {
+        available
+    }
+}
+
+/// Fixed part of the cost of `opcode`. Costs that depend on the fork or on
+/// the operands are charged by the instruction itself.
+pub fn static_cost(opcode: u8) -> u64 {
+    match opcode {
+        // STOP, RETURN, REVERT
+        0x00 | 0xf3 | 0xfd => 0,
+        // Charged by the instruction: account and storage accesses, calls
+        0x31 | 0x3b | 0x3c | 0x3f | 0x54 | 0x55 | 0xf1 | 0xf2 | 0xf4 | 0xfa | 0xff => 0,
+        // ADDRESS, ORIGIN, CALLER, CALLVALUE, CALLDATASIZE, CODESIZE, GASPRICE,
+        // RETURNDATASIZE, block context, POP, PC, MSIZE, GAS, PUSH0
+        0x30
+        | 0x32..=0x34
+        | 0x36
+        | 0x38
+        | 0x3a
+        | 0x3d
+        | 0x41..=0x46
+        | 0x48
+        | 0x4a
+        | 0x50
+        | 0x58..=0x5a
+        | 0x5f => 2,
+        0x5b => 1,
+        // ADD, SUB, comparisons, bitwise ops, CALLDATALOAD, copies, memory,
+        // BLOBHASH, PUSHn, DUPn, SWAPn
+        0x01
+        | 0x03
+        | 0x10..=0x1d
+        | 0x35
+        | 0x37
+        | 0x39
+        | 0x3e
+        | 0x49
+        | 0x51..=0x53
+        | 0x5e
+        | 0x60..=0x9f => 3,
+        // RJUMP
+        0xe0 => 2,
+        // RETF, DUPN, SWAPN, EXCHANGE
+        0xe4 | 0xe6..=0xe8 => 3,
+        // RJUMPI, RJUMPV
+        0xe1 | 0xe2 => 4,
+        // MUL, DIV, SDIV, MOD, SMOD, SIGNEXTEND, SELFBALANCE, CALLF, JUMPF
+        0x02 | 0x04..=0x07 | 0x0b | 0x47 | 0xe3 | 0xe5 => 5,
+        // ADDMOD, MULMOD, JUMP
+        0x08 | 0x09 | 0x56 => 8,
+        // EXP, JUMPI
+        0x0a | 0x57 => 10,
+        0x40 => 20,
+        0x20 => 30,
+        // TLOAD, TSTORE
+        0x5c | 0x5d => 100,
+        // LOGn
+        0xa0..=0xa4 => 375 * (opcode - 0xa0 + 1) as u64,
+        // CREATE, CREATE2
+        0xf0 | 0xf5 => 32000,
+        _ => 0,
+    }
+}
+
+/// Gas a transaction pays before any code runs: the base cost, its calldata
+/// and, from Berlin on, its access list (EIP-2930).
+pub fn intrinsic_gas(tx: &TxData, spec: SpecId) -> u64 {
+    let mut gas = TX_BASE_COST;
+
+    // EIP-2028 lowered the cost of non-zero bytes from 68
+    let non_zero_cost = if spec.is_enabled_in(SpecId::Istanbul) {
+        TX_DATA_NON_ZERO_COST
+    } else {
+        68
+    };
+    for &byte in tx.data.iter().flat_map(|data| data.iter()) {
+        gas += if byte == 0 {
+            TX_DATA_ZERO_COST
+        } else {
+            non_zero_cost
+        };
+    }
+
+    if spec.is_enabled_in(SpecId::Berlin) {
+        for item in tx.access_list.iter().flatten() {
+            gas += ACCESS_LIST_ADDRESS_COST;
+            gas += ACCESS_LIST_STORAGE_KEY_COST * item.storage_keys.len() as u64;
+        }
+    }
+    gas
+}
+
+/// What creating a contract from `size` bytes of init code costs for them,
+/// on top of the memory or the transaction data holding them.
+pub fn init_code_cost(size: usize, spec: SpecId) -> u64 {
+    if spec.is_enabled_in(SpecId::Shanghai) {
+        INITCODE_WORD_COST * size.div_ceil(32) as u64
+    } else {
+        0
+    }
+}
+
+/// What an EIP-1559 transaction pays per unit of gas in a block with
+/// `base_fee`: the base fee plus the priority fee, capped at the max fee.
+/// The base fee part is burnt, the rest goes to the coinbase.
+pub fn effective_gas_price(max_fee: U256, max_priority_fee: U256, base_fee: U256) -> U256 {
+    max_fee.min(base_fee.saturating_add(max_priority_fee))
+}
+
+/// Cost of an SSTORE writing `new` over `current`, and the change it makes to
+/// the refund counter. `original` is the value the slot had when the
+/// transaction started.
+///
+/// From Istanbul on this is net gas metering (EIP-2200), with the EIP-2929
+/// and EIP-3529 changes coming in through the schedule. Earlier forks only
+/// look at the current value.
+pub fn sstore_cost(
+    config: &ChainConfig,
+    original: U256,
+    current: U256,
+    new: U256,
+    cold: bool,
+) -> (u64, i64) {
+    let schedule = &config.gas;
+    let cold_cost = if cold { schedule.cold_sload } else { 0 };
+    let clears_refund = schedule.sstore_clears_refund as i64;
+
+    if !config.spec.is_enabled_in(SpecId::Istanbul) {
+        let cost = if current.is_zero() && !new.is_zero() {
+            schedule.sstore_set
+        } else {
+            schedule.sstore_reset
+        };
+        let refund = if !current.is_zero() && new.is_zero() {
+            clears_refund
+        } else {
+            0
+        };
+        return (cost + cold_cost, refund);
+    }
+
+    let warm_read = schedule.sload;
+    if current == new {
+        return (warm_read + cold_cost, 0);
+    }
+    // First write to the slot in this transaction
+    if original == current {
+        if original.is_zero() {
+            return (schedule.sstore_set + cold_cost, 0);
+        }
+        let refund = if new.is_zero() { clears_refund } else { 0 };
+        return (schedule.sstore_reset + cold_cost, refund);
+    }
+
+    // The slot is dirty: the first write already paid, adjust its refund
+    let mut refund = 0;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= clears_refund;
+        } else if new.is_zero() {
+            refund += clears_refund;
+        }
+    }
+    if original == new {
+        let restored = if original.is_zero() {
+            schedule.sstore_set
+        } else {
+            schedule.sstore_reset
+        };
+        refund += (restored - warm_read) as i64;
+    }
+    (warm_read + cold_cost, refund)
+}
@@ -0,0 +1,189 @@
+//! geth's `genesis.json`: the chain config, the header fields of block 0
+//! and the `alloc` of accounts it starts with, read into an `EvmData` to
+//! run code against.
+//!
+//! The spec is the last fork the config has active at genesis: block forks
+//! scheduled at block 0, the merge once the terminal total difficulty is
+//! reached, and time forks scheduled at or before the genesis timestamp.
+
+use std::fmt;
+use std::path::Path;
+
+use primitive_types::U256;
+use serde_json::{json, Map, Value};
+
+use crate::fmtutil::hex_address;
+use crate::hexutil::Bytes;
+use crate::spec::{ChainConfig, SpecId};
+use crate::t8n::{optional_quantity, parse_alloc, Alloc};
+use crate::{EvmContext, EvmData};
+
+// Config fields of the forks geth schedules by block number, in order
+const BLOCK_FORKS: [(&str, SpecId); 9] = [
+    ("homesteadBlock", SpecId::Homestead),
+    ("eip150Block", SpecId::Tangerine),
+    ("eip158Block", SpecId::SpuriousDragon),
+    ("byzantiumBlock", SpecId::Byzantium),
+    ("constantinopleBlock", SpecId::Constantinople),
+    ("petersburgBlock", SpecId::Petersburg),
+    ("istanbulBlock", SpecId::Istanbul),
+    ("berlinBlock", SpecId::Berlin),
+    ("londonBlock", SpecId::London),
+];
+
+// And those it schedules by timestamp, after the merge
+const TIME_FORKS: [(&str, SpecId); 4] = [
+    ("shanghaiTime", SpecId::Shanghai),
+    ("cancunTime", SpecId::Cancun),
+    ("pragueTime", SpecId::Prague),
+    ("osakaTime", SpecId::Osaka),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenesisError {
+    Io(String),
+    Invalid(String),
+}
+
+impl fmt::Display for GenesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenesisError::Io(error) => write!(f, "genesis file error: {}", error),
+            GenesisError::Invalid(reason) => write!(f, "invalid genesis: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GenesisError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Genesis {
+    pub chain_id: u64,
+    pub spec: SpecId,
+    pub coinbase: U256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub gas_limit: u64,
+    pub difficulty: U256,
+    /// PREVRANDAO of block 0 once the chain is merged.
+    pub mix_hash: U256,
+    pub base_fee: Option<U256>,
+    pub excess_blob_gas: Option<u64>,
+    pub alloc: Alloc,
+}
+
+impl Genesis {
+    pub fn from_json(value: &Value) -> Result<Self, GenesisError> {
+        let invalid = GenesisError::Invalid;
+        if !value.is_object() {
+            return Err(invalid("not an object".to_string()));
+        }
+        let field = |value: &Value, name: &str| optional_quantity(value, name).map_err(invalid);
+        let small = |value: &Value, name: &str| -> Result<Option<u64>, GenesisError> {
+            field(value, name)?
+                .map(|number| {
+                    u64::try_from(number).map_err(|_| invalid(format!("{} too large", name)))
+                })
+                .transpose()
+        };
+
+        let config = &value["config"];
+        let timestamp = small(value, "timestamp")?.unwrap_or_default();
+        let difficulty = field(value, "difficulty")?.unwrap_or_default();
+        let mut spec = SpecId::Frontier;
+        for (name, fork) in BLOCK_FORKS {
+            if small(config, name)? == Some(0) {
+                spec = fork;
+            }
+        }
+        let merged = field(config, "terminalTotalDifficulty")?
+            .is_some_and(|total_difficulty| total_difficulty <= difficulty);
+        if merged {
+            spec = SpecId::Merge;
+            for (name, fork) in TIME_FORKS {
+                if small(config, name)?.is_some_and(|time| time <= timestamp) {
+                    spec = fork;
+                }
+            }
+        }
+
+        let alloc = match &value["alloc"] {
+            Value::Null => Alloc::new(),
+            alloc => parse_alloc(alloc).map_err(|error| invalid(error.to_string()))?,
+        };
+        Ok(Self {
+            chain_id: small(config, "chainId")?.unwrap_or(1),
+            spec,
+            coinbase: field(value, "coinbase")?.unwrap_or_default(),
+            number: small(value, "number")?.unwrap_or_default(),
+            timestamp,
+            gas_limit: small(value, "gasLimit")?.unwrap_or_default(),
+            difficulty,
+            mix_hash: field(value, "mixHash")?.unwrap_or_default(),
+            base_fee: field(value, "baseFeePerGas")?,
+            excess_blob_gas: small(value, "excessBlobGas")?,
+            alloc,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, GenesisError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|error| GenesisError::Io(error.to_string()))?;
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|error| GenesisError::Invalid(error.to_string()))?;
+        Self::from_json(&value)
+    }
+
+    /// Block 0 as the block context, with DIFFICULTY reading the mix hash
+    /// once merged.
+    pub fn context(&self) -> EvmContext {
+        let hex = |value: U256| json!(format!("{:#x}", value));
+        let mut context = Map::new();
+        context.insert("coinbase".to_string(), json!(hex_address(self.coinbase)));
+        context.insert("chainid".to_string(), hex(self.chain_id.into()));
+        context.insert("number".to_string(), hex(self.number.into()));
+        context.insert("timestamp".to_string(), hex(self.timestamp.into()));
+        context.insert("gaslimit".to_string(), hex(self.gas_limit.into()));
+        let difficulty = if self.spec.is_enabled_in(SpecId::Merge) {
+            self.mix_hash
+        } else {
+            self.difficulty
+        };
+        context.insert("difficulty".to_string(), hex(difficulty));
+        if let Some(base_fee) = self.base_fee {
+            context.insert("basefee".to_string(), hex(base_fee));
+        }
+        if let Some(excess_blob_gas) = self.excess_blob_gas {
+            context.insert("excess_blob_gas".to_string(), hex(excess_blob_gas.into()));
+        }
+        serde_json::from_value(Value::Object(context)).unwrap()
+    }
+
+    /// The state of block 0, with its context and spec.
+    pub fn evm_data(&self) -> EvmData {
+        let mut data = EvmData {
+            context: Some(self.context()),
+            config: ChainConfig::new(self.spec),
+            ..Default::default()
+        };
+        insert_alloc(&mut data, &self.alloc);
+        data
+    }
+}
+
+/// Writes the accounts of `alloc` into the maps of `data`.
+pub fn insert_alloc(data: &mut EvmData, alloc: &Alloc) {
+    for (address, account) in alloc {
+        let key = address.to_string();
+        data.balances.insert(key.clone(), account.balance);
+        data.nonces.insert(key.clone(), account.nonce);
+        if !account.code.is_empty() {
+            data.codes
+                .insert(key.clone(), Bytes::from(account.code.clone()));
+        }
+        if !account.storage.is_empty() {
+            let slots = account.storage.iter().map(|(slot, value)| (*slot, *value));
+            data.state.insert(key, slots.collect());
+        }
+    }
+}
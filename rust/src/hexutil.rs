@@ -0,0 +1,342 @@
+//! Hex decoding and encoding for everything that comes in as text: code,
+//! calldata, state values, addresses and context fields.
+//!
+//! Input may carry a `0x` prefix, use either case and have an odd number of
+//! digits, in which case it reads as if padded with a leading zero.
+
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+
+use primitive_types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    /// A character that is not a hex digit, at a position of the input
+    /// without its prefix.
+    InvalidDigit { position: usize, found: char },
+    /// More digits than fit the target type.
+    TooLong { digits: usize, max: usize },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::InvalidDigit { position, found } => {
+                write!(f, "invalid hex digit {:?} at position {}", found, position)
+            }
+            HexError::TooLong { digits, max } => {
+                write!(f, "{} hex digits, at most {} fit", digits, max)
+            }
+        }
+    }
+}
+
+impl core::error::Error for HexError {}
+
+/// The digits of `text`, without surrounding whitespace and `0x` prefix.
+pub fn strip_prefix(text: &str) -> &str {
+    let text = text.trim();
+    text.strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text)
+}
+
+fn digit(position: usize, found: char) -> Result<u8, HexError> {
+    found
+        .to_digit(16)
+        .map(|value| value as u8)
+        .ok_or(HexError::InvalidDigit { position, found })
+}
+
+pub fn decode(text: &str) -> Result<Vec<u8>, HexError> {
+    let digits = strip_prefix(text);
+    let mut bytes = Vec::with_capacity(digits.len().div_ceil(2));
+    let mut chars = digits.chars().enumerate();
+
+    // An odd digit count leaves the first byte with a single digit
+    if digits.len() % 2 == 1 {
+        let (position, found) = chars.next().unwrap();
+        bytes.push(digit(position, found)?);
+    }
+    while let (Some((high_position, high)), Some((low_position, low))) =
+        (chars.next(), chars.next())
+    {
+        bytes.push(digit(high_position, high)? << 4 | digit(low_position, low)?);
+    }
+    Ok(bytes)
+}
+
+/// Lowercase digits without a prefix, the format of the state map.
+pub fn encode(bytes: impl AsRef<[u8]>) -> String {
+    hex::encode(bytes)
+}
+
+pub fn encode_prefixed(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Parses a big-endian number. No digits at all, as in `0x`, is zero.
+pub fn parse_u256(text: &str) -> Result<U256, HexError> {
+    let digits = strip_prefix(text);
+    if digits.len() > 64 {
+        return Err(HexError::TooLong {
+            digits: digits.len(),
+            max: 64,
+        });
+    }
+    let bytes = decode(digits)?;
+    Ok(U256::from_big_endian(&bytes))
+}
+
+pub fn parse_u64(text: &str) -> Result<u64, HexError> {
+    let digits = strip_prefix(text);
+    if digits.len() > 16 {
+        return Err(HexError::TooLong {
+            digits: digits.len(),
+            max: 16,
+        });
+    }
+    Ok(parse_u256(digits)?.as_u64())
+}
+
+/// An address, which must fit in 20 bytes.
+pub fn parse_address(text: &str) -> Result<U256, HexError> {
+    let digits = strip_prefix(text);
+    if digits.len() > 40 {
+        return Err(HexError::TooLong {
+            digits: digits.len(),
+            max: 40,
+        });
+    }
+    parse_u256(digits)
+}
+
+/// Bytes, read from hex with or without a prefix and written with one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Deref for Bytes {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for Bytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl FromStr for Bytes {
+    type Err = HexError;
+
+    fn from_str(text: &str) -> Result<Self, HexError> {
+        decode(text).map(Self)
+    }
+}
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_prefixed(&self.0))
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Bytes::from_hex(&text).map_err(de::Error::custom)
+    }
+}
+
+/// Values that inputs give as hex text. Reading takes the prefix or not,
+/// writing adds it.
+pub trait Hex: Sized {
+    fn from_hex(text: &str) -> Result<Self, HexError>;
+    fn to_hex(&self) -> String;
+}
+
+impl Hex for U256 {
+    fn from_hex(text: &str) -> Result<Self, HexError> {
+        parse_u256(text)
+    }
+
+    fn to_hex(&self) -> String {
+        format!("{:#x}", self)
+    }
+}
+
+impl Hex for u64 {
+    fn from_hex(text: &str) -> Result<Self, HexError> {
+        parse_u64(text)
+    }
+
+    fn to_hex(&self) -> String {
+        format!("{:#x}", self)
+    }
+}
+
+impl Hex for Bytes {
+    fn from_hex(text: &str) -> Result<Self, HexError> {
+        decode(text).map(Self)
+    }
+
+    fn to_hex(&self) -> String {
+        encode_prefixed(&self.0)
+    }
+}
+
+/// Bytes as 0x-prefixed hex, for `#[serde(with = "hexutil::bytes")]`.
+pub mod bytes {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::prelude::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::encode_prefixed(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::decode(&text).map_err(de::Error::custom)
+    }
+}
+
+/// `bytes` for optional fields, `null` when there are none.
+pub mod option_bytes {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::prelude::*;
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => super::bytes::serialize(bytes, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| super::decode(&text).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+/// A word holding an address as its 20 bytes, zero padded.
+pub mod address {
+    use primitive_types::U256;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::fmtutil::hex_address;
+    use crate::prelude::*;
+
+    pub fn serialize<S: Serializer>(address: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex_address(*address))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::parse_address(&text).map_err(de::Error::custom)
+    }
+}
+
+/// `address` for optional fields.
+pub mod option_address {
+    use primitive_types::U256;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::prelude::*;
+
+    pub fn serialize<S: Serializer>(
+        address: &Option<U256>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match address {
+            Some(address) => super::address::serialize(address, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<U256>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| super::parse_address(&text).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Optional `Hex` values, for `#[serde(default, with = "hexutil::option")]`.
+pub mod option {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use super::Hex;
+    use crate::prelude::*;
+
+    pub fn serialize<S: Serializer, T: Hex>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_hex()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Hex>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| T::from_hex(&text).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Lists of words, such as storage keys and blob hashes, written with all
+/// 64 digits.
+pub mod words {
+    use primitive_types::U256;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::fmtutil::hex_word;
+    use crate::prelude::*;
+
+    pub fn serialize<S: Serializer>(words: &[U256], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(words.iter().map(|word| hex_word(*word)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<U256>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|text| super::parse_u256(text).map_err(de::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,93 @@
+use core::fmt::Debug;
+
+use primitive_types::U256;
+
+use crate::prelude::*;
+use crate::EvmData;
+
+/// The parts of an account that are not code or storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccountInfo {
+    pub balance: U256,
+    pub nonce: u64,
+}
+
+/// A store the interpreter reads state from. Writes never reach it: they
+/// land in the maps of `EvmData`, which are journaled and shadow whatever
+/// the store holds for the same entry.
+///
+/// Every method has an empty default, so a store only implements what it
+/// has.
+pub trait Database: Debug + Send + Sync {
+    /// Balance and nonce of `address`, `None` for an account it does not
+    /// know.
+    fn basic(&self, _address: U256) -> Option<AccountInfo> {
+        None
+    }
+
+    fn code(&self, _address: U256) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Unset slots are zero.
+    fn storage(&self, _address: U256, _key: U256) -> U256 {
+        U256::zero()
+    }
+
+    /// Hash of block `number`. The interpreter only asks for the 256 most
+    /// recent blocks, anything else resolves to zero.
+    fn block_hash(&self, _number: U256) -> Option<U256> {
+        None
+    }
+}
+
+/// Block hashes by number, without any accounts.
+impl Database for HashMap<U256, U256> {
+    fn block_hash(&self, number: U256) -> Option<U256> {
+        self.get(&number).copied()
+    }
+}
+
+/// The in-memory maps, falling back to `database` for entries they do not
+/// hold.
+impl Database for EvmData {
+    fn basic(&self, address: U256) -> Option<AccountInfo> {
+        let key = address.to_string();
+        let stored = self.database.as_ref().and_then(|db| db.basic(address));
+        let balance = self.balances.get(&key).copied();
+        let nonce = self.nonces.get(&key).copied();
+        if stored.is_none() && balance.is_none() && nonce.is_none() {
+            return None;
+        }
+        let stored = stored.unwrap_or_default();
+        Some(AccountInfo {
+            balance: balance.unwrap_or(stored.balance),
+            nonce: nonce.unwrap_or(stored.nonce),
+        })
+    }
+
+    fn code(&self, address: U256) -> Option<Vec<u8>> {
+        match self.codes.get(&address.to_string()) {
+            Some(code) => Some(code.to_vec()),
+            None => self.database.as_ref().and_then(|db| db.code(address)),
+        }
+    }
+
+    fn storage(&self, address: U256, key: U256) -> U256 {
+        let account = address.to_string();
+        let slots = self.state.get(&account);
+        match slots.and_then(|slots| slots.get(&key)) {
+            Some(value) => *value,
+            None if self.cleared_storage.contains(&account) => U256::zero(),
+            None => self
+                .database
+                .as_ref()
+                .map(|db| db.storage(address, key))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn block_hash(&self, number: U256) -> Option<U256> {
+        self.database.as_ref().and_then(|db| db.block_hash(number))
+    }
+}
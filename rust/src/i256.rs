@@ -0,0 +1,155 @@
+//! Signed 256-bit integers, stored in two's complement as the EVM keeps them
+//! on the stack.
+//!
+//! Arithmetic follows the signed opcodes rather than Rust's integers:
+//! division and remainder by zero give zero, `MIN / -1` and `-MIN` wrap to
+//! `MIN`, and shifting by 256 bits or more leaves only the sign.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Div, Neg, Rem, Shr};
+
+use primitive_types::U256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct I256(U256);
+
+impl I256 {
+    pub const ZERO: I256 = I256(U256::zero());
+    pub const MINUS_ONE: I256 = I256(U256::MAX);
+    /// -2^255
+    pub const MIN: I256 = I256(U256([0, 0, 0, 1 << 63]));
+    /// 2^255 - 1
+    pub const MAX: I256 = I256(U256([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]));
+
+    /// The number whose two's complement is `value`.
+    pub fn from_two_complement(value: U256) -> Self {
+        I256(value)
+    }
+
+    pub fn to_two_complement(self) -> U256 {
+        self.0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// The magnitude, which for `MIN` is 2^255.
+    pub fn unsigned_abs(self) -> U256 {
+        if self.is_negative() {
+            self.0.overflowing_neg().0
+        } else {
+            self.0
+        }
+    }
+
+    // The number with `magnitude` and the sign given, wrapping as negation does
+    fn with_sign(magnitude: U256, negative: bool) -> Self {
+        let value = I256(magnitude);
+        if negative {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+impl From<U256> for I256 {
+    fn from(value: U256) -> Self {
+        I256::from_two_complement(value)
+    }
+}
+
+impl From<I256> for U256 {
+    fn from(value: I256) -> Self {
+        value.to_two_complement()
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(value: i128) -> Self {
+        I256::with_sign(U256::from(value.unsigned_abs()), value < 0)
+    }
+}
+
+impl Neg for I256 {
+    type Output = I256;
+
+    fn neg(self) -> I256 {
+        I256(self.0.overflowing_neg().0)
+    }
+}
+
+impl Div for I256 {
+    type Output = I256;
+
+    /// Rounds toward zero, as SDIV does.
+    fn div(self, divisor: I256) -> I256 {
+        let quotient = self
+            .unsigned_abs()
+            .checked_div(divisor.unsigned_abs())
+            .unwrap_or_default();
+        I256::with_sign(quotient, self.is_negative() != divisor.is_negative())
+    }
+}
+
+impl Rem for I256 {
+    type Output = I256;
+
+    /// Takes the sign of the dividend, as SMOD does.
+    fn rem(self, divisor: I256) -> I256 {
+        let remainder = self
+            .unsigned_abs()
+            .checked_rem(divisor.unsigned_abs())
+            .unwrap_or_default();
+        I256::with_sign(remainder, self.is_negative())
+    }
+}
+
+impl Shr<U256> for I256 {
+    type Output = I256;
+
+    /// Shifts in copies of the sign bit, as SAR does.
+    fn shr(self, shift: U256) -> I256 {
+        let fill = if self.is_negative() {
+            I256::MINUS_ONE
+        } else {
+            I256::ZERO
+        };
+        if shift >= U256::from(256) {
+            return fill;
+        }
+        let shift = shift.as_usize();
+        if shift == 0 {
+            return self;
+        }
+        I256((self.0 >> shift) | (fill.0 << (256 - shift)))
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &I256) -> Ordering {
+        // Negative numbers sort below the others, and among numbers of the
+        // same sign two's complement keeps the unsigned order
+        other
+            .is_negative()
+            .cmp(&self.is_negative())
+            .then(self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &I256) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.unsigned_abs())
+    }
+}
@@ -0,0 +1,167 @@
+use primitive_types::U256;
+
+use crate::{EvmData, EvmMemory, EvmResult, Log};
+
+/// Interpreter state right before an instruction executes.
+pub struct Step<'a> {
+    /// Code of the running frame.
+    pub code: &'a [u8],
+    pub pc: usize,
+    pub opcode: u8,
+    /// Call depth, starting at 1 for the outermost frame.
+    pub depth: usize,
+    /// Gas used by the frame so far, the instruction not included.
+    pub gas_used: u64,
+    pub stack: &'a [U256],
+    pub memory: &'a EvmMemory,
+    /// State as the instruction will see it.
+    pub data: &'a EvmData,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+}
+
+/// A message call about to run, the transaction itself included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallFrame<'a> {
+    pub kind: CallKind,
+    /// Depth of the frame the call runs in, 1 for the transaction.
+    pub depth: usize,
+    pub caller: U256,
+    /// The account the code runs as.
+    pub address: U256,
+    /// The account the code comes from, other than `address` for
+    /// DELEGATECALL.
+    pub code_address: U256,
+    pub value: U256,
+    pub input: &'a [u8],
+    /// Gas handed to the frame, the gas limit for the transaction. `None`
+    /// when unmetered.
+    pub gas: Option<u64>,
+}
+
+/// A contract creation about to run its init code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreateFrame<'a> {
+    pub depth: usize,
+    pub caller: U256,
+    pub address: U256,
+    pub value: U256,
+    pub init_code: &'a [u8],
+    pub gas: Option<u64>,
+}
+
+/// Hooks invoked by the interpreter while it runs, in every frame.
+///
+/// Every `*_start` is matched by an `*_end` for the same frame, and frames
+/// nest: everything between the two happened inside the frame.
+pub trait Inspector {
+    fn step(&mut self, _step: &Step) {}
+
+    fn sstore(&mut self, _key: U256, _value: U256) {}
+
+    /// A log was emitted. It is reported even if its frame is rolled back
+    /// later, which `EvmResult::logs` does not do.
+    fn log(&mut self, _log: &Log) {}
+
+    /// A contract printed `message` through `console.log`, right after the
+    /// `call_start` of its call to the console. Like logs, it is reported
+    /// even if the frame is rolled back.
+    fn console_log(&mut self, _message: &str) {}
+
+    fn call_start(&mut self, _call: &CallFrame) {}
+
+    /// `result` is what the call returned to its caller, gas used included.
+    fn call_end(&mut self, _call: &CallFrame, _result: &EvmResult) {}
+
+    fn create_start(&mut self, _create: &CreateFrame) {}
+
+    /// The result of the init code, with the code deposit in its gas used.
+    /// It is a failure when the init code ran fine but the code could not be
+    /// stored.
+    fn create_end(&mut self, _create: &CreateFrame, _result: &EvmResult) {}
+}
+
+impl Inspector for () {}
+
+impl<T: Inspector + ?Sized> Inspector for &mut T {
+    fn step(&mut self, step: &Step) {
+        (**self).step(step);
+    }
+
+    fn sstore(&mut self, key: U256, value: U256) {
+        (**self).sstore(key, value);
+    }
+
+    fn log(&mut self, log: &Log) {
+        (**self).log(log);
+    }
+
+    fn console_log(&mut self, message: &str) {
+        (**self).console_log(message);
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        (**self).call_start(call);
+    }
+
+    fn call_end(&mut self, call: &CallFrame, result: &EvmResult) {
+        (**self).call_end(call, result);
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        (**self).create_start(create);
+    }
+
+    fn create_end(&mut self, create: &CreateFrame, result: &EvmResult) {
+        (**self).create_end(create, result);
+    }
+}
+
+/// Two inspectors at once, the first hearing each hook first.
+impl<A: Inspector, B: Inspector> Inspector for (A, B) {
+    fn step(&mut self, step: &Step) {
+        self.0.step(step);
+        self.1.step(step);
+    }
+
+    fn sstore(&mut self, key: U256, value: U256) {
+        self.0.sstore(key, value);
+        self.1.sstore(key, value);
+    }
+
+    fn log(&mut self, log: &Log) {
+        self.0.log(log);
+        self.1.log(log);
+    }
+
+    fn console_log(&mut self, message: &str) {
+        self.0.console_log(message);
+        self.1.console_log(message);
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        self.0.call_start(call);
+        self.1.call_start(call);
+    }
+
+    fn call_end(&mut self, call: &CallFrame, result: &EvmResult) {
+        self.0.call_end(call, result);
+        self.1.call_end(call, result);
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        self.0.create_start(create);
+        self.1.create_start(create);
+    }
+
+    fn create_end(&mut self, create: &CreateFrame, result: &EvmResult) {
+        self.0.create_end(create, result);
+        self.1.create_end(create, result);
+    }
+}
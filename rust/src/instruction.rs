@@ -0,0 +1,40 @@
+//! Opcode handlers registered on `EvmData::instructions`, which take over
+//! an opcode from the interpreter, or give an unused one a meaning, to try
+//! out an EIP or a chain's own opcodes without forking the interpreter.
+//!
+//! A registered handler runs whatever the fork and the code format: the
+//! opcode is neither checked against the spec nor charged its static cost,
+//! the handler charges what it costs itself.
+
+use crate::gas::Gas;
+use crate::stack::Stack;
+use crate::{EvmData, EvmMemory, HaltReason};
+
+/// What a handler has of the running frame.
+pub struct InstructionContext<'a> {
+    /// Code of the running frame.
+    pub code: &'a [u8],
+    /// Of the byte after the opcode, where execution goes on. A handler
+    /// with immediates moves it past them.
+    pub pc: usize,
+    /// Call depth, starting at 1 for the outermost frame.
+    pub depth: usize,
+    /// False in a static context.
+    pub writable: bool,
+    pub stack: &'a mut Stack,
+    pub memory: &'a mut EvmMemory,
+    pub gas: &'a mut Gas,
+    pub data: &'a mut EvmData,
+}
+
+/// How the frame goes on after a handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    /// Ends the frame successfully, as STOP does.
+    Stop,
+    /// Fails the frame, which uses up its gas and is rolled back.
+    Halt(HaltReason),
+}
+
+pub type Instruction = fn(&mut InstructionContext) -> Control;
@@ -0,0 +1,247 @@
+//! Execution one instruction at a time, driven by the caller.
+//!
+//! An `Interpreter` runs the transaction on a thread of its own that stops
+//! before every instruction and waits for the next `step`, so the caller
+//! decides when execution moves on and sees it live rather than recorded, as
+//! the `Debugger` does. Dropping an interpreter midway abandons the run.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use primitive_types::U256;
+
+use crate::inspector::{Inspector, Step};
+use crate::{evm_with_inspector, EvmData, EvmResult, EXECUTION_STACK_SIZE};
+
+/// The interpreter right before an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepState {
+    pub pc: usize,
+    pub opcode: u8,
+    /// Call depth, starting at 1 for the outermost frame.
+    pub depth: usize,
+    /// The account whose code is running.
+    pub address: U256,
+    /// Gas used by the frame so far.
+    pub gas_used: u64,
+    /// Bottom of the stack first.
+    pub stack: Vec<U256>,
+    /// Memory of the frame up to its current size.
+    pub memory: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StepResult {
+    /// Stopped before an instruction, which the next `step` runs.
+    Step(StepState),
+    /// The transaction is over. Further steps return it again.
+    Done(EvmResult),
+}
+
+enum Event {
+    Step(StepState),
+    Done(Box<EvmResult>, Box<EvmData>),
+}
+
+// Unwinds the worker when the interpreter is dropped midway
+struct Abandoned;
+
+struct Pause {
+    events: Sender<Event>,
+    resume: Receiver<()>,
+}
+
+impl Inspector for Pause {
+    fn step(&mut self, step: &Step) {
+        let state = StepState {
+            pc: step.pc,
+            opcode: step.opcode,
+            depth: step.depth,
+            address: step.data.env.tx.address,
+            gas_used: step.gas_used,
+            stack: step.stack.to_vec(),
+            memory: step.memory.data().to_vec(),
+        };
+        if self.events.send(Event::Step(state)).is_err() || self.resume.recv().is_err() {
+            panic::resume_unwind(Box::new(Abandoned));
+        }
+    }
+}
+
+enum Run {
+    NotStarted {
+        code: Vec<u8>,
+        data: Box<EvmData>,
+    },
+    Running {
+        events: Receiver<Event>,
+        resume: Sender<()>,
+        worker: Option<JoinHandle<()>>,
+        paused: bool,
+    },
+    Done {
+        result: Box<EvmResult>,
+        data: Box<EvmData>,
+    },
+}
+
+pub struct Interpreter {
+    writable: bool,
+    run: Option<Run>,
+}
+
+impl Interpreter {
+    /// Runs `code` in the transaction and block of `data`.
+    pub fn new(code: Vec<u8>, data: EvmData) -> Self {
+        Self {
+            writable: true,
+            run: Some(Run::NotStarted {
+                code,
+                data: Box::new(data),
+            }),
+        }
+    }
+
+    /// Run without state changes, as STATICCALL does. Only before the first
+    /// step.
+    pub fn with_writable(mut self, writable: bool) -> Self {
+        self.writable = writable;
+        self
+    }
+
+    /// Runs the instruction the interpreter stopped before, if any, and stops
+    /// before the next one.
+    pub fn step(&mut self) -> StepResult {
+        if let Some(Run::NotStarted { .. }) = self.run {
+            let Some(Run::NotStarted { code, data }) = self.run.take() else {
+                unreachable!()
+            };
+            self.run = Some(self.start(code, *data));
+        }
+        let event = match self.run.as_mut().unwrap() {
+            Run::Done { result, .. } => return StepResult::Done((**result).clone()),
+            Run::NotStarted { .. } => unreachable!(),
+            Run::Running {
+                events,
+                resume,
+                worker,
+                paused,
+            } => {
+                if *paused {
+                    let _ = resume.send(());
+                }
+                match events.recv() {
+                    Ok(Event::Step(state)) => {
+                        *paused = true;
+                        return StepResult::Step(state);
+                    }
+                    Ok(event) => {
+                        let _ = worker.take().unwrap().join();
+                        event
+                    }
+                    // The interpreter panicked, pass it on
+                    Err(_) => {
+                        let payload = worker.take().unwrap().join().unwrap_err();
+                        panic::resume_unwind(payload)
+                    }
+                }
+            }
+        };
+        let Event::Done(result, data) = event else {
+            unreachable!()
+        };
+        let done = (*result).clone();
+        self.run = Some(Run::Done { result, data });
+        StepResult::Done(done)
+    }
+
+    /// Steps until the transaction is over.
+    pub fn finish(&mut self) -> EvmResult {
+        loop {
+            if let StepResult::Done(result) = self.step() {
+                return result;
+            }
+        }
+    }
+
+    /// The result, once the transaction is over.
+    pub fn result(&self) -> Option<&EvmResult> {
+        match &self.run {
+            Some(Run::Done { result, .. }) => Some(result),
+            _ => None,
+        }
+    }
+
+    /// The state as the transaction left it, once it is over.
+    pub fn into_data(mut self) -> Option<EvmData> {
+        match self.run.take() {
+            Some(Run::Done { data, .. }) => Some(*data),
+            _ => None,
+        }
+    }
+
+    fn start(&self, code: Vec<u8>, mut data: EvmData) -> Run {
+        let (events, received) = channel();
+        let (resume, resumed) = channel();
+        let writable = self.writable;
+        let worker = thread::Builder::new().stack_size(EXECUTION_STACK_SIZE);
+        let worker = worker.spawn(move || {
+            let mut pause = Pause {
+                events,
+                resume: resumed,
+            };
+            let run = panic::catch_unwind(AssertUnwindSafe(|| {
+                evm_with_inspector(&code, &mut data, writable, &mut pause)
+            }));
+            match run {
+                Ok(result) => {
+                    let _ = pause
+                        .events
+                        .send(Event::Done(Box::new(result), Box::new(data)));
+                }
+                Err(payload) if payload.is::<Abandoned>() => {}
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        });
+        let worker = worker.unwrap();
+        Run::Running {
+            events: received,
+            resume,
+            worker: Some(worker),
+            paused: false,
+        }
+    }
+}
+
+/// The states before each instruction, in order. The result is left for
+/// `result` once the iterator is exhausted.
+impl Iterator for Interpreter {
+    type Item = StepState;
+
+    fn next(&mut self) -> Option<StepState> {
+        match self.step() {
+            StepResult::Step(state) => Some(state),
+            StepResult::Done(_) => None,
+        }
+    }
+}
+
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        if let Some(Run::Running {
+            events,
+            resume,
+            mut worker,
+            ..
+        }) = self.run.take()
+        {
+            // The worker finds both ends gone at its next instruction
+            drop(resume);
+            drop(events);
+            if let Some(worker) = worker.take() {
+                let _ = worker.join();
+            }
+        }
+    }
+}
@@ -0,0 +1,451 @@
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use primitive_types::{H256, U256};
+
+use crate::cheatcodes::Prank;
+use crate::hexutil::Bytes;
+use crate::host::Database;
+use crate::prelude::*;
+use crate::statediff::StateDiff;
+use crate::trie::EMPTY_CODE_HASH;
+use crate::{EvmData, HaltReason, Log};
+
+/// One undoable change, recorded so a failed call frame can be rolled back
+/// without copying the state every time a frame starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    StateChanged {
+        address: String,
+        key: U256,
+        previous: Option<U256>,
+    },
+    BalanceChanged {
+        address: String,
+        previous: Option<U256>,
+    },
+    NonceChanged {
+        address: String,
+        previous: Option<u64>,
+    },
+    CodeChanged {
+        address: String,
+        previous: Option<Bytes>,
+    },
+    TransientStorageChanged {
+        address: U256,
+        key: U256,
+        previous: U256,
+    },
+    StorageCleared(String),
+    AccountCreated(U256),
+    SelfDestructed(U256),
+    AddressWarmed(U256),
+    SlotWarmed(U256, U256),
+    RefundChanged {
+        previous: i64,
+    },
+    LogEmitted,
+}
+
+/// Bookkeeping that lives for a single transaction: the EIP-2929 access sets,
+/// EIP-1153 transient storage, the gas refund counter and the logs.
+///
+/// Like the state, every change here is journaled and undone when the frame
+/// that made it fails. Changes from frames that succeed are kept until the
+/// transaction ends, when the whole substate is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct Substate {
+    pub transient_storage: HashMap<(U256, U256), U256>,
+    pub accessed_addresses: HashSet<U256>,
+    pub accessed_storage: HashSet<(U256, U256)>,
+    /// Storage values as they were when the transaction started, recorded on
+    /// the first write to a slot, by account and slot (EIP-2200).
+    pub original_storage: HashMap<(U256, U256), U256>,
+    /// Can dip below zero inside a transaction (EIP-2200), never at its end.
+    pub refund: i64,
+    pub logs: Vec<Log>,
+    /// Frames started so far, which numbers them. Not journaled: a frame
+    /// keeps its number when it is rolled back.
+    pub frames: usize,
+    /// Instructions run so far and when the transaction started, for the
+    /// limits of `EvmConfig`. Not journaled either.
+    pub instructions: u64,
+    #[cfg(feature = "std")]
+    pub started: Option<Instant>,
+    /// Why the transaction is cut short, a limit it ran into or an
+    /// unsupported precompile it called, which fails every frame left.
+    pub abort: Option<HaltReason>,
+    /// Accounts created by this transaction, which SELFDESTRUCT can still
+    /// delete under EIP-6780.
+    pub created: HashSet<U256>,
+    /// Accounts that ran SELFDESTRUCT, deleted when the transaction ends.
+    pub selfdestructs: HashSet<U256>,
+    /// Code hashes worked out by EXTCODEHASH, keyed like the balances and
+    /// dropped when the code changes.
+    pub code_hashes: HashMap<String, H256>,
+    /// Set by the prank cheatcodes, see `cheatcodes`.
+    pub prank: Option<Prank>,
+}
+
+/// Names a point between transactions that `EvmData::revert` winds the
+/// state back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SnapshotId(pub usize);
+
+/// Changes made by finished transactions while a snapshot is open, which
+/// would otherwise be dropped with the journal.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshots {
+    pub history: Vec<JournalEntry>,
+    /// Where each open snapshot starts in `history`, the id is the index.
+    pub open: Vec<usize>,
+}
+
+impl EvmData {
+    /// Marks the current point of the journal, for `revert_to`.
+    pub fn checkpoint(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// Undoes every change made since `checkpoint`, newest first.
+    pub fn revert_to(&mut self, checkpoint: usize) {
+        while self.journal.len() > checkpoint {
+            match self.journal.pop().unwrap() {
+                JournalEntry::StateChanged {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    self.put_state(address, key, previous);
+                }
+                JournalEntry::BalanceChanged { address, previous } => match previous {
+                    Some(balance) => {
+                        self.balances.insert(address, balance);
+                    }
+                    None => {
+                        self.balances.remove(&address);
+                    }
+                },
+                JournalEntry::NonceChanged { address, previous } => match previous {
+                    Some(nonce) => {
+                        self.nonces.insert(address, nonce);
+                    }
+                    None => {
+                        self.nonces.remove(&address);
+                    }
+                },
+                JournalEntry::CodeChanged { address, previous } => {
+                    self.substate.code_hashes.remove(&address);
+                    match previous {
+                        Some(code) => {
+                            self.codes.insert(address, code);
+                        }
+                        None => {
+                            self.codes.remove(&address);
+                        }
+                    }
+                }
+                JournalEntry::TransientStorageChanged {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    if previous.is_zero() {
+                        self.substate.transient_storage.remove(&(address, key));
+                    } else {
+                        self.substate
+                            .transient_storage
+                            .insert((address, key), previous);
+                    }
+                }
+                JournalEntry::StorageCleared(address) => {
+                    self.cleared_storage.remove(&address);
+                }
+                JournalEntry::AccountCreated(address) => {
+                    self.substate.created.remove(&address);
+                }
+                JournalEntry::SelfDestructed(address) => {
+                    self.substate.selfdestructs.remove(&address);
+                }
+                JournalEntry::AddressWarmed(address) => {
+                    self.substate.accessed_addresses.remove(&address);
+                }
+                JournalEntry::SlotWarmed(address, key) => {
+                    self.substate.accessed_storage.remove(&(address, key));
+                }
+                JournalEntry::RefundChanged { previous } => {
+                    self.substate.refund = previous;
+                }
+                JournalEntry::LogEmitted => {
+                    self.substate.logs.pop();
+                }
+            }
+        }
+    }
+
+    /// Ends the transaction: accounts that self-destructed are deleted,
+    /// storage included, its changes become final and the substate,
+    /// transient storage included, is cleared for the next one.
+    ///
+    /// Returns what the transaction changed, deletions included.
+    pub fn finish_transaction(&mut self) -> StateDiff {
+        for address in core::mem::take(&mut self.substate.selfdestructs) {
+            self.clear_storage(address);
+            let address = address.to_string();
+            let balance = self.balances.remove(&address);
+            let nonce = self.nonces.remove(&address);
+            let code = self.codes.remove(&address);
+            self.journal.extend([
+                JournalEntry::BalanceChanged {
+                    address: address.clone(),
+                    previous: balance,
+                },
+                JournalEntry::NonceChanged {
+                    address: address.clone(),
+                    previous: nonce,
+                },
+                JournalEntry::CodeChanged {
+                    address,
+                    previous: code,
+                },
+            ]);
+        }
+        let diff = self.state_diff();
+        if self.snapshots.open.is_empty() {
+            self.journal.clear();
+        } else {
+            // Only changes to accounts outlive the transaction
+            let changes = self.journal.drain(..).filter(|entry| {
+                matches!(
+                    entry,
+                    JournalEntry::StateChanged { .. }
+                        | JournalEntry::StorageCleared(_)
+                        | JournalEntry::BalanceChanged { .. }
+                        | JournalEntry::NonceChanged { .. }
+                        | JournalEntry::CodeChanged { .. }
+                )
+            });
+            self.snapshots.history.extend(changes);
+        }
+        self.substate = Substate::default();
+        diff
+    }
+
+    /// Opens a snapshot of the state as it is now, between transactions.
+    pub fn snapshot(&mut self) -> SnapshotId {
+        self.snapshots.open.push(self.snapshots.history.len());
+        SnapshotId(self.snapshots.open.len() - 1)
+    }
+
+    /// Winds the state back to `id`, which closes it and every snapshot
+    /// taken after it. False when `id` is not open.
+    pub fn revert(&mut self, id: SnapshotId) -> bool {
+        let Some(&start) = self.snapshots.open.get(id.0) else {
+            return false;
+        };
+        self.snapshots.open.truncate(id.0);
+        // Changes of a transaction still running go too
+        let mut changes = self.snapshots.history.split_off(start);
+        changes.append(&mut self.journal);
+        self.journal = changes;
+        self.revert_to(0);
+        self.substate = Substate::default();
+        true
+    }
+
+    /// Closes `id` and every snapshot taken after it, keeping the state as
+    /// it is. False when `id` is not open.
+    pub fn release(&mut self, id: SnapshotId) -> bool {
+        if id.0 >= self.snapshots.open.len() {
+            return false;
+        }
+        self.snapshots.open.truncate(id.0);
+        if self.snapshots.open.is_empty() {
+            self.snapshots.history.clear();
+        }
+        true
+    }
+
+    /// Sets or, with `None`, removes slot `key` of `address` in the state
+    /// map.
+    pub fn set_state(&mut self, address: U256, key: U256, value: Option<U256>) {
+        let address = address.to_string();
+        let previous = self.put_state(address.clone(), key, value);
+        self.journal.push(JournalEntry::StateChanged {
+            address,
+            key,
+            previous,
+        });
+    }
+
+    // Sets or removes a slot without journaling it, dropping the storage of
+    // an account once it has no slots left. Returns what the slot held.
+    fn put_state(&mut self, address: String, key: U256, value: Option<U256>) -> Option<U256> {
+        match value {
+            Some(value) => self.state.entry(address).or_default().insert(key, value),
+            None => {
+                let slots = self.state.get_mut(&address)?;
+                let previous = slots.remove(&key);
+                if slots.is_empty() {
+                    self.state.remove(&address);
+                }
+                previous
+            }
+        }
+    }
+
+    /// Wipes the storage of `address`, the slots the database holds for it
+    /// included.
+    pub fn clear_storage(&mut self, address: U256) {
+        let key = address.to_string();
+        let slots: Vec<U256> = self
+            .state
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .map(|(slot, _)| *slot)
+            .collect();
+        for slot in slots {
+            self.set_state(address, slot, None);
+        }
+        if self.cleared_storage.insert(key.clone()) {
+            self.journal.push(JournalEntry::StorageCleared(key));
+        }
+    }
+
+    /// Moves `value` from `from` to `to`. Returns false, changing nothing,
+    /// when `from` cannot afford it.
+    pub fn transfer(&mut self, from: U256, to: U256, value: U256) -> bool {
+        let balance = self.basic(from).unwrap_or_default().balance;
+        if balance < value {
+            return false;
+        }
+        if value.is_zero() || from == to {
+            return true;
+        }
+        self.set_balance(from, balance - value);
+        let received = self.basic(to).unwrap_or_default().balance;
+        self.set_balance(to, received + value);
+        true
+    }
+
+    pub fn set_balance(&mut self, address: U256, balance: U256) {
+        let address = address.to_string();
+        let previous = self.balances.insert(address.clone(), balance);
+        self.journal
+            .push(JournalEntry::BalanceChanged { address, previous });
+    }
+
+    pub fn set_nonce(&mut self, address: U256, nonce: u64) {
+        let address = address.to_string();
+        let previous = self.nonces.insert(address.clone(), nonce);
+        self.journal
+            .push(JournalEntry::NonceChanged { address, previous });
+    }
+
+    /// Sets the code of `address`; empty code removes it.
+    pub fn set_code(&mut self, address: U256, code: &[u8]) {
+        let address = address.to_string();
+        self.substate.code_hashes.remove(&address);
+        let previous = if code.is_empty() {
+            self.codes.remove(&address)
+        } else {
+            self.codes.insert(address.clone(), Bytes::from(code))
+        };
+        self.journal
+            .push(JournalEntry::CodeChanged { address, previous });
+    }
+
+    pub fn transient_storage(&self, address: U256, key: U256) -> U256 {
+        self.substate
+            .transient_storage
+            .get(&(address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_transient_storage(&mut self, address: U256, key: U256, value: U256) {
+        let previous = if value.is_zero() {
+            self.substate.transient_storage.remove(&(address, key))
+        } else {
+            self.substate
+                .transient_storage
+                .insert((address, key), value)
+        };
+        self.journal.push(JournalEntry::TransientStorageChanged {
+            address,
+            key,
+            previous: previous.unwrap_or_default(),
+        });
+    }
+
+    /// What EXTCODEHASH pushes: the hash of the code, the hash of empty code
+    /// for an account without any, and zero for an account that does not
+    /// exist or is empty (EIP-161).
+    pub fn code_hash(&mut self, address: U256) -> U256 {
+        let key = address.to_string();
+        if let Some(hash) = self.substate.code_hashes.get(&key) {
+            return U256::from_big_endian(hash.as_bytes());
+        }
+        let code = self.code(address).unwrap_or_default();
+        let hash = if !code.is_empty() {
+            self.keccak(&code)
+        } else if self
+            .basic(address)
+            .is_some_and(|account| account.nonce != 0 || !account.balance.is_zero())
+        {
+            EMPTY_CODE_HASH
+        } else {
+            return U256::zero();
+        };
+        self.substate.code_hashes.insert(key, hash);
+        U256::from_big_endian(hash.as_bytes())
+    }
+
+    pub fn mark_created(&mut self, address: U256) {
+        if self.substate.created.insert(address) {
+            self.journal.push(JournalEntry::AccountCreated(address));
+        }
+    }
+
+    /// Schedules `address` for deletion at the end of the transaction,
+    /// returns whether it was not already.
+    pub fn schedule_selfdestruct(&mut self, address: U256) -> bool {
+        let scheduled = self.substate.selfdestructs.insert(address);
+        if scheduled {
+            self.journal.push(JournalEntry::SelfDestructed(address));
+        }
+        scheduled
+    }
+
+    /// Adds `address` to the accessed addresses, returns whether it was cold.
+    pub fn access_address(&mut self, address: U256) -> bool {
+        let cold = self.substate.accessed_addresses.insert(address);
+        if cold {
+            self.journal.push(JournalEntry::AddressWarmed(address));
+        }
+        cold
+    }
+
+    /// Adds the slot to the accessed storage keys, returns whether it was cold.
+    pub fn access_slot(&mut self, address: U256, key: U256) -> bool {
+        let cold = self.substate.accessed_storage.insert((address, key));
+        if cold {
+            self.journal.push(JournalEntry::SlotWarmed(address, key));
+        }
+        cold
+    }
+
+    pub fn add_refund(&mut self, amount: i64) {
+        self.journal.push(JournalEntry::RefundChanged {
+            previous: self.substate.refund,
+        });
+        self.substate.refund += amount;
+    }
+
+    pub fn emit_log(&mut self, log: Log) {
+        self.substate.logs.push(log);
+        self.journal.push(JournalEntry::LogEmitted);
+    }
+}
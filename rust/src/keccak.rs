@@ -0,0 +1,90 @@
+//! Memoized Keccak-256. Contracts hash the same preimages over and over, the
+//! 64-byte key and slot of a mapping above all, and a run can keep the
+//! hashes it has worked out instead of redoing them.
+
+use alloc::collections::BTreeMap;
+
+use primitive_types::H256;
+
+use crate::prelude::*;
+use crate::trie::keccak;
+use crate::EvmData;
+
+/// Hashes by preimage, kept up to `capacity` entries, evicting the least
+/// recently used one when full.
+#[derive(Debug, Clone)]
+pub struct KeccakCache {
+    capacity: usize,
+    // Each hash with the tick it was last used at, and the preimages by tick
+    entries: HashMap<Vec<u8>, (H256, u64)>,
+    recency: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for KeccakCache {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl KeccakCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn hash(&mut self, input: &[u8]) -> H256 {
+        self.tick += 1;
+        if let Some((hash, used)) = self.entries.get_mut(input) {
+            self.hits += 1;
+            let preimage = self.recency.remove(used).unwrap();
+            self.recency.insert(self.tick, preimage);
+            *used = self.tick;
+            return *hash;
+        }
+
+        self.misses += 1;
+        let hash = keccak(input);
+        self.entries.insert(input.to_vec(), (hash, self.tick));
+        self.recency.insert(self.tick, input.to_vec());
+        while self.entries.len() > self.capacity {
+            let (_, oldest) = self.recency.pop_first().unwrap();
+            self.entries.remove(&oldest);
+        }
+        hash
+    }
+}
+
+impl EvmData {
+    /// Keccak-256 of `input`, through `keccak_cache` when there is one.
+    pub fn keccak(&mut self, input: &[u8]) -> H256 {
+        match &mut self.keccak_cache {
+            Some(cache) => cache.hash(input),
+            None => keccak(input),
+        }
+    }
+}
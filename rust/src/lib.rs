@@ -1,86 +1,562 @@
-use std::{collections::HashMap, str::FromStr};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{borrow::Cow, collections::BTreeMap, sync::Arc};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
-use funcs::is_valid_jump_dest;
 use primitive_types::{U256, U512};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+/// What the std prelude and collections give the core, from `alloc` and
+/// hashbrown when it is built without std.
+pub mod prelude {
+    pub use alloc::borrow::ToOwned;
+    pub use alloc::boxed::Box;
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::Vec;
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::{hash_map, HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub use std::collections::{hash_map, HashMap, HashSet};
+}
+
+pub mod abi;
+pub mod accesslist;
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod asm;
+#[cfg(feature = "std")]
+pub mod assertions;
+#[cfg(feature = "std")]
+pub mod block;
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod bundle;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod call;
+#[cfg(feature = "std")]
+pub mod calltrace;
+pub mod cheatcodes;
+pub mod config;
+pub mod console;
+pub mod coverage;
+#[cfg(feature = "std")]
+pub mod debugger;
+pub mod delegation;
+pub mod deploy;
+#[cfg(feature = "std")]
+pub mod dump;
+pub mod ecdsa;
+pub mod env;
+pub mod eof;
+#[cfg(feature = "std")]
+pub mod explain;
+pub mod fmtutil;
+#[cfg(feature = "std")]
+pub mod fork;
 mod funcs;
-use crate::funcs::{sar, sdiv, sgt, signextend, slt, smod};
-use sha3::{Digest, Keccak256};
+pub mod gas;
+#[cfg(feature = "std")]
+pub mod genesis;
+pub mod hexutil;
+pub mod host;
+pub mod i256;
+pub mod inspector;
+pub mod instruction;
+#[cfg(feature = "std")]
+pub mod interpreter;
+pub mod journal;
+pub mod keccak;
+#[cfg(feature = "std")]
+pub mod memo;
+#[cfg(feature = "std")]
+pub mod minimize;
+#[cfg(feature = "std")]
+pub mod node;
+pub mod opcodes;
+#[cfg(feature = "optimism")]
+pub mod optimism;
+#[cfg(feature = "std")]
+pub mod playground;
+pub mod precompiles;
+#[cfg(feature = "std")]
+pub mod prestate;
+#[cfg(feature = "std")]
+pub mod profiler;
+#[cfg(feature = "std")]
+pub mod replay;
+pub mod rlp;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "std")]
+pub mod scenario;
+#[cfg(feature = "std")]
+pub mod service;
+pub mod spec;
+pub mod stack;
+pub mod statediff;
+#[cfg(feature = "std")]
+pub mod t8n;
+#[cfg(feature = "std")]
+pub mod tracediff;
+#[cfg(feature = "std")]
+pub mod tracer;
+pub mod trie;
+#[cfg(feature = "std")]
+pub mod tui;
+pub mod tx;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod witness;
+
+use crate::funcs::{
+    blob_base_fee, byte, create2_address, create_address, exp, sar, sdiv, sgt, shl, shr,
+    signextend, slt, smod,
+};
+use crate::prelude::*;
+use analysis::{AnalysisCache, JumpDests};
+use config::EvmConfig;
+use deploy::{CreateReport, CODE_DEPOSIT_COST, MAX_CODE_SIZE, MAX_INITCODE_SIZE};
+use env::{Env, TxEnv};
+use eof::Container;
+use gas::{
+    copy_cost, init_code_cost, intrinsic_gas, sstore_cost, static_cost, Gas, CALL_STIPEND,
+    CALL_VALUE_COST, SSTORE_SENTRY,
+};
+use hexutil::Bytes;
+use host::Database;
+use inspector::{CallFrame, CallKind, CreateFrame, Inspector, Step};
+use instruction::{Control, Instruction, InstructionContext};
+use journal::{JournalEntry, Snapshots, Substate};
+use keccak::KeccakCache;
+use precompiles::{Precompile, PrecompileError};
+use spec::{is_opcode_enabled, ChainConfig, SpecId};
+use stack::{Stack, STACK_LIMIT};
+use statediff::StateDiff;
 
-#[derive(Debug, Clone)]
+/// The outcome of a run. Serializes with camelCase keys, words and bytes
+/// in 0x-prefixed hex.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct EvmResult {
+    #[serde(with = "hexutil::option_bytes")]
     pub value: Option<Vec<u8>>,
     pub stack: Vec<U256>,
     pub success: bool,
+    #[serde(with = "hexutil::bytes")]
     pub return_data: Vec<u8>,
+    pub creates: Vec<CreateReport>,
+    /// Gas used by the transaction, net of the refund.
+    pub gas_used: u64,
+    pub gas_refunded: u64,
+    /// Logs of the transaction, empty when it failed.
+    pub logs: Vec<Log>,
+    /// The frame that produced `return_data`, `None` when no frame returned
+    /// or reverted with data.
+    pub return_origin: Option<ReturnOrigin>,
+    /// Memory of the outermost frame as it ended, `MSIZE` bytes of it.
+    #[serde(with = "hexutil::bytes")]
+    pub memory: Vec<u8>,
+    /// Storage slots the transaction changed, by account and then by key.
+    /// Empty for the results of nested frames.
+    pub state_changes: BTreeMap<U256, BTreeMap<U256, StorageChange>>,
+    /// Accounts the transaction changed, in the shape of the `stateDiff` of
+    /// `trace_replayTransaction`. Empty for the results of nested frames.
+    pub state_diff: StateDiff,
+    /// Why the frame stopped.
+    pub halt_reason: Option<HaltReason>,
+}
+
+/// How a frame came to an end, the first three with success.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HaltReason {
+    /// STOP, or running past the end of the code.
+    Stop,
+    Return,
+    SelfDestruct,
+    Revert,
+    /// A jump to a destination that is not a JUMPDEST.
+    InvalidJump,
+    StackUnderflow,
+    /// A push onto a stack already holding 1024 words.
+    StackOverflow,
+    /// A state change attempted in a static context.
+    StaticModeViolation,
+    OutOfGas,
+    /// INVALID, or an opcode that is unknown or not enabled in the spec.
+    InvalidOpcode,
+    /// RETURNDATACOPY of bytes past the end of the return data.
+    ReturnDataOutOfBounds,
+    /// A precompile rejected its input.
+    PrecompileFailure,
+    /// The transaction ran into `EvmConfig::max_instructions`.
+    InstructionLimit,
+    /// The transaction ran into `EvmConfig::max_duration`.
+    TimeLimit,
+    /// A call to a precompile of the fork that is not implemented here. It
+    /// fails the whole transaction rather than pretend the call returned
+    /// nothing.
+    UnsupportedPrecompile,
+}
+
+impl EvmResult {
+    /// The decoded revert payload, when the frame ended with REVERT and
+    /// returned at least a selector.
+    pub fn revert_reason(&self) -> Option<abi::RevertReason> {
+        if self.halt_reason != Some(HaltReason::Revert) {
+            return None;
+        }
+        abi::decode_revert(&self.return_data)
+    }
+
+    /// The result as `evmrs run --json` prints it: serialized, with the
+    /// revert reason decoded.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut output = serde_json::to_value(self).unwrap();
+        if let Some(reason) = self.revert_reason() {
+            output["revertReason"] = serde_json::json!(reason.to_string());
+        }
+        output
+    }
+}
+
+/// The value of a storage slot before and after a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageChange {
+    pub before: U256,
+    pub after: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReturnKind {
+    Return,
+    Revert,
+}
+
+/// Where return data came from: the frame that ended with RETURN or REVERT
+/// and, when it passed on what a call of its own returned, that call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReturnOrigin {
+    /// Frames of a transaction are numbered in the order they start, from 0
+    /// for the outermost one.
+    pub frame: usize,
+    pub depth: usize,
+    #[serde(with = "hexutil::address")]
+    pub address: U256,
+    pub kind: ReturnKind,
+    /// Set when the data is byte for byte what the last call of the frame
+    /// returned, as with proxies bubbling up results and revert reasons.
+    pub forwarded_from: Option<Box<ReturnOrigin>>,
+}
+
+impl ReturnOrigin {
+    /// The frame the data was first returned from.
+    pub fn source(&self) -> &ReturnOrigin {
+        match &self.forwarded_from {
+            Some(origin) => origin.source(),
+            None => self,
+        }
+    }
+}
+
+impl fmt::Display for ReturnOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ReturnKind::Return => "RETURN",
+            ReturnKind::Revert => "REVERT",
+        };
+        write!(
+            f,
+            "{} of frame {} at depth {} ({:#x})",
+            kind, self.frame, self.depth, self.address
+        )?;
+        if let Some(origin) = &self.forwarded_from {
+            write!(f, ", forwarded from {}", origin)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// An event emitted by one of the LOG instructions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Log {
+    #[serde(with = "hexutil::address")]
+    pub address: U256,
+    pub topics: Vec<U256>,
+    #[serde(with = "hexutil::bytes")]
+    pub data: Vec<u8>,
+}
+
+/// The block a run sees. Fields are hex, with or without a `0x` prefix,
+/// and parsed as the context is deserialized.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct EvmContext {
-    coinbase: Option<String>,
-    basefee: Option<String>,
-    timestamp: Option<String>,
-    number: Option<String>,
-    difficulty: Option<String>,
-    gaslimit: Option<String>,
-    chainid: Option<String>,
+    #[serde(default, with = "hexutil::option_address")]
+    coinbase: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    basefee: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    timestamp: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    number: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    difficulty: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    gaslimit: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    chainid: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    excess_blob_gas: Option<U256>,
+    /// What OP Stack chains price the L1 data fee with.
+    #[cfg(feature = "optimism")]
+    #[serde(default, with = "hexutil::option")]
+    l1_base_fee: Option<U256>,
+    #[cfg(feature = "optimism")]
+    #[serde(default, with = "hexutil::option")]
+    l1_blob_base_fee: Option<U256>,
+    #[cfg(feature = "optimism")]
+    #[serde(default, with = "hexutil::option")]
+    base_fee_scalar: Option<U256>,
+    #[cfg(feature = "optimism")]
+    #[serde(default, with = "hexutil::option")]
+    blob_base_fee_scalar: Option<U256>,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+/// The transaction a run sees, hex like `EvmContext`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct TxData {
-    data: Option<String>,
-    from: Option<String>,
-    to: Option<String>,
-    gasprice: Option<String>,
-    origin: Option<String>,
-    value: Option<String>,
+    #[serde(default, with = "hexutil::option")]
+    data: Option<Bytes>,
+    #[serde(default, with = "hexutil::option_address")]
+    from: Option<U256>,
+    #[serde(default, with = "hexutil::option_address")]
+    to: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    gasprice: Option<U256>,
+    /// EIP-1559 fees. With a max fee, GASPRICE reads the effective gas
+    /// price in the block instead of `gasprice`.
+    #[serde(default, with = "hexutil::option")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(default, with = "hexutil::option_address")]
+    origin: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    value: Option<U256>,
+    #[serde(default, with = "hexutil::option")]
+    gas: Option<u64>,
+    /// Checked against the sender when the transaction runs in a block,
+    /// which takes its next nonce without it.
+    #[serde(default, with = "hexutil::option")]
+    nonce: Option<u64>,
+    #[serde(default, with = "hexutil::words")]
+    blob_versioned_hashes: Vec<U256>,
+    access_list: Option<Vec<AccessListItem>>,
+}
+
+/// An EIP-2930 access list entry: an account and storage keys of it that
+/// the transaction declares up front, paying for them to start out warm.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct AccessListItem {
+    #[serde(with = "hexutil::address")]
+    pub address: U256,
+    #[serde(with = "hexutil::words")]
+    pub storage_keys: Vec<U256>,
 }
 
+/// Memory shared by all the frames of a transaction. Each frame gets the
+/// part of the buffer past where its caller's ends, so a nested call reuses
+/// the allocation instead of making its own, and leaving the frame gives the
+/// space back.
 pub struct EvmMemory {
-    pub memory: Vec<u8>,
+    buffer: Vec<u8>,
+    // Where the running frame's memory starts in the buffer
+    checkpoint: usize,
+    // Checkpoints and sizes of the frames below the running one
+    frames: Vec<(usize, usize)>,
+    /// Active size of the running frame's memory, word aligned.
     pub size: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct EvmData {
     pub context: Option<EvmContext>,
     pub tx_data: Option<TxData>,
-    pub state: HashMap<String, String>,
+    /// Storage by account, keyed like the balances, then by slot.
+    pub state: HashMap<String, HashMap<U256, U256>>,
+    /// Accounts whose storage was wiped, keyed like the balances: the
+    /// database is no longer asked for their slots.
+    pub cleared_storage: HashSet<String>,
     pub balances: HashMap<String, U256>,
+    pub nonces: HashMap<String, u64>,
+    /// Account code, keyed like the balances.
+    pub codes: HashMap<String, Bytes>,
+    pub config: ChainConfig,
+    pub evm_config: EvmConfig,
+    /// Backing store for accounts, storage and block hashes the maps above
+    /// do not hold.
+    pub database: Option<Arc<dyn Database>>,
+    pub substate: Substate,
+    pub journal: Vec<JournalEntry>,
+    /// Changes kept across transactions for `snapshot` and `revert`.
+    pub snapshots: Snapshots,
+    /// Jump destinations of the code run so far, kept across transactions.
+    pub analysis: AnalysisCache,
+    /// `context` and `tx_data` parsed, set up at the start of each run.
+    pub env: Env,
+    /// Hashes worked out by SHA3, EXTCODEHASH and CREATE2, when memoized.
+    pub keccak_cache: Option<KeccakCache>,
+    /// Handlers that run in place of the interpreter's for their opcode.
+    pub instructions: BTreeMap<u8, Instruction>,
+    /// Precompiles by address, on top of the ones of the fork.
+    pub precompiles: BTreeMap<U256, Precompile>,
+}
+
+impl Default for EvmMemory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EvmMemory {
+    /// Memory never grows past this many bytes, whatever the gas: paying for
+    /// it would take more than any block holds.
+    pub const MAX_SIZE: usize = u32::MAX as usize;
+
     pub fn new() -> Self {
         Self {
-            memory: vec![0; 1000],
+            buffer: Vec::with_capacity(4 * 1024),
+            checkpoint: 0,
+            frames: vec![],
             size: 0,
         }
     }
 
+    /// Starts the memory of a nested frame, empty, after the running one's.
+    pub fn enter_frame(&mut self) {
+        self.frames.push((self.checkpoint, self.size));
+        self.checkpoint = self.buffer.len();
+        self.size = 0;
+    }
+
+    /// Drops the running frame's memory and goes back to its caller's.
+    pub fn exit_frame(&mut self) {
+        self.buffer.truncate(self.checkpoint);
+        if let Some((checkpoint, size)) = self.frames.pop() {
+            self.checkpoint = checkpoint;
+            self.size = size;
+        }
+    }
+
+    /// The running frame's memory, `size` bytes of it.
+    pub fn data(&self) -> &[u8] {
+        &self.buffer[self.checkpoint..]
+    }
+
+    fn frame_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.checkpoint..]
+    }
+
     pub fn read_u8s(&mut self, offset: usize, size: usize) -> Vec<u8> {
-        let mut res = Vec::new();
-        for i in 0..size {
-            res.push(self.memory[offset + i]);
+        if size == 0 {
+            return vec![];
         }
-        let end = offset + 31;
-        self.size = self.size.max(end + 32 - (end % 32));
-        res
+        self.expand(offset, size);
+        self.data()[offset..offset + size].to_vec()
     }
 
     pub fn read_u256(&mut self, offset: usize, size: usize) -> U256 {
-        let mut res = U256::zero();
+        self.expand(offset, size);
+        U256::from_big_endian(&self.data()[offset..offset + size])
+    }
+
+    pub fn write_u8(&mut self, offset: usize, data: u8) {
+        self.expand(offset, 1);
+        self.frame_mut()[offset] = data;
+    }
+
+    // Writes `size` bytes of `source` from `source_offset` on, zeros past its
+    // end, as the copy opcodes do. The whole destination counts as touched.
+    pub fn write_slice(
+        &mut self,
+        dest_offset: usize,
+        source: &[u8],
+        source_offset: usize,
+        size: usize,
+    ) {
+        self.expand(dest_offset, size);
+        let memory = self.frame_mut();
         for i in 0..size {
-            res = res << 8;
-            res = res + U256::from(self.memory[offset + i]);
+            let byte = source_offset
+                .checked_add(i)
+                .and_then(|index| source.get(index))
+                .copied()
+                .unwrap_or(0);
+            memory[dest_offset + i] = byte;
         }
-        let end = offset + 31;
-        self.size = self.size.max(end + 32 - (end % 32));
-        res
     }
 
-    pub fn write_u8(&mut self, offset: usize, data: u8) {
-        self.memory[offset] = data;
-        self.size = self.size.max(offset + 32 - (offset % 32));
+    // Copies `size` bytes of `source` from `source_offset` on, as CALLDATACOPY,
+    // CODECOPY and EXTCODECOPY do: whatever lies past the end of `source`,
+    // however far past, is copied as zeros.
+    pub fn copy_from(
+        &mut self,
+        dest_offset: usize,
+        source: &[u8],
+        source_offset: U256,
+        size: usize,
+    ) {
+        let source_offset = source_offset.min(U256::from(source.len())).as_usize();
+        self.write_slice(dest_offset, source, source_offset, size);
+    }
+
+    /// An access of `size` bytes at `offset` as usizes, `None` when it would
+    /// grow the memory past `MAX_SIZE`. An empty access touches nothing, so
+    /// its offset can be anything and is taken as zero.
+    pub fn range(offset: U256, size: U256) -> Option<(usize, usize)> {
+        if size.is_zero() {
+            return Some((0, 0));
+        }
+        let (end, overflow) = offset.overflowing_add(size);
+        if overflow || end > U256::from(Self::MAX_SIZE) {
+            return None;
+        }
+        Some((offset.as_usize(), size.as_usize()))
+    }
+
+    /// The active size after an access of `size` bytes at `offset`: up to
+    /// the end of the highest word it touches, unchanged when it is empty.
+    pub fn size_after(&self, offset: usize, size: usize) -> usize {
+        if size == 0 {
+            return self.size;
+        }
+        self.size.max((offset + size).div_ceil(32) * 32)
+    }
+
+    // Grows the active memory to cover [offset, offset + size), word aligned.
+    pub fn expand(&mut self, offset: usize, size: usize) {
+        self.size = self.size_after(offset, size);
+        // The buffer ends where the running frame's memory does
+        self.buffer.resize(self.checkpoint + self.size, 0);
+    }
+
+    // Copies within memory, the source is read in full before anything is written.
+    pub fn copy(&mut self, dest_offset: usize, source_offset: usize, size: usize) {
+        if size == 0 {
+            return;
+        }
+        self.expand(source_offset, size);
+        self.expand(dest_offset, size);
+        self.frame_mut()
+            .copy_within(source_offset..source_offset + size, dest_offset);
     }
 
     pub fn msize(&self) -> U256 {
@@ -88,71 +564,616 @@ impl EvmMemory {
     }
 }
 
-pub fn evm(_code: impl AsRef<[u8]>, data: &mut EvmData, writable: bool) -> EvmResult {
-    let mut stack: Vec<U256> = Vec::new();
+fn out_of_gas(stack: &Stack, gas: &Gas) -> EvmResult {
+    halt(stack, gas, HaltReason::OutOfGas)
+}
+
+// Fails the frame for `reason`. Like running out of gas, it uses up all the
+// gas of the frame and undoes what it did, but the caller carries on.
+fn halt(stack: &Stack, gas: &Gas, reason: HaltReason) -> EvmResult {
+    EvmResult {
+        value: None,
+        stack: stack.to_vec(),
+        success: false,
+        return_data: vec![],
+        creates: vec![],
+        gas_used: gas.used(),
+        gas_refunded: 0,
+        logs: vec![],
+        return_origin: None,
+        memory: vec![],
+        state_changes: BTreeMap::new(),
+        state_diff: StateDiff::default(),
+        halt_reason: Some(reason),
+    }
+}
+
+// Charges for memory over `size` bytes at `offset` and only then grows it,
+// so a frame never holds more memory than it paid for. Returns the access as
+// usizes, `None` when out of gas.
+fn grow_memory(
+    memory: &mut EvmMemory,
+    gas: &mut Gas,
+    offset: U256,
+    size: U256,
+) -> Option<(usize, usize)> {
+    let (offset, size) = EvmMemory::range(offset, size)?;
+    if !gas.charge_memory(memory.size_after(offset, size)) {
+        return None;
+    }
+    memory.expand(offset, size);
+    Some((offset, size))
+}
+
+// Grows memory over the argument and return regions of a call and charges
+// for it, before the gas passed on to the callee is worked out. The return
+// region is paid for whatever the callee ends up returning.
+fn expand_call_memory(
+    memory: &mut EvmMemory,
+    gas: &mut Gas,
+    args: (U256, U256),
+    ret: (U256, U256),
+) -> Option<((usize, usize), (usize, usize))> {
+    let args = grow_memory(memory, gas, args.0, args.1)?;
+    let ret = grow_memory(memory, gas, ret.0, ret.1)?;
+    Some((args, ret))
+}
+
+// The code a call to `address` runs and what following a delegation costs
+// on top of the call. From Prague, an account with a delegation designator
+// runs the code of its delegate (EIP-7702), which is accessed like any
+// other account. The designator is followed once, and a precompile that is
+// delegated to runs no code.
+fn call_code(data: &mut EvmData, address: U256) -> (Vec<u8>, u64) {
+    let spec = data.config.spec;
+    if is_precompile(data, address) {
+        return (vec![], 0);
+    }
+    let code = data.code(address).unwrap_or_default();
+    let delegate = delegation::delegate(&code).filter(|_| spec.is_enabled_in(SpecId::Prague));
+    let Some(delegate) = delegate else {
+        return (code, 0);
+    };
+    let cold = data.access_address(delegate);
+    let schedule = &data.config.gas;
+    let cost = schedule.account_access(cold, schedule.call);
+    if is_precompile(data, delegate) {
+        return (vec![], cost);
+    }
+    (data.code(delegate).unwrap_or_default(), cost)
+}
+
+// The precompiles of the fork and the chain, and those registered on `data`.
+fn is_precompile(data: &EvmData, address: U256) -> bool {
+    is_cheatcode(data, address)
+        || data.precompiles.contains_key(&address)
+        || precompiles::active_addresses(data.config.spec).contains(&address)
+        || precompiles::chain_addresses(data.config.chain).contains(&address)
+}
+
+fn is_cheatcode(data: &EvmData, address: U256) -> bool {
+    data.evm_config.cheatcodes && address == cheatcodes::CHEATCODE_ADDRESS
+}
+
+// Runs a precompile on behalf of a CALL from a frame at `depth` that
+// reserved `limit` gas for it.
+#[allow(clippy::too_many_arguments)]
+fn call_precompile(
+    data: &mut EvmData,
+    depth: usize,
+    address: U256,
+    gas: &mut Gas,
+    limit: Option<u64>,
+    memory: &mut EvmMemory,
+    args_offset: usize,
+    args_size: usize,
+    ret_offset: usize,
+    ret_size: usize,
+) -> EvmResult {
+    let input = memory.read_u8s(args_offset, args_size);
+    // A registered precompile works out its cost as it runs
+    let (cost, output) = match data.precompiles.get(&address) {
+        _ if is_cheatcode(data, address) => (
+            0,
+            cheatcodes::call(data, depth, &input).map_err(PrecompileError::Custom),
+        ),
+        Some(precompile) => match precompile(&input, limit) {
+            Ok(output) => (output.gas_used, Ok(output.bytes)),
+            Err(error) => (limit.unwrap_or_default(), Err(error)),
+        },
+        None => match precompiles::run(address, &input) {
+            Some(output) => (precompiles::gas_cost(address, &input), output),
+            None => (limit.unwrap_or_default(), Err(PrecompileError::Unsupported)),
+        },
+    };
+    let mut result = EvmResult {
+        value: None,
+        stack: vec![],
+        success: false,
+        return_data: vec![],
+        creates: vec![],
+        gas_used: cost,
+        gas_refunded: 0,
+        logs: vec![],
+        return_origin: None,
+        memory: vec![],
+        state_changes: BTreeMap::new(),
+        state_diff: StateDiff::default(),
+        halt_reason: None,
+    };
+    if let Some(limit) = limit.filter(|limit| cost > *limit) {
+        gas.settle(Some(limit), limit);
+        result.gas_used = limit;
+        result.halt_reason = Some(HaltReason::OutOfGas);
+        return result;
+    }
+    gas.settle(limit, cost);
+
+    match output {
+        Ok(output) => {
+            let copied = output.len().min(ret_size);
+            memory.write_slice(ret_offset, &output, 0, copied);
+            result.success = true;
+            result.value = Some(output.clone());
+            result.return_data = output;
+            result.halt_reason = Some(HaltReason::Return);
+        }
+        Err(PrecompileError::OutOfGas) => result.halt_reason = Some(HaltReason::OutOfGas),
+        Err(PrecompileError::Unsupported) => {
+            data.substate.abort = Some(HaltReason::UnsupportedPrecompile);
+            result.halt_reason = Some(HaltReason::UnsupportedPrecompile);
+        }
+        Err(_) => result.halt_reason = Some(HaltReason::PrecompileFailure),
+    }
+    result
+}
+
+pub fn evm(code: impl AsRef<[u8]>, data: &mut EvmData, writable: bool) -> EvmResult {
+    evm_with_inspector(code, data, writable, &mut ())
+}
+
+pub fn evm_with_inspector(
+    code: impl AsRef<[u8]>,
+    data: &mut EvmData,
+    writable: bool,
+    inspector: &mut dyn Inspector,
+) -> EvmResult {
+    data.env = Env::new(data.context.as_ref(), data.tx_data.as_ref());
+    start_clock(&mut data.substate);
+    let tx_data = data.tx_data.clone().unwrap_or_default();
+    let intrinsic_gas = intrinsic_gas(&tx_data, data.config.spec);
+    let gas_limit = match tx_data.gas {
+        Some(gas) if data.evm_config.gas_metering => {
+            if gas < intrinsic_gas {
+                return EvmResult {
+                    value: None,
+                    stack: vec![],
+                    success: false,
+                    return_data: vec![],
+                    creates: vec![],
+                    gas_used: gas,
+                    gas_refunded: 0,
+                    logs: vec![],
+                    return_origin: None,
+                    memory: vec![],
+                    state_changes: BTreeMap::new(),
+                    state_diff: StateDiff::default(),
+                    halt_reason: Some(HaltReason::OutOfGas),
+                };
+            }
+            Some(gas - intrinsic_gas)
+        }
+        _ => None,
+    };
+
+    // EIP-2929: the sender, the recipient and the precompiles start out warm
+    if data.config.spec.is_enabled_in(SpecId::Berlin) {
+        for address in [tx_data.origin, tx_data.from, tx_data.to]
+            .into_iter()
+            .flatten()
+        {
+            data.access_address(address);
+        }
+        for address in precompiles::active_addresses(data.config.spec) {
+            data.access_address(address);
+        }
+        let mut registered: Vec<U256> = data.precompiles.keys().copied().collect();
+        registered.extend(precompiles::chain_addresses(data.config.chain));
+        for address in registered {
+            data.access_address(address);
+        }
+        // EIP-3651: the coinbase too, from Shanghai
+        if data.config.spec.is_enabled_in(SpecId::Shanghai) {
+            data.access_address(data.env.block.coinbase);
+        }
+        // EIP-2930
+        for item in tx_data.access_list.iter().flatten() {
+            data.access_address(item.address);
+            for key in &item.storage_keys {
+                data.access_slot(item.address, *key);
+            }
+        }
+    }
+
+    // EIP-7702: a transaction to an account with a delegation runs the code
+    // of the delegate, which starts out warm
+    let delegate = delegation::delegate(code.as_ref())
+        .filter(|_| data.config.spec.is_enabled_in(SpecId::Prague));
+    let code = match delegate {
+        Some(delegate) => {
+            data.access_address(delegate);
+            Cow::Owned(data.code(delegate).unwrap_or_default())
+        }
+        None => Cow::Borrowed(code.as_ref()),
+    };
+
+    let input = tx_data.data.unwrap_or_default().0;
+    let call = CallFrame {
+        kind: if writable {
+            CallKind::Call
+        } else {
+            CallKind::StaticCall
+        },
+        depth: 1,
+        caller: tx_data.from.unwrap_or_default(),
+        address: tx_data.to.unwrap_or_default(),
+        code_address: tx_data.to.unwrap_or_default(),
+        value: tx_data.value.unwrap_or_default(),
+        input: &input,
+        gas: gas_limit.map(|gas| gas + intrinsic_gas),
+    };
+    inspector.call_start(&call);
+    console::inspect(inspector, &call);
+
+    let mut result = execute(
+        &code,
+        &input,
+        data,
+        writable,
+        inspector,
+        1,
+        gas_limit,
+        &mut EvmMemory::new(),
+    );
+    result.gas_used += intrinsic_gas;
+
+    // Refunds are capped to a share of the gas used (EIP-3529)
+    let refund = data.substate.refund.max(0) as u64;
+    result.gas_refunded = refund.min(result.gas_used / data.config.gas.max_refund_quotient);
+    result.gas_used -= result.gas_refunded;
+
+    // Logs of failed frames were dropped when they were rolled back
+    result.logs = core::mem::take(&mut data.substate.logs);
+    // Every slot written has its value from before the transaction recorded
+    for ((address, key), before) in core::mem::take(&mut data.substate.original_storage) {
+        let after = data.storage(address, key);
+        if after != before {
+            result
+                .state_changes
+                .entry(address)
+                .or_default()
+                .insert(key, StorageChange { before, after });
+        }
+    }
+    result.state_diff = data.finish_transaction();
+    inspector.call_end(&call, &result);
+    result
+}
+
+// When the transaction starts, for the time limit. Without std, and on
+// wasm32-unknown-unknown, there is no clock and the limit is not enforced.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+fn start_clock(substate: &mut Substate) {
+    substate.started = Some(Instant::now());
+}
+
+#[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+fn start_clock(_substate: &mut Substate) {}
+
+#[cfg(feature = "std")]
+fn elapsed(substate: &Substate) -> Option<core::time::Duration> {
+    substate.started.map(|started| started.elapsed())
+}
+
+#[cfg(not(feature = "std"))]
+fn elapsed(_substate: &Substate) -> Option<core::time::Duration> {
+    None
+}
+
+// Counts the instruction about to run against the limits of the
+// transaction.
+fn limit_reached(data: &mut EvmData) -> Option<HaltReason> {
+    let config = &data.evm_config;
+    let substate = &mut data.substate;
+    substate.instructions += 1;
+    if config
+        .max_instructions
+        .is_some_and(|max| substate.instructions > max)
+    {
+        substate.abort = Some(HaltReason::InstructionLimit);
+    }
+    // Reading the clock every instruction would cost more than the check
+    if substate.instructions % 1024 == 1 {
+        let elapsed = elapsed(substate);
+        if config
+            .max_duration
+            .zip(elapsed)
+            .is_some_and(|(max, elapsed)| elapsed > max)
+        {
+            substate.abort = Some(HaltReason::TimeLimit);
+        }
+    }
+    substate.abort
+}
+
+fn is_jump_dest(jump_dests: &JumpDests, dest: U256) -> bool {
+    dest <= U256::from(usize::MAX) && jump_dests.is_valid(dest.as_usize())
+}
+
+// The `size` byte big-endian immediate at `pc`, if the code holds it.
+fn immediate(code: &[u8], pc: usize, size: usize) -> Option<usize> {
+    code.get(pc..pc + size).map(|bytes| {
+        bytes
+            .iter()
+            .fold(0, |value, byte| value << 8 | *byte as usize)
+    })
+}
+
+// The pc `offset` bytes on from `pc`, if it falls inside `code`.
+fn relative_jump(code: &[u8], pc: usize, offset: usize) -> Option<usize> {
+    pc.checked_add_signed(offset as u16 as i16 as isize)
+        .filter(|dest| *dest < code.len())
+}
+
+// Address of the account whose code is running in the current frame.
+fn current_address(data: &EvmData) -> U256 {
+    data.env.tx.address
+}
+
+// An account exists once it has a balance, a nonce or code.
+fn account_exists(data: &EvmData, address: U256) -> bool {
+    data.basic(address).is_some() || data.code(address).is_some_and(|code| !code.is_empty())
+}
+
+// No code, nonce or balance (EIP-161).
+fn is_empty_account(data: &EvmData, address: U256) -> bool {
+    let account = data.basic(address).unwrap_or_default();
+    account.nonce == 0
+        && account.balance.is_zero()
+        && data.code(address).unwrap_or_default().is_empty()
+}
+
+/// How many frames nest below the one of the transaction, which is at depth
+/// 1: a CALL or CREATE from a frame deeper than this fails without running,
+/// keeping its gas.
+pub const CALL_DEPTH_LIMIT: usize = 1024;
+
+/// The stack a thread needs to run frames nested to [`CALL_DEPTH_LIMIT`],
+/// which is well beyond what threads get by default. Threads that run
+/// transactions should be spawned with it.
+pub const EXECUTION_STACK_SIZE: usize = 128 << 20;
+
+// Runs one call frame with `gas_limit` gas, unmetered without one. A frame
+// that fails has everything it changed rolled back, nested frames included;
+// a frame that succeeds leaves its changes to stand or fall with its caller.
+#[allow(clippy::too_many_arguments)]
+fn execute(
+    code: &[u8],
+    input: &[u8],
+    data: &mut EvmData,
+    writable: bool,
+    inspector: &mut dyn Inspector,
+    depth: usize,
+    gas_limit: Option<u64>,
+    memory: &mut EvmMemory,
+) -> EvmResult {
+    let checkpoint = data.checkpoint();
+    let frame = data.substate.frames;
+    data.substate.frames += 1;
+    memory.enter_frame();
+    let mut result = interpret(
+        code, input, data, writable, inspector, depth, frame, gas_limit, memory,
+    );
+    if depth == 1 {
+        result.memory = memory.data().to_vec();
+    }
+    memory.exit_frame();
+    // A limit hit or an unsupported precompile called in a nested frame
+    // fails its callers too, even those with nothing left to run
+    if let Some(reason) = data.substate.abort {
+        result.success = false;
+        result.value = None;
+        result.halt_reason = Some(reason);
+    }
+    if !result.success {
+        data.revert_to(checkpoint);
+        // Only REVERT hands back the gas it did not use
+        if result.value.is_none() {
+            result.gas_used = gas_limit.unwrap_or(result.gas_used);
+        }
+    }
+    result
+}
+
+// Stack accesses in `interpret`: a failed one halts the frame with what is
+// left on the stack.
+macro_rules! check {
+    ($stack:ident, $gas:ident, $access:expr) => {
+        match $access {
+            Ok(value) => value,
+            Err(error) => return halt(&$stack, &$gas, HaltReason::from(error)),
+        }
+    };
+}
+
+macro_rules! pop {
+    ($stack:ident, $gas:ident) => {
+        check!($stack, $gas, $stack.pop())
+    };
+}
+
+macro_rules! push {
+    ($stack:ident, $gas:ident, $value:expr $(,)?) => {{
+        let value = $value;
+        check!($stack, $gas, $stack.push(value))
+    }};
+}
+
+#[allow(clippy::too_many_arguments)]
+fn interpret(
+    code: &[u8],
+    input: &[u8],
+    data: &mut EvmData,
+    writable: bool,
+    inspector: &mut dyn Inspector,
+    depth: usize,
+    frame: usize,
+    gas_limit: Option<u64>,
+    memory: &mut EvmMemory,
+) -> EvmResult {
+    let mut gas = Gas::new(gas_limit);
+    let mut stack = Stack::new();
     let mut pc = 0;
-    let mut memory = EvmMemory::new();
     let mut return_data = vec![];
+    let mut return_origin: Option<ReturnOrigin> = None;
+    let mut creates = vec![];
+    let jump_dests = data.analysis.jump_dests(code);
 
-    let code = _code.as_ref();
+    // From Osaka, code behind the EOF magic runs one section at a time,
+    // starting with the first
+    let container = if eof::is_eof(code) && data.config.spec.is_enabled_in(SpecId::Osaka) {
+        match Container::parse(code) {
+            Ok(container) => Some(container),
+            Err(_) => return halt(&stack, &gas, HaltReason::InvalidOpcode),
+        }
+    } else {
+        None
+    };
+    let mut code = container
+        .as_ref()
+        .map_or(code, |container| container.code_sections[0]);
+    // Where each CALLF returns to: the section and the pc after it
+    let mut return_stack: Vec<(usize, usize)> = vec![];
+    let mut section = 0;
 
     while pc < code.len() {
+        // Memory grown by the previous instruction
+        if !gas.charge_memory(memory.size) {
+            return out_of_gas(&stack, &gas);
+        }
+        if let Some(reason) = limit_reached(data) {
+            return halt(&stack, &gas, reason);
+        }
+
         let opcode = code[pc];
+        inspector.step(&Step {
+            code,
+            pc,
+            opcode,
+            depth,
+            gas_used: gas.used(),
+            stack: stack.as_slice(),
+            memory,
+            data,
+        });
         pc += 1;
 
+        if let Some(&handler) = data.instructions.get(&opcode) {
+            let mut context = InstructionContext {
+                code,
+                pc,
+                depth,
+                writable,
+                stack: &mut stack,
+                memory,
+                gas: &mut gas,
+                data,
+            };
+            let control = handler(&mut context);
+            pc = context.pc;
+            match control {
+                Control::Continue => continue,
+                Control::Stop => break,
+                Control::Halt(reason) => return halt(&stack, &gas, reason),
+            }
+        }
+
+        let allowed = match container {
+            Some(_) => !eof::is_legacy_only(opcode),
+            None => !eof::is_eof_only(opcode),
+        };
+        if !is_opcode_enabled(data.config.spec, opcode) || !allowed {
+            if !data.evm_config.strict_halts {
+                continue;
+            }
+            return halt(&stack, &gas, HaltReason::InvalidOpcode);
+        }
+
+        if !gas.charge(static_cost(opcode)) {
+            return out_of_gas(&stack, &gas);
+        }
+
         if opcode == 0x00 {
             // STOP
             break;
         } else if opcode == 0x01 {
             // ADDvalue
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a.overflowing_add(b).0);
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(stack, gas, a.overflowing_add(b).0);
         } else if opcode == 0x02 {
             // MUL
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a.overflowing_mul(b).0);
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(stack, gas, a.overflowing_mul(b).0);
         } else if opcode == 0x03 {
             // SUB
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a.overflowing_sub(b).0);
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(stack, gas, a.overflowing_sub(b).0);
         } else if opcode == 0x04 {
             // DIV
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a.checked_div(b).or_else(|| Some(U256::zero())).unwrap());
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(
+                stack,
+                gas,
+                a.checked_div(b).or_else(|| Some(U256::zero())).unwrap()
+            );
         } else if opcode == 0x05 {
             // SDIV
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
             let value = sdiv(a, b);
-            stack.push(value);
+            push!(stack, gas, value);
         } else if opcode == 0x06 {
             // MOD
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a.checked_rem(b).or_else(|| Some(U256::zero())).unwrap());
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(
+                stack,
+                gas,
+                a.checked_rem(b).or_else(|| Some(U256::zero())).unwrap()
+            );
         } else if opcode == 0x07 {
             // SMOD
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
             let value = smod(a, b);
-            stack.push(value);
+            push!(stack, gas, value);
         } else if opcode == 0x08 {
             // ADDMOD
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            let n: U256 = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            let n: U256 = pop!(stack, gas);
             let sum = a.overflowing_add(b).0;
             let res = sum.checked_rem(n).or_else(|| Some(U256::zero())).unwrap();
-            stack.push(res);
+            push!(stack, gas, res);
         } else if opcode == 0x09 {
             // MULMOD
-            let a: U512 = stack.pop().unwrap().into();
-            let b: U512 = stack.pop().unwrap().into();
-            let n: U512 = stack.pop().unwrap().into();
+            let a: U512 = pop!(stack, gas).into();
+            let b: U512 = pop!(stack, gas).into();
+            let n: U512 = pop!(stack, gas).into();
 
             let res: U256 = if n == U512::zero() {
                 U256::zero()
@@ -161,684 +1182,1277 @@ pub fn evm(_code: impl AsRef<[u8]>, data: &mut EvmData, writable: bool) -> EvmRe
                 v.try_into().expect("c is less than U256::MAX")
             };
 
-            stack.push(res);
+            push!(stack, gas, res);
         } else if opcode == 0x0a {
             // EXP
-            let a = stack.pop().unwrap();
-            let exponent = stack.pop().unwrap();
-            stack.push(a.pow(exponent));
+            let a = pop!(stack, gas);
+            let exponent = pop!(stack, gas);
+            let exponent_bytes = exponent.bits().div_ceil(8) as u64;
+            if !gas.charge(data.config.gas.exp_byte * exponent_bytes) {
+                return out_of_gas(&stack, &gas);
+            }
+            push!(stack, gas, exp(a, exponent));
         } else if opcode == 0x0b {
             // SIGNEXTEND
-            let k = stack.pop().unwrap();
-            let v = stack.pop().unwrap();
+            let k = pop!(stack, gas);
+            let v = pop!(stack, gas);
 
             let value = signextend(k, v);
-            stack.push(value);
+            push!(stack, gas, value);
         } else if opcode == 0x10 {
             // LT
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
             if a < b {
-                stack.push(U256::one());
+                push!(stack, gas, U256::one());
             } else {
-                stack.push(U256::zero());
+                push!(stack, gas, U256::zero());
             }
         } else if opcode == 0x11 {
             // GT
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
             if a > b {
-                stack.push(U256::one());
+                push!(stack, gas, U256::one());
             } else {
-                stack.push(U256::zero());
+                push!(stack, gas, U256::zero());
             }
         } else if opcode == 0x12 {
             // SLT
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
 
             let value = slt(a, b);
-            stack.push(value);
+            push!(stack, gas, value);
         } else if opcode == 0x13 {
             // SGT
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
 
             let value = sgt(a, b);
-            stack.push(value);
+            push!(stack, gas, value);
         } else if opcode == 0x14 {
             // EQ
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
             if a == b {
-                stack.push(U256::one());
+                push!(stack, gas, U256::one());
             } else {
-                stack.push(U256::zero());
+                push!(stack, gas, U256::zero());
             }
         } else if opcode == 0x15 {
             // ISZERO
-            let a = stack.pop().unwrap();
+            let a = pop!(stack, gas);
             if a == U256::zero() {
-                stack.push(U256::one());
+                push!(stack, gas, U256::one());
             } else {
-                stack.push(U256::zero());
+                push!(stack, gas, U256::zero());
             }
         } else if opcode == 0x16 {
             // AND
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a & b);
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(stack, gas, a & b);
         } else if opcode == 0x17 {
             // OR
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a | b);
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(stack, gas, a | b);
         } else if opcode == 0x18 {
             // XOR
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(a ^ b);
+            let a = pop!(stack, gas);
+            let b = pop!(stack, gas);
+            push!(stack, gas, a ^ b);
         } else if opcode == 0x19 {
             // NOT
-            let a = stack.pop().unwrap();
-            stack.push(!a);
+            let a = pop!(stack, gas);
+            push!(stack, gas, !a);
         } else if opcode == 0x1a {
             // BYTE
-            let i: U256 = stack.pop().unwrap();
-            let x = stack.pop().unwrap();
-            if i * 8 >= U256::from(256) {
-                stack.push(U256::zero());
-            } else {
-                let y = (x >> U256::from(256 - 8) - i * 8) & 0xFF.into();
-                stack.push(U256::from(y));
-            }
+            let i = pop!(stack, gas);
+            let x = pop!(stack, gas);
+            push!(stack, gas, byte(i, x));
         } else if opcode == 0x1b {
             // SHL
-            let shift = stack.pop().unwrap();
-            let value = stack.pop().unwrap();
-            if shift >= U256::from(256) {
-                stack.push(U256::zero());
-            } else {
-                stack.push(value << shift);
-            }
+            let shift = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            push!(stack, gas, shl(shift, value));
         } else if opcode == 0x1c {
             // SHR
-            let shift = stack.pop().unwrap();
-            let value = stack.pop().unwrap();
-            if shift >= U256::from(256) {
-                stack.push(U256::zero());
-            } else {
-                stack.push(value >> shift);
-            }
+            let shift = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            push!(stack, gas, shr(shift, value));
         } else if opcode == 0x1d {
             // SAR
-            let shift = stack.pop().unwrap();
-            let value: U256 = stack.pop().unwrap();
+            let shift = pop!(stack, gas);
+            let value: U256 = pop!(stack, gas);
             let value = sar(shift, value);
-            stack.push(value);
+            push!(stack, gas, value);
         } else if opcode == 0x20 {
             // SHA3
-            let offset = stack.pop().unwrap();
-            let size = stack.pop().unwrap();
-            let value = memory.read_u8s(offset.as_usize(), size.as_usize());
-            let mut hasher = Keccak256::new();
-            hasher.update(value);
-            let result = hasher.finalize();
-            stack.push(U256::from_big_endian(&result));
+            let offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((offset, size)) = grow_memory(memory, &mut gas, offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            if !gas.charge(6 * size.div_ceil(32) as u64) {
+                return out_of_gas(&stack, &gas);
+            }
+            let value = memory.read_u8s(offset, size);
+            let hash = data.keccak(&value);
+            push!(stack, gas, U256::from_big_endian(hash.as_bytes()));
         } else if opcode == 0x30 {
             // ADDRESS
-            let address = data.tx_data.clone().unwrap().to.unwrap();
-            stack.push(U256::from_str_radix(address.as_str(), 16).unwrap());
+            push!(stack, gas, data.env.tx.address);
         } else if opcode == 0x31 {
             // BALANCE
-            let address = stack.pop().unwrap();
-            let balance = data.balances.get(&address.to_string());
-            stack.push(*balance.unwrap_or(&U256::zero()));
+            let address = pop!(stack, gas);
+            let cold = data.access_address(address);
+            let schedule = &data.config.gas;
+            if !gas.charge(schedule.account_access(cold, schedule.balance)) {
+                return out_of_gas(&stack, &gas);
+            }
+            let balance = data.basic(address).unwrap_or_default().balance;
+            push!(stack, gas, balance);
         } else if opcode == 0x32 {
             // ORIGIN
-            let origin = data.tx_data.clone().unwrap().origin.unwrap();
-            stack.push(U256::from_str_radix(origin.as_str(), 16).unwrap())
+            push!(stack, gas, data.env.tx.origin)
         } else if opcode == 0x33 {
             // CALLER
-            let data_from = data.tx_data.clone().unwrap().from;
-            let res = match data_from {
-                Some(address) => address,
-                None => {
-                    let address = data.tx_data.clone().unwrap().to.unwrap();
-                    address
-                }
-            };
-            stack.push(U256::from_str_radix(res.as_str(), 16).unwrap());
+            push!(stack, gas, data.env.tx.caller);
         } else if opcode == 0x34 {
             // CALLVALUE
-            let value = data.tx_data.clone().unwrap().value.unwrap();
-            stack.push(U256::from_str_radix(value.as_str(), 16).unwrap());
+            push!(stack, gas, data.env.tx.value);
         } else if opcode == 0x35 {
             // CALLDATALOAD
-            let i = stack.pop().unwrap();
-            let data = data.tx_data.clone().unwrap().data.unwrap();
-            let bytes = data
-                .as_bytes()
-                .chunks(2)
-                .map(std::str::from_utf8)
-                .collect::<Result<Vec<&str>, _>>()
-                .unwrap();
-            let mut data = bytes[i.as_usize()..bytes.len()].to_vec();
-            if data.len() < 32 {
-                while data.len() < 32 {
-                    data.push("00");
-                }
-            } else if data.len() > 32 {
-                data.truncate(32);
+            // Bytes past the end of the calldata read as zero
+            let offset = pop!(stack, gas);
+            let mut word = [0u8; 32];
+            if offset < U256::from(input.len()) {
+                let offset = offset.as_usize();
+                let end = input.len().min(offset + 32);
+                word[..end - offset].copy_from_slice(&input[offset..end]);
             }
-
-            stack.push(U256::from_str_radix(data.join("").as_str(), 16).unwrap())
+            push!(stack, gas, U256::from_big_endian(&word))
         } else if opcode == 0x36 {
             // CALLDATASIZE
-            match data.tx_data {
-                Some(ref txdata) => {
-                    let b = txdata.clone().data.unwrap();
-                    let bytes = b
-                        .as_bytes()
-                        .chunks(2)
-                        .map(std::str::from_utf8)
-                        .collect::<Result<Vec<&str>, _>>()
-                        .unwrap();
-                    stack.push(U256::from(bytes.len()));
-                }
-                None => stack.push(U256::zero()),
-            }
+            push!(stack, gas, U256::from(input.len()));
         } else if opcode == 0x37 {
             // CALLDATACOPY
-            let dest_offset = stack.pop().unwrap();
-            let source_offset = stack.pop().unwrap();
-            let size = stack.pop().unwrap();
-
-            let data = data.tx_data.clone().unwrap().data.unwrap();
-            let bytes = data
-                .as_bytes()
-                .chunks(2)
-                .map(std::str::from_utf8)
-                .collect::<Result<Vec<&str>, _>>()
-                .unwrap();
-            let data = bytes[source_offset.as_usize()..bytes.len()].to_vec();
-
-            for i in 0..size.as_usize() {
-                memory.write_u8(
-                    dest_offset.as_usize() + i,
-                    u8::from_str_radix(data[i], 16).ok().unwrap(),
-                );
+            let dest_offset = pop!(stack, gas);
+            let source_offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((dest_offset, size)) = grow_memory(memory, &mut gas, dest_offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            if !gas.charge(copy_cost(size)) {
+                return out_of_gas(&stack, &gas);
             }
+
+            memory.copy_from(dest_offset, input, source_offset, size);
         } else if opcode == 0x38 {
             // CODESIZE
-            stack.push(U256::from(code.len()));
+            push!(stack, gas, U256::from(code.len()));
         } else if opcode == 0x39 {
             // CODECOPY
-            let dest_offset = stack.pop().unwrap();
-            let source_offset = stack.pop().unwrap();
-            let size = stack.pop().unwrap().as_usize();
-
-            let mut code_to_copy = code[source_offset.as_usize()..code.len()].to_vec();
-
-            if code_to_copy.len() < size {
-                while code_to_copy.len() < size {
-                    code_to_copy.push(00 as u8);
-                }
-            } else if code_to_copy.len() > size {
-                code_to_copy.truncate(size);
+            let dest_offset = pop!(stack, gas);
+            let source_offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((dest_offset, size)) = grow_memory(memory, &mut gas, dest_offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            if !gas.charge(copy_cost(size)) {
+                return out_of_gas(&stack, &gas);
             }
 
-            for i in 0..size {
-                memory.write_u8(dest_offset.as_usize() + i, code_to_copy[i]);
-            }
+            memory.copy_from(dest_offset, code, source_offset, size);
         } else if opcode == 0x3a {
             // GASPRICE
-            let gasprice = data.tx_data.clone().unwrap().gasprice.unwrap();
-            stack.push(U256::from_str_radix(gasprice.as_str(), 16).unwrap());
+            push!(stack, gas, data.env.tx.gasprice);
         } else if opcode == 0x3b {
             // EXTCODESIZE
-            let address = stack.pop().unwrap();
-            if data.state.contains_key(&address.to_string()) {
-                let hardcoded_size = data.state.get(&address.to_string()).unwrap();
-                stack.push(U256::from_str(hardcoded_size).unwrap());
-            } else {
-                stack.push(U256::zero());
+            let address = pop!(stack, gas);
+            let cold = data.access_address(address);
+            let schedule = &data.config.gas;
+            if !gas.charge(schedule.account_access(cold, schedule.extcode)) {
+                return out_of_gas(&stack, &gas);
             }
+            let size = data.code(address).unwrap_or_default().len();
+            push!(stack, gas, U256::from(size));
         } else if opcode == 0x3c {
             // EXTCODECOPY
-            let address = stack.pop().unwrap(); //Hardcoded result
-            let dest_offset = stack.pop().unwrap();
-            let source_offset = stack.pop().unwrap();
-            let size = stack.pop().unwrap().as_usize();
-
-            // Hardcoded results in state
-            let extcode = data.state.get(&address.to_string()).unwrap();
-            let bytes = extcode
-                .as_bytes()
-                .chunks(2)
-                .map(std::str::from_utf8)
-                .collect::<Result<Vec<&str>, _>>()
-                .unwrap();
-            let extdata = bytes[source_offset.as_usize()..bytes.len()].to_vec();
-
-            for i in 0..size {
-                memory.write_u8(
-                    dest_offset.as_usize() + i,
-                    u8::from_str_radix(extdata[i], 16).ok().unwrap(),
-                );
+            let address = pop!(stack, gas);
+            let dest_offset = pop!(stack, gas);
+            let source_offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((dest_offset, size)) = grow_memory(memory, &mut gas, dest_offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            let cold = data.access_address(address);
+            let schedule = &data.config.gas;
+            let cost = schedule.account_access(cold, schedule.extcode) + copy_cost(size);
+            if !gas.charge(cost) {
+                return out_of_gas(&stack, &gas);
             }
+
+            let bytes = data.code(address).unwrap_or_default();
+            memory.copy_from(dest_offset, &bytes, source_offset, size);
         } else if opcode == 0x3d {
             // RETURNDATASIZE
-            stack.push(U256::from(return_data.len()));
+            push!(stack, gas, U256::from(return_data.len()));
         } else if opcode == 0x3e {
             // RETURNDATACOPY
-            let dest_offset = stack.pop().unwrap().as_usize();
-            let source_offset = stack.pop().unwrap().as_usize();
-            let size = stack.pop().unwrap().as_usize();
+            let dest_offset = pop!(stack, gas);
+            let source_offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
 
-            let data = return_data[source_offset..source_offset + size].to_vec();
-            for i in 0..size {
-                memory.write_u8(dest_offset + i, data[i]);
+            // Unlike the other copies, reading past the end is an error
+            // (EIP-211). Checked first, so the range fits in a usize below
+            let (end, overflow) = source_offset.overflowing_add(size);
+            if overflow || end > U256::from(return_data.len()) {
+                return halt(&stack, &gas, HaltReason::ReturnDataOutOfBounds);
+            }
+            let Some((dest_offset, size)) = grow_memory(memory, &mut gas, dest_offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            if !gas.charge(copy_cost(size)) {
+                return out_of_gas(&stack, &gas);
+            }
+            if size > 0 {
+                memory.write_slice(dest_offset, &return_data, source_offset.as_usize(), size);
             }
         } else if opcode == 0x3f {
             // EXTCODEHASH
-            let address = stack.pop().unwrap();
-            if data.state.contains_key(&address.to_string()) {
-                /*
-                Diferent value after hashing.
-
-                let hardcoded_hash = "FFFFFFFF";
-                let mut hasher = Keccak256::new();
-                hasher.update(hardcoded_hash);
-                let result = hasher.finalize();
-                stack.push(U256::from_big_endian(&result));
-                */
-                stack.push(
-                    U256::from_str_radix(
-                        "0x29045A592007D0C246EF02C2223570DA9522D0CF0F73282C79A1BC8F0BB2C238",
-                        16,
-                    )
-                    .unwrap(),
-                );
-            } else {
-                stack.push(U256::zero());
+            let address = pop!(stack, gas);
+            let cold = data.access_address(address);
+            let schedule = &data.config.gas;
+            if !gas.charge(schedule.account_access(cold, schedule.extcodehash)) {
+                return out_of_gas(&stack, &gas);
             }
+            push!(stack, gas, data.code_hash(address));
         } else if opcode == 0x40 {
             // BLOCKHASH
+            let number = pop!(stack, gas);
+            let current = data
+                .context
+                .as_ref()
+                .and_then(|context| context.number)
+                .unwrap_or_default();
+
+            // Only the 256 most recent complete blocks are available
+            let hash = if number >= current || current - number > U256::from(256) {
+                None
+            } else {
+                data.block_hash(number)
+            };
+            push!(stack, gas, hash.unwrap_or_default());
         } else if opcode == 0x41 {
             // COINBASE
-            let coinbase = data.context.clone().unwrap().coinbase.unwrap();
-            stack.push(U256::from_str_radix(coinbase.as_str(), 16).unwrap());
+            push!(stack, gas, data.env.block.coinbase);
         } else if opcode == 0x42 {
             // TIMESTAMP
-            let timestamp = data.context.clone().unwrap().timestamp.unwrap();
-            stack.push(U256::from_str_radix(timestamp.as_str(), 16).unwrap());
+            push!(stack, gas, data.env.block.timestamp);
         } else if opcode == 0x43 {
             // NUMBER
-            let number = data.context.clone().unwrap().number.unwrap();
-            stack.push(U256::from_str_radix(number.as_str(), 16).unwrap())
+            push!(stack, gas, data.env.block.number);
         } else if opcode == 0x44 {
             // DIFFICULTY
-            let difficulty = data.context.clone().unwrap().difficulty.unwrap();
-            stack.push(U256::from_str_radix(difficulty.as_str(), 16).unwrap())
+            push!(stack, gas, data.env.block.difficulty);
         } else if opcode == 0x45 {
             // GASLIMIT
-            let gaslimit = data.context.clone().unwrap().gaslimit.unwrap();
-            stack.push(U256::from_str_radix(gaslimit.as_str(), 16).unwrap())
+            push!(stack, gas, data.env.block.gaslimit);
         } else if opcode == 0x46 {
             // CHAINID
-            let chainid = data.context.clone().unwrap().chainid.unwrap();
-            stack.push(U256::from_str_radix(chainid.as_str(), 16).unwrap())
+            push!(stack, gas, data.env.block.chainid);
         } else if opcode == 0x47 {
             // SELFBALANCE
-            let address = data.tx_data.clone().unwrap().to.unwrap();
-            let balance = data.balances.get(&address.to_string());
-            stack.push(*balance.unwrap_or(&U256::zero()));
+            let balance = data
+                .basic(current_address(data))
+                .unwrap_or_default()
+                .balance;
+            push!(stack, gas, balance);
         } else if opcode == 0x48 {
             // BASEFEE
-            let base_fee = data.context.clone().unwrap().basefee.unwrap();
-            stack.push(U256::from_str_radix(base_fee.as_str(), 16).unwrap())
+            push!(stack, gas, data.env.block.basefee)
+        } else if opcode == 0x49 {
+            // BLOBHASH
+            let index = pop!(stack, gas);
+            let hashes = &data.env.tx.blob_hashes;
+            let hash = if index < U256::from(hashes.len()) {
+                hashes[index.as_usize()]
+            } else {
+                U256::zero()
+            };
+            push!(stack, gas, hash);
+        } else if opcode == 0x4a {
+            // BLOBBASEFEE
+            push!(stack, gas, blob_base_fee(data.env.block.excess_blob_gas));
         } else if opcode == 0x50 {
             // POP
-            stack.pop();
+            pop!(stack, gas);
         } else if opcode == 0x51 {
             // MLOAD
-            let a = stack.pop().unwrap().as_usize();
-            let value = memory.read_u256(a, 32);
-            stack.push(value);
+            let offset = pop!(stack, gas);
+            let Some((offset, _)) = grow_memory(memory, &mut gas, offset, U256::from(32)) else {
+                return out_of_gas(&stack, &gas);
+            };
+            let value = memory.read_u256(offset, 32);
+            push!(stack, gas, value);
         } else if opcode == 0x52 {
             // MSTORE
-            let index = stack.pop().unwrap().as_usize();
-            let value = stack.pop().unwrap();
+            let index = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            let Some((index, _)) = grow_memory(memory, &mut gas, index, U256::from(32)) else {
+                return out_of_gas(&stack, &gas);
+            };
             for i in 0..32 {
                 memory.write_u8(index + i, value.byte(31 - i));
             }
         } else if opcode == 0x53 {
             // MSTORE8
-            let a = stack.pop().unwrap();
-            let value = stack.pop().unwrap();
-            let index = a.as_usize();
+            let index = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            let Some((index, _)) = grow_memory(memory, &mut gas, index, U256::one()) else {
+                return out_of_gas(&stack, &gas);
+            };
             memory.write_u8(index, value.byte(0));
         } else if opcode == 0x54 {
             // SLOAD
-            let key = stack.pop().unwrap();
-            let value = data.state.get(&key.to_string());
-            match value {
-                Some(v) => stack.push(U256::from_str_radix(v, 16).unwrap()),
-                None => stack.push(U256::zero()),
+            let key = pop!(stack, gas);
+            let cold = data.access_slot(current_address(data), key);
+            if !gas.charge(data.config.gas.sload_cost(cold)) {
+                return out_of_gas(&stack, &gas);
             }
+            push!(stack, gas, data.storage(current_address(data), key));
         } else if opcode == 0x55 {
             // SSTORE
             if !writable {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
+                return halt(&stack, &gas, HaltReason::StaticModeViolation);
             }
-            let key = stack.pop().unwrap();
-            let value = stack.pop().unwrap();
+            let key = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            inspector.sstore(key, value);
 
-            if value == U256::zero() {
-                data.state.remove(&key.to_string());
-            } else {
-                data.state.insert(key.to_string(), value.to_string());
+            // EIP-2200: never with only the stipend of a value transfer left
+            if data.config.spec.is_enabled_in(SpecId::Istanbul)
+                && gas
+                    .remaining()
+                    .is_some_and(|remaining| remaining <= SSTORE_SENTRY)
+            {
+                return out_of_gas(&stack, &gas);
+            }
+
+            let address = current_address(data);
+            let current = data.storage(address, key);
+            let original = *data
+                .substate
+                .original_storage
+                .entry((address, key))
+                .or_insert(current);
+            let cold = data.access_slot(address, key);
+            let (cost, refund) = sstore_cost(&data.config, original, current, value, cold);
+            if !gas.charge(cost) {
+                return out_of_gas(&stack, &gas);
             }
+            if refund != 0 {
+                data.add_refund(refund);
+            }
+
+            // Zero is written out too, to shadow the slot in the database
+            data.set_state(address, key, Some(value));
         } else if opcode == 0x56 {
             // JUMP
-            let dest = stack.pop().unwrap().as_usize();
-            if !is_valid_jump_dest(code, dest) {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
+            let dest = pop!(stack, gas);
+            if !is_jump_dest(&jump_dests, dest) {
+                return halt(&stack, &gas, HaltReason::InvalidJump);
             }
 
-            pc = dest;
+            pc = dest.as_usize();
         } else if opcode == 0x57 {
             // JUMPI
-            let dest = stack.pop().unwrap().as_usize();
-            let cond = stack.pop().unwrap();
+            let dest = pop!(stack, gas);
+            let cond = pop!(stack, gas);
 
-            if !is_valid_jump_dest(code, dest) {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
+            if !is_jump_dest(&jump_dests, dest) {
+                return halt(&stack, &gas, HaltReason::InvalidJump);
             }
 
             if cond != U256::zero() {
-                pc = dest;
+                pc = dest.as_usize();
             }
         } else if opcode == 0x58 {
             // PC
-            stack.push(U256::from(pc - 1));
+            push!(stack, gas, U256::from(pc - 1));
         } else if opcode == 0x59 {
             // MSIZE
-            stack.push(memory.msize());
+            push!(stack, gas, memory.msize());
         } else if opcode == 0x5a {
             // GAS
-            stack.push(U256::MAX);
+            let remaining = gas.remaining().map(U256::from);
+            push!(stack, gas, remaining.unwrap_or(U256::MAX));
         } else if opcode == 0x5b {
             // JUMPDEST
-        } else if opcode >= 0x5f && opcode <= 0x7f {
+        } else if opcode == 0x5c {
+            // TLOAD
+            let key = pop!(stack, gas);
+            push!(
+                stack,
+                gas,
+                data.transient_storage(current_address(data), key)
+            );
+        } else if opcode == 0x5d {
+            // TSTORE
+            if !writable {
+                return halt(&stack, &gas, HaltReason::StaticModeViolation);
+            }
+            let key = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            data.set_transient_storage(current_address(data), key, value);
+        } else if opcode == 0x5e {
+            // MCOPY
+            let dest_offset = pop!(stack, gas);
+            let source_offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((dest_offset, _)) = grow_memory(memory, &mut gas, dest_offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            let Some((source_offset, size)) = grow_memory(memory, &mut gas, source_offset, size)
+            else {
+                return out_of_gas(&stack, &gas);
+            };
+            if !gas.charge(copy_cost(size)) {
+                return out_of_gas(&stack, &gas);
+            }
+            memory.copy(dest_offset, source_offset, size);
+        } else if (0x5f..=0x7f).contains(&opcode) {
             // PUSHX
             let push_number = opcode - 0x5f;
             if push_number == 0 {
-                stack.push(U256::from(0));
+                push!(stack, gas, U256::from(0));
             } else {
-                let r = code[pc..pc + push_number as usize].to_vec();
-                stack.push(U256::from_big_endian(&r));
-                pc += push_number as usize;
+                // Code that ends inside the immediate reads zeros past it
+                let size = push_number as usize;
+                let mut r: Vec<u8> = code
+                    .get(pc..)
+                    .unwrap_or_default()
+                    .iter()
+                    .take(size)
+                    .copied()
+                    .collect();
+                r.resize(size, 0);
+                push!(stack, gas, U256::from_big_endian(&r));
+                pc += size;
             }
-        } else if opcode >= 0x80 && opcode <= 0x8f {
+        } else if (0x80..=0x8f).contains(&opcode) {
             // DUPX
             let dup_number = (opcode - 0x80 + 1) as usize;
-            if dup_number > stack.len() {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
-            }
-
-            let value = stack[stack.len() - dup_number];
-            stack.push(value);
-        } else if opcode >= 0x90 && opcode <= 0x9f {
+            check!(stack, gas, stack.dup(dup_number));
+        } else if (0x90..=0x9f).contains(&opcode) {
             // SWAPX
             let swap_number = (opcode - 0x90 + 1) as usize;
-            if swap_number + 1 > stack.len() {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
-            }
-            stack.swap(swap_number, 0)
-        } else if opcode >= 0xA0 && opcode <= 0xA4 {
-            // LOGX (Not implemented)
-
+            check!(stack, gas, stack.swap(swap_number));
+        } else if (0xA0..=0xA4).contains(&opcode) {
+            // LOGX
             if !writable {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
+                return halt(&stack, &gas, HaltReason::StaticModeViolation);
             }
 
-            let _offset = stack.pop().unwrap();
-            let _size = stack.pop().unwrap();
+            let offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((offset, size)) = grow_memory(memory, &mut gas, offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            if !gas.charge(8 * size as u64) {
+                return out_of_gas(&stack, &gas);
+            }
             let log_number = (opcode - 0xA0) as usize;
+            let mut topics = Vec::with_capacity(log_number);
+            for _ in 0..log_number {
+                topics.push(pop!(stack, gas));
+            }
 
-            if log_number == 1 {
-                let _ = stack.pop().unwrap();
-            } else if log_number == 2 {
-                let _ = stack.pop().unwrap();
-                let _ = stack.pop().unwrap();
-            } else if log_number == 3 {
-                let _ = stack.pop().unwrap();
-                let _ = stack.pop().unwrap();
-                let _ = stack.pop().unwrap();
-            } else if log_number == 4 {
-                let _ = stack.pop().unwrap();
-                let _ = stack.pop().unwrap();
-                let _ = stack.pop().unwrap();
-                let _ = stack.pop().unwrap();
-            }
-        } else if opcode == 0xf0 {
-            // CREATE
+            let log = Log {
+                address: current_address(data),
+                topics,
+                data: memory.read_u8s(offset, size),
+            };
+            inspector.log(&log);
+            data.emit_log(log);
+        } else if opcode == 0xe0 || opcode == 0xe1 {
+            // RJUMP, RJUMPI
+            let Some(offset) = immediate(code, pc, 2) else {
+                return halt(&stack, &gas, HaltReason::InvalidJump);
+            };
+            pc += 2;
+            if opcode == 0xe0 || !pop!(stack, gas).is_zero() {
+                let Some(dest) = relative_jump(code, pc, offset) else {
+                    return halt(&stack, &gas, HaltReason::InvalidJump);
+                };
+                pc = dest;
+            }
+        } else if opcode == 0xe2 {
+            // RJUMPV
+            let case = pop!(stack, gas);
+            let Some(max_index) = immediate(code, pc, 1) else {
+                return halt(&stack, &gas, HaltReason::InvalidJump);
+            };
+            let offsets = pc + 1;
+            pc = offsets + 2 * (max_index + 1);
+            if case <= U256::from(max_index) {
+                let offset = immediate(code, offsets + 2 * case.as_usize(), 2);
+                let Some(dest) = offset.and_then(|offset| relative_jump(code, pc, offset)) else {
+                    return halt(&stack, &gas, HaltReason::InvalidJump);
+                };
+                pc = dest;
+            }
+        } else if opcode == 0xe3 || opcode == 0xe5 {
+            // CALLF, JUMPF
+            let container = container.as_ref().unwrap();
+            let target = immediate(code, pc, 2);
+            let Some((target, code_type)) =
+                target.and_then(|target| Some((target, container.types.get(target)?)))
+            else {
+                return halt(&stack, &gas, HaltReason::InvalidOpcode);
+            };
+            // The stack must hold all the section may push
+            if stack.len() + code_type.max_stack_increase as usize > STACK_LIMIT {
+                return halt(&stack, &gas, HaltReason::StackOverflow);
+            }
+            if opcode == 0xe3 {
+                if return_stack.len() == eof::RETURN_STACK_LIMIT {
+                    return halt(&stack, &gas, HaltReason::StackOverflow);
+                }
+                return_stack.push((section, pc + 2));
+            }
+            section = target;
+            code = container.code_sections[section];
+            pc = 0;
+        } else if opcode == 0xe4 {
+            // RETF
+            let Some((caller, return_pc)) = return_stack.pop() else {
+                return halt(&stack, &gas, HaltReason::InvalidOpcode);
+            };
+            section = caller;
+            code = container.as_ref().unwrap().code_sections[section];
+            pc = return_pc;
+        } else if (0xe6..=0xe8).contains(&opcode) {
+            // DUPN, SWAPN, EXCHANGE
+            let Some(n) = immediate(code, pc, 1) else {
+                return halt(&stack, &gas, HaltReason::StackUnderflow);
+            };
+            pc += 1;
+            if opcode == 0xe6 {
+                check!(stack, gas, stack.dup(n + 1));
+            } else if opcode == 0xe7 {
+                check!(stack, gas, stack.swap(n + 1));
+            } else {
+                let first = (n >> 4) + 1;
+                let second = first + (n & 0x0f) + 1;
+                check!(stack, gas, stack.exchange(first, second));
+            }
+        } else if opcode == 0xf0 || opcode == 0xf5 {
+            // CREATE, CREATE2
             if !writable {
-                return EvmResult {
+                return halt(&stack, &gas, HaltReason::StaticModeViolation);
+            }
+
+            let value = pop!(stack, gas);
+            let offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let salt = if opcode == 0xf5 {
+                Some(pop!(stack, gas))
+            } else {
+                None
+            };
+
+            let Some((offset, size)) = grow_memory(memory, &mut gas, offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
+            // EIP-3860: init code over the limit fails the creating frame
+            let shanghai = data.config.spec.is_enabled_in(SpecId::Shanghai);
+            if shanghai && size > MAX_INITCODE_SIZE {
+                return out_of_gas(&stack, &gas);
+            }
+            let init_code = memory.read_u8s(offset, size);
+            if !gas.charge(init_code_cost(size, data.config.spec)) {
+                return out_of_gas(&stack, &gas);
+            }
+            // CREATE2 hashes the init code for the address
+            if salt.is_some() && !gas.charge(6 * size.div_ceil(32) as u64) {
+                return out_of_gas(&stack, &gas);
+            }
+
+            let sender = current_address(data);
+            let account = data.basic(sender).unwrap_or_default();
+            // A creator too deep or that cannot afford the value fails the
+            // create before its nonce moves
+            if depth > CALL_DEPTH_LIMIT || account.balance < value {
+                return_data = vec![];
+                return_origin = None;
+                push!(stack, gas, U256::zero());
+                continue;
+            }
+            let nonce = account.nonce;
+            data.set_nonce(sender, nonce + 1);
+            let address = match salt {
+                Some(salt) => create2_address(sender, salt, data.keccak(&init_code)),
+                None => create_address(sender, nonce),
+            };
+            data.access_address(address);
+            data.mark_created(address);
+
+            let create_tx = TxEnv {
+                caller: sender,
+                address,
+                value,
+                ..data.env.tx.clone()
+            };
+            let parent_tx = core::mem::replace(&mut data.env.tx, create_tx);
+            let checkpoint = data.checkpoint();
+            // The account exists while its init code runs, which may send
+            // the value on
+            data.transfer(sender, address, value);
+            data.set_nonce(address, 1);
+            let limit = gas.reserve(U256::MAX, 0);
+            let create = CreateFrame {
+                depth: depth + 1,
+                caller: sender,
+                address,
+                value,
+                init_code: &init_code,
+                gas: limit,
+            };
+            inspector.create_start(&create);
+            let res = execute(
+                &init_code,
+                &[],
+                data,
+                true,
+                inspector,
+                depth + 1,
+                limit,
+                memory,
+            );
+            data.env.tx = parent_tx;
+            if !res.success {
+                data.revert_to(checkpoint);
+                inspector.create_end(&create, &res);
+                gas.settle(limit, res.gas_used);
+                return_data = res.value.unwrap_or_default();
+                return_origin = res.return_origin;
+                push!(stack, gas, U256::zero());
+                continue;
+            }
+
+            // The code deposit is paid from the gas left to the init code.
+            // Code over the EIP-170 limit fails the create like running out
+            // of gas for it would
+            let deployed_code = res.value.clone().unwrap_or_default();
+            let deposit = CODE_DEPOSIT_COST * deployed_code.len() as u64;
+            let used = res.gas_used + deposit;
+            let too_large = data.config.spec.is_enabled_in(SpecId::SpuriousDragon)
+                && deployed_code.len() > MAX_CODE_SIZE;
+            if too_large || limit.is_some_and(|limit| used > limit) {
+                let failed = EvmResult {
                     value: None,
-                    stack,
                     success: false,
-                    return_data: vec![],
+                    gas_used: limit.unwrap_or(used),
+                    halt_reason: Some(HaltReason::OutOfGas),
+                    ..res
                 };
+                inspector.create_end(&create, &failed);
+                gas.settle(limit, limit.unwrap_or(used));
+                data.revert_to(checkpoint);
+                return_data = vec![];
+                return_origin = None;
+                push!(stack, gas, U256::zero());
+                continue;
             }
+            inspector.create_end(
+                &create,
+                &EvmResult {
+                    gas_used: used,
+                    ..res.clone()
+                },
+            );
+            gas.settle(limit, used);
+            data.set_code(address, &deployed_code);
 
-            let value = stack.pop().unwrap();
-            let u_offset = stack.pop().unwrap();
-            let _args_offset = u_offset.as_usize();
-            let _args_size = stack.pop().unwrap().as_usize();
+            creates.extend(res.creates);
+            creates.push(CreateReport::new(
+                address,
+                init_code.len(),
+                deployed_code.len(),
+            ));
+            return_data = vec![];
+            return_origin = None;
+            push!(stack, gas, address);
+        } else if opcode == 0xf1 {
+            // CALL
+            let requested_gas = pop!(stack, gas);
+            let to = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            // Only calls moving value count as state changes
+            if !writable && !value.is_zero() {
+                return halt(&stack, &gas, HaltReason::StaticModeViolation);
+            }
+            let args_offset = pop!(stack, gas);
+            let args_size = pop!(stack, gas);
+            let ret_offset = pop!(stack, gas);
+            let ret_size = pop!(stack, gas);
 
-            if data.tx_data.clone().unwrap().to.unwrap()
-                == "0x9bbfed6889322e016e0a02ee459d306fc19545d9"
-            {
-                // Mocked revert based on the address of the contract.
-                // Avoiding to implement the whole EVM.
-                stack.push(U256::from(0));
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: true,
-                    return_data: vec![],
+            let Some(((args_offset, args_size), (ret_offset, ret_size))) = expand_call_memory(
+                memory,
+                &mut gas,
+                (args_offset, args_size),
+                (ret_offset, ret_size),
+            ) else {
+                return out_of_gas(&stack, &gas);
+            };
+
+            let cold = data.access_address(to);
+            let (code, delegation_cost) = call_code(data, to);
+            let schedule = &data.config.gas;
+            let mut cost = schedule.account_access(cold, schedule.call) + delegation_cost;
+            let mut stipend = 0;
+            if !value.is_zero() {
+                cost += CALL_VALUE_COST;
+                stipend = CALL_STIPEND;
+                // Like SELFDESTRUCT, value sent to an account that does not
+                // exist creates it (EIP-161)
+                let creates_account = if data.config.spec.is_enabled_in(SpecId::SpuriousDragon) {
+                    is_empty_account(data, to)
+                } else {
+                    !account_exists(data, to)
                 };
+                if creates_account {
+                    cost += data.config.gas.new_account;
+                }
+            }
+            if !gas.charge(cost) {
+                return out_of_gas(&stack, &gas);
             }
+            let limit = gas.reserve(requested_gas, stipend);
 
-            data.balances.insert(u_offset.to_string(), value);
+            let input = memory.read_u8s(args_offset, args_size);
+            let prank = cheatcodes::pranked(data, depth, to);
+            let call = CallFrame {
+                kind: CallKind::Call,
+                depth: depth + 1,
+                caller: prank.map_or(current_address(data), |prank| prank.sender),
+                address: to,
+                code_address: to,
+                value,
+                input: &input,
+                gas: limit,
+            };
+            // A caller too deep or that cannot afford the value fails the
+            // call before it starts, and keeps the gas it set aside
+            let checkpoint = data.checkpoint();
+            if depth > CALL_DEPTH_LIMIT || !data.transfer(call.caller, to, value) {
+                gas.settle(limit, 0);
+                return_data = vec![];
+                return_origin = None;
+                push!(stack, gas, U256::zero());
+                continue;
+            }
+            inspector.call_start(&call);
+            console::inspect(inspector, &call);
 
-            stack.push(u_offset);
-            // Create new contract. Harcoded for now.
-            data.state.insert(
-                u_offset.to_string(),
-                "ffffffff00000000000000000000000000000000000000000000000000000000".to_string(),
+            if is_precompile(data, to) {
+                let res = call_precompile(
+                    data,
+                    depth,
+                    to,
+                    &mut gas,
+                    limit,
+                    memory,
+                    args_offset,
+                    args_size,
+                    ret_offset,
+                    ret_size,
+                );
+                if !res.success {
+                    data.revert_to(checkpoint);
+                }
+                inspector.call_end(&call, &res);
+                return_data = res.return_data;
+                return_origin = None;
+                push!(stack, gas, U256::from(res.success as u64));
+                continue;
+            }
+
+            let call_tx = TxEnv {
+                caller: call.caller,
+                address: to,
+                origin: prank
+                    .and_then(|prank| prank.origin)
+                    .unwrap_or(data.env.tx.origin),
+                value,
+                ..data.env.tx.clone()
+            };
+            let parent_tx = core::mem::replace(&mut data.env.tx, call_tx);
+            // A static context lasts for every frame under it
+            let res = execute(
+                &code,
+                &input,
+                data,
+                writable,
+                inspector,
+                depth + 1,
+                limit,
+                memory,
             );
-        } else if opcode == 0xf1 {
-            // CALL
-            if !writable {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
+            data.env.tx = parent_tx;
+            if !res.success {
+                data.revert_to(checkpoint);
             }
-            let _gas = stack.pop().unwrap();
-            let to = stack.pop().unwrap();
-            let _value = stack.pop().unwrap();
-            let _args_offset = stack.pop().unwrap();
-            let _args_size = stack.pop().unwrap();
-            let ret_offset = stack.pop().unwrap().as_usize();
-            let ret_size = stack.pop().unwrap().as_usize();
-
-            let code_str = data.state.get(&to.to_string()).unwrap().clone();
-            let code: Vec<u8> = hex::decode(code_str).unwrap();
-            let res = evm(code, data, true);
+            inspector.call_end(&call, &res);
+            gas.settle(limit, res.gas_used);
             return_data = res.return_data;
-            if res.value.is_some() {
-                let val = res.value.unwrap();
-                for i in 0..ret_size {
-                    memory.write_u8(ret_offset + i, val[i]);
-                }
-                stack.push(U256::from(res.success as u64));
-            } else {
-                stack.push(U256::zero());
+            return_origin = res.return_origin;
+            if let Some(val) = res.value {
+                memory.write_slice(ret_offset, &val, 0, val.len().min(ret_size));
             }
+            push!(stack, gas, U256::from(res.success as u64));
         } else if opcode == 0xf2 {
             // CALLCODE
+            let requested_gas = pop!(stack, gas);
+            let to = pop!(stack, gas);
+            let value = pop!(stack, gas);
+            let args_offset = pop!(stack, gas);
+            let args_size = pop!(stack, gas);
+            let ret_offset = pop!(stack, gas);
+            let ret_size = pop!(stack, gas);
+
+            let Some(((args_offset, args_size), (ret_offset, ret_size))) = expand_call_memory(
+                memory,
+                &mut gas,
+                (args_offset, args_size),
+                (ret_offset, ret_size),
+            ) else {
+                return out_of_gas(&stack, &gas);
+            };
+
+            let cold = data.access_address(to);
+            let (code, delegation_cost) = call_code(data, to);
+            let schedule = &data.config.gas;
+            let mut cost = schedule.account_access(cold, schedule.call) + delegation_cost;
+            let mut stipend = 0;
+            if !value.is_zero() {
+                cost += CALL_VALUE_COST;
+                stipend = CALL_STIPEND;
+            }
+            if !gas.charge(cost) {
+                return out_of_gas(&stack, &gas);
+            }
+            let limit = gas.reserve(requested_gas, stipend);
+
+            // The code runs as the current account, which also sends the
+            // value, to itself: only its balance is checked
+            let address = current_address(data);
+            let input = memory.read_u8s(args_offset, args_size);
+            let call = CallFrame {
+                kind: CallKind::CallCode,
+                depth: depth + 1,
+                caller: address,
+                address,
+                code_address: to,
+                value,
+                input: &input,
+                gas: limit,
+            };
+            if depth > CALL_DEPTH_LIMIT || !data.transfer(address, address, value) {
+                gas.settle(limit, 0);
+                return_data = vec![];
+                return_origin = None;
+                push!(stack, gas, U256::zero());
+                continue;
+            }
+            inspector.call_start(&call);
+            console::inspect(inspector, &call);
+
+            if is_precompile(data, to) {
+                let res = call_precompile(
+                    data,
+                    depth,
+                    to,
+                    &mut gas,
+                    limit,
+                    memory,
+                    args_offset,
+                    args_size,
+                    ret_offset,
+                    ret_size,
+                );
+                inspector.call_end(&call, &res);
+                return_data = res.return_data;
+                return_origin = None;
+                push!(stack, gas, U256::from(res.success as u64));
+                continue;
+            }
+
+            let call_tx = TxEnv {
+                caller: address,
+                address,
+                value,
+                ..data.env.tx.clone()
+            };
+            let parent_tx = core::mem::replace(&mut data.env.tx, call_tx);
+            let res = execute(
+                &code,
+                &input,
+                data,
+                writable,
+                inspector,
+                depth + 1,
+                limit,
+                memory,
+            );
+            data.env.tx = parent_tx;
+            inspector.call_end(&call, &res);
+            gas.settle(limit, res.gas_used);
+            return_data = res.return_data;
+            return_origin = res.return_origin;
+            if let Some(val) = res.value {
+                memory.write_slice(ret_offset, &val, 0, val.len().min(ret_size));
+            }
+            push!(stack, gas, U256::from(res.success as u64));
         } else if opcode == 0xf3 {
             // RETURN
-            let offset = stack.pop().unwrap().as_usize();
-            let return_size = stack.pop().unwrap();
-            let size = return_size.as_usize();
-
+            let offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((offset, size)) = grow_memory(memory, &mut gas, offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
             let ret = memory.read_u8s(offset, size);
 
+            let origin = ReturnOrigin {
+                frame,
+                depth,
+                address: current_address(data),
+                kind: ReturnKind::Return,
+                forwarded_from: return_origin
+                    .filter(|_| !ret.is_empty() && ret == return_data)
+                    .map(Box::new),
+            };
             return EvmResult {
                 value: Some(ret.clone()),
-                stack,
+                stack: stack.to_vec(),
                 success: true,
                 return_data: ret,
+                creates,
+                gas_used: gas.used(),
+                gas_refunded: 0,
+                logs: vec![],
+                return_origin: Some(origin),
+                memory: vec![],
+                state_changes: BTreeMap::new(),
+                state_diff: StateDiff::default(),
+                halt_reason: Some(HaltReason::Return),
             };
         } else if opcode == 0xf4 {
             // DELEGATECALL
-            let _gas = stack.pop().unwrap();
-            let to = stack.pop().unwrap();
-            let _args_offset = stack.pop().unwrap();
-            let _args_size = stack.pop().unwrap();
-            let ret_offset = stack.pop().unwrap().as_usize();
-            let ret_size = stack.pop().unwrap().as_usize();
-
-            let code_str = data.state.get(&to.to_string()).unwrap().clone();
-            let code: Vec<u8> = hex::decode(code_str).unwrap();
-
-            let mut new_data = data.clone();
-            new_data.tx_data = Some(TxData {
-                to: Some(to.to_string()),
-                ..Default::default()
-            });
-            let res = evm(code, &mut new_data, true);
-            return_data = res.return_data;
-            for i in 0..ret_size {
-                memory.write_u8(ret_offset + i, return_data[i]);
+            let requested_gas = pop!(stack, gas);
+            let to = pop!(stack, gas);
+            let args_offset = pop!(stack, gas);
+            let args_size = pop!(stack, gas);
+            let ret_offset = pop!(stack, gas);
+            let ret_size = pop!(stack, gas);
+
+            let Some(((args_offset, args_size), (ret_offset, ret_size))) = expand_call_memory(
+                memory,
+                &mut gas,
+                (args_offset, args_size),
+                (ret_offset, ret_size),
+            ) else {
+                return out_of_gas(&stack, &gas);
+            };
+
+            let cold = data.access_address(to);
+            let (code, delegation_cost) = call_code(data, to);
+            let schedule = &data.config.gas;
+            if !gas.charge(schedule.account_access(cold, schedule.call) + delegation_cost) {
+                return out_of_gas(&stack, &gas);
             }
-            stack.push(U256::one());
-        } else if opcode == 0xf5 {
-            // CREATE2
-            if !writable {
-                return EvmResult {
-                    value: None,
-                    stack,
-                    success: false,
-                    return_data: vec![],
-                };
+            let limit = gas.reserve(requested_gas, 0);
+
+            // The code runs as the current account, with the caller and value
+            // of the current frame
+            let args = memory.read_u8s(args_offset, args_size);
+            let call = CallFrame {
+                kind: CallKind::DelegateCall,
+                depth: depth + 1,
+                caller: data.env.tx.caller,
+                address: current_address(data),
+                code_address: to,
+                value: data.env.tx.value,
+                input: &args,
+                gas: limit,
+            };
+            if depth > CALL_DEPTH_LIMIT {
+                gas.settle(limit, 0);
+                return_data = vec![];
+                return_origin = None;
+                push!(stack, gas, U256::zero());
+                continue;
             }
+            inspector.call_start(&call);
+            console::inspect(inspector, &call);
+
+            if is_precompile(data, to) {
+                let res = call_precompile(
+                    data,
+                    depth,
+                    to,
+                    &mut gas,
+                    limit,
+                    memory,
+                    args_offset,
+                    args_size,
+                    ret_offset,
+                    ret_size,
+                );
+                inspector.call_end(&call, &res);
+                return_data = res.return_data;
+                return_origin = None;
+                push!(stack, gas, U256::from(res.success as u64));
+                continue;
+            }
+
+            let res = execute(
+                &code,
+                &args,
+                data,
+                writable,
+                inspector,
+                depth + 1,
+                limit,
+                memory,
+            );
+            inspector.call_end(&call, &res);
+            gas.settle(limit, res.gas_used);
+            return_data = res.return_data;
+            return_origin = res.return_origin;
+            let copied = return_data.len().min(ret_size);
+            memory.write_slice(ret_offset, &return_data, 0, copied);
+            push!(stack, gas, U256::from(res.success as u64));
         } else if opcode == 0xfa {
             // STATICCALL
-            let _gas = stack.pop().unwrap();
-            let address = stack.pop().unwrap();
-            let _args_offset = stack.pop().unwrap();
-            let _args_size = stack.pop().unwrap();
-            let ret_offset = stack.pop().unwrap().as_usize();
-            let ret_size = stack.pop().unwrap().as_usize();
-            let code_str = data.state.get(&address.to_string()).unwrap().clone();
-            let code: Vec<u8> = hex::decode(code_str).unwrap();
-            let res = evm(code, data, false);
+            let requested_gas = pop!(stack, gas);
+            let address = pop!(stack, gas);
+            let args_offset = pop!(stack, gas);
+            let args_size = pop!(stack, gas);
+            let ret_offset = pop!(stack, gas);
+            let ret_size = pop!(stack, gas);
+
+            let Some(((args_offset, args_size), (ret_offset, ret_size))) = expand_call_memory(
+                memory,
+                &mut gas,
+                (args_offset, args_size),
+                (ret_offset, ret_size),
+            ) else {
+                return out_of_gas(&stack, &gas);
+            };
+
+            let cold = data.access_address(address);
+            let (code, delegation_cost) = call_code(data, address);
+            let schedule = &data.config.gas;
+            if !gas.charge(schedule.account_access(cold, schedule.call) + delegation_cost) {
+                return out_of_gas(&stack, &gas);
+            }
+            let limit = gas.reserve(requested_gas, 0);
+
+            let input = memory.read_u8s(args_offset, args_size);
+            let prank = cheatcodes::pranked(data, depth, address);
+            let call = CallFrame {
+                kind: CallKind::StaticCall,
+                depth: depth + 1,
+                caller: prank.map_or(current_address(data), |prank| prank.sender),
+                address,
+                code_address: address,
+                value: U256::zero(),
+                input: &input,
+                gas: limit,
+            };
+            if depth > CALL_DEPTH_LIMIT {
+                gas.settle(limit, 0);
+                return_data = vec![];
+                return_origin = None;
+                push!(stack, gas, U256::zero());
+                continue;
+            }
+            inspector.call_start(&call);
+            console::inspect(inspector, &call);
+
+            if is_precompile(data, address) {
+                let res = call_precompile(
+                    data,
+                    depth,
+                    address,
+                    &mut gas,
+                    limit,
+                    memory,
+                    args_offset,
+                    args_size,
+                    ret_offset,
+                    ret_size,
+                );
+                inspector.call_end(&call, &res);
+                return_data = res.return_data;
+                return_origin = None;
+                push!(stack, gas, U256::from(res.success as u64));
+                continue;
+            }
+
+            let call_tx = TxEnv {
+                caller: call.caller,
+                address,
+                origin: prank
+                    .and_then(|prank| prank.origin)
+                    .unwrap_or(data.env.tx.origin),
+                value: U256::zero(),
+                ..data.env.tx.clone()
+            };
+            let parent_tx = core::mem::replace(&mut data.env.tx, call_tx);
+            let res = execute(
+                &code,
+                &input,
+                data,
+                false,
+                inspector,
+                depth + 1,
+                limit,
+                memory,
+            );
+            data.env.tx = parent_tx;
+            inspector.call_end(&call, &res);
+            gas.settle(limit, res.gas_used);
             return_data = res.return_data;
-            if res.value.is_some() {
-                let val = res.value.unwrap();
-                for i in 0..ret_size {
-                    memory.write_u8(ret_offset + i, val[i]);
-                }
-                stack.push(U256::from(res.success as u64));
-            } else {
-                stack.push(U256::zero());
+            return_origin = res.return_origin;
+            if let Some(val) = res.value {
+                memory.write_slice(ret_offset, &val, 0, val.len().min(ret_size));
             }
+            push!(stack, gas, U256::from(res.success as u64));
         } else if opcode == 0xfd {
             // REVERT
-            let offset = stack.pop().unwrap().as_usize();
-            let return_size = stack.pop().unwrap();
-            let size = return_size.as_usize();
-
+            let offset = pop!(stack, gas);
+            let size = pop!(stack, gas);
+            let Some((offset, size)) = grow_memory(memory, &mut gas, offset, size) else {
+                return out_of_gas(&stack, &gas);
+            };
             let ret = memory.read_u8s(offset, size);
 
+            let origin = ReturnOrigin {
+                frame,
+                depth,
+                address: current_address(data),
+                kind: ReturnKind::Revert,
+                forwarded_from: return_origin
+                    .filter(|_| !ret.is_empty() && ret == return_data)
+                    .map(Box::new),
+            };
             return EvmResult {
-                value: Some(ret),
-                stack,
+                value: Some(ret.clone()),
+                stack: stack.to_vec(),
                 success: false,
-                return_data: vec![],
+                return_data: ret,
+                creates: vec![],
+                gas_used: gas.used(),
+                gas_refunded: 0,
+                logs: vec![],
+                return_origin: Some(origin),
+                memory: vec![],
+                state_changes: BTreeMap::new(),
+                state_diff: StateDiff::default(),
+                halt_reason: Some(HaltReason::Revert),
             };
         } else if opcode == 0xfe {
             // INVALID
-            return EvmResult {
-                value: None,
-                stack,
-                success: false,
-                return_data: vec![],
-            };
+            return halt(&stack, &gas, HaltReason::InvalidOpcode);
         } else if opcode == 0xff {
             // SELFDESTRUCT
+            if !writable {
+                return halt(&stack, &gas, HaltReason::StaticModeViolation);
+            }
+
+            let beneficiary = pop!(stack, gas);
+            let address = current_address(data);
+            let balance = data.basic(address).unwrap_or_default().balance;
+            let schedule = &data.config.gas;
+            let mut cost = schedule.selfdestruct;
+            // Before Spurious Dragon any account that does not exist counts
+            // as new, after it only empty ones receiving value (EIP-161)
+            let creates_account = if data.config.spec.is_enabled_in(SpecId::SpuriousDragon) {
+                !balance.is_zero() && is_empty_account(data, beneficiary)
+            } else {
+                !account_exists(data, beneficiary)
+            };
+            if creates_account {
+                cost += data.config.gas.new_account;
+            }
+            if data.access_address(beneficiary) {
+                cost += data.config.gas.cold_account_access;
+            }
+            if !gas.charge(cost) {
+                return out_of_gas(&stack, &gas);
+            }
 
-            let address = stack.pop().unwrap();
-            // Hardcoded for now.
-            data.state
-                .remove("1271253980042238172183243620132319847648413671085");
-            data.balances.insert(address.to_string(), U256::from(7));
+            // EIP-6780: from Cancun the account is only deleted when the
+            // same transaction created it, otherwise only the balance moves
+            let deleted = !data.config.spec.is_enabled_in(SpecId::Cancun)
+                || data.substate.created.contains(&address);
+            if beneficiary != address {
+                let received = data.basic(beneficiary).unwrap_or_default().balance;
+                data.set_balance(beneficiary, received + balance);
+                data.set_balance(address, U256::zero());
+            } else if deleted {
+                // Sending the balance to itself burns it
+                data.set_balance(address, U256::zero());
+            }
+            if deleted && data.schedule_selfdestruct(address) {
+                let refund = data.config.gas.selfdestruct_refund;
+                data.add_refund(refund as i64);
+            }
 
             return EvmResult {
                 value: None,
-                stack,
-                success: false,
+                stack: stack.to_vec(),
+                success: true,
                 return_data: vec![],
+                creates: vec![],
+                gas_used: gas.used(),
+                gas_refunded: 0,
+                logs: vec![],
+                return_origin: None,
+                memory: vec![],
+                state_changes: BTreeMap::new(),
+                state_diff: StateDiff::default(),
+                halt_reason: Some(HaltReason::SelfDestruct),
             };
         } else {
-            println!("Unknown opcode: {}", opcode);
+            // Enabled in the spec but not implemented here
+            return halt(&stack, &gas, HaltReason::InvalidOpcode);
         }
     }
 
-    stack = stack.into_iter().rev().collect();
-    return EvmResult {
+    if !gas.charge_memory(memory.size) {
+        return out_of_gas(&stack, &gas);
+    }
+
+    EvmResult {
         value: None,
-        stack: stack,
+        stack: stack.as_slice().iter().rev().copied().collect(),
         success: true,
         return_data: vec![],
-    };
+        creates,
+        gas_used: gas.used(),
+        gas_refunded: 0,
+        logs: vec![],
+        return_origin: None,
+        memory: vec![],
+        state_changes: BTreeMap::new(),
+        state_diff: StateDiff::default(),
+        halt_reason: Some(HaltReason::Stop),
+    }
 }
@@ -1,4 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Appends a line to a test's output, which is printed once the tests before
+// it are done
+macro_rules! outln {
+    ($out:expr) => {
+        $out.push('\n')
+    };
+    ($out:expr, $($arg:tt)*) => {{
+        $out.push_str(&format!($($arg)*));
+        $out.push('\n');
+    }};
+}
 
 /**
  * EVM From Scratch
@@ -9,17 +26,39 @@ use std::collections::HashMap;
  * - Install Rust: https://www.rust-lang.org/tools/install
  * - Edit `rust/lib.rs`
  * - Run `cd rust && cargo run` to run the tests
+ * - Run `cargo run -- --tests <path>` to run a suite of your own, with
+ *   `--filter <name>` to run only some tests and `--fail-fast=false` to
+ *   run them all and get a summary of the failures
+ * - Run `cargo run -- --genesis <genesis.json>` to run the tests on top
+ *   of the accounts, block and fork of a geth genesis file
+ * - Tests run on as many threads as there are cores, or `--jobs <n>`
+ * - Run `cargo run -- --coverage` to list the opcodes and the instructions
+ *   of each test's code that no test executed
+ * - Run `cargo run -- --format json` or `--format junit` for a report of
+ *   every test for CI instead of the text output
  *
  * Hint: most people who were trying to learn Rust and EVM at the same
  * gave up and switched to JavaScript, Python, or Go. If you are new
  * to Rust, implement EVM in another programming language first.
  */
-use evm::evm;
+use evm::asm::assemble;
+use evm::assertions::{Assertion, AssertionInspector};
+use evm::config::{EvmConfig, Preset};
+use evm::coverage::Coverage;
+use evm::evm_with_inspector;
+use evm::genesis::Genesis;
+use evm::hexutil::{self, parse_u256};
+use evm::minimize::minimize;
+use evm::spec::{ChainConfig, SpecId};
 use evm::EvmContext;
 use evm::EvmData;
+use evm::EvmResult;
+use evm::Log;
 use evm::TxData;
+use evm::EXECUTION_STACK_SIZE;
 use primitive_types::U256;
 use serde::Deserialize;
+use serde_json::{json, Value};
 
 #[derive(Debug, Deserialize)]
 struct Evmtest {
@@ -29,146 +68,809 @@ struct Evmtest {
     expect: Expect,
     block: Option<EvmContext>,
     tx: Option<TxData>,
+    spec: Option<SpecId>,
+    preset: Option<Preset>,
+    assertions: Option<Vec<AssertionSpec>>,
+    blockhashes: Option<HashMap<String, String>>,
+    state: Option<HashMap<String, AccountSpec>>,
+}
+
+/// An account of the pre-state. Storage slots are keyed by slot.
+#[derive(Debug, Deserialize)]
+struct AccountSpec {
+    balance: Option<String>,
+    nonce: Option<String>,
+    code: Option<AccountCode>,
+    storage: Option<HashMap<String, String>>,
+}
+
+// Code of accounts is given like the code of tests, but only `bin` is read:
+// `asm` may be missing
+#[derive(Debug, Deserialize)]
+struct AccountCode {
+    bin: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AssertionSpec {
+    StackTop { pc: String, stack_top: String },
+    StorageWrites { slot: String, writes: String },
 }
 
 #[derive(Debug, Deserialize)]
 struct Code {
     asm: String,
-    bin: String,
+    /// Left out, the code is assembled from `asm`.
+    bin: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Expect {
     stack: Option<Vec<String>>,
     success: bool,
-    // #[serde(rename = "return")]
-    // ret: Option<String>,
+    gas_used: Option<String>,
+    gas_refunded: Option<String>,
+    #[serde(rename = "return")]
+    ret: Option<String>,
+    logs: Option<Vec<ExpectedLog>>,
 }
 
-fn main() {
-    let text = std::fs::read_to_string("../evm.json").unwrap();
-    let data: Vec<Evmtest> = serde_json::from_str(&text).unwrap();
+#[derive(Debug, Deserialize)]
+struct ExpectedLog {
+    address: String,
+    data: String,
+    topics: Vec<String>,
+}
+
+impl ExpectedLog {
+    fn to_log(&self) -> Log {
+        Log {
+            address: parse_u256(&self.address).unwrap(),
+            topics: self
+                .topics
+                .iter()
+                .map(|topic| parse_u256(topic).unwrap())
+                .collect(),
+            data: hexutil::decode(&self.data).unwrap(),
+        }
+    }
+}
+
+// The default suite, built in so the binary runs from any directory
+const DEFAULT_TESTS: &str = include_str!("../../evm.json");
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: evm [--tests <path or glob>]... [--filter <text>]... [--index <n or n-m>]... [--fail-fast=<true|false>] [--genesis <path>] [--coverage] [--jobs <n>] [--format <text|json|junit>]"
+    );
+    std::process::exit(2);
+}
+
+struct Options {
+    paths: Vec<String>,
+    filters: Vec<String>,
+    // Inclusive ranges of test numbers, counting from 1 as the runner prints
+    indices: Vec<(usize, usize)>,
+    fail_fast: bool,
+    // The state every test starts from, before its own accounts
+    genesis: Option<Genesis>,
+    coverage: bool,
+    // Threads running tests
+    jobs: usize,
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+    Junit,
+}
 
+// What running a test came to
+struct Outcome {
+    passed: bool,
+    // Unless the code did not assemble
+    result: Option<EvmResult>,
+    // What went wrong, printed under the test in the text output
+    output: String,
+    duration: Duration,
+}
+
+fn parse_index(text: &str) -> (usize, usize) {
+    let number = |text: &str| text.trim().parse::<usize>().unwrap_or_else(|_| usage());
+    match text.split_once('-') {
+        Some((first, last)) => (number(first), number(last)),
+        None => (number(text), number(text)),
+    }
+}
+
+fn options() -> Options {
+    let mut options = Options {
+        paths: vec![],
+        filters: vec![],
+        indices: vec![],
+        fail_fast: true,
+        genesis: None,
+        coverage: false,
+        jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        format: Format::Text,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        // Every option takes its value as the next argument or after `=`
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .unwrap_or_else(|| usage())
+        };
+        match name.as_str() {
+            "--tests" => options.paths.push(value()),
+            "--filter" => options.filters.push(value()),
+            "--index" => options.indices.push(parse_index(&value())),
+            "--genesis" => {
+                let path = resolve(PathBuf::from(value()));
+                let genesis = Genesis::load(&path).unwrap_or_else(|error| {
+                    eprintln!("Cannot load {}: {}", path.display(), error);
+                    std::process::exit(1);
+                });
+                options.genesis = Some(genesis);
+            }
+            "--jobs" => {
+                options.jobs = match value().parse() {
+                    Ok(jobs) if jobs > 0 => jobs,
+                    _ => usage(),
+                }
+            }
+            "--format" => {
+                options.format = match value().as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "junit" => Format::Junit,
+                    _ => usage(),
+                }
+            }
+            "--coverage" if inline.is_none() => options.coverage = true,
+            "--fail-fast" => {
+                options.fail_fast = match inline.as_deref() {
+                    None | Some("true") => true,
+                    Some("false") => false,
+                    Some(_) => usage(),
+                }
+            }
+            _ => usage(),
+        }
+    }
+    options
+}
+
+impl Options {
+    // Tests are numbered before filtering, so numbers stay the same whatever
+    // the filters
+    fn selects(&self, number: usize, test: &Evmtest) -> bool {
+        let by_name = self.filters.is_empty()
+            || self
+                .filters
+                .iter()
+                .any(|filter| test.name.contains(filter.as_str()));
+        let by_index = self.indices.is_empty()
+            || self
+                .indices
+                .iter()
+                .any(|(first, last)| (*first..=*last).contains(&number));
+        by_name && by_index
+    }
+}
+
+// Relative paths that do not exist from the working directory are looked up
+// from the crate, so `--tests ../evm.json` works wherever cargo runs from.
+fn resolve(path: PathBuf) -> PathBuf {
+    if path.is_relative() && !path.exists() {
+        let from_manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join(&path);
+        if from_manifest.exists() {
+            return from_manifest;
+        }
+    }
+    path
+}
+
+// `*` and `?` in the last component of a path match any files of its
+// directory, in name order.
+fn expand(pattern: &str) -> Vec<PathBuf> {
+    let path = PathBuf::from(pattern);
+    let file_pattern = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !file_pattern.contains(['*', '?']) {
+        return vec![resolve(path)];
+    }
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => resolve(parent.to_path_buf()),
+        _ => PathBuf::from("."),
+    };
+    let entries = std::fs::read_dir(&directory).unwrap_or_else(|error| {
+        eprintln!("Cannot read {}: {}", directory.display(), error);
+        std::process::exit(1);
+    });
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| wildcard_match(&file_pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Whether the first `j` characters of the text match the pattern so far
+    let mut matches = vec![false; text.len() + 1];
+    matches[0] = true;
+    for p in &pattern {
+        let mut next = vec![false; text.len() + 1];
+        for j in 0..=text.len() {
+            next[j] = match p {
+                '*' => matches[j] || (j > 0 && next[j - 1]),
+                '?' => j > 0 && matches[j - 1],
+                c => j > 0 && matches[j - 1] && text[j - 1] == *c,
+            };
+        }
+        matches = next;
+    }
+    matches[text.len()]
+}
+
+fn load_tests(paths: &[String]) -> Vec<Evmtest> {
+    if paths.is_empty() {
+        return serde_json::from_str(DEFAULT_TESTS).unwrap();
+    }
+    let mut tests = vec![];
+    for pattern in paths {
+        let files = expand(pattern);
+        if files.is_empty() {
+            eprintln!("No fixture files match {}", pattern);
+            std::process::exit(1);
+        }
+        for path in files {
+            let text = std::fs::read_to_string(&path).unwrap_or_else(|error| {
+                eprintln!("Cannot read {}: {}", path.display(), error);
+                std::process::exit(1);
+            });
+            let file_tests: Vec<Evmtest> = serde_json::from_str(&text).unwrap_or_else(|error| {
+                eprintln!("Cannot parse {}: {}", path.display(), error);
+                std::process::exit(1);
+            });
+            tests.extend(file_tests);
+        }
+    }
+    tests
+}
+
+fn main() {
+    let options = options();
+    let data = load_tests(&options.paths);
     let total = data.len();
 
-    for (index, test) in data.iter().enumerate() {
-        println!("Test {} of {}: {}", index + 1, total, test.name);
+    let selected: Vec<(usize, &Evmtest)> = data
+        .iter()
+        .enumerate()
+        .filter(|(index, test)| options.selects(index + 1, test))
+        .collect();
+    let mut failures = vec![];
+    let mut coverage = options.coverage.then(Coverage::new);
+    let mut failed_fast = false;
+    // The tests that ran, in order, with what they came to
+    let mut ran: Vec<(usize, &Evmtest, Outcome)> = vec![];
 
-        let code: Vec<u8> = hex::decode(&test.code.bin).unwrap();
+    // Workers take the next test until none are left, and their results
+    // are printed in the order of the tests as soon as the ones before are
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.jobs)
+            .map(|_| {
+                let sender = sender.clone();
+                let (selected, next, stop) = (&selected, &next, &stop);
+                let (genesis, with_coverage) = (options.genesis.as_ref(), options.coverage);
+                let worker = thread::Builder::new().stack_size(EXECUTION_STACK_SIZE);
+                worker
+                    .spawn_scoped(scope, move || {
+                        let mut coverage = with_coverage.then(Coverage::new);
+                        while !stop.load(Ordering::Relaxed) {
+                            let position = next.fetch_add(1, Ordering::Relaxed);
+                            let Some((_, test)) = selected.get(position) else {
+                                break;
+                            };
+                            let started = Instant::now();
+                            let mut output = String::new();
+                            let (passed, result) =
+                                run_test(test, genesis, coverage.as_mut(), &mut output);
+                            let outcome = Outcome {
+                                passed,
+                                result,
+                                output,
+                                duration: started.elapsed(),
+                            };
+                            if sender.send((position, outcome)).is_err() {
+                                break;
+                            }
+                        }
+                        coverage
+                    })
+                    .unwrap()
+            })
+            .collect();
+        drop(sender);
 
-        let mut state: HashMap<String, String> = HashMap::new();
-        let mut balances: HashMap<String, U256> = HashMap::new();
+        let text = options.format == Format::Text;
+        let mut done = BTreeMap::new();
+        'results: for (position, outcome) in receiver.iter() {
+            done.insert(position, outcome);
+            while let Some(outcome) = done.remove(&ran.len()) {
+                let (index, test) = selected[ran.len()];
+                let passed = outcome.passed;
+                if text {
+                    println!("Test {} of {}: {}", index + 1, total, test.name);
+                    print!("{}", outcome.output);
+                }
+                ran.push((index, test, outcome));
+                if passed {
+                    if text {
+                        println!("PASS");
+                    }
+                } else if options.fail_fast {
+                    if text {
+                        println!("Progress: {}/{}\n\n", index, total);
+                    }
+                    stop.store(true, Ordering::Relaxed);
+                    failed_fast = true;
+                    break 'results;
+                } else {
+                    if text {
+                        println!("FAIL\n");
+                    }
+                    failures.push((index + 1, &test.name));
+                }
+            }
+        }
+        drop(receiver);
 
-        balances.insert(
-            "173983468828192506341714248598145129238407026077".to_string(),
-            U256::from(256),
-        );
+        for worker in workers {
+            if let (Some(coverage), Some(worker_coverage)) =
+                (coverage.as_mut(), worker.join().unwrap())
+            {
+                coverage.merge(worker_coverage);
+            }
+        }
+    });
+    if options.format != Format::Text {
+        let report = match options.format {
+            Format::Json => serde_json::to_string_pretty(&json_report(&ran)).unwrap(),
+            _ => junit_report(&ran),
+        };
+        println!("{}", report);
+        if let Some(coverage) = &coverage {
+            eprintln!("Coverage\n{}", coverage.summary());
+        }
+        if ran.iter().any(|(_, _, outcome)| !outcome.passed) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    if failed_fast {
+        panic!("Test failed");
+    }
 
-        balances.insert(
-            "0x1e79b045dc29eae9fdc69673c9dcd7c53e5e159d".to_string(),
-            U256::from(512),
+    if !options.fail_fast {
+        println!();
+        if !failures.is_empty() {
+            println!("Summary");
+            println!("  {:>5}  {:<6}  Name", "Test", "Result");
+            for (number, name) in &failures {
+                println!("  {:>5}  {:<6}  {}", number, "FAIL", name);
+            }
+        }
+        println!(
+            "Passed {} of {} selected tests, {} failed\n",
+            selected.len() - failures.len(),
+            selected.len(),
+            failures.len()
         );
+        if !failures.is_empty() {
+            std::process::exit(1);
+        }
+    }
+    if let Some(coverage) = &coverage {
+        println!("\nCoverage\n{}", coverage.summary());
+    }
+    println!("Congratulations!");
+}
 
-        state.insert(
-            "91343852333181432387730302044767688728495786666".to_string(),
-            "2".to_string(),
-        );
+fn hex_words<'a>(words: impl IntoIterator<Item = &'a U256>) -> Vec<String> {
+    words
+        .into_iter()
+        .map(|word| format!("{:#x}", word))
+        .collect()
+}
 
-        state.insert(
-            "91343852333181432387730302044767688728495787074".to_string(),
-            "60426000526001601ff3".to_string(),
-        );
+// The stack and gas a test expects, read as numbers
+fn expected(test: &Evmtest) -> (Vec<U256>, Option<U256>) {
+    let stack = test
+        .expect
+        .stack
+        .iter()
+        .flatten()
+        .map(|value| parse_u256(value).unwrap())
+        .collect();
+    let gas_used = test
+        .expect
+        .gas_used
+        .as_ref()
+        .map(|gas| parse_u256(gas).unwrap());
+    (stack, gas_used)
+}
 
-        state.insert(
-            "91343852333181432387730302044767688728495787075".to_string(),
-            "3360005260206000f3".to_string(),
-        );
+// Every test that ran, with what it expected next to what it got
+fn json_report(ran: &[(usize, &Evmtest, Outcome)]) -> Value {
+    let tests: Vec<Value> = ran
+        .iter()
+        .map(|(index, test, outcome)| {
+            let (stack, gas_used) = expected(test);
+            let mut report = json!({
+                "number": index + 1,
+                "name": test.name,
+                "passed": outcome.passed,
+                "duration": outcome.duration.as_secs_f64(),
+                "expected": {
+                    "success": test.expect.success,
+                    "stack": hex_words(&stack),
+                    "gasUsed": gas_used.map(|gas| gas.as_u64()),
+                },
+            });
+            if let Some(result) = &outcome.result {
+                report["actual"] = json!({
+                    "success": result.success,
+                    "stack": hex_words(&result.stack),
+                    "gasUsed": result.gas_used,
+                });
+            }
+            if !outcome.passed {
+                report["output"] = json!(outcome.output);
+            }
+            report
+        })
+        .collect();
+    let failed = ran.iter().filter(|(_, _, outcome)| !outcome.passed).count();
+    json!({
+        "passed": ran.len() - failed,
+        "failed": failed,
+        "tests": tests,
+    })
+}
 
-        state.insert(
-            "91343852333181432387730302044767688728495787076".to_string(),
-            "60426000526001601ffd".to_string(),
-        );
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
 
-        state.insert(
-            "1266634752353449195776526855020778617035141537245".to_string(),
-            "30600055".to_string(),
-        );
+// What a failed test got wrong, in a few words
+fn failure_message(test: &Evmtest, outcome: &Outcome) -> String {
+    let Some(result) = &outcome.result else {
+        return "the fixture code is invalid".to_string();
+    };
+    let (stack, gas_used) = expected(test);
+    if result.success != test.expect.success {
+        format!(
+            "expected success {}, got {}",
+            test.expect.success, result.success
+        )
+    } else if result.stack != stack {
+        format!(
+            "expected stack [{}], got [{}]",
+            hex_words(&stack).join(", "),
+            hex_words(&result.stack).join(", ")
+        )
+    } else if gas_used.is_some_and(|gas| gas != U256::from(result.gas_used)) {
+        format!(
+            "expected {} gas used, got {}",
+            gas_used.unwrap(),
+            result.gas_used
+        )
+    } else {
+        "the result does not match".to_string()
+    }
+}
 
-        state.insert(
-            "0".to_string(),
-            "0x1000000000000000000000000000000000000AAA".to_string(),
+// The tests as a JUnit XML suite, with what went wrong in each failure
+fn junit_report(ran: &[(usize, &Evmtest, Outcome)]) -> String {
+    let failed = ran.iter().filter(|(_, _, outcome)| !outcome.passed).count();
+    let time: f64 = ran
+        .iter()
+        .map(|(_, _, outcome)| outcome.duration.as_secs_f64())
+        .sum();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml += &format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" time=\"{:.6}\">\n",
+        ran.len(),
+        failed,
+        time
+    );
+    xml += &format!(
+        "  <testsuite name=\"evm\" tests=\"{}\" failures=\"{}\" time=\"{:.6}\">\n",
+        ran.len(),
+        failed,
+        time
+    );
+    for (_, test, outcome) in ran {
+        xml += &format!(
+            "    <testcase name=\"{}\" classname=\"evm\" time=\"{:.6}\"",
+            xml_escape(&test.name),
+            outcome.duration.as_secs_f64()
         );
-
-        state.insert(
-            "91343852333181432387730302044767688728495787080".to_string(),
-            "6042600055".to_string(),
+        if outcome.passed {
+            xml += "/>\n";
+            continue;
+        }
+        xml += &format!(
+            ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+            xml_escape(&failure_message(test, outcome)),
+            xml_escape(&outcome.output)
         );
+    }
+    xml + "  </testsuite>\n</testsuites>"
+}
 
-        state.insert(
-            "10".to_string(),
-            "6001000000000000000000000000000000000000000000000000000000000000".to_string(),
-        );
+fn format_log(log: &Log) -> String {
+    let topics: Vec<String> = log
+        .topics
+        .iter()
+        .map(|topic| format!("{:#x}", topic))
+        .collect();
+    format!(
+        "{:#x} [{}] {}",
+        log.address,
+        topics.join(", "),
+        hexutil::encode_prefixed(&log.data)
+    )
+}
 
-        state.insert(
-            "19".to_string(),
-            "0xffffffff00000000000000000000000000000000000000000000000000000000".to_string(),
-        );
+// Runs one fixture and writes what went wrong to `out` if it fails, adding
+// what it executed to `coverage`.
+fn run_test(
+    test: &Evmtest,
+    genesis: Option<&Genesis>,
+    coverage: Option<&mut Coverage>,
+    out: &mut String,
+) -> (bool, Option<EvmResult>) {
+    let code = match assemble(&test.code.asm) {
+        Ok(assembled) => assembled,
+        Err(error) => {
+            outln!(out, "Instructions: \n{}\n", test.code.asm);
+            outln!(out, "Fixture instructions do not assemble: {}\n", error);
+            return (false, None);
+        }
+    };
+    if let Some(bin) = &test.code.bin {
+        if hexutil::decode(bin).unwrap() != code {
+            outln!(out, "Instructions: \n{}\n", test.code.asm);
+            outln!(out, "Assemble to: {}", hexutil::encode(&code));
+            outln!(out, "Bytecode: {}\n", bin);
+            outln!(out, "Fixture code does not match its instructions\n");
+            return (false, None);
+        }
+    }
 
-        state.insert(
-            "1271253980042238172183243620132319847648413671085".to_string(),
-            "73a1c300000000000000000000000000000000a1c3ff".to_string(),
-        );
+    let genesis = genesis.map(Genesis::evm_data).unwrap_or_default();
+    let mut state = genesis.state;
+    let mut balances = genesis.balances;
+    let mut nonces = genesis.nonces;
+    let mut codes = genesis.codes;
+    for (address, account) in test.state.iter().flatten() {
+        // The maps are keyed by the address in decimal
+        let address = parse_u256(address).unwrap().to_string();
+        if let Some(balance) = &account.balance {
+            balances.insert(address.clone(), parse_u256(balance).unwrap());
+        }
+        if let Some(nonce) = &account.nonce {
+            nonces.insert(address.clone(), parse_u256(nonce).unwrap().as_u64());
+        }
+        if let Some(code) = &account.code {
+            let code = hexutil::decode(&code.bin).unwrap();
+            codes.insert(address.clone(), code.into());
+        }
+        for (key, value) in account.storage.iter().flatten() {
+            let slots = state.entry(address.clone()).or_default();
+            slots.insert(parse_u256(key).unwrap(), parse_u256(value).unwrap());
+        }
+    }
 
-        let mut evm_data = EvmData {
-            context: test.block.clone(),
-            tx_data: test.tx.clone(),
-            state: state,
-            balances: balances,
-        };
+    let mut block_hashes: HashMap<U256, U256> = HashMap::new();
+    for (number, hash) in test.blockhashes.iter().flatten() {
+        block_hashes.insert(parse_u256(number).unwrap(), parse_u256(hash).unwrap());
+    }
 
-        let result = evm(&code, &mut evm_data, true);
+    let mut evm_data = EvmData {
+        context: test.block.clone().or(genesis.context),
+        tx_data: test.tx.clone(),
+        state,
+        balances,
+        nonces,
+        codes,
+        config: test.spec.map_or(genesis.config, ChainConfig::new),
+        evm_config: EvmConfig::preset(test.preset.unwrap_or_default()),
+        database: Some(Arc::new(block_hashes)),
+        ..Default::default()
+    };
 
-        let mut expected_stack: Vec<U256> = Vec::new();
-        if let Some(ref stacks) = test.expect.stack {
-            for value in stacks {
-                expected_stack.push(U256::from_str_radix(value, 16).unwrap());
-            }
+    let mut assertions = Vec::new();
+    for assertion in test.assertions.iter().flatten() {
+        assertions.push(match assertion {
+            AssertionSpec::StackTop { pc, stack_top } => Assertion::StackTop {
+                pc: parse_u256(pc).unwrap().as_usize(),
+                value: parse_u256(stack_top).unwrap(),
+            },
+            AssertionSpec::StorageWrites { slot, writes } => Assertion::StorageWrites {
+                key: parse_u256(slot).unwrap(),
+                count: parse_u256(writes).unwrap().as_usize(),
+            },
+        });
+    }
+    let mut inspector = AssertionInspector::new(assertions);
+
+    let pre_state = evm_data.clone();
+    let result = match coverage {
+        Some(coverage) => {
+            coverage.name(&code, &test.name);
+            evm_with_inspector(&code, &mut evm_data, true, &mut (&mut inspector, coverage))
         }
+        None => evm_with_inspector(&code, &mut evm_data, true, &mut inspector),
+    };
 
-        let mut matching = result.stack.len() == expected_stack.len();
-        if matching {
-            for i in 0..result.stack.len() {
-                if result.stack[i] != expected_stack[i] {
-                    matching = false;
-                    break;
-                }
-            }
+    let mut expected_stack: Vec<U256> = Vec::new();
+    if let Some(ref stacks) = test.expect.stack {
+        for value in stacks {
+            expected_stack.push(parse_u256(value).unwrap());
         }
+    }
+
+    let mut matching = result.stack == expected_stack;
+
+    matching = matching && result.success == test.expect.success;
+
+    let gas_matches = |expected: &Option<String>, actual: u64| {
+        expected
+            .as_ref()
+            .is_none_or(|gas| parse_u256(gas).unwrap() == U256::from(actual))
+    };
+    matching = matching && gas_matches(&test.expect.gas_used, result.gas_used);
+    matching = matching && gas_matches(&test.expect.gas_refunded, result.gas_refunded);
 
-        matching = matching && result.success == test.expect.success;
+    let expected_return = test
+        .expect
+        .ret
+        .as_ref()
+        .map(|ret| hexutil::decode(ret).unwrap());
+    let return_matches = |result: &EvmResult| {
+        expected_return
+            .as_ref()
+            .is_none_or(|ret| *ret == result.return_data)
+    };
+    matching = matching && return_matches(&result);
 
-        if !matching {
-            println!("Instructions: \n{}\n", test.code.asm);
+    let expected_logs: Option<Vec<Log>> = test
+        .expect
+        .logs
+        .as_ref()
+        .map(|logs| logs.iter().map(ExpectedLog::to_log).collect());
+    let logs_match = expected_logs
+        .as_ref()
+        .is_none_or(|logs| *logs == result.logs);
+    matching = matching && logs_match;
 
-            println!("Expected success: {:?}", test.expect.success);
-            println!("Expected stack: [");
-            for v in expected_stack {
-                println!("  {:#X},", v);
+    let failed_assertions = inspector.failures();
+    matching = matching && failed_assertions.is_empty();
+
+    if !matching {
+        outln!(out, "Instructions: \n{}\n", test.code.asm);
+
+        outln!(out, "Expected success: {:?}", test.expect.success);
+        outln!(out, "Expected stack: [");
+        for v in &expected_stack {
+            outln!(out, "  {:#X},", v);
+        }
+        outln!(out, "]\n");
+
+        if test.expect.gas_used.is_some() || test.expect.gas_refunded.is_some() {
+            outln!(
+                out,
+                "Expected gas used: {:?}, refunded: {:?}",
+                test.expect.gas_used,
+                test.expect.gas_refunded
+            );
+            outln!(
+                out,
+                "Actual gas used: {:#x}, refunded: {:#x}\n",
+                result.gas_used,
+                result.gas_refunded
+            );
+        }
+
+        if let Some(ref ret) = expected_return {
+            outln!(out, "Expected return: {}", hexutil::encode(ret));
+            outln!(
+                out,
+                "Actual return: {}",
+                hexutil::encode(&result.return_data)
+            );
+            if let Some(ref origin) = result.return_origin {
+                outln!(out, "Returned by the {}", origin);
             }
-            println!("]\n");
+            outln!(out);
+        }
 
-            println!("Actual success: {:?}", result.success);
-            println!("Actual stack: [");
-            for v in result.stack {
-                println!("  {:#X},", v);
+        if !logs_match {
+            outln!(out, "Expected logs:");
+            for log in expected_logs.iter().flatten() {
+                outln!(out, "  {}", format_log(log));
             }
-            println!("]\n");
+            outln!(out, "Actual logs:");
+            for log in &result.logs {
+                outln!(out, "  {}", format_log(log));
+            }
+            outln!(out);
+        }
 
-            println!("\nHint: {}\n", test.hint);
-            println!("Progress: {}/{}\n\n", index, total);
-            panic!("Test failed");
+        outln!(out, "Actual success: {:?}", result.success);
+        if let Some(reason) = result.halt_reason {
+            outln!(out, "Halted by: {:?}", reason);
         }
-        println!("PASS");
+        outln!(out, "Actual stack: [");
+        for v in &result.stack {
+            outln!(out, "  {:#X},", v);
+        }
+        outln!(out, "]\n");
+
+        if !failed_assertions.is_empty() {
+            outln!(out, "Failed assertions:");
+            for failure in failed_assertions {
+                outln!(out, "  {}", failure);
+            }
+            outln!(out);
+        }
+
+        let minimized = minimize(&code, &pre_state, true, |result| {
+            result.stack != expected_stack
+                || result.success != test.expect.success
+                || !return_matches(result)
+        });
+        if let Some(minimized) = minimized {
+            let fixture = minimized.to_fixture(
+                &test.name,
+                &test.hint,
+                &code,
+                &expected_stack,
+                test.expect.success,
+            );
+            outln!(
+                out,
+                "Minimized fixture:\n{}\n",
+                serde_json::to_string_pretty(&fixture).unwrap()
+            );
+        }
+
+        outln!(out, "\nHint: {}\n", test.hint);
+        return (false, Some(result));
     }
-    println!("Congratulations!");
+    (true, Some(result))
 }
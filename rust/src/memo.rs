@@ -0,0 +1,261 @@
+//! Memoized calls: a call that runs the same code in the same block and
+//! transaction as one before it comes to the same result as long as what it
+//! read is unchanged, so `CallCache` keeps each result with the accounts and
+//! slots the call read and hands it back while they still hold.
+//!
+//! Calls are told apart by the hash of their code, their block context,
+//! their transaction and the configuration they run with. Block hashes are
+//! taken to stay the same for the same context, and runs with cheatcodes or
+//! instructions of their own, which can read anything, are never cached.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use primitive_types::{H256, U256};
+use serde_json::json;
+
+use crate::delegation;
+use crate::host::{AccountInfo, Database};
+use crate::inspector::{Inspector, Step};
+use crate::trie::keccak;
+use crate::{evm_with_inspector, hexutil, EvmData, EvmResult};
+
+/// What tells a call apart from others, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallKey(pub H256);
+
+impl CallKey {
+    /// The key of running `code` on `data`, `None` for runs that cannot be
+    /// cached.
+    pub fn new(code: &[u8], data: &EvmData, writable: bool) -> Option<Self> {
+        if data.evm_config.cheatcodes || !data.instructions.is_empty() {
+            return None;
+        }
+        let run = json!({
+            "code": hexutil::encode(keccak(code)),
+            "context": data.context,
+            "tx": data.tx_data,
+            "writable": writable,
+            "config": format!("{:?} {:?}", data.config, data.evm_config),
+            "precompiles": data.precompiles.keys().map(|address| address.to_string()).collect::<Vec<_>>(),
+        });
+        Some(CallKey(keccak(run.to_string().as_bytes())))
+    }
+}
+
+/// An account or slot as a call first read it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Read {
+    Account {
+        address: U256,
+        info: Option<AccountInfo>,
+        code: Option<Vec<u8>>,
+    },
+    Slot {
+        address: U256,
+        key: U256,
+        value: U256,
+    },
+}
+
+impl Read {
+    fn holds(&self, data: &EvmData) -> bool {
+        match self {
+            Read::Account {
+                address,
+                info,
+                code,
+            } => data.basic(*address) == *info && data.code(*address) == *code,
+            Read::Slot {
+                address,
+                key,
+                value,
+            } => data.storage(*address, *key) == *value,
+        }
+    }
+}
+
+/// Records what a call reads, as it was before the call changed it.
+#[derive(Debug, Clone, Default)]
+pub struct ReadSet {
+    reads: Vec<Read>,
+    accounts: HashSet<U256>,
+    slots: HashSet<(U256, U256)>,
+}
+
+impl ReadSet {
+    /// Starts with the recipient of the transaction of `data`, whose code
+    /// the call runs.
+    pub fn new(data: &EvmData) -> Self {
+        let mut reads = ReadSet::default();
+        if let Some(to) = data.tx_data.as_ref().and_then(|tx| tx.to) {
+            reads.account(data, to);
+        }
+        reads
+    }
+
+    pub fn reads(&self) -> &[Read] {
+        &self.reads
+    }
+
+    fn account(&mut self, data: &EvmData, address: U256) {
+        if !self.accounts.insert(address) {
+            return;
+        }
+        let code = data.code(address);
+        self.reads.push(Read::Account {
+            address,
+            info: data.basic(address),
+            code: code.clone(),
+        });
+        // A delegation runs the code of its delegate (EIP-7702)
+        if let Some(delegate) = code.as_deref().and_then(delegation::delegate) {
+            self.account(data, delegate);
+        }
+    }
+
+    fn slot(&mut self, data: &EvmData, address: U256, key: U256) {
+        if !self.slots.insert((address, key)) {
+            return;
+        }
+        self.reads.push(Read::Slot {
+            address,
+            key,
+            value: data.storage(address, key),
+        });
+    }
+}
+
+impl Inspector for ReadSet {
+    fn step(&mut self, step: &Step) {
+        let stack = step.stack;
+        let top = |n: usize| stack.len().checked_sub(n + 1).map(|index| stack[index]);
+        let current = step.data.env.tx.address;
+        match step.opcode {
+            // SLOAD, SSTORE
+            0x54 | 0x55 => {
+                if let Some(key) = top(0) {
+                    self.slot(step.data, current, key);
+                }
+            }
+            // BALANCE, EXTCODESIZE, EXTCODECOPY, EXTCODEHASH
+            0x31 | 0x3b | 0x3c | 0x3f => {
+                if let Some(address) = top(0) {
+                    self.account(step.data, address);
+                }
+            }
+            // CALL, CALLCODE, DELEGATECALL, STATICCALL
+            0xf1 | 0xf2 | 0xf4 | 0xfa => {
+                if let Some(address) = top(1) {
+                    self.account(step.data, address);
+                }
+            }
+            // SELFBALANCE, CREATE, CREATE2
+            0x47 | 0xf0 | 0xf5 => self.account(step.data, current),
+            // SELFDESTRUCT
+            0xff => {
+                self.account(step.data, current);
+                if let Some(beneficiary) = top(0) {
+                    self.account(step.data, beneficiary);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Results kept up to `capacity` calls, evicting the least recently used
+/// one when full.
+#[derive(Debug, Clone)]
+pub struct CallCache {
+    capacity: usize,
+    // Each entry with the tick it was last used at, and the entries by tick
+    entries: HashMap<CallKey, (Vec<Read>, EvmResult, u64)>,
+    recency: BTreeMap<u64, CallKey>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for CallCache {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl CallCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// The result of the call `key` if what it read is the same in `data`.
+    pub fn get(&mut self, key: &CallKey, data: &EvmData) -> Option<EvmResult> {
+        let entry = self
+            .entries
+            .get_mut(key)
+            .filter(|(reads, _, _)| reads.iter().all(|read| read.holds(data)));
+        let Some((_, result, used)) = entry else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.tick += 1;
+        self.recency.remove(used);
+        self.recency.insert(self.tick, *key);
+        *used = self.tick;
+        Some(result.clone())
+    }
+
+    /// Keeps `result` for the call `key`, which read `reads`.
+    pub fn insert(&mut self, key: CallKey, reads: ReadSet, result: EvmResult) {
+        self.tick += 1;
+        if let Some((_, _, used)) = self.entries.insert(key, (reads.reads, result, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            let (_, oldest) = self.recency.pop_first().unwrap();
+            self.entries.remove(&oldest);
+        }
+    }
+
+    /// Runs `code` on `data` like `evm`, or hands back the result of the
+    /// same call when what it read is unchanged. For calls whose changes are
+    /// thrown away, as those of `eth_call` are: a result handed back leaves
+    /// `data` as it is.
+    pub fn run(&mut self, code: &[u8], data: &mut EvmData, writable: bool) -> EvmResult {
+        let Some(key) = CallKey::new(code, data, writable) else {
+            return evm_with_inspector(code, data, writable, &mut ());
+        };
+        if let Some(result) = self.get(&key, data) {
+            return result;
+        }
+        let mut reads = ReadSet::new(data);
+        let result = evm_with_inspector(code, data, writable, &mut reads);
+        self.insert(key, reads, result.clone());
+        result
+    }
+}
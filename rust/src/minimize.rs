@@ -0,0 +1,220 @@
+use std::collections::BTreeSet;
+use std::panic::{self, AssertUnwindSafe};
+
+use primitive_types::U256;
+use serde_json::{json, Map, Value};
+
+use crate::hexutil::{self, Bytes};
+use crate::witness::{Access, WitnessTracer};
+use crate::{evm, evm_with_inspector, opcodes, EvmData, EvmResult};
+
+/// The smallest pre-state found that still reproduces a failure.
+#[derive(Debug, Clone)]
+pub struct Minimized {
+    pub data: EvmData,
+    /// Everything the original execution touched, as seen by `WitnessTracer`.
+    pub accesses: BTreeSet<Access>,
+}
+
+/// Shrinks the state of `pre` to the entries needed for `reproduces` to keep
+/// returning true, for turning a failing test or replayed transaction into a
+/// small regression fixture. Returns `None` if `pre` itself does not reproduce.
+///
+/// The state is first cut down to the witness of the original execution, then
+/// entries are dropped one at a time while the failure persists. Candidates
+/// that make the interpreter panic count as not reproducing; the panic hook is
+/// silenced while they run.
+pub fn minimize<F>(code: &[u8], pre: &EvmData, writable: bool, reproduces: F) -> Option<Minimized>
+where
+    F: Fn(&EvmResult) -> bool,
+{
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let minimized = shrink(code, pre, writable, &reproduces);
+    panic::set_hook(hook);
+    minimized
+}
+
+fn shrink<F>(code: &[u8], pre: &EvmData, writable: bool, reproduces: &F) -> Option<Minimized>
+where
+    F: Fn(&EvmResult) -> bool,
+{
+    let run = |data: &EvmData| {
+        let mut data = data.clone();
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            reproduces(&evm(code, &mut data, writable))
+        }))
+        .unwrap_or(false)
+    };
+
+    let mut witness = WitnessTracer::default();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut data = pre.clone();
+        evm_with_inspector(code, &mut data, writable, &mut witness)
+    }));
+    match result {
+        Ok(result) if reproduces(&result) => {}
+        _ => return None,
+    }
+    let accesses = witness.into_accesses();
+
+    let mut data = pre.clone();
+    for (address, slots) in data.state.iter_mut() {
+        let address = parse_key(address);
+        slots.retain(|key, _| accesses.contains(&Access::Storage(address, *key)));
+    }
+    data.state.retain(|_, slots| !slots.is_empty());
+    data.codes
+        .retain(|address, _| accesses.contains(&Access::Code(parse_key(address))));
+    data.balances
+        .retain(|address, _| accesses.contains(&Access::Balance(parse_key(address))));
+    if !run(&data) {
+        // The witness missed something the execution needed, start over.
+        data = pre.clone();
+    }
+
+    let mut slots: Vec<(String, U256)> = data
+        .state
+        .iter()
+        .flat_map(|(address, slots)| slots.keys().map(|key| (address.clone(), *key)))
+        .collect();
+    slots.sort();
+    for (address, key) in slots {
+        let mut candidate = data.clone();
+        candidate.state.get_mut(&address).unwrap().remove(&key);
+        if run(&candidate) {
+            data = candidate;
+        }
+    }
+
+    let mut addresses: Vec<String> = data.codes.keys().cloned().collect();
+    addresses.sort();
+    for address in addresses {
+        let mut candidate = data.clone();
+        candidate.codes.remove(&address);
+        if run(&candidate) {
+            data = candidate;
+        }
+    }
+
+    let mut addresses: Vec<String> = data.balances.keys().cloned().collect();
+    addresses.sort();
+    for address in addresses {
+        let mut candidate = data.clone();
+        candidate.balances.remove(&address);
+        if run(&candidate) {
+            data = candidate;
+        }
+    }
+
+    let mut addresses: Vec<String> = data.nonces.keys().cloned().collect();
+    addresses.sort();
+    for address in addresses {
+        let mut candidate = data.clone();
+        candidate.nonces.remove(&address);
+        if run(&candidate) {
+            data = candidate;
+        }
+    }
+
+    Some(Minimized { data, accesses })
+}
+
+// State maps are keyed by decimal strings, a few hardcoded ones by hex.
+fn parse_key(key: &str) -> U256 {
+    match key.strip_prefix("0x") {
+        Some(_) => hexutil::parse_u256(key).unwrap_or_default(),
+        None => U256::from_dec_str(key).unwrap_or_default(),
+    }
+}
+
+impl Minimized {
+    /// Writes the minimized case in the format of `evm.json`.
+    pub fn to_fixture(
+        &self,
+        name: &str,
+        hint: &str,
+        code: &[u8],
+        expect_stack: &[U256],
+        expect_success: bool,
+    ) -> Value {
+        let mut accounts = Map::new();
+
+        let mut state: Vec<(U256, U256, U256)> = self
+            .data
+            .state
+            .iter()
+            .flat_map(|(address, slots)| {
+                let address = parse_key(address);
+                slots
+                    .iter()
+                    .map(move |(key, value)| (address, *key, *value))
+            })
+            .collect();
+        state.sort();
+        for (address, key, value) in state {
+            let account = account(&mut accounts, address);
+            if account["storage"].is_null() {
+                account["storage"] = json!({});
+            }
+            account["storage"][format!("{:#x}", key)] = json!(format!("{:#x}", value));
+        }
+
+        let mut codes: Vec<(&String, &Bytes)> = self.data.codes.iter().collect();
+        codes.sort_by_key(|(address, _)| *address);
+        for (address, code) in codes {
+            account(&mut accounts, parse_key(address))["code"] = json!({
+                "asm": opcodes::disassemble(code),
+                "bin": hexutil::encode(code),
+            });
+        }
+
+        let mut balances: Vec<(&String, &U256)> = self.data.balances.iter().collect();
+        balances.sort();
+        for (address, balance) in balances {
+            account(&mut accounts, parse_key(address))["balance"] =
+                json!(format!("{:#x}", balance));
+        }
+
+        let mut fixture = json!({
+            "name": name,
+            "hint": hint,
+            "code": {
+                "asm": opcodes::disassemble(code),
+                "bin": hexutil::encode(code),
+            },
+            "expect": {
+                "stack": expect_stack.iter().map(|v| format!("{:#x}", v)).collect::<Vec<_>>(),
+                "success": expect_success,
+            },
+        });
+        if !accounts.is_empty() {
+            fixture["state"] = Value::Object(accounts);
+        }
+        if let Some(context) = &self.data.context {
+            fixture["block"] = without_nulls(json!(context));
+        }
+        if let Some(tx_data) = &self.data.tx_data {
+            fixture["tx"] = without_nulls(json!(tx_data));
+        }
+        fixture
+    }
+}
+
+fn account(accounts: &mut Map<String, Value>, address: U256) -> &mut Value {
+    accounts
+        .entry(format!("{:#x}", address))
+        .or_insert_with(|| json!({}))
+}
+
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
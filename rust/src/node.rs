@@ -0,0 +1,510 @@
+//! A development node in the manner of Anvil and Hardhat: transactions sent
+//! to it go through a pool and are mined into blocks with receipts, on the
+//! spot unless auto-mining is turned off. Cheats set balances, code, nonces,
+//! storage and the time of the next block directly.
+//!
+//! Transactions name their sender, as in `eth_sendTransaction`: there are
+//! no keys and any address can send. Raw ones have it recovered from their
+//! signature. Blocks have no base fee, so EIP-1559 transactions pay their
+//! max fee, and the gas price is zero unless given.
+//!
+//! Block hashes are the keccak of the fields kept here rather than of a full
+//! header, so they are stable but do not match any real chain.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use primitive_types::{H256, U256};
+use serde_json::{json, Value};
+
+use crate::block::Bloom;
+use crate::call::{CallRequest, StateOverrides};
+use crate::deploy::CODE_DEPOSIT_COST;
+use crate::fmtutil::hex_address;
+use crate::gas::{init_code_cost, intrinsic_gas, TX_CREATE_COST};
+use crate::host::Database;
+use crate::journal::SnapshotId;
+use crate::service::{EvmService, ServiceError};
+use crate::t8n::{self, Transaction};
+use crate::trie::{keccak, ordered_trie_root};
+use crate::tx::Transaction as SignedTransaction;
+use crate::{rlp, EvmContext, EvmData, Log, TxData};
+
+/// The chain id Anvil and Hardhat use.
+pub const DEV_CHAIN_ID: u64 = 31337;
+
+pub const DEV_GAS_LIMIT: u64 = 30_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeError {
+    /// The transaction could not be read.
+    InvalidTransaction(String),
+    /// The transaction cannot be included: a wrong nonce, too little gas or
+    /// too little balance for it.
+    Rejected(String),
+    /// The next block would not come after the latest one.
+    TimestampTooLow { timestamp: u64, latest: u64 },
+    /// Raw transactions carry no sender, only a signature to recover it
+    /// from, which needs the `k256` feature.
+    RawTransactionsUnsupported,
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::InvalidTransaction(reason) => write!(f, "invalid transaction: {}", reason),
+            NodeError::Rejected(reason) => write!(f, "{}", reason),
+            NodeError::TimestampTooLow { timestamp, latest } => write!(
+                f,
+                "timestamp {} is not after the latest block's {}",
+                timestamp, latest
+            ),
+            NodeError::RawTransactionsUnsupported => write!(
+                f,
+                "raw transactions need the k256 feature to recover their sender"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinedBlock {
+    pub number: u64,
+    pub hash: H256,
+    pub parent_hash: H256,
+    pub timestamp: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub coinbase: U256,
+    /// The hashes of the transactions, in order.
+    pub transactions: Vec<H256>,
+    pub bloom: Bloom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxReceipt {
+    pub transaction_hash: H256,
+    pub transaction_index: usize,
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub from: U256,
+    /// `None` for contract creations.
+    pub to: Option<U256>,
+    pub contract_address: Option<U256>,
+    pub gas_price: U256,
+    pub success: bool,
+    pub gas_used: u64,
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+    pub bloom: Bloom,
+    /// What the transaction returned, or reverted with.
+    pub output: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingTx {
+    hash: H256,
+    tx: Transaction,
+}
+
+// What a snapshot keeps besides the state
+#[derive(Debug, Clone)]
+struct NodeSnapshot {
+    blocks: usize,
+    pool: Vec<PendingTx>,
+    next_timestamp: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DevNode {
+    data: EvmData,
+    chain_id: u64,
+    coinbase: U256,
+    gas_limit: u64,
+    automine: bool,
+    pool: Vec<PendingTx>,
+    blocks: Vec<MinedBlock>,
+    receipts: HashMap<H256, TxReceipt>,
+    next_timestamp: Option<u64>,
+    snapshots: Vec<NodeSnapshot>,
+}
+
+impl DevNode {
+    /// A node whose genesis block holds `data`, auto-mining.
+    pub fn new(data: EvmData, chain_id: u64) -> Self {
+        let mut node = Self {
+            data,
+            chain_id,
+            coinbase: U256::zero(),
+            gas_limit: DEV_GAS_LIMIT,
+            automine: true,
+            pool: vec![],
+            blocks: vec![],
+            receipts: HashMap::new(),
+            next_timestamp: None,
+            snapshots: vec![],
+        };
+        let genesis = node.next_block();
+        node.seal(genesis, vec![], 0, Bloom::default());
+        node
+    }
+
+    /// The state as of the latest block, with its block context.
+    pub fn state(&self) -> &EvmData {
+        &self.data
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.latest().number
+    }
+
+    pub fn latest(&self) -> &MinedBlock {
+        self.blocks.last().unwrap()
+    }
+
+    pub fn block(&self, number: u64) -> Option<&MinedBlock> {
+        self.blocks.get(number as usize)
+    }
+
+    pub fn block_by_hash(&self, hash: H256) -> Option<&MinedBlock> {
+        self.blocks.iter().find(|block| block.hash == hash)
+    }
+
+    pub fn receipt(&self, hash: H256) -> Option<&TxReceipt> {
+        self.receipts.get(&hash)
+    }
+
+    /// The hashes of the transactions waiting to be mined.
+    pub fn pending(&self) -> Vec<H256> {
+        self.pool.iter().map(|pending| pending.hash).collect()
+    }
+
+    /// The nonce the next transaction of `address` takes, counting the ones
+    /// in the pool.
+    pub fn next_nonce(&self, address: U256) -> u64 {
+        let queued = self
+            .pool
+            .iter()
+            .filter(|pending| pending.tx.sender == address);
+        self.data.basic(address).unwrap_or_default().nonce + queued.count() as u64
+    }
+
+    pub fn automine(&self) -> bool {
+        self.automine
+    }
+
+    /// Without auto-mining, transactions wait in the pool for `mine`.
+    pub fn set_automine(&mut self, automine: bool) {
+        self.automine = automine;
+    }
+
+    /// Takes a transaction in the JSON of `eth_sendTransaction` and returns
+    /// its hash. The nonce defaults to the sender's next one and the gas to
+    /// an estimate against the latest block, so a transaction that would
+    /// fail there needs its gas given.
+    ///
+    /// When auto-mining, the transaction is mined in a block of its own and
+    /// one that cannot be included is an error. Otherwise it joins the pool
+    /// and is only checked when mined.
+    pub fn send_transaction(&mut self, request: &Value) -> Result<H256, NodeError> {
+        let mut request = request.clone();
+        let sender = Transaction::parse(&request, self.chain_id)
+            .map_err(NodeError::InvalidTransaction)?
+            .sender;
+        if request["nonce"].is_null() {
+            request["nonce"] = json!(format!("{:#x}", self.next_nonce(sender)));
+        }
+        if request["gas"].is_null() && request["gasLimit"].is_null() {
+            request["gas"] = json!(format!("{:#x}", self.estimate_gas(&request)?));
+        }
+        let tx =
+            Transaction::parse(&request, self.chain_id).map_err(NodeError::InvalidTransaction)?;
+        self.submit(tx)
+    }
+
+    // Pools or mines `tx`, as auto-mining has it
+    fn submit(&mut self, tx: Transaction) -> Result<H256, NodeError> {
+        let hash = keccak(&tx.rlp());
+
+        if !self.automine {
+            self.pool.push(PendingTx { hash, tx });
+            return Ok(hash);
+        }
+        let next_timestamp = self.next_timestamp;
+        let block = self.next_block();
+        let previous = self.data.context.replace(self.context(&block));
+        let spec = self.data.config.spec;
+        let outcome = match t8n::apply(
+            &mut self.data,
+            &tx,
+            spec,
+            self.coinbase,
+            None,
+            Some(self.gas_limit),
+        ) {
+            Ok(outcome) => outcome,
+            Err(reason) => {
+                self.data.context = previous;
+                self.next_timestamp = next_timestamp;
+                return Err(NodeError::Rejected(reason));
+            }
+        };
+        let receipt = self.receipt_of(&PendingTx { hash, tx }, outcome, 0, 0);
+        let bloom = receipt.bloom;
+        let gas_used = receipt.gas_used;
+        self.seal(block, vec![receipt], gas_used, bloom);
+        Ok(hash)
+    }
+
+    /// Takes a signed transaction in its raw bytes and returns its hash,
+    /// like `send_transaction`. The sender is recovered from the signature,
+    /// which needs the `k256` feature. Blob transactions are not taken.
+    pub fn send_raw_transaction(&mut self, raw: &[u8]) -> Result<H256, NodeError> {
+        let invalid = NodeError::InvalidTransaction;
+        let signed = SignedTransaction::decode(raw).map_err(|error| invalid(error.to_string()))?;
+        if let Some(chain_id) = signed.chain_id().filter(|id| *id != self.chain_id) {
+            return Err(invalid(format!(
+                "chain id {} instead of {}",
+                chain_id, self.chain_id
+            )));
+        }
+        let sender = match signed.sender() {
+            Some(sender) => sender,
+            None if cfg!(feature = "k256") => return Err(invalid("invalid signature".to_string())),
+            None => return Err(NodeError::RawTransactionsUnsupported),
+        };
+        let tx = Transaction::from_signed(&signed, sender).map_err(invalid)?;
+        self.submit(tx)
+    }
+
+    /// Mines a block with the transactions of the pool and returns its
+    /// number. Transactions that cannot be included are dropped.
+    pub fn mine(&mut self) -> u64 {
+        let block = self.next_block();
+        self.data.context = Some(self.context(&block));
+        let mut receipts: Vec<TxReceipt> = vec![];
+        let mut gas_used = 0;
+        let mut bloom = Bloom::default();
+        for pending in std::mem::take(&mut self.pool) {
+            let available = self.gas_limit - gas_used;
+            let spec = self.data.config.spec;
+            let outcome = match t8n::apply(
+                &mut self.data,
+                &pending.tx,
+                spec,
+                self.coinbase,
+                None,
+                Some(available),
+            ) {
+                Ok(outcome) => outcome,
+                Err(_) => continue,
+            };
+            let receipt = self.receipt_of(&pending, outcome, receipts.len(), gas_used);
+            gas_used = receipt.cumulative_gas_used;
+            bloom.accrue_bloom(&receipt.bloom);
+            receipts.push(receipt);
+        }
+        self.seal(block, receipts, gas_used, bloom);
+        self.block_number()
+    }
+
+    /// The timestamp of the next block, which has to come after the latest.
+    pub fn set_next_block_timestamp(&mut self, timestamp: u64) -> Result<(), NodeError> {
+        let latest = self.latest().timestamp;
+        if timestamp <= latest {
+            return Err(NodeError::TimestampTooLow { timestamp, latest });
+        }
+        self.next_timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    /// Takes a snapshot of the chain: the state, the blocks and the pool.
+    pub fn snapshot(&mut self) -> SnapshotId {
+        self.snapshots.push(NodeSnapshot {
+            blocks: self.blocks.len(),
+            pool: self.pool.clone(),
+            next_timestamp: self.next_timestamp,
+        });
+        self.data.snapshot()
+    }
+
+    /// Winds the chain back to `id`, dropping the blocks mined since. Like
+    /// `EvmData::revert`, it closes `id` and the snapshots after it.
+    pub fn revert(&mut self, id: SnapshotId) -> bool {
+        if !self.data.revert(id) {
+            return false;
+        }
+        let snapshot = self.snapshots.drain(id.0..).next().unwrap();
+        for block in self.blocks.drain(snapshot.blocks..) {
+            for hash in block.transactions {
+                self.receipts.remove(&hash);
+            }
+        }
+        self.pool = snapshot.pool;
+        self.next_timestamp = snapshot.next_timestamp;
+        let latest = self.latest().clone();
+        self.data.context = Some(self.context(&latest));
+        true
+    }
+
+    pub fn set_balance(&mut self, address: U256, balance: U256) {
+        self.data.set_balance(address, balance);
+        self.data.finish_transaction();
+    }
+
+    pub fn set_nonce(&mut self, address: U256, nonce: u64) {
+        self.data.set_nonce(address, nonce);
+        self.data.finish_transaction();
+    }
+
+    pub fn set_code(&mut self, address: U256, code: &[u8]) {
+        self.data.set_code(address, code);
+        self.data.finish_transaction();
+    }
+
+    pub fn set_storage_at(&mut self, address: U256, slot: U256, value: U256) {
+        self.data.set_state(address, slot, Some(value));
+        self.data.finish_transaction();
+    }
+
+    pub fn set_coinbase(&mut self, coinbase: U256) {
+        self.coinbase = coinbase;
+    }
+
+    fn estimate_gas(&self, request: &Value) -> Result<u64, NodeError> {
+        let call: CallRequest = serde_json::from_value(request.clone())
+            .map_err(|error| NodeError::InvalidTransaction(error.to_string()))?;
+        let service = EvmService::new(self.data.clone());
+        let overrides = StateOverrides::new();
+        let rejected = |error: ServiceError| NodeError::Rejected(error.to_string());
+        let mut gas = service
+            .estimate_gas(call.clone(), &overrides)
+            .map_err(rejected)?;
+        if call.to.is_none() {
+            // The estimate only covers running the init code, a creation
+            // also pays for the init code as data and for the deposit
+            // The service parsed the data already
+            let data = call.data.as_deref().map(|data| data.parse());
+            let init_code = TxData {
+                data: data.transpose().unwrap_or_default(),
+                ..Default::default()
+            };
+            let spec = self.data.config.spec;
+            let size = init_code.data.as_ref().map_or(0, |data| data.len());
+            let deployed = service.call(call, &overrides).map_err(rejected)?.value;
+            gas += intrinsic_gas(&init_code, spec) - intrinsic_gas(&TxData::default(), spec)
+                + TX_CREATE_COST
+                + init_code_cost(size, spec)
+                + CODE_DEPOSIT_COST * deployed.unwrap_or_default().len() as u64;
+        }
+        Ok(gas)
+    }
+
+    // The header of the block after the latest, without its results
+    fn next_block(&mut self) -> MinedBlock {
+        let parent = self.blocks.last();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        let timestamp = match (self.next_timestamp.take(), parent) {
+            (Some(timestamp), _) => timestamp,
+            (None, Some(parent)) => now.max(parent.timestamp + 1),
+            (None, None) => now,
+        };
+        MinedBlock {
+            number: parent.map_or(0, |parent| parent.number + 1),
+            hash: H256::zero(),
+            parent_hash: parent.map_or(H256::zero(), |parent| parent.hash),
+            timestamp,
+            gas_limit: self.gas_limit,
+            gas_used: 0,
+            coinbase: self.coinbase,
+            transactions: vec![],
+            bloom: Bloom::default(),
+        }
+    }
+
+    fn context(&self, block: &MinedBlock) -> EvmContext {
+        serde_json::from_value(json!({
+            "coinbase": hex_address(block.coinbase),
+            "number": format!("{:#x}", block.number),
+            "timestamp": format!("{:#x}", block.timestamp),
+            "gaslimit": format!("{:#x}", block.gas_limit),
+            "chainid": format!("{:#x}", self.chain_id),
+        }))
+        .unwrap()
+    }
+
+    fn receipt_of(
+        &self,
+        pending: &PendingTx,
+        outcome: t8n::Outcome,
+        index: usize,
+        cumulative_gas_used: u64,
+    ) -> TxReceipt {
+        let mut bloom = Bloom::default();
+        for log in &outcome.logs {
+            bloom.accrue_log(log);
+        }
+        TxReceipt {
+            transaction_hash: pending.hash,
+            transaction_index: index,
+            block_number: 0,
+            block_hash: H256::zero(),
+            from: pending.tx.sender,
+            to: pending.tx.to,
+            contract_address: outcome.created,
+            gas_price: outcome.gas_price,
+            success: outcome.success,
+            gas_used: outcome.gas_used,
+            cumulative_gas_used: cumulative_gas_used + outcome.gas_used,
+            logs: outcome.logs,
+            bloom,
+            output: outcome.output,
+        }
+    }
+
+    // Fills in the results of `block`, hashes it and makes it the latest
+    fn seal(
+        &mut self,
+        mut block: MinedBlock,
+        receipts: Vec<TxReceipt>,
+        gas_used: u64,
+        bloom: Bloom,
+    ) {
+        block.gas_used = gas_used;
+        block.bloom = bloom;
+        block.transactions = receipts
+            .iter()
+            .map(|receipt| receipt.transaction_hash)
+            .collect();
+        let hash_bytes = |hash: &H256| rlp::encode_bytes(hash.as_bytes());
+        block.hash = keccak(&rlp::encode_list(&[
+            hash_bytes(&block.parent_hash),
+            rlp::encode_u64(block.number),
+            rlp::encode_u64(block.timestamp),
+            rlp::encode_u64(block.gas_used),
+            hash_bytes(&ordered_trie_root(
+                block
+                    .transactions
+                    .iter()
+                    .map(|hash| hash.as_bytes().to_vec()),
+            )),
+        ]));
+        for mut receipt in receipts {
+            receipt.block_number = block.number;
+            receipt.block_hash = block.hash;
+            self.receipts.insert(receipt.transaction_hash, receipt);
+        }
+        self.data.context = Some(self.context(&block));
+        self.blocks.push(block);
+    }
+}
@@ -0,0 +1,84 @@
+//! Where OP Stack chains depart from mainnet, for `Chain::Optimism`:
+//!
+//! - every transaction but a deposit also pays for posting it to L1, from
+//!   the L1 fee fields of `EvmContext` (the Ecotone formula), into
+//!   `L1_FEE_VAULT`; the base fee goes to `BASE_FEE_VAULT` instead of being
+//!   burnt;
+//! - deposit transactions (type 0x7e) come from L1: they are not signed,
+//!   pay no fees, and mint ether to their sender that stays even when they
+//!   fail;
+//! - P256VERIFY (RIP-7212) is a precompile at 0x100.
+
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::EncodedPoint;
+use primitive_types::{H256, U256};
+
+use crate::prelude::*;
+use crate::EvmContext;
+
+pub const DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+pub const P256VERIFY: u64 = 0x100;
+pub const P256VERIFY_GAS: u64 = 3450;
+
+/// 0x420000000000000000000000000000000000001a
+pub const L1_FEE_VAULT: U256 = U256([0x1a, 0, 0x4200_0000, 0]);
+/// 0x4200000000000000000000000000000000000019
+pub const BASE_FEE_VAULT: U256 = U256([0x19, 0, 0x4200_0000, 0]);
+
+/// What a deposit transaction has on top of the fields of any other.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Deposit {
+    /// Identifies the L1 event the deposit comes from.
+    pub source_hash: H256,
+    /// Credited to the sender before the transaction runs.
+    pub mint: U256,
+    /// System transactions use no gas of the block.
+    pub is_system_tx: bool,
+}
+
+/// The fee for posting the encoded transaction `tx` to L1: its calldata
+/// gas, priced at the L1 base fee and blob base fee as scaled in `context`.
+/// Zero without the fields.
+pub fn l1_data_fee(context: Option<&EvmContext>, tx: &[u8]) -> U256 {
+    let Some(context) = context else {
+        return U256::zero();
+    };
+    let field = |value: Option<U256>| value.unwrap_or_default();
+    let calldata_gas = tx
+        .iter()
+        .map(|byte| if *byte == 0 { 4u64 } else { 16 })
+        .sum::<u64>();
+    let scaled_base_fee = field(context.base_fee_scalar)
+        .saturating_mul(field(context.l1_base_fee))
+        .saturating_mul(U256::from(16));
+    let scaled_blob_base_fee =
+        field(context.blob_base_fee_scalar).saturating_mul(field(context.l1_blob_base_fee));
+    U256::from(calldata_gas).saturating_mul(scaled_base_fee.saturating_add(scaled_blob_base_fee))
+        / U256::from(16_000_000)
+}
+
+/// Verifies a secp256r1 signature of a hash. Returns 1 as a word when it
+/// holds, and nothing otherwise.
+///
+/// Input layout: hash (32) | r (32) | s (32) | x (32) | y (32).
+pub fn p256_verify(input: &[u8]) -> Vec<u8> {
+    if input.len() != 160 {
+        return vec![];
+    }
+    let Ok(signature) = Signature::from_slice(&input[32..96]) else {
+        return vec![];
+    };
+    let point =
+        EncodedPoint::from_affine_coordinates(input[96..128].into(), input[128..160].into(), false);
+    let Ok(key) = VerifyingKey::from_encoded_point(&point) else {
+        return vec![];
+    };
+    if key.verify_prehash(&input[..32], &signature).is_err() {
+        return vec![];
+    }
+    let mut output = vec![0; 32];
+    output[31] = 1;
+    output
+}
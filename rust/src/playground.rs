@@ -0,0 +1,73 @@
+//! Runs bytecode from a JSON request and answers in JSON, for front ends
+//! like the browser playground of `wasm` that cannot build the Rust types.
+//!
+//! ```json
+//! { "code": "0x6001600201", "spec": "Cancun", "tx": { "value": "0x1" },
+//!   "block": { "number": "0x10" }, "static": false, "trace": true }
+//! ```
+//!
+//! Only `code` is required. `tx` and `block` take the fields of `TxData` and
+//! `EvmContext`. The answer is what `evmrs run --json` prints with, when
+//! asked for, the EIP-3155 trace.
+
+use serde_json::{json, Value};
+
+use crate::spec::{ChainConfig, SpecId};
+use crate::tracer::StreamTracer;
+use crate::{evm_with_inspector, hexutil, EvmContext, EvmData, TxData};
+
+pub fn run(request: &Value) -> Result<Value, String> {
+    let code = request["code"].as_str().ok_or("code is required")?;
+    let code = hexutil::decode(code).map_err(|error| format!("invalid code: {}", error))?;
+    let spec = match &request["spec"] {
+        Value::Null => SpecId::default(),
+        name => {
+            serde_json::from_value(name.clone()).map_err(|_| format!("unknown spec {}", name))?
+        }
+    };
+    let mut tx = match &request["tx"] {
+        Value::Null => json!({}),
+        tx => tx.clone(),
+    };
+    if let Some(tx) = tx.as_object_mut() {
+        tx.entry("data").or_insert(json!(""));
+    }
+    let tx: TxData =
+        serde_json::from_value(tx).map_err(|error| format!("invalid tx: {}", error))?;
+    let block = match &request["block"] {
+        Value::Null => None,
+        block => serde_json::from_value::<EvmContext>(block.clone())
+            .map(Some)
+            .map_err(|error| format!("invalid block: {}", error))?,
+    };
+    let writable = !request["static"].as_bool().unwrap_or(false);
+
+    let mut data = EvmData {
+        tx_data: Some(tx),
+        context: block,
+        config: ChainConfig::new(spec),
+        ..Default::default()
+    };
+    let mut trace = None;
+    let result = if request["trace"].as_bool().unwrap_or(false) {
+        let mut tracer = StreamTracer::new(Vec::new());
+        let result = evm_with_inspector(&code, &mut data, writable, &mut tracer);
+        let lines = tracer.finish().map_err(|error| error.to_string())?;
+        trace = Some(
+            lines
+                .split(|&byte| byte == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_slice(line).unwrap())
+                .collect::<Vec<Value>>(),
+        );
+        result
+    } else {
+        evm_with_inspector(&code, &mut data, writable, &mut ())
+    };
+
+    let mut output = result.to_json();
+    if let Some(trace) = trace {
+        output["trace"] = json!(trace);
+    }
+    Ok(output)
+}
@@ -0,0 +1,211 @@
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "optimism")]
+use crate::optimism;
+use crate::prelude::*;
+use crate::spec::{Chain, SpecId};
+use crate::{ecdsa, hexutil};
+
+pub const ECRECOVER: u64 = 0x01;
+pub const ECRECOVER_GAS: u64 = 3000;
+
+pub const POINT_EVALUATION: u64 = 0x0a;
+pub const POINT_EVALUATION_GAS: u64 = 50000;
+
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+pub const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+pub const BLS_MODULUS: &str = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrecompileError {
+    InvalidInputLength,
+    InvalidVersionedHash,
+    InvalidProof,
+    /// The call did not pass on enough gas.
+    OutOfGas,
+    /// A precompile of the fork that is not implemented here.
+    Unsupported,
+    /// Any other failure of a registered precompile.
+    Custom(String),
+}
+
+pub type PrecompileResult = Result<Vec<u8>, PrecompileError>;
+
+/// What a registered precompile returns, and what it cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// A precompile registered on `EvmData::precompiles`, next to the ones of
+/// the fork or in place of one. It gets the input and the gas the call
+/// passed on, `None` when unmetered, and fails with
+/// `PrecompileError::OutOfGas` when that is not enough. A failure uses up
+/// all the gas passed on.
+pub type Precompile = fn(&[u8], Option<u64>) -> Result<PrecompileOutput, PrecompileError>;
+
+pub fn is_precompile(address: U256, spec: SpecId) -> bool {
+    address == U256::from(ECRECOVER)
+        || address == U256::from(POINT_EVALUATION) && spec.is_enabled_in(SpecId::Cancun)
+}
+
+/// Addresses of every precompile the fork defines, whether or not it is
+/// implemented here. They start out warm in every transaction (EIP-2929).
+pub fn active_addresses(spec: SpecId) -> Vec<U256> {
+    let last = if spec.is_enabled_in(SpecId::Prague) {
+        0x11
+    } else if spec.is_enabled_in(SpecId::Cancun) {
+        0x0a
+    } else if spec.is_enabled_in(SpecId::Istanbul) {
+        0x09
+    } else if spec.is_enabled_in(SpecId::Byzantium) {
+        0x08
+    } else {
+        0x04
+    };
+    (1..=last).map(U256::from).collect()
+}
+
+/// Precompiles a chain has on top of those of the fork, which start out
+/// warm too.
+pub fn chain_addresses(chain: Chain) -> Vec<U256> {
+    match chain {
+        Chain::Ethereum => vec![],
+        #[cfg(feature = "optimism")]
+        Chain::Optimism => vec![U256::from(optimism::P256VERIFY)],
+    }
+}
+
+pub fn gas_cost(address: U256, _input: &[u8]) -> u64 {
+    if address == U256::from(ECRECOVER) {
+        ECRECOVER_GAS
+    } else if address == U256::from(POINT_EVALUATION) {
+        POINT_EVALUATION_GAS
+    } else {
+        chain_gas_cost(address)
+    }
+}
+
+pub fn run(address: U256, input: &[u8]) -> Option<PrecompileResult> {
+    if address == U256::from(ECRECOVER) {
+        Some(Ok(ecrecover(input)))
+    } else if address == U256::from(POINT_EVALUATION) {
+        Some(point_evaluation(input))
+    } else {
+        run_chain(address, input)
+    }
+}
+
+#[cfg(feature = "optimism")]
+fn chain_gas_cost(address: U256) -> u64 {
+    if address == U256::from(optimism::P256VERIFY) {
+        optimism::P256VERIFY_GAS
+    } else {
+        0
+    }
+}
+
+#[cfg(not(feature = "optimism"))]
+fn chain_gas_cost(_address: U256) -> u64 {
+    0
+}
+
+#[cfg(feature = "optimism")]
+fn run_chain(address: U256, input: &[u8]) -> Option<PrecompileResult> {
+    (address == U256::from(optimism::P256VERIFY)).then(|| Ok(optimism::p256_verify(input)))
+}
+
+#[cfg(not(feature = "optimism"))]
+fn run_chain(_address: U256, _input: &[u8]) -> Option<PrecompileResult> {
+    None
+}
+
+/// The address that signed a hash, left padded to a word, or nothing when
+/// the signature does not recover. Never fails: bad input is no address.
+///
+/// Input layout, zero padded to 128 bytes: hash (32) | v (32) | r (32) | s (32),
+/// where v is 27 or 28.
+pub fn ecrecover(input: &[u8]) -> Vec<u8> {
+    let mut padded = [0u8; 128];
+    let len = input.len().min(128);
+    padded[..len].copy_from_slice(&input[..len]);
+
+    let hash: [u8; 32] = padded[0..32].try_into().unwrap();
+    let v = U256::from_big_endian(&padded[32..64]);
+    let r = U256::from_big_endian(&padded[64..96]);
+    let s = U256::from_big_endian(&padded[96..128]);
+    if v != U256::from(27) && v != U256::from(28) {
+        return vec![];
+    }
+    match ecdsa::recover(&hash, v.low_u32() as u8 - 27, r, s) {
+        Some(address) => {
+            let mut output = vec![0; 32];
+            address.to_big_endian(&mut output);
+            output
+        }
+        None => vec![],
+    }
+}
+
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// EIP-4844 point evaluation: verifies that the blob committed to by
+/// `commitment` evaluates to `y` at `z`.
+///
+/// Input layout: versioned_hash (32) | z (32) | y (32) | commitment (48) | proof (48).
+pub fn point_evaluation(input: &[u8]) -> PrecompileResult {
+    if input.len() != 192 {
+        return Err(PrecompileError::InvalidInputLength);
+    }
+
+    let versioned_hash = &input[0..32];
+    let z = &input[32..64];
+    let y = &input[64..96];
+    let commitment = &input[96..144];
+    let proof = &input[144..192];
+
+    if kzg_to_versioned_hash(commitment) != versioned_hash {
+        return Err(PrecompileError::InvalidVersionedHash);
+    }
+
+    if !verify_kzg_proof(commitment, z, y, proof) {
+        return Err(PrecompileError::InvalidProof);
+    }
+
+    let mut output = vec![0; 64];
+    U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian(&mut output[0..32]);
+    hexutil::parse_u256(BLS_MODULUS)
+        .unwrap()
+        .to_big_endian(&mut output[32..64]);
+    Ok(output)
+}
+
+#[cfg(feature = "kzg")]
+fn verify_kzg_proof(commitment: &[u8], z: &[u8], y: &[u8], proof: &[u8]) -> bool {
+    use c_kzg::{Bytes32, Bytes48};
+
+    let (Ok(commitment), Ok(z), Ok(y), Ok(proof)) = (
+        Bytes48::from_bytes(commitment),
+        Bytes32::from_bytes(z),
+        Bytes32::from_bytes(y),
+        Bytes48::from_bytes(proof),
+    ) else {
+        return false;
+    };
+
+    c_kzg::ethereum_kzg_settings(0)
+        .verify_kzg_proof(&commitment, &z, &y, &proof)
+        .unwrap_or(false)
+}
+
+#[cfg(not(feature = "kzg"))]
+fn verify_kzg_proof(_commitment: &[u8], _z: &[u8], _y: &[u8], _proof: &[u8]) -> bool {
+    // Without the trusted setup no proof can be checked, so none is accepted.
+    false
+}
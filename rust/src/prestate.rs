@@ -0,0 +1,242 @@
+//! The state an execution touched, as it was before the transaction and,
+//! in diff mode, what became of it, in the format of geth's
+//! `prestateTracer`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use primitive_types::U256;
+use serde_json::{json, Map, Value};
+
+use crate::fmtutil::{hex_address, hex_word};
+use crate::hexutil;
+use crate::host::Database;
+use crate::inspector::{CallFrame, CreateFrame, Inspector, Step};
+use crate::{EvmData, EvmResult};
+
+/// An account as the tracer reports it. In diff mode post-states, fields
+/// that did not change are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub balance: Option<U256>,
+    pub nonce: Option<u64>,
+    pub code: Option<Vec<u8>>,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl AccountState {
+    /// Zero nonces, empty code and empty storage are left out, as geth does.
+    pub fn to_json(&self) -> Value {
+        let mut account = Map::new();
+        if let Some(balance) = self.balance {
+            account.insert("balance".to_string(), json!(format!("{:#x}", balance)));
+        }
+        if let Some(nonce) = self.nonce.filter(|nonce| *nonce != 0) {
+            account.insert("nonce".to_string(), json!(nonce));
+        }
+        if let Some(code) = self.code.as_ref().filter(|code| !code.is_empty()) {
+            account.insert("code".to_string(), json!(hexutil::encode_prefixed(code)));
+        }
+        if !self.storage.is_empty() {
+            let storage: Map<String, Value> = self
+                .storage
+                .iter()
+                .map(|(key, value)| (hex_word(*key), json!(hex_word(*value))))
+                .collect();
+            account.insert("storage".to_string(), Value::Object(storage));
+        }
+        Value::Object(account)
+    }
+}
+
+/// Accounts by address.
+pub type Accounts = BTreeMap<U256, AccountState>;
+
+pub fn accounts_json(accounts: &Accounts) -> Value {
+    Value::Object(
+        accounts
+            .iter()
+            .map(|(address, account)| (hex_address(*address), account.to_json()))
+            .collect(),
+    )
+}
+
+/// What `diffMode` reports: the accounts the transaction changed as they
+/// were, with only the slots that changed, and the fields and slots that
+/// changed as they are now. Accounts it created are only in `post`, slots
+/// it cleared only in `pre`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    pub pre: Accounts,
+    pub post: Accounts,
+}
+
+impl StateDiff {
+    pub fn to_json(&self) -> Value {
+        json!({ "pre": accounts_json(&self.pre), "post": accounts_json(&self.post) })
+    }
+}
+
+/// Records the accounts and slots an execution touches.
+///
+/// It keeps a copy of the data it is created from to read the pre-state
+/// back from, so create it right before running the transaction. Slots are
+/// filed under the account whose code is running, reads of the coinbase
+/// are not seen.
+#[derive(Debug)]
+pub struct PrestateTracer {
+    pre: EvmData,
+    touched: BTreeMap<U256, BTreeSet<U256>>,
+    // Account each open frame runs as, the innermost last
+    frames: Vec<U256>,
+}
+
+impl PrestateTracer {
+    pub fn new(data: &EvmData) -> Self {
+        Self {
+            pre: data.clone(),
+            touched: BTreeMap::new(),
+            frames: vec![],
+        }
+    }
+
+    /// The touched accounts and slots as they were before the transaction.
+    pub fn prestate(&self) -> Accounts {
+        self.touched
+            .iter()
+            .map(|(address, keys)| (*address, read_account(&self.pre, *address, keys)))
+            .collect()
+    }
+
+    /// How the touched state changed, with `post` the data the transaction
+    /// ran on.
+    pub fn diff(&self, post: &EvmData) -> StateDiff {
+        let mut diff = StateDiff::default();
+        for (address, keys) in &self.touched {
+            let before = read_account(&self.pre, *address, keys);
+            let after = read_account(post, *address, keys);
+            if before == after {
+                continue;
+            }
+
+            let changed = |state: &AccountState| -> BTreeMap<U256, U256> {
+                state
+                    .storage
+                    .iter()
+                    .filter(|(key, _)| before.storage[key] != after.storage[key])
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(key, value)| (*key, *value))
+                    .collect()
+            };
+            if exists(&self.pre, *address) || before.storage.values().any(|value| !value.is_zero())
+            {
+                diff.pre.insert(
+                    *address,
+                    AccountState {
+                        storage: changed(&before),
+                        ..before.clone()
+                    },
+                );
+            }
+            diff.post.insert(
+                *address,
+                AccountState {
+                    balance: changed_field(&before.balance, &after.balance),
+                    nonce: changed_field(&before.nonce, &after.nonce),
+                    code: changed_field(&before.code, &after.code),
+                    storage: changed(&after),
+                },
+            );
+        }
+        diff
+    }
+
+    fn touch(&mut self, address: U256) {
+        self.touched.entry(address).or_default();
+    }
+
+    fn touch_slot(&mut self, key: U256) {
+        let address = self.frames.last().copied().unwrap_or_default();
+        self.touched.entry(address).or_default().insert(key);
+    }
+}
+
+impl Inspector for PrestateTracer {
+    fn step(&mut self, step: &Step) {
+        let arg = |n: usize| step.stack.len().checked_sub(n + 1).map(|i| step.stack[i]);
+
+        match step.opcode {
+            // SLOAD, SSTORE
+            0x54 | 0x55 => {
+                if let Some(key) = arg(0) {
+                    self.touch_slot(key);
+                }
+            }
+            // SELFBALANCE
+            0x47 => {
+                let address = self.frames.last().copied().unwrap_or_default();
+                self.touch(address);
+            }
+            // BALANCE, EXTCODESIZE, EXTCODECOPY, EXTCODEHASH, SELFDESTRUCT
+            0x31 | 0x3b | 0x3c | 0x3f | 0xff => {
+                if let Some(address) = arg(0) {
+                    self.touch(address);
+                }
+            }
+            // CALL, CALLCODE, DELEGATECALL, STATICCALL, also when they fail
+            // before the callee starts
+            0xf1 | 0xf2 | 0xf4 | 0xfa => {
+                if let Some(address) = arg(1) {
+                    self.touch(address);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        self.touch(call.caller);
+        self.touch(call.address);
+        self.touch(call.code_address);
+        self.frames.push(call.address);
+    }
+
+    fn call_end(&mut self, _call: &CallFrame, _result: &EvmResult) {
+        self.frames.pop();
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        self.touch(create.caller);
+        self.touch(create.address);
+        self.frames.push(create.address);
+    }
+
+    fn create_end(&mut self, _create: &CreateFrame, _result: &EvmResult) {
+        self.frames.pop();
+    }
+}
+
+fn read_account(data: &EvmData, address: U256, keys: &BTreeSet<U256>) -> AccountState {
+    let account = data.basic(address).unwrap_or_default();
+    AccountState {
+        balance: Some(account.balance),
+        nonce: Some(account.nonce),
+        code: Some(data.code(address).unwrap_or_default()),
+        storage: keys
+            .iter()
+            .map(|key| (*key, data.storage(address, *key)))
+            .collect(),
+    }
+}
+
+// `after` when it differs from `before`
+fn changed_field<T: PartialEq + Clone>(before: &Option<T>, after: &Option<T>) -> Option<T> {
+    if before == after {
+        None
+    } else {
+        after.clone()
+    }
+}
+
+fn exists(data: &EvmData, address: U256) -> bool {
+    data.basic(address).is_some() || data.code(address).is_some_and(|code| !code.is_empty())
+}
@@ -0,0 +1,128 @@
+//! Where execution spends its time and gas, by opcode.
+//!
+//! An instruction is charged everything up to the next instruction of its
+//! frame, or the end of the frame: a CALL or CREATE includes the frame it
+//! starts. The transaction itself reports its gas with intrinsic gas and
+//! refunds folded in, so its last instruction is charged time but no gas.
+//! Timings include the cost of the inspector hooks themselves, so
+//! they are good for comparing opcodes rather than as absolute numbers.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::inspector::{CallFrame, CreateFrame, Inspector, Step};
+use crate::opcodes;
+use crate::EvmResult;
+
+/// Totals for one opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeProfile {
+    pub opcode: u8,
+    pub count: u64,
+    pub gas: u64,
+    pub time: Duration,
+}
+
+// The instruction of a frame still running, until its frame moves on
+struct Pending {
+    opcode: u8,
+    gas_used: u64,
+    started: Instant,
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    totals: HashMap<u8, OpcodeProfile>,
+    // By depth, the outermost frame first
+    pending: Vec<Option<Pending>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every opcode executed, the most time first.
+    pub fn report(&self) -> Vec<OpcodeProfile> {
+        let mut report: Vec<OpcodeProfile> = self.totals.values().copied().collect();
+        report.sort_by(|a, b| b.time.cmp(&a.time).then(a.opcode.cmp(&b.opcode)));
+        report
+    }
+
+    /// The report as a table, with each opcode's share of the time.
+    pub fn table(&self) -> String {
+        let report = self.report();
+        let total: Duration = report.iter().map(|entry| entry.time).sum();
+        let mut table = format!(
+            "{:<16} {:>10} {:>12} {:>12} {:>7}\n",
+            "opcode", "count", "gas", "time (µs)", "time %"
+        );
+        for entry in &report {
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                100.0 * entry.time.as_secs_f64() / total.as_secs_f64()
+            };
+            writeln!(
+                table,
+                "{:<16} {:>10} {:>12} {:>12.1} {:>6.1}%",
+                opcodes::name(entry.opcode).unwrap_or("INVALID"),
+                entry.count,
+                entry.gas,
+                entry.time.as_secs_f64() * 1e6,
+                share
+            )
+            .unwrap();
+        }
+        table
+    }
+
+    // Charges the pending instruction of the frame at `depth` with the gas
+    // used by the frame since it started
+    fn settle(&mut self, depth: usize, gas_used: u64) {
+        let Some(Some(pending)) = self.pending.get_mut(depth - 1).map(Option::take) else {
+            return;
+        };
+        let entry = self.totals.entry(pending.opcode).or_insert(OpcodeProfile {
+            opcode: pending.opcode,
+            count: 0,
+            gas: 0,
+            time: Duration::ZERO,
+        });
+        entry.count += 1;
+        entry.gas += gas_used.saturating_sub(pending.gas_used);
+        entry.time += pending.started.elapsed();
+    }
+
+    fn frame_end(&mut self, depth: usize, result: &EvmResult) {
+        let gas_used = match self.pending.get(depth - 1) {
+            Some(Some(pending)) if depth == 1 => pending.gas_used,
+            _ => result.gas_used,
+        };
+        self.settle(depth, gas_used);
+        self.pending.truncate(depth - 1);
+    }
+}
+
+impl Inspector for Profiler {
+    fn step(&mut self, step: &Step) {
+        if self.pending.len() < step.depth {
+            self.pending.resize_with(step.depth, || None);
+        }
+        self.settle(step.depth, step.gas_used);
+        self.pending[step.depth - 1] = Some(Pending {
+            opcode: step.opcode,
+            gas_used: step.gas_used,
+            started: Instant::now(),
+        });
+    }
+
+    fn call_end(&mut self, call: &CallFrame, result: &EvmResult) {
+        self.frame_end(call.depth, result);
+    }
+
+    fn create_end(&mut self, create: &CreateFrame, result: &EvmResult) {
+        self.frame_end(create.depth, result);
+    }
+}
@@ -0,0 +1,295 @@
+//! Replaying a transaction of a live chain by its hash: the transaction and
+//! its block come from a node, the state is read through a `ForkDb` as of
+//! the block before, and what the replay comes to is checked against the
+//! receipt the chain has.
+//!
+//! The transactions before it in its block run first, so it sees the state
+//! it saw on chain; with `ReplayOptions::isolated` it runs on the state of
+//! the parent block alone. Either way the system calls made at the start of
+//! a block (the beacon root of EIP-4788, the block hashes of EIP-2935) are
+//! not, and blob transactions cannot be replayed.
+
+use std::fmt;
+use std::sync::Arc;
+
+use primitive_types::U256;
+use serde_json::{json, Map, Value};
+
+use crate::fmtutil::hex_address;
+use crate::fork::{ForkDb, RpcError};
+use crate::hexutil;
+use crate::spec::{ChainConfig, SpecId};
+use crate::t8n::{self, quantity, Transaction};
+use crate::{EvmContext, EvmData, Log};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Rpc(RpcError),
+    /// The node does not have the transaction, or answered with something
+    /// that cannot be read.
+    Invalid(String),
+    /// A transaction of the block, the one replayed or one before it, could
+    /// not be applied.
+    Transaction {
+        index: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Rpc(error) => write!(f, "{}", error),
+            ReplayError::Invalid(reason) => write!(f, "{}", reason),
+            ReplayError::Transaction { index, reason } => {
+                write!(f, "transaction {} of the block: {}", index, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<RpcError> for ReplayError {
+    fn from(error: RpcError) -> Self {
+        ReplayError::Rpc(error)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayOptions {
+    /// The fork to run with, instead of the one mainnet ran the block with.
+    /// Needed for other chains.
+    pub spec: Option<SpecId>,
+    /// Run on the state of the parent block, without the transactions
+    /// before this one in its block.
+    pub isolated: bool,
+}
+
+/// What a transaction came to, on chain or replayed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxOutcome {
+    pub success: bool,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub block: u64,
+    /// Of the transaction in its block.
+    pub index: usize,
+    pub spec: SpecId,
+    /// From the receipt.
+    pub expected: TxOutcome,
+    pub actual: TxOutcome,
+    /// What the replay returned, or reverted with.
+    pub output: Vec<u8>,
+    /// How the replay differs from the receipt, empty when it does not.
+    pub mismatches: Vec<String>,
+}
+
+impl Replay {
+    pub fn matches(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let outcome = |outcome: &TxOutcome| {
+            json!({
+                "status": format!("{:#x}", outcome.success as u8),
+                "gasUsed": format!("{:#x}", outcome.gas_used),
+                "logs": outcome.logs.iter().map(log_json).collect::<Vec<_>>(),
+            })
+        };
+        json!({
+            "blockNumber": format!("{:#x}", self.block),
+            "transactionIndex": format!("{:#x}", self.index),
+            "spec": format!("{:?}", self.spec),
+            "expected": outcome(&self.expected),
+            "actual": outcome(&self.actual),
+            "output": hexutil::encode_prefixed(&self.output),
+            "mismatches": self.mismatches,
+        })
+    }
+}
+
+fn log_json(log: &Log) -> Value {
+    json!({
+        "address": hex_address(log.address),
+        "topics": log.topics.iter().map(|topic| format!("{:#x}", topic)).collect::<Vec<_>>(),
+        "data": hexutil::encode_prefixed(&log.data),
+    })
+}
+
+fn field(value: &Value, name: &str) -> Result<U256, ReplayError> {
+    quantity(&value[name]).ok_or_else(|| ReplayError::Invalid(format!("no {}", name)))
+}
+
+fn receipt_outcome(receipt: &Value) -> Result<TxOutcome, ReplayError> {
+    let invalid = |what: &str| ReplayError::Invalid(format!("invalid {} in the receipt", what));
+    let mut logs = vec![];
+    for log in receipt["logs"].as_array().into_iter().flatten() {
+        let topics = log["topics"]
+            .as_array()
+            .ok_or_else(|| invalid("log topics"))?
+            .iter()
+            .map(|topic| quantity(topic).ok_or_else(|| invalid("log topic")))
+            .collect::<Result<_, _>>()?;
+        let data = log["data"].as_str().ok_or_else(|| invalid("log data"))?;
+        logs.push(Log {
+            address: quantity(&log["address"]).ok_or_else(|| invalid("log address"))?,
+            topics,
+            data: hexutil::decode(data).map_err(|_| invalid("log data"))?,
+        });
+    }
+    Ok(TxOutcome {
+        success: field(receipt, "status")? == U256::one(),
+        gas_used: field(receipt, "gasUsed")?.as_u64(),
+        logs,
+    })
+}
+
+// The block environment of `block`, a block as `eth_getBlockByNumber` has it
+fn context(block: &Value, chain_id: u64) -> Result<EvmContext, ReplayError> {
+    let mut context = Map::new();
+    context.insert("coinbase".to_string(), block["miner"].clone());
+    context.insert("chainid".to_string(), json!(format!("{:#x}", chain_id)));
+    for (name, key) in [
+        ("number", "number"),
+        ("timestamp", "timestamp"),
+        ("gasLimit", "gaslimit"),
+        ("baseFeePerGas", "basefee"),
+        ("excessBlobGas", "excess_blob_gas"),
+    ] {
+        if !block[name].is_null() {
+            context.insert(key.to_string(), block[name].clone());
+        }
+    }
+    // After the merge, PREVRANDAO reads the mix hash
+    let difficulty = match field(block, "difficulty")? {
+        difficulty if difficulty.is_zero() => &block["mixHash"],
+        _ => &block["difficulty"],
+    };
+    context.insert("difficulty".to_string(), difficulty.clone());
+    serde_json::from_value(Value::Object(context))
+        .map_err(|error| ReplayError::Invalid(format!("invalid block: {}", error)))
+}
+
+// Where the replay and the receipt part ways
+fn compare(expected: &TxOutcome, actual: &TxOutcome) -> Vec<String> {
+    let mut mismatches = vec![];
+    if expected.success != actual.success {
+        mismatches.push(format!(
+            "status is {} instead of {}",
+            actual.success as u8, expected.success as u8
+        ));
+    }
+    if expected.gas_used != actual.gas_used {
+        mismatches.push(format!(
+            "gas used is {} instead of {}",
+            actual.gas_used, expected.gas_used
+        ));
+    }
+    if expected.logs.len() != actual.logs.len() {
+        mismatches.push(format!(
+            "{} logs instead of {}",
+            actual.logs.len(),
+            expected.logs.len()
+        ));
+    }
+    for (index, (expected, actual)) in expected.logs.iter().zip(&actual.logs).enumerate() {
+        if expected != actual {
+            mismatches.push(format!(
+                "log {} is {} instead of {}",
+                index,
+                log_json(actual),
+                log_json(expected)
+            ));
+        }
+    }
+    mismatches
+}
+
+/// Replays the transaction `hash` on the chain `fork` reads from, whatever
+/// block it is pinned to, and compares it with its receipt.
+pub fn replay(fork: ForkDb, hash: U256, options: &ReplayOptions) -> Result<Replay, ReplayError> {
+    let hash = json!(format!("{:#066x}", hash));
+    let tx = fork.request("eth_getTransactionByHash", json!([hash]))?;
+    if tx.is_null() {
+        return Err(ReplayError::Invalid("transaction not found".to_string()));
+    }
+    if tx["blockNumber"].is_null() {
+        return Err(ReplayError::Invalid("transaction is pending".to_string()));
+    }
+    let number = field(&tx, "blockNumber")?.as_u64();
+    let index = field(&tx, "transactionIndex")?.as_usize();
+    let block = fork.request(
+        "eth_getBlockByNumber",
+        json!([format!("{:#x}", number), true]),
+    )?;
+    let receipt = fork.request("eth_getTransactionReceipt", json!([hash]))?;
+    let chain_id = quantity(&fork.request("eth_chainId", json!([]))?)
+        .ok_or_else(|| ReplayError::Invalid("invalid chain id".to_string()))?
+        .as_u64();
+    let spec = match options.spec {
+        Some(spec) => spec,
+        None if chain_id == 1 => SpecId::mainnet(number, field(&block, "timestamp")?.as_u64()),
+        None => {
+            return Err(ReplayError::Invalid(format!(
+                "no fork schedule for chain {}, give the spec",
+                chain_id
+            )))
+        }
+    };
+
+    let fork = Arc::new(fork.with_block(number.saturating_sub(1)));
+    let mut data = EvmData {
+        context: Some(context(&block, chain_id)?),
+        config: ChainConfig::new(spec),
+        database: Some(fork.clone()),
+        ..Default::default()
+    };
+    let coinbase = field(&block, "miner")?;
+    let base_fee = quantity(&block["baseFeePerGas"]).filter(|_| spec.is_enabled_in(SpecId::London));
+    let gas_limit = field(&block, "gasLimit")?.as_u64();
+    let txs = block["transactions"]
+        .as_array()
+        .ok_or_else(|| ReplayError::Invalid("block without transactions".to_string()))?;
+    let first = if options.isolated { index } else { 0 };
+    let mut gas_used = 0;
+    let mut replayed = None;
+    for (position, tx) in txs.iter().enumerate().take(index + 1).skip(first) {
+        let failed = |reason: String| ReplayError::Transaction {
+            index: position,
+            reason,
+        };
+        let tx = Transaction::parse(tx, chain_id).map_err(failed)?;
+        let available = Some(gas_limit.saturating_sub(gas_used));
+        let outcome =
+            t8n::apply(&mut data, &tx, spec, coinbase, base_fee, available).map_err(failed)?;
+        gas_used += outcome.gas_used;
+        replayed = Some(outcome);
+    }
+    if let Some(error) = fork.error() {
+        return Err(ReplayError::Rpc(error));
+    }
+    let outcome =
+        replayed.ok_or_else(|| ReplayError::Invalid("transaction not in its block".to_string()))?;
+
+    let expected = receipt_outcome(&receipt)?;
+    let actual = TxOutcome {
+        success: outcome.success,
+        gas_used: outcome.gas_used,
+        logs: outcome.logs,
+    };
+    Ok(Replay {
+        block: number,
+        index,
+        spec,
+        mismatches: compare(&expected, &actual),
+        expected,
+        actual,
+        output: outcome.output,
+    })
+}
@@ -0,0 +1,195 @@
+//! Recursive Length Prefix encoding, the serialization of tries, receipts
+//! and transactions.
+//!
+//! Lists are built from items that are already encoded, so nested structures
+//! are encoded bottom up. Decoding goes the other way, into a tree of
+//! `Item`s, and only takes the canonical encoding.
+
+use core::fmt;
+
+use primitive_types::U256;
+
+use crate::prelude::*;
+
+fn length_prefix(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len_bytes: Vec<u8> = len
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    let mut prefix = vec![offset + 55 + len_bytes.len() as u8];
+    prefix.extend(len_bytes);
+    prefix
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = length_prefix(bytes.len(), 0x80);
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Joins encoded `items` into a list.
+pub fn encode_list<T: AsRef<[u8]>>(items: &[T]) -> Vec<u8> {
+    let payload: Vec<u8> = items
+        .iter()
+        .flat_map(|item| item.as_ref().iter().copied())
+        .collect();
+    let mut out = length_prefix(payload.len(), 0xc0);
+    out.extend(payload);
+    out
+}
+
+/// Numbers are big-endian without leading zeros, zero is the empty string.
+pub fn encode_u256(value: U256) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    let start = word.iter().position(|byte| *byte != 0).unwrap_or(32);
+    encode_bytes(&word[start..])
+}
+
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    encode_u256(U256::from(value))
+}
+
+/// A decoded value: a byte string or a list of items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Bytes(Vec<u8>),
+    List(Vec<Item>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ends inside an item.
+    UnexpectedEnd,
+    /// Bytes left over after the item.
+    TrailingBytes(usize),
+    /// A length or a single byte in a longer form than it needs.
+    NonCanonical,
+    /// A list where a byte string belongs, or the other way round.
+    UnexpectedType,
+    /// A number with leading zeros or too wide for its type.
+    InvalidNumber,
+    /// Lists nested deeper than `MAX_DEPTH`.
+    TooDeep,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "the input ends inside an item"),
+            DecodeError::TrailingBytes(count) => write!(f, "{} bytes after the item", count),
+            DecodeError::NonCanonical => write!(f, "not in canonical form"),
+            DecodeError::UnexpectedType => write!(f, "unexpected list or byte string"),
+            DecodeError::InvalidNumber => write!(f, "invalid number"),
+            DecodeError::TooDeep => write!(f, "lists nested deeper than {}", MAX_DEPTH),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// How deep lists nest in what decodes. Nothing Ethereum encodes comes
+/// close, and deeper input would take the stack to decode.
+pub const MAX_DEPTH: usize = 64;
+
+/// Decodes the one item `input` holds.
+pub fn decode(input: &[u8]) -> Result<Item, DecodeError> {
+    let (item, used) = decode_item(input, 0)?;
+    match input.len() - used {
+        0 => Ok(item),
+        left => Err(DecodeError::TrailingBytes(left)),
+    }
+}
+
+// The item at the start of `input` and how many bytes it takes, inside
+// `depth` lists
+fn decode_item(input: &[u8], depth: usize) -> Result<(Item, usize), DecodeError> {
+    let first = *input.first().ok_or(DecodeError::UnexpectedEnd)?;
+    let (is_list, start, len) = match first {
+        0x00..=0x7f => return Ok((Item::Bytes(vec![first]), 1)),
+        0x80..=0xb7 => (false, 1, (first - 0x80) as usize),
+        0xb8..=0xbf => {
+            let (len, size) = long_length(input, first - 0xb7)?;
+            (false, 1 + size, len)
+        }
+        0xc0..=0xf7 => (true, 1, (first - 0xc0) as usize),
+        0xf8..=0xff => {
+            let (len, size) = long_length(input, first - 0xf7)?;
+            (true, 1 + size, len)
+        }
+    };
+    let end = start.checked_add(len).ok_or(DecodeError::UnexpectedEnd)?;
+    let payload = input.get(start..end).ok_or(DecodeError::UnexpectedEnd)?;
+    if !is_list {
+        if len == 1 && payload[0] < 0x80 {
+            return Err(DecodeError::NonCanonical);
+        }
+        return Ok((Item::Bytes(payload.to_vec()), end));
+    }
+    if depth == MAX_DEPTH {
+        return Err(DecodeError::TooDeep);
+    }
+    let mut items = vec![];
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let (item, used) = decode_item(rest, depth + 1)?;
+        items.push(item);
+        rest = &rest[used..];
+    }
+    Ok((Item::List(items), end))
+}
+
+// A length of `size` bytes after the prefix, which has to need them
+fn long_length(input: &[u8], size: u8) -> Result<(usize, usize), DecodeError> {
+    let size = size as usize;
+    let bytes = input.get(1..1 + size).ok_or(DecodeError::UnexpectedEnd)?;
+    if bytes[0] == 0 || size > core::mem::size_of::<usize>() {
+        return Err(DecodeError::NonCanonical);
+    }
+    let len = bytes
+        .iter()
+        .fold(0usize, |len, byte| len << 8 | *byte as usize);
+    if len < 56 {
+        return Err(DecodeError::NonCanonical);
+    }
+    Ok((len, size))
+}
+
+impl Item {
+    pub fn bytes(&self) -> Result<&[u8], DecodeError> {
+        match self {
+            Item::Bytes(bytes) => Ok(bytes),
+            Item::List(_) => Err(DecodeError::UnexpectedType),
+        }
+    }
+
+    pub fn list(&self) -> Result<&[Item], DecodeError> {
+        match self {
+            Item::List(items) => Ok(items),
+            Item::Bytes(_) => Err(DecodeError::UnexpectedType),
+        }
+    }
+
+    pub fn u256(&self) -> Result<U256, DecodeError> {
+        let bytes = self.bytes()?;
+        if bytes.len() > 32 || bytes.first() == Some(&0) {
+            return Err(DecodeError::InvalidNumber);
+        }
+        Ok(U256::from_big_endian(bytes))
+    }
+
+    pub fn u64(&self) -> Result<u64, DecodeError> {
+        let value = self.u256()?;
+        if value > U256::from(u64::MAX) {
+            return Err(DecodeError::InvalidNumber);
+        }
+        Ok(value.as_u64())
+    }
+}
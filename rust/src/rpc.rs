@@ -0,0 +1,566 @@
+//! A JSON-RPC server over HTTP in front of an `EvmService`, so tools that
+//! talk to a node can simulate against the service state instead: a state
+//! built in memory or one forked from a chain through `ForkDb`.
+//!
+//! Supports `eth_call`, `eth_estimateGas`, `eth_createAccessList`,
+//! `eth_getBalance`, `eth_getCode`, `eth_getStorageAt` and
+//! `debug_traceCall`. Block tags are accepted and
+//! ignored, every request sees the service state. Built with the `rpc`
+//! feature.
+//!
+//! In front of a `DevNode` instead, it also takes transactions with
+//! `eth_sendTransaction`, serves blocks and receipts, and has the cheats of
+//! Anvil and Hardhat: `evm_mine`, `evm_setNextBlockTimestamp`,
+//! `evm_setAutomine`, `evm_snapshot`, `evm_revert` and `anvil_setBalance`
+//! with its siblings for code, nonces, storage and the coinbase.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use primitive_types::{H256, U256};
+use serde_json::{json, Value};
+
+use crate::call::{CallRequest, StateOverrides};
+use crate::calltrace::CallTracer;
+use crate::fmtutil::{hex_address, hex_word};
+use crate::host::Database;
+use crate::inspector::{Inspector, Step};
+use crate::journal::SnapshotId;
+use crate::node::{DevNode, MinedBlock, NodeError, TxReceipt};
+use crate::service::{EvmService, ServiceError};
+use crate::{abi, hexutil, opcodes, EvmResult, HaltReason, EXECUTION_STACK_SIZE};
+
+// Error codes of JSON-RPC and the ones nodes use on top
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const EXECUTION_REVERTED: i64 = 3;
+
+/// An error response, with the revert data in `data` for reverts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcFailure {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Vec<u8>>,
+}
+
+impl RpcFailure {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn reverted(return_data: Vec<u8>) -> Self {
+        let message = match abi::decode_revert(&return_data) {
+            Some(reason) => format!("execution reverted: {}", reason),
+            None => "execution reverted".to_string(),
+        };
+        Self {
+            code: EXECUTION_REVERTED,
+            message,
+            data: Some(return_data),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut error = json!({ "code": self.code, "message": self.message });
+        if let Some(data) = &self.data {
+            error["data"] = json!(hexutil::encode_prefixed(data));
+        }
+        error
+    }
+}
+
+impl From<ServiceError> for RpcFailure {
+    fn from(error: ServiceError) -> Self {
+        match error {
+            ServiceError::ExecutionFailed {
+                halt_reason: Some(HaltReason::Revert),
+                return_data,
+            } => RpcFailure::reverted(return_data),
+            ServiceError::InvalidRequest(_) => RpcFailure::new(INVALID_PARAMS, error.to_string()),
+            error => RpcFailure::new(SERVER_ERROR, error.to_string()),
+        }
+    }
+}
+
+/// Answers a JSON-RPC request, or a batch of them.
+pub fn handle(service: &EvmService, request: &Value) -> Value {
+    respond(request, &|method, params| dispatch(service, method, params))
+}
+
+/// Answers a JSON-RPC request, or a batch of them, on a dev node: on top of
+/// the methods of `handle`, which see the state of the latest block, it
+/// takes transactions, serves blocks and receipts and has the cheats.
+pub fn handle_dev(node: &Mutex<DevNode>, request: &Value) -> Value {
+    respond(request, &|method, params| {
+        // A panic in another request does not leave the node half changed
+        let mut node = node.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        dispatch_dev(&mut node, method, params)
+    })
+}
+
+type Dispatch<'a> = dyn Fn(&str, &Value) -> Result<Value, RpcFailure> + 'a;
+
+fn respond(request: &Value, dispatch: &Dispatch) -> Value {
+    match request {
+        Value::Array(batch) if !batch.is_empty() => Value::Array(
+            batch
+                .iter()
+                .map(|request| handle_one(request, dispatch))
+                .collect(),
+        ),
+        _ => handle_one(request, dispatch),
+    }
+}
+
+fn handle_one(request: &Value, dispatch: &Dispatch) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let outcome = match request.get("method").and_then(Value::as_str) {
+        Some(method) => {
+            let params = request.get("params").cloned().unwrap_or(json!([]));
+            dispatch(method, &params)
+        }
+        None => Err(RpcFailure::new(INVALID_REQUEST, "missing method")),
+    };
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(failure) => json!({ "jsonrpc": "2.0", "id": id, "error": failure.to_json() }),
+    }
+}
+
+fn dispatch(service: &EvmService, method: &str, params: &Value) -> Result<Value, RpcFailure> {
+    let params = Params(params);
+    let param = |index: usize| params.get(index);
+    let required = |index: usize| params.required(index);
+    let word = |index: usize| params.word(index);
+
+    match method {
+        "eth_call" => {
+            let overrides = parse::<StateOverrides>(param(2))?;
+            let result = service.call(parse(Some(required(0)?))?, &overrides)?;
+            call_output(result)
+        }
+        "eth_estimateGas" => {
+            let overrides = parse::<StateOverrides>(param(2))?;
+            let gas = service.estimate_gas(parse(Some(required(0)?))?, &overrides)?;
+            Ok(quantity(U256::from(gas)))
+        }
+        "eth_createAccessList" => {
+            let overrides = parse::<StateOverrides>(param(2))?;
+            let created = service.create_access_list(parse(Some(required(0)?))?, &overrides)?;
+            let access_list: Vec<Value> = created
+                .access_list
+                .iter()
+                .map(|item| {
+                    let keys: Vec<String> =
+                        item.storage_keys.iter().map(|key| hex_word(*key)).collect();
+                    json!({ "address": hex_address(item.address), "storageKeys": keys })
+                })
+                .collect();
+            let mut output = json!({
+                "accessList": access_list,
+                "gasUsed": quantity(U256::from(created.gas_used)),
+            });
+            if let Err(failure) = call_output(created.result) {
+                output["error"] = json!(failure.message);
+            }
+            Ok(output)
+        }
+        "eth_getBalance" => {
+            let account = service.state().basic(word(0)?).unwrap_or_default();
+            Ok(quantity(account.balance))
+        }
+        "eth_getCode" => {
+            let code = service.state().code(word(0)?).unwrap_or_default();
+            Ok(json!(hexutil::encode_prefixed(code)))
+        }
+        "eth_getStorageAt" => {
+            let value = service.state().storage(word(0)?, word(1)?);
+            Ok(json!(hex_word(value)))
+        }
+        "debug_traceCall" => {
+            let request: CallRequest = parse(Some(required(0)?))?;
+            let config = param(2).cloned().unwrap_or(json!({}));
+            let overrides = parse::<StateOverrides>(config.get("stateOverrides"))?;
+            match config.get("tracer").and_then(Value::as_str) {
+                Some("callTracer") => {
+                    let mut tracer = CallTracer::new();
+                    service.call_with_inspector(request, &overrides, &mut tracer)?;
+                    Ok(tracer.finish().map_or(Value::Null, |trace| trace.to_json()))
+                }
+                None => {
+                    let mut logger = StructLogger::default();
+                    let result = service.call_with_inspector(request, &overrides, &mut logger)?;
+                    Ok(json!({
+                        "gas": result.gas_used,
+                        "failed": !result.success,
+                        "returnValue": hex::encode(result.value.unwrap_or_default()),
+                        "structLogs": logger.logs,
+                    }))
+                }
+                Some(tracer) => Err(RpcFailure::new(
+                    INVALID_PARAMS,
+                    format!("tracer {} is not supported", tracer),
+                )),
+            }
+        }
+        _ => Err(RpcFailure::new(
+            METHOD_NOT_FOUND,
+            format!("the method {} does not exist/is not available", method),
+        )),
+    }
+}
+
+fn dispatch_dev(node: &mut DevNode, method: &str, params: &Value) -> Result<Value, RpcFailure> {
+    let params = Params(params);
+    let number = |index: usize| {
+        let value = params.word(index)?;
+        if value > U256::from(u64::MAX) {
+            return Err(RpcFailure::new(
+                INVALID_PARAMS,
+                format!("parameter {}: {} is too large", index, value),
+            ));
+        }
+        Ok(value.as_u64())
+    };
+
+    match method {
+        "eth_chainId" => Ok(quantity(U256::from(node.chain_id()))),
+        "eth_blockNumber" => Ok(quantity(U256::from(node.block_number()))),
+        "eth_getTransactionCount" => {
+            let address = params.word(0)?;
+            let nonce = match params.get(1).and_then(Value::as_str) {
+                Some("pending") => node.next_nonce(address),
+                _ => node.state().basic(address).unwrap_or_default().nonce,
+            };
+            Ok(quantity(U256::from(nonce)))
+        }
+        "eth_sendTransaction" => {
+            let hash = node.send_transaction(params.required(0)?)?;
+            Ok(json!(format!("{:?}", hash)))
+        }
+        "eth_sendRawTransaction" => {
+            let hash = node.send_raw_transaction(&params.bytes(0)?)?;
+            Ok(json!(format!("{:?}", hash)))
+        }
+        "eth_getTransactionReceipt" => Ok(node
+            .receipt(params.hash(0)?)
+            .map_or(Value::Null, |receipt| receipt_json(node, receipt))),
+        "eth_getBlockByNumber" => {
+            let block = match params.required(0)?.as_str() {
+                Some("latest" | "pending" | "safe" | "finalized") => Some(node.latest()),
+                Some("earliest") => node.block(0),
+                _ => node.block(number(0)?),
+            };
+            Ok(block.map_or(Value::Null, block_json))
+        }
+        "eth_getBlockByHash" => Ok(node
+            .block_by_hash(params.hash(0)?)
+            .map_or(Value::Null, block_json)),
+        "evm_mine" => {
+            if params.get(0).is_some() {
+                node.set_next_block_timestamp(number(0)?)?;
+            }
+            node.mine();
+            Ok(json!("0x0"))
+        }
+        "evm_setNextBlockTimestamp" => {
+            node.set_next_block_timestamp(number(0)?)?;
+            Ok(Value::Null)
+        }
+        "evm_snapshot" => Ok(quantity(U256::from(node.snapshot().0))),
+        "evm_revert" => Ok(json!(node.revert(SnapshotId(number(0)? as usize)))),
+        "evm_setAutomine" => {
+            let automine = params.required(0)?.as_bool().ok_or_else(|| {
+                RpcFailure::new(INVALID_PARAMS, "parameter 0: expected a boolean")
+            })?;
+            node.set_automine(automine);
+            Ok(Value::Null)
+        }
+        "anvil_setBalance" | "hardhat_setBalance" => {
+            node.set_balance(params.word(0)?, params.word(1)?);
+            Ok(Value::Null)
+        }
+        "anvil_setNonce" | "hardhat_setNonce" => {
+            node.set_nonce(params.word(0)?, number(1)?);
+            Ok(Value::Null)
+        }
+        "anvil_setCode" | "hardhat_setCode" => {
+            node.set_code(params.word(0)?, &params.bytes(1)?);
+            Ok(Value::Null)
+        }
+        "anvil_setStorageAt" | "hardhat_setStorageAt" => {
+            node.set_storage_at(params.word(0)?, params.word(1)?, params.word(2)?);
+            Ok(Value::Null)
+        }
+        "anvil_setCoinbase" | "hardhat_setCoinbase" => {
+            node.set_coinbase(params.word(0)?);
+            Ok(Value::Null)
+        }
+        _ => dispatch(&EvmService::new(node.state().clone()), method, params.0),
+    }
+}
+
+impl From<NodeError> for RpcFailure {
+    fn from(error: NodeError) -> Self {
+        match error {
+            NodeError::InvalidTransaction(_) => RpcFailure::new(INVALID_PARAMS, error.to_string()),
+            error => RpcFailure::new(SERVER_ERROR, error.to_string()),
+        }
+    }
+}
+
+fn block_json(block: &MinedBlock) -> Value {
+    let hashes: Vec<String> = block
+        .transactions
+        .iter()
+        .map(|hash| format!("{:?}", hash))
+        .collect();
+    json!({
+        "number": quantity(U256::from(block.number)),
+        "hash": format!("{:?}", block.hash),
+        "parentHash": format!("{:?}", block.parent_hash),
+        "timestamp": quantity(U256::from(block.timestamp)),
+        "gasLimit": quantity(U256::from(block.gas_limit)),
+        "gasUsed": quantity(U256::from(block.gas_used)),
+        "miner": hex_address(block.coinbase),
+        "logsBloom": hexutil::encode_prefixed(block.bloom.0),
+        "transactions": hashes,
+    })
+}
+
+fn receipt_json(node: &DevNode, receipt: &TxReceipt) -> Value {
+    // Log indexes count from the start of the block
+    let block = node.block(receipt.block_number).unwrap();
+    let first_log: usize = block.transactions[..receipt.transaction_index]
+        .iter()
+        .filter_map(|hash| node.receipt(*hash))
+        .map(|earlier| earlier.logs.len())
+        .sum();
+    let logs: Vec<Value> = receipt
+        .logs
+        .iter()
+        .enumerate()
+        .map(|(position, log)| {
+            json!({
+                "address": hex_address(log.address),
+                "topics": log.topics.iter().map(|topic| hex_word(*topic)).collect::<Vec<_>>(),
+                "data": hexutil::encode_prefixed(&log.data),
+                "blockNumber": quantity(U256::from(receipt.block_number)),
+                "blockHash": format!("{:?}", receipt.block_hash),
+                "transactionHash": format!("{:?}", receipt.transaction_hash),
+                "transactionIndex": quantity(U256::from(receipt.transaction_index)),
+                "logIndex": quantity(U256::from(first_log + position)),
+                "removed": false,
+            })
+        })
+        .collect();
+    json!({
+        "type": "0x0",
+        "transactionHash": format!("{:?}", receipt.transaction_hash),
+        "transactionIndex": quantity(U256::from(receipt.transaction_index)),
+        "blockNumber": quantity(U256::from(receipt.block_number)),
+        "blockHash": format!("{:?}", receipt.block_hash),
+        "from": hex_address(receipt.from),
+        "to": receipt.to.map(hex_address),
+        "contractAddress": receipt.contract_address.map(hex_address),
+        "status": quantity(U256::from(receipt.success as u8)),
+        "gasUsed": quantity(U256::from(receipt.gas_used)),
+        "cumulativeGasUsed": quantity(U256::from(receipt.cumulative_gas_used)),
+        "effectiveGasPrice": quantity(receipt.gas_price),
+        "logsBloom": hexutil::encode_prefixed(receipt.bloom.0),
+        "logs": logs,
+    })
+}
+
+// Positional parameters, where null is the same as absent
+struct Params<'a>(&'a Value);
+
+impl Params<'_> {
+    fn get(&self, index: usize) -> Option<&Value> {
+        self.0.get(index).filter(|param| !param.is_null())
+    }
+
+    fn required(&self, index: usize) -> Result<&Value, RpcFailure> {
+        self.get(index).ok_or_else(|| {
+            RpcFailure::new(
+                INVALID_PARAMS,
+                format!("missing value for parameter {}", index),
+            )
+        })
+    }
+
+    fn word(&self, index: usize) -> Result<U256, RpcFailure> {
+        let text = self.required(index)?.as_str().unwrap_or_default();
+        hexutil::parse_u256(text).map_err(|error| {
+            RpcFailure::new(INVALID_PARAMS, format!("parameter {}: {}", index, error))
+        })
+    }
+
+    fn bytes(&self, index: usize) -> Result<Vec<u8>, RpcFailure> {
+        let text = self.required(index)?.as_str().unwrap_or_default();
+        hexutil::decode(text).map_err(|error| {
+            RpcFailure::new(INVALID_PARAMS, format!("parameter {}: {}", index, error))
+        })
+    }
+
+    fn hash(&self, index: usize) -> Result<H256, RpcFailure> {
+        let mut word = [0u8; 32];
+        self.word(index)?.to_big_endian(&mut word);
+        Ok(H256(word))
+    }
+}
+
+// Absent parameters parse as the default
+fn parse<T: serde::de::DeserializeOwned + Default>(param: Option<&Value>) -> Result<T, RpcFailure> {
+    match param {
+        Some(param) => serde_json::from_value(param.clone())
+            .map_err(|error| RpcFailure::new(INVALID_PARAMS, error.to_string())),
+        None => Ok(T::default()),
+    }
+}
+
+fn quantity(value: U256) -> Value {
+    json!(format!("{:#x}", value))
+}
+
+fn call_output(result: EvmResult) -> Result<Value, RpcFailure> {
+    if result.success {
+        return Ok(json!(hexutil::encode_prefixed(
+            result.value.unwrap_or_default()
+        )));
+    }
+    match result.halt_reason {
+        Some(HaltReason::Revert) => Err(RpcFailure::reverted(result.return_data)),
+        reason => Err(RpcFailure::new(
+            SERVER_ERROR,
+            format!("execution failed: {:?}", reason),
+        )),
+    }
+}
+
+// The default tracer of `debug_traceCall`. Gas is left out of the logs:
+// steps only carry the gas used by their frame so far.
+#[derive(Debug, Default)]
+struct StructLogger {
+    logs: Vec<Value>,
+}
+
+impl Inspector for StructLogger {
+    fn step(&mut self, step: &Step) {
+        let stack: Vec<String> = step.stack.iter().map(|v| format!("{:#x}", v)).collect();
+        self.logs.push(json!({
+            "pc": step.pc,
+            "op": opcodes::name(step.opcode).unwrap_or("INVALID"),
+            "depth": step.depth,
+            "stack": stack,
+        }));
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Backend {
+    Service(Arc<EvmService>),
+    Node(Arc<Mutex<DevNode>>),
+}
+
+impl Backend {
+    fn handle(&self, request: &Value) -> Value {
+        match self {
+            Backend::Service(service) => handle(service, request),
+            Backend::Node(node) => handle_dev(node, request),
+        }
+    }
+}
+
+/// Serves JSON-RPC over HTTP, one thread per connection.
+#[derive(Debug)]
+pub struct RpcServer {
+    listener: TcpListener,
+    backend: Backend,
+}
+
+impl RpcServer {
+    pub fn bind(address: impl ToSocketAddrs, service: EvmService) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            backend: Backend::Service(Arc::new(service)),
+        })
+    }
+
+    /// A server in front of a dev node, which requests take turns on.
+    pub fn bind_dev(address: impl ToSocketAddrs, node: DevNode) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            backend: Backend::Node(Arc::new(Mutex::new(node))),
+        })
+    }
+
+    /// Where the server listens, with the port picked when bound to port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts connections until the listener fails.
+    pub fn serve(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let backend = self.backend.clone();
+            let worker = thread::Builder::new().stack_size(EXECUTION_STACK_SIZE);
+            worker.spawn(move || {
+                // A client that goes away mid-request only ends its thread
+                let _ = serve_connection(stream, &backend);
+            })?;
+        }
+        Ok(())
+    }
+}
+
+// One request per connection, which is closed after the response
+fn serve_connection(stream: TcpStream, backend: &Backend) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(request) => backend.handle(&request),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": RpcFailure::new(PARSE_ERROR, error.to_string()).to_json(),
+        }),
+    };
+    let body = response.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,591 @@
+//! Scenarios: deploys and calls run one after another on a `DevNode`, each
+//! checked against what it should return, log and leave in storage and
+//! balances. Where `evm.json` runs one piece of code once, a scenario can
+//! say "deploy A, deploy B, call A.transfer, check B's storage".
+//!
+//! A scenario is JSON or TOML:
+//!
+//! ```toml
+//! fork = "Cancun"
+//!
+//! [accounts.alice]
+//! address = "0x00000000000000000000000000000000000a11ce"
+//! balance = "1000000"
+//!
+//! [[steps]]
+//! deploy = "0x600a600c600039600a6000f3602a60005260206000f3"
+//! from = "alice"
+//! as = "answer"
+//!
+//! [[steps]]
+//! call = "answer"
+//! from = "alice"
+//! signature = "answer()"
+//! expect = { returns = "0x000000000000000000000000000000000000000000000000000000000000002a" }
+//! ```
+//!
+//! Accounts are named, and the name of an account, or the one a deploy
+//! gives its contract with `as`, goes anywhere an address does: `from`,
+//! `call`, arguments of type `address` and the keys of expectations.
+//! Numbers are decimal or `0x` hex. A step calls `signature` with `args`
+//! encoded by their types, or sends `data` as is; a deploy appends the
+//! encoded `args` to its code. Steps are expected to succeed unless their
+//! `expect` says otherwise. An expected slot is read from the account it
+//! is listed under.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use primitive_types::U256;
+use serde_json::{json, Map, Value};
+
+use crate::abi::{self, ParamType, Token};
+use crate::fmtutil::{hex_address, hex_word};
+use crate::genesis::insert_alloc;
+use crate::host::Database;
+use crate::node::{DevNode, TxReceipt, DEV_CHAIN_ID, DEV_GAS_LIMIT};
+use crate::spec::{ChainConfig, SpecId};
+use crate::t8n::{fork, optional_quantity, parse_alloc, quantity, Alloc};
+use crate::trie::keccak;
+use crate::{hexutil, EvmData};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    Io(String),
+    Invalid(String),
+    /// A step could not be sent: a name that is not known yet, arguments
+    /// that do not fit the signature, or a transaction the node rejected.
+    Step {
+        step: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "scenario file error: {}", error),
+            ScenarioError::Invalid(reason) => write!(f, "invalid scenario: {}", reason),
+            ScenarioError::Step { step, reason } => write!(f, "{}: {}", step, reason),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Creates a contract running this init code.
+    Deploy(Vec<u8>),
+    /// Calls the named account or address.
+    Call(String),
+}
+
+/// What a step should come to. Left out, a part is not checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectations {
+    pub success: bool,
+    pub returns: Option<Vec<u8>>,
+    /// All the logs of the step, in order.
+    pub logs: Option<Vec<ExpectedLog>>,
+    /// Slot values after the step, by account.
+    pub storage: Vec<(String, U256, U256)>,
+    pub balances: Vec<(String, U256)>,
+}
+
+impl Default for Expectations {
+    fn default() -> Self {
+        Self {
+            success: true,
+            returns: None,
+            logs: None,
+            storage: vec![],
+            balances: vec![],
+        }
+    }
+}
+
+/// A log as expected. A topic is a word, or an event signature such as
+/// `Transfer(address,address,uint256)` standing for its hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedLog {
+    pub address: Option<String>,
+    pub topics: Option<Vec<U256>>,
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// `name`, or the step's place in the scenario.
+    pub name: String,
+    pub action: Action,
+    pub from: String,
+    pub value: U256,
+    /// The limit of the block unless given.
+    pub gas: Option<u64>,
+    pub signature: Option<String>,
+    pub args: Vec<Value>,
+    /// Calldata, when there is no signature.
+    pub data: Vec<u8>,
+    /// The name a deploy gives its contract.
+    pub label: Option<String>,
+    pub expect: Expectations,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scenario {
+    pub spec: SpecId,
+    pub chain_id: u64,
+    /// Addresses of the accounts, by name.
+    pub names: BTreeMap<String, U256>,
+    pub alloc: Alloc,
+    pub steps: Vec<Step>,
+}
+
+/// How a step went, and each expectation it did not meet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepReport {
+    pub name: String,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Of the contract a deploy created.
+    pub address: Option<U256>,
+    pub failures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScenarioReport {
+    pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.failures.is_empty())
+    }
+
+    pub fn to_json(&self) -> Value {
+        let steps: Vec<Value> = self
+            .steps
+            .iter()
+            .map(|step| {
+                json!({
+                    "name": step.name,
+                    "success": step.success,
+                    "gasUsed": format!("{:#x}", step.gas_used),
+                    "address": step.address.map(hex_address),
+                    "failures": step.failures,
+                })
+            })
+            .collect();
+        json!({ "passed": self.passed(), "steps": steps })
+    }
+}
+
+impl Scenario {
+    pub fn from_json(value: &Value) -> Result<Self, ScenarioError> {
+        let invalid = ScenarioError::Invalid;
+        if !value.is_object() {
+            return Err(invalid("not an object".to_string()));
+        }
+        let spec = match &value["fork"] {
+            Value::Null => SpecId::default(),
+            Value::String(name) => fork(name).ok_or_else(|| invalid(format!("fork {}", name)))?,
+            other => return Err(invalid(format!("fork {}", other))),
+        };
+        let chain_id = match optional_quantity(value, "chainId").map_err(invalid)? {
+            Some(id) => u64::try_from(id).map_err(|_| invalid("chainId too large".to_string()))?,
+            None => DEV_CHAIN_ID,
+        };
+
+        // Keyed by address for `parse_alloc`, the names kept aside
+        let mut names = BTreeMap::new();
+        let mut accounts = Map::new();
+        for (name, account) in value["accounts"].as_object().into_iter().flatten() {
+            let address = match &account["address"] {
+                Value::String(address) => address.as_str(),
+                Value::Null => name.as_str(),
+                other => return Err(invalid(format!("address {}", other))),
+            };
+            let address = hexutil::parse_address(address)
+                .map_err(|_| invalid(format!("account {} has no address", name)))?;
+            names.insert(name.clone(), address);
+            let mut account = account.clone();
+            if let Some(fields) = account.as_object_mut() {
+                fields.remove("address");
+            }
+            accounts.insert(hex_address(address), account);
+        }
+        let alloc =
+            parse_alloc(&Value::Object(accounts)).map_err(|error| invalid(error.to_string()))?;
+
+        let steps = match &value["steps"] {
+            Value::Array(steps) => steps
+                .iter()
+                .enumerate()
+                .map(|(index, step)| parse_step(index, step))
+                .collect::<Result<_, _>>()?,
+            _ => return Err(invalid("steps is not a list".to_string())),
+        };
+        Ok(Self {
+            spec,
+            chain_id,
+            names,
+            alloc,
+            steps,
+        })
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ScenarioError> {
+        let value: Value =
+            toml::from_str(text).map_err(|error| ScenarioError::Invalid(error.to_string()))?;
+        Self::from_json(&value)
+    }
+
+    /// Reads a scenario file, TOML when it ends in `.toml` and JSON
+    /// otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let text =
+            std::fs::read_to_string(path).map_err(|error| ScenarioError::Io(error.to_string()))?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            return Self::from_toml(&text);
+        }
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|error| ScenarioError::Invalid(error.to_string()))?;
+        Self::from_json(&value)
+    }
+
+    /// Runs the steps in order on a dev node holding the accounts. A step
+    /// that misses its expectations is reported and the next one runs; one
+    /// that cannot be sent ends the run with an error.
+    pub fn run(&self) -> Result<ScenarioReport, ScenarioError> {
+        let mut data = EvmData {
+            config: ChainConfig::new(self.spec),
+            ..Default::default()
+        };
+        insert_alloc(&mut data, &self.alloc);
+        let mut node = DevNode::new(data, self.chain_id);
+        let mut names = self.names.clone();
+        let mut report = ScenarioReport::default();
+        for step in &self.steps {
+            let outcome = run_step(&mut node, &mut names, step);
+            let step_report = outcome.map_err(|reason| ScenarioError::Step {
+                step: step.name.clone(),
+                reason,
+            })?;
+            report.steps.push(step_report);
+        }
+        Ok(report)
+    }
+}
+
+fn parse_step(index: usize, value: &Value) -> Result<Step, ScenarioError> {
+    let name = match &value["name"] {
+        Value::String(name) => name.clone(),
+        _ => format!("step {}", index + 1),
+    };
+    let invalid = |reason: String| ScenarioError::Invalid(format!("{}: {}", name, reason));
+    let text = |field: &str| match &value[field] {
+        Value::Null => Ok(None),
+        Value::String(text) => Ok(Some(text.clone())),
+        other => Err(invalid(format!("{} {}", field, other))),
+    };
+    let bytes = |text: Option<String>| {
+        text.map(|text| hexutil::decode(&text))
+            .transpose()
+            .map_err(|error| invalid(error.to_string()))
+    };
+
+    let action = match (bytes(text("deploy")?)?, text("call")?) {
+        (Some(code), None) => Action::Deploy(code),
+        (None, Some(to)) => Action::Call(to),
+        _ => return Err(invalid("needs one of deploy and call".to_string())),
+    };
+    let gas = optional_quantity(value, "gas")
+        .map_err(invalid)?
+        .map(|gas| u64::try_from(gas).map_err(|_| invalid("gas too large".to_string())))
+        .transpose()?;
+    let args = match &value["args"] {
+        Value::Null => vec![],
+        Value::Array(args) => args.clone(),
+        other => return Err(invalid(format!("args {}", other))),
+    };
+    let expect = match &value["expect"] {
+        Value::Null => Expectations::default(),
+        expect => parse_expectations(expect).map_err(invalid)?,
+    };
+    Ok(Step {
+        action,
+        from: text("from")?.ok_or_else(|| invalid("no from".to_string()))?,
+        value: optional_quantity(value, "value")
+            .map_err(invalid)?
+            .unwrap_or_default(),
+        gas,
+        signature: text("signature")?,
+        args,
+        data: bytes(text("data")?)?.unwrap_or_default(),
+        label: text("as")?,
+        expect,
+        name,
+    })
+}
+
+fn parse_expectations(value: &Value) -> Result<Expectations, String> {
+    let bytes = |value: &Value| match value {
+        Value::String(text) => hexutil::decode(text).map_err(|error| error.to_string()),
+        other => Err(format!("{} is not hex", other)),
+    };
+    let word = |value: &Value| quantity(value).ok_or_else(|| format!("{} is not a number", value));
+
+    let mut expect = Expectations::default();
+    match &value["success"] {
+        Value::Null => {}
+        Value::Bool(success) => expect.success = *success,
+        other => return Err(format!("success {}", other)),
+    }
+    if !value["returns"].is_null() {
+        expect.returns = Some(bytes(&value["returns"])?);
+    }
+    if let Some(logs) = value["logs"].as_array() {
+        let mut expected = vec![];
+        for log in logs {
+            let topics = log["topics"]
+                .as_array()
+                .map(|topics| topics.iter().map(topic).collect::<Result<Vec<_>, _>>())
+                .transpose()?;
+            expected.push(ExpectedLog {
+                address: log["address"].as_str().map(str::to_string),
+                topics,
+                data: (!log["data"].is_null())
+                    .then(|| bytes(&log["data"]))
+                    .transpose()?,
+            });
+        }
+        expect.logs = Some(expected);
+    }
+    for (account, slots) in value["storage"].as_object().into_iter().flatten() {
+        for (slot, slot_value) in slots.as_object().into_iter().flatten() {
+            expect
+                .storage
+                .push((account.clone(), word(&json!(slot))?, word(slot_value)?));
+        }
+    }
+    for (account, balance) in value["balances"].as_object().into_iter().flatten() {
+        expect.balances.push((account.clone(), word(balance)?));
+    }
+    Ok(expect)
+}
+
+fn topic(value: &Value) -> Result<U256, String> {
+    match value {
+        Value::String(signature) if signature.contains('(') => Ok(U256::from_big_endian(
+            keccak(signature.as_bytes()).as_bytes(),
+        )),
+        value => quantity(value).ok_or_else(|| format!("topic {}", value)),
+    }
+}
+
+// The address of a name, or the address written out
+fn address(names: &BTreeMap<String, U256>, text: &str) -> Result<U256, String> {
+    match names.get(text) {
+        Some(address) => Ok(*address),
+        None => hexutil::parse_address(text).map_err(|_| format!("unknown account {}", text)),
+    }
+}
+
+// An argument as the value of its type
+fn token(names: &BTreeMap<String, U256>, kind: &ParamType, value: &Value) -> Result<Token, String> {
+    let mismatch = || format!("{} is not a {:?}", value, kind);
+    let token = match (kind, value) {
+        (ParamType::Uint, _) => Token::Uint(quantity(value).ok_or_else(mismatch)?),
+        // Negative ones in two's complement
+        (ParamType::Int, Value::String(text)) if text.starts_with('-') => {
+            let magnitude = quantity(&json!(text[1..])).ok_or_else(mismatch)?;
+            Token::Int(magnitude.overflowing_neg().0)
+        }
+        (ParamType::Int, Value::Number(number)) if number.as_i64().is_some_and(|n| n < 0) => {
+            let magnitude = U256::from(number.as_i64().unwrap().unsigned_abs());
+            Token::Int(magnitude.overflowing_neg().0)
+        }
+        (ParamType::Int, _) => Token::Int(quantity(value).ok_or_else(mismatch)?),
+        (ParamType::Address, Value::String(text)) => Token::Address(address(names, text)?),
+        (ParamType::Bool, Value::Bool(value)) => Token::Bool(*value),
+        (ParamType::Bytes, Value::String(text)) => {
+            Token::Bytes(hexutil::decode(text).map_err(|error| error.to_string())?)
+        }
+        (ParamType::String, Value::String(text)) => Token::String(text.clone()),
+        (ParamType::Array(inner), Value::Array(items)) => Token::Array(
+            items
+                .iter()
+                .map(|item| token(names, inner, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => return Err(mismatch()),
+    };
+    Ok(token)
+}
+
+// The encoded arguments of `step`, for its signature
+fn encoded_args(names: &BTreeMap<String, U256>, step: &Step) -> Result<Vec<Token>, String> {
+    let Some(signature) = &step.signature else {
+        if !step.args.is_empty() {
+            return Err("args without a signature".to_string());
+        }
+        return Ok(vec![]);
+    };
+    let types = abi::parse_params(signature)
+        .ok_or_else(|| format!("unsupported signature {}", signature))?;
+    if types.len() != step.args.len() {
+        return Err(format!(
+            "{} takes {} arguments, {} given",
+            signature,
+            types.len(),
+            step.args.len()
+        ));
+    }
+    types
+        .iter()
+        .zip(&step.args)
+        .map(|(kind, value)| token(names, kind, value))
+        .collect()
+}
+
+fn run_step(
+    node: &mut DevNode,
+    names: &mut BTreeMap<String, U256>,
+    step: &Step,
+) -> Result<StepReport, String> {
+    let tokens = encoded_args(names, step)?;
+    let from = address(names, &step.from)?;
+    let mut request = json!({
+        "from": hex_address(from),
+        "value": format!("{:#x}", step.value),
+        "gas": format!("{:#x}", step.gas.unwrap_or(DEV_GAS_LIMIT)),
+    });
+    let input = match &step.action {
+        Action::Deploy(code) => [code.as_slice(), &abi::encode(&tokens)].concat(),
+        Action::Call(to) => {
+            request["to"] = json!(hex_address(address(names, to)?));
+            match &step.signature {
+                Some(signature) => abi::encode_function(signature, &tokens),
+                None => step.data.clone(),
+            }
+        }
+    };
+    request["data"] = json!(hexutil::encode_prefixed(&input));
+
+    let hash = node
+        .send_transaction(&request)
+        .map_err(|error| error.to_string())?;
+    let receipt = node.receipt(hash).cloned().expect("mined on the spot");
+    if let (Some(label), Some(created)) = (&step.label, receipt.contract_address) {
+        names.insert(label.clone(), created);
+    }
+    Ok(StepReport {
+        name: step.name.clone(),
+        success: receipt.success,
+        gas_used: receipt.gas_used,
+        address: receipt.contract_address,
+        failures: check(node.state(), names, &step.expect, &receipt)?,
+    })
+}
+
+// Each expectation the step did not meet, in words
+fn check(
+    state: &EvmData,
+    names: &BTreeMap<String, U256>,
+    expect: &Expectations,
+    receipt: &TxReceipt,
+) -> Result<Vec<String>, String> {
+    let mut failures = vec![];
+    if receipt.success != expect.success {
+        failures.push(if expect.success {
+            "expected to succeed, failed".to_string()
+        } else {
+            "expected to fail, succeeded".to_string()
+        });
+    }
+    if let Some(returns) = expect
+        .returns
+        .as_ref()
+        .filter(|returns| **returns != receipt.output)
+    {
+        failures.push(format!(
+            "returned {} instead of {}",
+            hexutil::encode_prefixed(&receipt.output),
+            hexutil::encode_prefixed(returns)
+        ));
+    }
+    if let Some(logs) = &expect.logs {
+        if logs.len() != receipt.logs.len() {
+            failures.push(format!(
+                "{} logs instead of {}",
+                receipt.logs.len(),
+                logs.len()
+            ));
+        }
+        for (index, (expected, log)) in logs.iter().zip(&receipt.logs).enumerate() {
+            if let Some(emitter) = &expected.address {
+                let emitter = address(names, emitter)?;
+                if emitter != log.address {
+                    failures.push(format!(
+                        "log {} from {} instead of {}",
+                        index,
+                        hex_address(log.address),
+                        hex_address(emitter)
+                    ));
+                }
+            }
+            if expected
+                .topics
+                .as_ref()
+                .is_some_and(|topics| *topics != log.topics)
+            {
+                let words = |topics: &[U256]| {
+                    let topics: Vec<String> = topics.iter().map(|topic| hex_word(*topic)).collect();
+                    format!("[{}]", topics.join(", "))
+                };
+                failures.push(format!(
+                    "log {} has topics {} instead of {}",
+                    index,
+                    words(&log.topics),
+                    words(expected.topics.as_ref().unwrap())
+                ));
+            }
+            if let Some(data) = expected.data.as_ref().filter(|data| **data != log.data) {
+                failures.push(format!(
+                    "log {} has data {} instead of {}",
+                    index,
+                    hexutil::encode_prefixed(&log.data),
+                    hexutil::encode_prefixed(data)
+                ));
+            }
+        }
+    }
+    for (account, slot, value) in &expect.storage {
+        let found = state.storage(address(names, account)?, *slot);
+        if found != *value {
+            failures.push(format!(
+                "slot {:#x} of {} is {:#x} instead of {:#x}",
+                slot, account, found, value
+            ));
+        }
+    }
+    for (account, balance) in &expect.balances {
+        let found = state
+            .basic(address(names, account)?)
+            .unwrap_or_default()
+            .balance;
+        if found != *balance {
+            failures.push(format!(
+                "balance of {} is {} instead of {}",
+                account, found, balance
+            ));
+        }
+    }
+    Ok(failures)
+}
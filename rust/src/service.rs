@@ -0,0 +1,642 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use primitive_types::U256;
+
+use crate::abi;
+use crate::accesslist::AccessListTracer;
+use crate::call::{CallRequest, StateOverrides};
+use crate::deploy::MAX_INITCODE_SIZE;
+use crate::env::{fee_caps, BlockEnv};
+use crate::gas::CALL_STIPEND;
+use crate::hexutil::{self, Bytes};
+use crate::host::Database;
+use crate::inspector::Inspector;
+use crate::memo::{CallCache, CallKey, ReadSet};
+use crate::precompiles;
+use crate::{
+    evm_with_inspector, AccessListItem, EvmContext, EvmData, EvmResult, HaltReason, TxData,
+};
+
+/// Caps every request is held to, so one request cannot hog the service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceLimits {
+    /// The most gas a request may ask for, and what requests that do not set
+    /// a limit get. Keeps every execution metered and bounded.
+    pub max_gas: u64,
+    pub max_code_size: usize,
+    pub max_calldata_size: usize,
+    /// Hard stops on top of gas, see `EvmConfig`. Unset, the service state
+    /// decides.
+    pub max_instructions: Option<u64>,
+    pub max_duration: Option<Duration>,
+}
+
+impl Default for ServiceLimits {
+    fn default() -> Self {
+        Self {
+            max_gas: 30_000_000,
+            max_code_size: MAX_INITCODE_SIZE,
+            max_calldata_size: 128 * 1024,
+            max_instructions: None,
+            max_duration: None,
+        }
+    }
+}
+
+/// One simulation. The state, balance, nonce and code entries are laid over
+/// the service state for this request only.
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub code: Vec<u8>,
+    pub tx_data: Option<TxData>,
+    /// Replaces the block context of the service state when set.
+    pub context: Option<EvmContext>,
+    /// Slots by account, set on top of the storage the account has.
+    pub state: HashMap<String, HashMap<U256, U256>>,
+    /// Accounts whose storage is replaced by their slots in `state`.
+    pub replaced_storage: HashSet<String>,
+    pub balances: HashMap<String, U256>,
+    pub nonces: HashMap<String, u64>,
+    pub codes: HashMap<String, Bytes>,
+    pub static_call: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceError {
+    CodeTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    CalldataTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    GasLimitTooHigh {
+        gas: U256,
+        limit: u64,
+    },
+    /// EIP-1559: the priority fee is above the max fee.
+    TipAboveFeeCap {
+        max_priority_fee_per_gas: U256,
+        max_fee_per_gas: U256,
+    },
+    /// EIP-1559: the max fee does not cover the base fee of the block.
+    FeeCapTooLow {
+        max_fee_per_gas: U256,
+        base_fee: U256,
+    },
+    /// A field of the request did not parse.
+    InvalidRequest(String),
+    /// Gas estimation: the call runs out of gas even with the most it may
+    /// have.
+    GasRequiredExceedsAllowance {
+        cap: u64,
+    },
+    /// Gas estimation: the call fails for a reason other than gas, whatever
+    /// the limit. The return data is the revert data, if it reverted.
+    ExecutionFailed {
+        halt_reason: Option<HaltReason>,
+        return_data: Vec<u8>,
+    },
+    /// The interpreter panicked, the message is the panic payload.
+    Panicked(String),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::CodeTooLarge { size, limit } => {
+                write!(f, "code is {} bytes, the limit is {}", size, limit)
+            }
+            ServiceError::CalldataTooLarge { size, limit } => {
+                write!(f, "calldata is {} bytes, the limit is {}", size, limit)
+            }
+            ServiceError::GasLimitTooHigh { gas, limit } => {
+                write!(f, "gas limit {} is above the limit of {}", gas, limit)
+            }
+            ServiceError::TipAboveFeeCap {
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+            } => write!(
+                f,
+                "max priority fee per gas higher than max fee per gas: maxPriorityFeePerGas: {}, maxFeePerGas: {}",
+                max_priority_fee_per_gas, max_fee_per_gas
+            ),
+            ServiceError::FeeCapTooLow {
+                max_fee_per_gas,
+                base_fee,
+            } => write!(
+                f,
+                "max fee per gas less than block base fee: maxFeePerGas: {}, baseFee: {}",
+                max_fee_per_gas, base_fee
+            ),
+            ServiceError::InvalidRequest(message) => write!(f, "invalid request: {}", message),
+            ServiceError::GasRequiredExceedsAllowance { cap } => {
+                write!(f, "gas required exceeds allowance ({})", cap)
+            }
+            ServiceError::ExecutionFailed {
+                halt_reason: Some(HaltReason::Revert),
+                return_data,
+            } => match abi::decode_revert(return_data) {
+                Some(reason) => write!(f, "execution reverted: {}", reason),
+                None => write!(f, "execution reverted"),
+            },
+            ServiceError::ExecutionFailed { halt_reason, .. } => {
+                write!(f, "execution failed: {:?}", halt_reason)
+            }
+            ServiceError::Panicked(message) => write!(f, "execution panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+/// What `EvmService::create_access_list` comes to.
+#[derive(Debug, Clone)]
+pub struct AccessListResult {
+    pub access_list: Vec<AccessListItem>,
+    /// Gas the call uses with the access list, which it pays for up front.
+    pub gas_used: u64,
+    /// Gas the call uses without one.
+    pub gas_used_without: u64,
+    /// The call with the access list, which may have failed.
+    pub result: EvmResult,
+}
+
+// Runs with an access list before `create_access_list` gives up on it
+// settling, as one that changes the gas can change the path taken
+const ACCESS_LIST_ROUNDS: usize = 10;
+
+/// Counters since the service started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Metrics {
+    pub requests: u64,
+    pub succeeded: u64,
+    /// Executions that ran to completion but failed, reverts included.
+    pub failed: u64,
+    /// Requests turned away by the limits before running.
+    pub rejected: u64,
+    pub panicked: u64,
+    /// Requests answered from the call cache, without running.
+    pub cached: u64,
+    pub gas_used: u64,
+    /// Time spent executing, summed over all requests.
+    pub busy_time: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    rejected: AtomicU64,
+    panicked: AtomicU64,
+    gas_used: AtomicU64,
+    busy_nanos: AtomicU64,
+}
+
+/// Runs simulations against a shared state for embedding in servers, such as
+/// transaction preview backends.
+///
+/// Requests never change the service state: each one runs on its own copy
+/// with the request overlay applied. That copy is a clone of the whole
+/// state, so every request costs time and memory in proportion to the
+/// state, not to what it touches; keep the state of a busy service small.
+/// `simulate` takes `&self`, so a service behind an `Arc` can serve requests
+/// from many threads at once.
+///
+/// A panic in the interpreter is caught and reported as an error for that
+/// request only; this is a last resort, the limits are what keep requests
+/// well behaved. A stack overflow is not a panic and aborts the process, so
+/// requests must run on threads spawned with
+/// [`EXECUTION_STACK_SIZE`](crate::EXECUTION_STACK_SIZE), as the JSON-RPC
+/// server's are: there calls nested to the depth limit fit.
+#[derive(Debug)]
+pub struct EvmService {
+    state: EvmData,
+    limits: ServiceLimits,
+    counters: Counters,
+    cache: Option<Mutex<CallCache>>,
+}
+
+impl EvmService {
+    pub fn new(state: EvmData) -> Self {
+        Self {
+            state,
+            limits: ServiceLimits::default(),
+            counters: Counters::default(),
+            cache: None,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: ServiceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keeps the results of up to `capacity` requests, and answers a request
+    /// that runs the same as one of them from it while what that one read
+    /// is unchanged, see `CallCache`. Requests with an inspector always run.
+    pub fn with_call_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(Mutex::new(CallCache::new(capacity)));
+        self
+    }
+
+    pub fn state(&self) -> &EvmData {
+        &self.state
+    }
+
+    pub fn limits(&self) -> &ServiceLimits {
+        &self.limits
+    }
+
+    pub fn simulate(&self, request: Request) -> Result<EvmResult, ServiceError> {
+        self.serve(request, None)
+    }
+
+    /// `simulate` with `inspector` watching the execution, for traces.
+    pub fn simulate_with_inspector(
+        &self,
+        request: Request,
+        inspector: &mut dyn Inspector,
+    ) -> Result<EvmResult, ServiceError> {
+        self.serve(request, Some(inspector))
+    }
+
+    fn serve(
+        &self,
+        request: Request,
+        inspector: Option<&mut dyn Inspector>,
+    ) -> Result<EvmResult, ServiceError> {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let outcome = self.run(request, inspector);
+        let elapsed = started.elapsed().as_nanos() as u64;
+        self.counters
+            .busy_nanos
+            .fetch_add(elapsed, Ordering::Relaxed);
+
+        let counter = match &outcome {
+            Ok(result) => {
+                self.counters
+                    .gas_used
+                    .fetch_add(result.gas_used, Ordering::Relaxed);
+                if result.success {
+                    &self.counters.succeeded
+                } else {
+                    &self.counters.failed
+                }
+            }
+            Err(ServiceError::Panicked(_)) => &self.counters.panicked,
+            Err(_) => &self.counters.rejected,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        outcome
+    }
+
+    /// Runs a call the way `eth_call` does, with `overrides` laid over the
+    /// service state for this call only.
+    pub fn call(
+        &self,
+        request: CallRequest,
+        overrides: &StateOverrides,
+    ) -> Result<EvmResult, ServiceError> {
+        let simulation = self.call_request(request, overrides)?;
+        self.simulate(simulation)
+    }
+
+    /// `call` with `inspector` watching the execution, as `debug_traceCall`
+    /// does.
+    pub fn call_with_inspector(
+        &self,
+        request: CallRequest,
+        overrides: &StateOverrides,
+        inspector: &mut dyn Inspector,
+    ) -> Result<EvmResult, ServiceError> {
+        let simulation = self.call_request(request, overrides)?;
+        self.simulate_with_inspector(simulation, inspector)
+    }
+
+    /// The smallest gas limit the call succeeds with, found the way
+    /// `eth_estimateGas` does: by binary search between the gas the call
+    /// used and its own limit, or `max_gas` when it sets none.
+    ///
+    /// Gas used is not enough on its own: refunds come off it afterwards
+    /// and every call only passes on 63/64 of what is left (EIP-150), so a
+    /// limit a little above what was used is tried first. Counts as one
+    /// request in the metrics.
+    pub fn estimate_gas(
+        &self,
+        request: CallRequest,
+        overrides: &StateOverrides,
+    ) -> Result<u64, ServiceError> {
+        let cap = match &request.gas {
+            Some(gas) => hexutil::parse_u64(gas)
+                .map_err(|e| ServiceError::InvalidRequest(format!("gas: {}", e)))?
+                .min(self.limits.max_gas),
+            None => self.limits.max_gas,
+        };
+        let simulation = self.call_request(request, overrides)?;
+        let run = |gas: u64| {
+            let mut simulation = simulation.clone();
+            let tx_data = simulation.tx_data.as_mut().unwrap();
+            tx_data.gas = Some(gas);
+            self.run(simulation, None)
+        };
+
+        let result = self.simulate(Request {
+            tx_data: simulation.tx_data.clone().map(|tx_data| TxData {
+                gas: Some(cap),
+                ..tx_data
+            }),
+            ..simulation.clone()
+        })?;
+        if !result.success {
+            if result.halt_reason == Some(HaltReason::OutOfGas) {
+                return Err(ServiceError::GasRequiredExceedsAllowance { cap });
+            }
+            return Err(ServiceError::ExecutionFailed {
+                halt_reason: result.halt_reason,
+                return_data: result.return_data,
+            });
+        }
+
+        // Succeeds with `hi`, fails with anything up to `lo`
+        let used = result.gas_used + result.gas_refunded;
+        let mut lo = used.saturating_sub(1);
+        let mut hi = cap;
+        let optimistic = (used + CALL_STIPEND) * 64 / 63;
+        if optimistic < hi {
+            if run(optimistic)?.success {
+                hi = optimistic;
+            } else {
+                lo = optimistic;
+            }
+        }
+        while lo + 1 < hi {
+            let mid = lo + (hi - lo) / 2;
+            if run(mid)?.success {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        Ok(hi)
+    }
+
+    /// The access list of the call, as `eth_createAccessList` makes it: the
+    /// call runs with the list it touched last time until the list stays the
+    /// same, see `AccessListTracer`. Counts as one request in the metrics.
+    pub fn create_access_list(
+        &self,
+        request: CallRequest,
+        overrides: &StateOverrides,
+    ) -> Result<AccessListResult, ServiceError> {
+        let simulation = self.call_request(request, overrides)?;
+        let tx_data = simulation.tx_data.clone().unwrap_or_default();
+        let state = &self.state;
+        let mut excluded = precompiles::active_addresses(state.config.spec);
+        excluded.extend(precompiles::chain_addresses(state.config.chain));
+        excluded.extend(state.precompiles.keys());
+        excluded.extend(tx_data.from.into_iter().chain(tx_data.to));
+        let run = |access_list: &[AccessListItem], counted: bool| {
+            let mut tracer = AccessListTracer::new(excluded.clone());
+            let mut simulation = simulation.clone();
+            simulation.tx_data = Some(TxData {
+                access_list: Some(access_list.to_vec()),
+                ..tx_data.clone()
+            });
+            let result = match counted {
+                true => self.serve(simulation, Some(&mut tracer)),
+                false => self.run(simulation, Some(&mut tracer)),
+            }?;
+            Ok::<_, ServiceError>((tracer.access_list(), result))
+        };
+
+        // The list each run went with, and the one it touched
+        let mut access_list = vec![];
+        let (mut touched, mut result) = run(&access_list, true)?;
+        let gas_used_without = result.gas_used;
+        for _ in 0..ACCESS_LIST_ROUNDS {
+            if touched == access_list {
+                break;
+            }
+            access_list = touched;
+            (touched, result) = run(&access_list, false)?;
+        }
+        Ok(AccessListResult {
+            access_list,
+            gas_used: result.gas_used,
+            gas_used_without,
+            result,
+        })
+    }
+
+    // Turns an `eth_call` request and its overrides into a simulation
+    fn call_request(
+        &self,
+        request: CallRequest,
+        overrides: &StateOverrides,
+    ) -> Result<Request, ServiceError> {
+        let invalid = |field: &str, error: hexutil::HexError| {
+            ServiceError::InvalidRequest(format!("{}: {}", field, error))
+        };
+        let mut simulation = Request::default();
+        for (address, account) in overrides {
+            let address = hexutil::parse_u256(address).map_err(|e| invalid("address", e))?;
+            let account = account.parse().map_err(ServiceError::InvalidRequest)?;
+            let key = address.to_string();
+            if let Some(balance) = account.balance {
+                simulation.balances.insert(key.clone(), balance);
+            }
+            if let Some(nonce) = account.nonce {
+                simulation.nonces.insert(key.clone(), nonce);
+            }
+            if let Some(code) = account.code {
+                simulation.codes.insert(key.clone(), Bytes::from(code));
+            }
+            if account.replaces_storage {
+                simulation.replaced_storage.insert(key.clone());
+            }
+            simulation.state.insert(key, account.storage);
+        }
+
+        let address = |name: &str, text: &Option<String>| {
+            let address = text.as_deref().map(hexutil::parse_address);
+            address.transpose().map_err(|e| invalid(name, e))
+        };
+        let word = |name: &str, text: &Option<String>| {
+            let word = text.as_deref().map(hexutil::parse_u256);
+            word.transpose().map_err(|e| invalid(name, e))
+        };
+        let to = address("to", &request.to)?;
+        let from = address("from", &request.from)?;
+        let gas = request.gas.as_deref().map(hexutil::parse_u64);
+        let gas = gas.transpose().map_err(|e| invalid("gas", e))?;
+        let input = request.data.as_deref().map(hexutil::decode);
+        let input = input.transpose().map_err(|e| invalid("data", e))?;
+        let input = input.unwrap_or_default();
+        simulation.code = match to {
+            Some(to) => match simulation.codes.get(&to.to_string()) {
+                Some(code) => code.to_vec(),
+                None => self.state.code(to).unwrap_or_default(),
+            },
+            // Creations run the data as init code, without input
+            None => input.clone(),
+        };
+        if request.gas_price.is_some()
+            && (request.max_fee_per_gas.is_some() || request.max_priority_fee_per_gas.is_some())
+        {
+            return Err(ServiceError::InvalidRequest(
+                "both gasPrice and (maxFeePerGas or maxPriorityFeePerGas) specified".to_string(),
+            ));
+        }
+        // A priority fee alone is capped by a max fee of the same
+        let max_priority_fee_per_gas =
+            word("maxPriorityFeePerGas", &request.max_priority_fee_per_gas)?;
+        let max_fee_per_gas =
+            word("maxFeePerGas", &request.max_fee_per_gas)?.or(max_priority_fee_per_gas);
+        simulation.tx_data = Some(TxData {
+            data: to.map(|_| Bytes(input)),
+            from,
+            origin: from,
+            to,
+            gasprice: word("gasPrice", &request.gas_price)?,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            value: word("value", &request.value)?,
+            gas,
+            ..Default::default()
+        });
+        Ok(simulation)
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        Metrics {
+            requests: load(&self.counters.requests),
+            succeeded: load(&self.counters.succeeded),
+            failed: load(&self.counters.failed),
+            rejected: load(&self.counters.rejected),
+            panicked: load(&self.counters.panicked),
+            cached: self
+                .cache
+                .as_ref()
+                .map_or(0, |cache| cache.lock().unwrap().hits()),
+            gas_used: load(&self.counters.gas_used),
+            busy_time: Duration::from_nanos(load(&self.counters.busy_nanos)),
+        }
+    }
+
+    fn run(
+        &self,
+        request: Request,
+        inspector: Option<&mut dyn Inspector>,
+    ) -> Result<EvmResult, ServiceError> {
+        let limits = &self.limits;
+        if request.code.len() > limits.max_code_size {
+            return Err(ServiceError::CodeTooLarge {
+                size: request.code.len(),
+                limit: limits.max_code_size,
+            });
+        }
+
+        let mut tx_data = request.tx_data.unwrap_or_default();
+        let calldata_size = tx_data.data.as_ref().map_or(0, |data| data.len());
+        if calldata_size > limits.max_calldata_size {
+            return Err(ServiceError::CalldataTooLarge {
+                size: calldata_size,
+                limit: limits.max_calldata_size,
+            });
+        }
+        match tx_data.gas {
+            Some(gas) if gas > limits.max_gas => {
+                return Err(ServiceError::GasLimitTooHigh {
+                    gas: U256::from(gas),
+                    limit: limits.max_gas,
+                });
+            }
+            Some(_) => {}
+            None => tx_data.gas = Some(limits.max_gas),
+        }
+
+        let mut data = self.state.clone();
+        if request.context.is_some() {
+            data.context = request.context;
+        }
+        if let Some((max_fee_per_gas, max_priority_fee_per_gas)) = fee_caps(&tx_data) {
+            if max_priority_fee_per_gas > max_fee_per_gas {
+                return Err(ServiceError::TipAboveFeeCap {
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                });
+            }
+            let base_fee = data
+                .context
+                .as_ref()
+                .map(BlockEnv::from)
+                .unwrap_or_default()
+                .basefee;
+            if max_fee_per_gas < base_fee {
+                return Err(ServiceError::FeeCapTooLow {
+                    max_fee_per_gas,
+                    base_fee,
+                });
+            }
+        }
+        data.tx_data = Some(tx_data);
+        let config = &mut data.evm_config;
+        config.max_instructions = limits.max_instructions.or(config.max_instructions);
+        config.max_duration = limits.max_duration.or(config.max_duration);
+        for address in request.replaced_storage {
+            data.state.remove(&address);
+            data.cleared_storage.insert(address);
+        }
+        for (address, slots) in request.state {
+            data.state.entry(address).or_default().extend(slots);
+        }
+        data.balances.extend(request.balances);
+        data.nonces.extend(request.nonces);
+        data.codes.extend(request.codes);
+
+        let code = request.code;
+        let writable = !request.static_call;
+        panic::catch_unwind(AssertUnwindSafe(|| match (inspector, &self.cache) {
+            (Some(inspector), _) => evm_with_inspector(&code, &mut data, writable, inspector),
+            (None, Some(cache)) => cached(cache, &code, &mut data, writable),
+            (None, None) => evm_with_inspector(&code, &mut data, writable, &mut ()),
+        }))
+        .map_err(|payload| ServiceError::Panicked(panic_message(payload)))
+    }
+}
+
+// Runs `code` or answers from `cache`, which is only locked to look the
+// call up and to keep its result, so requests still run side by side
+fn cached(cache: &Mutex<CallCache>, code: &[u8], data: &mut EvmData, writable: bool) -> EvmResult {
+    let Some(key) = CallKey::new(code, data, writable) else {
+        return evm_with_inspector(code, data, writable, &mut ());
+    };
+    if let Some(result) = cache.lock().unwrap().get(&key, data) {
+        return result;
+    }
+    let mut reads = ReadSet::new(data);
+    let result = evm_with_inspector(code, data, writable, &mut reads);
+    cache.lock().unwrap().insert(key, reads, result.clone());
+    result
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
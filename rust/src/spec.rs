@@ -0,0 +1,244 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize)]
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    Tangerine,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Merge,
+    Shanghai,
+    #[default]
+    Cancun,
+    Prague,
+    /// EOF (EIP-7692).
+    Osaka,
+}
+
+impl SpecId {
+    pub fn is_enabled_in(self, fork: SpecId) -> bool {
+        self >= fork
+    }
+
+    /// The fork Ethereum mainnet ran block `number` with, made at
+    /// `timestamp`. Later blocks run as Prague: `Osaka` here is EOF, which
+    /// mainnet did not ship.
+    pub fn mainnet(number: u64, timestamp: u64) -> SpecId {
+        const BY_TIMESTAMP: [(u64, SpecId); 3] = [
+            (1_746_612_311, SpecId::Prague),
+            (1_710_338_135, SpecId::Cancun),
+            (1_681_338_455, SpecId::Shanghai),
+        ];
+        const BY_NUMBER: [(u64, SpecId); 10] = [
+            (15_537_394, SpecId::Merge),
+            (12_965_000, SpecId::London),
+            (12_244_000, SpecId::Berlin),
+            (9_069_000, SpecId::Istanbul),
+            (7_280_000, SpecId::Petersburg),
+            (4_370_000, SpecId::Byzantium),
+            (2_675_000, SpecId::SpuriousDragon),
+            (2_463_000, SpecId::Tangerine),
+            (1_150_000, SpecId::Homestead),
+            (0, SpecId::Frontier),
+        ];
+        if number >= 15_537_394 {
+            if let Some((_, spec)) = BY_TIMESTAMP.iter().find(|(start, _)| timestamp >= *start) {
+                return *spec;
+            }
+        }
+        BY_NUMBER
+            .iter()
+            .find(|(start, _)| number >= *start)
+            .map_or(SpecId::Frontier, |(_, spec)| *spec)
+    }
+}
+
+/// Fork in which an opcode became available, `None` for bytes no fork
+/// defines as an opcode. New opcodes are enabled for a fork by listing them
+/// here.
+pub fn opcode_introduced_in(opcode: u8) -> Option<SpecId> {
+    let fork = match opcode {
+        // DELEGATECALL
+        0xf4 => SpecId::Homestead,
+        // RETURNDATASIZE, RETURNDATACOPY, STATICCALL, REVERT
+        0x3d | 0x3e | 0xfa | 0xfd => SpecId::Byzantium,
+        // SHL, SHR, SAR, EXTCODEHASH, CREATE2
+        0x1b..=0x1d | 0x3f | 0xf5 => SpecId::Constantinople,
+        // CHAINID, SELFBALANCE
+        0x46 | 0x47 => SpecId::Istanbul,
+        // BASEFEE
+        0x48 => SpecId::London,
+        // PUSH0
+        0x5f => SpecId::Shanghai,
+        // BLOBHASH, BLOBBASEFEE, TLOAD, TSTORE, MCOPY
+        0x49 | 0x4a | 0x5c..=0x5e => SpecId::Cancun,
+        // RJUMP, RJUMPI, RJUMPV, CALLF, RETF, JUMPF, DUPN, SWAPN, EXCHANGE,
+        // in EOF code only
+        0xe0..=0xe8 => SpecId::Osaka,
+        0x00..=0x0b
+        | 0x10..=0x1a
+        | 0x20
+        | 0x30..=0x3c
+        | 0x40..=0x45
+        | 0x50..=0x5b
+        | 0x60..=0xa4
+        | 0xf0..=0xf3
+        | 0xfe
+        | 0xff => SpecId::Frontier,
+        _ => return None,
+    };
+    Some(fork)
+}
+
+pub fn is_opcode_enabled(spec: SpecId, opcode: u8) -> bool {
+    opcode_introduced_in(opcode).is_some_and(|fork| spec.is_enabled_in(fork))
+}
+
+/// Fork-dependent gas costs. Access costs are the warm price; from Berlin
+/// onwards the first access in a transaction costs the `cold_*` price
+/// instead (EIP-2929), and SSTORE pays `cold_sload` on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasSchedule {
+    pub sload: u64,
+    pub balance: u64,
+    pub extcode: u64,
+    pub extcodehash: u64,
+    pub call: u64,
+    pub selfdestruct: u64,
+    /// Paid by SELFDESTRUCT when it brings the beneficiary into existence.
+    pub new_account: u64,
+    pub selfdestruct_refund: u64,
+    pub exp_byte: u64,
+    pub sstore_set: u64,
+    pub sstore_reset: u64,
+    pub sstore_clears_refund: u64,
+    pub cold_sload: u64,
+    pub cold_account_access: u64,
+    pub max_refund_quotient: u64,
+}
+
+impl GasSchedule {
+    pub fn new(spec: SpecId) -> Self {
+        let mut schedule = Self {
+            sload: 50,
+            balance: 20,
+            extcode: 20,
+            extcodehash: 400,
+            call: 40,
+            selfdestruct: 0,
+            new_account: 0,
+            selfdestruct_refund: 24000,
+            exp_byte: 10,
+            sstore_set: 20000,
+            sstore_reset: 5000,
+            sstore_clears_refund: 15000,
+            cold_sload: 0,
+            cold_account_access: 0,
+            max_refund_quotient: 2,
+        };
+
+        if spec.is_enabled_in(SpecId::Tangerine) {
+            // EIP-150
+            schedule.sload = 200;
+            schedule.balance = 400;
+            schedule.extcode = 700;
+            schedule.call = 700;
+            schedule.selfdestruct = 5000;
+            schedule.new_account = 25000;
+        }
+        if spec.is_enabled_in(SpecId::SpuriousDragon) {
+            // EIP-160
+            schedule.exp_byte = 50;
+        }
+        if spec.is_enabled_in(SpecId::Istanbul) {
+            // EIP-1884
+            schedule.sload = 800;
+            schedule.balance = 700;
+            schedule.extcodehash = 700;
+        }
+        if spec.is_enabled_in(SpecId::Berlin) {
+            // EIP-2929
+            schedule.sload = 100;
+            schedule.balance = 100;
+            schedule.extcode = 100;
+            schedule.extcodehash = 100;
+            schedule.call = 100;
+            schedule.sstore_reset = 2900;
+            schedule.cold_sload = 2100;
+            schedule.cold_account_access = 2600;
+        }
+        if spec.is_enabled_in(SpecId::London) {
+            // EIP-3529
+            schedule.sstore_clears_refund = 4800;
+            schedule.selfdestruct_refund = 0;
+            schedule.max_refund_quotient = 5;
+        }
+
+        schedule
+    }
+
+    /// Price of an account access that costs `warm` once the account is warm.
+    pub fn account_access(&self, cold: bool, warm: u64) -> u64 {
+        if cold && self.cold_account_access > 0 {
+            self.cold_account_access
+        } else {
+            warm
+        }
+    }
+
+    pub fn sload_cost(&self, cold: bool) -> u64 {
+        if cold && self.cold_sload > 0 {
+            self.cold_sload
+        } else {
+            self.sload
+        }
+    }
+}
+
+/// The chain a run is on, for where it departs from mainnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Chain {
+    #[default]
+    Ethereum,
+    /// L1 data fees, deposit transactions and P256VERIFY, see `optimism`.
+    #[cfg(feature = "optimism")]
+    Optimism,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainConfig {
+    pub spec: SpecId,
+    pub gas: GasSchedule,
+    pub chain: Chain,
+}
+
+impl ChainConfig {
+    pub fn new(spec: SpecId) -> Self {
+        Self {
+            spec,
+            gas: GasSchedule::new(spec),
+            chain: Chain::Ethereum,
+        }
+    }
+
+    /// An OP Stack chain at the Ethereum fork `spec`.
+    #[cfg(feature = "optimism")]
+    pub fn optimism(spec: SpecId) -> Self {
+        Self {
+            chain: Chain::Optimism,
+            ..Self::new(spec)
+        }
+    }
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self::new(SpecId::default())
+    }
+}
@@ -0,0 +1,115 @@
+//! The operand stack of a frame: 1024 words allocated up front, so pushing
+//! never reallocates, with every access bounds checked here rather than at
+//! each opcode.
+
+use primitive_types::U256;
+
+use crate::prelude::*;
+use crate::HaltReason;
+
+/// Most words the stack may hold.
+pub const STACK_LIMIT: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    Underflow,
+    Overflow,
+}
+
+impl From<StackError> for HaltReason {
+    fn from(error: StackError) -> Self {
+        match error {
+            StackError::Underflow => HaltReason::StackUnderflow,
+            StackError::Overflow => HaltReason::StackOverflow,
+        }
+    }
+}
+
+pub struct Stack {
+    data: Box<[U256; STACK_LIMIT]>,
+    len: usize,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Self {
+            data: Box::new([U256::zero(); STACK_LIMIT]),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The words on the stack, the top one last.
+    pub fn as_slice(&self) -> &[U256] {
+        &self.data[..self.len]
+    }
+
+    pub fn to_vec(&self) -> Vec<U256> {
+        self.as_slice().to_vec()
+    }
+
+    pub fn push(&mut self, value: U256) -> Result<(), StackError> {
+        if self.len == STACK_LIMIT {
+            return Err(StackError::Overflow);
+        }
+        self.data[self.len] = value;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<U256, StackError> {
+        if self.len == 0 {
+            return Err(StackError::Underflow);
+        }
+        self.len -= 1;
+        Ok(self.data[self.len])
+    }
+
+    /// The word `depth` places below the top, 0 being the top itself.
+    pub fn peek(&self, depth: usize) -> Result<U256, StackError> {
+        if depth >= self.len {
+            return Err(StackError::Underflow);
+        }
+        Ok(self.data[self.len - 1 - depth])
+    }
+
+    /// Pushes a copy of the `n`th word, 1 being the top, as DUPn does.
+    pub fn dup(&mut self, n: usize) -> Result<(), StackError> {
+        let value = self.peek(n.wrapping_sub(1))?;
+        self.push(value)
+    }
+
+    /// Exchanges the top word with the one `n` places below it, as SWAPn does.
+    pub fn swap(&mut self, n: usize) -> Result<(), StackError> {
+        if n >= self.len {
+            return Err(StackError::Underflow);
+        }
+        let top = self.len - 1;
+        self.data.swap(top, top - n);
+        Ok(())
+    }
+
+    /// Exchanges the words `a` and `b` places below the top, as EXCHANGE
+    /// does.
+    pub fn exchange(&mut self, a: usize, b: usize) -> Result<(), StackError> {
+        if a.max(b) >= self.len {
+            return Err(StackError::Underflow);
+        }
+        let top = self.len - 1;
+        self.data.swap(top - a, top - b);
+        Ok(())
+    }
+}
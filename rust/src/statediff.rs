@@ -0,0 +1,323 @@
+//! What a transaction changed, account by account, as the `stateDiff` of
+//! OpenEthereum's `trace_replayTransaction` shows it.
+//!
+//! Each field of an account is `"="` when it stayed the same, `{"*":
+//! {"from", "to"}}` when it changed, `{"+": value}` for an account the
+//! transaction brought into existence and `{"-": value}` for one it deleted.
+//! Accounts the transaction did not change are left out, and so are slots
+//! of storage.
+//!
+//! The diff is worked out from the journal when the transaction finishes,
+//! so changes that were rolled back are not in it.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use primitive_types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+use crate::fmtutil::{hex_address, hex_word};
+use crate::hexutil::{Bytes, HexError};
+use crate::host::Database;
+use crate::journal::JournalEntry;
+use crate::prelude::*;
+use crate::{hexutil, EvmData};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diff<T> {
+    Same,
+    Born(T),
+    Died(T),
+    Changed { from: T, to: T },
+}
+
+impl<T: PartialEq> Diff<T> {
+    fn between(from: T, to: T) -> Self {
+        if from == to {
+            Diff::Same
+        } else {
+            Diff::Changed { from, to }
+        }
+    }
+
+    pub fn is_same(&self) -> bool {
+        matches!(self, Diff::Same)
+    }
+
+    fn to_json(&self, hex: impl Fn(&T) -> String) -> Value {
+        match self {
+            Diff::Same => json!("="),
+            Diff::Born(value) => json!({ "+": hex(value) }),
+            Diff::Died(value) => json!({ "-": hex(value) }),
+            Diff::Changed { from, to } => json!({ "*": { "from": hex(from), "to": hex(to) } }),
+        }
+    }
+
+    fn from_json(
+        value: &Value,
+        parse: impl Fn(&str) -> Result<T, HexError>,
+    ) -> Result<Self, String> {
+        let field = |value: &Value| {
+            value
+                .as_str()
+                .and_then(|text| parse(text).ok())
+                .ok_or_else(|| format!("invalid diff value {}", value))
+        };
+        if value == "=" {
+            return Ok(Diff::Same);
+        }
+        if let Some(value) = value.get("+") {
+            return Ok(Diff::Born(field(value)?));
+        }
+        if let Some(value) = value.get("-") {
+            return Ok(Diff::Died(field(value)?));
+        }
+        match value.get("*") {
+            Some(change) => Ok(Diff::Changed {
+                from: field(&change["from"])?,
+                to: field(&change["to"])?,
+            }),
+            None => Err(format!("invalid diff {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub balance: Diff<U256>,
+    pub nonce: Diff<u64>,
+    pub code: Diff<Vec<u8>>,
+    /// Changed slots only.
+    pub storage: BTreeMap<U256, Diff<U256>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    pub accounts: BTreeMap<U256, AccountDiff>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let accounts: Map<String, Value> = self
+            .accounts
+            .iter()
+            .map(|(address, account)| {
+                let storage: Map<String, Value> = account
+                    .storage
+                    .iter()
+                    .map(|(key, diff)| (hex_word(*key), diff.to_json(|value| hex_word(*value))))
+                    .collect();
+                let account = json!({
+                    "balance": account.balance.to_json(|balance| format!("{:#x}", balance)),
+                    "nonce": account.nonce.to_json(|nonce| format!("{:#x}", nonce)),
+                    "code": account.code.to_json(|code| hexutil::encode_prefixed(code)),
+                    "storage": storage,
+                });
+                (hex_address(*address), account)
+            })
+            .collect();
+        Value::Object(accounts)
+    }
+
+    /// Reads back what `to_json` wrote.
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let accounts = value.as_object().ok_or("state diff is not an object")?;
+        let word = |text: &str| hexutil::parse_u256(text).map_err(|error| error.to_string());
+        let mut diff = StateDiff::default();
+        for (address, account) in accounts {
+            let mut storage = BTreeMap::new();
+            for (key, slot) in account["storage"].as_object().into_iter().flatten() {
+                storage.insert(word(key)?, Diff::from_json(slot, hexutil::parse_u256)?);
+            }
+            let account = AccountDiff {
+                balance: Diff::from_json(&account["balance"], hexutil::parse_u256)?,
+                nonce: Diff::from_json(&account["nonce"], hexutil::parse_u64)?,
+                code: Diff::from_json(&account["code"], hexutil::decode)?,
+                storage,
+            };
+            diff.accounts.insert(word(address)?, account);
+        }
+        Ok(diff)
+    }
+}
+
+// In the JSON of `to_json`, which `trace_replayTransaction` clients read
+impl Serialize for StateDiff {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StateDiff {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        StateDiff::from_json(&value).map_err(de::Error::custom)
+    }
+}
+
+// The fields of an account, `None` where neither the maps nor the database
+// have one
+struct Fields {
+    balance: Option<U256>,
+    nonce: Option<u64>,
+    code: Option<Vec<u8>>,
+}
+
+impl Fields {
+    fn exists(&self) -> bool {
+        self.balance.is_some()
+            || self.nonce.is_some()
+            || self.code.as_ref().is_some_and(|code| !code.is_empty())
+    }
+}
+
+impl EvmData {
+    /// The changes of the transaction running, from its journal.
+    pub fn state_diff(&self) -> StateDiff {
+        // The value of each entry before its first change in the journal
+        let mut balances: HashMap<&str, Option<U256>> = HashMap::new();
+        let mut nonces: HashMap<&str, Option<u64>> = HashMap::new();
+        let mut codes: HashMap<&str, Option<&Bytes>> = HashMap::new();
+        let mut slots: HashMap<(&str, U256), Option<U256>> = HashMap::new();
+        for entry in &self.journal {
+            match entry {
+                JournalEntry::BalanceChanged { address, previous } => {
+                    balances.entry(address).or_insert(*previous);
+                }
+                JournalEntry::NonceChanged { address, previous } => {
+                    nonces.entry(address).or_insert(*previous);
+                }
+                JournalEntry::CodeChanged { address, previous } => {
+                    codes.entry(address).or_insert(previous.as_ref());
+                }
+                JournalEntry::StateChanged {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    slots.entry((address, *key)).or_insert(*previous);
+                }
+                _ => {}
+            }
+        }
+
+        let word = |key: &str| U256::from_dec_str(key).unwrap();
+        let stored = |address: U256| {
+            let db = self.database.as_ref();
+            (
+                db.and_then(|db| db.basic(address)),
+                db.and_then(|db| db.code(address)),
+            )
+        };
+        let after = |address: U256| {
+            let key = address.to_string();
+            let (info, code) = stored(address);
+            Fields {
+                balance: self
+                    .balances
+                    .get(&key)
+                    .copied()
+                    .or(info.map(|info| info.balance)),
+                nonce: self
+                    .nonces
+                    .get(&key)
+                    .copied()
+                    .or(info.map(|info| info.nonce)),
+                code: self.codes.get(&key).map(|code| code.to_vec()).or(code),
+            }
+        };
+        let before = |address: U256| {
+            let key = address.to_string();
+            let (info, code) = stored(address);
+            let now = after(address);
+            Fields {
+                balance: match balances.get(key.as_str()) {
+                    Some(previous) => previous.or(info.map(|info| info.balance)),
+                    None => now.balance,
+                },
+                nonce: match nonces.get(key.as_str()) {
+                    Some(previous) => previous.or(info.map(|info| info.nonce)),
+                    None => now.nonce,
+                },
+                code: match codes.get(key.as_str()) {
+                    Some(previous) => previous.map(|code| code.to_vec()).or(code),
+                    None => now.code,
+                },
+            }
+        };
+
+        let mut diff = StateDiff::default();
+        let touched: BTreeSet<U256> = balances
+            .keys()
+            .chain(nonces.keys())
+            .chain(codes.keys())
+            .map(|address| word(address))
+            .collect();
+        for address in touched {
+            let (from, to) = (before(address), after(address));
+            let account = match (from.exists(), to.exists()) {
+                (false, false) => continue,
+                (false, true) => AccountDiff {
+                    balance: Diff::Born(to.balance.unwrap_or_default()),
+                    nonce: Diff::Born(to.nonce.unwrap_or_default()),
+                    code: Diff::Born(to.code.unwrap_or_default()),
+                    storage: BTreeMap::new(),
+                },
+                (true, false) => AccountDiff {
+                    balance: Diff::Died(from.balance.unwrap_or_default()),
+                    nonce: Diff::Died(from.nonce.unwrap_or_default()),
+                    code: Diff::Died(from.code.unwrap_or_default()),
+                    storage: BTreeMap::new(),
+                },
+                (true, true) => AccountDiff {
+                    balance: Diff::between(
+                        from.balance.unwrap_or_default(),
+                        to.balance.unwrap_or_default(),
+                    ),
+                    nonce: Diff::between(
+                        from.nonce.unwrap_or_default(),
+                        to.nonce.unwrap_or_default(),
+                    ),
+                    code: Diff::between(from.code.unwrap_or_default(), to.code.unwrap_or_default()),
+                    storage: BTreeMap::new(),
+                },
+            };
+            if account.balance.is_same() && account.nonce.is_same() && account.code.is_same() {
+                continue;
+            }
+            diff.accounts.insert(address, account);
+        }
+
+        let mut storage: BTreeMap<U256, BTreeMap<U256, Diff<U256>>> = BTreeMap::new();
+        for ((address, key), previous) in slots {
+            let address = word(address);
+            let from = match previous {
+                Some(value) => value,
+                None => self
+                    .database
+                    .as_ref()
+                    .map(|db| db.storage(address, key))
+                    .unwrap_or_default(),
+            };
+            let to = self.storage(address, key);
+            if from != to {
+                let slots = storage.entry(address).or_default();
+                slots.insert(key, Diff::Changed { from, to });
+            }
+        }
+        for (address, slots) in storage {
+            let account = diff.accounts.entry(address).or_insert_with(|| AccountDiff {
+                balance: Diff::Same,
+                nonce: Diff::Same,
+                code: Diff::Same,
+                storage: BTreeMap::new(),
+            });
+            account.storage = slots;
+        }
+        diff
+    }
+}
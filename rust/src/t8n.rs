@@ -0,0 +1,1140 @@
+//! A state transition tool speaking the JSON of geth's `evm t8n`, so test
+//! generators such as retesteth and execution-spec-tests can drive this
+//! interpreter: a pre-state (`alloc`), a block environment and transactions
+//! go in, the post-state and receipts come out.
+//!
+//! Transactions name their sender in `sender` (or `from`), or, with the
+//! `k256` feature, have it recovered from their signature or are signed
+//! with their `secretKey`. Legacy, EIP-2930, EIP-1559 and, from Prague,
+//! EIP-7702 transactions are accepted; the fees of the last two are the
+//! base fee, which is burnt, and their priority fee, which goes to the
+//! coinbase. With the `optimism` feature, `transition_with_config` runs them
+//! on an OP Stack chain, which also takes deposit transactions (type 0x7e),
+//! see `optimism`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use primitive_types::{H256, U256};
+use serde_json::{json, Map, Value};
+
+use crate::block::{log_rlp, Bloom, Receipt};
+use crate::delegation;
+use crate::deploy::{CODE_DEPOSIT_COST, MAX_CODE_SIZE, MAX_INITCODE_SIZE};
+use crate::ecdsa;
+use crate::fmtutil::{hex_address, hex_word};
+use crate::funcs::create_address;
+use crate::gas::{
+    effective_gas_price, init_code_cost, intrinsic_gas, PER_EMPTY_ACCOUNT_COST, TX_CREATE_COST,
+};
+use crate::genesis::insert_alloc;
+use crate::hexutil::Bytes;
+use crate::host::Database;
+use crate::inspector::Inspector;
+#[cfg(feature = "optimism")]
+use crate::optimism::{self, Deposit};
+use crate::spec::{Chain, ChainConfig, SpecId};
+use crate::trie::{keccak, ordered_trie_root, state_root, storage_root, TrieAccount};
+use crate::tx::{
+    legacy_chain_id, AccessListEntry, AccessListTx, Authorization, DynamicFeeTx, LegacyTx,
+    SetCodeTx, Signature, Transaction as SignedTransaction,
+};
+use crate::{evm_with_inspector, hexutil, rlp, AccessListItem, EvmContext, EvmData, Log, TxData};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum T8nError {
+    InvalidAlloc(String),
+    InvalidEnv(String),
+    InvalidTxs(String),
+}
+
+impl fmt::Display for T8nError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            T8nError::InvalidAlloc(reason) => write!(f, "invalid alloc: {}", reason),
+            T8nError::InvalidEnv(reason) => write!(f, "invalid env: {}", reason),
+            T8nError::InvalidTxs(reason) => write!(f, "invalid txs: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for T8nError {}
+
+/// The fork named as in geth's `--state.fork`.
+pub fn fork(name: &str) -> Option<SpecId> {
+    let spec = match name {
+        "Frontier" => SpecId::Frontier,
+        "Homestead" => SpecId::Homestead,
+        "EIP150" | "TangerineWhistle" => SpecId::Tangerine,
+        "EIP158" | "SpuriousDragon" => SpecId::SpuriousDragon,
+        "Byzantium" => SpecId::Byzantium,
+        "Constantinople" => SpecId::Constantinople,
+        "ConstantinopleFix" | "Petersburg" => SpecId::Petersburg,
+        "Istanbul" => SpecId::Istanbul,
+        "Berlin" => SpecId::Berlin,
+        "London" => SpecId::London,
+        "Merge" | "Paris" => SpecId::Merge,
+        "Shanghai" => SpecId::Shanghai,
+        "Cancun" => SpecId::Cancun,
+        "Prague" => SpecId::Prague,
+        "Osaka" => SpecId::Osaka,
+        _ => return None,
+    };
+    Some(spec)
+}
+
+/// An account of an alloc.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllocAccount {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl AllocAccount {
+    fn is_empty(&self) -> bool {
+        self.balance.is_zero() && self.nonce == 0 && self.code.is_empty() && self.storage.is_empty()
+    }
+
+    /// As geth writes it: quantities in hex, empty fields left out.
+    pub fn to_json(&self) -> Value {
+        let mut account = Map::new();
+        account.insert("balance".to_string(), json!(format!("{:#x}", self.balance)));
+        if self.nonce != 0 {
+            account.insert("nonce".to_string(), json!(format!("{:#x}", self.nonce)));
+        }
+        if !self.code.is_empty() {
+            account.insert(
+                "code".to_string(),
+                json!(hexutil::encode_prefixed(&self.code)),
+            );
+        }
+        if !self.storage.is_empty() {
+            let storage: Map<String, Value> = self
+                .storage
+                .iter()
+                .map(|(key, value)| (hex_word(*key), json!(hex_word(*value))))
+                .collect();
+            account.insert("storage".to_string(), Value::Object(storage));
+        }
+        Value::Object(account)
+    }
+
+    fn trie_account(&self) -> TrieAccount {
+        TrieAccount {
+            nonce: self.nonce,
+            balance: self.balance,
+            storage_root: storage_root(self.storage.iter().map(|(key, value)| (*key, *value))),
+            code_hash: keccak(&self.code),
+        }
+    }
+}
+
+pub type Alloc = BTreeMap<U256, AllocAccount>;
+
+// Hex with a prefix, decimal without, as the test generators mix both
+pub(crate) fn quantity(value: &Value) -> Option<U256> {
+    match value {
+        Value::String(text) if text.starts_with("0x") => hexutil::parse_u256(text).ok(),
+        Value::String(text) => U256::from_dec_str(text).ok(),
+        Value::Number(number) => number.as_u64().map(U256::from),
+        _ => None,
+    }
+}
+
+pub(crate) fn optional_quantity(value: &Value, field: &str) -> Result<Option<U256>, String> {
+    match &value[field] {
+        Value::Null => Ok(None),
+        field_value => quantity(field_value)
+            .map(Some)
+            .ok_or_else(|| format!("{} is not a number", field)),
+    }
+}
+
+// Nonces, gas and chain ids, which do not go above 64 bits
+fn optional_u64(value: &Value, field: &str) -> Result<Option<u64>, String> {
+    match optional_quantity(value, field)? {
+        Some(number) if number > U256::from(u64::MAX) => {
+            Err(format!("{} is above 2^64 - 1", field))
+        }
+        number => Ok(number.map(|number| number.as_u64())),
+    }
+}
+
+fn bytes(value: &Value, field: &str) -> Result<Vec<u8>, String> {
+    match &value[field] {
+        Value::Null => Ok(vec![]),
+        Value::String(text) => {
+            hexutil::decode(text).map_err(|error| format!("{}: {}", field, error))
+        }
+        _ => Err(format!("{} is not hex", field)),
+    }
+}
+
+pub fn parse_alloc(value: &Value) -> Result<Alloc, T8nError> {
+    let invalid = T8nError::InvalidAlloc;
+    let accounts = value
+        .as_object()
+        .ok_or_else(|| invalid("not an object".to_string()))?;
+    let mut alloc = Alloc::new();
+    for (address, account) in accounts {
+        let address = hexutil::parse_u256(address).map_err(|error| invalid(error.to_string()))?;
+        let field = |name: &str| optional_quantity(account, name).map_err(invalid);
+        let mut storage = BTreeMap::new();
+        for (key, value) in account["storage"].as_object().into_iter().flatten() {
+            let key = quantity(&json!(key)).ok_or_else(|| invalid(format!("slot {}", key)))?;
+            let value = quantity(value).ok_or_else(|| invalid(format!("slot value {}", value)))?;
+            if !value.is_zero() {
+                storage.insert(key, value);
+            }
+        }
+        alloc.insert(
+            address,
+            AllocAccount {
+                balance: field("balance")?.unwrap_or_default(),
+                nonce: optional_u64(account, "nonce")
+                    .map_err(invalid)?
+                    .unwrap_or_default(),
+                code: bytes(account, "code").map_err(invalid)?,
+                storage,
+            },
+        );
+    }
+    Ok(alloc)
+}
+
+pub fn alloc_json(alloc: &Alloc) -> Value {
+    Value::Object(
+        alloc
+            .iter()
+            .map(|(address, account)| (hex_address(*address), account.to_json()))
+            .collect(),
+    )
+}
+
+/// A transaction as test generators write it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Transaction {
+    /// 0 for legacy, 1 for EIP-2930, 2 for EIP-1559 and 4 for EIP-7702
+    /// transactions.
+    pub tx_type: u8,
+    /// What typed transactions sign over.
+    pub chain_id: u64,
+    pub sender: U256,
+    pub nonce: u64,
+    /// The max fee of EIP-1559 and EIP-7702 transactions.
+    pub gas_price: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub gas: u64,
+    /// `None` creates a contract.
+    pub to: Option<U256>,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListEntry>,
+    pub authorization_list: Vec<Authorization>,
+    pub signature: [U256; 3],
+    /// Set for the deposits of OP Stack chains, which are not signed.
+    #[cfg(feature = "optimism")]
+    pub deposit: Option<Deposit>,
+}
+
+impl Transaction {
+    /// Without a sender, the transaction is signed with its `secretKey`
+    /// (for `chain_id`, unless `protected` is false) or its sender is
+    /// recovered from `v`, `r` and `s`.
+    pub(crate) fn parse(value: &Value, chain_id: u64) -> Result<Self, String> {
+        let field = |name: &str| optional_quantity(value, name);
+        let small = |name: &str| optional_u64(value, name);
+        let tx_type = field("type")?.unwrap_or_default();
+        #[cfg(feature = "optimism")]
+        let deposit = tx_type == U256::from(optimism::DEPOSIT_TX_TYPE);
+        #[cfg(not(feature = "optimism"))]
+        let deposit = false;
+        if !deposit && (tx_type > U256::from(4) || tx_type == U256::from(3)) {
+            return Err(format!("transaction type {:#x} not supported", tx_type));
+        }
+        let tx_type = tx_type.as_u32() as u8;
+        let address = |value: &Value| {
+            value
+                .as_str()
+                .and_then(|text| hexutil::parse_u256(text).ok())
+                .ok_or_else(|| format!("invalid address {}", value))
+        };
+        let sender = match (&value["sender"], &value["from"]) {
+            (Value::Null, Value::Null) => None,
+            (Value::Null, from) => Some(address(from)?),
+            (sender, _) => Some(address(sender)?),
+        };
+        let to = match &value["to"] {
+            Value::Null => None,
+            Value::String(text) if text.is_empty() => None,
+            to => Some(address(to)?),
+        };
+        let input = match value["input"] {
+            Value::Null => bytes(value, "data")?,
+            _ => bytes(value, "input")?,
+        };
+        let gas_price = match tx_type {
+            2 | 4 => field("maxFeePerGas")?,
+            _ => field("gasPrice")?,
+        };
+        let mut tx = Self {
+            tx_type,
+            chain_id: small("chainId")?.unwrap_or(chain_id),
+            sender: sender.unwrap_or_default(),
+            nonce: small("nonce")?.unwrap_or_default(),
+            gas_price: gas_price.unwrap_or_default(),
+            max_priority_fee_per_gas: field("maxPriorityFeePerGas")?.unwrap_or_default(),
+            gas: small("gas")?.or(small("gasLimit")?).unwrap_or_default(),
+            to,
+            value: field("value")?.unwrap_or_default(),
+            input,
+            access_list: access_list(&value["accessList"])?,
+            authorization_list: authorization_list(&value["authorizationList"])?,
+            signature: [
+                field("v")?.unwrap_or_default(),
+                field("r")?.unwrap_or_default(),
+                field("s")?.unwrap_or_default(),
+            ],
+            #[cfg(feature = "optimism")]
+            deposit: None,
+        };
+        #[cfg(feature = "optimism")]
+        if deposit {
+            if sender.is_none() {
+                return Err("no sender".to_string());
+            }
+            let source_hash = field("sourceHash")?.unwrap_or_default();
+            tx.deposit = Some(Deposit {
+                source_hash: H256(source_hash.into()),
+                mint: field("mint")?.unwrap_or_default(),
+                is_system_tx: value["isSystemTx"].as_bool().unwrap_or_default(),
+            });
+        }
+        if sender.is_some() {
+            return Ok(tx);
+        }
+        let needs_k256 = |what: &str| {
+            if cfg!(feature = "k256") {
+                format!("invalid {}", what)
+            } else {
+                format!("no sender, {} needs the k256 feature", what)
+            }
+        };
+        if value["secretKey"].is_null() {
+            if tx.signature.iter().all(U256::is_zero) {
+                return Err("no sender".to_string());
+            }
+            let signed = tx.signed(legacy_chain_id(tx.signature[0]));
+            tx.sender = signed.sender().ok_or_else(|| needs_k256("signature"))?;
+            return Ok(tx);
+        }
+        let key = bytes(value, "secretKey")?;
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| "secretKey is not 32 bytes".to_string())?;
+        let protected = value["protected"].as_bool().unwrap_or(true);
+        let signed = tx
+            .signed(protected.then_some(chain_id))
+            .sign(&key)
+            .ok_or_else(|| needs_k256("secretKey"))?;
+        let signature = signed.signature();
+        tx.signature = [signature.v, signature.r, signature.s];
+        tx.sender = ecdsa::address_of(&key).unwrap();
+        Ok(tx)
+    }
+
+    /// As a signed transaction of its type. A legacy one is EIP-155 when
+    /// given a chain id, typed ones sign over their own.
+    pub(crate) fn signed(&self, chain_id: Option<u64>) -> SignedTransaction {
+        let signature = Signature {
+            v: self.signature[0],
+            r: self.signature[1],
+            s: self.signature[2],
+        };
+        match self.tx_type {
+            0 => SignedTransaction::Legacy(LegacyTx {
+                chain_id,
+                nonce: self.nonce,
+                gas_price: self.gas_price,
+                gas_limit: self.gas,
+                to: self.to,
+                value: self.value,
+                input: self.input.clone(),
+                signature,
+            }),
+            1 => SignedTransaction::AccessList(AccessListTx {
+                chain_id: self.chain_id,
+                nonce: self.nonce,
+                gas_price: self.gas_price,
+                gas_limit: self.gas,
+                to: self.to,
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+                signature,
+            }),
+            2 => SignedTransaction::DynamicFee(DynamicFeeTx {
+                chain_id: self.chain_id,
+                nonce: self.nonce,
+                max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+                max_fee_per_gas: self.gas_price,
+                gas_limit: self.gas,
+                to: self.to,
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+                signature,
+            }),
+            _ => SignedTransaction::SetCode(SetCodeTx {
+                chain_id: self.chain_id,
+                nonce: self.nonce,
+                max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+                max_fee_per_gas: self.gas_price,
+                gas_limit: self.gas,
+                to: self.to.unwrap_or_default(),
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+                authorization_list: self.authorization_list.clone(),
+                signature,
+            }),
+        }
+    }
+
+    /// A decoded transaction sent by `sender`. Blob transactions are not
+    /// taken.
+    pub(crate) fn from_signed(signed: &SignedTransaction, sender: U256) -> Result<Self, String> {
+        if let SignedTransaction::Blob(_) = signed {
+            return Err(format!(
+                "transaction type {:#x} not supported",
+                signed.tx_type()
+            ));
+        }
+        let signature = signed.signature();
+        Ok(Self {
+            tx_type: signed.tx_type(),
+            chain_id: signed.chain_id().unwrap_or_default(),
+            sender,
+            nonce: signed.nonce(),
+            gas_price: signed.max_fee_per_gas(),
+            max_priority_fee_per_gas: signed.max_priority_fee_per_gas().unwrap_or_default(),
+            gas: signed.gas_limit(),
+            to: signed.to(),
+            value: signed.value(),
+            input: signed.input().to_vec(),
+            access_list: signed.access_list().to_vec(),
+            authorization_list: signed.authorization_list().to_vec(),
+            signature: [signature.v, signature.r, signature.s],
+            #[cfg(feature = "optimism")]
+            deposit: None,
+        })
+    }
+
+    // Pays a max fee and a priority fee rather than a gas price (EIP-1559)
+    fn dynamic_fee(&self) -> bool {
+        matches!(self.tx_type, 2 | 4)
+    }
+
+    /// What the transaction pays per unit of gas in a block with
+    /// `base_fee`.
+    pub(crate) fn effective_gas_price(&self, base_fee: Option<U256>) -> U256 {
+        match base_fee {
+            Some(base_fee) if self.dynamic_fee() => {
+                effective_gas_price(self.gas_price, self.max_priority_fee_per_gas, base_fee)
+            }
+            _ => self.gas_price,
+        }
+    }
+
+    // The encoding in blocks: `[nonce, gasPrice, gas, to, value, data, v,
+    // r, s]` for legacy transactions, the type and the payload for the others
+    pub(crate) fn rlp(&self) -> Vec<u8> {
+        #[cfg(feature = "optimism")]
+        if let Some(deposit) = &self.deposit {
+            return self.deposit_rlp(deposit);
+        }
+        if self.tx_type != 0 {
+            return self.signed(None).encode();
+        }
+        let to = match self.to {
+            Some(to) => rlp::encode_bytes(&address_bytes(to)),
+            None => rlp::encode_bytes(&[]),
+        };
+        rlp::encode_list(&[
+            rlp::encode_u64(self.nonce),
+            rlp::encode_u256(self.gas_price),
+            rlp::encode_u64(self.gas),
+            to,
+            rlp::encode_u256(self.value),
+            rlp::encode_bytes(&self.input),
+            rlp::encode_u256(self.signature[0]),
+            rlp::encode_u256(self.signature[1]),
+            rlp::encode_u256(self.signature[2]),
+        ])
+    }
+
+    // The type and `[sourceHash, from, to, mint, value, gas, isSystemTx,
+    // data]`
+    #[cfg(feature = "optimism")]
+    fn deposit_rlp(&self, deposit: &Deposit) -> Vec<u8> {
+        let to = match self.to {
+            Some(to) => rlp::encode_bytes(&address_bytes(to)),
+            None => rlp::encode_bytes(&[]),
+        };
+        let mut encoded = vec![optimism::DEPOSIT_TX_TYPE];
+        encoded.extend(rlp::encode_list(&[
+            rlp::encode_bytes(deposit.source_hash.as_bytes()),
+            rlp::encode_bytes(&address_bytes(self.sender)),
+            to,
+            rlp::encode_u256(deposit.mint),
+            rlp::encode_u256(self.value),
+            rlp::encode_u64(self.gas),
+            rlp::encode_u64(deposit.is_system_tx as u64),
+            rlp::encode_bytes(&self.input),
+        ]));
+        encoded
+    }
+
+    fn tx_data(&self, to: U256, input: &[u8], gas: u64) -> TxData {
+        let dynamic_fee = self.dynamic_fee();
+        let access_list: Vec<AccessListItem> = self
+            .access_list
+            .iter()
+            .map(|entry| AccessListItem {
+                address: entry.address,
+                storage_keys: entry.storage_keys.clone(),
+            })
+            .collect();
+        TxData {
+            data: Some(Bytes::from(input)),
+            from: Some(self.sender),
+            to: Some(to),
+            gasprice: Some(self.gas_price).filter(|_| !dynamic_fee),
+            max_fee_per_gas: Some(self.gas_price).filter(|_| dynamic_fee),
+            max_priority_fee_per_gas: Some(self.max_priority_fee_per_gas).filter(|_| dynamic_fee),
+            origin: Some(self.sender),
+            value: Some(self.value),
+            gas: Some(gas),
+            access_list: (!access_list.is_empty()).then_some(access_list),
+            ..Default::default()
+        }
+    }
+}
+
+// `[{ "chainId", "address", "nonce", "yParity", "r", "s" }]`, which may be
+// left out. `v` stands in for a missing `yParity`.
+fn authorization_list(value: &Value) -> Result<Vec<Authorization>, String> {
+    let invalid = || format!("invalid authorization list {}", value);
+    let entries = match value {
+        Value::Null => return Ok(vec![]),
+        Value::Array(entries) => entries,
+        _ => return Err(invalid()),
+    };
+    entries
+        .iter()
+        .map(|entry| {
+            let field = |name: &str| {
+                optional_quantity(entry, name)
+                    .ok()
+                    .flatten()
+                    .ok_or_else(invalid)
+            };
+            let y_parity = field("yParity").or_else(|_| field("v"))?;
+            Ok(Authorization {
+                chain_id: field("chainId")?,
+                address: field("address")?,
+                nonce: u64::try_from(field("nonce")?).map_err(|_| invalid())?,
+                y_parity: u8::try_from(y_parity).map_err(|_| invalid())?,
+                r: field("r")?,
+                s: field("s")?,
+            })
+        })
+        .collect()
+}
+
+// `[{ "address", "storageKeys" }]`, which may be left out
+fn access_list(value: &Value) -> Result<Vec<AccessListEntry>, String> {
+    let invalid = || format!("invalid access list {}", value);
+    let entries = match value {
+        Value::Null => return Ok(vec![]),
+        Value::Array(entries) => entries,
+        _ => return Err(invalid()),
+    };
+    entries
+        .iter()
+        .map(|entry| {
+            let word = |value: &Value| {
+                value
+                    .as_str()
+                    .and_then(|text| hexutil::parse_u256(text).ok())
+                    .ok_or_else(invalid)
+            };
+            let storage_keys = match &entry["storageKeys"] {
+                Value::Null => vec![],
+                Value::Array(keys) => keys.iter().map(word).collect::<Result<_, _>>()?,
+                _ => return Err(invalid()),
+            };
+            Ok(AccessListEntry {
+                address: word(&entry["address"])?,
+                storage_keys,
+            })
+        })
+        .collect()
+}
+
+fn address_bytes(address: U256) -> [u8; 20] {
+    let mut word = [0u8; 32];
+    address.to_big_endian(&mut word);
+    word[12..].try_into().unwrap()
+}
+
+/// The post-state and the `result` object of `evm t8n`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct T8nOutput {
+    pub alloc: Alloc,
+    pub result: Value,
+}
+
+/// Applies `txs` to `alloc` in the block described by `env`. Transactions
+/// that cannot be included are listed in `rejected` with the reason, and
+/// leave the state as it was.
+pub fn transition(
+    alloc: &Value,
+    env: &Value,
+    txs: &Value,
+    spec: SpecId,
+    chain_id: u64,
+) -> Result<T8nOutput, T8nError> {
+    transition_with_config(alloc, env, txs, ChainConfig::new(spec), chain_id)
+}
+
+/// `transition` on the chain of `config`. On an OP Stack chain `env` also
+/// takes `l1BaseFee`, `l1BlobBaseFee`, `baseFeeScalar` and
+/// `blobBaseFeeScalar` for the L1 data fee.
+pub fn transition_with_config(
+    alloc: &Value,
+    env: &Value,
+    txs: &Value,
+    config: ChainConfig,
+    chain_id: u64,
+) -> Result<T8nOutput, T8nError> {
+    let spec = config.spec;
+    let pre = parse_alloc(alloc)?;
+    let env_field = |name: &str| optional_quantity(env, name).map_err(T8nError::InvalidEnv);
+    let coinbase = env_field("currentCoinbase")?.unwrap_or_default();
+    let base_fee = env_field("currentBaseFee")?.filter(|_| spec.is_enabled_in(SpecId::London));
+    let block_gas_limit = optional_u64(env, "currentGasLimit").map_err(T8nError::InvalidEnv)?;
+    let difficulty = if spec.is_enabled_in(SpecId::Merge) {
+        env_field("currentRandom")?
+    } else {
+        env_field("currentDifficulty")?
+    };
+
+    let mut context = Map::new();
+    let hex = |value: U256| json!(format!("{:#x}", value));
+    context.insert("coinbase".to_string(), json!(hex_address(coinbase)));
+    context.insert("chainid".to_string(), hex(U256::from(chain_id)));
+    for (name, field) in [
+        ("currentNumber", "number"),
+        ("currentTimestamp", "timestamp"),
+        ("currentGasLimit", "gaslimit"),
+        ("currentExcessBlobGas", "excess_blob_gas"),
+    ] {
+        if let Some(value) = env_field(name)? {
+            context.insert(field.to_string(), hex(value));
+        }
+    }
+    #[cfg(feature = "optimism")]
+    for (name, field) in [
+        ("l1BaseFee", "l1_base_fee"),
+        ("l1BlobBaseFee", "l1_blob_base_fee"),
+        ("baseFeeScalar", "base_fee_scalar"),
+        ("blobBaseFeeScalar", "blob_base_fee_scalar"),
+    ] {
+        if let Some(value) = env_field(name)? {
+            context.insert(field.to_string(), hex(value));
+        }
+    }
+    if let Some(base_fee) = base_fee {
+        context.insert("basefee".to_string(), hex(base_fee));
+    }
+    if let Some(difficulty) = difficulty {
+        context.insert("difficulty".to_string(), hex(difficulty));
+    }
+    let context: EvmContext = serde_json::from_value(Value::Object(context))
+        .map_err(|error| T8nError::InvalidEnv(error.to_string()))?;
+
+    let mut block_hashes: HashMap<U256, U256> = HashMap::new();
+    for (number, hash) in env["blockHashes"].as_object().into_iter().flatten() {
+        let number = quantity(&json!(number))
+            .ok_or_else(|| T8nError::InvalidEnv(format!("block number {}", number)))?;
+        let hash =
+            quantity(hash).ok_or_else(|| T8nError::InvalidEnv(format!("block hash {}", hash)))?;
+        block_hashes.insert(number, hash);
+    }
+
+    let mut data = EvmData {
+        context: Some(context),
+        config,
+        database: Some(Arc::new(block_hashes)),
+        ..Default::default()
+    };
+    insert_alloc(&mut data, &pre);
+
+    let txs = txs
+        .as_array()
+        .ok_or_else(|| T8nError::InvalidTxs("not an array".to_string()))?;
+    let mut included = vec![];
+    let mut receipts = vec![];
+    let mut receipts_json = vec![];
+    let mut rejected = vec![];
+    let mut created = BTreeSet::new();
+    let mut all_logs: Vec<Log> = vec![];
+    let mut bloom = Bloom::default();
+    let mut gas_used = 0u64;
+
+    for (index, tx) in txs.iter().enumerate() {
+        let tx = match Transaction::parse(tx, chain_id) {
+            Ok(tx) => tx,
+            Err(error) => {
+                rejected.push(json!({ "index": index, "error": error }));
+                continue;
+            }
+        };
+        let available = block_gas_limit.map(|limit| limit.saturating_sub(gas_used));
+        let outcome = match apply(&mut data, &tx, spec, coinbase, base_fee, available) {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                rejected.push(json!({ "index": index, "error": error }));
+                continue;
+            }
+        };
+        gas_used += outcome.gas_used;
+        if let Some(address) = outcome.created {
+            created.insert(address);
+        }
+
+        let encoded = tx.rlp();
+        let hash = keccak(&encoded);
+        let mut receipt_bloom = Bloom::default();
+        for log in &outcome.logs {
+            receipt_bloom.accrue_log(log);
+        }
+        bloom.accrue_bloom(&receipt_bloom);
+        let logs: Vec<Value> = outcome
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(position, log)| {
+                json!({
+                    "address": hex_address(log.address),
+                    "topics": log.topics.iter().map(|topic| hex_word(*topic)).collect::<Vec<_>>(),
+                    "data": hexutil::encode_prefixed(&log.data),
+                    "transactionHash": format!("{:?}", hash),
+                    "transactionIndex": format!("{:#x}", included.len()),
+                    "logIndex": format!("{:#x}", all_logs.len() + position),
+                    "removed": false,
+                })
+            })
+            .collect();
+        receipts_json.push(json!({
+            "type": format!("{:#x}", tx.tx_type),
+            "root": "0x",
+            "status": format!("{:#x}", outcome.success as u8),
+            "cumulativeGasUsed": format!("{:#x}", gas_used),
+            "logsBloom": hexutil::encode_prefixed(receipt_bloom.0),
+            "logs": if logs.is_empty() { Value::Null } else { json!(logs) },
+            "transactionHash": format!("{:?}", hash),
+            "contractAddress": hex_address(outcome.created.unwrap_or_default()),
+            "gasUsed": format!("{:#x}", outcome.gas_used),
+            "blockHash": format!("{:?}", H256::zero()),
+            "transactionIndex": format!("{:#x}", included.len()),
+        }));
+        all_logs.extend(outcome.logs.iter().cloned());
+        receipts.push(Receipt {
+            tx_type: tx.tx_type,
+            success: outcome.success,
+            gas_used: outcome.gas_used,
+            cumulative_gas_used: gas_used,
+            logs: outcome.logs,
+            bloom: receipt_bloom,
+        });
+        included.push(encoded);
+    }
+
+    // Every account the transactions may have changed
+    let mut addresses: BTreeSet<U256> = pre.keys().copied().collect();
+    addresses.extend(
+        data.balances
+            .keys()
+            .map(|key| U256::from_dec_str(key).unwrap()),
+    );
+    addresses.extend(
+        data.nonces
+            .keys()
+            .map(|key| U256::from_dec_str(key).unwrap()),
+    );
+    addresses.extend(
+        data.state
+            .keys()
+            .map(|key| U256::from_dec_str(key).unwrap()),
+    );
+    addresses.extend(created);
+
+    let mut post = Alloc::new();
+    for address in addresses {
+        let info = data.basic(address).unwrap_or_default();
+        let account = AllocAccount {
+            balance: info.balance,
+            nonce: info.nonce,
+            code: data.code(address).unwrap_or_default(),
+            storage: data
+                .state
+                .get(&address.to_string())
+                .into_iter()
+                .flatten()
+                .filter(|(_, value)| !value.is_zero())
+                .map(|(slot, value)| (*slot, *value))
+                .collect(),
+        };
+        // EIP-161 removes the empty accounts a transaction touches
+        if account.is_empty() && spec.is_enabled_in(SpecId::SpuriousDragon) {
+            continue;
+        }
+        post.insert(address, account);
+    }
+
+    let logs_rlp: Vec<Vec<u8>> = all_logs.iter().map(log_rlp).collect();
+    let mut result = json!({
+        "stateRoot": format!("{:?}", state_root(post.iter().map(|(address, account)| (*address, account.trie_account())))),
+        "txRoot": format!("{:?}", ordered_trie_root(&included)),
+        "receiptsRoot": format!("{:?}", ordered_trie_root(receipts.iter().map(Receipt::rlp))),
+        "logsHash": format!("{:?}", keccak(&rlp::encode_list(&logs_rlp))),
+        "logsBloom": hexutil::encode_prefixed(bloom.0),
+        "receipts": receipts_json,
+        "gasUsed": format!("{:#x}", gas_used),
+    });
+    if !rejected.is_empty() {
+        result["rejected"] = json!(rejected);
+    }
+    if let Some(difficulty) = difficulty.filter(|_| !spec.is_enabled_in(SpecId::Merge)) {
+        result["currentDifficulty"] = json!(format!("{:#x}", difficulty));
+    }
+    if let Some(base_fee) = base_fee {
+        result["currentBaseFee"] = json!(format!("{:#x}", base_fee));
+    }
+    Ok(T8nOutput {
+        alloc: post,
+        result,
+    })
+}
+
+pub(crate) struct Outcome {
+    pub success: bool,
+    /// What the transaction paid per unit of gas.
+    pub gas_price: U256,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    pub created: Option<U256>,
+    /// What the transaction returned, or reverted with.
+    pub output: Vec<u8>,
+}
+
+// Checks that `tx` can be included and applies it: the nonce, the fee, the
+// value and the execution. An error leaves `data` untouched.
+pub(crate) fn apply(
+    data: &mut EvmData,
+    tx: &Transaction,
+    spec: SpecId,
+    coinbase: U256,
+    base_fee: Option<U256>,
+    available: Option<u64>,
+) -> Result<Outcome, String> {
+    apply_with_inspector(data, tx, spec, coinbase, base_fee, available, &mut ())
+}
+
+// `apply` with `inspector` watching the execution
+pub(crate) fn apply_with_inspector(
+    data: &mut EvmData,
+    tx: &Transaction,
+    spec: SpecId,
+    coinbase: U256,
+    base_fee: Option<U256>,
+    available: Option<u64>,
+    inspector: &mut dyn Inspector,
+) -> Result<Outcome, String> {
+    #[cfg(feature = "optimism")]
+    if let Some(deposit) = &tx.deposit {
+        return apply_deposit(data, tx, deposit, spec, available, inspector);
+    }
+    if tx.tx_type == 4 {
+        if !spec.is_enabled_in(SpecId::Prague) {
+            return Err("transaction type not supported".to_string());
+        }
+        if tx.to.is_none() {
+            return Err("EIP-7702 transaction cannot be used to create contract".to_string());
+        }
+        if tx.authorization_list.is_empty() {
+            return Err("EIP-7702 transaction with empty auth list".to_string());
+        }
+    }
+    let sender = data.basic(tx.sender).unwrap_or_default();
+    if tx.nonce < sender.nonce {
+        return Err(format!(
+            "nonce too low: address {}, tx: {} state: {}",
+            hex_address(tx.sender),
+            tx.nonce,
+            sender.nonce
+        ));
+    }
+    if tx.nonce > sender.nonce {
+        return Err(format!(
+            "nonce too high: address {}, tx: {} state: {}",
+            hex_address(tx.sender),
+            tx.nonce,
+            sender.nonce
+        ));
+    }
+    let intrinsic = check_gas(tx, spec, available)?;
+    if tx.dynamic_fee() && tx.max_priority_fee_per_gas > tx.gas_price {
+        return Err(format!(
+            "max priority fee per gas higher than max fee per gas: address {}, maxPriorityFeePerGas: {}, maxFeePerGas: {}",
+            hex_address(tx.sender),
+            tx.max_priority_fee_per_gas,
+            tx.gas_price
+        ));
+    }
+    if base_fee.is_some_and(|base_fee| tx.gas_price < base_fee) {
+        return Err(format!(
+            "max fee per gas less than block base fee: address {}, maxFeePerGas: {}, baseFee: {}",
+            hex_address(tx.sender),
+            tx.gas_price,
+            base_fee.unwrap()
+        ));
+    }
+    // The sender has to afford the max fee, but pays the effective price
+    let l1_fee = l1_data_fee(data, tx);
+    let (max_fee, overflow) = tx.gas_price.overflowing_mul(U256::from(tx.gas));
+    let (max_cost, overflow_value) = max_fee.overflowing_add(tx.value);
+    let (max_cost, overflow_l1_fee) = max_cost.overflowing_add(l1_fee);
+    if overflow || overflow_value || overflow_l1_fee || sender.balance < max_cost {
+        return Err(format!(
+            "insufficient funds for gas * price + value: address {} have {} want {}",
+            hex_address(tx.sender),
+            sender.balance,
+            max_cost
+        ));
+    }
+    let gas_price = tx.effective_gas_price(base_fee);
+    let fee = gas_price * U256::from(tx.gas);
+
+    let balance = |data: &EvmData, address: U256| data.basic(address).unwrap_or_default().balance;
+    data.set_nonce(tx.sender, tx.nonce + 1);
+    data.set_balance(tx.sender, sender.balance - fee - l1_fee);
+    // Delegations stay even if the transaction fails. Authorities that
+    // already existed get part of their intrinsic gas back
+    if !tx.authorization_list.is_empty() {
+        let refund = delegation::authorize(data, &tx.authorization_list);
+        data.substate.refund += refund as i64;
+    }
+    // Only the nonce, the fee and the delegations stay if it fails
+    let outcome = run_transaction(data, tx, spec, intrinsic, inspector);
+    let gas_used = outcome.gas_used;
+    let refund = gas_price * U256::from(tx.gas - gas_used);
+    data.set_balance(tx.sender, balance(data, tx.sender) + refund);
+    let tip = gas_price - base_fee.unwrap_or_default();
+    data.set_balance(
+        coinbase,
+        balance(data, coinbase) + tip * U256::from(gas_used),
+    );
+    let base_fee_paid = base_fee.unwrap_or_default() * U256::from(gas_used);
+    pay_vaults(data, base_fee_paid, l1_fee);
+    data.finish_transaction();
+
+    Ok(Outcome {
+        gas_price,
+        ..outcome
+    })
+}
+
+// The intrinsic gas of `tx`, once it is known to fit in its gas limit and
+// the block
+fn check_gas(tx: &Transaction, spec: SpecId, available: Option<u64>) -> Result<u64, String> {
+    let mut intrinsic = intrinsic_gas(&tx.tx_data(U256::zero(), &tx.input, tx.gas), spec);
+    if tx.to.is_none() {
+        intrinsic += TX_CREATE_COST;
+        // EIP-3860
+        if spec.is_enabled_in(SpecId::Shanghai) && tx.input.len() > MAX_INITCODE_SIZE {
+            return Err(format!(
+                "max initcode size exceeded: code size {} limit {}",
+                tx.input.len(),
+                MAX_INITCODE_SIZE
+            ));
+        }
+        intrinsic += init_code_cost(tx.input.len(), spec);
+    }
+    intrinsic += PER_EMPTY_ACCOUNT_COST * tx.authorization_list.len() as u64;
+    if tx.gas < intrinsic {
+        return Err(format!(
+            "intrinsic gas too low: have {}, want {}",
+            tx.gas, intrinsic
+        ));
+    }
+    if available.is_some_and(|available| tx.gas > available) {
+        return Err(format!(
+            "gas limit reached: tx wants {}, block has {} left",
+            tx.gas,
+            available.unwrap()
+        ));
+    }
+    Ok(intrinsic)
+}
+
+// Moves the value and runs the transaction, whose fees are settled by the
+// caller. A transaction that fails leaves `data` as it found it.
+fn run_transaction(
+    data: &mut EvmData,
+    tx: &Transaction,
+    spec: SpecId,
+    intrinsic: u64,
+    inspector: &mut dyn Inspector,
+) -> Outcome {
+    let balance = |data: &EvmData, address: U256| data.basic(address).unwrap_or_default().balance;
+    let pre = data.clone();
+    data.set_balance(tx.sender, balance(data, tx.sender) - tx.value);
+    let (to, code, input) = match tx.to {
+        Some(to) => (to, data.code(to).unwrap_or_default(), tx.input.clone()),
+        None => (
+            create_address(tx.sender, tx.nonce),
+            tx.input.clone(),
+            vec![],
+        ),
+    };
+    data.set_balance(to, balance(data, to) + tx.value);
+    if tx.to.is_none() {
+        data.mark_created(to);
+    }
+
+    // The interpreter charges the intrinsic gas of what it runs, the rest
+    // of it is taken off up front
+    let run = tx.tx_data(to, &input, tx.gas);
+    let charged_before = intrinsic - intrinsic_gas(&run, spec);
+    let run = tx.tx_data(to, &input, tx.gas - charged_before);
+    data.tx_data = Some(run);
+    let result = evm_with_inspector(&code, data, true, inspector);
+    data.tx_data = None;
+
+    let mut success = result.success;
+    let mut gas_used = result.gas_used + charged_before;
+    let mut created = None;
+    if tx.to.is_none() && success {
+        let deployed = result.value.clone().unwrap_or_default();
+        let deposit = CODE_DEPOSIT_COST * deployed.len() as u64;
+        // EIP-170
+        let too_large =
+            spec.is_enabled_in(SpecId::SpuriousDragon) && deployed.len() > MAX_CODE_SIZE;
+        if !too_large && gas_used + deposit <= tx.gas {
+            gas_used += deposit;
+            data.codes
+                .insert(to.to_string(), Bytes::from(deployed.clone()));
+            created = Some(to);
+        } else {
+            success = false;
+            gas_used = tx.gas;
+        }
+    }
+
+    if !success {
+        *data = pre;
+    }
+    Outcome {
+        success,
+        gas_price: U256::zero(),
+        gas_used,
+        logs: if success { result.logs } else { vec![] },
+        created,
+        output: result.return_data,
+    }
+}
+
+// What an OP Stack chain charges for posting `tx` to L1, nothing elsewhere
+#[cfg_attr(not(feature = "optimism"), allow(unused_variables))]
+fn l1_data_fee(data: &EvmData, tx: &Transaction) -> U256 {
+    match data.config.chain {
+        Chain::Ethereum => U256::zero(),
+        #[cfg(feature = "optimism")]
+        Chain::Optimism => optimism::l1_data_fee(data.context.as_ref(), &tx.rlp()),
+    }
+}
+
+// Mainnet burns the base fee, OP Stack chains keep it and the L1 data fee
+// in vaults
+#[cfg_attr(not(feature = "optimism"), allow(unused_variables))]
+fn pay_vaults(data: &mut EvmData, base_fee: U256, l1_fee: U256) {
+    match data.config.chain {
+        Chain::Ethereum => {}
+        #[cfg(feature = "optimism")]
+        Chain::Optimism => {
+            for (vault, fee) in [
+                (optimism::BASE_FEE_VAULT, base_fee),
+                (optimism::L1_FEE_VAULT, l1_fee),
+            ] {
+                let balance = data.basic(vault).unwrap_or_default().balance;
+                data.set_balance(vault, balance + fee);
+            }
+        }
+    }
+}
+
+// Applies a deposit of an OP Stack chain: the mint and the nonce stay even
+// if it fails, and it pays no fees
+#[cfg(feature = "optimism")]
+fn apply_deposit(
+    data: &mut EvmData,
+    tx: &Transaction,
+    deposit: &Deposit,
+    spec: SpecId,
+    available: Option<u64>,
+    inspector: &mut dyn Inspector,
+) -> Result<Outcome, String> {
+    if data.config.chain != Chain::Optimism {
+        return Err(format!(
+            "transaction type {:#x} not supported",
+            optimism::DEPOSIT_TX_TYPE
+        ));
+    }
+    let available = available.filter(|_| !deposit.is_system_tx);
+    let intrinsic = check_gas(tx, spec, available)?;
+    let sender = data.basic(tx.sender).unwrap_or_default();
+    let minted = sender.balance.saturating_add(deposit.mint);
+    data.set_balance(tx.sender, minted);
+    data.set_nonce(tx.sender, sender.nonce + 1);
+    let tx = Transaction {
+        nonce: sender.nonce,
+        ..tx.clone()
+    };
+    let mut outcome = if minted < tx.value {
+        Outcome {
+            success: false,
+            gas_price: U256::zero(),
+            gas_used: tx.gas,
+            logs: vec![],
+            created: None,
+            output: vec![],
+        }
+    } else {
+        run_transaction(data, &tx, spec, intrinsic, inspector)
+    };
+    // Failed deposits use all their gas, system ones none of the block's
+    if !outcome.success {
+        outcome.gas_used = tx.gas;
+    }
+    if deposit.is_system_tx {
+        outcome.gas_used = 0;
+    }
+    data.finish_transaction();
+    Ok(outcome)
+}
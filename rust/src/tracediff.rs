@@ -0,0 +1,265 @@
+//! Finds where two EIP-3155 traces of the same execution part ways, such as
+//! the one `evmrs run --trace` writes and one from geth's or revm's tracer.
+//!
+//! Steps are compared in order on pc, opcode, depth and stack, and on gas
+//! and gas cost when both traces have them: the trace of `StreamTracer`
+//! leaves gas out. Numbers may be JSON numbers or hex strings, padded or
+//! not, and lines that are not steps, like the summary line at the end, are
+//! skipped.
+
+use std::fmt;
+
+use primitive_types::U256;
+use serde_json::Value;
+
+use crate::hexutil::parse_u256;
+use crate::opcodes;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceDiffError {
+    Io(String),
+    /// A line that is not JSON or a step with a field that cannot be read,
+    /// numbered from 1.
+    Invalid {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for TraceDiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceDiffError::Io(error) => write!(f, "{}", error),
+            TraceDiffError::Invalid { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for TraceDiffError {}
+
+/// One instruction of a trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub pc: u64,
+    pub op: u8,
+    pub depth: u64,
+    /// Bottom first, as EIP-3155 has it.
+    pub stack: Vec<U256>,
+    /// Gas left before the instruction.
+    pub gas: Option<u64>,
+    pub gas_cost: Option<u64>,
+}
+
+fn number(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(text) => parse_u256(text).ok(),
+        _ => None,
+    }
+}
+
+impl TraceStep {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        let field = |name: &str| -> Result<Option<U256>, String> {
+            match value.get(name) {
+                None | Some(Value::Null) => Ok(None),
+                Some(field) => number(field)
+                    .map(Some)
+                    .ok_or_else(|| format!("invalid {}", name)),
+            }
+        };
+        let small = |name: &str| -> Result<Option<u64>, String> {
+            match field(name)? {
+                Some(value) if value > U256::from(u64::MAX) => Err(format!("invalid {}", name)),
+                value => Ok(value.map(|value| value.as_u64())),
+            }
+        };
+        let op = small("op")?.ok_or("no op")?;
+        let stack = match value.get("stack") {
+            None => vec![],
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| number(item).ok_or_else(|| "invalid stack item".to_string()))
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err("invalid stack".to_string()),
+        };
+        Ok(TraceStep {
+            pc: small("pc")?.ok_or("no pc")?,
+            op: u8::try_from(op).map_err(|_| "invalid op".to_string())?,
+            depth: small("depth")?.unwrap_or(1),
+            stack,
+            gas: small("gas")?,
+            gas_cost: small("gasCost")?,
+        })
+    }
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "pc {} {} at depth {}",
+            self.pc,
+            opcodes::name(self.op).unwrap_or("INVALID"),
+            self.depth
+        )?;
+        if let Some(gas) = self.gas {
+            write!(f, ", gas {}", gas)?;
+        }
+        if let Some(cost) = self.gas_cost {
+            write!(f, ", cost {}", cost)?;
+        }
+        let stack: Vec<String> = self
+            .stack
+            .iter()
+            .map(|item| format!("{:#x}", item))
+            .collect();
+        write!(f, ", stack [{}]", stack.join(", "))
+    }
+}
+
+/// The steps of an EIP-3155 trace, one JSON object per line.
+pub fn parse_trace(text: &str) -> Result<Vec<TraceStep>, TraceDiffError> {
+    let mut steps = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let invalid = |reason: String| TraceDiffError::Invalid {
+            line: index + 1,
+            reason,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let value: Value =
+            serde_json::from_str(line).map_err(|error| invalid(error.to_string()))?;
+        // The summary line, and anything else without a pc
+        if value.get("pc").is_none() {
+            continue;
+        }
+        steps.push(TraceStep::from_json(&value).map_err(invalid)?);
+    }
+    Ok(steps)
+}
+
+/// Reads and parses the trace at `path`.
+pub fn load_trace(path: impl AsRef<std::path::Path>) -> Result<Vec<TraceStep>, TraceDiffError> {
+    let text =
+        std::fs::read_to_string(path).map_err(|error| TraceDiffError::Io(error.to_string()))?;
+    parse_trace(&text)
+}
+
+/// The first step where two traces differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Numbered from 0.
+    pub step: usize,
+    /// The step before, the same in both traces, whose instruction usually
+    /// made the difference.
+    pub previous: Option<TraceStep>,
+    /// `None` when the trace ended before this step.
+    pub left: Option<TraceStep>,
+    pub right: Option<TraceStep>,
+    /// What differs: `pc`, `op`, `depth`, `stack`, `gas`, `gasCost`, or
+    /// `length` when one trace ended.
+    pub fields: Vec<&'static str>,
+    /// Stack items that differ, by position from the top, with the left
+    /// value and the right one. `None` stands for an item one side lacks.
+    pub stack: Vec<(usize, Option<U256>, Option<U256>)>,
+}
+
+fn compare(left: &TraceStep, right: &TraceStep) -> Vec<&'static str> {
+    let mut fields = vec![];
+    if left.pc != right.pc {
+        fields.push("pc");
+    }
+    if left.op != right.op {
+        fields.push("op");
+    }
+    if left.depth != right.depth {
+        fields.push("depth");
+    }
+    if left.stack != right.stack {
+        fields.push("stack");
+    }
+    if left.gas.is_some() && right.gas.is_some() && left.gas != right.gas {
+        fields.push("gas");
+    }
+    if left.gas_cost.is_some() && right.gas_cost.is_some() && left.gas_cost != right.gas_cost {
+        fields.push("gasCost");
+    }
+    fields
+}
+
+/// Where `left` and `right` first differ, `None` when they are the same
+/// step for step.
+pub fn diff(left: &[TraceStep], right: &[TraceStep]) -> Option<Divergence> {
+    for step in 0..left.len().max(right.len()) {
+        let (l, r) = (left.get(step), right.get(step));
+        let fields = match (l, r) {
+            (Some(l), Some(r)) => compare(l, r),
+            _ => vec!["length"],
+        };
+        if fields.is_empty() {
+            continue;
+        }
+        let mut stack = vec![];
+        if let (Some(l), Some(r)) = (l, r) {
+            for position in 0..l.stack.len().max(r.stack.len()) {
+                let item = |stack: &[U256]| stack.iter().rev().nth(position).copied();
+                let (a, b) = (item(&l.stack), item(&r.stack));
+                if a != b {
+                    stack.push((position, a, b));
+                }
+            }
+        }
+        return Some(Divergence {
+            step,
+            previous: step.checked_sub(1).map(|previous| left[previous].clone()),
+            left: l.cloned(),
+            right: r.cloned(),
+            fields,
+            stack,
+        });
+    }
+    None
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Traces diverge at step {}: {}",
+            self.step,
+            self.fields.join(", ")
+        )?;
+        let side = |step: &Option<TraceStep>| match step {
+            Some(step) => step.to_string(),
+            None => "trace ended".to_string(),
+        };
+        if let Some(previous) = &self.previous {
+            writeln!(f, "  after: {}", previous)?;
+        }
+        writeln!(f, "  left:  {}", side(&self.left))?;
+        writeln!(f, "  right: {}", side(&self.right))?;
+        for (position, left, right) in &self.stack {
+            let item =
+                |value: &Option<U256>| value.map_or("-".to_string(), |v| format!("{:#x}", v));
+            writeln!(
+                f,
+                "  stack[{}] from the top: {} != {}",
+                position,
+                item(left),
+                item(right)
+            )?;
+        }
+        if let (Some(left), Some(right)) = (&self.left, &self.right) {
+            if let (Some(a), Some(b)) = (left.gas, right.gas) {
+                if a != b {
+                    let difference = a as i128 - b as i128;
+                    writeln!(f, "  gas: {} != {} ({:+})", a, b, difference)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
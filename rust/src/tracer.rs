@@ -0,0 +1,94 @@
+use std::io::{self, BufWriter, Write};
+
+use serde_json::json;
+
+use crate::inspector::{Inspector, Step};
+use crate::{hexutil, opcodes};
+
+/// Streams an EIP-3155 style JSON line per executed instruction to `W`.
+///
+/// Lines go through a bounded `BufWriter`, so memory use stays flat no matter
+/// how long the execution runs; a slow reader simply blocks the interpreter.
+/// The first write error stops the trace and is kept for `finish` to return.
+pub struct StreamTracer<W: Write> {
+    writer: BufWriter<W>,
+    with_memory: bool,
+    flush_interval: Option<usize>,
+    lines: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> StreamTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            with_memory: false,
+            flush_interval: None,
+            lines: 0,
+            error: None,
+        }
+    }
+
+    /// Include a hex dump of memory in every line.
+    pub fn with_memory(mut self, with_memory: bool) -> Self {
+        self.with_memory = with_memory;
+        self
+    }
+
+    /// Flush after every `lines` lines, for readers that follow the trace live.
+    pub fn with_flush_interval(mut self, lines: usize) -> Self {
+        self.flush_interval = Some(lines.max(1));
+        self
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Flushes what is left and hands back the writer, or the first error.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.into_inner().map_err(|error| error.into_error())
+    }
+
+    fn write_line(&mut self, line: &serde_json::Value) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, line)?;
+        self.writer.write_all(b"\n")?;
+        self.lines += 1;
+
+        if let Some(interval) = self.flush_interval {
+            if self.lines.is_multiple_of(interval) {
+                self.writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Inspector for StreamTracer<W> {
+    fn step(&mut self, step: &Step) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let stack: Vec<String> = step.stack.iter().map(|v| format!("{:#x}", v)).collect();
+        let mut line = json!({
+            "pc": step.pc,
+            "op": step.opcode,
+            "opName": opcodes::name(step.opcode).unwrap_or("INVALID"),
+            "depth": step.depth,
+            "stack": stack,
+            "memSize": step.memory.size,
+        });
+        if self.with_memory {
+            let memory = step.memory.data();
+            line["memory"] = json!(hexutil::encode_prefixed(memory));
+        }
+
+        if let Err(error) = self.write_line(&line) {
+            self.error = Some(error);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Roots of the hexary Merkle Patricia Trie, as found in block headers: the
+//! state root, storage roots and the receipt and transaction roots.
+//!
+//! Only roots are computed. The trie is built from the sorted entries in one
+//! pass and never kept, which is all validating against fixtures needs.
+
+use primitive_types::{H256, U256};
+use sha3::{Digest, Keccak256};
+
+use crate::prelude::*;
+use crate::rlp;
+
+/// Root of a trie without entries, the keccak hash of the empty string RLP.
+pub const EMPTY_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Hash of empty code, the code hash of accounts without code.
+pub const EMPTY_CODE_HASH: H256 = H256([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+pub fn keccak(data: &[u8]) -> H256 {
+    H256::from_slice(&Keccak256::digest(data))
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+// Packs nibbles two to a byte, with a first nibble flagging leaves and an
+// odd count.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + nibbles.len() as u8 % 2;
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push(flag << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(flag << 4);
+        nibbles
+    };
+    out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    out
+}
+
+// How a node is referenced from its parent: inline when its encoding is
+// shorter than a hash, by hash otherwise.
+fn reference(node: Vec<u8>) -> Vec<u8> {
+    if node.len() < 32 {
+        node
+    } else {
+        rlp::encode_bytes(keccak(&node).as_bytes())
+    }
+}
+
+// Encodes the node holding `entries`, which are sorted, unique and share
+// their first `depth` nibbles.
+fn encode_node(entries: &[(Vec<u8>, &[u8])], depth: usize) -> Vec<u8> {
+    match entries {
+        [] => rlp::encode_bytes(&[]),
+        [(key, value)] => rlp::encode_list(&[
+            rlp::encode_bytes(&hex_prefix(&key[depth..], true)),
+            rlp::encode_bytes(value),
+        ]),
+        _ => {
+            let first = &entries[0].0;
+            let last = &entries[entries.len() - 1].0;
+            let shared = first[depth..]
+                .iter()
+                .zip(&last[depth..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if shared > 0 {
+                let branch = encode_node(entries, depth + shared);
+                return rlp::encode_list(&[
+                    rlp::encode_bytes(&hex_prefix(&first[depth..depth + shared], false)),
+                    reference(branch),
+                ]);
+            }
+
+            // A key ending here can only be the first one, the others are
+            // longer and sort after it
+            let (value, rest) = if first.len() == depth {
+                (entries[0].1, &entries[1..])
+            } else {
+                (&[][..], entries)
+            };
+            let mut items = Vec::with_capacity(17);
+            for nibble in 0..16 {
+                let start = rest.partition_point(|(key, _)| key[depth] < nibble);
+                let end = rest.partition_point(|(key, _)| key[depth] <= nibble);
+                items.push(match end - start {
+                    0 => rlp::encode_bytes(&[]),
+                    _ => reference(encode_node(&rest[start..end], depth + 1)),
+                });
+            }
+            items.push(rlp::encode_bytes(value));
+            rlp::encode_list(&items)
+        }
+    }
+}
+
+/// Root of the trie mapping each key to its value. Entries with an empty
+/// value are not in the trie; of repeated keys the last one counts.
+pub fn trie_root<K, V>(entries: impl IntoIterator<Item = (K, V)>) -> H256
+where
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    let mut entries: Vec<(Vec<u8>, V)> = entries
+        .into_iter()
+        .map(|(key, value)| (nibbles(key.as_ref()), value))
+        .collect();
+    entries.reverse();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.dedup_by(|later, earlier| later.0 == earlier.0);
+
+    let entries: Vec<(Vec<u8>, &[u8])> = entries
+        .iter()
+        .filter(|(_, value)| !value.as_ref().is_empty())
+        .map(|(key, value)| (key.clone(), value.as_ref()))
+        .collect();
+    keccak(&encode_node(&entries, 0))
+}
+
+/// Root of a trie keyed by the keccak hash of each key, as the state and
+/// storage tries are.
+pub fn secure_trie_root<K, V>(entries: impl IntoIterator<Item = (K, V)>) -> H256
+where
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    trie_root(
+        entries
+            .into_iter()
+            .map(|(key, value)| (keccak(key.as_ref()), value)),
+    )
+}
+
+/// Root of a trie keyed by the RLP of each position, as the receipt and
+/// transaction tries are.
+pub fn ordered_trie_root<V: AsRef<[u8]>>(values: impl IntoIterator<Item = V>) -> H256 {
+    trie_root(
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (rlp::encode_u64(index as u64), value)),
+    )
+}
+
+/// Storage root of an account from its slots. Zero values are not stored.
+pub fn storage_root(slots: impl IntoIterator<Item = (U256, U256)>) -> H256 {
+    secure_trie_root(slots.into_iter().map(|(key, value)| {
+        let mut word = [0u8; 32];
+        key.to_big_endian(&mut word);
+        let value = if value.is_zero() {
+            vec![]
+        } else {
+            rlp::encode_u256(value)
+        };
+        (word, value)
+    }))
+}
+
+/// An account as the state trie stores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrieAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: H256,
+    pub code_hash: H256,
+}
+
+impl Default for TrieAccount {
+    fn default() -> Self {
+        Self {
+            nonce: 0,
+            balance: U256::zero(),
+            storage_root: EMPTY_ROOT,
+            code_hash: EMPTY_CODE_HASH,
+        }
+    }
+}
+
+impl TrieAccount {
+    pub fn rlp(&self) -> Vec<u8> {
+        rlp::encode_list(&[
+            rlp::encode_u64(self.nonce),
+            rlp::encode_u256(self.balance),
+            rlp::encode_bytes(self.storage_root.as_bytes()),
+            rlp::encode_bytes(self.code_hash.as_bytes()),
+        ])
+    }
+}
+
+/// State root from every account and its address.
+pub fn state_root(accounts: impl IntoIterator<Item = (U256, TrieAccount)>) -> H256 {
+    secure_trie_root(accounts.into_iter().map(|(address, account)| {
+        let mut word = [0u8; 32];
+        address.to_big_endian(&mut word);
+        (word[12..].to_vec(), account.rlp())
+    }))
+}
@@ -0,0 +1,178 @@
+//! The screen and commands of the terminal debugger, `evmrs debug`.
+//!
+//! Commands are read a line at a time, so the debugger runs in any terminal
+//! without putting it in raw mode: a key, then Enter.
+
+use primitive_types::U256;
+
+use crate::debugger::{Breakpoint, Debugger};
+use crate::hexutil::{self, parse_u256, parse_u64};
+use crate::opcodes;
+
+// Instructions shown around the current one
+const CODE_LINES: usize = 15;
+const MEMORY_ROW: usize = 16;
+
+const HIGHLIGHT: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+pub const HELP: &str = "[s]tep  [b]ack  [c]ontinue  [u] <pc> run until  \
+[p] <pc> / [o] <opcode> / [k] <slot> toggle breakpoint  [q]uit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Step,
+    StepBack,
+    Continue,
+    RunUntil(usize),
+    ToggleBreakpoint(Breakpoint),
+    Quit,
+}
+
+impl Command {
+    /// Reads one command line. An empty line steps, numbers are hex.
+    pub fn parse(line: &str) -> Option<Command> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("s");
+        let argument = words.next();
+        let pc = || Some(parse_u64(argument?).ok()? as usize);
+        let command = match command {
+            "s" => Command::Step,
+            "b" => Command::StepBack,
+            "c" => Command::Continue,
+            "u" => Command::RunUntil(pc()?),
+            "p" => Command::ToggleBreakpoint(Breakpoint::Pc(pc()?)),
+            "o" => Command::ToggleBreakpoint(Breakpoint::Opcode(parse_opcode(argument?)?)),
+            "k" => Command::ToggleBreakpoint(Breakpoint::StorageKey(parse_u256(argument?).ok()?)),
+            "q" => Command::Quit,
+            _ => return None,
+        };
+        Some(command)
+    }
+
+    /// Carries the command out, `false` for `Quit`.
+    pub fn apply(self, debugger: &mut Debugger) -> bool {
+        match self {
+            Command::Step => {
+                debugger.step();
+            }
+            Command::StepBack => {
+                debugger.step_back();
+            }
+            Command::Continue => {
+                debugger.resume();
+            }
+            Command::RunUntil(pc) => {
+                debugger.run_until(pc);
+            }
+            Command::ToggleBreakpoint(breakpoint) => {
+                if debugger.breakpoints().contains(&breakpoint) {
+                    debugger.remove_breakpoint(&breakpoint);
+                } else {
+                    debugger.add_breakpoint(breakpoint);
+                }
+            }
+            Command::Quit => return false,
+        }
+        true
+    }
+}
+
+// A mnemonic, or the opcode in hex
+fn parse_opcode(text: &str) -> Option<u8> {
+    let upper = text.to_uppercase();
+    (0..=255u8)
+        .find(|opcode| opcodes::name(*opcode) == Some(upper.as_str()))
+        .or_else(|| u8::try_from(parse_u64(text).ok()?).ok())
+}
+
+/// The whole screen: code with the current instruction highlighted, the
+/// stack, memory and the storage slots used so far.
+pub fn render(debugger: &Debugger) -> String {
+    let mut lines = vec![];
+    let Some(snapshot) = debugger.current() else {
+        let result = debugger.result();
+        lines.push(format!(
+            "Finished after {} steps: {}, {} gas used",
+            debugger.position(),
+            if result.success { "success" } else { "failure" },
+            result.gas_used
+        ));
+        if let Some(value) = &result.value {
+            lines.push(format!("Returned {}", hexutil::encode_prefixed(value)));
+        }
+        lines.push(String::new());
+        lines.push(HELP.to_string());
+        return lines.join("\n");
+    };
+
+    lines.push(format!(
+        "Code (depth {}, {:#x})",
+        snapshot.depth, snapshot.address
+    ));
+    let instructions = opcodes::instructions(&snapshot.code);
+    let current = instructions
+        .iter()
+        .position(|(pc, _)| *pc == snapshot.pc)
+        .unwrap_or_default();
+    let start = current
+        .saturating_sub(CODE_LINES / 2)
+        .min(instructions.len().saturating_sub(CODE_LINES));
+    for (pc, instruction) in instructions.iter().skip(start).take(CODE_LINES) {
+        let marker = if debugger.breakpoints().contains(&Breakpoint::Pc(*pc)) {
+            '*'
+        } else {
+            ' '
+        };
+        let line = format!("{} {:04x}  {}", marker, pc, instruction);
+        if *pc == snapshot.pc {
+            lines.push(format!("{}{}{}", HIGHLIGHT, line, RESET));
+        } else {
+            lines.push(line);
+        }
+    }
+
+    lines.push(String::new());
+    lines.push(format!("Stack ({} items, top first)", snapshot.stack.len()));
+    for (index, value) in snapshot.stack.iter().rev().enumerate() {
+        lines.push(format!("  {:>4}  {:#x}", index, value));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("Memory ({} bytes)", snapshot.memory.len()));
+    for (row, bytes) in snapshot.memory.chunks(MEMORY_ROW).enumerate() {
+        let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        lines.push(format!("  {:04x}  {}", row * MEMORY_ROW, bytes.join(" ")));
+    }
+
+    lines.push(String::new());
+    lines.push("Storage".to_string());
+    for key in used_slots(debugger) {
+        lines.push(format!("  {:#x}  {:#x}", key, snapshot.storage(key)));
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "Step {} of {}",
+        debugger.position() + 1,
+        debugger.snapshots().len()
+    ));
+    lines.push(HELP.to_string());
+    lines.join("\n")
+}
+
+// Slots read or written up to the current instruction, in order
+fn used_slots(debugger: &Debugger) -> Vec<U256> {
+    let mut slots = vec![];
+    for snapshot in &debugger.snapshots()[..=debugger.position()] {
+        // SLOAD, SSTORE
+        if matches!(snapshot.opcode, 0x54 | 0x55) {
+            if let Some(key) = snapshot.stack.last() {
+                if !slots.contains(key) {
+                    slots.push(*key);
+                }
+            }
+        }
+    }
+    slots
+}
@@ -0,0 +1,805 @@
+//! Transactions as nodes pass them around: the EIP-2718 envelope around
+//! legacy, access list (EIP-2930), dynamic fee (EIP-1559), blob (EIP-4844)
+//! and set code (EIP-7702) transactions, decoded from the raw bytes of
+//! `eth_sendRawTransaction` and encoded back.
+//!
+//! With the `k256` feature, transactions are signed and their senders
+//! recovered from the signature.
+
+use core::fmt;
+
+use primitive_types::{H256, U256};
+
+use crate::gas::effective_gas_price;
+use crate::hexutil::Bytes;
+use crate::prelude::*;
+use crate::rlp::{self, DecodeError, Item};
+use crate::trie::keccak;
+use crate::{ecdsa, AccessListItem, TxData};
+
+/// `v` is the recovery id of typed transactions, and for legacy ones the
+/// value with the chain id folded in from EIP-155 on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Signature {
+    pub v: U256,
+    pub r: U256,
+    pub s: U256,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessListEntry {
+    pub address: U256,
+    pub storage_keys: Vec<U256>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LegacyTx {
+    /// Set for EIP-155 transactions, which fold it into `v`.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    /// `None` creates a contract.
+    pub to: Option<U256>,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessListTx {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    pub to: Option<U256>,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListEntry>,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DynamicFeeTx {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: Option<U256>,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListEntry>,
+    pub signature: Signature,
+}
+
+/// Permission from the account that signs it to have its code delegate to
+/// `address` (EIP-7702). A chain id of zero is valid on every chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Authorization {
+    pub chain_id: U256,
+    pub address: U256,
+    pub nonce: u64,
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+/// Set code transactions cannot create contracts either.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetCodeTx {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: U256,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListEntry>,
+    pub authorization_list: Vec<Authorization>,
+    pub signature: Signature,
+}
+
+/// Blob transactions cannot create contracts. The blobs themselves travel
+/// next to the transaction and are dropped when decoding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobTx {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: U256,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListEntry>,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<H256>,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transaction {
+    Legacy(LegacyTx),
+    AccessList(AccessListTx),
+    DynamicFee(DynamicFeeTx),
+    Blob(BlobTx),
+    SetCode(SetCodeTx),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxDecodeError {
+    Empty,
+    Rlp(DecodeError),
+    /// An EIP-2718 type byte other than 1, 2, 3 and 4.
+    UnsupportedType(u8),
+    FieldCount {
+        expected: usize,
+        found: usize,
+    },
+    /// A recipient that is not 20 bytes, or empty where one is required.
+    InvalidAddress,
+}
+
+impl fmt::Display for TxDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxDecodeError::Empty => write!(f, "empty transaction"),
+            TxDecodeError::Rlp(error) => write!(f, "rlp: {}", error),
+            TxDecodeError::UnsupportedType(kind) => {
+                write!(f, "transaction type {:#x} not supported", kind)
+            }
+            TxDecodeError::FieldCount { expected, found } => {
+                write!(f, "{} fields, expected {}", found, expected)
+            }
+            TxDecodeError::InvalidAddress => write!(f, "invalid recipient address"),
+        }
+    }
+}
+
+impl core::error::Error for TxDecodeError {}
+
+impl From<DecodeError> for TxDecodeError {
+    fn from(error: DecodeError) -> Self {
+        TxDecodeError::Rlp(error)
+    }
+}
+
+impl Authorization {
+    /// The hash the authority signs: of `0x05` and `[chainId, address,
+    /// nonce]`.
+    pub fn signing_hash(&self) -> H256 {
+        let fields = rlp::encode_list(&[
+            rlp::encode_u256(self.chain_id),
+            encode_to(Some(self.address)),
+            rlp::encode_u64(self.nonce),
+        ]);
+        keccak(&[vec![0x05], fields].concat())
+    }
+
+    /// Signs the authorization with `secret_key`, replacing its signature.
+    /// `None` for an invalid key, or without the `k256` feature.
+    pub fn sign(mut self, secret_key: &[u8; 32]) -> Option<Self> {
+        let (y_parity, r, s) = ecdsa::sign(self.signing_hash().as_fixed_bytes(), secret_key)?;
+        self.y_parity = y_parity;
+        self.r = r;
+        self.s = s;
+        Some(self)
+    }
+
+    /// The account that signed the authorization. `None` when the
+    /// signature does not recover or has a high `s`, or without the `k256`
+    /// feature.
+    pub fn authority(&self) -> Option<U256> {
+        if self.y_parity > 1 || !ecdsa::is_low_s(self.s) {
+            return None;
+        }
+        ecdsa::recover(
+            self.signing_hash().as_fixed_bytes(),
+            self.y_parity,
+            self.r,
+            self.s,
+        )
+    }
+}
+
+impl Transaction {
+    /// Decodes the raw bytes of a transaction: an RLP list for legacy ones,
+    /// the type byte and an RLP list for the others. Blob transactions are
+    /// also taken in their network form, with the blobs attached.
+    pub fn decode(raw: &[u8]) -> Result<Self, TxDecodeError> {
+        let (&first, payload) = raw.split_first().ok_or(TxDecodeError::Empty)?;
+        if first >= 0xc0 {
+            return decode_legacy(rlp::decode(raw)?.list()?);
+        }
+        let item = rlp::decode(payload)?;
+        let fields = item.list()?;
+        match first {
+            0x01 => decode_access_list_tx(fields),
+            0x02 => decode_dynamic_fee_tx(fields),
+            // The network form wraps the transaction with its blobs,
+            // commitments and proofs
+            0x03 => match fields.first() {
+                Some(Item::List(inner)) if fields.len() == 4 => decode_blob_tx(inner),
+                _ => decode_blob_tx(fields),
+            },
+            0x04 => decode_set_code_tx(fields),
+            kind => Err(TxDecodeError::UnsupportedType(kind)),
+        }
+    }
+
+    // The fields before the signature, encoded
+    fn unsigned_fields(&self) -> Vec<Vec<u8>> {
+        let common = |nonce: u64, gas_limit: u64, to: Option<U256>, value, input: &[u8]| {
+            (
+                rlp::encode_u64(nonce),
+                rlp::encode_u64(gas_limit),
+                encode_to(to),
+                rlp::encode_u256(value),
+                rlp::encode_bytes(input),
+            )
+        };
+        match self {
+            Transaction::Legacy(tx) => {
+                let (nonce, gas, to, value, input) =
+                    common(tx.nonce, tx.gas_limit, tx.to, tx.value, &tx.input);
+                vec![nonce, rlp::encode_u256(tx.gas_price), gas, to, value, input]
+            }
+            Transaction::AccessList(tx) => {
+                let (nonce, gas, to, value, input) =
+                    common(tx.nonce, tx.gas_limit, tx.to, tx.value, &tx.input);
+                vec![
+                    rlp::encode_u64(tx.chain_id),
+                    nonce,
+                    rlp::encode_u256(tx.gas_price),
+                    gas,
+                    to,
+                    value,
+                    input,
+                    encode_access_list(&tx.access_list),
+                ]
+            }
+            Transaction::DynamicFee(tx) => {
+                let (nonce, gas, to, value, input) =
+                    common(tx.nonce, tx.gas_limit, tx.to, tx.value, &tx.input);
+                vec![
+                    rlp::encode_u64(tx.chain_id),
+                    nonce,
+                    rlp::encode_u256(tx.max_priority_fee_per_gas),
+                    rlp::encode_u256(tx.max_fee_per_gas),
+                    gas,
+                    to,
+                    value,
+                    input,
+                    encode_access_list(&tx.access_list),
+                ]
+            }
+            Transaction::Blob(tx) => {
+                let (nonce, gas, to, value, input) =
+                    common(tx.nonce, tx.gas_limit, Some(tx.to), tx.value, &tx.input);
+                let hashes: Vec<Vec<u8>> = tx
+                    .blob_versioned_hashes
+                    .iter()
+                    .map(|hash| rlp::encode_bytes(hash.as_bytes()))
+                    .collect();
+                vec![
+                    rlp::encode_u64(tx.chain_id),
+                    nonce,
+                    rlp::encode_u256(tx.max_priority_fee_per_gas),
+                    rlp::encode_u256(tx.max_fee_per_gas),
+                    gas,
+                    to,
+                    value,
+                    input,
+                    encode_access_list(&tx.access_list),
+                    rlp::encode_u256(tx.max_fee_per_blob_gas),
+                    rlp::encode_list(&hashes),
+                ]
+            }
+            Transaction::SetCode(tx) => {
+                let (nonce, gas, to, value, input) =
+                    common(tx.nonce, tx.gas_limit, Some(tx.to), tx.value, &tx.input);
+                vec![
+                    rlp::encode_u64(tx.chain_id),
+                    nonce,
+                    rlp::encode_u256(tx.max_priority_fee_per_gas),
+                    rlp::encode_u256(tx.max_fee_per_gas),
+                    gas,
+                    to,
+                    value,
+                    input,
+                    encode_access_list(&tx.access_list),
+                    encode_authorization_list(&tx.authorization_list),
+                ]
+            }
+        }
+    }
+
+    /// The canonical encoding, which the hash is taken over. Blob
+    /// transactions come out without their blobs.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut fields = self.unsigned_fields();
+        fields.extend(encode_signature(self.signature()));
+        self.envelope(rlp::encode_list(&fields))
+    }
+
+    // Typed transactions are their type followed by the RLP
+    fn envelope(&self, payload: Vec<u8>) -> Vec<u8> {
+        match self.tx_type() {
+            0 => payload,
+            kind => [vec![kind], payload].concat(),
+        }
+    }
+
+    /// The hash the sender signs: of the fields before the signature, with
+    /// the chain id and two zeros for EIP-155 legacy transactions.
+    pub fn signing_hash(&self) -> H256 {
+        let mut fields = self.unsigned_fields();
+        if let Transaction::Legacy(LegacyTx {
+            chain_id: Some(chain_id),
+            ..
+        }) = self
+        {
+            fields.extend([
+                rlp::encode_u64(*chain_id),
+                rlp::encode_bytes(&[]),
+                rlp::encode_bytes(&[]),
+            ]);
+        }
+        keccak(&self.envelope(rlp::encode_list(&fields)))
+    }
+
+    /// Signs the transaction with `secret_key`, replacing its signature.
+    /// `None` for an invalid key, or without the `k256` feature.
+    pub fn sign(mut self, secret_key: &[u8; 32]) -> Option<Self> {
+        let (parity, r, s) = ecdsa::sign(self.signing_hash().as_fixed_bytes(), secret_key)?;
+        let v = match &self {
+            Transaction::Legacy(tx) => match tx.chain_id {
+                Some(chain_id) => U256::from(chain_id) * 2 + 35 + parity,
+                None => U256::from(27 + parity),
+            },
+            _ => U256::from(parity),
+        };
+        let signature = match &mut self {
+            Transaction::Legacy(tx) => &mut tx.signature,
+            Transaction::AccessList(tx) => &mut tx.signature,
+            Transaction::DynamicFee(tx) => &mut tx.signature,
+            Transaction::Blob(tx) => &mut tx.signature,
+            Transaction::SetCode(tx) => &mut tx.signature,
+        };
+        *signature = Signature { v, r, s };
+        Some(self)
+    }
+
+    /// The address that signed the transaction. `None` when the signature
+    /// does not recover, has a high `s` (EIP-2) or a `v` that does not fit
+    /// the type, or without the `k256` feature.
+    pub fn sender(&self) -> Option<U256> {
+        let signature = self.signature();
+        let v = signature.v;
+        let recovery_id = match self {
+            Transaction::Legacy(tx) => match tx.chain_id {
+                Some(chain_id) => v.checked_sub(U256::from(chain_id) * 2 + 35)?,
+                None => v.checked_sub(U256::from(27))?,
+            },
+            _ => v,
+        };
+        if recovery_id > U256::one() || !ecdsa::is_low_s(signature.s) {
+            return None;
+        }
+        ecdsa::recover(
+            self.signing_hash().as_fixed_bytes(),
+            recovery_id.low_u32() as u8,
+            signature.r,
+            signature.s,
+        )
+    }
+
+    pub fn hash(&self) -> H256 {
+        keccak(&self.encode())
+    }
+
+    /// The EIP-2718 type, 0 for legacy transactions.
+    pub fn tx_type(&self) -> u8 {
+        match self {
+            Transaction::Legacy(_) => 0,
+            Transaction::AccessList(_) => 1,
+            Transaction::DynamicFee(_) => 2,
+            Transaction::Blob(_) => 3,
+            Transaction::SetCode(_) => 4,
+        }
+    }
+
+    /// Legacy transactions only have one from EIP-155 on.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            Transaction::Legacy(tx) => tx.chain_id,
+            Transaction::AccessList(tx) => Some(tx.chain_id),
+            Transaction::DynamicFee(tx) => Some(tx.chain_id),
+            Transaction::Blob(tx) => Some(tx.chain_id),
+            Transaction::SetCode(tx) => Some(tx.chain_id),
+        }
+    }
+
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Transaction::Legacy(tx) => tx.nonce,
+            Transaction::AccessList(tx) => tx.nonce,
+            Transaction::DynamicFee(tx) => tx.nonce,
+            Transaction::Blob(tx) => tx.nonce,
+            Transaction::SetCode(tx) => tx.nonce,
+        }
+    }
+
+    pub fn gas_limit(&self) -> u64 {
+        match self {
+            Transaction::Legacy(tx) => tx.gas_limit,
+            Transaction::AccessList(tx) => tx.gas_limit,
+            Transaction::DynamicFee(tx) => tx.gas_limit,
+            Transaction::Blob(tx) => tx.gas_limit,
+            Transaction::SetCode(tx) => tx.gas_limit,
+        }
+    }
+
+    pub fn to(&self) -> Option<U256> {
+        match self {
+            Transaction::Legacy(tx) => tx.to,
+            Transaction::AccessList(tx) => tx.to,
+            Transaction::DynamicFee(tx) => tx.to,
+            Transaction::Blob(tx) => Some(tx.to),
+            Transaction::SetCode(tx) => Some(tx.to),
+        }
+    }
+
+    pub fn value(&self) -> U256 {
+        match self {
+            Transaction::Legacy(tx) => tx.value,
+            Transaction::AccessList(tx) => tx.value,
+            Transaction::DynamicFee(tx) => tx.value,
+            Transaction::Blob(tx) => tx.value,
+            Transaction::SetCode(tx) => tx.value,
+        }
+    }
+
+    pub fn input(&self) -> &[u8] {
+        match self {
+            Transaction::Legacy(tx) => &tx.input,
+            Transaction::AccessList(tx) => &tx.input,
+            Transaction::DynamicFee(tx) => &tx.input,
+            Transaction::Blob(tx) => &tx.input,
+            Transaction::SetCode(tx) => &tx.input,
+        }
+    }
+
+    pub fn access_list(&self) -> &[AccessListEntry] {
+        match self {
+            Transaction::Legacy(_) => &[],
+            Transaction::AccessList(tx) => &tx.access_list,
+            Transaction::DynamicFee(tx) => &tx.access_list,
+            Transaction::Blob(tx) => &tx.access_list,
+            Transaction::SetCode(tx) => &tx.access_list,
+        }
+    }
+
+    pub fn authorization_list(&self) -> &[Authorization] {
+        match self {
+            Transaction::SetCode(tx) => &tx.authorization_list,
+            _ => &[],
+        }
+    }
+
+    pub fn blob_versioned_hashes(&self) -> &[H256] {
+        match self {
+            Transaction::Blob(tx) => &tx.blob_versioned_hashes,
+            _ => &[],
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        match self {
+            Transaction::Legacy(tx) => &tx.signature,
+            Transaction::AccessList(tx) => &tx.signature,
+            Transaction::DynamicFee(tx) => &tx.signature,
+            Transaction::Blob(tx) => &tx.signature,
+            Transaction::SetCode(tx) => &tx.signature,
+        }
+    }
+
+    /// The most the transaction pays per unit of gas: the gas price, or
+    /// the max fee of EIP-1559 transactions.
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            Transaction::Legacy(tx) => tx.gas_price,
+            Transaction::AccessList(tx) => tx.gas_price,
+            Transaction::DynamicFee(tx) => tx.max_fee_per_gas,
+            Transaction::Blob(tx) => tx.max_fee_per_gas,
+            Transaction::SetCode(tx) => tx.max_fee_per_gas,
+        }
+    }
+
+    /// What the transaction pays per unit of gas in a block with
+    /// `base_fee`: the base fee plus the priority fee, capped at the max
+    /// fee. Transactions with a gas price pay just that.
+    pub fn effective_gas_price(&self, base_fee: Option<U256>) -> U256 {
+        let (max_fee, priority_fee) = match self {
+            Transaction::DynamicFee(tx) => (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            Transaction::Blob(tx) => (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            Transaction::SetCode(tx) => (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            _ => return self.max_fee_per_gas(),
+        };
+        match base_fee {
+            Some(base_fee) => effective_gas_price(max_fee, priority_fee, base_fee),
+            None => max_fee,
+        }
+    }
+
+    /// The priority fee of EIP-1559 transactions, `None` for the others.
+    pub fn max_priority_fee_per_gas(&self) -> Option<U256> {
+        match self {
+            Transaction::DynamicFee(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::Blob(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::SetCode(tx) => Some(tx.max_priority_fee_per_gas),
+            _ => None,
+        }
+    }
+
+    /// The transaction as the interpreter runs it, sent by `sender`. An
+    /// EIP-1559 transaction carries its fees, which give the gas price once
+    /// the block is known. The authorizations of a set code transaction
+    /// are left out, see `delegation::authorize`.
+    pub fn tx_data(&self, sender: U256) -> TxData {
+        let access_list: Vec<AccessListItem> = self
+            .access_list()
+            .iter()
+            .map(|entry| AccessListItem {
+                address: entry.address,
+                storage_keys: entry.storage_keys.clone(),
+            })
+            .collect();
+        let priority_fee = self.max_priority_fee_per_gas();
+        TxData {
+            data: Some(Bytes::from(self.input())),
+            from: Some(sender),
+            to: self.to(),
+            gasprice: priority_fee.is_none().then(|| self.max_fee_per_gas()),
+            max_fee_per_gas: priority_fee.map(|_| self.max_fee_per_gas()),
+            max_priority_fee_per_gas: priority_fee,
+            origin: Some(sender),
+            value: Some(self.value()),
+            gas: Some(self.gas_limit()),
+            nonce: Some(self.nonce()),
+            blob_versioned_hashes: self
+                .blob_versioned_hashes()
+                .iter()
+                .map(|hash| U256::from_big_endian(hash.as_bytes()))
+                .collect(),
+            access_list: (!access_list.is_empty()).then_some(access_list),
+        }
+    }
+}
+
+/// The chain id an EIP-155 `v` folds in, `None` for the 27 and 28 of
+/// transactions from before it.
+pub fn legacy_chain_id(v: U256) -> Option<u64> {
+    (v >= U256::from(35)).then(|| ((v - 35) / 2).low_u64())
+}
+
+fn expect_fields(fields: &[Item], expected: usize) -> Result<(), TxDecodeError> {
+    if fields.len() != expected {
+        return Err(TxDecodeError::FieldCount {
+            expected,
+            found: fields.len(),
+        });
+    }
+    Ok(())
+}
+
+// An empty recipient creates a contract
+fn decode_to(item: &Item) -> Result<Option<U256>, TxDecodeError> {
+    match item.bytes()? {
+        [] => Ok(None),
+        bytes if bytes.len() == 20 => Ok(Some(U256::from_big_endian(bytes))),
+        _ => Err(TxDecodeError::InvalidAddress),
+    }
+}
+
+fn decode_signature(fields: &[Item]) -> Result<Signature, TxDecodeError> {
+    Ok(Signature {
+        v: fields[0].u256()?,
+        r: fields[1].u256()?,
+        s: fields[2].u256()?,
+    })
+}
+
+fn decode_access_list(item: &Item) -> Result<Vec<AccessListEntry>, TxDecodeError> {
+    item.list()?
+        .iter()
+        .map(|entry| {
+            let entry = entry.list()?;
+            expect_fields(entry, 2)?;
+            let address = decode_to(&entry[0])?.ok_or(TxDecodeError::InvalidAddress)?;
+            let storage_keys = entry[1]
+                .list()?
+                .iter()
+                .map(|key| match key.bytes()? {
+                    bytes if bytes.len() == 32 => Ok(U256::from_big_endian(bytes)),
+                    _ => Err(TxDecodeError::Rlp(DecodeError::InvalidNumber)),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(AccessListEntry {
+                address,
+                storage_keys,
+            })
+        })
+        .collect()
+}
+
+// `[nonce, gasPrice, gas, to, value, data, v, r, s]`
+fn decode_legacy(fields: &[Item]) -> Result<Transaction, TxDecodeError> {
+    expect_fields(fields, 9)?;
+    let signature = decode_signature(&fields[6..])?;
+    Ok(Transaction::Legacy(LegacyTx {
+        chain_id: legacy_chain_id(signature.v),
+        nonce: fields[0].u64()?,
+        gas_price: fields[1].u256()?,
+        gas_limit: fields[2].u64()?,
+        to: decode_to(&fields[3])?,
+        value: fields[4].u256()?,
+        input: fields[5].bytes()?.to_vec(),
+        signature,
+    }))
+}
+
+// `[chainId, nonce, gasPrice, gas, to, value, data, accessList, yParity, r, s]`
+fn decode_access_list_tx(fields: &[Item]) -> Result<Transaction, TxDecodeError> {
+    expect_fields(fields, 11)?;
+    Ok(Transaction::AccessList(AccessListTx {
+        chain_id: fields[0].u64()?,
+        nonce: fields[1].u64()?,
+        gas_price: fields[2].u256()?,
+        gas_limit: fields[3].u64()?,
+        to: decode_to(&fields[4])?,
+        value: fields[5].u256()?,
+        input: fields[6].bytes()?.to_vec(),
+        access_list: decode_access_list(&fields[7])?,
+        signature: decode_signature(&fields[8..])?,
+    }))
+}
+
+// `[chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gas, to, value,
+// data, accessList, yParity, r, s]`
+fn decode_dynamic_fee_tx(fields: &[Item]) -> Result<Transaction, TxDecodeError> {
+    expect_fields(fields, 12)?;
+    Ok(Transaction::DynamicFee(DynamicFeeTx {
+        chain_id: fields[0].u64()?,
+        nonce: fields[1].u64()?,
+        max_priority_fee_per_gas: fields[2].u256()?,
+        max_fee_per_gas: fields[3].u256()?,
+        gas_limit: fields[4].u64()?,
+        to: decode_to(&fields[5])?,
+        value: fields[6].u256()?,
+        input: fields[7].bytes()?.to_vec(),
+        access_list: decode_access_list(&fields[8])?,
+        signature: decode_signature(&fields[9..])?,
+    }))
+}
+
+// The fields of a dynamic fee transaction with `maxFeePerBlobGas` and
+// `blobVersionedHashes` before the signature
+fn decode_blob_tx(fields: &[Item]) -> Result<Transaction, TxDecodeError> {
+    expect_fields(fields, 14)?;
+    let blob_versioned_hashes = fields[10]
+        .list()?
+        .iter()
+        .map(|hash| match hash.bytes()? {
+            bytes if bytes.len() == 32 => Ok(H256::from_slice(bytes)),
+            _ => Err(TxDecodeError::Rlp(DecodeError::InvalidNumber)),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Transaction::Blob(BlobTx {
+        chain_id: fields[0].u64()?,
+        nonce: fields[1].u64()?,
+        max_priority_fee_per_gas: fields[2].u256()?,
+        max_fee_per_gas: fields[3].u256()?,
+        gas_limit: fields[4].u64()?,
+        to: decode_to(&fields[5])?.ok_or(TxDecodeError::InvalidAddress)?,
+        value: fields[6].u256()?,
+        input: fields[7].bytes()?.to_vec(),
+        access_list: decode_access_list(&fields[8])?,
+        max_fee_per_blob_gas: fields[9].u256()?,
+        blob_versioned_hashes,
+        signature: decode_signature(&fields[11..])?,
+    }))
+}
+
+// `[chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gas, to, value,
+// data, accessList, authorizationList, yParity, r, s]`
+fn decode_set_code_tx(fields: &[Item]) -> Result<Transaction, TxDecodeError> {
+    expect_fields(fields, 13)?;
+    let authorization_list = fields[9]
+        .list()?
+        .iter()
+        .map(|authorization| {
+            let fields = authorization.list()?;
+            expect_fields(fields, 6)?;
+            Ok(Authorization {
+                chain_id: fields[0].u256()?,
+                address: decode_to(&fields[1])?.ok_or(TxDecodeError::InvalidAddress)?,
+                nonce: fields[2].u64()?,
+                y_parity: u8::try_from(fields[3].u64()?)
+                    .map_err(|_| TxDecodeError::Rlp(DecodeError::InvalidNumber))?,
+                r: fields[4].u256()?,
+                s: fields[5].u256()?,
+            })
+        })
+        .collect::<Result<_, TxDecodeError>>()?;
+    Ok(Transaction::SetCode(SetCodeTx {
+        chain_id: fields[0].u64()?,
+        nonce: fields[1].u64()?,
+        max_priority_fee_per_gas: fields[2].u256()?,
+        max_fee_per_gas: fields[3].u256()?,
+        gas_limit: fields[4].u64()?,
+        to: decode_to(&fields[5])?.ok_or(TxDecodeError::InvalidAddress)?,
+        value: fields[6].u256()?,
+        input: fields[7].bytes()?.to_vec(),
+        access_list: decode_access_list(&fields[8])?,
+        authorization_list,
+        signature: decode_signature(&fields[10..])?,
+    }))
+}
+
+fn encode_to(to: Option<U256>) -> Vec<u8> {
+    match to {
+        Some(to) => {
+            let mut word = [0u8; 32];
+            to.to_big_endian(&mut word);
+            rlp::encode_bytes(&word[12..])
+        }
+        None => rlp::encode_bytes(&[]),
+    }
+}
+
+fn encode_access_list(access_list: &[AccessListEntry]) -> Vec<u8> {
+    let entries: Vec<Vec<u8>> = access_list
+        .iter()
+        .map(|entry| {
+            let keys: Vec<Vec<u8>> = entry
+                .storage_keys
+                .iter()
+                .map(|key| {
+                    let mut word = [0u8; 32];
+                    key.to_big_endian(&mut word);
+                    rlp::encode_bytes(&word)
+                })
+                .collect();
+            rlp::encode_list(&[encode_to(Some(entry.address)), rlp::encode_list(&keys)])
+        })
+        .collect();
+    rlp::encode_list(&entries)
+}
+
+fn encode_authorization_list(authorization_list: &[Authorization]) -> Vec<u8> {
+    let entries: Vec<Vec<u8>> = authorization_list
+        .iter()
+        .map(|authorization| {
+            rlp::encode_list(&[
+                rlp::encode_u256(authorization.chain_id),
+                encode_to(Some(authorization.address)),
+                rlp::encode_u64(authorization.nonce),
+                rlp::encode_u64(authorization.y_parity as u64),
+                rlp::encode_u256(authorization.r),
+                rlp::encode_u256(authorization.s),
+            ])
+        })
+        .collect();
+    rlp::encode_list(&entries)
+}
+
+fn encode_signature(signature: &Signature) -> [Vec<u8>; 3] {
+    [
+        rlp::encode_u256(signature.v),
+        rlp::encode_u256(signature.r),
+        rlp::encode_u256(signature.s),
+    ]
+}
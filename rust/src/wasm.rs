@@ -0,0 +1,51 @@
+//! wasm-bindgen bindings, to run the interpreter in the browser.
+//!
+//! Build a `cdylib` for `wasm32-unknown-unknown` without kzg, whose C library
+//! does not compile there:
+//!
+//! ```sh
+//! cargo rustc --lib --crate-type cdylib --target wasm32-unknown-unknown \
+//!     --no-default-features --features k256,wasm
+//! wasm-bindgen --target web target/wasm32-unknown-unknown/debug/evm.wasm --out-dir pkg
+//! ```
+//!
+//! ```js
+//! const result = run({ code: "0x600160020160005260206000a0", trace: true });
+//! result.stack; result.logs; result.trace;
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{asm, hexutil, opcodes, playground};
+
+fn to_js(value: &serde_json::Value) -> JsValue {
+    js_sys::JSON::parse(&value.to_string()).unwrap()
+}
+
+fn from_js(value: &JsValue) -> Result<serde_json::Value, JsError> {
+    let text: String = js_sys::JSON::stringify(value)
+        .map_err(|_| JsError::new("request is not JSON"))?
+        .into();
+    serde_json::from_str(&text).map_err(|error| JsError::new(&error.to_string()))
+}
+
+/// Runs a `playground` request and returns its result as an object.
+#[wasm_bindgen]
+pub fn run(request: JsValue) -> Result<JsValue, JsError> {
+    let output = playground::run(&from_js(&request)?).map_err(|error| JsError::new(&error))?;
+    Ok(to_js(&output))
+}
+
+/// Bytecode, 0x-prefixed, of an assembly listing.
+#[wasm_bindgen]
+pub fn assemble(source: &str) -> Result<String, JsError> {
+    let code = asm::assemble(source).map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(hexutil::encode_prefixed(&code))
+}
+
+/// One line per instruction of the bytecode.
+#[wasm_bindgen]
+pub fn disassemble(code: &str) -> Result<String, JsError> {
+    let code = hexutil::decode(code).map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(opcodes::disassemble(&code))
+}
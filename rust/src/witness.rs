@@ -0,0 +1,55 @@
+use std::collections::BTreeSet;
+
+use primitive_types::U256;
+
+use crate::inspector::{Inspector, Step};
+
+/// A piece of pre-state that an execution looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Access {
+    /// A slot of the account whose code is running.
+    Storage(U256, U256),
+    Code(U256),
+    Balance(U256),
+}
+
+/// Records which storage slots, code and balances an execution touches,
+/// the witness needed to run it again against a smaller state.
+///
+/// Accesses are read off the stack before each instruction, so reads of the
+/// running account itself (SELFBALANCE, CREATE nonces) are not seen.
+#[derive(Debug, Default)]
+pub struct WitnessTracer {
+    accesses: BTreeSet<Access>,
+}
+
+impl WitnessTracer {
+    pub fn accesses(&self) -> &BTreeSet<Access> {
+        &self.accesses
+    }
+
+    pub fn into_accesses(self) -> BTreeSet<Access> {
+        self.accesses
+    }
+}
+
+impl Inspector for WitnessTracer {
+    fn step(&mut self, step: &Step) {
+        let arg = |n: usize| step.stack.len().checked_sub(n + 1).map(|i| step.stack[i]);
+
+        let access = match step.opcode {
+            // SLOAD, SSTORE
+            0x54 | 0x55 => arg(0).map(|key| Access::Storage(step.data.env.tx.address, key)),
+            // BALANCE, SELFDESTRUCT
+            0x31 | 0xff => arg(0).map(Access::Balance),
+            // EXTCODESIZE, EXTCODECOPY, EXTCODEHASH
+            0x3b | 0x3c | 0x3f => arg(0).map(Access::Code),
+            // CALL, CALLCODE, DELEGATECALL, STATICCALL
+            0xf1 | 0xf2 | 0xf4 | 0xfa => arg(1).map(Access::Code),
+            _ => None,
+        };
+        if let Some(access) = access {
+            self.accesses.insert(access);
+        }
+    }
+}
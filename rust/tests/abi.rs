@@ -0,0 +1,107 @@
+//! ABI encoding and decoding of call arguments and return values.
+
+use evm::abi::{decode, encode, encode_function, parse_params, ParamType, Token};
+use primitive_types::U256;
+
+fn words(hex: &[&str]) -> Vec<u8> {
+    hex::decode(hex.concat()).unwrap()
+}
+
+#[test]
+fn static_arguments() {
+    // The first example of the Solidity ABI specification
+    let calldata = encode_function(
+        "baz(uint32,bool)",
+        &[Token::Uint(U256::from(69)), Token::Bool(true)],
+    );
+    let expected = words(&[
+        "cdcd77c0",
+        "0000000000000000000000000000000000000000000000000000000000000045",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    ]);
+    assert_eq!(calldata, expected);
+}
+
+#[test]
+fn dynamic_arguments() {
+    // The second example of the Solidity ABI specification
+    let tokens = vec![
+        Token::Bytes(b"dave".to_vec()),
+        Token::Bool(true),
+        Token::Array(vec![
+            Token::Uint(U256::from(1)),
+            Token::Uint(U256::from(2)),
+            Token::Uint(U256::from(3)),
+        ]),
+    ];
+    let calldata = encode_function("sam(bytes,bool,uint256[])", &tokens);
+    let expected = words(&[
+        "a5643bf2",
+        "0000000000000000000000000000000000000000000000000000000000000060",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "00000000000000000000000000000000000000000000000000000000000000a0",
+        "0000000000000000000000000000000000000000000000000000000000000004",
+        "6461766500000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000003",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+        "0000000000000000000000000000000000000000000000000000000000000003",
+    ]);
+    assert_eq!(calldata, expected);
+
+    let types = parse_params("sam(bytes,bool,uint256[])").unwrap();
+    assert_eq!(decode(&types, &calldata[4..]), Some(tokens));
+}
+
+#[test]
+fn strings_and_addresses_round_trip() {
+    let tokens = vec![
+        Token::Address(U256::from(0xa11ce)),
+        Token::String("hello, world".to_string()),
+        Token::Array(vec![
+            Token::String("a".to_string()),
+            Token::String(String::new()),
+        ]),
+    ];
+    let types = vec![
+        ParamType::Address,
+        ParamType::String,
+        ParamType::Array(Box::new(ParamType::String)),
+    ];
+    assert_eq!(decode(&types, &encode(&tokens)), Some(tokens));
+}
+
+#[test]
+fn malformed_data_does_not_decode() {
+    let bool_two = words(&["0000000000000000000000000000000000000000000000000000000000000002"]);
+    assert_eq!(decode(&[ParamType::Bool], &bool_two), None);
+
+    let wide_address = words(&["0000000000000000000000010000000000000000000000000000000000000000"]);
+    assert_eq!(decode(&[ParamType::Address], &wide_address), None);
+
+    // An array claiming more items than there is data for
+    let long_array = words(&[
+        "0000000000000000000000000000000000000000000000000000000000000020",
+        "00000000000000000000000000000000000000000000000000000000ffffffff",
+    ]);
+    let array = ParamType::Array(Box::new(ParamType::Uint));
+    assert_eq!(decode(&[array], &long_array), None);
+
+    assert_eq!(decode(&[ParamType::Uint], &[0; 31]), None);
+}
+
+#[test]
+fn signatures() {
+    assert_eq!(
+        parse_params("f(uint8,int256,address[],bytes)"),
+        Some(vec![
+            ParamType::Uint,
+            ParamType::Int,
+            ParamType::Array(Box::new(ParamType::Address)),
+            ParamType::Bytes,
+        ])
+    );
+    assert_eq!(parse_params("f()"), Some(vec![]));
+    assert_eq!(parse_params("f(uint7)"), None);
+    assert_eq!(parse_params("f(bytes32)"), None);
+}
@@ -0,0 +1,96 @@
+//! Access lists made the way `eth_createAccessList` makes them.
+#![cfg(feature = "std")]
+
+use evm::accesslist::AccessListTracer;
+use evm::call::{CallRequest, StateOverrides};
+use evm::service::EvmService;
+use evm::{evm_with_inspector, AccessListItem, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+// SLOAD(1), BALANCE(0xb0b), STATICCALL to the ecrecover precompile and
+// EXTCODESIZE of itself
+const TOUCHES: &str = "60015450 610b0b3150 600060006000600060015afa50 61c0de3b50 00";
+
+fn data() -> EvmData {
+    let mut data = EvmData::default();
+    data.codes.insert(
+        0xc0de.to_string(),
+        TOUCHES.replace(' ', "").parse().unwrap(),
+    );
+    data
+}
+
+fn item(address: u64, keys: &[u64]) -> AccessListItem {
+    AccessListItem {
+        address: U256::from(address),
+        storage_keys: keys.iter().map(|key| U256::from(*key)).collect(),
+    }
+}
+
+#[test]
+fn the_accounts_and_slots_touched_are_listed() {
+    let mut data = data();
+    data.tx_data = Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap());
+    let mut tracer = AccessListTracer::new([U256::from(0xc0de), U256::from(1)]);
+    let code = hex::decode(TOUCHES.replace(' ', "")).unwrap();
+    evm_with_inspector(code, &mut data, true, &mut tracer);
+    // The recipient is only left out while none of its slots are touched
+    assert_eq!(
+        tracer.access_list(),
+        vec![item(0xb0b, &[]), item(0xc0de, &[1])]
+    );
+}
+
+#[test]
+fn keys_are_listed_under_the_account_whose_storage_they_are() {
+    let mut data = EvmData::default();
+    // SLOAD(2)
+    data.codes
+        .insert(0xbeef.to_string(), "6002545000".parse().unwrap());
+    // SLOAD(3)
+    data.codes
+        .insert(0xd00d.to_string(), "6003545000".parse().unwrap());
+    data.tx_data = Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap());
+    let mut tracer = AccessListTracer::new([U256::from(0xc0de)]);
+    // DELEGATECALL(gas, 0xbeef, 0, 0, 0, 0), CALL(gas, 0xd00d, 0, 0, 0, 0, 0)
+    let code = "600060006000600061beef5af450 6000600060006000600061d00d5af150 00";
+    let code = hex::decode(code.replace(' ', "")).unwrap();
+    evm_with_inspector(code, &mut data, true, &mut tracer);
+    assert_eq!(
+        tracer.access_list(),
+        vec![item(0xbeef, &[]), item(0xc0de, &[2]), item(0xd00d, &[3])]
+    );
+}
+
+#[test]
+fn the_list_comes_with_the_gas_with_and_without_it() {
+    let service = EvmService::new(data());
+    let call: CallRequest =
+        serde_json::from_value(json!({ "from": "0xa11ce", "to": "0xc0de" })).unwrap();
+    let created = service
+        .create_access_list(call, &StateOverrides::new())
+        .unwrap();
+    assert!(created.result.success);
+    assert_eq!(
+        created.access_list,
+        vec![item(0xb0b, &[]), item(0xc0de, &[1])]
+    );
+    // Two accounts and a key cost 2 * 2400 + 1900 up front, the cold
+    // BALANCE and SLOAD 2500 and 2000 less
+    assert_eq!(created.gas_used, created.gas_used_without + 2200);
+    assert_eq!(service.metrics().requests, 1);
+}
+
+#[test]
+fn calls_that_touch_nothing_have_an_empty_list() {
+    let mut data = EvmData::default();
+    data.codes.insert(0xc0de.to_string(), "00".parse().unwrap());
+    let service = EvmService::new(data);
+    let call: CallRequest = serde_json::from_value(json!({ "to": "0xc0de" })).unwrap();
+    let created = service
+        .create_access_list(call, &StateOverrides::new())
+        .unwrap();
+    assert!(created.access_list.is_empty());
+    assert_eq!(created.gas_used, created.gas_used_without);
+}
@@ -0,0 +1,75 @@
+//! Jump destination analysis and its cache.
+
+use std::sync::Arc;
+
+use evm::analysis::{AnalysisCache, JumpDests};
+use evm::{evm, EvmData, HaltReason, TxData};
+use serde_json::json;
+
+#[test]
+fn push_data_is_not_a_jump_dest() {
+    // JUMPDEST, PUSH2 0x5b5b, JUMPDEST
+    let dests = JumpDests::analyze(&hex::decode("5b615b5b5b").unwrap());
+    assert!(dests.is_valid(0));
+    assert!(!dests.is_valid(2));
+    assert!(!dests.is_valid(3));
+    assert!(dests.is_valid(4));
+    assert!(!dests.is_valid(5));
+    assert!(!dests.is_valid(usize::MAX));
+}
+
+#[test]
+fn truncated_push_swallows_the_tail() {
+    // PUSH32 with only two bytes of immediate
+    let dests = JumpDests::analyze(&hex::decode("7f5b5b").unwrap());
+    assert!(!dests.is_valid(1));
+    assert!(!dests.is_valid(2));
+}
+
+#[test]
+fn dests_past_the_first_word() {
+    let mut code = vec![0x00; 130];
+    code[129] = 0x5b;
+    let dests = JumpDests::analyze(&code);
+    assert!(dests.is_valid(129));
+    assert!(!dests.is_valid(128));
+}
+
+#[test]
+fn cache_shares_analysis_by_code() {
+    let mut cache = AnalysisCache::default();
+    let first = cache.jump_dests(&[0x5b, 0x00]);
+    let again = cache.jump_dests(&[0x5b, 0x00]);
+    assert!(Arc::ptr_eq(&first, &again));
+    cache.jump_dests(&[0x00, 0x5b]);
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn evm_analyses_each_code_once() {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        ..Default::default()
+    };
+    // PUSH1 4, JUMP, INVALID, JUMPDEST, STOP
+    let code = hex::decode("600456fe5b00").unwrap();
+    assert!(evm(code.clone(), &mut data, true).success);
+    assert!(evm(code, &mut data, true).success);
+    assert_eq!(data.analysis.len(), 1);
+}
+
+#[test]
+fn huge_destination_is_an_invalid_jump() {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        ..Default::default()
+    };
+    // PUSH32 2^256-1, JUMP
+    let code = hex::decode(format!("7f{}56", "ff".repeat(32))).unwrap();
+    let result = evm(code, &mut data, true);
+    assert_eq!(result.halt_reason, Some(HaltReason::InvalidJump));
+}
@@ -0,0 +1,84 @@
+//! Assembling the fixture dialect, with labels.
+#![cfg(feature = "std")]
+
+use evm::asm::{assemble, AsmError};
+use evm::opcodes::disassemble;
+
+#[test]
+fn reverses_the_disassembler() {
+    // EIP-1167 minimal proxy
+    let code = hex::decode(
+        "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3",
+    )
+    .unwrap();
+    assert_eq!(assemble(&disassemble(&code)).unwrap(), code);
+}
+
+#[test]
+fn pads_push_data_and_reads_decimal() {
+    assert_eq!(
+        assemble("PUSH2 0x1\npush1 255\nPUSH0").unwrap(),
+        vec![0x61, 0x00, 0x01, 0x60, 0xff, 0x5f]
+    );
+    assert_eq!(assemble("KECCAK256\nSHA3").unwrap(), vec![0x20, 0x20]);
+}
+
+#[test]
+fn labels_resolve_forwards_and_backwards() {
+    let code = assemble(
+        "
+        PUSH1 end   // skip the loop
+        JUMP
+        loop:
+        JUMPDEST
+        PUSH1 loop
+        JUMP
+        end:
+        JUMPDEST
+        ",
+    )
+    .unwrap();
+    assert_eq!(code, vec![0x60, 0x07, 0x56, 0x5b, 0x60, 0x03, 0x56, 0x5b]);
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    assert_eq!(
+        assemble("STOP\nPUSH1 0x100"),
+        Err(AsmError::ArgumentTooLong { line: 2, width: 1 })
+    );
+    assert_eq!(
+        assemble("JUMP 0x1"),
+        Err(AsmError::UnexpectedArgument { line: 1 })
+    );
+    assert_eq!(
+        assemble("PUSH1 nowhere"),
+        Err(AsmError::UnknownLabel {
+            line: 1,
+            label: "nowhere".to_string()
+        })
+    );
+    assert_eq!(
+        assemble("PUSH1 0x1\nFOO").unwrap_err().to_string(),
+        "line 2: unknown instruction FOO"
+    );
+}
+
+#[test]
+fn eof_jumps_take_relative_offsets() {
+    let code = assemble(
+        "
+        loop:
+        PUSH0
+        RJUMPI loop
+        RJUMP end
+        RJUMP -3
+        end:
+        DUPN 2
+        RJUMPV 0x0100000002
+        ",
+    )
+    .unwrap();
+    assert_eq!(hex::encode(&code), "5fe1fffce00003e0fffde602e20100000002");
+    assert_eq!(assemble(&disassemble(&code)).unwrap(), code);
+}
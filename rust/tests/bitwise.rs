@@ -0,0 +1,172 @@
+//! BYTE, SHL, SHR and SAR at their edges, against the vectors of EIP-145
+//! and against the same operations done another way.
+
+use evm::{evm, EvmData};
+use primitive_types::U256;
+use proptest::prelude::*;
+
+const BYTE: u8 = 0x1a;
+const SHL: u8 = 0x1b;
+const SHR: u8 = 0x1c;
+const SAR: u8 = 0x1d;
+
+// Runs `opcode` on `a`, the top of the stack, and `b`
+fn run(opcode: u8, a: U256, b: U256) -> U256 {
+    let mut code = vec![0x7f];
+    code.extend(word(b));
+    code.push(0x7f);
+    code.extend(word(a));
+    code.extend([opcode, 0x00]);
+    let result = evm(code, &mut EvmData::default(), true);
+    assert!(result.success);
+    result.stack[0]
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+fn hex(value: &str) -> U256 {
+    U256::from_str_radix(value, 16).unwrap()
+}
+
+// The top bit alone, the bits below it and every bit
+fn min() -> U256 {
+    U256::one() << 255
+}
+
+fn max() -> U256 {
+    U256::MAX >> 1
+}
+
+#[test]
+fn shl_vectors() {
+    let cases = [
+        (U256::one(), 0x00, U256::one()),
+        (U256::one(), 0x01, U256::from(2)),
+        (U256::one(), 0xff, min()),
+        (U256::one(), 0x100, U256::zero()),
+        (U256::one(), 0x101, U256::zero()),
+        (U256::MAX, 0x00, U256::MAX),
+        (U256::MAX, 0x01, U256::MAX - 1),
+        (U256::MAX, 0xff, min()),
+        (U256::MAX, 0x100, U256::zero()),
+        (U256::zero(), 0x01, U256::zero()),
+        (max(), 0x01, U256::MAX - 1),
+    ];
+    for (value, shift, expected) in cases {
+        assert_eq!(
+            run(SHL, U256::from(shift), value),
+            expected,
+            "{value:x} << {shift}"
+        );
+    }
+    assert_eq!(run(SHL, U256::MAX, U256::MAX), U256::zero());
+}
+
+#[test]
+fn shr_vectors() {
+    let cases = [
+        (U256::one(), 0x00, U256::one()),
+        (U256::one(), 0x01, U256::zero()),
+        (min(), 0x01, U256::one() << 254),
+        (min(), 0xff, U256::one()),
+        (min(), 0x100, U256::zero()),
+        (min(), 0x101, U256::zero()),
+        (U256::MAX, 0x00, U256::MAX),
+        (U256::MAX, 0x01, max()),
+        (U256::MAX, 0xff, U256::one()),
+        (U256::MAX, 0x100, U256::zero()),
+        (U256::zero(), 0x01, U256::zero()),
+    ];
+    for (value, shift, expected) in cases {
+        assert_eq!(
+            run(SHR, U256::from(shift), value),
+            expected,
+            "{value:x} >> {shift}"
+        );
+    }
+    assert_eq!(run(SHR, U256::MAX, U256::MAX), U256::zero());
+}
+
+#[test]
+fn sar_vectors() {
+    let cases = [
+        (U256::one(), 0x00, U256::one()),
+        (U256::one(), 0x01, U256::zero()),
+        (min(), 0x01, hex("c0") << 248),
+        (min(), 0xff, U256::MAX),
+        (min(), 0x100, U256::MAX),
+        (min(), 0x101, U256::MAX),
+        (U256::MAX, 0x00, U256::MAX),
+        (U256::MAX, 0x01, U256::MAX),
+        (U256::MAX, 0xff, U256::MAX),
+        (U256::MAX, 0x100, U256::MAX),
+        (U256::zero(), 0x01, U256::zero()),
+        (U256::one() << 254, 0xfe, U256::one()),
+        (max(), 0xf8, hex("7f")),
+        (max(), 0xfe, U256::one()),
+        (max(), 0xff, U256::zero()),
+        (max(), 0x100, U256::zero()),
+    ];
+    for (value, shift, expected) in cases {
+        assert_eq!(
+            run(SAR, U256::from(shift), value),
+            expected,
+            "{value:x} >> {shift}"
+        );
+    }
+    // However far, negative numbers end up as -1 and the others as 0
+    assert_eq!(run(SAR, U256::MAX, min()), U256::MAX);
+    assert_eq!(run(SAR, U256::MAX, max()), U256::zero());
+}
+
+#[test]
+fn byte_vectors() {
+    let x = hex("0102030405060708091011121314151617181920212223242526272829303132");
+    assert_eq!(run(BYTE, U256::zero(), x), U256::from(0x01));
+    assert_eq!(run(BYTE, U256::from(1), x), U256::from(0x02));
+    assert_eq!(run(BYTE, U256::from(31), x), U256::from(0x32));
+    assert_eq!(run(BYTE, U256::from(32), x), U256::zero());
+    assert_eq!(run(BYTE, U256::from(256), x), U256::zero());
+    // Indexes whose bit offset does not fit in 256 bits
+    assert_eq!(run(BYTE, U256::one() << 253, x), U256::zero());
+    assert_eq!(run(BYTE, U256::MAX, x), U256::zero());
+}
+
+fn u256() -> impl Strategy<Value = U256> {
+    any::<[u8; 32]>().prop_map(|bytes| U256::from_big_endian(&bytes))
+}
+
+proptest! {
+    #[test]
+    fn byte_matches_big_endian(x in u256(), i in 0usize..40) {
+        let expected = word(x).get(i).copied().unwrap_or(0);
+        prop_assert_eq!(run(BYTE, U256::from(i), x), U256::from(expected));
+    }
+
+    #[test]
+    fn shifts_match_multiplying_and_dividing(value in u256(), shift in 0u32..300) {
+        let (shl, shr) = if shift < 256 {
+            let factor = U256::one() << shift as usize;
+            (value.overflowing_mul(factor).0, value / factor)
+        } else {
+            (U256::zero(), U256::zero())
+        };
+        prop_assert_eq!(run(SHL, U256::from(shift), value), shl);
+        prop_assert_eq!(run(SHR, U256::from(shift), value), shr);
+    }
+
+    #[test]
+    fn sar_matches_shr_of_the_complement(value in u256(), shift in 0u32..300) {
+        // A negative number shifts like its complement, complemented back
+        let expected = if value.bit(255) {
+            !run(SHR, U256::from(shift), !value)
+        } else {
+            run(SHR, U256::from(shift), value)
+        };
+        prop_assert_eq!(run(SAR, U256::from(shift), value), expected);
+    }
+}
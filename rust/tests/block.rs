@@ -0,0 +1,344 @@
+//! Executing transactions as a block and the receipts it produces.
+#![cfg(feature = "std")]
+
+use evm::block::{Block, BlockError};
+use evm::host::Database;
+use evm::{EvmContext, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const SENDER: u64 = 0xa11ce;
+const LOGGER: u64 = 0xc0de;
+const REVERTER: u64 = 0xdead;
+
+// Stores 0xbb in memory and logs its last byte with topic 0x42
+const LOGGER_CODE: &str = "60bb60005260426001601fa1";
+const LOG_TOPIC: u64 = 0x42;
+
+fn context(gaslimit: u64) -> EvmContext {
+    serde_json::from_value(json!({ "gaslimit": format!("{:x}", gaslimit) })).unwrap()
+}
+
+fn tx(to: u64, gas: Option<u64>) -> TxData {
+    tx_from(SENDER, to, gas)
+}
+
+fn tx_from(sender: u64, to: u64, gas: Option<u64>) -> TxData {
+    serde_json::from_value(json!({
+        "from": format!("{:#x}", sender),
+        "origin": format!("{:#x}", sender),
+        "to": format!("{:#x}", to),
+        "value": "0",
+        "gasprice": "1",
+        "gas": gas.map(|gas| format!("{:x}", gas)),
+    }))
+    .unwrap()
+}
+
+fn data() -> EvmData {
+    let mut data = EvmData::default();
+    for sender in SENDER..SENDER + 8 {
+        data.balances
+            .insert(sender.to_string(), U256::from(10u64.pow(18)));
+    }
+    data.codes
+        .insert(LOGGER.to_string(), LOGGER_CODE.parse().unwrap());
+    data.codes.insert(
+        REVERTER.to_string(),
+        format!("{}600080fd", LOGGER_CODE).parse().unwrap(),
+    );
+    data
+}
+
+fn address_bytes(address: u64) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    U256::from(address).to_big_endian(&mut word);
+    word[12..].to_vec()
+}
+
+fn topic_bytes(topic: u64) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    U256::from(topic).to_big_endian(&mut word);
+    word.to_vec()
+}
+
+#[test]
+fn receipts_track_cumulative_gas_and_logs() {
+    let block = Block::new(
+        context(30_000_000),
+        vec![
+            tx(LOGGER, Some(100_000)),
+            tx(REVERTER, Some(100_000)),
+            tx(LOGGER, None),
+        ],
+    );
+    let (_, result) = block.execute(&data()).unwrap();
+
+    assert_eq!(result.receipts.len(), 3);
+    let [first, reverted, last] = &result.receipts[..] else {
+        unreachable!()
+    };
+
+    assert!(first.success);
+    assert_eq!(first.logs.len(), 1);
+    assert_eq!(first.logs[0].address, U256::from(LOGGER));
+    assert_eq!(first.logs[0].topics, vec![U256::from(LOG_TOPIC)]);
+    assert_eq!(first.logs[0].data, vec![0xbb]);
+    assert!(first.gas_used > 21000);
+    assert_eq!(first.cumulative_gas_used, first.gas_used);
+
+    // A reverted transaction pays for its gas but keeps no logs
+    assert!(!reverted.success);
+    assert!(reverted.logs.is_empty());
+    assert_eq!(reverted.bloom, Default::default());
+    assert_eq!(
+        reverted.cumulative_gas_used,
+        first.gas_used + reverted.gas_used
+    );
+
+    assert!(last.success);
+    assert_eq!(last.gas_used, first.gas_used);
+    assert_eq!(last.cumulative_gas_used, result.gas_used);
+}
+
+#[test]
+fn bloom_covers_log_address_and_topics() {
+    let block = Block::new(context(30_000_000), vec![tx(LOGGER, Some(100_000))]);
+    let (_, result) = block.execute(&data()).unwrap();
+
+    let bloom = &result.receipts[0].bloom;
+    assert!(bloom.contains_input(&address_bytes(LOGGER)));
+    assert!(bloom.contains_input(&topic_bytes(LOG_TOPIC)));
+    assert!(!bloom.contains_input(&address_bytes(REVERTER)));
+    assert_eq!(&result.bloom, bloom);
+}
+
+#[test]
+fn transaction_above_block_gas_limit_is_rejected() {
+    let data = data();
+    let block = Block::new(
+        context(120_000),
+        vec![tx(LOGGER, Some(100_000)), tx(LOGGER, Some(100_000))],
+    );
+
+    let error = block.execute(&data).unwrap_err();
+    let BlockError::GasLimitExceeded {
+        index,
+        gas,
+        available,
+    } = error
+    else {
+        panic!("{}", error);
+    };
+    assert_eq!(index, 1);
+    assert_eq!(gas, 100_000);
+    assert!(available < 120_000 - 21_000);
+}
+
+#[test]
+fn gas_limits_above_64_bits_leave_every_transaction_room() {
+    let data = data();
+    let context = serde_json::from_value(json!({ "gaslimit": "10000000000000000" })).unwrap();
+    let block = Block::new(context, vec![tx(LOGGER, Some(100_000))]);
+
+    let (_, result) = block.execute(&data).unwrap();
+    assert_eq!(result.receipts.len(), 1);
+    assert!(result.receipts[0].success);
+}
+
+#[test]
+fn dynamic_fees_must_cover_the_base_fee() {
+    let context: EvmContext =
+        serde_json::from_value(json!({ "gaslimit": "0x1000000", "basefee": "0xa" })).unwrap();
+    let dynamic_fee = |max_fee: &str| -> TxData {
+        serde_json::from_value(json!({
+            "from": "0xa11ce",
+            "to": format!("{:#x}", LOGGER),
+            "max_fee_per_gas": max_fee,
+            "max_priority_fee_per_gas": "0x1",
+            "gas": "0x10000",
+        }))
+        .unwrap()
+    };
+
+    let block = Block::new(context.clone(), vec![dynamic_fee("0xb")]);
+    let (_, result) = block.execute(&data()).unwrap();
+    // Typed receipts start with the type of their transaction
+    assert_eq!(result.receipts[0].tx_type, 2);
+    assert_eq!(result.receipts[0].rlp()[0], 0x02);
+
+    let block = Block::new(
+        context,
+        vec![dynamic_fee("0xb"), dynamic_fee("0x9"), dynamic_fee("0x0")],
+    );
+    assert_eq!(
+        block.execute(&data()).unwrap_err(),
+        BlockError::FeeCapTooLow {
+            index: 1,
+            max_fee_per_gas: U256::from(9),
+            base_fee: U256::from(10)
+        }
+    );
+}
+
+// Adds one to slot 0
+const COUNTER: u64 = 0xc001;
+// Stores 0x2a in slot 5
+const WRITER: u64 = 0x5107;
+// Creates an empty contract
+const CREATOR: u64 = 0xc4ea;
+
+fn parallel_data() -> EvmData {
+    let mut data = data();
+    data.codes
+        .insert(COUNTER.to_string(), "600054600101600055".parse().unwrap());
+    data.codes
+        .insert(WRITER.to_string(), "602a600555".parse().unwrap());
+    data.codes
+        .insert(CREATOR.to_string(), "600060006000f000".parse().unwrap());
+    data
+}
+
+#[test]
+fn parallel_execution_matches_execution_in_order() {
+    let data = parallel_data();
+    let block = Block::new(
+        context(30_000_000),
+        vec![
+            tx(COUNTER, Some(100_000)),
+            tx_from(SENDER + 1, LOGGER, Some(100_000)),
+            tx_from(SENDER + 2, COUNTER, Some(100_000)),
+            tx_from(SENDER + 3, WRITER, Some(100_000)),
+            tx(CREATOR, Some(100_000)),
+            tx_from(SENDER + 4, REVERTER, Some(100_000)),
+            tx_from(SENDER + 5, CREATOR, Some(100_000)),
+            tx(COUNTER, None),
+        ],
+    );
+    let (serial, expected) = block.execute(&data).unwrap();
+    for jobs in [1, 2, 8] {
+        let (post, result) = block.execute_parallel(&data, jobs).unwrap();
+        assert_eq!(result, expected);
+        assert_eq!(post.state, serial.state);
+        assert_eq!(post.codes, serial.codes);
+        assert_eq!(post.nonces, serial.nonces);
+        assert_eq!(post.balances, serial.balances);
+    }
+    // Each counter saw the one before it
+    assert_eq!(
+        serial.state[&COUNTER.to_string()][&U256::zero()],
+        U256::from(3)
+    );
+    assert_eq!(serial.nonces[&CREATOR.to_string()], 2);
+}
+
+#[test]
+fn parallel_execution_rejects_what_execution_in_order_does() {
+    let block = Block::new(
+        context(120_000),
+        vec![tx(COUNTER, Some(60_000)), tx(COUNTER, Some(100_000))],
+    );
+    assert_eq!(
+        block.execute_parallel(&parallel_data(), 4).unwrap_err(),
+        block.execute(&parallel_data()).unwrap_err()
+    );
+}
+
+#[test]
+fn parallel_execution_keeps_the_same_slot_of_two_accounts_apart() {
+    const OTHER_COUNTER: u64 = 0xc002;
+    let mut data = parallel_data();
+    data.codes.insert(
+        OTHER_COUNTER.to_string(),
+        "600054600101600055".parse().unwrap(),
+    );
+    let block = Block::new(
+        context(30_000_000),
+        vec![
+            tx_from(SENDER, COUNTER, Some(100_000)),
+            tx_from(SENDER + 1, OTHER_COUNTER, Some(100_000)),
+            tx_from(SENDER + 2, OTHER_COUNTER, Some(100_000)),
+            tx_from(SENDER + 3, COUNTER, Some(100_000)),
+            tx_from(SENDER + 4, OTHER_COUNTER, Some(100_000)),
+        ],
+    );
+    let (serial, expected) = block.execute(&data).unwrap();
+    let (post, result) = block.execute_parallel(&data, 4).unwrap();
+    assert_eq!(result, expected);
+    assert_eq!(post.state, serial.state);
+    assert_eq!(post.balances, serial.balances);
+    let count = |address: u64| post.state[&address.to_string()][&U256::zero()];
+    assert_eq!(count(COUNTER), U256::from(2));
+    assert_eq!(count(OTHER_COUNTER), U256::from(3));
+}
+
+#[test]
+fn transactions_pay_for_their_gas_and_move_their_value() {
+    let sent = |nonce: Option<u64>| -> TxData {
+        serde_json::from_value(json!({
+            "from": format!("{:#x}", SENDER),
+            "to": format!("{:#x}", LOGGER),
+            "value": "7",
+            "gasprice": "1",
+            "gas": "0x186a0",
+            "nonce": nonce.map(|nonce| format!("{:#x}", nonce)),
+        }))
+        .unwrap()
+    };
+    let block = Block::new(context(30_000_000), vec![sent(None), tx(REVERTER, None)]);
+    let (post, result) = block.execute(&data()).unwrap();
+
+    let sender = post.basic(U256::from(SENDER)).unwrap();
+    assert_eq!(sender.nonce, 2);
+    let spent = U256::from(result.gas_used) + 7;
+    assert_eq!(sender.balance, U256::from(10u64.pow(18)) - spent);
+    assert_eq!(
+        post.basic(U256::from(LOGGER)).unwrap().balance,
+        U256::from(7)
+    );
+
+    // A nonce of its own must be the next one of the sender
+    let block = Block::new(context(30_000_000), vec![sent(Some(0)), sent(Some(0))]);
+    assert!(matches!(
+        block.execute(&data()).unwrap_err(),
+        BlockError::Rejected { index: 1, .. }
+    ));
+
+    let broke = tx_from(0xb0b, LOGGER, Some(100_000));
+    let block = Block::new(context(30_000_000), vec![broke]);
+    assert!(matches!(
+        block.execute(&data()).unwrap_err(),
+        BlockError::Rejected { index: 0, .. }
+    ));
+}
+
+#[test]
+fn dynamic_fees_burn_the_base_fee_and_tip_the_coinbase() {
+    const COINBASE: u64 = 0xc014;
+    let context: EvmContext = serde_json::from_value(json!({
+        "gaslimit": "0x1000000",
+        "basefee": "0xa",
+        "coinbase": format!("{:#x}", COINBASE),
+    }))
+    .unwrap();
+    let tx: TxData = serde_json::from_value(json!({
+        "from": format!("{:#x}", SENDER),
+        "to": format!("{:#x}", LOGGER),
+        "max_fee_per_gas": "0x10",
+        "max_priority_fee_per_gas": "0x2",
+        "gas": "0x10000",
+    }))
+    .unwrap();
+    let block = Block::new(context, vec![tx]);
+    let (post, result) = block.execute(&data()).unwrap();
+
+    // The sender pays the base fee and the tip, not its max fee, and only
+    // the tip reaches the coinbase
+    let balance = |address: u64| post.basic(U256::from(address)).unwrap().balance;
+    let gas_used = U256::from(result.gas_used);
+    assert_eq!(
+        balance(SENDER),
+        U256::from(10u64.pow(18)) - gas_used * (10 + 2)
+    );
+    assert_eq!(balance(COINBASE), gas_used * 2);
+}
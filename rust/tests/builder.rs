@@ -0,0 +1,102 @@
+//! Runs set up with `Evm::builder` instead of a hand-filled `EvmData`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use evm::builder::Evm;
+use evm::config::{EvmConfig, Preset};
+use evm::env::{BlockEnv, TxEnv};
+use evm::host::Database;
+use evm::spec::SpecId;
+use primitive_types::U256;
+
+const CONTRACT: u64 = 0xc0de;
+const ALICE: u64 = 0xa11ce;
+
+#[derive(Debug, Default)]
+struct CodeDb {
+    code: HashMap<U256, Vec<u8>>,
+}
+
+impl Database for CodeDb {
+    fn code(&self, address: U256) -> Option<Vec<u8>> {
+        self.code.get(&address).cloned()
+    }
+}
+
+fn tx() -> TxEnv {
+    TxEnv {
+        caller: U256::from(ALICE),
+        address: U256::from(CONTRACT),
+        origin: U256::from(ALICE),
+        value: U256::from(7),
+        gasprice: U256::from(3),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn block_and_tx_are_what_the_code_sees() {
+    let mut evm = Evm::builder()
+        .with_spec(SpecId::Cancun)
+        .with_block(BlockEnv {
+            number: U256::from(100),
+            chainid: U256::one(),
+            ..Default::default()
+        })
+        .with_tx(tx())
+        // NUMBER, CHAINID, CALLER, CALLVALUE, GASPRICE
+        .with_code(hex::decode("434633343a").unwrap())
+        .build();
+    let result = evm.transact();
+    assert!(result.success);
+    // Top of the stack first
+    assert_eq!(result.stack, [3, 7, ALICE, 1, 100].map(U256::from).to_vec());
+    assert_eq!(evm.data().config.spec, SpecId::Cancun);
+}
+
+#[test]
+fn code_comes_from_the_database_by_default() {
+    let mut db = CodeDb::default();
+    // CALLDATASIZE, PUSH1 0 CALLDATALOAD
+    db.code
+        .insert(U256::from(CONTRACT), hex::decode("36600035").unwrap());
+    let mut evm = Evm::builder()
+        .with_tx(tx())
+        .with_input(vec![0xff; 32])
+        .with_db(Arc::new(db))
+        .build();
+    let result = evm.transact();
+    assert_eq!(result.stack, vec![U256::MAX, U256::from(32)]);
+}
+
+#[test]
+fn the_gas_limit_is_enforced() {
+    let build = |gas_limit| {
+        Evm::builder()
+            .with_config(EvmConfig::preset(Preset::SpecExact))
+            .with_tx(tx())
+            .with_gas_limit(gas_limit)
+            // PUSH1 1 PUSH1 0 SSTORE
+            .with_code(hex::decode("6001600055").unwrap())
+            .build()
+    };
+    assert!(build(100_000).transact().success);
+    assert!(!build(21_010).transact().success);
+}
+
+#[test]
+fn state_carries_over_between_transactions() {
+    // PUSH1 0 SLOAD PUSH1 1 ADD PUSH1 0 SSTORE
+    let mut evm = Evm::builder()
+        .with_tx(tx())
+        .with_code(hex::decode("60005460010160005500").unwrap())
+        .build();
+    evm.transact();
+    evm.transact();
+    let data = evm.into_data();
+    assert_eq!(
+        evm::host::Database::storage(&data, U256::from(CONTRACT), U256::zero()),
+        U256::from(2)
+    );
+}
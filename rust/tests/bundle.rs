@@ -0,0 +1,241 @@
+//! Simulating bundles of signed transactions on a copy of the state.
+#![cfg(all(feature = "std", feature = "k256"))]
+
+use evm::abi::encode_call;
+use evm::bundle::{profit_and_loss, simulate_bundle, BundleOptions};
+use evm::spec::{ChainConfig, SpecId};
+use evm::statediff::Diff;
+use evm::tx::{DynamicFeeTx, Transaction};
+use evm::{EvmContext, EvmData};
+use primitive_types::U256;
+use serde_json::json;
+
+const KEY: &str = "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+const SIGNER: &str = "a94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+const COINBASE: u64 = 0xc0b1;
+const BOB: u64 = 0xb0b;
+const REVERTER: u64 = 0xdead;
+
+fn signer() -> U256 {
+    U256::from_str_radix(SIGNER, 16).unwrap()
+}
+
+fn data() -> EvmData {
+    let mut data = EvmData {
+        config: ChainConfig::new(SpecId::Cancun),
+        ..Default::default()
+    };
+    data.balances
+        .insert(signer().to_string(), U256::from(10u64.pow(18)));
+    data.codes
+        .insert(REVERTER.to_string(), "60006000fd".parse().unwrap());
+    data
+}
+
+fn block() -> EvmContext {
+    serde_json::from_value(json!({
+        "coinbase": format!("{:#x}", COINBASE),
+        "basefee": "0x7",
+        "gaslimit": "0x1c9c380",
+        "number": "0x10",
+    }))
+    .unwrap()
+}
+
+fn tx(nonce: u64, to: u64, value: u64) -> Transaction {
+    call(nonce, to, value, vec![])
+}
+
+fn call(nonce: u64, to: u64, value: u64, input: Vec<u8>) -> Transaction {
+    let key: [u8; 32] = hex::decode(KEY).unwrap().try_into().unwrap();
+    Transaction::DynamicFee(DynamicFeeTx {
+        chain_id: 1,
+        nonce,
+        max_priority_fee_per_gas: U256::from(2),
+        max_fee_per_gas: U256::from(20),
+        gas_limit: 100_000,
+        to: Some(U256::from(to)),
+        value: U256::from(value),
+        input,
+        ..Default::default()
+    })
+    .sign(&key)
+    .unwrap()
+}
+
+#[test]
+fn a_bundle_runs_in_order_on_a_copy_of_the_state() {
+    let data = data();
+    let txs = vec![tx(0, COINBASE, 5), tx(1, BOB, 3)];
+    let bundle = simulate_bundle(&data, txs.clone(), &block(), &BundleOptions::default());
+
+    assert!(!bundle.reverted);
+    assert_eq!(bundle.results.len(), 2);
+    for (result, tx) in bundle.results.iter().zip(&txs) {
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.hash, tx.hash());
+        assert_eq!(result.from, Some(signer()));
+        // The base fee and the tip
+        assert_eq!(result.gas_price, U256::from(9));
+    }
+    assert_eq!(bundle.results[1].gas_used, 21_000);
+    assert_eq!(bundle.gas_used, 42_000);
+
+    // Tips, and the value the first transaction sent it
+    let tips = 2 * bundle.gas_used;
+    assert_eq!(bundle.coinbase.amount(), U256::from(tips + 5));
+    assert_eq!(
+        bundle.results[0].coinbase.amount(),
+        U256::from(2 * 21_000 + 5)
+    );
+    assert_eq!(bundle.results[1].coinbase.amount(), U256::from(2 * 21_000));
+
+    let diff = &bundle.state_diff.accounts;
+    assert_eq!(diff[&U256::from(BOB)].balance, Diff::Born(U256::from(3)));
+    assert_eq!(diff[&signer()].nonce, Diff::Changed { from: 0, to: 2 });
+    assert_eq!(
+        diff[&U256::from(COINBASE)].balance,
+        Diff::Born(U256::from(tips + 5))
+    );
+
+    // The state given is left as it was
+    assert_eq!(data.balances.get(&BOB.to_string()), None);
+    assert_eq!(bundle.state.balances[&BOB.to_string()], U256::from(3));
+    assert!(bundle.state.snapshots.open.is_empty());
+}
+
+#[test]
+fn slots_show_under_the_account_that_stored_them() {
+    let mut data = data();
+    // SSTORE(1, 1) and SSTORE(1, 2)
+    data.codes
+        .insert(0xc001.to_string(), "6001600155".parse().unwrap());
+    data.codes
+        .insert(0xc002.to_string(), "6002600155".parse().unwrap());
+    let txs = vec![tx(0, 0xc001, 0), tx(1, 0xc002, 0)];
+    let bundle = simulate_bundle(&data, txs, &block(), &BundleOptions::default());
+
+    let slot = |address: u64| {
+        bundle.state_diff.accounts[&U256::from(address)].storage[&U256::one()].clone()
+    };
+    let stored = |value: u64| Diff::Changed {
+        from: U256::zero(),
+        to: U256::from(value),
+    };
+    assert_eq!(slot(0xc001), stored(1));
+    assert_eq!(slot(0xc002), stored(2));
+}
+
+#[test]
+fn transactions_that_cannot_be_included_are_skipped() {
+    let bundle = simulate_bundle(
+        &data(),
+        vec![tx(1, BOB, 3), tx(0, BOB, 4)],
+        &block(),
+        &BundleOptions::default(),
+    );
+    assert!(!bundle.reverted);
+    assert_eq!(
+        bundle.results[0].error.as_deref(),
+        Some("nonce too high: address 0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b, tx: 1 state: 0")
+    );
+    assert_eq!(bundle.results[0].gas_used, 0);
+    assert!(bundle.results[1].success);
+    assert_eq!(bundle.gas_used, 21_000);
+    assert_eq!(bundle.state.balances[&BOB.to_string()], U256::from(4));
+}
+
+#[test]
+fn gas_limits_above_64_bits_are_no_limit() {
+    let block = serde_json::from_value(json!({
+        "coinbase": format!("{:#x}", COINBASE),
+        "basefee": "0x7",
+        "gaslimit": "0x10000000000000000",
+    }))
+    .unwrap();
+    let bundle = simulate_bundle(
+        &data(),
+        vec![tx(0, BOB, 3)],
+        &block,
+        &BundleOptions::default(),
+    );
+    assert!(bundle.results[0].success, "{:?}", bundle.results[0].error);
+}
+
+#[test]
+fn a_failure_reverts_the_bundle_when_asked() {
+    let txs = vec![tx(0, BOB, 3), tx(1, REVERTER, 0), tx(2, BOB, 3)];
+
+    let bundle = simulate_bundle(&data(), txs.clone(), &block(), &BundleOptions::default());
+    assert!(!bundle.reverted);
+    assert_eq!(bundle.results.len(), 3);
+    assert!(!bundle.results[1].success);
+    assert_eq!(bundle.state.balances[&BOB.to_string()], U256::from(6));
+
+    let options = BundleOptions {
+        revert_on_failure: true,
+    };
+    let bundle = simulate_bundle(&data(), txs, &block(), &options);
+    assert!(bundle.reverted);
+    // The transactions after the failure do not run
+    assert_eq!(bundle.results.len(), 2);
+    assert!(bundle.results[0].success);
+    assert!(bundle.state_diff.is_empty());
+    assert_eq!(bundle.coinbase.amount(), U256::zero());
+    assert_eq!(bundle.state.balances.get(&BOB.to_string()), None);
+}
+
+#[test]
+fn profit_and_loss_reports_eth_moved() {
+    let data = data();
+    let bundle = simulate_bundle(
+        &data,
+        vec![tx(0, BOB, 3)],
+        &block(),
+        &BundleOptions::default(),
+    );
+    let report = profit_and_loss(&data, &bundle, signer(), &[]);
+
+    let paid = report.searcher_eth();
+    assert!(!paid.is_profit());
+    // The value, and the base fee and tip of its gas
+    assert_eq!(paid.amount(), U256::from(3 + 9 * 21_000));
+    let bob = report.eth[&U256::from(BOB)];
+    assert_eq!((bob.before, bob.after), (U256::zero(), U256::from(3)));
+    assert_eq!(
+        report.eth[&U256::from(COINBASE)].amount(),
+        U256::from(2 * 21_000)
+    );
+}
+
+#[test]
+fn profit_and_loss_reports_tokens_of_holders_without_a_balance() {
+    const WETH: u64 = 0xe7e7;
+    const POOL: u64 = 0x9001;
+    let mut data = data();
+    let weth9 = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/contracts/weth9.hex");
+    let weth9 = std::fs::read_to_string(weth9).unwrap();
+    data.codes
+        .insert(WETH.to_string(), weth9.trim().parse().unwrap());
+    // A contract the state has no balance for
+    data.codes.insert(POOL.to_string(), "00".parse().unwrap());
+
+    let transfer = encode_call(
+        "transfer(address,uint256)",
+        &[U256::from(POOL), U256::from(400)],
+    );
+    let txs = vec![
+        call(0, WETH, 1000, encode_call("deposit()", &[])),
+        call(1, WETH, 0, transfer),
+    ];
+    let bundle = simulate_bundle(&data, txs, &block(), &BundleOptions::default());
+    assert!(bundle.results.iter().all(|result| result.success));
+    let report = profit_and_loss(&data, &bundle, signer(), &[U256::from(WETH)]);
+
+    let weth = U256::from(WETH);
+    assert_eq!(report.searcher_token(weth).after, U256::from(600));
+    let pool = report.tokens[&weth][&U256::from(POOL)];
+    assert_eq!((pool.before, pool.after), (U256::zero(), U256::from(400)));
+    assert_eq!(report.tokens[&weth].len(), 2);
+    assert_eq!(report.eth[&weth].after, U256::from(1000));
+}
@@ -0,0 +1,109 @@
+//! The state cache: eviction order and the file format.
+#![cfg(feature = "std")]
+
+use evm::cache::{CacheError, CacheKey, CacheValue, StateCache};
+use evm::host::AccountInfo;
+use primitive_types::U256;
+
+fn slot(key: u64) -> CacheKey {
+    CacheKey::Storage {
+        block: 1,
+        address: U256::from(0xc0de),
+        key: U256::from(key),
+    }
+}
+
+#[test]
+fn evicts_the_least_recently_used() {
+    let mut cache = StateCache::new(2);
+    cache.insert(slot(1), CacheValue::Storage(U256::from(1)));
+    cache.insert(slot(2), CacheValue::Storage(U256::from(2)));
+    // Using slot 1 makes slot 2 the oldest
+    assert!(cache.get(&slot(1)).is_some());
+    cache.insert(slot(3), CacheValue::Storage(U256::from(3)));
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&slot(2)), None);
+    assert_eq!(
+        cache.get(&slot(1)),
+        Some(CacheValue::Storage(U256::from(1)))
+    );
+    assert_eq!(
+        cache.get(&slot(3)),
+        Some(CacheValue::Storage(U256::from(3)))
+    );
+    assert_eq!((cache.hits(), cache.misses()), (3, 1));
+}
+
+#[test]
+fn blocks_are_part_of_the_key() {
+    let mut cache = StateCache::default();
+    let at = |block| CacheKey::Code {
+        block,
+        address: U256::from(0xc0de),
+    };
+    cache.insert(at(1), CacheValue::Code(vec![0x00]));
+    assert_eq!(cache.get(&at(2)), None);
+}
+
+#[test]
+fn survives_a_round_trip_through_a_file() {
+    let mut cache = StateCache::new(10);
+    let account = CacheKey::Account {
+        block: 7,
+        address: U256::from(0xa11ce),
+    };
+    let entries = [
+        (
+            account,
+            CacheValue::Account(AccountInfo {
+                balance: U256::exp10(18),
+                nonce: 4,
+            }),
+        ),
+        (
+            CacheKey::Code {
+                block: 7,
+                address: U256::from(0xc0de),
+            },
+            CacheValue::Code(vec![0x60, 0x01]),
+        ),
+        (slot(1), CacheValue::Storage(U256::MAX)),
+        (
+            CacheKey::BlockHash {
+                number: U256::from(6),
+            },
+            CacheValue::BlockHash(Some(U256::from(0xabcd))),
+        ),
+        (
+            CacheKey::BlockHash {
+                number: U256::from(8),
+            },
+            CacheValue::BlockHash(None),
+        ),
+    ];
+    for (key, value) in entries.clone() {
+        cache.insert(key, value);
+    }
+
+    let path = std::env::temp_dir().join(format!("evm-cache-{}.json", std::process::id()));
+    cache.save(&path).unwrap();
+    // A smaller capacity keeps the most recent entries
+    let mut loaded = StateCache::load(&path, 4).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.len(), 4);
+    assert_eq!(loaded.get(&account), None);
+    for (key, value) in entries.into_iter().skip(1) {
+        assert_eq!(loaded.get(&key), Some(value));
+    }
+}
+
+#[test]
+fn rejects_other_versions() {
+    let value = serde_json::json!({ "version": 2, "entries": [] });
+    assert!(matches!(
+        StateCache::from_json(&value, 10),
+        Err(CacheError::Invalid(_))
+    ));
+}
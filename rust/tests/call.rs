@@ -0,0 +1,202 @@
+//! `EvmService::call`, the `eth_call` shaped entry point, with state
+//! overrides.
+#![cfg(feature = "std")]
+
+use evm::call::{CallRequest, StateOverrides};
+use evm::host::Database;
+use evm::service::{EvmService, ServiceError};
+use evm::EvmData;
+use primitive_types::U256;
+use serde_json::json;
+
+// Returns SLOAD(1) + BALANCE(CALLER)
+const SUMS: &str = "600154333101 60005260206000f3";
+
+fn returned(value: Option<Vec<u8>>) -> U256 {
+    U256::from(&value.unwrap()[..])
+}
+
+fn request(value: serde_json::Value) -> CallRequest {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn overrides_last_for_the_call_only() {
+    let service = EvmService::new(EvmData::default());
+    let overrides: StateOverrides = serde_json::from_value(json!({
+        "0xc0de": {
+            "code": SUMS.replace(' ', ""),
+            "stateDiff": { "0x1": "0x2" },
+        },
+        "0xa11ce": { "balance": "0x5", "nonce": "0x1" },
+    }))
+    .unwrap();
+    let call = request(json!({ "from": "0xa11ce", "to": "0xc0de", "input": "0x" }));
+
+    let result = service.call(call.clone(), &overrides).unwrap();
+    assert!(result.success);
+    assert_eq!(returned(result.value), U256::from(7));
+
+    let state = service.state();
+    assert_eq!(state.code(U256::from(0xc0de)), None);
+    assert_eq!(state.basic(U256::from(0xa11ce)), None);
+    let result = service.call(call, &StateOverrides::new()).unwrap();
+    assert_eq!(result.value, None);
+}
+
+#[test]
+fn state_replaces_the_storage_of_its_account_and_state_diff_sets_slots() {
+    // Returns SLOAD(1) + SLOAD(2)
+    let code = "6002546001540160005260206000f3";
+    let mut data = EvmData::default();
+    for address in [0xc0de, 0xbeef] {
+        data.codes
+            .insert(address.to_string(), code.parse().unwrap());
+        data.state.insert(
+            address.to_string(),
+            [(U256::one(), U256::from(3)), (U256::from(2), U256::from(4))].into(),
+        );
+    }
+    let service = EvmService::new(data);
+    let sum = |to: &str, overrides: serde_json::Value| {
+        let overrides: StateOverrides = serde_json::from_value(overrides).unwrap();
+        let call = request(json!({ "to": to }));
+        returned(service.call(call, &overrides).unwrap().value)
+    };
+
+    let diff = json!({ "0xc0de": { "stateDiff": { "0x1": "0x5" } } });
+    assert_eq!(sum("0xc0de", diff), U256::from(9));
+    let state = json!({ "0xc0de": { "state": { "0x1": "0x5" } } });
+    assert_eq!(sum("0xc0de", state.clone()), U256::from(5));
+    // Other accounts keep theirs
+    assert_eq!(sum("0xbeef", state), U256::from(7));
+}
+
+#[test]
+fn calls_without_to_run_the_data_as_init_code() {
+    let service = EvmService::new(EvmData::default());
+    // Returns CALLVALUE
+    let call = request(json!({ "data": "0x3460005260206000f3", "value": "0x2a" }));
+    let result = service.call(call, &StateOverrides::new()).unwrap();
+    assert_eq!(returned(result.value), U256::from(42));
+}
+
+#[test]
+fn malformed_overrides_are_rejected() {
+    let service = EvmService::new(EvmData::default());
+    let call = request(json!({ "to": "0xc0de" }));
+
+    let both: StateOverrides = serde_json::from_value(json!({
+        "0xc0de": { "state": {}, "stateDiff": {} },
+    }))
+    .unwrap();
+    assert!(matches!(
+        service.call(call.clone(), &both),
+        Err(ServiceError::InvalidRequest(_))
+    ));
+
+    let bad_balance: StateOverrides = serde_json::from_value(json!({
+        "0xc0de": { "balance": "0xzz" },
+    }))
+    .unwrap();
+    let error = service.call(call, &bad_balance).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid request: balance: invalid hex digit 'z' at position 0"
+    );
+}
+
+fn estimate_overrides(code: &str) -> StateOverrides {
+    serde_json::from_value(json!({
+        "0xc0de": { "code": code },
+        // SSTORE(0, 1)
+        "0xc1d": { "code": "600160005500" },
+    }))
+    .unwrap()
+}
+
+#[test]
+fn estimates_are_the_smallest_limit_that_succeeds() {
+    let service = EvmService::new(EvmData::default());
+    // Calls 0xc1d with all the gas it has, reverts if that fails
+    let overrides = estimate_overrides("600080808080610c1d5af1601257600080fd5b00");
+    let call = request(json!({ "from": "0xa11ce", "to": "0xc0de" }));
+
+    let gas = service.estimate_gas(call.clone(), &overrides).unwrap();
+    let with = |gas: u64| {
+        let call = CallRequest {
+            gas: Some(format!("{:#x}", gas)),
+            ..call.clone()
+        };
+        service.call(call, &overrides).unwrap().success
+    };
+    assert!(with(gas));
+    assert!(!with(gas - 1));
+    // More than used, because of the 63/64 passed on to 0xc1d
+    let used = service.call(call.clone(), &overrides).unwrap().gas_used;
+    assert!(gas > used);
+}
+
+#[test]
+fn calls_that_always_fail_are_reported() {
+    let service = EvmService::new(EvmData::default());
+    let call = request(json!({ "to": "0xc0de" }));
+
+    // MSTORE8(0, 0x42), REVERT(0, 1)
+    let overrides = estimate_overrides("604260005360016000fd");
+    let error = service.estimate_gas(call.clone(), &overrides).unwrap_err();
+    assert!(matches!(
+        &error,
+        ServiceError::ExecutionFailed { return_data, .. } if return_data == &[0x42]
+    ));
+    assert_eq!(error.to_string(), "execution reverted");
+
+    // An infinite loop
+    let overrides = estimate_overrides("5b600056");
+    assert_eq!(
+        service.estimate_gas(call, &overrides),
+        Err(ServiceError::GasRequiredExceedsAllowance { cap: 30_000_000 })
+    );
+}
+
+#[test]
+fn fees_are_checked_against_the_base_fee() {
+    let service = EvmService::new(EvmData {
+        context: Some(serde_json::from_value(json!({ "basefee": "0xa" })).unwrap()),
+        ..Default::default()
+    });
+    // Returns GASPRICE
+    let overrides: StateOverrides =
+        serde_json::from_value(json!({ "0xc0de": { "code": "0x3a60005260206000f3" } })).unwrap();
+    let call = |fees: serde_json::Value| {
+        let mut call = json!({ "to": "0xc0de" });
+        call.as_object_mut()
+            .unwrap()
+            .extend(fees.as_object().unwrap().clone());
+        service.call(request(call), &overrides)
+    };
+
+    let result = call(json!({ "maxFeePerGas": "0x64", "maxPriorityFeePerGas": "0x2" }));
+    assert_eq!(returned(result.unwrap().value), U256::from(12));
+    assert_eq!(
+        call(json!({ "maxFeePerGas": "0x9" })).unwrap_err(),
+        ServiceError::FeeCapTooLow {
+            max_fee_per_gas: U256::from(9),
+            base_fee: U256::from(10)
+        }
+    );
+    assert_eq!(
+        call(json!({ "maxFeePerGas": "0x64", "maxPriorityFeePerGas": "0x65" })).unwrap_err(),
+        ServiceError::TipAboveFeeCap {
+            max_priority_fee_per_gas: U256::from(0x65),
+            max_fee_per_gas: U256::from(0x64)
+        }
+    );
+    assert!(matches!(
+        call(json!({ "gasPrice": "0x64", "maxFeePerGas": "0x64" })),
+        Err(ServiceError::InvalidRequest(_))
+    ));
+    // Without EIP-1559 fees nothing is checked
+    let result = call(json!({ "gasPrice": "0x1" }));
+    assert_eq!(returned(result.unwrap().value), U256::one());
+}
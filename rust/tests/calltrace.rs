@@ -0,0 +1,68 @@
+//! The callTracer view of an execution.
+#![cfg(feature = "std")]
+
+use evm::calltrace::CallTracer;
+use evm::{evm_with_inspector, EvmData, TxData};
+use serde_json::{json, Value};
+
+fn trace(code: &str, data: &mut EvmData) -> Value {
+    data.tx_data = Some(
+        serde_json::from_value::<TxData>(json!({
+            "from": "0xa11ce",
+            "to": "0xc0de",
+            "gas": "0xf4240",
+        }))
+        .unwrap(),
+    );
+    let mut tracer = CallTracer::new();
+    let code = hex::decode(code.replace(' ', "")).unwrap();
+    evm_with_inspector(code, data, true, &mut tracer);
+    tracer.finish().unwrap().to_json()
+}
+
+#[test]
+fn calls_nest_under_the_transaction() {
+    let mut data = EvmData::default();
+    // Callee: returns 0x42 as a single byte
+    data.codes
+        .insert("170".to_string(), "60426000526001601ff3".parse().unwrap());
+
+    // CALL(gas, 0xaa, 0, 0, 0, 0, 0)
+    let trace = trace("600080808080 60aa 5a f1", &mut data);
+    assert_eq!(trace["type"], "CALL");
+    assert_eq!(trace["from"], "0x00000000000000000000000000000000000a11ce");
+    assert_eq!(trace["gas"], "0xf4240");
+    assert!(trace.get("error").is_none());
+
+    let calls = trace["calls"].as_array().unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(
+        calls[0],
+        json!({
+            "type": "CALL",
+            "from": "0x000000000000000000000000000000000000c0de",
+            "to": "0x00000000000000000000000000000000000000aa",
+            "value": "0x0",
+            "gas": calls[0]["gas"],
+            "gasUsed": "0x12",
+            "input": "0x",
+            "output": "0x42",
+        })
+    );
+}
+
+#[test]
+fn reverts_are_reported_with_their_data() {
+    let mut data = EvmData::default();
+    // Callee: reverts with 0x42
+    data.codes
+        .insert("170".to_string(), "60426000526001601ffd".parse().unwrap());
+
+    // STATICCALL(gas, 0xaa, 0, 0, 0, 0)
+    let trace = trace("6000808080 60aa 5a fa", &mut data);
+    let call = &trace["calls"][0];
+    assert_eq!(call["type"], "STATICCALL");
+    assert!(call.get("value").is_none());
+    assert_eq!(call["output"], "0x42");
+    assert_eq!(call["error"], "execution reverted");
+}
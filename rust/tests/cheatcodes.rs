@@ -0,0 +1,190 @@
+//! Foundry's cheatcodes, called the way forge-std's `vm` calls them.
+
+use evm::abi::{encode_function, Token};
+use evm::builder::Evm;
+use evm::cheatcodes::CHEATCODE_ADDRESS;
+use evm::config::{EvmConfig, Preset};
+use evm::env::TxEnv;
+use evm::spec::SpecId;
+use evm::EvmResult;
+use primitive_types::U256;
+
+const TEST: u64 = 0x7e57;
+const ORIGIN: u64 = 0x0419;
+const ALICE: u64 = 0xa11ce;
+// Returns CALLER and ORIGIN
+const WHO: u64 = 0xc0de;
+const WHO_CODE: &str = "336000523260205260406000f3";
+
+// Writes `calldata` to memory and calls `to` with it, leaving the success
+// flag on the stack and the first word returned at 0
+fn call(to: U256, calldata: &[u8]) -> String {
+    let mut code = String::new();
+    for (index, chunk) in calldata.chunks(32).enumerate() {
+        let mut word = chunk.to_vec();
+        word.resize(32, 0);
+        code += &format!("7f{}61{:04x}52", hex::encode(word), index * 32);
+    }
+    let mut address = [0; 32];
+    to.to_big_endian(&mut address);
+    code + &format!(
+        "6040600061{:04x}600060007f{}5af1",
+        calldata.len(),
+        hex::encode(address)
+    )
+}
+
+fn vm(signature: &str, args: &[Token]) -> String {
+    call(CHEATCODE_ADDRESS, &encode_function(signature, args))
+}
+
+fn who() -> String {
+    call(U256::from(WHO), &[])
+}
+
+// PUSH1 0 MLOAD, the first word returned
+const RETURNED: &str = "600051";
+// The second word
+const RETURNED_SECOND: &str = "602051";
+
+fn run(code: &str, cheatcodes: bool) -> EvmResult {
+    let mut evm = Evm::builder()
+        .with_spec(SpecId::Cancun)
+        .with_config(EvmConfig {
+            cheatcodes,
+            ..EvmConfig::preset(Preset::SpecExact)
+        })
+        .with_tx(TxEnv {
+            address: U256::from(TEST),
+            origin: U256::from(ORIGIN),
+            ..Default::default()
+        })
+        .with_gas_limit(10_000_000)
+        .with_code(hex::decode(code).unwrap())
+        .build();
+    evm.data_mut()
+        .codes
+        .insert(WHO.to_string(), WHO_CODE.parse().unwrap());
+    let result = evm.transact();
+    assert!(result.success, "{:?}", result);
+    result
+}
+
+fn uint(value: u64) -> Token {
+    Token::Uint(U256::from(value))
+}
+
+fn address(value: u64) -> Token {
+    Token::Address(U256::from(value))
+}
+
+#[test]
+fn warp_and_roll_set_the_block() {
+    // TIMESTAMP, NUMBER
+    let code = vm("warp(uint256)", &[uint(1234)]) + &vm("roll(uint256)", &[uint(99)]) + "4243";
+    let result = run(&code, true);
+    assert_eq!(
+        result.stack,
+        vec![U256::from(99), U256::from(1234), U256::one(), U256::one()]
+    );
+
+    // Without cheatcodes the address is an account like any other
+    let result = run(&code, false);
+    assert_eq!(result.stack[..2], [U256::zero(), U256::zero()]);
+}
+
+#[test]
+fn deal_etch_store_and_load_change_accounts() {
+    let code = vm("deal(address,uint256)", &[address(ALICE), uint(500)])
+        // BALANCE(ALICE)
+        + &format!("62{:06x}31", ALICE)
+        + &vm(
+            "etch(address,bytes)",
+            &[
+                address(0xe7c4),
+                // RETURN(0, 32) of 42
+                Token::Bytes(hex::decode("602a60005260206000f3").unwrap()),
+            ],
+        )
+        + &call(U256::from(0xe7c4), &[])
+        + RETURNED
+        + &vm(
+            "store(address,bytes32,bytes32)",
+            &[address(ALICE), uint(7), uint(0x5703e)],
+        )
+        // SLOAD(7), of the running account rather than ALICE
+        + "600754"
+        + &vm("load(address,bytes32)", &[address(ALICE), uint(7)])
+        + RETURNED;
+    let result = run(&code, true);
+    assert_eq!(
+        result.stack,
+        vec![
+            U256::from(0x5703e),
+            U256::one(),
+            U256::zero(),
+            U256::one(),
+            U256::from(42),
+            U256::one(),
+            U256::one(),
+            U256::from(500),
+            U256::one(),
+        ]
+    );
+}
+
+#[test]
+fn prank_sets_the_caller_of_the_next_call_only() {
+    let code = vm("prank(address)", &[address(ALICE)])
+        + &who()
+        + RETURNED
+        + &who()
+        + RETURNED
+        + &vm("prank(address,address)", &[address(ALICE), address(0xb0b)])
+        + &who()
+        + RETURNED_SECOND;
+    let result = run(&code, true);
+    assert_eq!(
+        result.stack[..5],
+        [
+            U256::from(0xb0b),
+            U256::one(),
+            U256::one(),
+            U256::from(TEST),
+            U256::one(),
+        ]
+    );
+    assert_eq!(result.stack[5], U256::from(ALICE));
+}
+
+#[test]
+fn start_prank_lasts_until_stop_prank() {
+    let code = vm("startPrank(address)", &[address(ALICE)])
+        + &who()
+        + RETURNED
+        + &who()
+        + RETURNED
+        + &vm("stopPrank()", &[])
+        + &who()
+        + RETURNED;
+    let one = U256::one();
+    assert_eq!(
+        run(&code, true).stack,
+        vec![
+            U256::from(TEST),
+            one,
+            one,
+            U256::from(ALICE),
+            one,
+            U256::from(ALICE),
+            one,
+            one,
+        ]
+    );
+}
+
+#[test]
+fn unknown_cheatcodes_fail_the_call() {
+    let code = vm("thisIsNotACheatcode()", &[]);
+    assert_eq!(run(&code, true).stack, vec![U256::zero()]);
+}
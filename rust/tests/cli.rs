@@ -0,0 +1,240 @@
+//! The `evmrs` binary, run as a user would.
+
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+fn evmrs(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_evmrs"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn run_prints_the_result() {
+    let output = evmrs(&["run", "--code", "0x6001600101"]);
+    assert!(output.contains("Success: true\n"));
+    assert!(output.contains("Stack (1 items, top first):\n  0x2\n"));
+}
+
+#[test]
+fn run_zero_pads_a_truncated_push() {
+    let output = evmrs(&["run", "--code", "0x60"]);
+    assert!(output.contains("Success: true\n"));
+    assert!(output.contains("Stack (1 items, top first):\n  0x0\n"));
+
+    let output = evmrs(&["run", "--code", "0x6101"]);
+    assert!(output.contains("Stack (1 items, top first):\n  0x100\n"));
+}
+
+#[test]
+fn run_survives_a_contract_calling_itself() {
+    // CALL(gas, ADDRESS, 0, 0, 0, 0, 0) until the calls run out of depth
+    let output = evmrs(&["run", "--code", "600080808080305af1"]);
+    assert!(output.contains("Success: true\n"));
+    assert!(output.contains("Stack (1 items, top first):\n  0x1\n"));
+}
+
+#[test]
+fn run_takes_the_context_from_options() {
+    // CALLVALUE, NUMBER, CALLDATASIZE, then LOG1(0, 0, 7)
+    let output = evmrs(&[
+        "run",
+        "--code",
+        "344336600760006000a1",
+        "--value",
+        "1000",
+        "--number",
+        "0x10",
+        "--calldata",
+        "0xaabb",
+        "--to",
+        "0xc0de",
+        "--gas",
+        "100000",
+        "--json",
+    ]);
+    let result: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(result["success"], true);
+    assert_eq!(result["stack"], json!(["0x2", "0x10", "0x3e8"]));
+    assert_eq!(result["logs"][0]["topics"], json!(["0x7"]));
+    assert_eq!(
+        result["logs"][0]["address"],
+        "0x000000000000000000000000000000000000c0de"
+    );
+}
+
+#[test]
+fn run_explains_each_instruction() {
+    let output = evmrs(&["run", "--code", "6001600101", "--explain"]);
+    assert!(output.starts_with("0x0000  PUSH1 places 0x1 on the stack"));
+}
+
+#[test]
+fn run_prints_what_contracts_log_to_the_console() {
+    // STATICCALL(gas, console, 0, 0x24, 0, 0) of logUint(7)
+    let code = format!(
+        "63{}60e01b60005260076004526000600060246000\
+         73000000000000000000636f6e736f6c652e6c6f675afa",
+        hex::encode(evm::abi::selector("logUint(uint256)"))
+    );
+    let code = code.as_str();
+    let output = evmrs(&["run", "--code", code]);
+    assert!(output.ends_with("Console:\n  7\n"), "{}", output);
+
+    let output = evmrs(&["run", "--code", code, "--json"]);
+    let result: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(result["console"], json!(["7"]));
+
+    let output = evmrs(&["run", "--code", code, "--explain"]);
+    assert!(output.contains("\n  console.log  7\n"), "{}", output);
+}
+
+#[test]
+fn t8n_reads_stdin_and_prints_to_stdout() {
+    let input = json!({
+        "alloc": { "0xa11ce": { "balance": "0x100000" } },
+        "env": { "currentCoinbase": "0xc0ffee", "currentGasLimit": "0x1000000" },
+        "txs": [{ "sender": "0xa11ce", "gas": "0x5208", "to": "0xb0b", "value": "0x5" }],
+    });
+    let mut child = Command::new(env!("CARGO_BIN_EXE_evmrs"))
+        .args([
+            "t8n",
+            "--input.alloc=stdin",
+            "--input.env=stdin",
+            "--input.txs=stdin",
+            "--output.result=stdout",
+            "--output.alloc=stdout",
+            "--state.fork=Berlin",
+        ])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::io::Write::write_all(child.stdin.as_mut().unwrap(), input.to_string().as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let output: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(output["result"]["gasUsed"], json!("0x5208"));
+    assert_eq!(
+        output["alloc"]["0x0000000000000000000000000000000000000b0b"]["balance"],
+        json!("0x5")
+    );
+}
+
+#[test]
+fn run_profiles_by_opcode_on_stderr() {
+    let output = Command::new(env!("CARGO_BIN_EXE_evmrs"))
+        .args(["run", "--code", "6001600101", "--profile", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let table = String::from_utf8(output.stderr).unwrap();
+    assert!(table.starts_with("opcode"));
+    assert!(table
+        .lines()
+        .any(|line| line.starts_with("PUSH1") && line.contains(" 2 ")));
+    let result: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["stack"], json!(["0x2"]));
+}
+
+#[test]
+fn run_dumps_the_state_for_the_next_run() {
+    let path = std::env::temp_dir().join(format!("evmrs-dump-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    // SSTORE(1, SLOAD(1) + 1), twice over the dumped state
+    let increment = "600154600101600155";
+    evmrs(&["run", "--code", increment, "--dump", path]);
+    evmrs(&[
+        "run",
+        "--code",
+        increment,
+        "--prestate",
+        path,
+        "--dump",
+        path,
+    ]);
+    let dump: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    // The code runs as the zero address
+    let storage = &dump["accounts"]["0x0000000000000000000000000000000000000000"]["storage"];
+    assert_eq!(
+        storage["0x0000000000000000000000000000000000000000000000000000000000000001"],
+        json!("0x0000000000000000000000000000000000000000000000000000000000000002")
+    );
+}
+
+#[test]
+fn scenario_run_checks_each_step() {
+    let path = std::env::temp_dir().join(format!("evmrs-scenario-{}.toml", std::process::id()));
+    // Deploys code returning 42, then calls it
+    let scenario = r#"
+[accounts.alice]
+address = "0x00000000000000000000000000000000000a11ce"
+
+[[steps]]
+deploy = "0x600a600c600039600a6000f3602a60005260206000f3"
+from = "alice"
+as = "answer"
+
+[[steps]]
+name = "answer"
+call = "answer"
+from = "alice"
+expect = { returns = "0x000000000000000000000000000000000000000000000000000000000000002a" }
+"#;
+    std::fs::write(&path, scenario).unwrap();
+    let output = evmrs(&["scenario", "run", path.to_str().unwrap()]);
+    assert_eq!(output, "ok    step 1\nok    answer\n");
+
+    let failing = scenario.replace("002a\" }", "002b\" }");
+    std::fs::write(&path, failing).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_evmrs"))
+        .args(["scenario", "run", path.to_str().unwrap(), "--json"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["passed"], json!(false));
+    assert_eq!(report["steps"][1]["failures"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn trace_diff_points_at_the_first_difference() {
+    let dir = std::env::temp_dir();
+    let left = dir.join(format!("evmrs-left-{}.jsonl", std::process::id()));
+    let right = dir.join(format!("evmrs-right-{}.jsonl", std::process::id()));
+    std::fs::write(
+        &left,
+        "{\"pc\":0,\"op\":96,\"stack\":[]}\n{\"pc\":2,\"op\":0,\"stack\":[\"0x1\"]}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &right,
+        "{\"pc\":0,\"op\":96,\"stack\":[]}\n{\"pc\":2,\"op\":0,\"stack\":[\"0x2\"]}\n",
+    )
+    .unwrap();
+    let (left, right) = (left.to_str().unwrap(), right.to_str().unwrap());
+
+    assert_eq!(
+        evmrs(&["trace-diff", left, left]),
+        "Traces match over 2 steps\n"
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_evmrs"))
+        .args(["trace-diff", left, right])
+        .output()
+        .unwrap();
+    std::fs::remove_file(left).unwrap();
+    std::fs::remove_file(right).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("Traces diverge at step 1: stack\n"),
+        "{}",
+        stdout
+    );
+}
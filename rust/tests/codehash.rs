@@ -0,0 +1,39 @@
+//! Code hashes as EXTCODEHASH sees them.
+
+use evm::trie::{keccak, EMPTY_CODE_HASH};
+use evm::EvmData;
+use primitive_types::U256;
+
+fn word(hash: primitive_types::H256) -> U256 {
+    U256::from_big_endian(hash.as_bytes())
+}
+
+#[test]
+fn missing_empty_and_codeless_accounts() {
+    let mut data = EvmData::default();
+    let address = U256::from(0xaaa);
+    assert_eq!(data.code_hash(address), U256::zero());
+
+    // Empty under EIP-161
+    data.balances.insert(address.to_string(), U256::zero());
+    assert_eq!(data.code_hash(address), U256::zero());
+
+    data.nonces.insert(address.to_string(), 1);
+    assert_eq!(data.code_hash(address), word(EMPTY_CODE_HASH));
+}
+
+#[test]
+fn cached_hash_follows_code_changes() {
+    let mut data = EvmData::default();
+    let address = U256::from(0xaaa);
+    data.nonces.insert(address.to_string(), 1);
+    assert_eq!(data.code_hash(address), word(EMPTY_CODE_HASH));
+
+    let checkpoint = data.checkpoint();
+    data.set_code(address, &[0xff; 4]);
+    assert_eq!(data.code_hash(address), word(keccak(&[0xff; 4])));
+    assert_eq!(data.substate.code_hashes.len(), 1);
+
+    data.revert_to(checkpoint);
+    assert_eq!(data.code_hash(address), word(EMPTY_CODE_HASH));
+}
@@ -0,0 +1,118 @@
+//! Hardhat's console.log, decoded from the calls contracts make to the
+//! console address.
+
+use evm::abi::{encode_function, Token};
+use evm::console::{self, ConsoleLogger, CONSOLE_ADDRESS};
+use evm::{evm_with_inspector, hexutil, EvmData};
+use primitive_types::U256;
+
+// Writes `calldata` to memory and STATICCALLs the console with it, leaving
+// its flag on the stack
+fn log(calldata: &[u8]) -> String {
+    let mut code = String::new();
+    for (index, chunk) in calldata.chunks(32).enumerate() {
+        let mut word = chunk.to_vec();
+        word.resize(32, 0);
+        code += &format!("7f{}61{:04x}52", hex::encode(word), index * 32);
+    }
+    let mut address = [0; 32];
+    CONSOLE_ADDRESS.to_big_endian(&mut address);
+    code + &format!(
+        "6000600061{:04x}60007f{}5afa",
+        calldata.len(),
+        hex::encode(address)
+    )
+}
+
+fn string(value: &str) -> Token {
+    Token::String(value.to_string())
+}
+
+fn uint(value: u64) -> Token {
+    Token::Uint(U256::from(value))
+}
+
+#[test]
+fn the_console_is_at_console_log_in_ascii() {
+    assert_eq!(
+        hexutil::parse_address("0x000000000000000000636F6e736F6c652e6c6f67").unwrap(),
+        CONSOLE_ADDRESS
+    );
+}
+
+#[test]
+fn every_overload_is_formatted() {
+    let format = |signature: &str, args: &[Token]| {
+        console::format(&encode_function(signature, args)).unwrap()
+    };
+    assert_eq!(format("log()", &[]), "");
+    assert_eq!(format("log(uint256)", &[uint(42)]), "42");
+    // As older versions of console.sol hash it
+    assert_eq!(format("log(uint)", &[uint(42)]), "42");
+    assert_eq!(
+        format(
+            "log(int256)",
+            &[Token::Int(U256::from(7).overflowing_neg().0)]
+        ),
+        "-7"
+    );
+    assert_eq!(
+        format(
+            "log(string,address,bool,uint256)",
+            &[
+                string("to"),
+                Token::Address(U256::from(0xa11ce)),
+                Token::Bool(true),
+                uint(3)
+            ]
+        ),
+        "to 0x00000000000000000000000000000000000a11ce true 3"
+    );
+    assert_eq!(
+        format("logBytes(bytes)", &[Token::Bytes(vec![0xca, 0xfe])]),
+        "0xcafe"
+    );
+    assert_eq!(
+        format(
+            "logBytes2(bytes2)",
+            &[Token::Uint(U256::from(0xbeef) << 240)]
+        ),
+        "0xbeef"
+    );
+
+    assert_eq!(
+        console::format(&encode_function("notALog(uint256)", &[uint(1)])),
+        None
+    );
+    assert_eq!(console::format(&[0x01]), None);
+}
+
+#[test]
+fn a_leading_string_takes_the_arguments_after_it() {
+    let input = encode_function(
+        "log(string,uint256,bool)",
+        &[string("%s%% of %d"), uint(50), Token::Bool(false)],
+    );
+    assert_eq!(console::format(&input).unwrap(), "50% of false");
+
+    let input = encode_function("log(string,uint256)", &[string("%s and"), uint(1)]);
+    assert_eq!(console::format(&input).unwrap(), "1 and");
+    let input = encode_function("log(string,uint256)", &[string("left over"), uint(1)]);
+    assert_eq!(console::format(&input).unwrap(), "left over 1");
+}
+
+#[test]
+fn inspectors_hear_what_contracts_print() {
+    let code = log(&encode_function(
+        "log(string,uint256)",
+        &[string("balance is %d"), uint(1000)],
+    )) + &log(&encode_function("logString(string)", &[string("done")]));
+    let mut data = EvmData::default();
+    let mut inner = ();
+    let mut logger = ConsoleLogger::new(&mut inner);
+    let result = evm_with_inspector(hex::decode(code).unwrap(), &mut data, true, &mut logger);
+
+    assert!(result.success);
+    assert!(result.logs.is_empty());
+    assert_eq!(logger.messages, vec!["balance is 1000", "done"]);
+}
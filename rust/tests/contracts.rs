@@ -0,0 +1,189 @@
+//! Disassembly and execution of well-known mainnet contracts.
+//!
+//! The `.asm` files next to the bytecode are golden files: run with
+//! `UPDATE_GOLDEN=1` to rewrite them after an intended change to the
+//! disassembler, and review the diff.
+
+use std::path::PathBuf;
+
+use evm::abi::{decode_word, encode_call};
+use evm::opcodes::disassemble;
+use evm::{evm, EvmData, EvmResult, ReturnKind, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const WETH9: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+const PROXY: &str = "0x1000000000000000000000000000000000001167";
+const ALICE: u64 = 0xa11ce;
+const BOB: u64 = 0xb0b;
+
+fn contract_path(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/contracts")
+        .join(file)
+}
+
+fn bytecode(name: &str) -> Vec<u8> {
+    let code = std::fs::read_to_string(contract_path(&format!("{}.hex", name))).unwrap();
+    hex::decode(code.trim()).unwrap()
+}
+
+// EIP-1167 minimal proxy runtime code forwarding every call to `target`
+fn minimal_proxy(target: &str) -> Vec<u8> {
+    let code = format!(
+        "363d3d373d3d3d363d73{}5af43d82803e903d91602b57fd5bf3",
+        target.trim_start_matches("0x")
+    );
+    hex::decode(code).unwrap()
+}
+
+fn check_golden(name: &str, code: &[u8]) {
+    let path = contract_path(&format!("{}.asm", name));
+    let actual = disassemble(code) + "\n";
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        actual == expected,
+        "disassembly of {} differs from {}, rerun with UPDATE_GOLDEN=1 if intended",
+        name,
+        path.display()
+    );
+}
+
+fn call(
+    data: &mut EvmData,
+    code: &[u8],
+    to: &str,
+    from: u64,
+    calldata: Vec<u8>,
+    value: u64,
+) -> EvmResult {
+    let tx_data: TxData = serde_json::from_value(json!({
+        "from": format!("{:#x}", from),
+        "origin": format!("{:#x}", from),
+        "to": to,
+        "data": hex::encode(calldata),
+        "value": format!("{:x}", value),
+        "gasprice": "1",
+        "gas": "1000000",
+    }))
+    .unwrap();
+    data.tx_data = Some(tx_data);
+    evm(code, data, true)
+}
+
+fn balance_of(data: &mut EvmData, code: &[u8], to: &str, holder: u64) -> U256 {
+    let calldata = encode_call("balanceOf(address)", &[U256::from(holder)]);
+    let result = call(data, code, to, ALICE, calldata, 0);
+    assert!(result.success);
+    decode_word(&result.value.unwrap(), 0).unwrap()
+}
+
+#[test]
+fn weth9_disassembly() {
+    check_golden("weth9", &bytecode("weth9"));
+}
+
+#[test]
+fn usdc_proxy_disassembly() {
+    check_golden("usdc_proxy", &bytecode("usdc_proxy"));
+}
+
+#[test]
+fn minimal_proxy_disassembly() {
+    check_golden("minimal_proxy", &minimal_proxy(WETH9));
+}
+
+#[test]
+fn weth9_deposit_and_transfer() {
+    let code = bytecode("weth9");
+    let mut data = EvmData::default();
+
+    let result = call(
+        &mut data,
+        &code,
+        WETH9,
+        ALICE,
+        encode_call("deposit()", &[]),
+        1000,
+    );
+    assert!(result.success);
+    assert_eq!(balance_of(&mut data, &code, WETH9, ALICE), U256::from(1000));
+
+    let calldata = encode_call(
+        "transfer(address,uint256)",
+        &[U256::from(BOB), U256::from(300)],
+    );
+    let result = call(&mut data, &code, WETH9, ALICE, calldata, 0);
+    assert!(result.success);
+    assert_eq!(decode_word(&result.value.unwrap(), 0), Some(U256::one()));
+
+    assert_eq!(balance_of(&mut data, &code, WETH9, ALICE), U256::from(700));
+    assert_eq!(balance_of(&mut data, &code, WETH9, BOB), U256::from(300));
+}
+
+#[test]
+fn weth9_transfer_above_balance_reverts() {
+    let code = bytecode("weth9");
+    let mut data = EvmData::default();
+
+    let calldata = encode_call("transfer(address,uint256)", &[U256::from(BOB), U256::one()]);
+    let result = call(&mut data, &code, WETH9, ALICE, calldata, 0);
+    assert!(!result.success);
+    assert_eq!(balance_of(&mut data, &code, WETH9, BOB), U256::zero());
+}
+
+#[test]
+fn minimal_proxy_delegates_to_weth9() {
+    let proxy = minimal_proxy(WETH9);
+    let mut data = EvmData::default();
+    let weth9 = U256::from_str_radix(WETH9, 16).unwrap();
+    data.codes
+        .insert(weth9.to_string(), bytecode("weth9").into());
+
+    let result = call(
+        &mut data,
+        &proxy,
+        PROXY,
+        ALICE,
+        encode_call("deposit()", &[]),
+        1000,
+    );
+    assert!(result.success);
+    assert_eq!(
+        balance_of(&mut data, &proxy, PROXY, ALICE),
+        U256::from(1000)
+    );
+
+    // A revert in the implementation comes back out of the proxy
+    let calldata = encode_call("withdraw(uint256)", &[U256::from(1001)]);
+    let result = call(&mut data, &proxy, PROXY, ALICE, calldata, 0);
+    assert!(!result.success);
+    assert_eq!(
+        balance_of(&mut data, &proxy, PROXY, ALICE),
+        U256::from(1000)
+    );
+}
+
+#[test]
+fn minimal_proxy_return_data_comes_from_the_implementation() {
+    let proxy = minimal_proxy(WETH9);
+    let mut data = EvmData::default();
+    let weth9 = U256::from_str_radix(WETH9, 16).unwrap();
+    data.codes
+        .insert(weth9.to_string(), bytecode("weth9").into());
+
+    let calldata = encode_call("balanceOf(address)", &[U256::from(ALICE)]);
+    let result = call(&mut data, &proxy, PROXY, ALICE, calldata, 0);
+    let origin = result.return_origin.unwrap();
+    assert_eq!((origin.frame, origin.depth), (0, 1));
+    assert_eq!(origin.kind, ReturnKind::Return);
+
+    // The implementation runs as the proxy, one frame down
+    let source = origin.source();
+    assert_eq!((source.frame, source.depth), (1, 2));
+    assert_eq!(source.address, U256::from_str_radix(PROXY, 16).unwrap());
+}
@@ -0,0 +1,24 @@
+CALLDATASIZE
+RETURNDATASIZE
+RETURNDATASIZE
+CALLDATACOPY
+RETURNDATASIZE
+RETURNDATASIZE
+RETURNDATASIZE
+CALLDATASIZE
+RETURNDATASIZE
+PUSH20 0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2
+GAS
+DELEGATECALL
+RETURNDATASIZE
+DUP3
+DUP1
+RETURNDATACOPY
+SWAP1
+RETURNDATASIZE
+SWAP2
+PUSH1 0x2b
+JUMPI
+REVERT
+JUMPDEST
+RETURN
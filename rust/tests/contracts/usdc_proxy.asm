@@ -0,0 +1,843 @@
+PUSH1 0x80
+PUSH1 0x40
+MSTORE
+PUSH1 0x04
+CALLDATASIZE
+LT
+PUSH2 0x006d
+JUMPI
+PUSH1 0x00
+CALLDATALOAD
+PUSH29 0x0100000000000000000000000000000000000000000000000000000000
+SWAP1
+DIV
+PUSH4 0xffffffff
+AND
+DUP1
+PUSH4 0x3659cfe6
+EQ
+PUSH2 0x0077
+JUMPI
+DUP1
+PUSH4 0x4f1ef286
+EQ
+PUSH2 0x00ba
+JUMPI
+DUP1
+PUSH4 0x5c60da1b
+EQ
+PUSH2 0x0108
+JUMPI
+DUP1
+PUSH4 0x8f283970
+EQ
+PUSH2 0x015f
+JUMPI
+DUP1
+PUSH4 0xf851a440
+EQ
+PUSH2 0x01a2
+JUMPI
+JUMPDEST
+PUSH2 0x0075
+PUSH2 0x01f9
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+CALLVALUE
+DUP1
+ISZERO
+PUSH2 0x0083
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+POP
+PUSH2 0x00b8
+PUSH1 0x04
+DUP1
+CALLDATASIZE
+SUB
+DUP2
+ADD
+SWAP1
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP3
+SWAP2
+SWAP1
+POP
+POP
+POP
+PUSH2 0x0213
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+PUSH2 0x0106
+PUSH1 0x04
+DUP1
+CALLDATASIZE
+SUB
+DUP2
+ADD
+SWAP1
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP3
+SWAP2
+SWAP1
+DUP1
+CALLDATALOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+DUP3
+ADD
+DUP1
+CALLDATALOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP2
+SWAP1
+SWAP2
+SWAP3
+SWAP4
+SWAP2
+SWAP3
+SWAP4
+SWAP1
+POP
+POP
+POP
+PUSH2 0x0268
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+CALLVALUE
+DUP1
+ISZERO
+PUSH2 0x0114
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+POP
+PUSH2 0x011d
+PUSH2 0x0308
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+DUP1
+ISZERO
+PUSH2 0x016b
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+POP
+PUSH2 0x01a0
+PUSH1 0x04
+DUP1
+CALLDATASIZE
+SUB
+DUP2
+ADD
+SWAP1
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP3
+SWAP2
+SWAP1
+POP
+POP
+POP
+PUSH2 0x0360
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+CALLVALUE
+DUP1
+ISZERO
+PUSH2 0x01ae
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+POP
+PUSH2 0x01b7
+PUSH2 0x051e
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+PUSH2 0x0201
+PUSH2 0x0576
+JUMP
+JUMPDEST
+PUSH2 0x0211
+PUSH2 0x020c
+PUSH2 0x0651
+JUMP
+JUMPDEST
+PUSH2 0x0682
+JUMP
+JUMPDEST
+JUMP
+JUMPDEST
+PUSH2 0x021b
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+PUSH2 0x025c
+JUMPI
+PUSH2 0x0257
+DUP2
+PUSH2 0x06d9
+JUMP
+JUMPDEST
+PUSH2 0x0265
+JUMP
+JUMPDEST
+PUSH2 0x0264
+PUSH2 0x01f9
+JUMP
+JUMPDEST
+JUMPDEST
+POP
+JUMP
+JUMPDEST
+PUSH2 0x0270
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+PUSH2 0x02fa
+JUMPI
+PUSH2 0x02ac
+DUP4
+PUSH2 0x06d9
+JUMP
+JUMPDEST
+ADDRESS
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLVALUE
+DUP4
+DUP4
+PUSH1 0x40
+MLOAD
+DUP1
+DUP4
+DUP4
+DUP1
+DUP3
+DUP5
+CALLDATACOPY
+DUP3
+ADD
+SWAP2
+POP
+POP
+SWAP3
+POP
+POP
+POP
+PUSH1 0x00
+PUSH1 0x40
+MLOAD
+DUP1
+DUP4
+SUB
+DUP2
+DUP6
+DUP8
+GAS
+CALL
+SWAP3
+POP
+POP
+POP
+ISZERO
+ISZERO
+PUSH2 0x02f5
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x0303
+JUMP
+JUMPDEST
+PUSH2 0x0302
+PUSH2 0x01f9
+JUMP
+JUMPDEST
+JUMPDEST
+POP
+POP
+POP
+JUMP
+JUMPDEST
+PUSH1 0x00
+PUSH2 0x0312
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+PUSH2 0x0354
+JUMPI
+PUSH2 0x034d
+PUSH2 0x0651
+JUMP
+JUMPDEST
+SWAP1
+POP
+PUSH2 0x035d
+JUMP
+JUMPDEST
+PUSH2 0x035c
+PUSH2 0x01f9
+JUMP
+JUMPDEST
+JUMPDEST
+SWAP1
+JUMP
+JUMPDEST
+PUSH2 0x0368
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+PUSH2 0x0512
+JUMPI
+PUSH1 0x00
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+ISZERO
+ISZERO
+PUSH2 0x0466
+JUMPI
+PUSH1 0x40
+MLOAD
+PUSH32 0x08c379a000000000000000000000000000000000000000000000000000000000
+DUP2
+MSTORE
+PUSH1 0x04
+ADD
+DUP1
+DUP1
+PUSH1 0x20
+ADD
+DUP3
+DUP2
+SUB
+DUP3
+MSTORE
+PUSH1 0x36
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+DUP1
+PUSH32 0x43616e6e6f74206368616e6765207468652061646d696e206f6620612070726f
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH32 0x787920746f20746865207a65726f206164647265737300000000000000000000
+DUP2
+MSTORE
+POP
+PUSH1 0x40
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+REVERT
+JUMPDEST
+PUSH32 0x7e644d79422f17c01e4894b5f4f588d331ebfa28653d42ae832dc59e38c9798f
+PUSH2 0x048f
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+DUP3
+PUSH1 0x40
+MLOAD
+DUP1
+DUP4
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+DUP3
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP3
+POP
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+LOG1
+PUSH2 0x050d
+DUP2
+PUSH2 0x0748
+JUMP
+JUMPDEST
+PUSH2 0x051b
+JUMP
+JUMPDEST
+PUSH2 0x051a
+PUSH2 0x01f9
+JUMP
+JUMPDEST
+JUMPDEST
+POP
+JUMP
+JUMPDEST
+PUSH1 0x00
+PUSH2 0x0528
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+PUSH2 0x056a
+JUMPI
+PUSH2 0x0563
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+SWAP1
+POP
+PUSH2 0x0573
+JUMP
+JUMPDEST
+PUSH2 0x0572
+PUSH2 0x01f9
+JUMP
+JUMPDEST
+JUMPDEST
+SWAP1
+JUMP
+JUMPDEST
+PUSH2 0x057e
+PUSH2 0x06a8
+JUMP
+JUMPDEST
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+ISZERO
+ISZERO
+PUSH2 0x0647
+JUMPI
+PUSH1 0x40
+MLOAD
+PUSH32 0x08c379a000000000000000000000000000000000000000000000000000000000
+DUP2
+MSTORE
+PUSH1 0x04
+ADD
+DUP1
+DUP1
+PUSH1 0x20
+ADD
+DUP3
+DUP2
+SUB
+DUP3
+MSTORE
+PUSH1 0x32
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+DUP1
+PUSH32 0x43616e6e6f742063616c6c2066616c6c6261636b2066756e6374696f6e206672
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH32 0x6f6d207468652070726f78792061646d696e0000000000000000000000000000
+DUP2
+MSTORE
+POP
+PUSH1 0x40
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+REVERT
+JUMPDEST
+PUSH2 0x064f
+PUSH2 0x0777
+JUMP
+JUMPDEST
+JUMP
+JUMPDEST
+PUSH1 0x00
+DUP1
+PUSH32 0x7050c9e0f4ca769c69bd3a8ef740bc37934f8e2c036e5a723fd8ee048ed3f8c3
+PUSH1 0x01
+MUL
+SWAP1
+POP
+DUP1
+SLOAD
+SWAP2
+POP
+POP
+SWAP1
+JUMP
+JUMPDEST
+CALLDATASIZE
+PUSH1 0x00
+DUP1
+CALLDATACOPY
+PUSH1 0x00
+DUP1
+CALLDATASIZE
+PUSH1 0x00
+DUP5
+GAS
+DELEGATECALL
+RETURNDATASIZE
+PUSH1 0x00
+DUP1
+RETURNDATACOPY
+DUP1
+PUSH1 0x00
+DUP2
+EQ
+PUSH2 0x06a3
+JUMPI
+RETURNDATASIZE
+PUSH1 0x00
+RETURN
+JUMPDEST
+RETURNDATASIZE
+PUSH1 0x00
+REVERT
+JUMPDEST
+PUSH1 0x00
+DUP1
+PUSH32 0x10d6a54a4754c8869d6886b5f5d7fbfa5b4522237ea5c60d11bc4e7a1ff9390b
+PUSH1 0x01
+MUL
+SWAP1
+POP
+DUP1
+SLOAD
+SWAP2
+POP
+POP
+SWAP1
+JUMP
+JUMPDEST
+PUSH2 0x06e2
+DUP2
+PUSH2 0x0779
+JUMP
+JUMPDEST
+PUSH32 0xbc7cd75a20ee27fd9adebab32041f755214dbc6bffa90cc0225b39da2e5c2d3b
+DUP2
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+LOG1
+POP
+JUMP
+JUMPDEST
+PUSH1 0x00
+PUSH32 0x10d6a54a4754c8869d6886b5f5d7fbfa5b4522237ea5c60d11bc4e7a1ff9390b
+PUSH1 0x01
+MUL
+SWAP1
+POP
+DUP2
+DUP2
+SSTORE
+POP
+POP
+JUMP
+JUMPDEST
+JUMP
+JUMPDEST
+PUSH1 0x00
+PUSH2 0x0784
+DUP3
+PUSH2 0x084b
+JUMP
+JUMPDEST
+ISZERO
+ISZERO
+PUSH2 0x081e
+JUMPI
+PUSH1 0x40
+MLOAD
+PUSH32 0x08c379a000000000000000000000000000000000000000000000000000000000
+DUP2
+MSTORE
+PUSH1 0x04
+ADD
+DUP1
+DUP1
+PUSH1 0x20
+ADD
+DUP3
+DUP2
+SUB
+DUP3
+MSTORE
+PUSH1 0x3b
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+DUP1
+PUSH32 0x43616e6e6f742073657420612070726f787920696d706c656d656e746174696f
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH32 0x6e20746f2061206e6f6e2d636f6e747261637420616464726573730000000000
+DUP2
+MSTORE
+POP
+PUSH1 0x40
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+REVERT
+JUMPDEST
+PUSH32 0x7050c9e0f4ca769c69bd3a8ef740bc37934f8e2c036e5a723fd8ee048ed3f8c3
+PUSH1 0x01
+MUL
+SWAP1
+POP
+DUP2
+DUP2
+SSTORE
+POP
+POP
+JUMP
+JUMPDEST
+PUSH1 0x00
+DUP1
+DUP3
+EXTCODESIZE
+SWAP1
+POP
+PUSH1 0x00
+DUP2
+GT
+SWAP2
+POP
+POP
+SWAP2
+SWAP1
+POP
+JUMP
+STOP
+LOG1
+PUSH6 0x627a7a723058
+SHA3
+LOG4
+INVALID
+SELFBALANCE
+INVALID
+INVALID
+SHA3
+INVALID
+GAS
+INVALID
+INVALID
+SWAPN 0x4d
+TIMESTAMP
+DUP15
+SWAP9
+DUP12
+INVALID
+INVALID
+INVALID
+MUL
+INVALID
+INVALID
+AND
+SSTORE
+ORIGIN
+INVALID
+INVALID
+INVALID
+SAR
+INVALID
+INVALID
+INVALID
+STOP
+INVALID
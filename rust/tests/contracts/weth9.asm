@@ -0,0 +1,1577 @@
+PUSH1 0x60
+PUSH1 0x40
+MSTORE
+PUSH1 0x04
+CALLDATASIZE
+LT
+PUSH2 0x00af
+JUMPI
+PUSH1 0x00
+CALLDATALOAD
+PUSH29 0x0100000000000000000000000000000000000000000000000000000000
+SWAP1
+DIV
+PUSH4 0xffffffff
+AND
+DUP1
+PUSH4 0x06fdde03
+EQ
+PUSH2 0x00b9
+JUMPI
+DUP1
+PUSH4 0x095ea7b3
+EQ
+PUSH2 0x0147
+JUMPI
+DUP1
+PUSH4 0x18160ddd
+EQ
+PUSH2 0x01a1
+JUMPI
+DUP1
+PUSH4 0x23b872dd
+EQ
+PUSH2 0x01ca
+JUMPI
+DUP1
+PUSH4 0x2e1a7d4d
+EQ
+PUSH2 0x0243
+JUMPI
+DUP1
+PUSH4 0x313ce567
+EQ
+PUSH2 0x0266
+JUMPI
+DUP1
+PUSH4 0x70a08231
+EQ
+PUSH2 0x0295
+JUMPI
+DUP1
+PUSH4 0x95d89b41
+EQ
+PUSH2 0x02e2
+JUMPI
+DUP1
+PUSH4 0xa9059cbb
+EQ
+PUSH2 0x0370
+JUMPI
+DUP1
+PUSH4 0xd0e30db0
+EQ
+PUSH2 0x03ca
+JUMPI
+DUP1
+PUSH4 0xdd62ed3e
+EQ
+PUSH2 0x03d4
+JUMPI
+JUMPDEST
+PUSH2 0x00b7
+PUSH2 0x0440
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x00c4
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x00cc
+PUSH2 0x04dd
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP1
+PUSH1 0x20
+ADD
+DUP3
+DUP2
+SUB
+DUP3
+MSTORE
+DUP4
+DUP2
+DUP2
+MLOAD
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+DUP1
+MLOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+DUP1
+DUP4
+DUP4
+PUSH1 0x00
+JUMPDEST
+DUP4
+DUP2
+LT
+ISZERO
+PUSH2 0x010c
+JUMPI
+DUP1
+DUP3
+ADD
+MLOAD
+DUP2
+DUP5
+ADD
+MSTORE
+PUSH1 0x20
+DUP2
+ADD
+SWAP1
+POP
+PUSH2 0x00f1
+JUMP
+JUMPDEST
+POP
+POP
+POP
+POP
+SWAP1
+POP
+SWAP1
+DUP2
+ADD
+SWAP1
+PUSH1 0x1f
+AND
+DUP1
+ISZERO
+PUSH2 0x0139
+JUMPI
+DUP1
+DUP3
+SUB
+DUP1
+MLOAD
+PUSH1 0x01
+DUP4
+PUSH1 0x20
+SUB
+PUSH2 0x0100
+EXP
+SUB
+NOT
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+JUMPDEST
+POP
+SWAP3
+POP
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x0152
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x0187
+PUSH1 0x04
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+DUP1
+CALLDATALOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+POP
+POP
+PUSH2 0x057b
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+ISZERO
+ISZERO
+ISZERO
+ISZERO
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x01ac
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x01b4
+PUSH2 0x066d
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x01d5
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x0229
+PUSH1 0x04
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+DUP1
+CALLDATALOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+POP
+POP
+PUSH2 0x068c
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+ISZERO
+ISZERO
+ISZERO
+ISZERO
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x024e
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x0264
+PUSH1 0x04
+DUP1
+DUP1
+CALLDATALOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+POP
+POP
+PUSH2 0x09d9
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x0271
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x0279
+PUSH2 0x0b05
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+PUSH1 0xff
+AND
+PUSH1 0xff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x02a0
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x02cc
+PUSH1 0x04
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+POP
+POP
+PUSH2 0x0b18
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x02ed
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x02f5
+PUSH2 0x0b30
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP1
+PUSH1 0x20
+ADD
+DUP3
+DUP2
+SUB
+DUP3
+MSTORE
+DUP4
+DUP2
+DUP2
+MLOAD
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+DUP1
+MLOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+DUP1
+DUP4
+DUP4
+PUSH1 0x00
+JUMPDEST
+DUP4
+DUP2
+LT
+ISZERO
+PUSH2 0x0335
+JUMPI
+DUP1
+DUP3
+ADD
+MLOAD
+DUP2
+DUP5
+ADD
+MSTORE
+PUSH1 0x20
+DUP2
+ADD
+SWAP1
+POP
+PUSH2 0x031a
+JUMP
+JUMPDEST
+POP
+POP
+POP
+POP
+SWAP1
+POP
+SWAP1
+DUP2
+ADD
+SWAP1
+PUSH1 0x1f
+AND
+DUP1
+ISZERO
+PUSH2 0x0362
+JUMPI
+DUP1
+DUP3
+SUB
+DUP1
+MLOAD
+PUSH1 0x01
+DUP4
+PUSH1 0x20
+SUB
+PUSH2 0x0100
+EXP
+SUB
+NOT
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+JUMPDEST
+POP
+SWAP3
+POP
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x037b
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x03b0
+PUSH1 0x04
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+DUP1
+CALLDATALOAD
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+POP
+POP
+PUSH2 0x0bce
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+ISZERO
+ISZERO
+ISZERO
+ISZERO
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+PUSH2 0x03d2
+PUSH2 0x0440
+JUMP
+JUMPDEST
+STOP
+JUMPDEST
+CALLVALUE
+ISZERO
+PUSH2 0x03df
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+PUSH2 0x042a
+PUSH1 0x04
+DUP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+DUP1
+CALLDATALOAD
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+SWAP1
+PUSH1 0x20
+ADD
+SWAP1
+SWAP2
+SWAP1
+POP
+POP
+PUSH2 0x0be3
+JUMP
+JUMPDEST
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+RETURN
+JUMPDEST
+CALLVALUE
+PUSH1 0x03
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+DUP3
+DUP3
+SLOAD
+ADD
+SWAP3
+POP
+POP
+DUP2
+SWAP1
+SSTORE
+POP
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH32 0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c
+CALLVALUE
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+LOG2
+JUMP
+JUMPDEST
+PUSH1 0x00
+DUP1
+SLOAD
+PUSH1 0x01
+DUP2
+PUSH1 0x01
+AND
+ISZERO
+PUSH2 0x0100
+MUL
+SUB
+AND
+PUSH1 0x02
+SWAP1
+DIV
+DUP1
+PUSH1 0x1f
+ADD
+PUSH1 0x20
+DUP1
+SWAP2
+DIV
+MUL
+PUSH1 0x20
+ADD
+PUSH1 0x40
+MLOAD
+SWAP1
+DUP2
+ADD
+PUSH1 0x40
+MSTORE
+DUP1
+SWAP3
+SWAP2
+SWAP1
+DUP2
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+DUP3
+DUP1
+SLOAD
+PUSH1 0x01
+DUP2
+PUSH1 0x01
+AND
+ISZERO
+PUSH2 0x0100
+MUL
+SUB
+AND
+PUSH1 0x02
+SWAP1
+DIV
+DUP1
+ISZERO
+PUSH2 0x0573
+JUMPI
+DUP1
+PUSH1 0x1f
+LT
+PUSH2 0x0548
+JUMPI
+PUSH2 0x0100
+DUP1
+DUP4
+SLOAD
+DIV
+MUL
+DUP4
+MSTORE
+SWAP2
+PUSH1 0x20
+ADD
+SWAP2
+PUSH2 0x0573
+JUMP
+JUMPDEST
+DUP3
+ADD
+SWAP2
+SWAP1
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+SHA3
+SWAP1
+JUMPDEST
+DUP2
+SLOAD
+DUP2
+MSTORE
+SWAP1
+PUSH1 0x01
+ADD
+SWAP1
+PUSH1 0x20
+ADD
+DUP1
+DUP4
+GT
+PUSH2 0x0556
+JUMPI
+DUP3
+SWAP1
+SUB
+PUSH1 0x1f
+AND
+DUP3
+ADD
+SWAP2
+JUMPDEST
+POP
+POP
+POP
+POP
+POP
+DUP2
+JUMP
+JUMPDEST
+PUSH1 0x00
+DUP2
+PUSH1 0x04
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+DUP6
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+DUP2
+SWAP1
+SSTORE
+POP
+DUP3
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH32 0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925
+DUP5
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+LOG3
+PUSH1 0x01
+SWAP1
+POP
+SWAP3
+SWAP2
+POP
+POP
+JUMP
+JUMPDEST
+PUSH1 0x00
+ADDRESS
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+BALANCE
+SWAP1
+POP
+SWAP1
+JUMP
+JUMPDEST
+PUSH1 0x00
+DUP2
+PUSH1 0x03
+PUSH1 0x00
+DUP7
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+SLOAD
+LT
+ISZERO
+ISZERO
+ISZERO
+PUSH2 0x06dc
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP5
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+EQ
+ISZERO
+DUP1
+ISZERO
+PUSH2 0x07b4
+JUMPI
+POP
+PUSH32 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
+PUSH1 0x04
+PUSH1 0x00
+DUP7
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+SLOAD
+EQ
+ISZERO
+JUMPDEST
+ISZERO
+PUSH2 0x08cf
+JUMPI
+DUP2
+PUSH1 0x04
+PUSH1 0x00
+DUP7
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+SLOAD
+LT
+ISZERO
+ISZERO
+ISZERO
+PUSH2 0x0844
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+DUP2
+PUSH1 0x04
+PUSH1 0x00
+DUP7
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+DUP3
+DUP3
+SLOAD
+SUB
+SWAP3
+POP
+POP
+DUP2
+SWAP1
+SSTORE
+POP
+JUMPDEST
+DUP2
+PUSH1 0x03
+PUSH1 0x00
+DUP7
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+DUP3
+DUP3
+SLOAD
+SUB
+SWAP3
+POP
+POP
+DUP2
+SWAP1
+SSTORE
+POP
+DUP2
+PUSH1 0x03
+PUSH1 0x00
+DUP6
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+DUP3
+DUP3
+SLOAD
+ADD
+SWAP3
+POP
+POP
+DUP2
+SWAP1
+SSTORE
+POP
+DUP3
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP5
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH32 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef
+DUP5
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+LOG3
+PUSH1 0x01
+SWAP1
+POP
+SWAP4
+SWAP3
+POP
+POP
+POP
+JUMP
+JUMPDEST
+DUP1
+PUSH1 0x03
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+SLOAD
+LT
+ISZERO
+ISZERO
+ISZERO
+PUSH2 0x0a27
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+DUP1
+PUSH1 0x03
+PUSH1 0x00
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP1
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+DUP3
+DUP3
+SLOAD
+SUB
+SWAP3
+POP
+POP
+DUP2
+SWAP1
+SSTORE
+POP
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH2 0x08fc
+DUP3
+SWAP1
+DUP2
+ISZERO
+MUL
+SWAP1
+PUSH1 0x40
+MLOAD
+PUSH1 0x00
+PUSH1 0x40
+MLOAD
+DUP1
+DUP4
+SUB
+DUP2
+DUP6
+DUP9
+DUP9
+CALL
+SWAP4
+POP
+POP
+POP
+POP
+ISZERO
+ISZERO
+PUSH2 0x0ab4
+JUMPI
+PUSH1 0x00
+DUP1
+REVERT
+JUMPDEST
+CALLER
+PUSH20 0xffffffffffffffffffffffffffffffffffffffff
+AND
+PUSH32 0x7fcf532c15f0a6db0bd6d0e038bea71d30d808c7d98cb3bf7268a95bf5081b65
+DUP3
+PUSH1 0x40
+MLOAD
+DUP1
+DUP3
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+SWAP2
+POP
+POP
+PUSH1 0x40
+MLOAD
+DUP1
+SWAP2
+SUB
+SWAP1
+LOG2
+POP
+JUMP
+JUMPDEST
+PUSH1 0x02
+PUSH1 0x00
+SWAP1
+SLOAD
+SWAP1
+PUSH2 0x0100
+EXP
+SWAP1
+DIV
+PUSH1 0xff
+AND
+DUP2
+JUMP
+JUMPDEST
+PUSH1 0x03
+PUSH1 0x20
+MSTORE
+DUP1
+PUSH1 0x00
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+SWAP2
+POP
+SWAP1
+POP
+SLOAD
+DUP2
+JUMP
+JUMPDEST
+PUSH1 0x01
+DUP1
+SLOAD
+PUSH1 0x01
+DUP2
+PUSH1 0x01
+AND
+ISZERO
+PUSH2 0x0100
+MUL
+SUB
+AND
+PUSH1 0x02
+SWAP1
+DIV
+DUP1
+PUSH1 0x1f
+ADD
+PUSH1 0x20
+DUP1
+SWAP2
+DIV
+MUL
+PUSH1 0x20
+ADD
+PUSH1 0x40
+MLOAD
+SWAP1
+DUP2
+ADD
+PUSH1 0x40
+MSTORE
+DUP1
+SWAP3
+SWAP2
+SWAP1
+DUP2
+DUP2
+MSTORE
+PUSH1 0x20
+ADD
+DUP3
+DUP1
+SLOAD
+PUSH1 0x01
+DUP2
+PUSH1 0x01
+AND
+ISZERO
+PUSH2 0x0100
+MUL
+SUB
+AND
+PUSH1 0x02
+SWAP1
+DIV
+DUP1
+ISZERO
+PUSH2 0x0bc6
+JUMPI
+DUP1
+PUSH1 0x1f
+LT
+PUSH2 0x0b9b
+JUMPI
+PUSH2 0x0100
+DUP1
+DUP4
+SLOAD
+DIV
+MUL
+DUP4
+MSTORE
+SWAP2
+PUSH1 0x20
+ADD
+SWAP2
+PUSH2 0x0bc6
+JUMP
+JUMPDEST
+DUP3
+ADD
+SWAP2
+SWAP1
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+SHA3
+SWAP1
+JUMPDEST
+DUP2
+SLOAD
+DUP2
+MSTORE
+SWAP1
+PUSH1 0x01
+ADD
+SWAP1
+PUSH1 0x20
+ADD
+DUP1
+DUP4
+GT
+PUSH2 0x0ba9
+JUMPI
+DUP3
+SWAP1
+SUB
+PUSH1 0x1f
+AND
+DUP3
+ADD
+SWAP2
+JUMPDEST
+POP
+POP
+POP
+POP
+POP
+DUP2
+JUMP
+JUMPDEST
+PUSH1 0x00
+PUSH2 0x0bdb
+CALLER
+DUP5
+DUP5
+PUSH2 0x068c
+JUMP
+JUMPDEST
+SWAP1
+POP
+SWAP3
+SWAP2
+POP
+POP
+JUMP
+JUMPDEST
+PUSH1 0x04
+PUSH1 0x20
+MSTORE
+DUP2
+PUSH1 0x00
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+SHA3
+PUSH1 0x20
+MSTORE
+DUP1
+PUSH1 0x00
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+SHA3
+PUSH1 0x00
+SWAP2
+POP
+SWAP2
+POP
+POP
+SLOAD
+DUP2
+JUMP
+STOP
+LOG1
+PUSH6 0x627a7a723058
+SHA3
+INVALID
+INVALID
+INVALID
+INVALID
+INVALID
+EXTCODECOPY
+INVALID
+INVALID
+LOG3
+INVALID
+INVALID
+DELEGATECALL
+PUSH8 0x28389c2fe2c165d5
+STATICCALL
+STATICCALL
+SMOD
+PUSH7 0x1e4e004f6c344a
+STOP
+INVALID
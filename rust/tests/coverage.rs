@@ -0,0 +1,102 @@
+//! Coverage of opcodes and of the instructions of each contract, over
+//! several runs.
+
+use evm::coverage::Coverage;
+use evm::inspector::{Inspector, Step};
+use evm::{evm_with_inspector, EvmData};
+
+fn run(coverage: &mut Coverage, code: &str, data: &mut EvmData) {
+    let result = evm_with_inspector(hex::decode(code).unwrap(), data, true, coverage);
+    assert!(result.success, "{:?}", result);
+}
+
+// PUSH1 1, PUSH1 9, JUMPI, PUSH1 2, PUSH1 0, JUMPDEST, STOP: the pushes in
+// between are jumped over
+const BRANCH: &str = "6001600957600260005b00";
+
+#[test]
+fn instructions_that_never_ran_are_listed() {
+    let mut coverage = Coverage::new();
+    coverage.name(&hex::decode(BRANCH).unwrap(), "branch");
+    run(&mut coverage, BRANCH, &mut EvmData::default());
+
+    let contracts: Vec<_> = coverage.contracts().collect();
+    assert_eq!(contracts.len(), 1);
+    assert_eq!(contracts[0].name.as_deref(), Some("branch"));
+    assert_eq!(
+        contracts[0].unexecuted(),
+        vec![(5, "PUSH1 0x02".to_string()), (7, "PUSH1 0x00".to_string())]
+    );
+    assert_eq!(contracts[0].ratio(), (5, 7));
+    assert_eq!(coverage.opcodes()[&0x60], 2);
+    assert!(coverage
+        .summary()
+        .contains("\nbranch: 5 of 7 instructions executed\n  0x0005  PUSH1 0x02\n"));
+}
+
+#[test]
+fn runs_add_up_and_called_contracts_count_on_their_own() {
+    let mut coverage = Coverage::new();
+    let mut data = EvmData::default();
+    // ADD, STOP
+    data.codes
+        .insert("4660".to_string(), "600160010100".parse().unwrap());
+    // CALL(gas, 0x1234, 0, 0, 0, 0, 0)
+    let caller = "600060006000600060006112345af100";
+    run(&mut coverage, caller, &mut data);
+    run(&mut coverage, caller, &mut data);
+
+    assert_eq!(coverage.contracts().count(), 2);
+    assert!(coverage
+        .contracts()
+        .all(|contract| contract.unexecuted().is_empty()));
+    assert_eq!(coverage.opcodes()[&0xf1], 2);
+    assert_eq!(coverage.opcodes()[&0x01], 2);
+    assert!(coverage.unexecuted_opcodes().contains(&0x02));
+    assert!(!coverage.unexecuted_opcodes().contains(&0x01));
+}
+
+// Counts the instructions run
+#[derive(Default)]
+struct Steps(usize);
+
+impl Inspector for Steps {
+    fn step(&mut self, _step: &Step) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn coverage_runs_alongside_another_inspector() {
+    let mut coverage = Coverage::new();
+    let mut steps = Steps::default();
+    let code = hex::decode(BRANCH).unwrap();
+    evm_with_inspector(
+        code,
+        &mut EvmData::default(),
+        true,
+        &mut (&mut steps, &mut coverage),
+    );
+    assert_eq!(coverage.opcodes()[&0x57], 1);
+    assert_eq!(steps.0, 5);
+}
+
+#[test]
+fn coverage_collected_apart_merges() {
+    let mut first = Coverage::new();
+    first.name(&hex::decode(BRANCH).unwrap(), "branch");
+    run(&mut first, BRANCH, &mut EvmData::default());
+    let mut second = Coverage::new();
+    run(&mut second, BRANCH, &mut EvmData::default());
+    // PUSH1 1, STOP
+    run(&mut second, "600100", &mut EvmData::default());
+
+    second.merge(first);
+    assert_eq!(second.contracts().count(), 2);
+    assert_eq!(second.opcodes()[&0x57], 2);
+    let branch = second
+        .contracts()
+        .find(|contract| contract.name.as_deref() == Some("branch"))
+        .unwrap();
+    assert_eq!(branch.ratio(), (5, 7));
+}
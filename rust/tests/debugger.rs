@@ -0,0 +1,71 @@
+//! Stepping through an execution with the debugger.
+#![cfg(feature = "std")]
+
+use evm::debugger::{Breakpoint, Debugger};
+use evm::{EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+// PUSH1 5, PUSH1 1, SSTORE, PUSH1 1, SLOAD, PUSH1 0, MSTORE, STOP
+const CODE: &str = "6005 6001 55 6001 54 6000 52 00";
+
+fn debugger() -> Debugger {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0xf4240" })).unwrap(),
+        ),
+        ..Default::default()
+    };
+    Debugger::new(hex::decode(CODE.replace(' ', "")).unwrap(), &mut data, true)
+}
+
+#[test]
+fn steps_one_instruction_at_a_time() {
+    let mut debugger = debugger();
+    assert_eq!(debugger.pc(), Some(0));
+    assert!(debugger.stack().is_empty());
+
+    debugger.step();
+    debugger.step();
+    assert_eq!(debugger.pc(), Some(4));
+    assert_eq!(debugger.stack(), [U256::from(5), U256::one()]);
+    assert_eq!(debugger.storage(U256::one()), U256::zero());
+
+    debugger.step();
+    assert_eq!(debugger.storage(U256::one()), U256::from(5));
+
+    debugger.step_back();
+    assert_eq!(debugger.pc(), Some(4));
+    assert_eq!(debugger.storage(U256::one()), U256::zero());
+}
+
+#[test]
+fn runs_to_a_pc() {
+    let mut debugger = debugger();
+    let snapshot = debugger.run_until(0x0b).unwrap();
+    assert_eq!(snapshot.opcode, 0x00);
+    assert_eq!(debugger.memory().len(), 32);
+    assert_eq!(debugger.memory()[31], 5);
+
+    assert!(debugger.run_until(0x0b).is_none());
+    assert!(debugger.is_finished());
+    assert!(debugger.result().success);
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let mut debugger = debugger();
+    debugger.add_breakpoint(Breakpoint::StorageKey(U256::one()));
+    debugger.add_breakpoint(Breakpoint::Opcode(0x52));
+
+    assert_eq!(debugger.resume().unwrap().opcode, 0x55);
+    assert_eq!(debugger.resume().unwrap().opcode, 0x54);
+    assert_eq!(debugger.resume().unwrap().opcode, 0x52);
+    assert!(debugger.resume().is_none());
+
+    debugger.remove_breakpoint(&Breakpoint::Opcode(0x52));
+    assert_eq!(
+        debugger.breakpoints(),
+        [Breakpoint::StorageKey(U256::one())]
+    );
+}
@@ -0,0 +1,180 @@
+//! EIP-7702: delegation designators, authorizations and set code
+//! transactions.
+#![cfg(feature = "std")]
+
+use evm::delegation::{delegate, designator};
+use evm::spec::SpecId;
+use evm::t8n::transition;
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+const SENDER: &str = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+const CONTRACT: &str = "0x0000000000000000000000000000000000001000";
+#[cfg(feature = "k256")]
+const SECRET_KEY: &str = "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+
+fn alloc() -> Value {
+    json!({
+        SENDER: { "balance": "0x1000000000" },
+        // SSTORE(1, 42)
+        CONTRACT: { "code": "0x602a600155" },
+    })
+}
+
+fn env() -> Value {
+    json!({
+        "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+        "currentGasLimit": "0x1000000",
+        "currentNumber": "0x1",
+        "currentTimestamp": "0x3e8",
+        "currentBaseFee": "0x7",
+        "currentRandom": "0x0",
+    })
+}
+
+fn set_code_tx(authorization_list: Value) -> Value {
+    json!({
+        "type": "0x4",
+        "sender": SENDER,
+        "nonce": "0x0",
+        "maxFeePerGas": "0xa",
+        "maxPriorityFeePerGas": "0x1",
+        "gas": "0x186a0",
+        "to": SENDER,
+        "value": "0x0",
+        "input": "0x",
+        "authorizationList": authorization_list,
+        "v": "0x0",
+        "r": "0x1",
+        "s": "0x1",
+    })
+}
+
+#[test]
+fn designators_name_their_delegate() {
+    let address = U256::from(0x1000);
+    let code = designator(address);
+    assert_eq!(code.len(), 23);
+    assert_eq!(&code[..3], &[0xef, 0x01, 0x00]);
+    assert_eq!(delegate(&code), Some(address));
+    assert_eq!(delegate(&code[..22]), None);
+    assert_eq!(delegate(&[0x60, 0x00]), None);
+}
+
+#[test]
+fn set_code_transactions_are_checked() {
+    let auth = json!([{
+        "chainId": "0x1", "address": CONTRACT, "nonce": "0x1",
+        "yParity": "0x0", "r": "0x1", "s": "0x1",
+    }]);
+    let mut create = set_code_tx(auth.clone());
+    create["to"] = Value::Null;
+    let txs = json!([set_code_tx(json!([])), create]);
+    let output = transition(&alloc(), &env(), &txs, SpecId::Prague, 1).unwrap();
+    let rejected = output.result["rejected"].as_array().unwrap();
+    assert_eq!(
+        rejected[0]["error"],
+        json!("EIP-7702 transaction with empty auth list")
+    );
+    assert_eq!(
+        rejected[1]["error"],
+        json!("EIP-7702 transaction cannot be used to create contract")
+    );
+
+    let output = transition(
+        &alloc(),
+        &env(),
+        &json!([set_code_tx(auth)]),
+        SpecId::Cancun,
+        1,
+    )
+    .unwrap();
+    assert!(output.result["rejected"][0]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("transaction type not supported"));
+}
+
+#[cfg(feature = "k256")]
+#[test]
+fn authorizations_are_signed_by_their_authority() {
+    use evm::tx::{Authorization, SetCodeTx, Transaction};
+
+    let key: [u8; 32] = hex::decode(SECRET_KEY).unwrap().try_into().unwrap();
+    let authorization = Authorization {
+        chain_id: U256::one(),
+        address: U256::from(0x1000),
+        nonce: 1,
+        ..Default::default()
+    }
+    .sign(&key)
+    .unwrap();
+    let sender = U256::from_str_radix(&SENDER[2..], 16).unwrap();
+    assert_eq!(authorization.authority(), Some(sender));
+    let high_parity = Authorization {
+        y_parity: 2,
+        ..authorization.clone()
+    };
+    assert_eq!(high_parity.authority(), None);
+
+    let tx = Transaction::SetCode(SetCodeTx {
+        chain_id: 1,
+        gas_limit: 100_000,
+        to: sender,
+        authorization_list: vec![authorization.clone()],
+        ..Default::default()
+    })
+    .sign(&key)
+    .unwrap();
+    let decoded = Transaction::decode(&tx.encode()).unwrap();
+    assert_eq!(decoded, tx);
+    assert_eq!(decoded.authorization_list(), &[authorization][..]);
+    assert_eq!(decoded.sender(), Some(sender));
+}
+
+#[cfg(feature = "k256")]
+#[test]
+fn a_delegated_account_runs_the_code_of_its_delegate() {
+    use evm::t8n::alloc_json;
+    use evm::tx::Authorization;
+
+    let key: [u8; 32] = hex::decode(SECRET_KEY).unwrap().try_into().unwrap();
+    // The sender delegates to itself: the authorization is checked after
+    // its nonce went up
+    let sign = |nonce: u64| {
+        let authorization = Authorization {
+            chain_id: U256::one(),
+            address: U256::from(0x1000),
+            nonce,
+            ..Default::default()
+        }
+        .sign(&key)
+        .unwrap();
+        json!({
+            "chainId": "0x1",
+            "address": CONTRACT,
+            "nonce": format!("{:#x}", nonce),
+            "yParity": format!("{:#x}", authorization.y_parity),
+            "r": format!("{:#x}", authorization.r),
+            "s": format!("{:#x}", authorization.s),
+        })
+    };
+    // The second authorization has a stale nonce and is skipped
+    let tx = set_code_tx(json!([sign(1), sign(1)]));
+    let output = transition(&alloc(), &env(), &json!([tx]), SpecId::Prague, 1).unwrap();
+    assert_eq!(output.result.get("rejected"), None);
+    assert_eq!(output.result["receipts"][0]["status"], json!("0x1"));
+    assert_eq!(output.result["receipts"][0]["type"], json!("0x4"));
+
+    let alloc = alloc_json(&output.alloc);
+    assert_eq!(
+        alloc[SENDER]["code"],
+        json!(format!("0x{}", hex::encode(designator(U256::from(0x1000)))))
+    );
+    assert_eq!(alloc[SENDER]["nonce"], json!("0x2"));
+    assert_eq!(
+        alloc[SENDER]["storage"]
+            ["0x0000000000000000000000000000000000000000000000000000000000000001"],
+        json!("0x000000000000000000000000000000000000000000000000000000000000002a")
+    );
+}
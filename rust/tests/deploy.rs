@@ -0,0 +1,142 @@
+//! Deploying contracts with `deploy::deploy`, then calling them.
+
+use evm::deploy::{deploy, DeployError, MAX_CODE_SIZE, MAX_INITCODE_SIZE};
+use evm::host::Database;
+use evm::spec::{ChainConfig, SpecId};
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const DEPLOYER: u64 = 0xa11ce;
+// Returns 42 as a word
+const RUNTIME: &str = "602a60005260206000f3";
+// Stores RUNTIME at 22..32 and returns it
+const INIT: &str = "69602a60005260206000f3600052600a6016f3";
+
+fn data() -> EvmData {
+    let mut data = EvmData::default();
+    data.balances.insert(DEPLOYER.to_string(), U256::from(100));
+    data.nonces.insert(DEPLOYER.to_string(), 3);
+    data
+}
+
+#[test]
+fn deployed_contracts_can_be_called() {
+    let mut data = data();
+    let deployer = U256::from(DEPLOYER);
+    let address = deploy(
+        &hex::decode(INIT).unwrap(),
+        deployer,
+        U256::from(10),
+        &mut data,
+    )
+    .unwrap();
+
+    // keccak(rlp([deployer, 3]))[12..]
+    let expected = "fc14a3000713f07c3b16b1e22125a67949d67bd8";
+    assert_eq!(address, U256::from_str_radix(expected, 16).unwrap());
+    assert_eq!(data.code(address), Some(hex::decode(RUNTIME).unwrap()));
+    let account = data.basic(address).unwrap();
+    assert_eq!((account.nonce, account.balance), (1, U256::from(10)));
+    let deployer = data.basic(deployer).unwrap();
+    assert_eq!((deployer.nonce, deployer.balance), (4, U256::from(90)));
+
+    data.tx_data =
+        Some(serde_json::from_value::<TxData>(json!({ "to": format!("{:#x}", address) })).unwrap());
+    let code = data.code(address).unwrap();
+    let result = evm(code, &mut data, true);
+    assert_eq!(
+        result.value.map(|value| U256::from(&value[..])),
+        Some(U256::from(42))
+    );
+}
+
+#[test]
+fn failed_deployments_change_nothing() {
+    let mut data = data();
+    let deployer = U256::from(DEPLOYER);
+
+    // REVERT(0, 0)
+    let error = deploy(
+        &hex::decode("60006000fd").unwrap(),
+        deployer,
+        U256::zero(),
+        &mut data,
+    );
+    assert!(matches!(error, Err(DeployError::Failed(_))));
+
+    // RETURN(0, MAX_CODE_SIZE + 1)
+    let init = format!("61{:04x}6000f3", MAX_CODE_SIZE + 1);
+    let error = deploy(
+        &hex::decode(init).unwrap(),
+        deployer,
+        U256::zero(),
+        &mut data,
+    );
+    assert!(matches!(
+        error,
+        Err(DeployError::CodeTooLarge { size, .. }) if size == MAX_CODE_SIZE + 1
+    ));
+
+    let error = deploy(
+        &hex::decode(INIT).unwrap(),
+        deployer,
+        U256::from(101),
+        &mut data,
+    );
+    assert!(matches!(
+        error,
+        Err(DeployError::InsufficientBalance { .. })
+    ));
+
+    assert_eq!(data.basic(deployer).unwrap().nonce, 3);
+    assert!(data.codes.is_empty());
+}
+
+#[test]
+fn init_code_limit_applies_from_shanghai() {
+    let init = vec![0; MAX_INITCODE_SIZE + 1];
+    let deployer = U256::from(DEPLOYER);
+
+    let mut shanghai = data();
+    let error = deploy(&init, deployer, U256::zero(), &mut shanghai);
+    assert!(matches!(error, Err(DeployError::InitCodeTooLarge { .. })));
+
+    // STOPs right away, deploying no code
+    let mut london = data();
+    london.config = ChainConfig::new(SpecId::London);
+    assert!(deploy(&init, deployer, U256::zero(), &mut london).is_ok());
+}
+
+#[test]
+fn create_enforces_the_same_limits() {
+    let run = |code: String, spec: SpecId| {
+        let mut data = data();
+        data.config = ChainConfig::new(spec);
+        data.tx_data = Some(
+            serde_json::from_value::<TxData>(json!({ "to": format!("{:#x}", DEPLOYER) })).unwrap(),
+        );
+        let result = evm(hex::decode(code).unwrap(), &mut data, true);
+        (result, data)
+    };
+
+    // Init code RETURN(0, MAX_CODE_SIZE + 1) stored at 26..32, then
+    // CREATE(0, 26, 6)
+    let init = format!("61{:04x}6000f3", MAX_CODE_SIZE + 1);
+    let code = format!("65{}600052 6006601a6000f0", init).replace(' ', "");
+    let (result, data) = run(code.clone(), SpecId::Cancun);
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::zero()]);
+    assert!(data.codes.is_empty());
+    // EIP-170 came with Spurious Dragon
+    let (result, data) = run(code, SpecId::Tangerine);
+    assert_eq!(data.code(result.stack[0]).unwrap().len(), MAX_CODE_SIZE + 1);
+
+    // CREATE(0, 0, MAX_INITCODE_SIZE + 1)
+    let code = format!("61{:04x}60006000f0", MAX_INITCODE_SIZE + 1);
+    let (result, _) = run(code.clone(), SpecId::Shanghai);
+    assert!(!result.success);
+    let (result, _) = run(code, SpecId::London);
+    assert!(result.success);
+    assert!(!result.stack[0].is_zero());
+}
@@ -0,0 +1,116 @@
+//! Dumping the state to JSON and loading it back.
+#![cfg(feature = "std")]
+
+use evm::dump::DumpError;
+use evm::host::Database;
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn state() -> EvmData {
+    let mut data = EvmData::default();
+    data.set_balance(U256::from(0xa11ce), U256::from(1000));
+    data.set_nonce(U256::from(0xa11ce), 3);
+    data.set_code(U256::from(0xc0de), &[0x60, 0x00]);
+    data.set_state(U256::from(0xc0de), U256::one(), Some(U256::from(0x2a)));
+    data
+}
+
+#[test]
+fn dumps_are_sorted_and_full_width() {
+    let dump = state().dump();
+    assert_eq!(
+        dump,
+        json!({
+            "version": 2,
+            "accounts": {
+                "0x00000000000000000000000000000000000a11ce": { "balance": "0x3e8", "nonce": "0x3" },
+                "0x000000000000000000000000000000000000c0de": {
+                    "code": "0x6000",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                            "0x000000000000000000000000000000000000000000000000000000000000002a",
+                    },
+                },
+            },
+        })
+    );
+    assert_eq!(dump.to_string(), state().dump().to_string());
+}
+
+#[test]
+fn a_loaded_dump_is_the_pre_state_of_the_next_run() {
+    let mut data = state();
+    // SSTORE(1, SLOAD(1) + 1)
+    let increment = [0x60, 0x01, 0x54, 0x60, 0x01, 0x01, 0x60, 0x01, 0x55];
+    data.tx_data = Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap());
+    assert!(evm(increment, &mut data, true).success);
+    let dump = data.dump();
+
+    let mut next = EvmData {
+        tx_data: data.tx_data.clone(),
+        ..Default::default()
+    };
+    next.load(&dump).unwrap();
+    assert_eq!(next.dump(), dump);
+    assert_eq!(next.basic(U256::from(0xa11ce)).unwrap().nonce, 3);
+    assert!(evm(increment, &mut next, true).success);
+    assert_eq!(
+        next.storage(U256::from(0xc0de), U256::one()),
+        U256::from(44)
+    );
+
+    let path = std::env::temp_dir().join(format!("evm-dump-{}.json", std::process::id()));
+    next.save_dump(&path).unwrap();
+    let mut loaded = EvmData::default();
+    loaded.load_dump(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.dump(), next.dump());
+}
+
+#[test]
+fn storage_is_dumped_under_its_account() {
+    let mut data = state();
+    data.set_state(U256::from(0xbeef), U256::one(), Some(U256::from(7)));
+    let dump = data.dump();
+    let slot = |address: &str| {
+        dump["accounts"][address]["storage"]
+            ["0x0000000000000000000000000000000000000000000000000000000000000001"]
+            .clone()
+    };
+    assert!(slot("0x000000000000000000000000000000000000c0de")
+        .as_str()
+        .unwrap()
+        .ends_with("2a"));
+    assert!(slot("0x000000000000000000000000000000000000beef")
+        .as_str()
+        .unwrap()
+        .ends_with("07"));
+
+    let mut loaded = EvmData::default();
+    loaded.load(&dump).unwrap();
+    assert_eq!(
+        loaded.storage(U256::from(0xc0de), U256::one()),
+        U256::from(0x2a)
+    );
+    assert_eq!(
+        loaded.storage(U256::from(0xbeef), U256::one()),
+        U256::from(7)
+    );
+}
+
+#[test]
+fn malformed_dumps_are_errors() {
+    let mut data = state();
+    assert_eq!(
+        data.load(&json!({ "accounts": {} })),
+        Err(DumpError::Invalid("expected version 2".to_string()))
+    );
+    let bad_code = json!({ "version": 2, "accounts": { "0xc0de": { "code": "0xzz" } } });
+    assert!(matches!(
+        data.load(&bad_code),
+        Err(DumpError::Invalid(reason)) if reason.starts_with("code")
+    ));
+    // A failed load leaves the state as it was
+    assert_eq!(data.dump(), state().dump());
+}
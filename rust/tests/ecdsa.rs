@@ -0,0 +1,108 @@
+//! secp256k1 signing and recovery, the ecrecover precompile and the senders
+//! of signed transactions.
+#![cfg(feature = "k256")]
+
+use evm::ecdsa::{address_of, recover, sign};
+use evm::precompiles::ecrecover;
+use evm::tx::{LegacyTx, Transaction};
+use primitive_types::{H256, U256};
+
+// The key of many go-ethereum tests and its address
+const SECRET_KEY: &str = "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+const ADDRESS: &str = "a94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+// The signed example of EIP-155, its signing hash and its sender
+const EIP155_SIGNED: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+const EIP155_SIGNING_HASH: &str =
+    "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53";
+const EIP155_SENDER: &str = "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+
+fn key() -> [u8; 32] {
+    hex::decode(SECRET_KEY).unwrap().try_into().unwrap()
+}
+
+fn address(hex: &str) -> U256 {
+    U256::from_str_radix(hex, 16).unwrap()
+}
+
+#[test]
+fn signatures_recover_the_signer() {
+    assert_eq!(address_of(&key()), Some(address(ADDRESS)));
+    let hash = [0x42; 32];
+    let (recovery_id, r, s) = sign(&hash, &key()).unwrap();
+    assert_eq!(recover(&hash, recovery_id, r, s), Some(address(ADDRESS)));
+    assert_ne!(
+        recover(&[0x43; 32], recovery_id, r, s),
+        Some(address(ADDRESS))
+    );
+    assert_eq!(recover(&hash, 2, r, s), None);
+    assert_eq!(recover(&hash, recovery_id, U256::zero(), s), None);
+    assert_eq!(address_of(&[0; 32]), None);
+}
+
+#[test]
+fn the_precompile_takes_high_s_and_fails_quietly() {
+    let hash = [0x42; 32];
+    let (recovery_id, r, s) = sign(&hash, &key()).unwrap();
+    let input = |v: u64, s: U256| {
+        let mut input = hash.to_vec();
+        for word in [U256::from(v), r, s] {
+            let mut bytes = [0u8; 32];
+            word.to_big_endian(&mut bytes);
+            input.extend(bytes);
+        }
+        input
+    };
+    let expected = format!("{:0>64}", ADDRESS);
+    let v = 27 + recovery_id as u64;
+    assert_eq!(hex::encode(ecrecover(&input(v, s))), expected);
+
+    // The twin signature, with the other parity
+    let order = U256::from_str_radix(
+        "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        16,
+    )
+    .unwrap();
+    let twin = input(27 + (1 - recovery_id as u64), order - s);
+    assert_eq!(hex::encode(ecrecover(&twin)), expected);
+
+    assert!(ecrecover(&input(29, s)).is_empty());
+    assert!(ecrecover(&[]).is_empty());
+}
+
+#[test]
+fn transaction_senders() {
+    let raw = hex::decode(EIP155_SIGNED).unwrap();
+    let tx = Transaction::decode(&raw).unwrap();
+    assert_eq!(
+        tx.signing_hash(),
+        EIP155_SIGNING_HASH.parse::<H256>().unwrap()
+    );
+    assert_eq!(tx.sender(), Some(address(EIP155_SENDER)));
+
+    // Signing takes the chain id into v
+    let unsigned = Transaction::Legacy(LegacyTx {
+        chain_id: Some(5),
+        nonce: 1,
+        gas_limit: 21000,
+        to: Some(U256::from(0xb0b)),
+        ..Default::default()
+    });
+    let signed = unsigned.clone().sign(&key()).unwrap();
+    let v = signed.signature().v;
+    assert!(v == U256::from(45) || v == U256::from(46));
+    assert_eq!(signed.sender(), Some(address(ADDRESS)));
+    let decoded = Transaction::decode(&signed.encode()).unwrap();
+    assert_eq!(decoded.sender(), Some(address(ADDRESS)));
+    assert_eq!(decoded.chain_id(), Some(5));
+
+    // A signature for another chain recovers someone else
+    let Transaction::Legacy(mut other_chain) = signed else {
+        unreachable!()
+    };
+    other_chain.chain_id = Some(1);
+    other_chain.signature.v = other_chain.signature.v - 8;
+    assert_ne!(
+        Transaction::Legacy(other_chain).sender(),
+        Some(address(ADDRESS))
+    );
+}
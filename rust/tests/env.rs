@@ -0,0 +1,61 @@
+//! The environment opcodes read `EvmData::env`, parsed once per run.
+
+use evm::env::Env;
+use evm::{evm, EvmContext, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn tx(value: serde_json::Value) -> TxData {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn env_is_parsed_from_context_and_tx() {
+    let context: EvmContext = serde_json::from_value(json!({
+        "coinbase": "0x777",
+        "number": "0x10",
+        "chainid": "0x1",
+    }))
+    .unwrap();
+    let tx = tx(json!({ "to": "0xc0de", "origin": "0x1337", "blob_versioned_hashes": ["0x01"] }));
+    let env = Env::new(Some(&context), Some(&tx));
+    assert_eq!(env.block.coinbase, U256::from(0x777));
+    assert_eq!(env.block.number, U256::from(0x10));
+    // Left out, so zero
+    assert_eq!(env.block.basefee, U256::zero());
+    assert_eq!(env.tx.address, U256::from(0xc0de));
+    // No sender: the account calls itself
+    assert_eq!(env.tx.caller, U256::from(0xc0de));
+    assert_eq!(env.tx.origin, U256::from(0x1337));
+    assert_eq!(env.tx.blob_hashes, vec![U256::one()]);
+}
+
+#[test]
+fn missing_fields_read_as_zero() {
+    let mut data = EvmData {
+        tx_data: Some(tx(json!({ "to": "0xc0de" }))),
+        ..Default::default()
+    };
+    // ORIGIN, CALLVALUE, GASPRICE, COINBASE, BASEFEE
+    let result = evm(hex::decode("32343a4148").unwrap(), &mut data, true);
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::zero(); 5]);
+}
+
+#[test]
+fn init_code_sees_the_creator_and_the_origin() {
+    let mut data = EvmData {
+        tx_data: Some(tx(json!({ "to": "0xc0de", "origin": "0x1337" }))),
+        ..Default::default()
+    };
+    // Init code SSTORE(0, ORIGIN) SSTORE(1, CALLER), run by CREATE
+    let code = "68 326000553360015500 600052 6009 6017 6000 f0 00";
+    let result = evm(hex::decode(code.replace(' ', "")).unwrap(), &mut data, true);
+    assert!(result.success);
+    let created = &result.state_changes[&result.creates[0].address];
+    let slot = |key: u64| created[&U256::from(key)].after;
+    assert_eq!(slot(0), U256::from(0x1337));
+    assert_eq!(slot(1), U256::from(0xc0de));
+    // Back in the creating frame
+    assert_eq!(data.env.tx.address, U256::from(0xc0de));
+}
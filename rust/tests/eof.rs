@@ -0,0 +1,179 @@
+//! EOF containers and the instructions their code runs with.
+#![cfg(feature = "std")]
+
+use evm::asm::assemble;
+use evm::config::{EvmConfig, Preset};
+use evm::eof::{Container, EofError};
+use evm::spec::{ChainConfig, SpecId};
+use evm::{evm, EvmData, EvmResult, HaltReason, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+// A container of code sections given as (inputs, outputs, max stack
+// increase, assembly), with `data` after them
+fn container(sections: &[(u8, u8, u16, &str)], data: &[u8]) -> Vec<u8> {
+    let codes: Vec<_> = sections
+        .iter()
+        .map(|(_, _, _, asm)| assemble(asm).unwrap())
+        .collect();
+    let mut bytes = vec![0xef, 0x00, 0x01, 0x01];
+    bytes.extend((sections.len() as u16 * 4).to_be_bytes());
+    bytes.push(0x02);
+    bytes.extend((sections.len() as u16).to_be_bytes());
+    for code in &codes {
+        bytes.extend((code.len() as u16).to_be_bytes());
+    }
+    bytes.push(0xff);
+    bytes.extend((data.len() as u16).to_be_bytes());
+    bytes.push(0x00);
+    for (inputs, outputs, max_stack_increase, _) in sections {
+        bytes.extend([*inputs, *outputs]);
+        bytes.extend(max_stack_increase.to_be_bytes());
+    }
+    bytes.extend(codes.concat());
+    bytes.extend(data);
+    bytes
+}
+
+fn run(code: &[u8], spec: SpecId) -> EvmResult {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        config: ChainConfig::new(spec),
+        evm_config: EvmConfig::preset(Preset::SpecExact),
+        ..Default::default()
+    };
+    evm(code, &mut data, true)
+}
+
+fn stack(result: &EvmResult) -> Vec<U256> {
+    assert!(result.success, "{:?}", result.halt_reason);
+    result.stack.clone()
+}
+
+fn words(values: &[u64]) -> Vec<U256> {
+    values.iter().map(|value| U256::from(*value)).collect()
+}
+
+#[test]
+fn headers_are_checked() {
+    let code = container(&[(0, 0x80, 0, "STOP")], &[0xaa, 0xbb]);
+    let parsed = Container::parse(&code).unwrap();
+    assert_eq!(parsed.code_sections, vec![&[0x00][..]]);
+    assert_eq!(parsed.data, &[0xaa, 0xbb]);
+    assert_eq!(parsed.types[0].outputs, 0x80);
+
+    let mut version = code.clone();
+    version[2] = 2;
+    assert_eq!(
+        Container::parse(&version),
+        Err(EofError::UnsupportedVersion(2))
+    );
+    assert_eq!(
+        Container::parse(&code[..code.len() - 1]),
+        Err(EofError::Truncated)
+    );
+    assert_eq!(
+        Container::parse(&[code.clone(), vec![0]].concat()),
+        Err(EofError::TrailingBytes)
+    );
+    let returning = container(&[(0, 0, 0, "STOP")], &[]);
+    assert_eq!(
+        Container::parse(&returning),
+        Err(EofError::InvalidFirstSectionType)
+    );
+    assert_eq!(
+        Container::parse(&[0xef, 0x00, 0x01, 0x02]),
+        Err(EofError::MissingSection(0x01))
+    );
+}
+
+#[test]
+fn sections_call_and_return() {
+    let code = container(
+        &[
+            (0, 0x80, 2, "PUSH1 2\nPUSH1 3\nCALLF 1\nJUMPF 2"),
+            // Adds its two inputs
+            (2, 1, 0, "ADD\nRETF"),
+            (0, 0x80, 1, "PUSH1 7\nSTOP"),
+        ],
+        &[],
+    );
+    assert_eq!(stack(&run(&code, SpecId::Osaka)), words(&[7, 5]));
+}
+
+#[test]
+fn relative_jumps_loop_and_branch() {
+    let countdown = container(
+        &[(
+            0,
+            0x80,
+            3,
+            "
+            PUSH1 3
+            loop:
+            PUSH1 1
+            SWAP1
+            SUB
+            DUP1
+            RJUMPI loop
+            ",
+        )],
+        &[],
+    );
+    let result = run(&countdown, SpecId::Osaka);
+    assert_eq!(stack(&result), words(&[0]));
+
+    // Case 1 skips the push of 0x11, any case past the table none
+    let switch = |case: u8| {
+        let asm = format!(
+            "PUSH1 {}\nRJUMPV 0x0100000002\nPUSH1 0x11\nPUSH1 0x22\nSTOP",
+            case
+        );
+        container(&[(0, 0x80, 2, &asm)], &[])
+    };
+    assert_eq!(stack(&run(&switch(0), SpecId::Osaka)), words(&[0x22, 0x11]));
+    assert_eq!(stack(&run(&switch(1), SpecId::Osaka)), words(&[0x22]));
+    assert_eq!(stack(&run(&switch(9), SpecId::Osaka)), words(&[0x22, 0x11]));
+}
+
+#[test]
+fn deep_stack_instructions_take_immediates() {
+    let code = container(
+        &[(
+            0,
+            0x80,
+            4,
+            // 1 2 3, DUPN 2 copies the 1, SWAPN 0 swaps the top two,
+            // EXCHANGE 0 the two below the top
+            "PUSH1 1\nPUSH1 2\nPUSH1 3\nDUPN 2\nSWAPN 0\nEXCHANGE 0",
+        )],
+        &[],
+    );
+    assert_eq!(stack(&run(&code, SpecId::Osaka)), words(&[3, 2, 1, 1]));
+}
+
+#[test]
+fn the_return_stack_is_limited() {
+    let code = container(&[(0, 0x80, 0, "CALLF 1"), (0, 0, 0, "CALLF 1\nRETF")], &[]);
+    let result = run(&code, SpecId::Osaka);
+    assert_eq!(result.halt_reason, Some(HaltReason::StackOverflow));
+}
+
+#[test]
+fn eof_and_legacy_code_keep_to_their_instructions() {
+    let jump = container(&[(0, 0x80, 1, "PUSH1 0\nJUMP")], &[]);
+    let result = run(&jump, SpecId::Osaka);
+    assert_eq!(result.halt_reason, Some(HaltReason::InvalidOpcode));
+
+    let legacy = assemble("RJUMP 0").unwrap();
+    let result = run(&legacy, SpecId::Osaka);
+    assert_eq!(result.halt_reason, Some(HaltReason::InvalidOpcode));
+
+    // Before Osaka a container is legacy code starting with INVALID 0xef
+    let code = container(&[(0, 0x80, 0, "STOP")], &[]);
+    assert!(run(&code, SpecId::Osaka).success);
+    assert!(!run(&code, SpecId::Prague).success);
+    assert!(!run(&code[..code.len() - 1], SpecId::Osaka).success);
+}
@@ -0,0 +1,82 @@
+//! EXP wraps around 2^256 and is priced by the bytes of its exponent.
+
+use evm::config::{EvmConfig, Preset};
+use evm::spec::{ChainConfig, SpecId};
+use evm::{evm, EvmData, EvmResult, TxData};
+use primitive_types::U256;
+use proptest::prelude::*;
+use serde_json::json;
+
+// Runs EXP of `base` to `exponent` on `spec`
+fn run(base: U256, exponent: U256, spec: SpecId) -> EvmResult {
+    let mut code = vec![0x7f];
+    code.extend(word(exponent));
+    code.push(0x7f);
+    code.extend(word(base));
+    code.extend([0x0a, 0x00]);
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        config: ChainConfig::new(spec),
+        evm_config: EvmConfig::preset(Preset::SpecExact),
+        ..Default::default()
+    };
+    let result = evm(code, &mut data, true);
+    assert!(result.success);
+    result
+}
+
+fn exp(base: U256, exponent: U256) -> U256 {
+    run(base, exponent, SpecId::Cancun).stack[0]
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+#[test]
+fn powers_wrap_around() {
+    let two = U256::from(2);
+    assert_eq!(exp(two, U256::from(255)), U256::one() << 255);
+    assert_eq!(exp(two, U256::from(256)), U256::zero());
+    assert_eq!(exp(two, U256::MAX), U256::zero());
+    // -1 to an odd power is -1, to an even one 1
+    assert_eq!(exp(U256::MAX, U256::MAX), U256::MAX);
+    assert_eq!(exp(U256::MAX, U256::MAX - 1), U256::one());
+    // 3 is odd, so are its powers
+    assert!(exp(U256::from(3), U256::MAX).bit(0));
+
+    assert_eq!(exp(U256::zero(), U256::zero()), U256::one());
+    assert_eq!(exp(U256::zero(), U256::one()), U256::zero());
+    assert_eq!(exp(U256::MAX, U256::zero()), U256::one());
+    assert_eq!(exp(U256::from(10), U256::from(18)), U256::exp10(18));
+}
+
+#[test]
+fn each_byte_of_the_exponent_costs() {
+    // The transaction, two PUSH32 and EXP itself
+    let base = 21000 + 3 + 3 + 10;
+    let gas = |exponent: U256, spec| run(U256::from(3), exponent, spec).gas_used;
+    assert_eq!(gas(U256::zero(), SpecId::Cancun), base);
+    assert_eq!(gas(U256::one(), SpecId::Cancun), base + 50);
+    assert_eq!(gas(U256::from(0xff), SpecId::Cancun), base + 50);
+    assert_eq!(gas(U256::from(0x100), SpecId::Cancun), base + 2 * 50);
+    assert_eq!(gas(U256::MAX, SpecId::Cancun), base + 32 * 50);
+    // 10 a byte before Spurious Dragon (EIP-160)
+    assert_eq!(gas(U256::MAX, SpecId::Homestead), base + 32 * 10);
+}
+
+proptest! {
+    #[test]
+    fn powers_match_repeated_multiplication(base: u128, exponent in 0u32..64) {
+        let base = U256::from(base);
+        let mut expected = U256::one();
+        for _ in 0..exponent {
+            expected = expected.overflowing_mul(base).0;
+        }
+        prop_assert_eq!(exp(base, U256::from(exponent)), expected);
+    }
+}
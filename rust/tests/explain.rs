@@ -0,0 +1,85 @@
+//! Plain-English traces of executions.
+#![cfg(feature = "std")]
+
+use evm::explain::ExplainTracer;
+use evm::{evm_with_inspector, EvmData};
+
+fn explain(code: &str) -> Vec<String> {
+    let mut tracer = ExplainTracer::new();
+    let result = evm_with_inspector(
+        hex::decode(code).unwrap(),
+        &mut EvmData::default(),
+        true,
+        &mut tracer,
+    );
+    tracer.finish(&result)
+}
+
+#[test]
+fn explains_pushes_and_jumps() {
+    // PUSH1 1, PUSH1 6, JUMPI, STOP, JUMPDEST, PUSH1 0x42
+    let lines = explain("6001600657005b6042");
+    assert_eq!(
+        lines,
+        vec![
+            "0x0000  PUSH1 places 0x1 on the stack  [] -> [0x1]",
+            "0x0002  PUSH1 places 0x6 on the stack  [0x1] -> [0x6, 0x1]",
+            "0x0004  JUMPI: condition is nonzero, jumping to 0x6  [0x6, 0x1] -> []",
+            "0x0006  JUMPDEST marks a place jumps may land  [] -> []",
+            "0x0007  PUSH1 places 0x42 on the stack  [] -> [0x42]",
+        ]
+    );
+}
+
+#[test]
+fn explains_arithmetic_and_storage() {
+    // PUSH1 2, PUSH1 3, ADD, PUSH1 0, SSTORE
+    let lines = explain("600260030160005500");
+    assert_eq!(
+        lines[2],
+        "0x0004  ADD: 0x3 + 0x2 = 0x5  [0x3, 0x2] -> [0x5]"
+    );
+    assert_eq!(
+        lines[4],
+        "0x0007  SSTORE writes 0x5 to storage slot 0x0  [0x0, 0x5] -> []"
+    );
+}
+
+#[test]
+fn indents_nested_frames() {
+    let mut data = EvmData::default();
+    // Callee: PUSH1 1, POP
+    data.codes
+        .insert("170".to_string(), "600150".parse().unwrap());
+
+    // CALL(gas, 0xaa, 0, 0, 0, 0, 0)
+    let code = hex::decode("600080808080 60aa 5a f1".replace(' ', "")).unwrap();
+    let mut tracer = ExplainTracer::new();
+    let result = evm_with_inspector(code, &mut data, true, &mut tracer);
+    let lines = tracer.finish(&result);
+
+    let call = lines
+        .iter()
+        .position(|line| line.contains("CALL calls 0xaa"))
+        .unwrap();
+    assert!(lines[call].contains("it succeeded"));
+    assert!(lines[call + 1].starts_with("  0x0000  PUSH1 places 0x1"));
+    assert!(lines[call + 2].starts_with("  0x0002  POP discards 0x1"));
+}
+
+#[test]
+fn names_where_return_data_came_from() {
+    // MSTORE8(0, 0x42), REVERT(0, 1)
+    let lines = explain("604260005360016000fd");
+    assert_eq!(
+        lines.last().unwrap(),
+        "1 bytes of return data, from the REVERT of frame 0 at depth 1 (0x0)"
+    );
+}
+
+#[test]
+fn shows_signed_operands_as_signed() {
+    // SDIV(-6, 2) with -6 pushed as a full word
+    let lines = explain(&format!("6002 7f{}fa 05", "ff".repeat(31)).replace(' ', ""));
+    assert!(lines[2].starts_with("0x0023  SDIV: -6 / 2 = -3  "));
+}
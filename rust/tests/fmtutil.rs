@@ -0,0 +1,46 @@
+//! Formatting words for people.
+
+use evm::fmtutil::{format_ether, format_signed, format_units, format_word, hex_address, hex_word};
+use primitive_types::U256;
+
+#[test]
+fn formats_amounts_in_units() {
+    assert_eq!(format_units(U256::from(1_500_000), 6), "1.5");
+    assert_eq!(format_units(U256::zero(), 6), "0");
+    assert_eq!(format_ether(U256::exp10(18) * 3), "3 ETH");
+    assert_eq!(format_ether(U256::one()), "0.000000000000000001 ETH");
+    assert_eq!(
+        format_ether(U256::MAX),
+        "115792089237316195423570985008687907853269984665640564039457.584007913129639935 ETH"
+    );
+}
+
+#[test]
+fn formats_fixed_width_hex() {
+    assert_eq!(
+        hex_word(U256::from(0x2a)),
+        format!("0x{}2a", "0".repeat(62))
+    );
+    assert_eq!(hex_address(U256::MAX), format!("0x{}", "f".repeat(40)));
+    assert_eq!(
+        hex_address(U256::from(0xc0de)),
+        "0x000000000000000000000000000000000000c0de"
+    );
+}
+
+#[test]
+fn reads_words_as_signed() {
+    assert_eq!(format_signed(U256::MAX), "-1");
+    assert_eq!(format_signed(U256::from(7)), "7");
+    assert_eq!(
+        format_signed(U256::one() << 255),
+        "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+    );
+}
+
+#[test]
+fn picks_the_most_readable_form() {
+    assert_eq!(format_word(U256::from(1000)), "1000");
+    assert_eq!(format_word(U256::MAX - 1), "-2");
+    assert_eq!(format_word(U256::exp10(18)), "0xde0b6b3a7640000");
+}
@@ -0,0 +1,261 @@
+//! Running against state fetched over JSON-RPC, from a canned node and from
+//! one served over HTTP.
+#![cfg(feature = "std")]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use evm::cache::StateCache;
+use evm::fork::{ForkDb, HttpTransport, RpcError, Transport};
+use evm::host::Database;
+use evm::{evm, hexutil, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+const CONTRACT: &str = "0x000000000000000000000000000000000000c0de";
+
+// Answers from a fixed table and records the requests it was sent
+#[derive(Debug, Default, Clone)]
+struct CannedNode {
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl CannedNode {
+    fn methods(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request["method"].as_str().unwrap().to_string())
+            .collect()
+    }
+}
+
+impl Transport for CannedNode {
+    fn send(&self, body: &str) -> Result<String, RpcError> {
+        let request: Value = serde_json::from_str(body).unwrap();
+        self.requests.lock().unwrap().push(request.clone());
+        let params = &request["params"];
+        let result = match request["method"].as_str().unwrap() {
+            "eth_getBalance" => json!("0x3e8"),
+            "eth_getTransactionCount" => json!("0x7"),
+            // SLOAD(1)
+            "eth_getCode" if params[0] == CONTRACT => json!("0x600154"),
+            "eth_getCode" => json!("0x"),
+            "eth_getStorageAt" if params[0] == CONTRACT && params[1] == "0x1" => {
+                json!("0x000000000000000000000000000000000000000000000000000000000000002a")
+            }
+            "eth_getStorageAt" => json!("0x0"),
+            "eth_getBlockByNumber" => json!({ "hash": "0xabcd" }),
+            method => {
+                let error = json!({ "code": -32601, "message": format!("{} not found", method) });
+                return Ok(
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }).to_string(),
+                );
+            }
+        };
+        Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string())
+    }
+}
+
+#[test]
+fn lookups_are_pinned_to_the_fork_block() {
+    let node = CannedNode::default();
+    let fork = ForkDb::new(node.clone(), 0x10);
+    let contract = hexutil::parse_u256(CONTRACT).unwrap();
+
+    assert_eq!(fork.basic(contract).unwrap().balance, U256::from(1000));
+    assert_eq!(fork.basic(contract).unwrap().nonce, 7);
+    assert_eq!(fork.code(contract), Some(vec![0x60, 0x01, 0x54]));
+    assert_eq!(fork.storage(contract, U256::one()), U256::from(0x2a));
+    assert_eq!(fork.error(), None);
+
+    for request in node.requests.lock().unwrap().iter() {
+        let params = request["params"].as_array().unwrap();
+        assert_eq!(params.last().unwrap(), "0x10");
+    }
+}
+
+#[test]
+fn execution_writes_stay_local() {
+    let node = CannedNode::default();
+    let contract = hexutil::parse_u256(CONTRACT).unwrap();
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": CONTRACT, "gas": "0xf4240" })).unwrap(),
+        ),
+        database: Some(Arc::new(ForkDb::new(node.clone(), 0x10))),
+        ..Default::default()
+    };
+
+    let code = data.code(contract).unwrap();
+    // SLOAD(1), then SSTORE(1, 5) and SLOAD(1) again
+    let result = evm(&code, &mut data, true);
+    assert_eq!(result.stack, vec![U256::from(0x2a)]);
+    let result = evm(
+        hexutil::decode("6005600155600154").unwrap(),
+        &mut data,
+        true,
+    );
+    assert_eq!(result.stack, vec![U256::from(5)]);
+
+    assert_eq!(data.storage(contract, U256::one()), U256::from(5));
+    let methods = node.methods();
+    assert!(methods.iter().all(|method| method.starts_with("eth_get")));
+}
+
+#[test]
+fn failed_lookups_are_recorded() {
+    #[derive(Debug)]
+    struct Unreachable;
+
+    impl Transport for Unreachable {
+        fn send(&self, _body: &str) -> Result<String, RpcError> {
+            Err(RpcError::Io("connection refused".to_string()))
+        }
+    }
+
+    let fork = ForkDb::new(Unreachable, 1);
+    assert_eq!(fork.storage(U256::one(), U256::one()), U256::zero());
+    assert_eq!(
+        fork.error(),
+        Some(RpcError::Io("connection refused".to_string()))
+    );
+}
+
+#[test]
+fn node_errors_are_returned() {
+    let fork = ForkDb::new(CannedNode::default(), 1);
+    let error = fork.request("eth_chainId", json!([])).unwrap_err();
+    assert_eq!(
+        error,
+        RpcError::Node {
+            code: -32601,
+            message: "eth_chainId not found".to_string(),
+        }
+    );
+}
+
+#[test]
+fn cached_lookups_reach_the_node_once() {
+    let node = CannedNode::default();
+    let fork = ForkDb::new(node.clone(), 0x10).with_cache(StateCache::default());
+    let contract = hexutil::parse_u256(CONTRACT).unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(fork.storage(contract, U256::one()), U256::from(0x2a));
+        assert_eq!(fork.basic(contract).unwrap().nonce, 7);
+    }
+    assert_eq!(
+        node.methods(),
+        vec![
+            "eth_getStorageAt",
+            "eth_getBalance",
+            "eth_getTransactionCount"
+        ]
+    );
+    assert_eq!(fork.cache().unwrap().len(), 2);
+}
+
+#[test]
+fn saved_caches_run_offline() {
+    #[derive(Debug)]
+    struct Offline;
+
+    impl Transport for Offline {
+        fn send(&self, _body: &str) -> Result<String, RpcError> {
+            Err(RpcError::Io("offline".to_string()))
+        }
+    }
+
+    let contract = hexutil::parse_u256(CONTRACT).unwrap();
+    let online = ForkDb::new(CannedNode::default(), 0x10).with_cache(StateCache::default());
+    let code = online.code(contract).unwrap();
+    online.storage(contract, U256::one());
+    let path = std::env::temp_dir().join(format!("evm-fork-cache-{}.json", std::process::id()));
+    online.save_cache(&path).unwrap();
+
+    let cache = StateCache::load(&path, 1000).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": CONTRACT, "gas": "0xf4240" })).unwrap(),
+        ),
+        database: Some(Arc::new(ForkDb::new(Offline, 0x10).with_cache(cache))),
+        ..Default::default()
+    };
+    let result = evm(&code, &mut data, true);
+    assert_eq!(result.stack, vec![U256::from(0x2a)]);
+}
+
+#[test]
+fn failed_lookups_are_not_cached() {
+    #[derive(Debug)]
+    struct Flaky;
+
+    impl Transport for Flaky {
+        fn send(&self, _body: &str) -> Result<String, RpcError> {
+            Err(RpcError::HttpStatus(503))
+        }
+    }
+
+    let fork = ForkDb::new(Flaky, 1).with_cache(StateCache::default());
+    assert_eq!(fork.storage(U256::one(), U256::one()), U256::zero());
+    assert_eq!(fork.error(), Some(RpcError::HttpStatus(503)));
+    assert!(fork.cache().unwrap().is_empty());
+}
+
+#[test]
+fn http_transport_posts_to_the_node() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+        // Reads until the whole body announced by Content-Length is in
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                if body.len() >= length {
+                    break;
+                }
+            }
+        }
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":"0x0102"}"#;
+        write!(
+            stream,
+            "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let fork = ForkDb::http(&format!("http://127.0.0.1:{}/rpc", port), 5).unwrap();
+    assert_eq!(fork.fetch_code(U256::from(0xc0de)).unwrap(), vec![1, 2]);
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /rpc HTTP/1.0\r\n"));
+    assert!(request.contains(r#""method":"eth_getCode""#));
+}
+
+#[test]
+fn only_plain_http_is_built_in() {
+    assert_eq!(
+        HttpTransport::new("https://example.com"),
+        Err(RpcError::UnsupportedUrl("https://example.com".to_string()))
+    );
+    assert!(HttpTransport::new("http://localhost:8545").is_ok());
+}
@@ -0,0 +1,118 @@
+//! Loading geth genesis files.
+#![cfg(feature = "std")]
+
+use evm::genesis::{Genesis, GenesisError};
+use evm::host::Database;
+use evm::spec::SpecId;
+use evm::{evm, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn genesis() -> serde_json::Value {
+    json!({
+        "config": {
+            "chainId": 1337,
+            "homesteadBlock": 0,
+            "eip150Block": 0,
+            "eip155Block": 0,
+            "eip158Block": 0,
+            "byzantiumBlock": 0,
+            "constantinopleBlock": 0,
+            "petersburgBlock": 0,
+            "istanbulBlock": 0,
+            "berlinBlock": 0,
+            "londonBlock": 0,
+            "terminalTotalDifficulty": 0,
+            "terminalTotalDifficultyPassed": true,
+            "shanghaiTime": 0,
+            "cancunTime": 0,
+            "pragueTime": 2000000000u64,
+        },
+        "nonce": "0x0",
+        "timestamp": "0x65000000",
+        "gasLimit": "0x1c9c380",
+        "difficulty": "0x0",
+        "mixHash": "0x00000000000000000000000000000000000000000000000000000000000000aa",
+        "coinbase": "0x0000000000000000000000000000000000000000",
+        "baseFeePerGas": "0x3b9aca00",
+        "alloc": {
+            // geth writes addresses without a prefix and balances in decimal
+            "a11ce00000000000000000000000000000000000": { "balance": "1000000000000000000" },
+            "0x000000000000000000000000000000000000c0de": {
+                // CHAINID, BASEFEE, SLOAD(1), BALANCE(ALICE)
+                "code": "0x464860015473a11ce0000000000000000000000000000000000031",
+                "nonce": "0x1",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": "0x2a",
+                },
+            },
+            // The same slot of another account
+            "0x000000000000000000000000000000000000beef": {
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": "0x7",
+                },
+            },
+        },
+    })
+}
+
+#[test]
+fn the_spec_is_the_last_fork_active_at_genesis() {
+    let loaded = Genesis::from_json(&genesis()).unwrap();
+    assert_eq!(loaded.spec, SpecId::Cancun);
+    assert_eq!(loaded.chain_id, 1337);
+
+    let mut later = genesis();
+    later["config"]["pragueTime"] = json!(0);
+    assert_eq!(Genesis::from_json(&later).unwrap().spec, SpecId::Prague);
+
+    // Not merged: time forks wait for the merge
+    let mut work = genesis();
+    work["config"]["terminalTotalDifficulty"] = json!("0x100");
+    assert_eq!(Genesis::from_json(&work).unwrap().spec, SpecId::London);
+
+    let frontier = Genesis::from_json(&json!({ "config": {} })).unwrap();
+    assert_eq!(frontier.spec, SpecId::Frontier);
+    assert_eq!(frontier.chain_id, 1);
+}
+
+#[test]
+fn accounts_and_block_zero_are_loaded() {
+    let mut data = Genesis::from_json(&genesis()).unwrap().evm_data();
+    let contract = U256::from(0xc0de);
+    assert_eq!(data.basic(contract).unwrap().nonce, 1);
+    assert_eq!(data.storage(contract, U256::one()), U256::from(42));
+    assert_eq!(data.storage(U256::from(0xbeef), U256::one()), U256::from(7));
+
+    data.tx_data = Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap());
+    let code = data.code(contract).unwrap();
+    let result = evm(code, &mut data, true);
+    assert!(result.success);
+    assert_eq!(
+        result.stack,
+        vec![
+            U256::exp10(18),
+            U256::from(42),
+            U256::from(1_000_000_000),
+            U256::from(1337),
+        ]
+    );
+}
+
+#[test]
+fn malformed_genesis_files_are_errors() {
+    assert_eq!(
+        Genesis::from_json(&json!([])),
+        Err(GenesisError::Invalid("not an object".to_string()))
+    );
+    let mut balance = genesis();
+    balance["alloc"]["0x000000000000000000000000000000000000c0de"]["balance"] = json!("lots");
+    assert!(matches!(
+        Genesis::from_json(&balance),
+        Err(GenesisError::Invalid(reason)) if reason.contains("balance")
+    ));
+    assert!(matches!(
+        Genesis::load("no/such/genesis.json"),
+        Err(GenesisError::Io(_))
+    ));
+}
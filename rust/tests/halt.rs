@@ -0,0 +1,110 @@
+//! Why execution stopped, as `EvmResult::halt_reason` tells it.
+
+use evm::config::{EvmConfig, Preset};
+use evm::{evm, EvmData, HaltReason, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn run(code: &str, writable: bool) -> evm::EvmResult {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        evm_config: EvmConfig::preset(Preset::SpecExact),
+        ..Default::default()
+    };
+    evm(
+        hex::decode(code.replace(' ', "")).unwrap(),
+        &mut data,
+        writable,
+    )
+}
+
+fn reason(code: &str) -> Option<HaltReason> {
+    run(code, true).halt_reason
+}
+
+#[test]
+fn successful_halts() {
+    assert_eq!(reason("6001 00"), Some(HaltReason::Stop));
+    assert_eq!(reason("6001"), Some(HaltReason::Stop));
+    assert_eq!(reason("6000 6000 f3"), Some(HaltReason::Return));
+    assert_eq!(reason("6000 ff"), Some(HaltReason::SelfDestruct));
+}
+
+#[test]
+fn failed_halts() {
+    assert_eq!(reason("6000 6000 fd"), Some(HaltReason::Revert));
+    assert_eq!(reason("6003 56"), Some(HaltReason::InvalidJump));
+    assert_eq!(reason("80"), Some(HaltReason::StackUnderflow));
+    assert_eq!(reason("01"), Some(HaltReason::StackUnderflow));
+    assert_eq!(reason("6001 90"), Some(HaltReason::StackUnderflow));
+    assert_eq!(
+        reason(&"6001".repeat(1025)),
+        Some(HaltReason::StackOverflow)
+    );
+    assert_eq!(reason("fe"), Some(HaltReason::InvalidOpcode));
+    assert_eq!(reason("5b 6000 56"), Some(HaltReason::OutOfGas));
+    assert_eq!(
+        reason("6001 6000 6000 3e"),
+        Some(HaltReason::ReturnDataOutOfBounds)
+    );
+}
+
+#[test]
+fn state_changes_in_a_static_context_are_violations() {
+    // SSTORE, LOG0, SELFDESTRUCT and a CALL with value
+    for code in [
+        "6001 6000 55",
+        "6000 6000 a0",
+        "6000 ff",
+        "6000 6000 6000 6000 6001 6000 6000 f1",
+    ] {
+        let result = run(code, false);
+        assert!(!result.success, "{}", code);
+        assert_eq!(result.halt_reason, Some(HaltReason::StaticModeViolation));
+    }
+    // A CALL without value is no state change
+    assert!(run("6000 6000 6000 6000 6000 6000 6000 f1", false).success);
+}
+
+#[test]
+fn staticcalls_that_return_nothing_succeed() {
+    // STATICCALL(gas, 0xbeef, 0, 0, 0, 0) to an account without code
+    let result = run("6000 808080 61beef 5a fa", true);
+    assert_eq!(result.stack, vec![U256::one()]);
+
+    // and to one whose code only stops
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        ..Default::default()
+    };
+    data.codes.insert(0xbeef.to_string(), "00".parse().unwrap());
+    let result = evm(
+        hex::decode("600080808061beef5afa").unwrap(),
+        &mut data,
+        true,
+    );
+    assert_eq!(result.stack, vec![U256::one()]);
+}
+
+#[test]
+fn unknown_opcodes_use_up_the_gas_of_the_frame() {
+    let result = run("6001 0c 6002", true);
+    assert!(!result.success);
+    assert_eq!(result.halt_reason, Some(HaltReason::InvalidOpcode));
+    assert_eq!(result.gas_used, 0x186a0);
+}
+
+#[test]
+fn the_teaching_preset_skips_unknown_opcodes() {
+    let mut data = EvmData {
+        evm_config: EvmConfig::preset(Preset::Teaching),
+        ..Default::default()
+    };
+    let result = evm(hex::decode("60010c6002").unwrap(), &mut data, true);
+    assert!(result.success);
+    assert_eq!(result.stack.len(), 2);
+}
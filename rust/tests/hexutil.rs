@@ -0,0 +1,81 @@
+//! Hex inputs read the same with or without a `0x` prefix, and are checked
+//! against the size of what they hold.
+
+use evm::env::Env;
+use evm::hexutil::{self, Bytes, HexError};
+use evm::{EvmContext, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+#[test]
+fn prefixed_and_unprefixed_forms_are_equal() {
+    assert_eq!(hexutil::parse_u256("0xc0de"), hexutil::parse_u256("c0de"));
+    assert_eq!(hexutil::parse_u256("0XC0DE"), Ok(U256::from(0xc0de)));
+    assert_eq!(hexutil::parse_u64("0x"), Ok(0));
+    assert_eq!(hexutil::decode("0xabc"), Ok(vec![0x0a, 0xbc]));
+
+    let prefixed: TxData = serde_json::from_value(json!({
+        "to": "0xc0de",
+        "data": "0x0102",
+        "gas": "0x5208",
+        "value": "0x1",
+    }))
+    .unwrap();
+    let unprefixed: TxData = serde_json::from_value(json!({
+        "to": "c0de",
+        "data": "0102",
+        "gas": "5208",
+        "value": "1",
+    }))
+    .unwrap();
+    let written = serde_json::to_value(&prefixed).unwrap();
+    assert_eq!(written, serde_json::to_value(&unprefixed).unwrap());
+    assert_eq!(
+        written["to"],
+        json!("0x000000000000000000000000000000000000c0de")
+    );
+    assert_eq!(written["data"], json!("0x0102"));
+    assert_eq!(written["gas"], json!("0x5208"));
+    assert_eq!(Env::new(None, Some(&prefixed)).tx.value, U256::one());
+}
+
+#[test]
+fn inputs_longer_than_their_type_are_rejected() {
+    let address = format!("0x1{:040x}", 0);
+    assert_eq!(
+        hexutil::parse_address(&address),
+        Err(HexError::TooLong {
+            digits: 41,
+            max: 40
+        })
+    );
+    assert!(serde_json::from_value::<TxData>(json!({ "to": address })).is_err());
+    assert!(serde_json::from_value::<TxData>(json!({ "gas": "0x10000000000000000" })).is_err());
+    assert!(
+        serde_json::from_value::<EvmContext>(json!({ "number": format!("0x1{:064x}", 0) }))
+            .is_err()
+    );
+}
+
+#[test]
+fn invalid_digits_are_errors_not_panics() {
+    assert_eq!(
+        hexutil::decode("0x12z4"),
+        Err(HexError::InvalidDigit {
+            position: 2,
+            found: 'z'
+        })
+    );
+    let error = serde_json::from_value::<TxData>(json!({ "data": "0xnope" })).unwrap_err();
+    assert!(error.to_string().contains("invalid hex digit"));
+}
+
+#[test]
+fn bytes_round_trip_as_prefixed_hex() {
+    let bytes: Bytes = "00ff".parse().unwrap();
+    assert_eq!(bytes.len(), 2);
+    assert_eq!(bytes.to_string(), "0x00ff");
+    assert_eq!(serde_json::to_value(&bytes).unwrap(), json!("0x00ff"));
+    let back: Bytes = serde_json::from_value(json!("0x00ff")).unwrap();
+    assert_eq!(back, bytes);
+}
@@ -0,0 +1,150 @@
+//! Reading state through the `Database` trait, from the maps of `EvmData`
+//! and from a backing store behind them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use evm::host::{AccountInfo, Database};
+use evm::{evm, hexutil, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const CONTRACT: u64 = 0xc0de;
+const ALICE: u64 = 0xa11ce;
+
+#[derive(Debug, Default)]
+struct StaticDb {
+    accounts: HashMap<U256, AccountInfo>,
+    code: HashMap<U256, Vec<u8>>,
+    storage: HashMap<(U256, U256), U256>,
+}
+
+impl Database for StaticDb {
+    fn basic(&self, address: U256) -> Option<AccountInfo> {
+        self.accounts.get(&address).copied()
+    }
+
+    fn code(&self, address: U256) -> Option<Vec<u8>> {
+        self.code.get(&address).cloned()
+    }
+
+    fn storage(&self, address: U256, key: U256) -> U256 {
+        self.storage
+            .get(&(address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+fn db() -> StaticDb {
+    let mut db = StaticDb::default();
+    db.accounts.insert(
+        U256::from(ALICE),
+        AccountInfo {
+            balance: U256::from(1000),
+            nonce: 3,
+        },
+    );
+    db.storage
+        .insert((U256::from(CONTRACT), U256::one()), U256::from(0x2a));
+    db
+}
+
+fn data(database: StaticDb) -> EvmData {
+    EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({
+                "from": format!("{:#x}", ALICE),
+                "to": format!("{:#x}", CONTRACT),
+                "gas": "0xf4240",
+            }))
+            .unwrap(),
+        ),
+        database: Some(Arc::new(database)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reads_fall_back_to_the_database() {
+    let mut data = data(db());
+    // SLOAD(1), BALANCE(alice)
+    let code = hexutil::decode(&format!("60015462{:06x}31", ALICE)).unwrap();
+    let result = evm(code, &mut data, true);
+
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::from(1000), U256::from(0x2a)]);
+}
+
+#[test]
+fn writes_shadow_the_database() {
+    let mut data = data(db());
+    // SSTORE(1, 0), SLOAD(1)
+    let code = hexutil::decode("6000600155600154").unwrap();
+    let result = evm(code, &mut data, true);
+
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::zero()]);
+    assert_eq!(
+        data.storage(U256::from(CONTRACT), U256::one()),
+        U256::zero()
+    );
+    let database = data.database.as_ref().unwrap();
+    assert_eq!(
+        database.storage(U256::from(CONTRACT), U256::one()),
+        U256::from(0x2a)
+    );
+}
+
+#[test]
+fn maps_take_precedence_per_field() {
+    let mut data = data(db());
+    data.balances.insert(ALICE.to_string(), U256::from(5));
+
+    assert_eq!(
+        data.basic(U256::from(ALICE)),
+        Some(AccountInfo {
+            balance: U256::from(5),
+            nonce: 3,
+        })
+    );
+    assert_eq!(data.basic(U256::from(0xb0b)), None);
+}
+
+#[test]
+fn calls_run_code_from_the_database() {
+    let callee = U256::from(0xca11);
+    let mut database = db();
+    // Returns 0x42 as a single byte
+    database
+        .code
+        .insert(callee, hexutil::decode("60426000526001601ff3").unwrap());
+    let mut data = data(database);
+
+    // CALL(gas, callee, 0, 0, 0, 0, 1), MLOAD(0)
+    let code = hexutil::decode("6001600060006000600061ca115af1600051").unwrap();
+    let result = evm(code, &mut data, true);
+
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::from(0x42) << 248, U256::one()]);
+}
+
+#[test]
+fn storage_is_kept_per_account() {
+    let callee = U256::from(0xca11);
+    let mut data = data(db());
+    // SSTORE(1, 7)
+    data.set_code(callee, &hexutil::decode("6007600155").unwrap());
+
+    // CALL(gas, callee, 0, 0, 0, 0, 0), SLOAD(1)
+    let code = hexutil::decode("6000600060006000600061ca115af1600154").unwrap();
+    let result = evm(code, &mut data, true);
+
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::from(0x2a), U256::one()]);
+    assert_eq!(data.storage(callee, U256::one()), U256::from(7));
+    assert_eq!(
+        data.storage(U256::from(CONTRACT), U256::one()),
+        U256::from(0x2a)
+    );
+}
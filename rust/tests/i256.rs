@@ -0,0 +1,67 @@
+//! `I256` against `i128`, and at the edges only 256 bits reach.
+
+use evm::i256::I256;
+use primitive_types::U256;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn arithmetic_matches_i128(a in -(1i128 << 126)..(1i128 << 126), b: i128) {
+        prop_assert_eq!(-I256::from(a), I256::from(-a));
+        if b != 0 {
+            prop_assert_eq!(I256::from(a) / I256::from(b), I256::from(a / b));
+            prop_assert_eq!(I256::from(a) % I256::from(b), I256::from(a % b));
+        }
+    }
+
+    #[test]
+    fn order_matches_i128(a: i128, b: i128) {
+        prop_assert_eq!(I256::from(a).cmp(&I256::from(b)), a.cmp(&b));
+    }
+
+    #[test]
+    fn display_matches_i128(a: i128) {
+        prop_assert_eq!(I256::from(a).to_string(), a.to_string());
+    }
+}
+
+#[test]
+fn two_complement_round_trips() {
+    assert_eq!(I256::from_two_complement(U256::MAX), I256::MINUS_ONE);
+    assert_eq!(I256::MIN.to_two_complement(), U256::one() << 255);
+    assert_eq!(U256::from(I256::from(-2)), U256::MAX - 1);
+    assert!(I256::MIN.is_negative());
+    assert!(!I256::MAX.is_negative());
+}
+
+#[test]
+fn overflows_wrap_as_the_opcodes_do() {
+    assert_eq!(-I256::MIN, I256::MIN);
+    assert_eq!(I256::MIN / I256::MINUS_ONE, I256::MIN);
+    assert_eq!(I256::MIN % I256::MINUS_ONE, I256::ZERO);
+    assert_eq!(I256::MIN.unsigned_abs(), U256::one() << 255);
+}
+
+#[test]
+fn division_by_zero_is_zero() {
+    assert_eq!(I256::from(-7) / I256::ZERO, I256::ZERO);
+    assert_eq!(I256::from(-7) % I256::ZERO, I256::ZERO);
+}
+
+#[test]
+fn shifts_keep_the_sign() {
+    assert_eq!(I256::from(-8) >> U256::from(2), I256::from(-2));
+    assert_eq!(I256::from(8) >> U256::from(2), I256::from(2));
+    assert_eq!(I256::MIN >> U256::from(255), I256::MINUS_ONE);
+    assert_eq!(I256::MIN >> U256::from(1000), I256::MINUS_ONE);
+    assert_eq!(I256::MAX >> U256::from(256), I256::ZERO);
+    assert_eq!(I256::from(-5) >> U256::zero(), I256::from(-5));
+}
+
+#[test]
+fn min_and_max_bound_the_order() {
+    assert!(I256::MIN < I256::MINUS_ONE);
+    assert!(I256::MINUS_ONE < I256::ZERO);
+    assert!(I256::ZERO < I256::MAX);
+    assert_eq!(I256::MIN.to_string(), format!("-{}", U256::one() << 255));
+}
@@ -0,0 +1,140 @@
+//! Call, create and log events reported to inspectors.
+
+use evm::inspector::{CallFrame, CreateFrame, Inspector};
+use evm::{evm_with_inspector, EvmData, EvmResult, Log, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+}
+
+impl Inspector for Recorder {
+    fn log(&mut self, log: &Log) {
+        self.events.push(format!("log {:?}", log.topics));
+    }
+
+    fn call_start(&mut self, call: &CallFrame) {
+        self.events.push(format!(
+            "call {:?} {} {:#x} -> {:#x}",
+            call.kind, call.depth, call.caller, call.address
+        ));
+    }
+
+    fn call_end(&mut self, call: &CallFrame, result: &EvmResult) {
+        self.events
+            .push(format!("end {} {}", call.depth, result.success));
+    }
+
+    fn create_start(&mut self, create: &CreateFrame) {
+        self.events.push(format!(
+            "create {} {} bytes",
+            create.depth,
+            create.init_code.len()
+        ));
+    }
+
+    fn create_end(&mut self, create: &CreateFrame, result: &EvmResult) {
+        self.events.push(format!(
+            "created {:#x} {} {}",
+            create.address, result.success, result.gas_used
+        ));
+    }
+}
+
+fn run(code: &str, data: &mut EvmData) -> (EvmResult, Vec<String>) {
+    data.tx_data = Some(
+        serde_json::from_value::<TxData>(json!({
+            "from": "0xa11ce",
+            "to": "0xc0de",
+            "gas": "0xf4240",
+        }))
+        .unwrap(),
+    );
+    let mut recorder = Recorder::default();
+    let code = hex::decode(code.replace(' ', "")).unwrap();
+    let result = evm_with_inspector(code, data, true, &mut recorder);
+    (result, recorder.events)
+}
+
+#[test]
+fn calls_nest_inside_the_transaction() {
+    let mut data = EvmData::default();
+    // Callee: PUSH1 1, POP
+    data.codes
+        .insert("170".to_string(), "600150".parse().unwrap());
+
+    // CALL(gas, 0xaa, 0, 0, 0, 0, 0)
+    let (_, events) = run("600080808080 60aa 5a f1", &mut data);
+    assert_eq!(
+        events,
+        vec![
+            "call Call 1 0xa11ce -> 0xc0de",
+            "call Call 2 0xc0de -> 0xaa",
+            "end 2 true",
+            "end 1 true",
+        ]
+    );
+}
+
+#[test]
+fn logs_are_reported_even_when_rolled_back() {
+    // LOG1(0, 0, 0x42), then REVERT(0, 0)
+    let (result, events) = run("6042 6000 6000 a1 6000 6000 fd", &mut EvmData::default());
+    assert!(result.logs.is_empty());
+    assert_eq!(events[1], "log [66]");
+    assert_eq!(events[2], "end 1 false");
+}
+
+#[test]
+fn creates_report_their_address_and_deposit() {
+    // Init code returning the single byte 0x42
+    let init_code = "604260005360016000f3";
+    // MSTORE(0, init code), CREATE(0, 22, 10)
+    let code = format!("69 {} 6000 52 600a 6016 6000 f0", init_code);
+    let (result, events) = run(&code, &mut EvmData::default());
+    let address = result.stack[0];
+
+    assert_eq!(events[1], "create 2 10 bytes");
+    // 18 gas of init code and 200 for the byte of code stored
+    assert_eq!(events[2], format!("created {:#x} true 218", address));
+    assert_ne!(address, U256::zero());
+}
+
+#[test]
+fn calls_past_the_depth_limit_fail_without_running() {
+    let mut data = EvmData::default();
+    // CALL(gas, ADDRESS, 0, 0, 0, 0, 0): the contract calls itself forever
+    let recursive = "600080808080305af1";
+    data.codes
+        .insert("49374".to_string(), recursive.parse().unwrap());
+
+    // Enough gas for 63/64 of it to be passed down to the depth limit
+    data.tx_data = Some(
+        serde_json::from_value::<TxData>(json!({
+            "from": "0xa11ce",
+            "to": "0xc0de",
+            "gas": "0x100000000000",
+        }))
+        .unwrap(),
+    );
+
+    let runner = std::thread::Builder::new().stack_size(evm::EXECUTION_STACK_SIZE);
+    let (result, events) = runner
+        .spawn(move || {
+            let mut recorder = Recorder::default();
+            let code = hex::decode(recursive).unwrap();
+            let result = evm_with_inspector(code, &mut data, true, &mut recorder);
+            (result, recorder.events)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(result.success);
+    let starts = events.iter().filter(|e| e.starts_with("call ")).count();
+    assert_eq!(starts, evm::CALL_DEPTH_LIMIT + 1);
+    // The deepest frame's own call fails, and it goes on to succeed
+    let deepest = evm::CALL_DEPTH_LIMIT + 1;
+    assert_eq!(events[starts], format!("end {deepest} true"));
+}
@@ -0,0 +1,95 @@
+//! Opcode handlers registered in place of the interpreter's.
+
+use evm::builder::Evm;
+use evm::config::{EvmConfig, Preset};
+use evm::env::TxEnv;
+use evm::instruction::{Control, InstructionContext};
+use evm::spec::SpecId;
+use evm::HaltReason;
+use primitive_types::U256;
+
+// Pushes the 2-byte immediate after it, times two, for 5 gas
+fn push2_double(context: &mut InstructionContext) -> Control {
+    if !context.gas.charge(5) {
+        return Control::Halt(HaltReason::OutOfGas);
+    }
+    let immediate = &context.code[context.pc..context.pc + 2];
+    let value = U256::from_big_endian(immediate) * 2;
+    context.pc += 2;
+    match context.stack.push(value) {
+        Ok(()) => Control::Continue,
+        Err(error) => Control::Halt(HaltReason::from(error)),
+    }
+}
+
+fn subtract(context: &mut InstructionContext) -> Control {
+    let (Ok(a), Ok(b)) = (context.stack.pop(), context.stack.pop()) else {
+        return Control::Halt(HaltReason::StackUnderflow);
+    };
+    context.stack.push(a - b).unwrap();
+    Control::Continue
+}
+
+fn stop(_: &mut InstructionContext) -> Control {
+    Control::Stop
+}
+
+fn evm(code: &str) -> evm::builder::EvmBuilder {
+    Evm::builder()
+        .with_spec(SpecId::Cancun)
+        .with_config(EvmConfig::preset(Preset::SpecExact))
+        .with_tx(TxEnv {
+            address: U256::from(0xc0de),
+            ..Default::default()
+        })
+        .with_gas_limit(100_000)
+        .with_code(hex::decode(code).unwrap())
+}
+
+#[test]
+fn unused_opcodes_can_be_given_a_handler() {
+    // 0xf6 0x0102, PUSH1 1
+    let result = evm("f601026001")
+        .with_instruction(0xf6, push2_double)
+        .build()
+        .transact();
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::one(), U256::from(0x0204)]);
+    // The transaction, the handler and PUSH1
+    assert_eq!(result.gas_used, 21000 + 5 + 3);
+
+    let result = evm("f601026001").build().transact();
+    assert_eq!(result.halt_reason, Some(HaltReason::InvalidOpcode));
+}
+
+#[test]
+fn handlers_override_the_interpreter() {
+    // PUSH1 2 PUSH1 5 ADD
+    let result = evm("6002600501")
+        .with_instruction(0x01, subtract)
+        .build()
+        .transact();
+    assert_eq!(result.stack, vec![U256::from(3)]);
+    // Without its static cost
+    assert_eq!(result.gas_used, 21000 + 3 + 3);
+}
+
+#[test]
+fn handlers_end_and_fail_the_frame() {
+    // 0xf6 PUSH1 1
+    let result = evm("f66001")
+        .with_instruction(0xf6, stop)
+        .build()
+        .transact();
+    assert!(result.success);
+    assert!(result.stack.is_empty());
+
+    // ADD on an empty stack
+    let result = evm("01")
+        .with_instruction(0x01, subtract)
+        .build()
+        .transact();
+    assert!(!result.success);
+    assert_eq!(result.halt_reason, Some(HaltReason::StackUnderflow));
+    assert_eq!(result.gas_used, 100_000);
+}
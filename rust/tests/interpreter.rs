@@ -0,0 +1,108 @@
+//! Stepping through an execution with `Interpreter`.
+#![cfg(feature = "std")]
+
+use evm::config::{EvmConfig, Preset};
+use evm::host::Database;
+use evm::interpreter::{Interpreter, StepResult};
+use evm::{EvmData, HaltReason, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn data() -> EvmData {
+    EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn steps_stop_before_each_instruction() {
+    // PUSH1 2, PUSH1 3, ADD, PUSH1 0, MSTORE
+    let mut interpreter = Interpreter::new(hex::decode("6002600301600052").unwrap(), data());
+
+    let StepResult::Step(first) = interpreter.step() else {
+        panic!("no first step")
+    };
+    assert_eq!((first.pc, first.opcode, first.depth), (0, 0x60, 1));
+    assert!(first.stack.is_empty());
+    assert_eq!(first.address, U256::from(0xc0de));
+
+    interpreter.step();
+    let StepResult::Step(add) = interpreter.step() else {
+        panic!("no ADD")
+    };
+    assert_eq!(add.opcode, 0x01);
+    assert_eq!(add.stack, vec![U256::from(2), U256::from(3)]);
+    assert_eq!(add.gas_used, 6);
+
+    interpreter.step();
+    let StepResult::Step(mstore) = interpreter.step() else {
+        panic!("no MSTORE")
+    };
+    assert!(mstore.memory.is_empty());
+
+    let StepResult::Done(result) = interpreter.step() else {
+        panic!("not done")
+    };
+    assert!(result.success);
+    assert_eq!(result.memory[31], 5);
+    // Done stays done
+    assert!(matches!(interpreter.step(), StepResult::Done(_)));
+}
+
+#[test]
+fn iterates_over_the_steps() {
+    let mut interpreter = Interpreter::new(hex::decode("6001600101").unwrap(), data());
+    let pcs: Vec<usize> = interpreter.by_ref().map(|state| state.pc).collect();
+    assert_eq!(pcs, vec![0, 2, 4]);
+    assert_eq!(interpreter.result().unwrap().stack, vec![U256::from(2)]);
+}
+
+#[test]
+fn nested_frames_are_stepped_too() {
+    let mut data = data();
+    // 0xc0de: PUSH1 1, STOP
+    data.codes
+        .insert("49374".to_string(), "600100".parse().unwrap());
+    let call = hex::decode("6000600060006000600061c0de61fffff1").unwrap();
+    let depths: Vec<usize> = Interpreter::new(call, data)
+        .map(|state| state.depth)
+        .collect();
+    assert_eq!(depths.iter().filter(|&&depth| depth == 2).count(), 2);
+}
+
+#[test]
+fn finish_hands_back_the_state() {
+    // SSTORE(1, 7)
+    let interpreter = Interpreter::new(hex::decode("6007600155").unwrap(), data());
+    assert!(interpreter.into_data().is_none());
+
+    let mut interpreter = Interpreter::new(hex::decode("6007600155").unwrap(), data());
+    assert!(interpreter.finish().success);
+    let data = interpreter.into_data().unwrap();
+    assert_eq!(data.storage(U256::from(0xc0de), U256::one()), U256::from(7));
+}
+
+#[test]
+fn static_runs_cannot_write() {
+    let mut interpreter =
+        Interpreter::new(hex::decode("6007600155").unwrap(), data()).with_writable(false);
+    let result = interpreter.finish();
+    assert_eq!(result.halt_reason, Some(HaltReason::StaticModeViolation));
+}
+
+#[test]
+fn dropping_midway_abandons_an_endless_run() {
+    let data = EvmData {
+        evm_config: EvmConfig::preset(Preset::Teaching),
+        ..data()
+    };
+    // JUMPDEST, PUSH1 0, JUMP forever
+    let mut interpreter = Interpreter::new(hex::decode("5b600056").unwrap(), data);
+    for _ in 0..100 {
+        assert!(matches!(interpreter.step(), StepResult::Step(_)));
+    }
+    drop(interpreter);
+}
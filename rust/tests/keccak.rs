@@ -0,0 +1,85 @@
+//! The memoized Keccak-256 behind SHA3, EXTCODEHASH and CREATE2.
+
+use evm::keccak::KeccakCache;
+use evm::trie::keccak;
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+#[test]
+fn repeated_preimages_hit() {
+    let mut cache = KeccakCache::new(8);
+    assert_eq!(cache.hash(b"abc"), keccak(b"abc"));
+    assert_eq!(cache.hash(b"abc"), keccak(b"abc"));
+    assert_eq!(cache.hash(b""), keccak(b""));
+    assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn least_recently_used_is_evicted() {
+    let mut cache = KeccakCache::new(2);
+    cache.hash(b"a");
+    cache.hash(b"b");
+    cache.hash(b"a");
+    cache.hash(b"c");
+    assert_eq!(cache.len(), 2);
+    cache.hash(b"a");
+    assert_eq!(cache.hits(), 2);
+    cache.hash(b"b");
+    assert_eq!(cache.misses(), 4);
+}
+
+fn data() -> EvmData {
+    EvmData {
+        tx_data: Some(serde_json::from_value::<TxData>(json!({ "to": "0x0" })).unwrap()),
+        keccak_cache: Some(KeccakCache::default()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sha3_of_a_mapping_slot_is_memoized() {
+    let mut data = data();
+    // SHA3(0, 64) twice
+    let result = evm(
+        hex::decode("60406000206040600020").unwrap(),
+        &mut data,
+        true,
+    );
+    assert!(result.success);
+    assert_eq!(result.stack[0], result.stack[1]);
+    assert_eq!(
+        result.stack[0],
+        U256::from_big_endian(keccak(&[0; 64]).as_bytes())
+    );
+    let cache = data.keccak_cache.unwrap();
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+}
+
+#[test]
+fn create2_matches_eip_1014() {
+    // Sender 0, salt 0 and init code 0x00, the first example of EIP-1014
+    let mut data = data();
+    let result = evm(
+        hex::decode("60006000536000600160006000f5").unwrap(),
+        &mut data,
+        true,
+    );
+    assert!(result.success);
+    assert_eq!(
+        result.stack[0],
+        U256::from_str_radix("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38", 16).unwrap()
+    );
+    // Without a cache the hash is the same
+    let mut data = EvmData {
+        keccak_cache: None,
+        ..self::data()
+    };
+    let again = evm(
+        hex::decode("60006000536000600160006000f5").unwrap(),
+        &mut data,
+        true,
+    );
+    assert_eq!(again.stack, result.stack);
+}
@@ -0,0 +1,112 @@
+//! Instruction-count and wall-clock limits, which stop code that gas does
+//! not.
+#![cfg(feature = "std")]
+
+use std::time::{Duration, Instant};
+
+use evm::config::{EvmConfig, Preset};
+use evm::service::{EvmService, Request, ServiceLimits};
+use evm::{evm, EvmData, HaltReason, TxData};
+use serde_json::json;
+
+// JUMPDEST, PUSH1 0, JUMP: loops forever without gas
+const INFINITE_LOOP: &str = "5b600056";
+
+fn unmetered(config: EvmConfig) -> EvmData {
+    EvmData {
+        tx_data: Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap()),
+        evm_config: config,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn instruction_limit_stops_an_infinite_loop() {
+    let mut data = unmetered(EvmConfig {
+        max_instructions: Some(3000),
+        ..EvmConfig::preset(Preset::Teaching)
+    });
+    let result = evm(hex::decode(INFINITE_LOOP).unwrap(), &mut data, true);
+    assert!(!result.success);
+    assert_eq!(result.halt_reason, Some(HaltReason::InstructionLimit));
+}
+
+#[test]
+fn instruction_limit_counts_every_instruction() {
+    let config = |max| EvmConfig {
+        max_instructions: Some(max),
+        ..EvmConfig::default()
+    };
+    // Three instructions then STOP
+    let code = hex::decode("600160020100").unwrap();
+    assert!(evm(&code, &mut unmetered(config(4)), true).success);
+    let result = evm(&code, &mut unmetered(config(3)), true);
+    assert_eq!(result.halt_reason, Some(HaltReason::InstructionLimit));
+}
+
+#[test]
+fn time_limit_stops_an_infinite_loop() {
+    let mut data = unmetered(EvmConfig {
+        max_duration: Some(Duration::from_millis(50)),
+        ..EvmConfig::preset(Preset::Teaching)
+    });
+    let started = Instant::now();
+    let result = evm(hex::decode(INFINITE_LOOP).unwrap(), &mut data, true);
+    assert_eq!(result.halt_reason, Some(HaltReason::TimeLimit));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn limits_hold_across_nested_frames() {
+    let mut data = unmetered(EvmConfig {
+        max_instructions: Some(10_000),
+        ..EvmConfig::preset(Preset::Teaching)
+    });
+    // 0xc0de loops forever, the transaction calls it
+    data.codes
+        .insert("49374".to_string(), INFINITE_LOOP.parse().unwrap());
+    let call = "6000600060006000600061c0de5af1";
+    let result = evm(hex::decode(call).unwrap(), &mut data, true);
+    assert!(!result.success);
+    assert_eq!(result.halt_reason, Some(HaltReason::InstructionLimit));
+}
+
+#[test]
+fn service_limits_apply_to_requests() {
+    let service = EvmService::new(EvmData::default()).with_limits(ServiceLimits {
+        max_instructions: Some(100),
+        ..ServiceLimits::default()
+    });
+    let result = service
+        .simulate(Request {
+            code: hex::decode(INFINITE_LOOP).unwrap(),
+            ..Request::default()
+        })
+        .unwrap();
+    assert_eq!(result.halt_reason, Some(HaltReason::InstructionLimit));
+}
+
+#[test]
+fn service_requests_survive_calls_to_the_depth_limit() {
+    // CALL(gas, ADDRESS, 0, 0, 0, 0, 0): the contract calls itself forever
+    let recursive = "600080808080305af1";
+    let mut state = EvmData::default();
+    state
+        .codes
+        .insert("0".to_string(), recursive.parse().unwrap());
+    let service = EvmService::new(state);
+
+    let runner = std::thread::Builder::new().stack_size(evm::EXECUTION_STACK_SIZE);
+    let result = runner
+        .spawn(move || {
+            service.simulate(Request {
+                code: hex::decode(recursive).unwrap(),
+                ..Request::default()
+            })
+        })
+        .unwrap()
+        .join()
+        .unwrap()
+        .unwrap();
+    assert!(result.success);
+}
@@ -0,0 +1,156 @@
+//! Memoized calls, handed back while what they read is unchanged.
+#![cfg(feature = "std")]
+
+use std::collections::HashMap;
+
+use evm::call::{CallRequest, StateOverrides};
+use evm::memo::{CallCache, CallKey, Read, ReadSet};
+use evm::service::EvmService;
+use evm::{evm_with_inspector, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+// Returns SLOAD(1) + BALANCE(CALLER)
+const SUMS: &str = "600154333101 60005260206000f3";
+// Stores 7 in slot 1 and returns SLOAD(1)
+const STORES: &str = "6007600155 600154 60005260206000f3";
+
+fn code(text: &str) -> Vec<u8> {
+    hex::decode(text.replace(' ', "")).unwrap()
+}
+
+fn data(input: &str) -> EvmData {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({
+                "from": "0xa11ce",
+                "to": "0xc0de",
+                "data": input,
+                "gas": "0x100000",
+            }))
+            .unwrap(),
+        ),
+        ..Default::default()
+    };
+    data.codes
+        .insert(0xc0de.to_string(), SUMS.replace(' ', "").parse().unwrap());
+    data.state.insert(
+        0xc0de.to_string(),
+        HashMap::from([(U256::one(), U256::from(2))]),
+    );
+    data.balances.insert(0xa11ce.to_string(), U256::from(5));
+    data
+}
+
+fn returned(value: Option<Vec<u8>>) -> U256 {
+    U256::from(&value.unwrap()[..])
+}
+
+#[test]
+fn calls_are_handed_back_while_what_they_read_holds() {
+    let mut cache = CallCache::new(8);
+    let first = cache.run(&code(SUMS), &mut data("0x"), false);
+    assert_eq!(returned(first.value.clone()), U256::from(7));
+    assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+    let again = cache.run(&code(SUMS), &mut data("0x"), false);
+    assert_eq!(again.value, first.value);
+    assert_eq!(again.gas_used, first.gas_used);
+    assert_eq!(cache.hits(), 1);
+
+    // Slots it did not read make no difference, the same slot of another
+    // account included
+    let mut unrelated = data("0x");
+    unrelated.state.insert(
+        0xbeef.to_string(),
+        HashMap::from([(U256::one(), U256::from(9))]),
+    );
+    cache.run(&code(SUMS), &mut unrelated, false);
+    assert_eq!(cache.hits(), 2);
+
+    // Other input is another call
+    cache.run(&code(SUMS), &mut data("0x01"), false);
+    assert_eq!((cache.hits(), cache.len()), (2, 2));
+
+    // A slot it read changed
+    let mut changed = data("0x");
+    changed.state.insert(
+        0xc0de.to_string(),
+        HashMap::from([(U256::one(), U256::from(3))]),
+    );
+    let result = cache.run(&code(SUMS), &mut changed, false);
+    assert_eq!(returned(result.value), U256::from(8));
+    assert_eq!(cache.hits(), 2);
+
+    // So did a balance
+    let mut changed = data("0x");
+    changed.balances.insert(0xa11ce.to_string(), U256::from(6));
+    let result = cache.run(&code(SUMS), &mut changed, false);
+    assert_eq!(returned(result.value), U256::from(8));
+    assert_eq!(cache.hits(), 2);
+}
+
+#[test]
+fn reads_are_of_the_state_before_the_call() {
+    let mut data = data("0x");
+    let mut reads = ReadSet::new(&data);
+    evm_with_inspector(code(STORES), &mut data, true, &mut reads);
+    assert!(reads.reads().contains(&Read::Slot {
+        address: U256::from(0xc0de),
+        key: U256::one(),
+        value: U256::from(2),
+    }));
+    // The recipient, whose code runs
+    assert!(matches!(
+        reads.reads()[0],
+        Read::Account { address, .. } if address == U256::from(0xc0de)
+    ));
+}
+
+#[test]
+fn runs_with_cheatcodes_are_not_cached() {
+    let mut data = data("0x");
+    data.evm_config.cheatcodes = true;
+    assert_eq!(CallKey::new(&code(SUMS), &data, false), None);
+    let mut cache = CallCache::new(8);
+    cache.run(&code(SUMS), &mut data, false);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn least_recently_used_calls_are_evicted() {
+    let mut cache = CallCache::new(2);
+    for input in ["0x01", "0x02", "0x01", "0x03"] {
+        cache.run(&code(SUMS), &mut data(input), false);
+    }
+    assert_eq!(cache.len(), 2);
+    cache.run(&code(SUMS), &mut data("0x01"), false);
+    cache.run(&code(SUMS), &mut data("0x02"), false);
+    assert_eq!(cache.hits(), 2);
+    assert_eq!(cache.misses(), 4);
+}
+
+#[test]
+fn services_answer_repeated_calls_from_the_cache() {
+    let service = EvmService::new(data("0x")).with_call_cache(16);
+    let call: CallRequest =
+        serde_json::from_value(json!({ "from": "0xa11ce", "to": "0xc0de" })).unwrap();
+    let first = service.call(call.clone(), &StateOverrides::new()).unwrap();
+    let again = service.call(call.clone(), &StateOverrides::new()).unwrap();
+    assert_eq!(again.value, first.value);
+    assert_eq!(service.metrics().cached, 1);
+    assert_eq!(service.metrics().requests, 2);
+
+    // Overrides are state like any other
+    let overrides: StateOverrides =
+        serde_json::from_value(json!({ "0xc0de": { "stateDiff": { "0x1": "0x4" } } })).unwrap();
+    let result = service.call(call.clone(), &overrides).unwrap();
+    assert_eq!(returned(result.value), U256::from(9));
+    assert_eq!(service.metrics().cached, 1);
+    // And one that replaces the storage of the account
+    let overrides: StateOverrides =
+        serde_json::from_value(json!({ "0xc0de": { "state": {} } })).unwrap();
+    let result = service.call(call, &overrides).unwrap();
+    assert_eq!(returned(result.value), U256::from(5));
+    assert_eq!(service.metrics().cached, 1);
+}
@@ -0,0 +1,141 @@
+//! One memory buffer shared by the frames of a transaction.
+
+use evm::config::{EvmConfig, Preset};
+use evm::{evm, EvmData, EvmMemory, EvmResult, HaltReason, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+#[test]
+fn nested_frame_starts_empty_and_gives_its_space_back() {
+    let mut memory = EvmMemory::new();
+    memory.enter_frame();
+    memory.write_u8(0, 0xaa);
+    assert_eq!(memory.size, 32);
+
+    memory.enter_frame();
+    assert_eq!(memory.size, 0);
+    assert!(memory.data().is_empty());
+    assert_eq!(memory.read_u8s(0, 1), vec![0]);
+    memory.write_u8(40, 0xbb);
+    assert_eq!(memory.size, 64);
+    memory.exit_frame();
+
+    assert_eq!(memory.size, 32);
+    assert_eq!(memory.data().len(), 32);
+    assert_eq!(memory.data()[0], 0xaa);
+    memory.exit_frame();
+}
+
+#[test]
+fn memory_given_back_is_zero_when_reused() {
+    let mut memory = EvmMemory::new();
+    memory.enter_frame();
+    memory.enter_frame();
+    memory.write_u8(5, 0xff);
+    memory.exit_frame();
+    memory.enter_frame();
+    assert_eq!(memory.read_u8s(0, 32), vec![0; 32]);
+    memory.exit_frame();
+    memory.exit_frame();
+}
+
+#[test]
+fn zero_sized_access_past_the_end_does_not_expand() {
+    let mut memory = EvmMemory::new();
+    memory.enter_frame();
+    assert!(memory.read_u8s(1000, 0).is_empty());
+    memory.copy(2000, 1000, 0);
+    assert_eq!(memory.size, 0);
+}
+
+#[test]
+fn accesses_end_on_the_word_they_touch_last() {
+    let mut memory = EvmMemory::new();
+    memory.enter_frame();
+    assert_eq!(memory.size_after(0, 1), 32);
+    assert_eq!(memory.size_after(31, 1), 32);
+    assert_eq!(memory.size_after(32, 1), 64);
+    assert_eq!(memory.size_after(31, 2), 64);
+    assert_eq!(memory.size_after(1000, 0), 0);
+
+    memory.write_u8(31, 1);
+    assert_eq!(memory.msize(), U256::from(32));
+    assert_eq!(memory.read_u256(1, 32), U256::from(1) << 8);
+    assert_eq!(memory.msize(), U256::from(64));
+    memory.write_slice(64, &[], 0, 0);
+    assert_eq!(memory.size, 64);
+}
+
+#[test]
+fn empty_accesses_can_be_anywhere() {
+    let max = U256::from(EvmMemory::MAX_SIZE);
+    assert_eq!(EvmMemory::range(U256::MAX, U256::zero()), Some((0, 0)));
+    assert_eq!(
+        EvmMemory::range(max - 1, U256::one()),
+        Some((EvmMemory::MAX_SIZE - 1, 1))
+    );
+    assert_eq!(EvmMemory::range(max, U256::one()), None);
+    assert_eq!(EvmMemory::range(U256::MAX, U256::one()), None);
+    assert_eq!(EvmMemory::range(U256::one(), U256::MAX), None);
+}
+
+const FAR: &str = "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+
+fn run(code: &str) -> EvmResult {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        evm_config: EvmConfig::preset(Preset::SpecExact),
+        ..Default::default()
+    };
+    evm(
+        hex::decode(code.replace(' ', "").replace("FAR", &FAR[2..])).unwrap(),
+        &mut data,
+        true,
+    )
+}
+
+fn msize(code: &str) -> U256 {
+    let result = run(&format!("{code} 59 00"));
+    assert!(result.success);
+    result.stack[0]
+}
+
+#[test]
+fn msize_counts_the_highest_word_touched() {
+    assert_eq!(msize(""), U256::zero());
+    // MLOAD at 31 reads into the second word
+    assert_eq!(msize("601f 51 50"), U256::from(64));
+    // MSTORE8 at 31 stays in the first, at 32 it does not
+    assert_eq!(msize("6001 601f 53"), U256::from(32));
+    assert_eq!(msize("6001 6020 53"), U256::from(64));
+    // MCOPY grows memory over its source as well as its destination
+    assert_eq!(msize("6001 6040 6000 5e"), U256::from(96));
+}
+
+#[test]
+fn empty_operations_do_not_expand_memory() {
+    // SHA3, CALLDATACOPY, LOG0 and MCOPY of nothing far past the end
+    assert_eq!(msize("6000 7fFAR 20 50"), U256::zero());
+    assert_eq!(msize("6000 6000 7fFAR 37"), U256::zero());
+    assert_eq!(msize("6000 7fFAR a0"), U256::zero());
+    assert_eq!(msize("6000 7fFAR 7fFAR 5e"), U256::zero());
+
+    let result = run("6000 7fFAR f3");
+    assert!(result.success);
+    assert_eq!(result.value, Some(vec![]));
+}
+
+#[test]
+fn memory_is_paid_for_before_it_grows() {
+    // Too far to pay for, whatever the gas
+    let result = run("7fFAR 51");
+    assert_eq!(result.halt_reason, Some(HaltReason::OutOfGas));
+    let result = run("6001 7fFAR 20");
+    assert_eq!(result.halt_reason, Some(HaltReason::OutOfGas));
+
+    // The transaction, two PUSH1, MSTORE and a word of memory
+    let result = run("6001 6000 52 00");
+    assert_eq!(result.gas_used, 21000 + 3 + 3 + 3 + 3);
+}
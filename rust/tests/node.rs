@@ -0,0 +1,224 @@
+//! The dev node: transactions, the pool, mining and the cheats.
+#![cfg(feature = "std")]
+
+use evm::host::Database;
+use evm::node::{DevNode, NodeError, DEV_CHAIN_ID};
+use evm::EvmData;
+use primitive_types::U256;
+use serde_json::json;
+
+const ALICE: u64 = 0xa11ce;
+const BOB: u64 = 0xb0b;
+const EIP155_SIGNED: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+fn node() -> DevNode {
+    let mut node = DevNode::new(EvmData::default(), DEV_CHAIN_ID);
+    node.set_balance(U256::from(ALICE), U256::from(1_000_000));
+    node
+}
+
+fn balance(node: &DevNode, address: u64) -> U256 {
+    node.state()
+        .basic(U256::from(address))
+        .unwrap_or_default()
+        .balance
+}
+
+#[test]
+fn transactions_are_mined_on_the_spot() {
+    let mut node = node();
+    assert_eq!(node.block_number(), 0);
+
+    let transfer = json!({ "from": "0xa11ce", "to": "0xb0b", "value": "0x64" });
+    let first = node.send_transaction(&transfer).unwrap();
+    let second = node.send_transaction(&transfer).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(node.block_number(), 2);
+    assert_eq!(balance(&node, BOB), U256::from(200));
+    assert_eq!(node.next_nonce(U256::from(ALICE)), 2);
+
+    let receipt = node.receipt(second).unwrap();
+    assert!(receipt.success);
+    assert_eq!(receipt.block_number, 2);
+    assert_eq!(receipt.gas_used, 21000);
+    let block = node.block(2).unwrap();
+    assert_eq!(block.hash, receipt.block_hash);
+    assert_eq!(block.parent_hash, node.block(1).unwrap().hash);
+    assert_eq!(block.transactions, vec![second]);
+}
+
+#[test]
+fn contracts_are_created() {
+    let mut node = node();
+    // Deploys the one byte 0x2a: MSTORE8(0, 0x2a), RETURN(0, 1)
+    let create = json!({ "from": "0xa11ce", "data": "0x602a60005360016000f3" });
+    let hash = node.send_transaction(&create).unwrap();
+    let address = node.receipt(hash).unwrap().contract_address.unwrap();
+    assert_eq!(node.state().code(address), Some(vec![0x2a]));
+}
+
+#[test]
+fn the_pool_waits_for_mine_without_automine() {
+    let mut node = node();
+    node.set_automine(false);
+    let transfer = json!({ "from": "0xa11ce", "to": "0xb0b", "value": "0x1" });
+    let first = node.send_transaction(&transfer).unwrap();
+    let second = node.send_transaction(&transfer).unwrap();
+    // A nonce that is already taken, dropped when mined
+    let stale = node
+        .send_transaction(&json!({ "from": "0xa11ce", "to": "0xb0b", "nonce": "0x0" }))
+        .unwrap();
+    assert_eq!(node.pending(), vec![first, second, stale]);
+    assert_eq!(node.receipt(first), None);
+    assert_eq!(node.block_number(), 0);
+
+    assert_eq!(node.mine(), 1);
+    assert!(node.pending().is_empty());
+    assert_eq!(node.block(1).unwrap().transactions, vec![first, second]);
+    assert_eq!(node.receipt(second).unwrap().cumulative_gas_used, 42000);
+    assert_eq!(node.receipt(stale), None);
+    assert_eq!(balance(&node, BOB), U256::from(2));
+}
+
+#[test]
+fn transactions_that_can_not_be_included_are_errors() {
+    let mut node = node();
+    let poor = json!({ "from": "0xb0b", "to": "0xa11ce", "value": "0x1" });
+    assert!(matches!(
+        node.send_transaction(&poor),
+        Err(NodeError::Rejected(reason)) if reason.starts_with("insufficient funds")
+    ));
+    let no_sender = json!({ "to": "0xa11ce" });
+    assert_eq!(
+        node.send_transaction(&no_sender),
+        Err(NodeError::InvalidTransaction("no sender".to_string()))
+    );
+    assert_eq!(node.block_number(), 0);
+    assert_eq!(
+        node.send_raw_transaction(&[0xc0]),
+        Err(NodeError::InvalidTransaction(
+            "0 fields, expected 9".to_string()
+        ))
+    );
+    // The signed example of EIP-155
+    let raw = hex::decode(EIP155_SIGNED).unwrap();
+    assert_eq!(
+        node.send_raw_transaction(&raw),
+        Err(NodeError::InvalidTransaction(
+            "chain id 1 instead of 31337".to_string()
+        ))
+    );
+}
+
+#[cfg(feature = "k256")]
+#[test]
+fn raw_transactions_are_sent_by_their_signer() {
+    use evm::tx::{DynamicFeeTx, LegacyTx, Transaction};
+
+    let key: [u8; 32] =
+        hex::decode("45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let signer = U256::from_str_radix("a94f5374fce5edbc8e2a8697c15331677e6ebf0b", 16).unwrap();
+    let mut node = node();
+    node.set_balance(signer, U256::from(1_000_000));
+
+    let tx = Transaction::Legacy(LegacyTx {
+        chain_id: Some(DEV_CHAIN_ID),
+        gas_limit: 21000,
+        to: Some(U256::from(BOB)),
+        value: U256::from(7),
+        ..Default::default()
+    })
+    .sign(&key)
+    .unwrap();
+    let hash = node.send_raw_transaction(&tx.encode()).unwrap();
+    assert_eq!(hash, tx.hash());
+    let receipt = node.receipt(hash).unwrap();
+    assert!(receipt.success);
+    assert_eq!(receipt.from, signer);
+    assert_eq!(balance(&node, BOB), U256::from(7));
+    assert_eq!(node.next_nonce(signer), 1);
+
+    // Without a base fee, EIP-1559 transactions pay their max fee
+    let tx = Transaction::DynamicFee(DynamicFeeTx {
+        chain_id: DEV_CHAIN_ID,
+        nonce: 1,
+        max_priority_fee_per_gas: U256::from(1),
+        max_fee_per_gas: U256::from(2),
+        gas_limit: 21000,
+        to: Some(U256::from(BOB)),
+        ..Default::default()
+    })
+    .sign(&key)
+    .unwrap();
+    let hash = node.send_raw_transaction(&tx.encode()).unwrap();
+    assert_eq!(hash, tx.hash());
+    let receipt = node.receipt(hash).unwrap();
+    assert_eq!(receipt.gas_price, U256::from(2));
+    assert_eq!(
+        node.state().basic(signer).unwrap().balance,
+        U256::from(1_000_000 - 7 - 42000)
+    );
+}
+
+#[test]
+fn the_next_timestamp_can_be_set() {
+    let mut node = node();
+    let latest = node.latest().timestamp;
+    assert_eq!(
+        node.set_next_block_timestamp(latest),
+        Err(NodeError::TimestampTooLow {
+            timestamp: latest,
+            latest
+        })
+    );
+
+    node.set_next_block_timestamp(latest + 1000).unwrap();
+    node.mine();
+    assert_eq!(node.latest().timestamp, latest + 1000);
+    node.mine();
+    assert!(node.latest().timestamp > latest + 1000);
+}
+
+#[test]
+fn cheats_change_the_state() {
+    let mut node = node();
+    let contract = U256::from(0xc0de);
+    node.set_code(contract, &[0x00]);
+    node.set_nonce(contract, 5);
+    node.set_storage_at(contract, U256::from(1), U256::from(7));
+    node.set_balance(contract, U256::from(9));
+
+    let state = node.state();
+    assert_eq!(state.code(contract), Some(vec![0x00]));
+    assert_eq!(state.basic(contract).unwrap().nonce, 5);
+    assert_eq!(state.basic(contract).unwrap().balance, U256::from(9));
+    assert_eq!(state.storage(contract, U256::from(1)), U256::from(7));
+    assert!(state.journal.is_empty());
+}
+
+#[test]
+fn snapshots_wind_the_chain_back() {
+    let mut node = node();
+    let transfer = json!({ "from": "0xa11ce", "to": "0xb0b", "value": "0x5" });
+    let kept = node.send_transaction(&transfer).unwrap();
+    let snapshot = node.snapshot();
+    let dropped = node.send_transaction(&transfer).unwrap();
+    node.set_balance(U256::from(0xc0de), U256::one());
+    assert_eq!(node.block_number(), 2);
+
+    assert!(node.revert(snapshot));
+    assert_eq!(node.block_number(), 1);
+    assert_eq!(balance(&node, BOB), U256::from(5));
+    assert_eq!(balance(&node, 0xc0de), U256::zero());
+    assert!(node.receipt(kept).is_some());
+    assert_eq!(node.receipt(dropped), None);
+    assert!(!node.revert(snapshot));
+
+    // The chain goes on from the snapshot
+    let again = node.send_transaction(&transfer).unwrap();
+    assert_eq!(again, dropped);
+    assert_eq!(node.block_number(), 2);
+}
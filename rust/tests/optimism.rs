@@ -0,0 +1,233 @@
+//! The Optimism chain preset: the L1 data fee, deposit transactions and
+//! P256VERIFY.
+#![cfg(feature = "optimism")]
+
+use evm::builder::Evm;
+use evm::config::{EvmConfig, Preset};
+use evm::env::TxEnv;
+use evm::optimism::{self, P256VERIFY};
+use evm::spec::{ChainConfig, SpecId};
+use evm::t8n::{alloc_json, transition_with_config};
+use evm::EvmContext;
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+const SENDER: &str = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+const CONTRACT: &str = "0x0000000000000000000000000000000000001000";
+const REVERTER: &str = "0x0000000000000000000000000000000000002000";
+const COINBASE: &str = "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba";
+const L1_FEE_VAULT: &str = "0x420000000000000000000000000000000000001a";
+const BASE_FEE_VAULT: &str = "0x4200000000000000000000000000000000000019";
+
+// hash | r | s | x | y, signed with the key 0x1234567
+const P256_INPUT: &str = "ecad8fb0bf00f65742742da8f63ce76136345f3eae04fe27d080450ca3ee5491\
+    1fa9501e83691c3557790f48a4bdc1d136dd356c318dacece31924f6b94799a1\
+    ecd4fe1729aa7000ae284f6a39976b302f19e60060292d5ead8114bfbd40f8cf\
+    088bb9ff22ab291a74c86fc677ba897baadee370cc6129b82d170ba3fc26415c\
+    442da9a716067956d91eaa02b93ad409490e87cd5e758ea6a331a1deb75ba846";
+
+#[test]
+fn the_l1_data_fee_prices_calldata_gas_at_the_scaled_l1_fees() {
+    let context: EvmContext = serde_json::from_value(json!({
+        "l1_base_fee": "0x3b9aca00",
+        "l1_blob_base_fee": "0x1",
+        "base_fee_scalar": "0x558",
+        "blob_base_fee_scalar": "0xc5fc5",
+    }))
+    .unwrap();
+    // 4 for the zero byte, 16 for each other one
+    let fee = optimism::l1_data_fee(Some(&context), &[0, 1, 2]);
+    assert_eq!(
+        fee,
+        U256::from(36u64 * (16 * 1368 * 1_000_000_000 + 810_949) / 16_000_000)
+    );
+    assert_eq!(optimism::l1_data_fee(None, &[0, 1, 2]), U256::zero());
+}
+
+// CALLDATACOPY(0, 0, CALLDATASIZE), STATICCALL(gas, 0x100, 0, 160, 0, 32),
+// MLOAD(0)
+fn verify(config: ChainConfig, input: &str) -> Vec<U256> {
+    let code = format!("3660006000376020600060a0600061{:04x}5afa600051", P256VERIFY);
+    let result = Evm::builder()
+        .with_chain_config(config)
+        .with_config(EvmConfig::preset(Preset::SpecExact))
+        .with_tx(TxEnv {
+            address: U256::from(0xc0de),
+            ..Default::default()
+        })
+        .with_gas_limit(100_000)
+        .with_input(hex::decode(input).unwrap())
+        .with_code(hex::decode(code).unwrap())
+        .build()
+        .transact();
+    assert!(result.success);
+    result.stack
+}
+
+#[test]
+fn p256verify_returns_one_for_a_valid_signature() {
+    let input: String = P256_INPUT.split_whitespace().collect();
+    let mut tampered = hex::decode(&input).unwrap();
+    tampered[40] ^= 1;
+    let hash = U256::from_big_endian(&tampered[..32]);
+
+    assert_eq!(
+        optimism::p256_verify(&hex::decode(&input).unwrap()),
+        [vec![0; 31], vec![1]].concat()
+    );
+    assert_eq!(optimism::p256_verify(&tampered), Vec::<u8>::new());
+    assert_eq!(optimism::p256_verify(&[0; 32]), Vec::<u8>::new());
+
+    let optimism = ChainConfig::optimism(SpecId::Cancun);
+    assert_eq!(
+        verify(optimism.clone(), &input),
+        vec![U256::one(), U256::one()]
+    );
+    // Nothing is written for an invalid one, so the hash is left in memory
+    assert_eq!(verify(optimism, &hex::encode(&tampered))[0], hash);
+    // On Ethereum 0x100 is an account without code
+    assert_eq!(verify(ChainConfig::new(SpecId::Cancun), &input)[0], hash);
+}
+
+fn alloc() -> Value {
+    json!({
+        SENDER: { "balance": "0x1000000000" },
+        // SSTORE(1, 42)
+        CONTRACT: { "code": "0x602a600155" },
+        // REVERT(0, 0)
+        REVERTER: { "code": "0x60006000fd" },
+    })
+}
+
+fn env() -> Value {
+    json!({
+        "currentCoinbase": COINBASE,
+        "currentGasLimit": "0x1000000",
+        "currentNumber": "0x1",
+        "currentTimestamp": "0x3e8",
+        "currentBaseFee": "0x7",
+        "currentRandom": "0x0",
+        "l1BaseFee": "0x3b9aca00",
+        "l1BlobBaseFee": "0x1",
+        "baseFeeScalar": "0x558",
+        "blobBaseFeeScalar": "0xc5fc5",
+    })
+}
+
+fn tx(to: &str) -> Value {
+    json!({
+        "sender": SENDER,
+        "nonce": "0x0",
+        "gasPrice": "0xa",
+        "gas": "0x186a0",
+        "to": to,
+        "value": "0x1",
+        "input": "0x",
+        "v": "0x1b",
+        "r": "0x1",
+        "s": "0x1",
+    })
+}
+
+fn deposit(to: &str, system: bool) -> Value {
+    json!({
+        "type": "0x7e",
+        "sender": SENDER,
+        "sourceHash": format!("0x{:064x}", 0xd0),
+        "mint": "0x100",
+        "gas": "0x186a0",
+        "to": to,
+        "value": "0x1",
+        "input": "0x",
+        "isSystemTx": system,
+    })
+}
+
+fn run(txs: Value, config: ChainConfig) -> (Value, Value) {
+    let output = transition_with_config(&alloc(), &env(), &txs, config, 10).unwrap();
+    (output.result, alloc_json(&output.alloc))
+}
+
+fn balance(alloc: &Value, address: &str) -> U256 {
+    alloc[address]["balance"]
+        .as_str()
+        .map(|balance| U256::from_str_radix(balance.trim_start_matches("0x"), 16).unwrap())
+        .unwrap_or_default()
+}
+
+#[test]
+fn transactions_pay_the_l1_fee_and_the_base_fee_into_the_vaults() {
+    let (result, alloc) = run(json!([tx(CONTRACT)]), ChainConfig::optimism(SpecId::Cancun));
+    assert_eq!(result["receipts"][0]["status"], json!("0x1"));
+    let used = 43106u64;
+    assert_eq!(result["gasUsed"], json!(format!("{:#x}", used)));
+
+    let l1_fee = balance(&alloc, L1_FEE_VAULT);
+    assert!(!l1_fee.is_zero());
+    assert_eq!(balance(&alloc, BASE_FEE_VAULT), U256::from(used * 7));
+    assert_eq!(balance(&alloc, COINBASE), U256::from(used * 3));
+    assert_eq!(
+        balance(&alloc, SENDER),
+        U256::from(0x1000000000u64 - used * 10 - 1) - l1_fee
+    );
+
+    // On Ethereum the base fee is burnt and there is no L1 fee
+    let (_, alloc) = run(json!([tx(CONTRACT)]), ChainConfig::new(SpecId::Cancun));
+    assert_eq!(alloc.get(L1_FEE_VAULT), None);
+    assert_eq!(
+        balance(&alloc, SENDER),
+        U256::from(0x1000000000u64 - used * 10 - 1)
+    );
+}
+
+#[test]
+fn deposits_mint_to_their_sender_and_pay_no_fees() {
+    let (result, alloc) = run(
+        json!([deposit(CONTRACT, false)]),
+        ChainConfig::optimism(SpecId::Cancun),
+    );
+    assert_eq!(result["receipts"][0]["status"], json!("0x1"));
+    assert_eq!(result["gasUsed"], json!(format!("{:#x}", 43106)));
+    assert_eq!(alloc[SENDER]["nonce"], json!("0x1"));
+    assert_eq!(
+        balance(&alloc, SENDER),
+        U256::from(0x1000000000u64 + 0x100 - 1)
+    );
+    assert_eq!(balance(&alloc, CONTRACT), U256::one());
+    assert_eq!(balance(&alloc, COINBASE), U256::zero());
+    assert_eq!(balance(&alloc, L1_FEE_VAULT), U256::zero());
+
+    // System deposits use none of the block's gas
+    let (result, _) = run(
+        json!([deposit(CONTRACT, true)]),
+        ChainConfig::optimism(SpecId::Cancun),
+    );
+    assert_eq!(result["gasUsed"], json!("0x0"));
+}
+
+#[test]
+fn failed_deposits_keep_the_mint_and_use_all_their_gas() {
+    let (result, alloc) = run(
+        json!([deposit(REVERTER, false)]),
+        ChainConfig::optimism(SpecId::Cancun),
+    );
+    assert_eq!(result["receipts"][0]["status"], json!("0x0"));
+    assert_eq!(result["gasUsed"], json!("0x186a0"));
+    assert_eq!(alloc[SENDER]["nonce"], json!("0x1"));
+    assert_eq!(balance(&alloc, SENDER), U256::from(0x1000000000u64 + 0x100));
+    assert_eq!(balance(&alloc, REVERTER), U256::zero());
+}
+
+#[test]
+fn deposits_are_rejected_on_ethereum() {
+    let (result, alloc) = run(
+        json!([deposit(CONTRACT, false)]),
+        ChainConfig::new(SpecId::Cancun),
+    );
+    assert_eq!(result["rejected"][0]["index"], json!(0));
+    assert!(result["rejected"][0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("not supported"));
+    assert_eq!(balance(&alloc, SENDER), U256::from(0x1000000000u64));
+}
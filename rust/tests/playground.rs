@@ -0,0 +1,55 @@
+//! JSON requests of the playground the wasm bindings expose.
+#![cfg(feature = "std")]
+
+use evm::playground::run;
+use serde_json::json;
+
+#[test]
+fn results_have_the_stack_logs_and_trace() {
+    // PUSH1 1, PUSH1 2, ADD, LOG0 of no data
+    let output = run(&json!({ "code": "0x6001600201600080a0", "trace": true })).unwrap();
+    assert_eq!(output["success"], json!(true));
+    assert_eq!(output["stack"], json!(["0x3"]));
+    assert_eq!(output["haltReason"], json!("Stop"));
+    assert_eq!(
+        output["logs"],
+        json!([{
+            "address": "0x0000000000000000000000000000000000000000",
+            "topics": [],
+            "data": "0x",
+        }])
+    );
+    let trace = output["trace"].as_array().unwrap();
+    assert_eq!(trace[2]["opName"], json!("ADD"));
+    assert_eq!(trace.len(), 6);
+
+    assert!(run(&json!({ "code": "0x00" })).unwrap()["trace"].is_null());
+}
+
+#[test]
+fn tx_block_and_spec_are_taken_from_the_request() {
+    // CALLVALUE, NUMBER, PUSH0
+    let request = json!({
+        "code": "0x34435f",
+        "spec": "Shanghai",
+        "tx": { "value": "0x7" },
+        "block": { "number": "0x10" },
+    });
+    let output = run(&request).unwrap();
+    assert_eq!(output["stack"], json!(["0x0", "0x10", "0x7"]));
+
+    let request = json!({ "code": "0x34435f", "spec": "London" });
+    assert_eq!(run(&request).unwrap()["haltReason"], json!("InvalidOpcode"));
+}
+
+#[test]
+fn bad_requests_are_errors() {
+    assert_eq!(run(&json!({})), Err("code is required".to_string()));
+    assert!(run(&json!({ "code": "0xzz" }))
+        .unwrap_err()
+        .starts_with("invalid code"));
+    assert_eq!(
+        run(&json!({ "code": "0x00", "spec": "Atlantis" })),
+        Err("unknown spec \"Atlantis\"".to_string())
+    );
+}
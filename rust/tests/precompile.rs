@@ -0,0 +1,131 @@
+//! Precompiles registered next to the ones of the fork.
+
+use evm::builder::{Evm, EvmBuilder};
+use evm::config::{EvmConfig, Preset};
+use evm::env::TxEnv;
+use evm::precompiles::{PrecompileError, PrecompileOutput};
+use evm::spec::SpecId;
+use evm::HaltReason;
+use primitive_types::U256;
+
+const DOUBLE: u64 = 0x100;
+const DOUBLE_GAS: u64 = 50;
+
+// The input word times two
+fn double(input: &[u8], gas: Option<u64>) -> Result<PrecompileOutput, PrecompileError> {
+    if gas.is_some_and(|gas| gas < DOUBLE_GAS) {
+        return Err(PrecompileError::OutOfGas);
+    }
+    if input.len() != 32 {
+        return Err(PrecompileError::Custom("expected one word".to_string()));
+    }
+    let mut bytes = vec![0; 32];
+    (U256::from_big_endian(input) * U256::from(2)).to_big_endian(&mut bytes);
+    Ok(PrecompileOutput {
+        gas_used: DOUBLE_GAS,
+        bytes,
+    })
+}
+
+fn seven(_: &[u8], _: Option<u64>) -> Result<PrecompileOutput, PrecompileError> {
+    let mut bytes = vec![0; 32];
+    bytes[31] = 7;
+    Ok(PrecompileOutput { gas_used: 1, bytes })
+}
+
+// GAS, or PUSH1 10 for too little gas
+const ALL_GAS: &str = "5a";
+const LITTLE_GAS: &str = "600a";
+
+// MSTORE(0, 0x2a), STATICCALL(gas, address, 0, args_size, 0, 32), MLOAD(0)
+fn caller(address: u64, gas: &str, args_size: u8) -> EvmBuilder {
+    let code = format!(
+        "602a600052 6020 6000 60{:02x} 6000 61{:04x} {} fa 600051",
+        args_size, address, gas
+    );
+    Evm::builder()
+        .with_spec(SpecId::Cancun)
+        .with_config(EvmConfig::preset(Preset::SpecExact))
+        .with_tx(TxEnv {
+            address: U256::from(0xc0de),
+            ..Default::default()
+        })
+        .with_gas_limit(100_000)
+        .with_code(hex::decode(code.replace(' ', "")).unwrap())
+}
+
+#[test]
+fn registered_precompiles_are_called_like_the_built_in_ones() {
+    let result = caller(DOUBLE, ALL_GAS, 32)
+        .with_precompile(U256::from(DOUBLE), double)
+        .build()
+        .transact();
+    assert!(result.success);
+    // The output over the input, and STATICCALL succeeded
+    assert_eq!(result.stack, vec![U256::from(84), U256::one()]);
+
+    // Without it, 0x100 is an account without code, which returns nothing
+    let result = caller(DOUBLE, ALL_GAS, 32).build().transact();
+    assert_eq!(result.stack[0], U256::from(42));
+}
+
+#[test]
+fn registered_precompiles_start_out_warm_and_charge_their_cost() {
+    let gas_used = |builder: EvmBuilder| builder.build().transact().gas_used;
+    let registered =
+        gas_used(caller(DOUBLE, ALL_GAS, 32).with_precompile(U256::from(DOUBLE), double));
+    let cold_account = gas_used(caller(DOUBLE, ALL_GAS, 32));
+    // The cold access an account pays, less the warm one and the cost
+    assert_eq!(cold_account - registered, 2600 - 100 - DOUBLE_GAS);
+}
+
+#[test]
+fn failures_use_up_the_gas_passed_on() {
+    let result = caller(DOUBLE, LITTLE_GAS, 32)
+        .with_precompile(U256::from(DOUBLE), double)
+        .build()
+        .transact();
+    assert!(result.success);
+    // Nothing written, STATICCALL failed
+    assert_eq!(result.stack, vec![U256::from(42), U256::zero()]);
+    let succeeded = caller(DOUBLE, ALL_GAS, 32)
+        .with_precompile(U256::from(DOUBLE), double)
+        .build()
+        .transact();
+    // GAS costs 2, PUSH1 3 and the 10 passed on are gone
+    assert_eq!(result.gas_used, succeeded.gas_used + 1 - DOUBLE_GAS + 10);
+
+    let result = caller(DOUBLE, ALL_GAS, 0)
+        .with_precompile(U256::from(DOUBLE), double)
+        .build()
+        .transact();
+    assert_eq!(result.stack, vec![U256::from(42), U256::zero()]);
+    // All but the 64th kept back by the caller
+    assert!(result.gas_used > 98_000);
+}
+
+#[test]
+fn registering_at_a_built_in_address_replaces_it() {
+    let result = caller(0x01, ALL_GAS, 32)
+        .with_precompile(U256::one(), seven)
+        .build()
+        .transact();
+    assert_eq!(result.stack, vec![U256::from(7), U256::one()]);
+}
+
+#[test]
+fn precompiles_of_the_fork_missing_here_fail_the_transaction() {
+    // SHA256 and BLAKE2F
+    for address in [0x02, 0x09] {
+        let result = caller(address, ALL_GAS, 32).build().transact();
+        assert!(!result.success);
+        assert_eq!(result.halt_reason, Some(HaltReason::UnsupportedPrecompile));
+    }
+
+    // Registering one fills the gap
+    let result = caller(0x02, ALL_GAS, 32)
+        .with_precompile(U256::from(0x02), seven)
+        .build()
+        .transact();
+    assert_eq!(result.stack, vec![U256::from(7), U256::one()]);
+}
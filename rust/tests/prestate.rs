@@ -0,0 +1,98 @@
+//! The prestateTracer view of an execution, plain and in diff mode.
+#![cfg(feature = "std")]
+
+use std::collections::HashMap;
+
+use evm::prestate::{accounts_json, PrestateTracer};
+use evm::{evm_with_inspector, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const SLOT: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+const OTHER_SLOT: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+fn data() -> EvmData {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({
+                "from": "0xa11ce",
+                "to": "0xc0de",
+                "gas": "0xf4240",
+            }))
+            .unwrap(),
+        ),
+        ..Default::default()
+    };
+    data.balances.insert(0xa11ce.to_string(), U256::from(1000));
+    data.state.insert(
+        0xc0de.to_string(),
+        HashMap::from([
+            (U256::from(1), U256::from(5)),
+            (U256::from(2), U256::from(7)),
+        ]),
+    );
+    data
+}
+
+// SLOAD(1), POP, SSTORE(2, 9)
+const CODE: &str = "600154 50 6009600255";
+
+#[test]
+fn touched_state_is_reported_as_it_was() {
+    let mut data = data();
+    let mut tracer = PrestateTracer::new(&data);
+    let result = evm_with_inspector(
+        hex::decode(CODE.replace(' ', "")).unwrap(),
+        &mut data,
+        true,
+        &mut tracer,
+    );
+    assert!(result.success);
+
+    assert_eq!(
+        accounts_json(&tracer.prestate()),
+        json!({
+            "0x00000000000000000000000000000000000a11ce": { "balance": "0x3e8" },
+            "0x000000000000000000000000000000000000c0de": {
+                "balance": "0x0",
+                "storage": {
+                    SLOT: "0x0000000000000000000000000000000000000000000000000000000000000005",
+                    OTHER_SLOT: "0x0000000000000000000000000000000000000000000000000000000000000007",
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn diff_mode_keeps_only_what_changed() {
+    let mut data = data();
+    let mut tracer = PrestateTracer::new(&data);
+    evm_with_inspector(
+        hex::decode(CODE.replace(' ', "")).unwrap(),
+        &mut data,
+        true,
+        &mut tracer,
+    );
+
+    assert_eq!(
+        tracer.diff(&data).to_json(),
+        json!({
+            "pre": {
+                "0x000000000000000000000000000000000000c0de": {
+                    "balance": "0x0",
+                    "storage": {
+                        OTHER_SLOT: "0x0000000000000000000000000000000000000000000000000000000000000007",
+                    },
+                },
+            },
+            "post": {
+                "0x000000000000000000000000000000000000c0de": {
+                    "storage": {
+                        OTHER_SLOT: "0x0000000000000000000000000000000000000000000000000000000000000009",
+                    },
+                },
+            },
+        })
+    );
+}
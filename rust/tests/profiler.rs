@@ -0,0 +1,63 @@
+//! Per-opcode counts, gas and time.
+#![cfg(feature = "std")]
+
+use evm::profiler::Profiler;
+use evm::{evm_with_inspector, EvmData, TxData};
+use serde_json::json;
+
+fn profile(code: &str) -> Profiler {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        ..Default::default()
+    };
+    // The code is also that of 0xc0de, for calls to it
+    data.codes
+        .insert("49374".to_string(), code.parse().unwrap());
+    let mut profiler = Profiler::new();
+    evm_with_inspector(hex::decode(code).unwrap(), &mut data, true, &mut profiler);
+    profiler
+}
+
+fn counts_and_gas(profiler: &Profiler) -> Vec<(u8, u64, u64)> {
+    let mut entries: Vec<_> = profiler
+        .report()
+        .iter()
+        .map(|entry| (entry.opcode, entry.count, entry.gas))
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn counts_and_gas_by_opcode() {
+    // PUSH1 1, PUSH1 1, ADD, PUSH1 0, MSTORE
+    let profiler = profile("600160010160005200");
+    assert_eq!(
+        counts_and_gas(&profiler),
+        vec![(0x00, 1, 0), (0x01, 1, 3), (0x52, 1, 6), (0x60, 3, 9)]
+    );
+}
+
+#[test]
+fn report_is_sorted_by_time() {
+    let report = profile("6101005b600190038060035700").report();
+    assert!(report.windows(2).all(|pair| pair[0].time >= pair[1].time));
+    let jumpi = report.iter().find(|entry| entry.opcode == 0x57).unwrap();
+    assert_eq!((jumpi.count, jumpi.gas), (256, 2560));
+}
+
+#[test]
+fn a_call_is_charged_with_the_frame_it_starts() {
+    // CALL to 0xc0de itself with 1000 gas, recursing until the gas runs out
+    let profiler = profile("6000600060006000600061c0de6103e8f1");
+    let call = profiler
+        .report()
+        .into_iter()
+        .find(|entry| entry.opcode == 0xf1)
+        .unwrap();
+    assert!(call.count >= 2);
+    assert!(call.gas > 1000);
+    assert!(!profiler.table().is_empty());
+}
@@ -0,0 +1,183 @@
+//! Replaying transactions of a chain, served by a canned node.
+#![cfg(feature = "std")]
+
+use evm::fork::{ForkDb, RpcError, Transport};
+use evm::replay::{replay, ReplayError, ReplayOptions};
+use evm::spec::SpecId;
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+const ALICE: &str = "0x00000000000000000000000000000000000a11ce";
+const BOB: &str = "0x0000000000000000000000000000000000000b0b";
+const EMITTER: &str = "0x000000000000000000000000000000000000e417";
+// MSTORE(0, 42), LOG1(0, 32, 7)
+const EMITTER_CODE: &str = "0x602a600052600760206000a100";
+const PAYMENT: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+const CALL: &str = "0x2222222222222222222222222222222222222222222222222222222222222222";
+
+// A chain whose block 0x10 has a payment from Alice to Bob, then a call from
+// Alice to a contract that logs. Its receipts say what `receipt` says.
+#[derive(Debug)]
+struct Chain {
+    receipt: Value,
+}
+
+fn tx(hash: &str, index: u64, nonce: u64, to: &str, value: u64, gas: u64) -> Value {
+    json!({
+        "hash": hash,
+        "type": "0x2",
+        "blockNumber": "0x10",
+        "transactionIndex": format!("{:#x}", index),
+        "chainId": "0x1",
+        "from": ALICE,
+        "to": to,
+        "nonce": format!("{:#x}", nonce),
+        "value": format!("{:#x}", value),
+        "gas": format!("{:#x}", gas),
+        "maxFeePerGas": "0x64",
+        "maxPriorityFeePerGas": "0x2",
+        "input": "0x",
+        "accessList": [],
+    })
+}
+
+impl Transport for Chain {
+    fn send(&self, body: &str) -> Result<String, RpcError> {
+        let request: Value = serde_json::from_str(body).unwrap();
+        let params = &request["params"];
+        let state = |tag: &Value| assert_eq!(tag, "0xf", "state read at the wrong block");
+        let txs = [
+            tx(PAYMENT, 0, 0, BOB, 1000, 21_000),
+            tx(CALL, 1, 1, EMITTER, 0, 100_000),
+        ];
+        let result = match request["method"].as_str().unwrap() {
+            "eth_chainId" => json!("0x1"),
+            "eth_getTransactionByHash" => match params[0].as_str().unwrap() {
+                PAYMENT => txs[0].clone(),
+                CALL => txs[1].clone(),
+                _ => Value::Null,
+            },
+            "eth_getTransactionReceipt" => self.receipt.clone(),
+            "eth_getBlockByNumber" if params[0] == "0x10" => json!({
+                "number": "0x10",
+                "miner": "0x000000000000000000000000000000000000c0b1",
+                "timestamp": "0x65f1b057",
+                "gasLimit": "0x1c9c380",
+                "baseFeePerGas": "0x7",
+                "difficulty": "0x0",
+                "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000abc",
+                "transactions": txs,
+            }),
+            "eth_getBalance" => {
+                state(&params[1]);
+                json!(if params[0] == ALICE {
+                    "0xde0b6b3a7640000"
+                } else {
+                    "0x0"
+                })
+            }
+            "eth_getTransactionCount" => {
+                state(&params[1]);
+                json!("0x0")
+            }
+            "eth_getCode" => {
+                state(&params[1]);
+                json!(if params[0] == EMITTER {
+                    EMITTER_CODE
+                } else {
+                    "0x"
+                })
+            }
+            "eth_getStorageAt" => json!("0x0"),
+            method => panic!("unexpected {}", method),
+        };
+        Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string())
+    }
+}
+
+fn receipt(status: &str, gas_used: u64, topic: &str) -> Value {
+    json!({
+        "status": status,
+        "gasUsed": format!("{:#x}", gas_used),
+        "logs": [{
+            "address": EMITTER,
+            "topics": [topic],
+            "data": "0x000000000000000000000000000000000000000000000000000000000000002a",
+        }],
+    })
+}
+
+fn hash(text: &str) -> U256 {
+    evm::hexutil::parse_u256(text).unwrap()
+}
+
+fn cancun() -> ReplayOptions {
+    ReplayOptions {
+        spec: Some(SpecId::Cancun),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn a_replay_that_matches_its_receipt() {
+    let chain = Chain {
+        receipt: receipt("0x1", 22_027, "0x7"),
+    };
+    let replayed = replay(ForkDb::new(chain, 0), hash(CALL), &cancun()).unwrap();
+    assert!(replayed.matches(), "{:?}", replayed.mismatches);
+    assert_eq!(replayed.block, 0x10);
+    assert_eq!(replayed.index, 1);
+    assert_eq!(replayed.actual.logs[0].topics, vec![U256::from(7)]);
+    assert_eq!(replayed.to_json()["actual"]["gasUsed"], "0x560b");
+}
+
+#[test]
+fn differences_from_the_receipt_are_listed() {
+    let chain = Chain {
+        receipt: receipt("0x0", 30_000, "0x8"),
+    };
+    let replayed = replay(ForkDb::new(chain, 0), hash(CALL), &cancun()).unwrap();
+    assert!(!replayed.matches());
+    assert_eq!(replayed.mismatches[0], "status is 1 instead of 0");
+    assert_eq!(replayed.mismatches[1], "gas used is 22027 instead of 30000");
+    assert!(replayed.mismatches[2].starts_with("log 0 is "));
+}
+
+#[test]
+fn isolated_replays_skip_the_transactions_before() {
+    let chain = Chain {
+        receipt: receipt("0x1", 22_027, "0x7"),
+    };
+    let options = ReplayOptions {
+        isolated: true,
+        ..cancun()
+    };
+    // On the parent state Alice has not sent the payment yet
+    assert_eq!(
+        replay(ForkDb::new(chain, 0), hash(CALL), &options),
+        Err(ReplayError::Transaction {
+            index: 1,
+            reason: format!("nonce too high: address {}, tx: 1 state: 0", ALICE),
+        })
+    );
+}
+
+#[test]
+fn unknown_transactions_are_errors() {
+    let chain = Chain {
+        receipt: Value::Null,
+    };
+    assert_eq!(
+        replay(ForkDb::new(chain, 0), U256::from(0x33), &cancun()),
+        Err(ReplayError::Invalid("transaction not found".to_string()))
+    );
+}
+
+#[test]
+fn mainnet_blocks_run_with_the_fork_of_their_time() {
+    assert_eq!(SpecId::mainnet(0, 0), SpecId::Frontier);
+    assert_eq!(SpecId::mainnet(12_965_000, 0), SpecId::London);
+    assert_eq!(SpecId::mainnet(15_537_394, 1_663_224_179), SpecId::Merge);
+    assert_eq!(SpecId::mainnet(19_426_587, 1_710_338_135), SpecId::Cancun);
+    assert_eq!(SpecId::mainnet(23_000_000, 1_760_000_000), SpecId::Prague);
+}
@@ -0,0 +1,135 @@
+//! What `EvmResult` reports beyond the stack: memory, storage changes, logs
+//! and gas.
+
+use evm::{evm, EvmData, StorageChange, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn data() -> EvmData {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({
+                "to": "0xc0de",
+                "gas": "0xf4240",
+            }))
+            .unwrap(),
+        ),
+        ..Default::default()
+    };
+    data.state.insert(
+        0xc0de.to_string(),
+        [
+            (U256::from(1), U256::from(5)),
+            (U256::from(2), U256::from(7)),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    data
+}
+
+fn run(code: &str, data: &mut EvmData) -> evm::EvmResult {
+    evm(hex::decode(code.replace(' ', "")).unwrap(), data, true)
+}
+
+#[test]
+fn memory_is_reported_to_msize() {
+    // MSTORE8(0x21, 0xaa)
+    let result = run("60aa 6021 53", &mut data());
+    let mut expected = vec![0; 64];
+    expected[0x21] = 0xaa;
+    assert_eq!(result.memory, expected);
+}
+
+#[test]
+fn memory_is_empty_when_untouched() {
+    let result = run("6001 6002 01", &mut data());
+    assert!(result.memory.is_empty());
+}
+
+#[test]
+fn storage_changes_have_the_values_before_and_after() {
+    // SSTORE(1, 9), SSTORE(3, 4), SSTORE(2, 8), SSTORE(2, 7)
+    let result = run(
+        "6009 6001 55 6004 6003 55 6008 6002 55 6007 6002 55",
+        &mut data(),
+    );
+    assert!(result.success);
+    assert_eq!(result.state_changes.len(), 1);
+    let changes: Vec<_> = result.state_changes[&U256::from(0xc0de)]
+        .clone()
+        .into_iter()
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (
+                U256::from(1),
+                StorageChange {
+                    before: U256::from(5),
+                    after: U256::from(9),
+                },
+            ),
+            (
+                U256::from(3),
+                StorageChange {
+                    before: U256::zero(),
+                    after: U256::from(4),
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn a_failed_transaction_changes_nothing() {
+    // SSTORE(1, 9), then REVERT(0, 0)
+    let result = run("6009 6001 55 6000 6000 fd", &mut data());
+    assert!(!result.success);
+    assert!(result.state_changes.is_empty());
+}
+
+#[test]
+fn logs_and_gas_are_reported() {
+    // LOG1(0, 0, 7), then SSTORE(1, 0) for a refund
+    let result = run("6007 6000 6000 a1 6000 6001 55", &mut data());
+    assert_eq!(result.logs.len(), 1);
+    assert_eq!(result.logs[0].topics, [U256::from(7)]);
+    assert!(result.gas_refunded > 0);
+    assert!(result.gas_used > 21_000);
+}
+
+#[test]
+fn results_serialize_with_hex_words_and_bytes() {
+    // LOG1(0, 1, 7) of the byte MSTORE8(0, 0xaa) wrote, SSTORE(1, 6)
+    let result = run("60aa 6000 53 6007 6001 6000 a1 6006 6001 55", &mut data());
+    let value = serde_json::to_value(&result).unwrap();
+    assert_eq!(value["success"], json!(true));
+    assert_eq!(value["memory"], json!(format!("0xaa{}", "00".repeat(31))));
+    assert_eq!(value["haltReason"], json!("Stop"));
+    assert_eq!(
+        value["logs"],
+        json!([{
+            "address": "0x000000000000000000000000000000000000c0de",
+            "topics": ["0x7"],
+            "data": "0xaa",
+        }])
+    );
+    assert_eq!(
+        value["stateChanges"],
+        json!({ "0xc0de": { "0x1": { "before": "0x5", "after": "0x6" } } })
+    );
+    assert_eq!(
+        value["stateDiff"]["0x000000000000000000000000000000000000c0de"]["storage"]
+            .as_object()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let back: evm::EvmResult = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(back.logs, result.logs);
+    assert_eq!(back.memory, result.memory);
+    assert_eq!(back.state_diff, result.state_diff);
+    assert_eq!(serde_json::to_value(&back).unwrap(), value);
+}
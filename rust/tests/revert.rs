@@ -0,0 +1,137 @@
+//! Decoding revert payloads: `Error(string)`, `Panic(uint256)` and custom
+//! errors.
+
+use evm::abi::{decode_revert, panic_name, selector, RevertReason};
+use evm::{evm, EvmData};
+use primitive_types::U256;
+
+fn word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    U256::from(value).to_big_endian(&mut word);
+    word
+}
+
+fn error_payload(message: &str) -> Vec<u8> {
+    let mut payload = selector("Error(string)").to_vec();
+    payload.extend_from_slice(&word(0x20));
+    payload.extend_from_slice(&word(message.len() as u64));
+    let mut bytes = message.as_bytes().to_vec();
+    bytes.resize(message.len().div_ceil(32) * 32, 0);
+    payload.extend(bytes);
+    payload
+}
+
+fn panic_payload(code: u64) -> Vec<u8> {
+    let mut payload = selector("Panic(uint256)").to_vec();
+    payload.extend_from_slice(&word(code));
+    payload
+}
+
+// Code that writes `payload` to memory and reverts with it
+fn reverting(payload: &[u8]) -> Vec<u8> {
+    let mut code = vec![];
+    for (index, chunk) in payload.chunks(32).enumerate() {
+        let mut padded = [0u8; 32];
+        padded[..chunk.len()].copy_from_slice(chunk);
+        code.push(0x7f);
+        code.extend_from_slice(&padded);
+        code.extend_from_slice(&[0x61, 0, (index * 32) as u8, 0x52]);
+    }
+    code.extend_from_slice(&[0x60, payload.len() as u8, 0x60, 0, 0xfd]);
+    code
+}
+
+#[test]
+fn error_strings_are_decoded() {
+    assert_eq!(
+        decode_revert(&error_payload("not the owner")),
+        Some(RevertReason::Error("not the owner".to_string()))
+    );
+    assert_eq!(
+        decode_revert(&error_payload("")),
+        Some(RevertReason::Error(String::new()))
+    );
+}
+
+#[test]
+fn panics_are_decoded_and_named() {
+    let reason = decode_revert(&panic_payload(0x11)).unwrap();
+    assert_eq!(reason, RevertReason::Panic(U256::from(0x11)));
+    assert_eq!(
+        reason.to_string(),
+        "Panic(0x11: arithmetic overflow or underflow)"
+    );
+    assert_eq!(
+        panic_name(U256::from(0x32)),
+        Some("array index out of bounds")
+    );
+    assert_eq!(panic_name(U256::from(0x99)), None);
+    assert_eq!(panic_name(U256::MAX), None);
+}
+
+#[test]
+fn other_selectors_are_custom_errors() {
+    let mut payload = selector("Unauthorized(address)").to_vec();
+    payload.extend_from_slice(&word(0xa11ce));
+    assert_eq!(
+        decode_revert(&payload),
+        Some(RevertReason::Custom {
+            selector: selector("Unauthorized(address)"),
+            data: word(0xa11ce).to_vec(),
+        })
+    );
+}
+
+#[test]
+fn malformed_error_strings_are_left_encoded() {
+    let mut payload = error_payload("truncated");
+    payload.truncate(4 + 64 + 3);
+    assert!(matches!(
+        decode_revert(&payload),
+        Some(RevertReason::Custom { .. })
+    ));
+    // A length far past the end
+    let mut payload = selector("Error(string)").to_vec();
+    payload.extend_from_slice(&word(0x20));
+    payload.extend_from_slice(&[0xff; 32]);
+    assert!(matches!(
+        decode_revert(&payload),
+        Some(RevertReason::Custom { .. })
+    ));
+}
+
+#[test]
+fn payloads_shorter_than_a_selector_have_no_reason() {
+    assert_eq!(decode_revert(&[]), None);
+    assert_eq!(decode_revert(&[0x08, 0xc3]), None);
+}
+
+#[test]
+fn results_decode_what_they_reverted_with() {
+    let result = evm(
+        reverting(&error_payload("boom")),
+        &mut EvmData::default(),
+        true,
+    );
+    assert!(!result.success);
+    assert_eq!(
+        result.revert_reason(),
+        Some(RevertReason::Error("boom".to_string()))
+    );
+
+    let result = evm(reverting(&panic_payload(1)), &mut EvmData::default(), true);
+    assert_eq!(
+        result.revert_reason().unwrap().to_string(),
+        "Panic(0x1: assertion failed)"
+    );
+}
+
+#[test]
+fn results_that_did_not_revert_have_no_reason() {
+    // RETURN of a payload that looks like an error
+    let mut code = reverting(&error_payload("fine"));
+    *code.last_mut().unwrap() = 0xf3;
+    let result = evm(code, &mut EvmData::default(), true);
+    assert!(result.success);
+    assert_eq!(result.revert_reason(), None);
+}
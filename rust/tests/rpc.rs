@@ -0,0 +1,201 @@
+//! The JSON-RPC server of the `rpc` feature.
+#![cfg(feature = "rpc")]
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+
+use evm::fork::{HttpTransport, Transport};
+use evm::node::{DevNode, DEV_CHAIN_ID};
+use evm::rpc::{handle, handle_dev, RpcServer};
+use evm::service::EvmService;
+use evm::EvmData;
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+// Returns SLOAD(1)
+const LOADS: &str = "60015460005260206000f3";
+
+fn service() -> EvmService {
+    let mut data = EvmData::default();
+    data.balances.insert("49374".to_string(), U256::from(0x2a));
+    data.codes
+        .insert("49374".to_string(), LOADS.parse().unwrap());
+    data.state.insert(
+        "49374".to_string(),
+        HashMap::from([(U256::one(), U256::from(7))]),
+    );
+    EvmService::new(data)
+}
+
+fn rpc(service: &EvmService, method: &str, params: Value) -> Value {
+    handle(
+        service,
+        &json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }),
+    )
+}
+
+#[test]
+fn state_reads() {
+    let service = service();
+    let balance = rpc(&service, "eth_getBalance", json!(["0xc0de", "latest"]));
+    assert_eq!(balance["result"], "0x2a");
+    let code = rpc(&service, "eth_getCode", json!(["0xc0de", "latest"]));
+    assert_eq!(code["result"], format!("0x{}", LOADS));
+    let slot = rpc(
+        &service,
+        "eth_getStorageAt",
+        json!(["0xc0de", "0x1", "latest"]),
+    );
+    assert_eq!(
+        slot["result"],
+        "0x0000000000000000000000000000000000000000000000000000000000000007"
+    );
+}
+
+#[test]
+fn calls_estimates_and_traces() {
+    let service = service();
+    let call = json!({ "from": "0xa11ce", "to": "0xc0de" });
+
+    let output = rpc(&service, "eth_call", json!([call, "latest"]));
+    assert_eq!(
+        output["result"],
+        "0x0000000000000000000000000000000000000000000000000000000000000007"
+    );
+    let overrides = json!({ "0xc0de": { "stateDiff": { "0x1": "0x8" } } });
+    let output = rpc(&service, "eth_call", json!([call, "latest", overrides]));
+    assert!(output["result"].as_str().unwrap().ends_with('8'));
+
+    let gas = rpc(&service, "eth_estimateGas", json!([call]));
+    assert!(gas["result"].as_str().unwrap().starts_with("0x"));
+
+    let trace = rpc(
+        &service,
+        "debug_traceCall",
+        json!([call, "latest", { "tracer": "callTracer" }]),
+    );
+    assert_eq!(trace["result"]["type"], "CALL");
+    let logs = rpc(&service, "debug_traceCall", json!([call, "latest"]));
+    assert_eq!(logs["result"]["structLogs"][1]["op"], "SLOAD");
+}
+
+#[test]
+fn errors() {
+    let service = service();
+    // REVERT(0, 1) with 0x42 in memory
+    let overrides = json!({ "0xc0de": { "code": "604260005360016000fd" } });
+    let reverted = rpc(
+        &service,
+        "eth_call",
+        json!([{ "to": "0xc0de" }, "latest", overrides]),
+    );
+    assert_eq!(
+        reverted["error"],
+        json!({ "code": 3, "message": "execution reverted", "data": "0x42" })
+    );
+
+    let unknown = rpc(&service, "eth_mine", json!([]));
+    assert_eq!(unknown["error"]["code"], -32601);
+    let missing = rpc(&service, "eth_getBalance", json!([]));
+    assert_eq!(missing["error"]["code"], -32602);
+}
+
+#[test]
+fn serves_over_http() {
+    let server = RpcServer::bind("127.0.0.1:0", service()).unwrap();
+    let url = format!("http://{}/", server.local_addr().unwrap());
+    thread::spawn(move || server.serve());
+
+    let transport = HttpTransport::new(&url).unwrap();
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": 1, "method": "eth_getBalance", "params": ["0xc0de"] },
+        { "jsonrpc": "2.0", "id": 2, "method": "eth_getCode", "params": ["0xc0de"] },
+    ]);
+    let response: Value =
+        serde_json::from_str(&transport.send(&batch.to_string()).unwrap()).unwrap();
+    assert_eq!(response[0]["result"], "0x2a");
+    assert_eq!(response[1]["id"], 2);
+
+    let response = transport.send("{").unwrap();
+    assert!(response.contains("-32700"));
+}
+
+#[test]
+fn dev_node_mines_and_cheats() {
+    let node = Mutex::new(DevNode::new(EvmData::default(), DEV_CHAIN_ID));
+    let rpc = |method: &str, params: Value| {
+        handle_dev(
+            &node,
+            &json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }),
+        )
+    };
+
+    rpc("anvil_setBalance", json!(["0xa11ce", "0x100000"]));
+    rpc("anvil_setCode", json!(["0xc0de", format!("0x{}", LOADS)]));
+    rpc("anvil_setStorageAt", json!(["0xc0de", "0x1", "0x7"]));
+    rpc("anvil_setStorageAt", json!(["0xbeef", "0x1", "0x9"]));
+    let slot = |address: &str| rpc("eth_getStorageAt", json!([address, "0x1", "latest"]));
+    assert!(slot("0xc0de")["result"].as_str().unwrap().ends_with("07"));
+    assert!(slot("0xbeef")["result"].as_str().unwrap().ends_with("09"));
+    assert_eq!(rpc("eth_chainId", json!([]))["result"], "0x7a69");
+
+    let sent = rpc(
+        "eth_sendTransaction",
+        json!([{ "from": "0xa11ce", "to": "0xb0b", "value": "0x10" }]),
+    );
+    let hash = sent["result"].as_str().unwrap();
+    let receipt = rpc("eth_getTransactionReceipt", json!([hash]));
+    assert_eq!(receipt["result"]["status"], "0x1");
+    assert_eq!(receipt["result"]["blockNumber"], "0x1");
+    assert_eq!(rpc("eth_getBalance", json!(["0xb0b"]))["result"], "0x10");
+    assert_eq!(
+        rpc("eth_getTransactionCount", json!(["0xa11ce", "latest"]))["result"],
+        "0x1"
+    );
+
+    rpc("evm_setNextBlockTimestamp", json!(["0xffffffffff"]));
+    assert_eq!(rpc("evm_mine", json!([]))["result"], "0x0");
+    let block = rpc("eth_getBlockByNumber", json!(["latest", false]));
+    assert_eq!(block["result"]["number"], "0x2");
+    assert_eq!(block["result"]["timestamp"], "0xffffffffff");
+    let by_hash = rpc("eth_getBlockByHash", json!([block["result"]["hash"]]));
+    assert_eq!(by_hash["result"], block["result"]);
+
+    let snapshot = rpc("evm_snapshot", json!([]));
+    rpc("anvil_setBalance", json!(["0xb0b", "0x0"]));
+    assert_eq!(
+        rpc("evm_revert", json!([snapshot["result"]]))["result"],
+        true
+    );
+    assert_eq!(rpc("eth_getBalance", json!(["0xb0b"]))["result"], "0x10");
+    assert_eq!(
+        rpc("evm_revert", json!([snapshot["result"]]))["result"],
+        false
+    );
+
+    // Calls see the state the cheats left
+    let output = rpc("eth_call", json!([{ "to": "0xc0de" }, "latest"]));
+    assert!(output["result"].as_str().unwrap().ends_with('7'));
+    let raw = rpc("eth_sendRawTransaction", json!(["0xc0"]));
+    assert_eq!(raw["error"]["code"], -32602);
+}
+
+#[test]
+fn access_lists() {
+    let service = service();
+    let call = json!({ "from": "0xa11ce", "to": "0xc0de" });
+    let output = rpc(&service, "eth_createAccessList", json!([call, "latest"]));
+    assert_eq!(
+        output["result"]["accessList"],
+        json!([{
+            "address": "0x000000000000000000000000000000000000c0de",
+            "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000001"],
+        }])
+    );
+    assert!(output["result"]["gasUsed"]
+        .as_str()
+        .unwrap()
+        .starts_with("0x"));
+    assert_eq!(output["result"].get("error"), None);
+}
@@ -0,0 +1,123 @@
+//! The fixture runner, run as `cargo run` runs it.
+
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+
+fn runner(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_evm"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn fixture(name: &str, asm: &str, stack: &[&str]) -> serde_json::Value {
+    json!({
+        "name": name,
+        "hint": "",
+        "code": { "asm": asm },
+        "expect": { "success": true, "stack": stack },
+    })
+}
+
+#[test]
+fn results_come_out_in_the_order_of_the_tests_whatever_the_threads() {
+    let serial = runner(&["--jobs", "1"]);
+    let parallel = runner(&["--jobs", "8"]);
+    assert!(serial.status.success());
+    assert_eq!(
+        String::from_utf8(parallel.stdout).unwrap(),
+        String::from_utf8(serial.stdout).unwrap()
+    );
+}
+
+// Runs two passing and two failing tests with `args`
+fn run_mixed(name: &str, args: &[&str]) -> Output {
+    let tests = json!([
+        fixture("one", "PUSH1 1", &["0x1"]),
+        fixture("wrong", "PUSH1 1", &["0x2"]),
+        fixture("two", "PUSH1 2", &["0x2"]),
+        fixture("also <wrong>", "PUSH1 3", &["0x2"]),
+    ]);
+    run_tests(name, tests, args)
+}
+
+fn run_tests(name: &str, tests: Value, args: &[&str]) -> Output {
+    let path = std::env::temp_dir().join(format!("evm-{}-{}.json", name, std::process::id()));
+    std::fs::write(&path, tests.to_string()).unwrap();
+    let mut all_args = vec!["--tests", path.to_str().unwrap(), "--jobs", "3"];
+    all_args.extend(args);
+    let output = runner(&all_args);
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn failures_are_summed_up_after_every_test_ran() {
+    let output = run_mixed("summary", &["--fail-fast=false"]);
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let headers: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("Test "))
+        .collect();
+    assert_eq!(
+        headers,
+        [
+            "Test 1 of 4: one",
+            "Test 2 of 4: wrong",
+            "Test 3 of 4: two",
+            "Test 4 of 4: also <wrong>",
+        ]
+    );
+    assert!(stdout.contains("      2  FAIL    wrong\n      4  FAIL    also <wrong>\n"));
+    assert!(stdout.contains("Passed 2 of 4 selected tests, 2 failed\n"));
+}
+
+#[test]
+fn the_summary_is_left_out_when_nothing_failed() {
+    let tests = json!([
+        fixture("one", "PUSH1 1", &["0x1"]),
+        fixture("two", "PUSH1 2", &["0x2"]),
+    ]);
+    let output = run_tests("passing", tests, &["--fail-fast=false"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Summary"));
+    assert!(stdout.contains("Passed 2 of 2 selected tests, 0 failed\n"));
+}
+
+#[test]
+fn json_reports_have_what_each_test_expected_and_got() {
+    let output = run_mixed("json", &["--format", "json", "--fail-fast=false"]);
+    assert!(!output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["passed"], 2);
+    assert_eq!(report["failed"], 2);
+    let wrong = &report["tests"][1];
+    assert_eq!(wrong["name"], "wrong");
+    assert_eq!(wrong["number"], 2);
+    assert_eq!(wrong["passed"], false);
+    assert_eq!(wrong["expected"]["stack"], json!(["0x2"]));
+    assert_eq!(wrong["actual"]["stack"], json!(["0x1"]));
+    assert_eq!(wrong["actual"]["gasUsed"], 21003);
+    assert!(wrong["output"].as_str().unwrap().contains("Expected stack"));
+    assert!(wrong["duration"].as_f64().unwrap() >= 0.0);
+    assert_eq!(report["tests"][0]["passed"], true);
+    assert!(report["tests"][0].get("output").is_none());
+}
+
+#[test]
+fn junit_reports_list_the_failures() {
+    let output = run_mixed("junit", &["--format=junit", "--fail-fast=false"]);
+    assert!(!output.status.success());
+    let xml = String::from_utf8(output.stdout).unwrap();
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains("<testsuite name=\"evm\" tests=\"4\" failures=\"2\""));
+    assert!(xml.contains("<testcase name=\"one\" classname=\"evm\""));
+    assert!(xml.contains("<failure message=\"expected stack [0x2], got [0x1]\">"));
+    assert!(xml.contains("<testcase name=\"also &lt;wrong&gt;\""));
+    assert_eq!(xml.matches("<testcase").count(), 4);
+}
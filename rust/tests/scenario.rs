@@ -0,0 +1,202 @@
+//! Scenarios of deploys and calls, with expectations on each step.
+#![cfg(feature = "std")]
+
+use std::path::PathBuf;
+
+use evm::scenario::{Scenario, ScenarioError};
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+const ALICE: &str = "0x00000000000000000000000000000000000a11ce";
+const BOB: &str = "0x0000000000000000000000000000000000000b0b";
+
+// Init code returning `runtime`
+fn deployable(runtime: &str) -> String {
+    let size = runtime.len() / 2;
+    format!("0x60{:02x}600c60003960{:02x}6000f3{}", size, size, runtime)
+}
+
+fn word(value: u64) -> String {
+    format!("0x{:064x}", value)
+}
+
+fn run(scenario: Value) -> evm::scenario::ScenarioReport {
+    Scenario::from_json(&scenario).unwrap().run().unwrap()
+}
+
+#[test]
+fn steps_see_the_contracts_deployed_before_them() {
+    let report = run(json!({
+        "fork": "Cancun",
+        "accounts": { "alice": { "address": ALICE, "balance": "1000" } },
+        "steps": [
+            // SSTORE(1, 42)
+            { "deploy": deployable("602a600155"), "from": "alice", "as": "b" },
+            // CALL(gas, CALLDATALOAD(4), 0, 0, 0, 0, 0)
+            {
+                "deploy": deployable("600060006000600060006004355af100"),
+                "from": "alice",
+                "as": "a",
+            },
+            {
+                "name": "a pokes b",
+                "call": "a",
+                "from": "alice",
+                "signature": "poke(address)",
+                "args": ["b"],
+                // The slot of b, not that of a
+                "expect": { "storage": { "b": { "0x1": "42" }, "a": { "0x1": "0" } } },
+            },
+        ],
+    }));
+    assert!(report.passed(), "{:?}", report);
+    assert_eq!(report.steps.len(), 3);
+    assert_eq!(report.steps[2].name, "a pokes b");
+    assert!(report.steps[0].address.is_some());
+    assert_ne!(report.steps[0].address, report.steps[1].address);
+}
+
+#[test]
+fn a_toml_scenario_moves_weth_between_named_accounts() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/contracts/weth9.hex");
+    let weth = std::fs::read_to_string(path).unwrap();
+    let scenario = format!(
+        r#"
+fork = "Cancun"
+
+[accounts.alice]
+address = "{ALICE}"
+balance = "1000"
+
+[accounts.bob]
+address = "{BOB}"
+
+[accounts.weth]
+address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+code = "0x{weth}"
+
+[[steps]]
+call = "weth"
+from = "alice"
+signature = "deposit()"
+value = 1000
+expect.balances = {{ alice = "0", weth = "1000" }}
+
+[[steps.expect.logs]]
+address = "weth"
+topics = ["Deposit(address,uint256)", "{alice_topic}"]
+data = "{amount}"
+
+[[steps]]
+call = "weth"
+from = "alice"
+signature = "transfer(address,uint256)"
+args = ["bob", "0x190"]
+expect = {{ returns = "{yes}" }}
+
+[[steps]]
+call = "weth"
+from = "bob"
+signature = "balanceOf(address)"
+args = ["bob"]
+expect = {{ returns = "{bob_balance}" }}
+"#,
+        weth = weth.trim(),
+        alice_topic = word(0xa11ce),
+        amount = word(1000),
+        yes = word(1),
+        bob_balance = word(400),
+    );
+    let report = Scenario::from_toml(&scenario).unwrap().run().unwrap();
+    assert!(report.passed(), "{:?}", report);
+    assert!(report.steps.iter().all(|step| step.success));
+}
+
+#[test]
+fn unmet_expectations_are_reported_and_the_next_steps_still_run() {
+    let report = run(json!({
+        "accounts": { "alice": { "address": ALICE, "balance": "10" } },
+        "steps": [
+            // REVERT(0, 0)
+            { "deploy": deployable("60006000fd"), "from": "alice", "as": "reverter" },
+            {
+                "call": "reverter",
+                "from": "alice",
+                "expect": { "success": false },
+            },
+            {
+                "call": "reverter",
+                "from": "alice",
+                "value": "1",
+                "expect": { "returns": "0x01", "balances": { "alice": "9" } },
+            },
+            // RETURN(0, 32) of a zero word
+            { "deploy": deployable("60206000f3"), "from": "alice", "as": "zero" },
+        ],
+    }));
+    assert!(!report.passed());
+    assert!(report.steps[1].failures.is_empty());
+    assert!(!report.steps[1].success);
+    assert_eq!(
+        report.steps[2].failures,
+        vec![
+            "expected to succeed, failed".to_string(),
+            "returned 0x instead of 0x01".to_string(),
+            "balance of alice is 10 instead of 9".to_string(),
+        ]
+    );
+    assert!(report.steps[3].failures.is_empty());
+    assert_eq!(report.to_json()["passed"], json!(false));
+}
+
+#[test]
+fn arguments_are_encoded_by_the_types_of_the_signature() {
+    // RETURN(0, CALLDATASIZE) of the calldata
+    let echo = deployable("366000600037366000f3");
+    let expected = evm::abi::encode_function(
+        "echo(int256,bool,address)",
+        &[
+            evm::abi::Token::Int(U256::from(5).overflowing_neg().0),
+            evm::abi::Token::Bool(true),
+            evm::abi::Token::Address(U256::from(0xa11ce)),
+        ],
+    );
+    let report = run(json!({
+        "accounts": { "alice": { "address": ALICE } },
+        "steps": [
+            { "deploy": echo, "from": "alice", "as": "echo" },
+            {
+                "call": "echo",
+                "from": "alice",
+                "signature": "echo(int256,bool,address)",
+                "args": ["-5", true, "alice"],
+                "expect": { "returns": format!("0x{}", hex::encode(expected)) },
+            },
+        ],
+    }));
+    assert!(report.passed(), "{:?}", report);
+}
+
+#[test]
+fn steps_that_cannot_be_sent_end_the_run() {
+    let scenario = Scenario::from_json(&json!({
+        "accounts": { "alice": { "address": ALICE } },
+        "steps": [{ "call": "nobody", "from": "alice" }],
+    }))
+    .unwrap();
+    assert_eq!(
+        scenario.run(),
+        Err(ScenarioError::Step {
+            step: "step 1".to_string(),
+            reason: "unknown account nobody".to_string(),
+        })
+    );
+
+    let missing_sender = json!({ "steps": [{ "call": ALICE }] });
+    assert_eq!(
+        Scenario::from_json(&missing_sender),
+        Err(ScenarioError::Invalid("step 1: no from".to_string()))
+    );
+    let both = json!({ "steps": [{ "call": ALICE, "deploy": "0x00", "from": ALICE }] });
+    assert!(Scenario::from_json(&both).is_err());
+}
@@ -0,0 +1,135 @@
+//! SELFDESTRUCT: the balance goes to the beneficiary, the account is deleted
+//! when the transaction ends, and from Cancun only if the same transaction
+//! created it (EIP-6780).
+
+use evm::host::Database;
+use evm::spec::{ChainConfig, SpecId};
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const DEAD: u64 = 0xdead;
+const BENEFICIARY: u64 = 0xbe;
+// PUSH1 0xbe, SELFDESTRUCT
+const DESTRUCTS: &str = "60beff";
+// CALL(gas, 0xdead, 0, 0, 0, 0, 0)
+const CALLS_DEAD: &str = "60008080808061dead5af1";
+
+fn data(spec: SpecId) -> EvmData {
+    let mut data = EvmData {
+        tx_data: Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap()),
+        config: ChainConfig::new(spec),
+        ..Default::default()
+    };
+    data.balances.insert(DEAD.to_string(), U256::from(7));
+    data.codes
+        .insert(DEAD.to_string(), DESTRUCTS.parse().unwrap());
+    data
+}
+
+fn balance(data: &EvmData, address: u64) -> U256 {
+    data.basic(U256::from(address)).unwrap_or_default().balance
+}
+
+#[test]
+fn cancun_moves_the_balance_and_keeps_the_account() {
+    let mut data = data(SpecId::Cancun);
+    let result = evm(hex::decode(CALLS_DEAD).unwrap(), &mut data, true);
+    assert!(result.success);
+
+    assert_eq!(balance(&data, BENEFICIARY), U256::from(7));
+    assert_eq!(balance(&data, DEAD), U256::zero());
+    assert_eq!(
+        data.code(U256::from(DEAD)),
+        Some(hex::decode(DESTRUCTS).unwrap())
+    );
+}
+
+#[test]
+fn before_cancun_the_account_is_deleted_when_the_transaction_ends() {
+    let mut data = data(SpecId::Shanghai);
+    let result = evm(hex::decode(CALLS_DEAD).unwrap(), &mut data, true);
+    assert!(result.success);
+
+    assert_eq!(balance(&data, BENEFICIARY), U256::from(7));
+    assert_eq!(data.basic(U256::from(DEAD)), None);
+    assert_eq!(data.code(U256::from(DEAD)), None);
+}
+
+#[test]
+fn cancun_deletes_accounts_created_in_the_same_transaction() {
+    let mut data = data(SpecId::Cancun);
+    data.balances.insert(0xc0de.to_string(), U256::from(5));
+    // Init code PUSH1 0xbe, SELFDESTRUCT stored at 29..32, then
+    // CREATE(value 5, offset 29, size 3)
+    let code = "6260beff600052 6003601d6005f0";
+    let result = evm(hex::decode(code.replace(' ', "")).unwrap(), &mut data, true);
+    assert!(result.success);
+    let created = result.stack[0];
+    assert!(!created.is_zero());
+
+    assert_eq!(balance(&data, BENEFICIARY), U256::from(5));
+    assert_eq!(data.basic(created), None);
+}
+
+#[test]
+fn a_reverted_selfdestruct_is_not_carried_out() {
+    let mut data = data(SpecId::Shanghai);
+    // Calls 0xdead, then reverts
+    let code = format!("{}600080fd", CALLS_DEAD);
+    let result = evm(hex::decode(code).unwrap(), &mut data, true);
+    assert!(!result.success);
+
+    assert_eq!(balance(&data, BENEFICIARY), U256::zero());
+    assert_eq!(balance(&data, DEAD), U256::from(7));
+    assert_eq!(
+        data.code(U256::from(DEAD)),
+        Some(hex::decode(DESTRUCTS).unwrap())
+    );
+}
+
+#[test]
+fn the_refund_is_gone_from_london() {
+    let mut berlin = data(SpecId::Berlin);
+    let mut london = data(SpecId::London);
+    let code = hex::decode(CALLS_DEAD).unwrap();
+    assert!(evm(&code, &mut berlin, true).gas_refunded > 0);
+    assert_eq!(evm(&code, &mut london, true).gas_refunded, 0);
+}
+
+#[test]
+fn a_deleted_account_loses_its_storage() {
+    let mut data = data(SpecId::Shanghai);
+    data.state.insert(
+        DEAD.to_string(),
+        [(U256::zero(), U256::from(9))].into_iter().collect(),
+    );
+    data.state.insert(
+        BENEFICIARY.to_string(),
+        [(U256::zero(), U256::from(3))].into_iter().collect(),
+    );
+    let result = evm(hex::decode(CALLS_DEAD).unwrap(), &mut data, true);
+    assert!(result.success);
+
+    assert_eq!(data.storage(U256::from(DEAD), U256::zero()), U256::zero());
+    assert!(!data.state.contains_key(&DEAD.to_string()));
+    assert_eq!(
+        data.storage(U256::from(BENEFICIARY), U256::zero()),
+        U256::from(3)
+    );
+}
+
+#[test]
+fn cancun_clears_the_storage_of_accounts_created_in_the_same_transaction() {
+    let mut data = data(SpecId::Cancun);
+    // Init code SSTORE(0, 1), PUSH1 0xbe, SELFDESTRUCT stored at 24..32, then
+    // CREATE(value 0, offset 24, size 8)
+    let code = "676001600055 60beff 600052 6008 6018 6000 f0";
+    let result = evm(hex::decode(code.replace(' ', "")).unwrap(), &mut data, true);
+    assert!(result.success);
+    let created = result.stack[0];
+    assert!(!created.is_zero());
+
+    assert_eq!(data.storage(created, U256::zero()), U256::zero());
+    assert!(!data.state.contains_key(&created.to_string()));
+}
@@ -0,0 +1,174 @@
+//! The signed opcodes against a reference on `i128`, whose values are
+//! sign-extended to 256 bits, plus the edges only 256 bits reach.
+
+use evm::{evm, EvmData};
+use primitive_types::U256;
+use proptest::prelude::*;
+
+const SDIV: u8 = 0x05;
+const SMOD: u8 = 0x07;
+const SIGNEXTEND: u8 = 0x0b;
+const SLT: u8 = 0x12;
+const SGT: u8 = 0x13;
+const SAR: u8 = 0x1d;
+
+fn min() -> U256 {
+    U256::one() << 255
+}
+
+fn minus_one() -> U256 {
+    U256::MAX
+}
+
+fn word(value: i128) -> U256 {
+    let magnitude = U256::from(value.unsigned_abs());
+    if value < 0 {
+        magnitude.overflowing_neg().0
+    } else {
+        magnitude
+    }
+}
+
+// `opcode` with `a` on top of the stack and `b` under it
+fn apply(opcode: u8, a: U256, b: U256) -> U256 {
+    let mut code = vec![0x7f];
+    code.extend_from_slice(&<[u8; 32]>::from(b));
+    code.push(0x7f);
+    code.extend_from_slice(&<[u8; 32]>::from(a));
+    code.push(opcode);
+    let result = evm(code, &mut EvmData::default(), true);
+    assert!(result.success);
+    assert_eq!(result.stack.len(), 1);
+    result.stack[0]
+}
+
+// Truncates toward zero as SDIV does, without the overflow of MIN / -1
+fn reference_sdiv(a: i128, b: i128) -> U256 {
+    if b == 0 {
+        return U256::zero();
+    }
+    let quotient = U256::from(a.unsigned_abs() / b.unsigned_abs());
+    if (a < 0) != (b < 0) {
+        quotient.overflowing_neg().0
+    } else {
+        quotient
+    }
+}
+
+// The sign of the result is the sign of the dividend
+fn reference_smod(a: i128, b: i128) -> U256 {
+    if b == 0 {
+        return U256::zero();
+    }
+    let remainder = U256::from(a.unsigned_abs() % b.unsigned_abs());
+    if a < 0 {
+        remainder.overflowing_neg().0
+    } else {
+        remainder
+    }
+}
+
+fn reference_sar(shift: u32, value: i128) -> U256 {
+    word(value >> shift.min(127))
+}
+
+// Sign-extends from byte `k`, counted from the least significant
+fn reference_signextend(k: u32, value: i128) -> U256 {
+    if k >= 15 {
+        return word(value);
+    }
+    let unused = 128 - 8 * (k + 1);
+    word((value << unused) >> unused)
+}
+
+proptest! {
+    #[test]
+    fn sdiv_matches_the_reference(a: i128, b: i128) {
+        prop_assert_eq!(apply(SDIV, word(a), word(b)), reference_sdiv(a, b));
+    }
+
+    #[test]
+    fn sdiv_of_small_values_matches_the_reference(a in -1000i128..1000, b in -20i128..20) {
+        prop_assert_eq!(apply(SDIV, word(a), word(b)), reference_sdiv(a, b));
+    }
+
+    #[test]
+    fn smod_matches_the_reference(a: i128, b: i128) {
+        prop_assert_eq!(apply(SMOD, word(a), word(b)), reference_smod(a, b));
+    }
+
+    #[test]
+    fn smod_of_small_values_matches_the_reference(a in -1000i128..1000, b in -20i128..20) {
+        prop_assert_eq!(apply(SMOD, word(a), word(b)), reference_smod(a, b));
+    }
+
+    #[test]
+    fn slt_and_sgt_match_the_reference(a: i128, b: i128) {
+        prop_assert_eq!(apply(SLT, word(a), word(b)), U256::from((a < b) as u8));
+        prop_assert_eq!(apply(SGT, word(a), word(b)), U256::from((a > b) as u8));
+    }
+
+    #[test]
+    fn slt_and_sgt_match_the_reference_on_close_values(a in -50i128..50, b in -50i128..50) {
+        prop_assert_eq!(apply(SLT, word(a), word(b)), U256::from((a < b) as u8));
+        prop_assert_eq!(apply(SGT, word(a), word(b)), U256::from((a > b) as u8));
+    }
+
+    #[test]
+    fn sar_matches_the_reference(shift in 0u32..300, value: i128) {
+        let expected = reference_sar(shift, value);
+        prop_assert_eq!(apply(SAR, U256::from(shift), word(value)), expected);
+    }
+
+    #[test]
+    fn signextend_matches_the_reference(k in 0u32..40, value: i128) {
+        let expected = reference_signextend(k, value);
+        prop_assert_eq!(apply(SIGNEXTEND, U256::from(k), word(value)), expected);
+    }
+
+    #[test]
+    fn signed_comparisons_are_antisymmetric(a: [u8; 32], b: [u8; 32]) {
+        let (a, b) = (U256::from(a), U256::from(b));
+        prop_assert_eq!(apply(SLT, a, b), apply(SGT, b, a));
+        if a != b {
+            prop_assert_eq!(apply(SLT, a, b) + apply(SLT, b, a), U256::one());
+        }
+    }
+}
+
+#[test]
+fn min_divided_by_minus_one_overflows_to_min() {
+    assert_eq!(apply(SDIV, min(), minus_one()), min());
+    assert_eq!(apply(SMOD, min(), minus_one()), U256::zero());
+    assert_eq!(apply(SDIV, min(), U256::one()), min());
+    assert_eq!(apply(SDIV, minus_one(), min()), U256::zero());
+}
+
+#[test]
+fn min_and_max_compare_as_signed() {
+    let max = U256::MAX >> 1;
+    assert_eq!(apply(SLT, min(), max), U256::one());
+    assert_eq!(apply(SGT, max, min()), U256::one());
+    assert_eq!(apply(SLT, min(), minus_one()), U256::one());
+    assert_eq!(apply(SGT, U256::zero(), minus_one()), U256::one());
+}
+
+#[test]
+fn sar_by_255_and_beyond_leaves_the_sign() {
+    assert_eq!(apply(SAR, U256::from(255), min()), minus_one());
+    assert_eq!(apply(SAR, U256::from(255), U256::MAX >> 1), U256::zero());
+    assert_eq!(apply(SAR, U256::from(256), min()), minus_one());
+    assert_eq!(apply(SAR, U256::from(256), U256::one()), U256::zero());
+    assert_eq!(apply(SAR, U256::MAX, minus_one()), minus_one());
+    assert_eq!(apply(SAR, U256::from(254), min()), minus_one() - 1);
+}
+
+#[test]
+fn signextend_from_the_top_byte_keeps_the_word() {
+    assert_eq!(apply(SIGNEXTEND, U256::from(31), min()), min());
+    assert_eq!(apply(SIGNEXTEND, U256::from(30), min()), U256::zero());
+    assert_eq!(
+        apply(SIGNEXTEND, U256::MAX, U256::from(0xff)),
+        U256::from(0xff)
+    );
+}
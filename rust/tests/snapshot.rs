@@ -0,0 +1,91 @@
+//! Snapshots of the state between transactions, and reverting to them.
+
+use evm::host::Database;
+use evm::journal::SnapshotId;
+use evm::spec::{ChainConfig, SpecId};
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+// SSTORE(1, CALLVALUE)
+const STORES_VALUE: &str = "34600155";
+const DEAD: u64 = 0xdead;
+
+fn data() -> EvmData {
+    let mut data = EvmData {
+        config: ChainConfig::new(SpecId::Shanghai),
+        ..Default::default()
+    };
+    data.balances.insert(DEAD.to_string(), U256::from(7));
+    data.codes
+        .insert(DEAD.to_string(), "60beff".parse().unwrap());
+    data
+}
+
+fn store(data: &mut EvmData, value: u64) {
+    data.tx_data = Some(
+        serde_json::from_value::<TxData>(json!({
+            "to": "0xc0de",
+            "value": format!("{:#x}", value),
+        }))
+        .unwrap(),
+    );
+    let result = evm(hex::decode(STORES_VALUE).unwrap(), data, true);
+    assert!(result.success);
+}
+
+fn slot(data: &EvmData) -> U256 {
+    data.storage(U256::from(0xc0de), U256::one())
+}
+
+#[test]
+fn revert_undoes_the_transactions_since_the_snapshot() {
+    let mut data = data();
+    store(&mut data, 1);
+    let first = data.snapshot();
+    store(&mut data, 2);
+    let second = data.snapshot();
+    store(&mut data, 3);
+    assert_eq!(slot(&data), U256::from(3));
+
+    assert!(data.revert(second));
+    assert_eq!(slot(&data), U256::from(2));
+    // Reverting closes the snapshot
+    assert!(!data.revert(second));
+
+    assert!(data.revert(first));
+    assert_eq!(slot(&data), U256::one());
+    assert!(data.snapshots.history.is_empty());
+    assert!(!data.revert(SnapshotId(7)));
+}
+
+#[test]
+fn revert_closes_later_snapshots() {
+    let mut data = data();
+    let first = data.snapshot();
+    store(&mut data, 1);
+    let second = data.snapshot();
+    assert!(data.revert(first));
+    assert!(!data.revert(second));
+    assert_eq!(slot(&data), U256::zero());
+}
+
+#[test]
+fn deleted_accounts_come_back() {
+    let mut data = data();
+    let snapshot = data.snapshot();
+    data.tx_data = Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap());
+    // CALL(gas, 0xdead, 0, 0, 0, 0, 0), which self-destructs
+    let result = evm(
+        hex::decode("60008080808061dead5af1").unwrap(),
+        &mut data,
+        true,
+    );
+    assert!(result.success);
+    assert_eq!(data.basic(U256::from(DEAD)), None);
+
+    assert!(data.revert(snapshot));
+    assert_eq!(data.basic(U256::from(DEAD)).unwrap().balance, U256::from(7));
+    assert_eq!(data.code(U256::from(DEAD)), Some(vec![0x60, 0xbe, 0xff]));
+    assert_eq!(data.basic(U256::from(0xbe)), None);
+}
@@ -0,0 +1,20 @@
+//! Which opcodes each fork defines.
+
+use evm::spec::{is_opcode_enabled, opcode_introduced_in, SpecId};
+
+#[test]
+fn opcodes_are_enabled_from_the_fork_that_introduced_them() {
+    assert_eq!(opcode_introduced_in(0x01), Some(SpecId::Frontier));
+    assert_eq!(opcode_introduced_in(0x5f), Some(SpecId::Shanghai));
+    assert!(!is_opcode_enabled(SpecId::London, 0x5f));
+    assert!(is_opcode_enabled(SpecId::Shanghai, 0x5f));
+    assert!(is_opcode_enabled(SpecId::Cancun, 0x5e));
+}
+
+#[test]
+fn undefined_opcodes_are_enabled_in_no_fork() {
+    for opcode in [0x0c, 0x21, 0x4b, 0xa5, 0xef, 0xf6, 0xfb] {
+        assert_eq!(opcode_introduced_in(opcode), None, "{:#x}", opcode);
+        assert!(!is_opcode_enabled(SpecId::Prague, opcode), "{:#x}", opcode);
+    }
+}
@@ -0,0 +1,87 @@
+//! The fixed-capacity operand stack.
+
+use evm::stack::{Stack, StackError, STACK_LIMIT};
+use evm::{evm, EvmData};
+use primitive_types::U256;
+
+#[test]
+fn push_pop_and_peek() {
+    let mut stack = Stack::new();
+    assert_eq!(stack.pop(), Err(StackError::Underflow));
+    stack.push(U256::from(1)).unwrap();
+    stack.push(U256::from(2)).unwrap();
+    assert_eq!(stack.peek(0), Ok(U256::from(2)));
+    assert_eq!(stack.peek(1), Ok(U256::from(1)));
+    assert_eq!(stack.peek(2), Err(StackError::Underflow));
+    assert_eq!(stack.as_slice(), &[U256::from(1), U256::from(2)]);
+    assert_eq!(stack.pop(), Ok(U256::from(2)));
+    assert_eq!(stack.len(), 1);
+}
+
+#[test]
+fn push_past_the_limit_overflows() {
+    let mut stack = Stack::new();
+    for i in 0..STACK_LIMIT {
+        stack.push(U256::from(i)).unwrap();
+    }
+    assert_eq!(stack.push(U256::zero()), Err(StackError::Overflow));
+    assert_eq!(stack.len(), STACK_LIMIT);
+    assert_eq!(stack.dup(1), Err(StackError::Overflow));
+}
+
+#[test]
+fn dup_and_swap() {
+    let mut stack = Stack::new();
+    for i in 1..=3 {
+        stack.push(U256::from(i)).unwrap();
+    }
+    stack.dup(3).unwrap();
+    assert_eq!(stack.peek(0), Ok(U256::from(1)));
+    stack.swap(2).unwrap();
+    assert_eq!(
+        stack.as_slice(),
+        &[U256::from(1), U256::from(1), U256::from(3), U256::from(2)]
+    );
+    assert_eq!(stack.dup(5), Err(StackError::Underflow));
+    assert_eq!(stack.swap(4), Err(StackError::Underflow));
+    assert_eq!(stack.dup(0), Err(StackError::Underflow));
+}
+
+#[test]
+fn swaps_and_dups_count_from_the_top_of_deep_stacks() {
+    let mut stack = Stack::new();
+    for i in 0..1000 {
+        stack.push(U256::from(i)).unwrap();
+    }
+    for n in 1..=16 {
+        let mut swapped = stack.to_vec();
+        swapped.swap(999, 999 - n);
+        let mut deep = Stack::new();
+        for word in stack.as_slice() {
+            deep.push(*word).unwrap();
+        }
+        deep.swap(n).unwrap();
+        assert_eq!(deep.as_slice(), swapped.as_slice(), "SWAP{n}");
+
+        deep.dup(n).unwrap();
+        assert_eq!(deep.peek(0), Ok(swapped[1000 - n]), "DUP{n}");
+        assert_eq!(deep.len(), 1001);
+    }
+}
+
+#[test]
+fn swap_opcodes_on_a_deep_stack() {
+    // PUSH1 0 to PUSH1 19, then SWAP1 and SWAP16
+    let mut code: Vec<u8> = (0..20).flat_map(|i| [0x60, i]).collect();
+    code.extend([0x90, 0x9f, 0x00]);
+    let result = evm(code, &mut EvmData::default(), true);
+    assert!(result.success);
+
+    // The result has the top of the stack first
+    let mut expected: Vec<U256> = (0..20).rev().map(U256::from).collect();
+    expected.swap(0, 1);
+    expected.swap(0, 16);
+    assert_eq!(result.stack, expected);
+    assert_eq!(result.stack[0], U256::from(3));
+    assert_eq!(result.stack[16], U256::from(18));
+}
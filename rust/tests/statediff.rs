@@ -0,0 +1,99 @@
+//! The state diff of a transaction, as `trace_replayTransaction` gives it.
+
+use evm::spec::{ChainConfig, SpecId};
+use evm::statediff::Diff;
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+fn data(spec: SpecId) -> EvmData {
+    let mut data = EvmData {
+        tx_data: Some(serde_json::from_value::<TxData>(json!({ "to": "0xc0de" })).unwrap()),
+        config: ChainConfig::new(spec),
+        ..Default::default()
+    };
+    data.balances.insert(0xdead.to_string(), U256::from(7));
+    // PUSH1 0xbe, SELFDESTRUCT
+    data.codes
+        .insert(0xdead.to_string(), "60beff".parse().unwrap());
+    data.balances.insert(0xa11ce.to_string(), U256::from(1));
+    data
+}
+
+#[test]
+fn deleted_and_new_accounts_are_died_and_born() {
+    let mut data = data(SpecId::Shanghai);
+    // CALL(gas, 0xdead, 0, 0, 0, 0, 0)
+    let code = hex::decode("60008080808061dead5af1").unwrap();
+    let diff = evm(code, &mut data, true).state_diff;
+
+    let dead = &diff.accounts[&U256::from(0xdead)];
+    assert_eq!(dead.balance, Diff::Died(U256::from(7)));
+    assert_eq!(dead.code, Diff::Died(vec![0x60, 0xbe, 0xff]));
+    let beneficiary = &diff.accounts[&U256::from(0xbe)];
+    assert_eq!(beneficiary.balance, Diff::Born(U256::from(7)));
+    assert_eq!(beneficiary.code, Diff::Born(vec![]));
+    // Accounts the transaction did not change are left out
+    assert!(!diff.accounts.contains_key(&U256::from(0xa11ce)));
+    assert_eq!(diff.accounts.len(), 2);
+}
+
+#[test]
+fn changed_fields_and_slots_have_their_values_before_and_after() {
+    let mut data = data(SpecId::Cancun);
+    data.state.insert(
+        0xc0de.to_string(),
+        [(U256::one(), U256::from(0x2a))].into_iter().collect(),
+    );
+    // SSTORE(1, 0x2b), SSTORE(2, 2), SSTORE(2, 0), and back to 0x2a
+    let code = hex::decode("602b60015560026002556000600255602a600155").unwrap();
+    let diff = evm(code, &mut data, true).state_diff;
+    assert!(diff.is_empty());
+
+    // Moving the balance of 0xdead, which is kept from Cancun
+    let code = hex::decode("602b60015560008080808061dead5af1").unwrap();
+    let diff = evm(code, &mut data, true).state_diff;
+    assert_eq!(
+        diff.to_json(),
+        json!({
+            "0x00000000000000000000000000000000000000be": {
+                "balance": { "+": "0x7" }, "nonce": { "+": "0x0" }, "code": { "+": "0x" },
+                "storage": {},
+            },
+            "0x000000000000000000000000000000000000c0de": {
+                "balance": "=", "nonce": "=", "code": "=",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": {
+                        "*": {
+                            "from": "0x000000000000000000000000000000000000000000000000000000000000002a",
+                            "to": "0x000000000000000000000000000000000000000000000000000000000000002b",
+                        },
+                    },
+                },
+            },
+            "0x000000000000000000000000000000000000dead": {
+                "balance": { "*": { "from": "0x7", "to": "0x0" } },
+                "nonce": "=", "code": "=", "storage": {},
+            },
+        })
+    );
+}
+
+#[test]
+fn slots_show_under_the_account_that_wrote_them() {
+    let mut data = data(SpecId::Cancun);
+    // SSTORE(1, 7)
+    data.codes
+        .insert(0xbeef.to_string(), "6007600155".parse().unwrap());
+    // SSTORE(1, 0x2b), CALL(gas, 0xbeef, 0, 0, 0, 0, 0)
+    let code = hex::decode("602b60015560008080808061beef5af1").unwrap();
+    let diff = evm(code, &mut data, true).state_diff;
+
+    let slot = |address: u64| diff.accounts[&U256::from(address)].storage[&U256::one()].clone();
+    let changed = |to: u64| Diff::Changed {
+        from: U256::zero(),
+        to: U256::from(to),
+    };
+    assert_eq!(slot(0xc0de), changed(0x2b));
+    assert_eq!(slot(0xbeef), changed(7));
+}
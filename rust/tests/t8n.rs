@@ -0,0 +1,308 @@
+//! State transitions in the JSON of geth's `evm t8n`.
+#![cfg(feature = "std")]
+
+use evm::deploy::{MAX_CODE_SIZE, MAX_INITCODE_SIZE};
+use evm::spec::SpecId;
+use evm::t8n::{alloc_json, fork, transition, T8nError};
+use serde_json::{json, Value};
+
+const SENDER: &str = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+const CONTRACT: &str = "0x0000000000000000000000000000000000001000";
+const COINBASE: &str = "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba";
+const SLOT: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+fn alloc() -> Value {
+    json!({
+        SENDER: { "balance": "0x1000000000" },
+        // SSTORE(1, 42)
+        CONTRACT: { "code": "0x602a600155" },
+    })
+}
+
+fn env() -> Value {
+    json!({
+        "currentCoinbase": COINBASE,
+        "currentGasLimit": "0x1000000",
+        "currentNumber": "0x1",
+        "currentTimestamp": "0x3e8",
+        "currentBaseFee": "0x7",
+        "currentRandom": "0x0",
+    })
+}
+
+fn tx(nonce: u64, to: &str, gas: u64) -> Value {
+    json!({
+        "sender": SENDER,
+        "nonce": format!("{:#x}", nonce),
+        "gasPrice": "0xa",
+        "gas": format!("{:#x}", gas),
+        "to": to,
+        "value": "0x1",
+        "input": "0x",
+        "v": "0x1b",
+        "r": "0x1",
+        "s": "0x1",
+    })
+}
+
+#[test]
+fn a_call_moves_value_charges_gas_and_writes_storage() {
+    let output = transition(
+        &alloc(),
+        &env(),
+        &json!([tx(0, CONTRACT, 100_000)]),
+        SpecId::London,
+        1,
+    )
+    .unwrap();
+    // 21000 for the transaction, 22100 for a cold SSTORE from zero, 6 for the pushes
+    let used = 43106u64;
+    assert_eq!(output.result["gasUsed"], json!(format!("{:#x}", used)));
+    assert_eq!(output.result["receipts"][0]["status"], json!("0x1"));
+    assert_eq!(output.result.get("rejected"), None);
+
+    let alloc = alloc_json(&output.alloc);
+    assert_eq!(alloc[CONTRACT]["balance"], json!("0x1"));
+    assert_eq!(
+        alloc[CONTRACT]["storage"][SLOT],
+        json!(format!("0x{:064x}", 42))
+    );
+    assert_eq!(alloc[SENDER]["nonce"], json!("0x1"));
+    assert_eq!(
+        alloc[SENDER]["balance"],
+        json!(format!("{:#x}", 0x1000000000u64 - used * 10 - 1))
+    );
+    // The tip above the base fee
+    assert_eq!(
+        alloc[COINBASE]["balance"],
+        json!(format!("{:#x}", used * 3))
+    );
+}
+
+#[test]
+fn transactions_that_cannot_be_included_are_rejected() {
+    let txs = json!([
+        tx(0, CONTRACT, 100_000),
+        tx(5, CONTRACT, 100_000),
+        tx(1, CONTRACT, 20_000),
+        { "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8" },
+    ]);
+    let output = transition(&alloc(), &env(), &txs, SpecId::London, 1).unwrap();
+    let rejected = output.result["rejected"].as_array().unwrap();
+    let indices: Vec<_> = rejected
+        .iter()
+        .map(|entry| entry["index"].clone())
+        .collect();
+    assert_eq!(indices, [json!(1), json!(2), json!(3)]);
+    assert!(rejected[0]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("nonce too high"));
+    assert!(rejected[1]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("intrinsic gas too low"));
+    assert_eq!(output.result["receipts"].as_array().unwrap().len(), 1);
+
+    let alloc = alloc_json(&output.alloc);
+    assert_eq!(alloc[SENDER]["nonce"], json!("0x1"));
+}
+
+#[test]
+fn dynamic_fee_transactions_burn_the_base_fee() {
+    let mut tx = tx(0, CONTRACT, 100_000);
+    let fields = tx.as_object_mut().unwrap();
+    fields.remove("gasPrice");
+    fields.insert("type".to_string(), json!("0x2"));
+    // Pays the base fee of 7 and a tip of 3
+    fields.insert("maxFeePerGas".to_string(), json!("0x14"));
+    fields.insert("maxPriorityFeePerGas".to_string(), json!("0x3"));
+    let mut tip_above_max = tx.clone();
+    tip_above_max["maxPriorityFeePerGas"] = json!("0x15");
+    let mut below_base_fee = tx.clone();
+    below_base_fee["maxFeePerGas"] = json!("0x6");
+    below_base_fee["maxPriorityFeePerGas"] = json!("0x0");
+
+    let txs = json!([tip_above_max, below_base_fee, tx]);
+    let output = transition(&alloc(), &env(), &txs, SpecId::London, 1).unwrap();
+    let rejected = output.result["rejected"].as_array().unwrap();
+    assert!(rejected[0]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("max priority fee per gas higher than max fee per gas"));
+    assert!(rejected[1]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("max fee per gas less than block base fee"));
+    let receipt = &output.result["receipts"][0];
+    assert_eq!(receipt["type"], json!("0x2"));
+
+    let used = 43106u64;
+    let alloc = alloc_json(&output.alloc);
+    assert_eq!(
+        alloc[SENDER]["balance"],
+        json!(format!("{:#x}", 0x1000000000u64 - used * 10 - 1))
+    );
+    assert_eq!(
+        alloc[COINBASE]["balance"],
+        json!(format!("{:#x}", used * 3))
+    );
+}
+
+#[cfg(feature = "k256")]
+#[test]
+fn senders_are_recovered_from_the_signature() {
+    let mut signed = tx(0, CONTRACT, 100_000);
+    signed.as_object_mut().unwrap().remove("sender");
+    signed["secretKey"] =
+        json!("0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8");
+    let mut forged = tx(1, CONTRACT, 100_000);
+    forged.as_object_mut().unwrap().remove("sender");
+    forged["r"] = json!("0x0");
+    let output = transition(
+        &alloc(),
+        &env(),
+        &json!([signed, forged]),
+        SpecId::London,
+        1,
+    )
+    .unwrap();
+    assert_eq!(output.result["receipts"].as_array().unwrap().len(), 1);
+    let rejected = output.result["rejected"].as_array().unwrap();
+    assert_eq!(rejected[0]["index"], json!(1));
+    assert_eq!(rejected[0]["error"], json!("invalid signature"));
+
+    let alloc = alloc_json(&output.alloc);
+    assert_eq!(alloc[SENDER]["nonce"], json!("0x1"));
+}
+
+#[test]
+fn a_creation_deploys_the_returned_code() {
+    // Returns the single byte 0xfe
+    let mut create = tx(0, "", 100_000);
+    create["to"] = Value::Null;
+    create["input"] = json!("0x60fe60005360016000f3");
+    let output = transition(&alloc(), &env(), &json!([create]), SpecId::London, 1).unwrap();
+    let address = output.result["receipts"][0]["contractAddress"]
+        .as_str()
+        .unwrap();
+    // The address of the first creation of this sender
+    assert_eq!(address, "0x6295ee1b4f6dd65047762f924ecd367c17eabf8f");
+    let alloc = alloc_json(&output.alloc);
+    assert_eq!(alloc[address]["code"], json!("0xfe"));
+    assert_eq!(alloc[address]["balance"], json!("0x1"));
+}
+
+#[test]
+fn forks_go_by_their_geth_names() {
+    assert_eq!(fork("ConstantinopleFix"), Some(SpecId::Petersburg));
+    assert_eq!(fork("Paris"), Some(SpecId::Merge));
+    assert_eq!(fork("Homestead"), Some(SpecId::Homestead));
+    assert_eq!(fork("Olympic"), None);
+}
+
+#[test]
+fn a_malformed_alloc_is_an_error() {
+    let error = transition(&json!([]), &env(), &json!([]), SpecId::London, 1).unwrap_err();
+    assert!(matches!(error, T8nError::InvalidAlloc(_)));
+}
+
+#[test]
+fn numbers_above_64_bits_are_errors_not_panics() {
+    const TWO_TO_64: &str = "0x10000000000000000";
+    let mut large_gas_limit = env();
+    large_gas_limit["currentGasLimit"] = json!(TWO_TO_64);
+    let error = transition(&alloc(), &large_gas_limit, &json!([]), SpecId::London, 1).unwrap_err();
+    assert!(matches!(error, T8nError::InvalidEnv(_)));
+
+    let mut large_nonce = alloc();
+    large_nonce[SENDER]["nonce"] = json!(TWO_TO_64);
+    let error = transition(&large_nonce, &env(), &json!([]), SpecId::London, 1).unwrap_err();
+    assert!(matches!(error, T8nError::InvalidAlloc(_)));
+
+    let txs: Vec<_> = ["nonce", "gas", "chainId"]
+        .into_iter()
+        .map(|field| {
+            let mut tx = tx(0, CONTRACT, 100_000);
+            tx[field] = json!(TWO_TO_64);
+            tx
+        })
+        .collect();
+    let output = transition(&alloc(), &env(), &json!(txs), SpecId::London, 1).unwrap();
+    let rejected = output.result["rejected"].as_array().unwrap();
+    assert_eq!(rejected.len(), 3);
+    for (entry, field) in rejected.iter().zip(["nonce", "gas", "chainId"]) {
+        assert_eq!(
+            entry["error"],
+            json!(format!("{} is above 2^64 - 1", field))
+        );
+    }
+}
+
+#[test]
+fn storage_stays_with_its_account() {
+    const CALLER: &str = "0x00000000000000000000000000000000000000aa";
+    const CALLEE: &str = "0x00000000000000000000000000000000000000cc";
+    let zero = format!("0x{:064x}", 0);
+    let alloc = json!({
+        SENDER: { "balance": "0x1000000000" },
+        // SSTORE(1, SLOAD(0)), then CALL(gas, 0xcc, 5, 0, 0, 0, 0)
+        CALLER: {
+            "balance": "0x10",
+            "code": "0x6000546001556000808080600560cc5af1",
+            "storage": { "0x0": "0x7" },
+        },
+        // SSTORE(1, SLOAD(0))
+        CALLEE: { "code": "0x600054600155", "storage": { "0x0": "0x9" } },
+    });
+    let output = transition(
+        &alloc,
+        &env(),
+        &json!([tx(0, CALLER, 200_000)]),
+        SpecId::Cancun,
+        1,
+    )
+    .unwrap();
+    assert_eq!(output.result["receipts"][0]["status"], json!("0x1"));
+
+    let alloc = alloc_json(&output.alloc);
+    let word = |value: u64| json!(format!("0x{:064x}", value));
+    assert_eq!(alloc[CALLER]["storage"][&zero], word(7));
+    assert_eq!(alloc[CALLER]["storage"][SLOT], word(7));
+    assert_eq!(alloc[CALLEE]["storage"][&zero], word(9));
+    assert_eq!(alloc[CALLEE]["storage"][SLOT], word(9));
+    // 0x10 and the value of the transaction, less what went on
+    assert_eq!(alloc[CALLER]["balance"], json!("0xc"));
+    assert_eq!(alloc[CALLEE]["balance"], json!("0x5"));
+}
+
+#[test]
+fn init_code_is_paid_for_and_limited_from_shanghai() {
+    let mut create = tx(0, "", 100_000);
+    create["to"] = Value::Null;
+    create["input"] = json!("0x60fe60005360016000f3");
+    let gas_used = |spec| {
+        let output = transition(&alloc(), &env(), &json!([create.clone()]), spec, 1).unwrap();
+        let used = output.result["gasUsed"].as_str().unwrap().to_string();
+        u64::from_str_radix(used.trim_start_matches("0x"), 16).unwrap()
+    };
+    // Ten bytes make one word
+    assert_eq!(gas_used(SpecId::Shanghai), gas_used(SpecId::London) + 2);
+
+    create["input"] = json!(format!("0x{}", "00".repeat(MAX_INITCODE_SIZE + 1)));
+    create["gas"] = json!("0x100000");
+    let output = transition(&alloc(), &env(), &json!([create]), SpecId::Shanghai, 1).unwrap();
+    let error = output.result["rejected"][0]["error"].as_str().unwrap();
+    assert!(error.starts_with("max initcode size exceeded"), "{}", error);
+}
+
+#[test]
+fn a_creation_over_the_code_size_limit_fails() {
+    // RETURN(0, MAX_CODE_SIZE + 1)
+    let mut create = tx(0, "", 6_000_000);
+    create["to"] = Value::Null;
+    create["input"] = json!(format!("0x61{:04x}6000f3", MAX_CODE_SIZE + 1));
+    let output = transition(&alloc(), &env(), &json!([create]), SpecId::London, 1).unwrap();
+    assert_eq!(output.result["receipts"][0]["status"], json!("0x0"));
+    assert_eq!(output.result["gasUsed"], json!(format!("{:#x}", 6_000_000)));
+}
@@ -0,0 +1,138 @@
+//! Diffing EIP-3155 traces, ours against ones written the way geth writes
+//! them.
+#![cfg(feature = "std")]
+
+use evm::tracediff::{diff, parse_trace, TraceDiffError};
+use evm::tracer::StreamTracer;
+use evm::{evm_with_inspector, EvmData};
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+// PUSH1 2, PUSH1 3, ADD, PUSH1 0, SSTORE
+const CODE: &str = "600260030160005500";
+
+fn our_trace() -> String {
+    let mut tracer = StreamTracer::new(vec![]);
+    evm_with_inspector(
+        hex::decode(CODE).unwrap(),
+        &mut EvmData::default(),
+        true,
+        &mut tracer,
+    );
+    String::from_utf8(tracer.finish().unwrap()).unwrap()
+}
+
+// The same steps with hex quantities, gas and a summary line, as geth has
+// them
+fn geth_trace(text: &str, edit: impl Fn(usize, &mut Value)) -> String {
+    let mut lines = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let mut step: Value = serde_json::from_str(line).unwrap();
+        let stack: Vec<String> = step["stack"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                format!(
+                    "0x{:064x}",
+                    evm::hexutil::parse_u256(item.as_str().unwrap()).unwrap()
+                )
+            })
+            .collect();
+        step["stack"] = json!(stack);
+        step["gas"] = json!(format!("{:#x}", 100_000 - 3 * index));
+        step["gasCost"] = json!("0x3");
+        edit(index, &mut step);
+        lines.push(step.to_string());
+    }
+    lines.push(json!({ "output": "", "gasUsed": "0x5" }).to_string());
+    lines.join("\n")
+}
+
+#[test]
+fn the_same_execution_gives_no_divergence() {
+    let ours = parse_trace(&our_trace()).unwrap();
+    let theirs = parse_trace(&geth_trace(&our_trace(), |_, _| {})).unwrap();
+    assert_eq!(ours.len(), 6);
+    assert_eq!(theirs.len(), 6);
+    assert_eq!(diff(&ours, &theirs), None);
+}
+
+#[test]
+fn the_first_differing_step_is_reported_with_the_one_before() {
+    let ours = parse_trace(&our_trace()).unwrap();
+    // ADD leaves 6 instead of 5
+    let theirs = geth_trace(&our_trace(), |index, step| {
+        if index == 3 {
+            step["stack"] = json!(["0x6"]);
+        }
+    });
+    let divergence = diff(&ours, &parse_trace(&theirs).unwrap()).unwrap();
+    assert_eq!(divergence.step, 3);
+    assert_eq!(divergence.fields, vec!["stack"]);
+    assert_eq!(divergence.previous.as_ref().unwrap().op, 0x01);
+    assert_eq!(
+        divergence.stack,
+        vec![(0, Some(U256::from(5)), Some(U256::from(6)))]
+    );
+    let report = divergence.to_string();
+    assert!(
+        report.starts_with("Traces diverge at step 3: stack\n"),
+        "{}",
+        report
+    );
+    assert!(report.contains("  after: pc 4 ADD at depth 1"));
+    assert!(report.contains("  stack[0] from the top: 0x5 != 0x6\n"));
+}
+
+#[test]
+fn gas_is_compared_when_both_traces_have_it() {
+    let with_gas = |cost: &'static str| {
+        parse_trace(&geth_trace(&our_trace(), move |index, step| {
+            if index == 2 {
+                step["gasCost"] = json!(cost);
+                step["gas"] = json!("0x1");
+            }
+        }))
+        .unwrap()
+    };
+    let divergence = diff(&with_gas("0x3"), &with_gas("0x5")).unwrap();
+    assert_eq!(divergence.fields, vec!["gasCost"]);
+    let divergence = diff(
+        &with_gas("0x3"),
+        &parse_trace(&geth_trace(&our_trace(), |_, _| {})).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(divergence.fields, vec!["gas"]);
+    assert!(divergence
+        .to_string()
+        .contains("  gas: 1 != 99994 (-99993)\n"));
+}
+
+#[test]
+fn a_trace_that_stops_early_diverges_where_it_ends() {
+    let ours = parse_trace(&our_trace()).unwrap();
+    let divergence = diff(&ours, &ours[..4]).unwrap();
+    assert_eq!(divergence.step, 4);
+    assert_eq!(divergence.fields, vec!["length"]);
+    assert_eq!(divergence.right, None);
+    assert!(divergence.to_string().contains("  right: trace ended\n"));
+}
+
+#[test]
+fn unreadable_lines_are_errors() {
+    assert_eq!(
+        parse_trace("{\"pc\":0,\"op\":0}\nnot json"),
+        Err(TraceDiffError::Invalid {
+            line: 2,
+            reason: "expected ident at line 1 column 2".to_string()
+        })
+    );
+    assert_eq!(
+        parse_trace("{\"pc\":0,\"op\":\"0x100\"}"),
+        Err(TraceDiffError::Invalid {
+            line: 1,
+            reason: "invalid op".to_string()
+        })
+    );
+}
@@ -0,0 +1,103 @@
+//! Trie roots against vectors from the Ethereum trie tests and roots
+//! computed with other client implementations.
+#![cfg(feature = "std")]
+
+use evm::trie::{
+    keccak, ordered_trie_root, secure_trie_root, state_root, storage_root, trie_root, TrieAccount,
+    EMPTY_CODE_HASH, EMPTY_ROOT,
+};
+use primitive_types::{H256, U256};
+
+fn h256(hex: &str) -> H256 {
+    hex.parse().unwrap()
+}
+
+#[test]
+fn empty_roots() {
+    assert_eq!(trie_root(Vec::<(&[u8], &[u8])>::new()), EMPTY_ROOT);
+    assert_eq!(storage_root([(U256::one(), U256::zero())]), EMPTY_ROOT);
+    assert_eq!(keccak(&[]), EMPTY_CODE_HASH);
+}
+
+#[test]
+fn single_item() {
+    let root = trie_root([(
+        "A",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+    )]);
+    assert_eq!(
+        root,
+        h256("9fde5d73dc512e146997ff39e04767035149770637faae51d08899fb03940421")
+    );
+}
+
+#[test]
+fn branches_and_extensions_in_any_order() {
+    let expected = h256("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3");
+    let entries = [
+        ("doe", "reindeer"),
+        ("dog", "puppy"),
+        ("dogglesworth", "cat"),
+    ];
+    assert_eq!(trie_root(entries), expected);
+    assert_eq!(trie_root(entries.into_iter().rev()), expected);
+
+    let root = trie_root([("foo", "bar"), ("food", "bass")]);
+    assert_eq!(
+        root,
+        h256("17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3")
+    );
+}
+
+#[test]
+fn removed_and_overwritten_keys() {
+    let root = trie_root([
+        ("do", "verb"),
+        ("horse", "stallion"),
+        ("doge", "coin"),
+        ("dog", "puppy"),
+        ("horse", ""),
+        ("doge", "coins"),
+        ("doge", "coin"),
+    ]);
+    assert_eq!(
+        root,
+        trie_root([("do", "verb"), ("dog", "puppy"), ("doge", "coin")])
+    );
+    assert_eq!(
+        root,
+        h256("ef7b2fe20f5d2c30c46ad4d83c39811bcbf1721aef2e805c0e107947320888b6")
+    );
+}
+
+#[test]
+fn secure_and_ordered_roots_hash_their_keys() {
+    let entries = [(b"key".to_vec(), b"value".to_vec())];
+    assert_eq!(
+        secure_trie_root(entries.clone()),
+        trie_root([(keccak(b"key"), b"value".to_vec())])
+    );
+    assert_eq!(
+        ordered_trie_root([b"first".to_vec(), b"second".to_vec()]),
+        trie_root([
+            (vec![0x80], b"first".to_vec()),
+            (vec![0x01], b"second".to_vec())
+        ])
+    );
+}
+
+#[test]
+fn state_root_of_empty_accounts() {
+    // Three precompiles touched with no balance, as in early state tests
+    let accounts = (1..=3u64).map(|address| (U256::from(address), TrieAccount::default()));
+    let root = state_root(accounts);
+    assert_ne!(root, EMPTY_ROOT);
+    assert_eq!(
+        root,
+        state_root(
+            (1..=3u64)
+                .rev()
+                .map(|address| (U256::from(address), TrieAccount::default()))
+        )
+    );
+}
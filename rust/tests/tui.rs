@@ -0,0 +1,43 @@
+//! Commands and screen of the terminal debugger.
+#![cfg(feature = "std")]
+
+use evm::debugger::{Breakpoint, Debugger};
+use evm::tui::{render, Command};
+use evm::EvmData;
+use primitive_types::U256;
+
+#[test]
+fn commands_parse_with_hex_arguments() {
+    assert_eq!(Command::parse(""), Some(Command::Step));
+    assert_eq!(Command::parse("u 1a"), Some(Command::RunUntil(0x1a)));
+    assert_eq!(
+        Command::parse("o sstore"),
+        Some(Command::ToggleBreakpoint(Breakpoint::Opcode(0x55)))
+    );
+    assert_eq!(
+        Command::parse("k 0x2"),
+        Some(Command::ToggleBreakpoint(Breakpoint::StorageKey(
+            U256::from(2)
+        )))
+    );
+    assert_eq!(Command::parse("u"), None);
+    assert_eq!(Command::parse("x"), None);
+}
+
+#[test]
+fn screen_shows_the_current_instruction() {
+    // PUSH1 5, PUSH1 1, SSTORE, STOP
+    let code = hex::decode("600560015500").unwrap();
+    let mut debugger = Debugger::new(code, &mut EvmData::default(), true);
+    Command::parse("p 4").unwrap().apply(&mut debugger);
+    Command::parse("c").unwrap().apply(&mut debugger);
+
+    let screen = render(&debugger);
+    assert!(screen.contains("\x1b[7m* 0004  SSTORE\x1b[0m"));
+    assert!(screen.contains("     0  0x1\n     1  0x5\n"));
+    assert!(screen.contains("Storage\n  0x1  0x0\n"));
+
+    assert!(!Command::Quit.apply(&mut debugger));
+    Command::parse("c").unwrap().apply(&mut debugger);
+    assert!(render(&debugger).starts_with("Finished after 4 steps: success"));
+}
@@ -0,0 +1,230 @@
+//! Decoding and encoding of raw transactions of every EIP-2718 type.
+
+use evm::env::Env;
+use evm::rlp::DecodeError;
+use evm::tx::{
+    AccessListEntry, AccessListTx, BlobTx, DynamicFeeTx, LegacyTx, Signature, Transaction,
+    TxDecodeError,
+};
+use evm::EvmContext;
+use primitive_types::{H256, U256};
+use serde_json::{json, Value};
+
+// The signed example of EIP-155
+const EIP155_SIGNED: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+fn signature() -> Signature {
+    Signature {
+        v: U256::one(),
+        r: U256::from(0x1234),
+        s: U256::from(0x5678),
+    }
+}
+
+fn access_list() -> Vec<AccessListEntry> {
+    vec![AccessListEntry {
+        address: U256::from(0xc0de),
+        storage_keys: vec![U256::zero(), U256::from(7)],
+    }]
+}
+
+fn round_trips(tx: Transaction) {
+    let raw = tx.encode();
+    assert_eq!(raw[0], tx.tx_type());
+    assert_eq!(Transaction::decode(&raw), Ok(tx));
+}
+
+#[test]
+fn legacy_transactions() {
+    let raw = hex::decode(EIP155_SIGNED).unwrap();
+    let tx = Transaction::decode(&raw).unwrap();
+    let expected = LegacyTx {
+        chain_id: Some(1),
+        nonce: 9,
+        gas_price: U256::from(20_000_000_000u64),
+        gas_limit: 21000,
+        to: Some(U256::from_big_endian(&[0x35; 20])),
+        value: U256::from(1_000_000_000_000_000_000u64),
+        input: vec![],
+        signature: Signature {
+            v: U256::from(37),
+            r: U256::from_dec_str(
+                "18515461264373351373200002665853028612451056578545711640558177340181847433846",
+            )
+            .unwrap(),
+            s: U256::from_dec_str(
+                "46948507304638947509940763649030358759909902576025900602547168820602576006531",
+            )
+            .unwrap(),
+        },
+    };
+    assert_eq!(tx, Transaction::Legacy(expected));
+    assert_eq!(tx.tx_type(), 0);
+    assert_eq!(tx.chain_id(), Some(1));
+    assert_eq!(tx.encode(), raw);
+}
+
+#[test]
+fn typed_transactions_round_trip() {
+    round_trips(Transaction::AccessList(AccessListTx {
+        chain_id: 1,
+        nonce: 3,
+        gas_price: U256::from(10),
+        gas_limit: 50000,
+        to: None,
+        value: U256::zero(),
+        input: vec![0x60, 0x00],
+        access_list: access_list(),
+        signature: signature(),
+    }));
+    round_trips(Transaction::DynamicFee(DynamicFeeTx {
+        chain_id: 5,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::from(2),
+        max_fee_per_gas: U256::from(100),
+        gas_limit: 21000,
+        to: Some(U256::from(0xb0b)),
+        value: U256::from(1),
+        input: vec![],
+        access_list: vec![],
+        signature: signature(),
+    }));
+    round_trips(Transaction::Blob(BlobTx {
+        chain_id: 1,
+        nonce: 1,
+        max_priority_fee_per_gas: U256::from(2),
+        max_fee_per_gas: U256::from(100),
+        gas_limit: 21000,
+        to: U256::from(0xb0b),
+        value: U256::zero(),
+        input: vec![0xff],
+        access_list: access_list(),
+        max_fee_per_blob_gas: U256::from(3),
+        blob_versioned_hashes: vec![H256::repeat_byte(0x01)],
+        signature: signature(),
+    }));
+}
+
+#[test]
+fn blob_transactions_in_network_form() {
+    let tx = Transaction::Blob(BlobTx {
+        to: U256::from(0xb0b),
+        blob_versioned_hashes: vec![H256::repeat_byte(0x01)],
+        ..Default::default()
+    });
+    let canonical = tx.encode();
+    // type || rlp([tx, blobs, commitments, proofs])
+    let inner = evm::rlp::encode_list(&[
+        canonical[1..].to_vec(),
+        evm::rlp::encode_list(&[evm::rlp::encode_bytes(&[0; 64])]),
+        evm::rlp::encode_list(&[evm::rlp::encode_bytes(&[0; 48])]),
+        evm::rlp::encode_list(&[evm::rlp::encode_bytes(&[0; 48])]),
+    ]);
+    let mut network = vec![0x03];
+    network.extend(inner);
+    let decoded = Transaction::decode(&network).unwrap();
+    assert_eq!(decoded, tx);
+    assert_eq!(decoded.hash(), tx.hash());
+}
+
+#[test]
+fn fees_and_tx_data() {
+    let tx = Transaction::DynamicFee(DynamicFeeTx {
+        max_priority_fee_per_gas: U256::from(2),
+        max_fee_per_gas: U256::from(100),
+        to: Some(U256::from(0xb0b)),
+        access_list: access_list(),
+        ..Default::default()
+    });
+    assert_eq!(tx.effective_gas_price(Some(U256::from(10))), U256::from(12));
+    assert_eq!(
+        tx.effective_gas_price(Some(U256::from(99))),
+        U256::from(100)
+    );
+    assert_eq!(tx.effective_gas_price(None), U256::from(100));
+
+    let tx_data = tx.tx_data(U256::from(0xa11ce));
+    let context: EvmContext = serde_json::from_value(json!({ "basefee": "0xa" })).unwrap();
+    assert_eq!(
+        Env::new(Some(&context), Some(&tx_data)).tx.gasprice,
+        U256::from(12)
+    );
+    let tx_data = serde_json::to_value(tx_data).unwrap();
+    assert_eq!(
+        tx_data["from"],
+        "0x00000000000000000000000000000000000a11ce"
+    );
+    assert_eq!(tx_data["gasprice"], Value::Null);
+    assert_eq!(tx_data["max_fee_per_gas"], "0x64");
+    assert_eq!(tx_data["max_priority_fee_per_gas"], "0x2");
+    assert_eq!(
+        tx_data["access_list"][0]["storage_keys"][1],
+        "0x0000000000000000000000000000000000000000000000000000000000000007"
+    );
+}
+
+#[test]
+fn malformed_transactions() {
+    assert_eq!(Transaction::decode(&[]), Err(TxDecodeError::Empty));
+    assert_eq!(
+        Transaction::decode(&[0x05, 0xc0]),
+        Err(TxDecodeError::UnsupportedType(5))
+    );
+    assert_eq!(
+        Transaction::decode(&[0x02, 0xc1, 0x01]),
+        Err(TxDecodeError::FieldCount {
+            expected: 12,
+            found: 1
+        })
+    );
+    let mut truncated = hex::decode(EIP155_SIGNED).unwrap();
+    truncated.pop();
+    assert_eq!(
+        Transaction::decode(&truncated),
+        Err(TxDecodeError::Rlp(DecodeError::UnexpectedEnd))
+    );
+    // A recipient of 19 bytes
+    let mut fields = vec![evm::rlp::encode_bytes(&[]); 9];
+    fields[3] = evm::rlp::encode_bytes(&[0x35; 19]);
+    assert_eq!(
+        Transaction::decode(&evm::rlp::encode_list(&fields)),
+        Err(TxDecodeError::InvalidAddress)
+    );
+}
+
+#[test]
+fn deeply_nested_lists_do_not_decode() {
+    // Lists each holding the next one, down to an empty list, with their
+    // prefixes worked out from the inside
+    let nested = |depth: usize| {
+        let mut prefixes = vec![];
+        let mut len: usize = 1;
+        for _ in 0..depth {
+            let prefix = if len < 56 {
+                vec![0xc0 + len as u8]
+            } else {
+                let digits: Vec<u8> = len
+                    .to_be_bytes()
+                    .into_iter()
+                    .skip_while(|byte| *byte == 0)
+                    .collect();
+                [vec![0xf7 + digits.len() as u8], digits].concat()
+            };
+            len += prefix.len();
+            prefixes.push(prefix);
+        }
+        let mut input: Vec<u8> = prefixes.into_iter().rev().flatten().collect();
+        input.push(0xc0);
+        input
+    };
+    // The empty list is one more
+    assert!(evm::rlp::decode(&nested(evm::rlp::MAX_DEPTH - 1)).is_ok());
+    assert_eq!(
+        evm::rlp::decode(&nested(evm::rlp::MAX_DEPTH)),
+        Err(DecodeError::TooDeep)
+    );
+    assert_eq!(
+        Transaction::decode(&nested(100_000)),
+        Err(TxDecodeError::Rlp(DecodeError::TooDeep))
+    );
+}
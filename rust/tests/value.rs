@@ -0,0 +1,139 @@
+//! Value moved by calls and creates: taken from the caller, given to the
+//! callee, and left where it was when the caller cannot afford it or the
+//! callee fails.
+
+use evm::host::Database;
+use evm::{evm, EvmData, TxData};
+use primitive_types::U256;
+use serde_json::json;
+
+const CONTRACT: u64 = 0xc0de;
+const CALLEE: u64 = 0xbeef;
+
+fn data(balance: u64) -> EvmData {
+    let mut data = EvmData {
+        tx_data: Some(
+            serde_json::from_value::<TxData>(json!({ "to": "0xc0de", "gas": "0x186a0" })).unwrap(),
+        ),
+        ..Default::default()
+    };
+    data.balances
+        .insert(CONTRACT.to_string(), U256::from(balance));
+    data
+}
+
+fn balance(data: &EvmData, address: u64) -> U256 {
+    data.basic(U256::from(address)).unwrap_or_default().balance
+}
+
+// CALL(gas, 0xbeef, value, 0, 0, 0, 0)
+fn calls_with(value: u8) -> Vec<u8> {
+    hex::decode(format!("600080808060{:02x}61beef5af1", value)).unwrap()
+}
+
+// CREATE(value, 0, 0)
+fn creates_with(value: u8) -> Vec<u8> {
+    hex::decode(format!("6000600060{:02x}f0", value)).unwrap()
+}
+
+#[test]
+fn call_moves_the_value() {
+    let mut data = data(10);
+    let result = evm(calls_with(3), &mut data, true);
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::one()]);
+
+    assert_eq!(balance(&data, CONTRACT), U256::from(7));
+    assert_eq!(balance(&data, CALLEE), U256::from(3));
+}
+
+#[test]
+fn call_without_the_balance_fails_and_moves_nothing() {
+    let mut data = data(2);
+    let result = evm(calls_with(3), &mut data, true);
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::zero()]);
+
+    assert_eq!(balance(&data, CONTRACT), U256::from(2));
+    assert_eq!(balance(&data, CALLEE), U256::zero());
+}
+
+#[test]
+fn a_failed_callee_hands_the_value_back() {
+    let mut data = data(10);
+    // The callee reverts
+    data.codes
+        .insert(CALLEE.to_string(), "60006000fd".parse().unwrap());
+    let result = evm(calls_with(3), &mut data, true);
+    assert_eq!(result.stack, vec![U256::zero()]);
+
+    assert_eq!(balance(&data, CONTRACT), U256::from(10));
+    assert_eq!(balance(&data, CALLEE), U256::zero());
+}
+
+#[test]
+fn create_moves_the_value() {
+    let mut data = data(10);
+    let result = evm(creates_with(4), &mut data, true);
+    assert!(result.success);
+    let created = result.stack[0];
+    assert!(!created.is_zero());
+
+    assert_eq!(balance(&data, CONTRACT), U256::from(6));
+    assert_eq!(data.basic(created).unwrap().balance, U256::from(4));
+}
+
+#[test]
+fn create_adds_to_the_balance_already_there() {
+    let created = evm(creates_with(4), &mut data(10), true).stack[0];
+    let mut data = data(10);
+    data.balances.insert(created.to_string(), U256::from(100));
+    let result = evm(creates_with(4), &mut data, true);
+    assert_eq!(result.stack, vec![created]);
+
+    assert_eq!(balance(&data, CONTRACT), U256::from(6));
+    assert_eq!(data.basic(created).unwrap().balance, U256::from(104));
+}
+
+#[test]
+fn create_without_the_balance_fails_and_moves_nothing() {
+    let mut data = data(2);
+    let result = evm(creates_with(4), &mut data, true);
+    assert!(result.success);
+    assert_eq!(result.stack, vec![U256::zero()]);
+
+    let account = data.basic(U256::from(CONTRACT)).unwrap();
+    assert_eq!(account.balance, U256::from(2));
+    assert_eq!(account.nonce, 0);
+}
+
+#[test]
+fn callcode_runs_the_code_as_the_caller_and_keeps_the_value() {
+    // CALLCODE(gas, 0xbeef, value, 0, 0, 0, 0)
+    let callcode = |value: u8| hex::decode(format!("600080808060{:02x}61beef5af2", value)).unwrap();
+    let run = |balance: u64, value: u8| {
+        let mut data = data(balance);
+        // SSTORE(0, CALLVALUE)
+        data.codes
+            .insert(CALLEE.to_string(), "34600055".parse().unwrap());
+        let result = evm(callcode(value), &mut data, true);
+        (result, data)
+    };
+
+    let (result, data) = run(10, 3);
+    assert_eq!(result.stack, vec![U256::one()]);
+    assert_eq!(
+        data.storage(U256::from(CONTRACT), U256::zero()),
+        U256::from(3)
+    );
+    assert_eq!(data.storage(U256::from(CALLEE), U256::zero()), U256::zero());
+    assert_eq!(balance(&data, CONTRACT), U256::from(10));
+    assert_eq!(balance(&data, CALLEE), U256::zero());
+
+    let (result, data) = run(2, 3);
+    assert_eq!(result.stack, vec![U256::zero()]);
+    assert_eq!(
+        data.storage(U256::from(CONTRACT), U256::zero()),
+        U256::zero()
+    );
+}
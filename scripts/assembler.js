@@ -51,6 +51,8 @@ const OPCODES = {
   CHAINID: 0x46,
   SELFBALANCE: 0x47,
   BASEFEE: 0x48,
+  BLOBHASH: 0x49,
+  BLOBBASEFEE: 0x4a,
   POP: 0x50,
   MLOAD: 0x51,
   MSTORE: 0x52,
@@ -63,6 +65,9 @@ const OPCODES = {
   MSIZE: 0x59,
   GAS: 0x5a,
   JUMPDEST: 0x5b,
+  TLOAD: 0x5c,
+  TSTORE: 0x5d,
+  MCOPY: 0x5e,
   PUSH0: 0x5f,
   PUSH1: 0x60,
   PUSH2: 0x61,